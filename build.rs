@@ -12,8 +12,13 @@ use rspack_core::{
 use rspack_tasks::within_compiler_context_for_testing;
 use serde_json::json;
 
+/// Set to `1` to reuse the bundle already in `frontend/dist` instead of running Rspack,
+/// for quicker rebuilds while only templates or Rust code change
+const SKIP_FRONTEND_ENV: &str = "IRON_SHIELD_SKIP_FRONTEND";
+
 fn main() {
     println!("cargo:rerun-if-changed=frontend/src");
+    println!("cargo:rerun-if-env-changed={SKIP_FRONTEND_ENV}");
 
     let enable_sourcemaps = !matches!(env::var("PROFILE").as_deref(), Ok("release"));
 
@@ -21,6 +26,15 @@ fn main() {
         .join("frontend")
         .join("dist");
 
+    if env::var(SKIP_FRONTEND_ENV).as_deref() == Ok("1") {
+        if dist_dir.join("main.js").exists() {
+            println!("cargo:warning=Skipping the frontend bundle; using the one in frontend/dist");
+            println!("cargo:rustc-env=FRONTEND_DIST_DIR={}", dist_dir.display());
+            return;
+        }
+        println!("cargo:warning=No bundle in frontend/dist yet; building the frontend");
+    }
+
     let rt = tokio::runtime::Builder::new_multi_thread()
         .enable_all()
         .build()
@@ -6,21 +6,93 @@ export type ClockFormat = "12hour" | "24hour";
 let clockInterval: number | undefined;
 
 /**
- * Initialize the live clock and start ticking once per second.
+ * Initialize the live clock and the world clocks and start ticking once per second.
  */
 export function initClock(): void {
   const timeElement = document.getElementById("time");
-  if (!(timeElement instanceof HTMLElement)) {
+  const worldClocks = initWorldClocks();
+  if (!(timeElement instanceof HTMLElement) && worldClocks.length === 0) {
     return;
   }
 
   const format =
-    (timeElement.dataset.format as ClockFormat | undefined) ?? "24hour";
-  updateTimeAndBackground(timeElement, format);
+    (timeElement?.dataset.format as ClockFormat | undefined) ?? "24hour";
+  const zonedTime = createZonedTime(timeElement?.dataset.timezone);
+  const tick = (): void => {
+    if (timeElement instanceof HTMLElement) {
+      updateTimeAndBackground(timeElement, format, zonedTime);
+    }
+    worldClocks.forEach(updateWorldClock);
+  };
+  tick();
+
+  clockInterval = window.setInterval(tick, 1000);
+}
 
-  clockInterval = window.setInterval(() => {
-    updateTimeAndBackground(timeElement, format);
-  }, 1000);
+interface WorldClock {
+  element: HTMLElement;
+  format: ClockFormat;
+  zonedTime: ZonedTime;
+}
+
+/**
+ * Collect the clocks of the world-clock row, each ticking in its own time zone.
+ */
+function initWorldClocks(): WorldClock[] {
+  const row = document.getElementById("world-clocks");
+  if (!row) {
+    return [];
+  }
+
+  const format = (row.dataset.format as ClockFormat | undefined) ?? "24hour";
+  return Array.from(row.querySelectorAll<HTMLElement>(".world-clock-time")).map(
+    (element) => ({
+      element,
+      format,
+      zonedTime: createZonedTime(element.dataset.timezone),
+    }),
+  );
+}
+
+function updateWorldClock(clock: WorldClock): void {
+  const { hours, minutes } = clock.zonedTime(new Date());
+  clock.element.textContent = formatTime(hours, minutes, clock.format);
+}
+
+type ZonedTime = (date: Date) => { hours: number; minutes: number };
+
+/**
+ * Build a function reading hours/minutes in the configured IANA time zone.
+ * Falls back to the browser's local time when no valid zone is configured.
+ */
+function createZonedTime(timeZone: string | undefined): ZonedTime {
+  const local: ZonedTime = (date) => ({
+    hours: date.getHours(),
+    minutes: date.getMinutes(),
+  });
+  if (!timeZone) {
+    return local;
+  }
+
+  let formatter: Intl.DateTimeFormat;
+  try {
+    formatter = new Intl.DateTimeFormat("en-US", {
+      timeZone,
+      hour: "numeric",
+      minute: "numeric",
+      hourCycle: "h23",
+    });
+  } catch {
+    console.warn(`Unknown time zone "${timeZone}", using local time`);
+    return local;
+  }
+
+  return (date) => {
+    const parts = formatter.formatToParts(date);
+    const part = (type: Intl.DateTimeFormatPartTypes) =>
+      Number(parts.find((p) => p.type === type)?.value ?? 0);
+    return { hours: part("hour") % 24, minutes: part("minute") };
+  };
 }
 
 /**
@@ -29,10 +101,11 @@ export function initClock(): void {
 function updateTimeAndBackground(
   element: HTMLElement,
   format: ClockFormat,
+  zonedTime: ZonedTime,
 ): void {
-  const now = new Date();
-  element.textContent = formatTime(now.getHours(), now.getMinutes(), format);
-  updateTimeOfDayBackground(now);
+  const { hours, minutes } = zonedTime(new Date());
+  element.textContent = formatTime(hours, minutes, format);
+  updateTimeOfDayBackground(hours);
 }
 
 /**
@@ -61,9 +134,7 @@ function formatTime(
 /**
  * Apply the data-time attribute used for background gradients.
  */
-function updateTimeOfDayBackground(date: Date): void {
-  const hour = date.getHours();
-
+function updateTimeOfDayBackground(hour: number): void {
   if (hour >= 5 && hour < 12) {
     setDataTime("morning");
   } else if (hour >= 12 && hour < 17) {
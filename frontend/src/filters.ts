@@ -246,6 +246,26 @@ function applyFilters(
   });
 
   emptyState.hidden = visibleCount !== 0;
+  updateGroupVisibility(cards);
+}
+
+/**
+ * Hide group and category sections whose sites are all filtered out.
+ */
+function updateGroupVisibility(cards: SiteCard[]): void {
+  const groups = new Set<HTMLElement>();
+  cards.forEach((card) => {
+    const group = card.closest<HTMLElement>(
+      ".site-group, .site-category-section, .site-group-ungrouped",
+    );
+    if (group) {
+      groups.add(group);
+    }
+  });
+
+  groups.forEach((group) => {
+    group.hidden = !group.querySelector(".site-card:not([hidden])");
+  });
 }
 
 function getCardTags(card: SiteCard): string[] {
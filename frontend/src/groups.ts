@@ -0,0 +1,58 @@
+const GROUP_STATE_STORAGE_KEY = "ironShieldCollapsedGroups";
+
+type GroupState = Record<string, boolean>;
+
+/**
+ * Restores each site group's collapsed state from the last visit and remembers changes.
+ *
+ * Groups the visitor has never toggled keep the `collapsed` default from the config.
+ */
+export function initSiteGroups(): void {
+  const groups = document.querySelectorAll<HTMLDetailsElement>("details.site-group");
+  if (groups.length === 0) {
+    return;
+  }
+
+  const dashboard = document.body.dataset.dashboard ?? "";
+  const state = readGroupState();
+
+  groups.forEach((group) => {
+    const key = groupKey(dashboard, group.dataset.group ?? "");
+    const collapsed = state[key];
+    if (typeof collapsed === "boolean") {
+      group.open = !collapsed;
+    }
+
+    group.addEventListener("toggle", () => {
+      const latest = readGroupState();
+      latest[key] = !group.open;
+      writeGroupState(latest);
+    });
+  });
+}
+
+function groupKey(dashboard: string, group: string): string {
+  return `${dashboard}/${group}`;
+}
+
+function readGroupState(): GroupState {
+  try {
+    const stored = localStorage.getItem(GROUP_STATE_STORAGE_KEY);
+    const parsed: unknown = stored ? JSON.parse(stored) : null;
+    if (parsed && typeof parsed === "object" && !Array.isArray(parsed)) {
+      return parsed as GroupState;
+    }
+  } catch {
+    // Ignore storage access and parse issues.
+  }
+
+  return {};
+}
+
+function writeGroupState(state: GroupState): void {
+  try {
+    localStorage.setItem(GROUP_STATE_STORAGE_KEY, JSON.stringify(state));
+  } catch {
+    // Ignore persistence errors.
+  }
+}
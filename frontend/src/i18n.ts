@@ -0,0 +1,28 @@
+let translations: Record<string, string> | null = null;
+
+/**
+ * Read the strings rendered by the server in the configured language (see `i18n.rs`).
+ */
+function loadTranslations(): Record<string, string> {
+  if (translations) {
+    return translations;
+  }
+
+  translations = {};
+  const script = typeof document === "undefined" ? null : document.getElementById("translations");
+  if (script?.textContent) {
+    try {
+      translations = JSON.parse(script.textContent) as Record<string, string>;
+    } catch (error) {
+      console.error("Failed to parse translations payload", error);
+    }
+  }
+  return translations;
+}
+
+/**
+ * Text for `key` in the dashboard's language, or `fallback` when the page has none.
+ */
+export function t(key: string, fallback: string): string {
+  return loadTranslations()[key] ?? fallback;
+}
@@ -5,13 +5,18 @@
 import "./styles/base.css";
 import "./styles/clock.css";
 import "./styles/filters.css";
+import "./styles/kiosk.css";
 import "./styles/notifications.css";
 import "./styles/sites.css";
 import "./styles/settings.css";
 import faviconUrl from "./favicon.svg";
 import { initClock } from "./clock.ts";
 import { initUptimeSSE } from "./uptime.ts";
+import { initOutageBanner } from "./outages.ts";
+import { initKiosk } from "./kiosk.ts";
 import { initSiteFilters } from "./filters.ts";
+import { initSiteGroups } from "./groups.ts";
+import { initSiteSearch } from "./search.ts";
 import { initSiteStatusNotifications } from "./notifications.ts";
 import { initSettingsPanel } from "./settings.ts";
 import { initSiteClickTelemetry } from "./telemetry.ts";
@@ -34,9 +39,13 @@ function bootstrap(): void {
   applyFavicon();
   initThemeManager();
   initClock();
+  initSiteGroups();
   initSiteFilters();
+  initSiteSearch();
   initSiteStatusNotifications();
   initSettingsPanel();
+  initKiosk();
+  initOutageBanner();
   initUptimeSSE();
   initSiteClickTelemetry();
 }
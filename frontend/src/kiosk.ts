@@ -0,0 +1,53 @@
+/**
+ * Rotates the kiosk view through its category sections and keeps the screen awake.
+ */
+export function initKiosk(): void {
+  if (typeof document === "undefined" || document.body.dataset.kiosk === undefined) {
+    return;
+  }
+
+  void keepScreenAwake();
+
+  const sections = Array.from(document.querySelectorAll<HTMLElement>(".kiosk-section"));
+  const rotateSecs = Number(document.body.dataset.rotateSecs ?? 0);
+  if (sections.length < 2 || !(rotateSecs > 0)) {
+    sections.forEach((section) => {
+      section.hidden = false;
+    });
+    return;
+  }
+
+  let current = Math.max(
+    sections.findIndex((section) => !section.hidden),
+    0,
+  );
+  const show = (index: number): void => {
+    current = index % sections.length;
+    sections.forEach((section, position) => {
+      section.hidden = position !== current;
+    });
+  };
+  show(current);
+  window.setInterval(() => show(current + 1), rotateSecs * 1000);
+}
+
+/**
+ * Ask the browser not to dim or lock the screen, again whenever the page becomes
+ * visible, since the lock is released while it is hidden.
+ */
+async function keepScreenAwake(): Promise<void> {
+  const request = async (): Promise<void> => {
+    try {
+      await navigator.wakeLock?.request("screen");
+    } catch {
+      // Not allowed or not supported; the display's own settings apply
+    }
+  };
+
+  document.addEventListener("visibilitychange", () => {
+    if (document.visibilityState === "visible") {
+      void request();
+    }
+  });
+  await request();
+}
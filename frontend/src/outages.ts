@@ -0,0 +1,78 @@
+import { t } from "./i18n.ts";
+import { formatDuration } from "./time.ts";
+
+const OUTAGE_TICK_MS = 30_000;
+
+/**
+ * Keep the "down for" durations of the server-rendered outage banner current.
+ */
+export function initOutageBanner(): void {
+  if (typeof document === "undefined" || !document.getElementById("outage-banner")) {
+    return;
+  }
+
+  refreshOutageDurations();
+  window.setInterval(refreshOutageDurations, OUTAGE_TICK_MS);
+}
+
+/**
+ * Add, update, or remove the banner entry of a site after a completed check. `downSince`
+ * is the Unix time in seconds the site went down, absent while it is not down.
+ */
+export function recordOutage(siteId: string, status: string, downSince?: number | null): void {
+  const banner = document.getElementById("outage-banner");
+  const list = banner?.querySelector<HTMLUListElement>(".outage-list");
+  if (!banner || !list) {
+    return;
+  }
+
+  const existing = Array.from(list.querySelectorAll<HTMLLIElement>(".outage")).find(
+    (item) => item.dataset.site === siteId,
+  );
+
+  if (status !== "down" || typeof downSince !== "number") {
+    existing?.remove();
+  } else {
+    const item = existing ?? createOutageItem(siteId);
+    item.dataset.downSince = String(downSince);
+    const position = Array.from(list.children).find(
+      (other) => other !== item && outageSortKey(other as HTMLElement) > outageSortKey(item),
+    );
+    list.insertBefore(item, position ?? null);
+  }
+
+  banner.hidden = list.children.length === 0;
+  refreshOutageDurations();
+}
+
+function createOutageItem(siteId: string): HTMLLIElement {
+  const item = document.createElement("li");
+  item.className = "outage";
+  item.dataset.site = siteId;
+
+  const name = document.createElement("span");
+  name.className = "outage-site";
+  name.textContent = siteId;
+  const duration = document.createElement("span");
+  duration.className = "outage-duration";
+  item.append(name, " ", duration);
+  return item;
+}
+
+/** Longest outage first, ties by name, like the server-rendered banner. */
+function outageSortKey(item: HTMLElement): string {
+  const since = Number(item.dataset.downSince ?? 0);
+  return `${String(since).padStart(12, "0")}\u0000${item.dataset.site ?? ""}`;
+}
+
+function refreshOutageDurations(): void {
+  const now = Math.floor(Date.now() / 1000);
+  const label = t("outage.down_for", "down for");
+  document.querySelectorAll<HTMLElement>("#outage-banner .outage").forEach((item) => {
+    const duration = item.querySelector<HTMLElement>(".outage-duration");
+    const since = Number(item.dataset.downSince);
+    if (duration && Number.isFinite(since)) {
+      duration.textContent = `${label} ${formatDuration(Math.max(now - since, 0))}`;
+    }
+  });
+}
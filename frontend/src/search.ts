@@ -0,0 +1,135 @@
+/**
+ * Result of `GET /api/search`, best match first.
+ */
+interface SearchResult {
+  name: string;
+  url: string;
+  category: string;
+  tags: string[];
+  icon?: string | null;
+  score: number;
+}
+
+const SEARCH_LIMIT = 8;
+const SEARCH_DEBOUNCE_MS = 120;
+
+/**
+ * Turn the site search box into a launcher: matches from the server are listed below
+ * the input, arrow keys move through them, and Enter opens the highlighted site.
+ */
+export function initSiteSearch(): void {
+  if (typeof document === "undefined") {
+    return;
+  }
+
+  const input = document.getElementById("site-search") as HTMLInputElement | null;
+  const list = document.getElementById("site-search-results") as HTMLUListElement | null;
+  if (!input || !list) {
+    return;
+  }
+
+  let results: SearchResult[] = [];
+  let activeIndex = -1;
+  let pending: AbortController | null = null;
+  let debounce: number | undefined;
+
+  const setActive = (index: number): void => {
+    activeIndex = results.length === 0 ? -1 : (index + results.length) % results.length;
+    Array.from(list.children).forEach((option, position) => {
+      option.setAttribute("aria-selected", String(position === activeIndex));
+    });
+    const active = activeIndex >= 0 ? list.children[activeIndex] : null;
+    if (active) {
+      input.setAttribute("aria-activedescendant", active.id);
+      active.scrollIntoView({ block: "nearest" });
+    } else {
+      input.removeAttribute("aria-activedescendant");
+    }
+  };
+
+  const render = (next: SearchResult[]): void => {
+    results = next;
+    list.replaceChildren(...results.map(renderResult));
+    list.hidden = results.length === 0;
+    input.setAttribute("aria-expanded", String(!list.hidden));
+    setActive(0);
+  };
+
+  const runSearch = async (query: string): Promise<void> => {
+    pending?.abort();
+    if (query.trim().length === 0) {
+      render([]);
+      return;
+    }
+
+    const controller = new AbortController();
+    pending = controller;
+    const params = new URLSearchParams({ q: query, limit: String(SEARCH_LIMIT) });
+    try {
+      const response = await fetch(`/api/search?${params.toString()}`, {
+        signal: controller.signal,
+      });
+      if (response.ok) {
+        render((await response.json()) as SearchResult[]);
+      }
+    } catch {
+      // Aborted by a newer query or offline; the dashboard filter still works
+    }
+  };
+
+  input.addEventListener("input", () => {
+    window.clearTimeout(debounce);
+    debounce = window.setTimeout(() => {
+      void runSearch(input.value);
+    }, SEARCH_DEBOUNCE_MS);
+  });
+
+  input.addEventListener("keydown", (event) => {
+    if (event.isComposing || results.length === 0) {
+      return;
+    }
+
+    if (event.key === "ArrowDown" || event.key === "ArrowUp") {
+      event.preventDefault();
+      setActive(activeIndex + (event.key === "ArrowDown" ? 1 : -1));
+    } else if (event.key === "Enter") {
+      const result = results[Math.max(activeIndex, 0)];
+      if (result) {
+        event.preventDefault();
+        window.location.assign(result.url);
+      }
+    }
+  });
+}
+
+function renderResult(result: SearchResult, index: number): HTMLLIElement {
+  const option = document.createElement("li");
+  option.id = `site-search-result-${index}`;
+  option.className = "site-search-result";
+  option.setAttribute("role", "option");
+
+  const link = document.createElement("a");
+  link.href = result.url;
+  link.tabIndex = -1;
+  if (result.icon) {
+    const icon = document.createElement("img");
+    icon.className = "site-icon";
+    icon.src = result.icon;
+    icon.alt = "";
+    icon.width = 16;
+    icon.height = 16;
+    link.appendChild(icon);
+  }
+  link.appendChild(document.createTextNode(result.name));
+  option.appendChild(link);
+
+  const detail = [result.category, ...result.tags].filter((label) => label.length > 0);
+  if (detail.length > 0) {
+    const labels = document.createElement("span");
+    labels.className = "site-search-result-labels";
+    labels.textContent = detail.join(", ");
+    option.appendChild(labels);
+  }
+
+  return option;
+}
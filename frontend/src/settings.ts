@@ -5,8 +5,15 @@ interface SiteConfig {
     url: string;
     category: string;
     tags: string[];
-    monitor_interval_secs: number;
+    monitor_interval_secs: number | null;
+    monitor_timeout_secs?: number | null;
+    monitor_retries?: number | null;
+    monitor_user_agent?: string | null;
     disabled: boolean;
+    icon?: string | null;
+    order?: number | null;
+    // Edited in the config file only; kept as-is when the settings page saves
+    assertions?: Record<string, unknown> | null;
 }
 
 interface ConfigData {
@@ -20,10 +27,11 @@ type NotificationVariant = "success" | "error";
 
 const BORDER_DEFAULT = "var(--color-border)";
 const BORDER_ERROR = "var(--color-error)";
-const DEFAULT_MONITOR_INTERVAL_SECS = 5; // Keep in sync with backend defaults.
 const MIN_MONITOR_INTERVAL_SECS = 5;
+const API_KEY_STORAGE_KEY = "iron-shield-api-key";
 
 let configData: ConfigData | null = null;
+let configRevision: string | null = null;
 let modalElement: HTMLElement | null = null;
 let sitesListElement: HTMLElement | null = null;
 let notificationElement: HTMLElement | null = null;
@@ -69,6 +77,7 @@ function parseInitialConfig(): ConfigData | null {
     if (!configScript?.textContent) {
         return null;
     }
+    configRevision = configScript.dataset.revision || null;
 
     try {
         return JSON.parse(configScript.textContent) as ConfigData;
@@ -84,7 +93,7 @@ function normalizeSiteConfig(site: SiteConfig): SiteConfig {
         Number.isFinite(site.monitor_interval_secs) &&
         site.monitor_interval_secs >= MIN_MONITOR_INTERVAL_SECS
             ? Math.round(site.monitor_interval_secs)
-            : DEFAULT_MONITOR_INTERVAL_SECS;
+            : null;
 
     return {
         ...site,
@@ -173,6 +182,7 @@ function applyInitialValues(): void {
 
 function bindFormHandlers(): void {
     const addSiteButton = document.getElementById("add-site-btn") as HTMLButtonElement | null;
+    const testSiteButton = document.getElementById("test-site-btn") as HTMLButtonElement | null;
     const newSiteNameInput = document.getElementById("new-site-name") as HTMLInputElement | null;
     const newSiteUrlInput = document.getElementById("new-site-url") as HTMLInputElement | null;
     const saveButton = document.getElementById("settings-save-button") as HTMLButtonElement | null;
@@ -182,6 +192,7 @@ function bindFormHandlers(): void {
     newSiteUrlInput?.addEventListener("input", () => validateUrlInput(newSiteUrlInput));
 
     addSiteButton?.addEventListener("click", handleAddSite);
+    testSiteButton?.addEventListener("click", () => testNewSite(testSiteButton));
     saveButton?.addEventListener("click", saveSettings);
     resetButton?.addEventListener("click", resetSettings);
 }
@@ -213,8 +224,10 @@ function handleAddSite(): void {
         url: urlInput.value.trim(),
         category: categoryInput.value.trim(),
         tags: parseTags(tagsInput.value),
-        monitor_interval_secs: DEFAULT_MONITOR_INTERVAL_SECS,
+        monitor_interval_secs: null,
         disabled: false,
+        icon: null,
+        order: null,
     };
 
     configData.sites.push(newSite);
@@ -228,6 +241,94 @@ function handleAddSite(): void {
     clearInputError(urlInput);
 }
 
+// Sends the API key remembered in this browser; when the server asks for one (401), the
+// user is prompted and the request is retried once with the key they enter
+function apiFetch(url: string, init: RequestInit): Promise<Response> {
+    const send = (key: string | null): Promise<Response> => {
+        const headers = new Headers(init.headers);
+        if (key) {
+            headers.set("Authorization", `Bearer ${key}`);
+        }
+        return fetch(url, { ...init, headers });
+    };
+
+    return send(localStorage.getItem(API_KEY_STORAGE_KEY)).then((response) => {
+        if (response.status !== 401) {
+            return response;
+        }
+        const key = window.prompt("Saving settings needs an API key. Enter your key:")?.trim();
+        if (!key) {
+            return response;
+        }
+        return send(key).then((retried) => {
+            if (retried.status !== 401) {
+                localStorage.setItem(API_KEY_STORAGE_KEY, key);
+            }
+            return retried;
+        });
+    });
+}
+
+interface CheckResult {
+    status: "up" | "down";
+    response_time_ms: number | null;
+    failure_reason?: string;
+}
+
+function testNewSite(button: HTMLButtonElement): void {
+    const urlInput = document.getElementById("new-site-url") as HTMLInputElement | null;
+    if (!urlInput || !validateUrlInput(urlInput)) {
+        showNotification("Enter a valid URL to test.", "error");
+        return;
+    }
+
+    hideNotification();
+    button.disabled = true;
+    button.textContent = "Testing…";
+
+    apiFetch("/api/check", {
+        method: "POST",
+        headers: { "Content-Type": "application/json" },
+        body: JSON.stringify({ url: urlInput.value.trim() }),
+    })
+        .then((response) => {
+            if (!response.ok) {
+                return response.text().then((text) => {
+                    let message = text || "Check failed";
+                    try {
+                        const body = JSON.parse(text) as ApiErrorResponse;
+                        const urlError = body.errors.find((error) => error.field === "url");
+                        if (urlError) {
+                            setInputError(urlInput, urlError.message);
+                        }
+                        message = body.message;
+                    } catch {
+                        // Not an error response from the API; show the text as is
+                    }
+                    throw new Error(message);
+                });
+            }
+            return response.json().then((result: CheckResult) => {
+                const latency =
+                    result.response_time_ms === null ? "" : ` in ${result.response_time_ms} ms`;
+                if (result.status === "up") {
+                    showNotification(`Site is up (answered${latency}).`, "success");
+                } else {
+                    const reason = result.failure_reason ?? "no answer";
+                    showNotification(`Site is down${latency}: ${reason}`, "error");
+                }
+            });
+        })
+        .catch((error: Error) => {
+            console.error("Error testing site", error);
+            showNotification(`Error testing site: ${error.message}`, "error");
+        })
+        .finally(() => {
+            button.disabled = false;
+            button.textContent = "Test";
+        });
+}
+
 function parseTags(rawTags: string): string[] {
     return rawTags
         .split(",")
@@ -286,19 +387,50 @@ function createSiteItem(site: SiteConfig, index: number): HTMLElement {
         "Category (e.g., Work)",
     );
 
+    const iconInput = createLabeledInput(
+        `site-icon-${index}`,
+        "Icon",
+        site.icon ?? "",
+        (value) => {
+            site.icon = value.trim() || null;
+        },
+        undefined,
+        "text",
+        "Image URL or icon name (e.g., server)",
+    );
+
+    const orderInput = createLabeledInput(
+        `site-order-${index}`,
+        "Display Order",
+        site.order === null || site.order === undefined ? "" : String(site.order),
+        (value) => {
+            const parsed = Number(value);
+            site.order = value.trim() && Number.isFinite(parsed) ? Math.round(parsed) : null;
+        },
+        undefined,
+        "number",
+        "Lower numbers are shown first",
+        {
+            step: "1",
+            inputmode: "numeric",
+        },
+    );
+
     const intervalInput = createLabeledInput(
         `site-interval-${index}`,
         "Check Interval (seconds)",
-        String(site.monitor_interval_secs ?? DEFAULT_MONITOR_INTERVAL_SECS),
+        site.monitor_interval_secs === null ? "" : String(site.monitor_interval_secs),
         (value) => {
             const parsed = Number(value);
-            if (Number.isFinite(parsed)) {
+            if (!value.trim()) {
+                site.monitor_interval_secs = null;
+            } else if (Number.isFinite(parsed)) {
                 site.monitor_interval_secs = Math.round(parsed);
             }
         },
         (input) => validateIntervalInput(input),
         "number",
-        undefined,
+        "Default from monitoring settings",
         {
             min: `${MIN_MONITOR_INTERVAL_SECS}`,
             step: "1",
@@ -372,6 +504,8 @@ function createSiteItem(site: SiteConfig, index: number): HTMLElement {
         nameInput,
         urlInput,
         categoryInput,
+        iconInput,
+        orderInput,
         intervalInput,
         disabledGroup,
         tagsGroup,
@@ -528,17 +662,26 @@ function saveSettings(): void {
         saveButton.textContent = "Saving…";
     }
 
-    fetch("/api/config", {
-        method: "POST",
-        headers: {
-            "Content-Type": "application/json",
-        },
-        body: JSON.stringify(configData),
-    })
+    const headers: Record<string, string> = {
+        "Content-Type": "application/json",
+    };
+    // Rejected with 409 when another tab saved since this page loaded
+    if (configRevision) {
+        headers["If-Match"] = `"${configRevision}"`;
+    }
+
+    const body = JSON.stringify(configData);
+    confirmRemovals(body)
+        .then((confirmed) =>
+            confirmed ? apiFetch("/api/config", { method: "POST", headers, body }) : null,
+        )
         .then((response) => {
+            if (!response) {
+                return null;
+            }
             if (!response.ok) {
                 return response.text().then((text) => {
-                    throw new Error(text || "Failed to save settings");
+                    throw new Error(describeSaveError(text));
                 });
             }
 
@@ -560,6 +703,90 @@ function saveSettings(): void {
         });
 }
 
+interface ConfigPreview {
+    changes: string[];
+    removed_sites: string[];
+}
+
+// Asks before a save that would delete sites; resolves to false when the user cancels
+function confirmRemovals(body: string): Promise<boolean> {
+    return apiFetch("/api/config/preview", {
+        method: "POST",
+        headers: { "Content-Type": "application/json" },
+        body,
+    }).then((response) => {
+        if (!response.ok) {
+            return response.text().then((text) => {
+                throw new Error(describeSaveError(text));
+            });
+        }
+        return response.json().then((preview: ConfigPreview) => {
+            const removed = preview.removed_sites;
+            if (removed.length === 0) {
+                return true;
+            }
+            const sites = removed.length === 1 ? "1 site" : `${removed.length} sites`;
+            return window.confirm(
+                `You are about to delete ${sites}: ${removed.join(", ")}.\n\n${preview.changes.join("\n")}`,
+            );
+        });
+    });
+}
+
+interface ApiErrorResponse {
+    code: string;
+    message: string;
+    errors: { field: string; message: string }[];
+}
+
+const SITE_FIELD_INPUTS: Record<string, string> = {
+    name: "site-name",
+    url: "site-url",
+    category: "site-category",
+    icon: "site-icon",
+    order: "site-order",
+    monitor_interval_secs: "site-interval",
+};
+
+function describeSaveError(body: string): string {
+    let response: ApiErrorResponse;
+    try {
+        response = JSON.parse(body) as ApiErrorResponse;
+    } catch {
+        return body || "Failed to save settings";
+    }
+
+    for (const error of response.errors) {
+        const input = inputForField(error.field);
+        if (input) {
+            setInputError(input, error.message);
+        }
+    }
+    if (response.errors.length === 0) {
+        return response.message;
+    }
+    return `Validation errors:\n${response.errors
+        .map((error) => `${error.field} ${error.message}`)
+        .join("\n")}`;
+}
+
+function inputForField(field: string): HTMLInputElement | null {
+    const topLevel: Record<string, string> = {
+        site_name: "settings-site-name",
+        opentelemetry_endpoint: "settings-telemetry-endpoint",
+    };
+    if (topLevel[field]) {
+        return document.getElementById(topLevel[field]) as HTMLInputElement | null;
+    }
+
+    const match = /^sites\[(\d+)\]\.(\w+)/.exec(field);
+    const prefix = match ? SITE_FIELD_INPUTS[match[2]] : undefined;
+    if (!match || !prefix) {
+        return null;
+    }
+    return document.getElementById(`${prefix}-${match[1]}`) as HTMLInputElement | null;
+}
+
 function validateSites(): string[] {
     if (!configData) {
         return [];
@@ -574,11 +801,23 @@ function validateSites(): string[] {
             `site-interval-${index}`,
         ) as HTMLInputElement | null;
 
+        const nameKey = site.name.trim().toLowerCase();
+        const duplicateOf = configData!.sites
+            .slice(0, index)
+            .findIndex((other) => other.name.trim().toLowerCase() === nameKey);
+
         if (!site.name.trim()) {
             errors.push(`Site ${index + 1}: Name is required.`);
             if (nameInput) {
                 setInputError(nameInput, "Site name cannot be empty");
             }
+        } else if (duplicateOf !== -1) {
+            errors.push(
+                `Site ${index + 1}: Name "${site.name}" is already used by site ${duplicateOf + 1}.`,
+            );
+            if (nameInput) {
+                setInputError(nameInput, "Site names must be unique (ignoring case)");
+            }
         }
 
         if (!site.url.trim()) {
@@ -594,8 +833,9 @@ function validateSites(): string[] {
         }
 
         if (
-            !Number.isFinite(site.monitor_interval_secs) ||
-            site.monitor_interval_secs < MIN_MONITOR_INTERVAL_SECS
+            site.monitor_interval_secs !== null &&
+            (!Number.isFinite(site.monitor_interval_secs) ||
+                site.monitor_interval_secs < MIN_MONITOR_INTERVAL_SECS)
         ) {
             errors.push(
                 `Site ${index + 1}: Interval must be at least ${MIN_MONITOR_INTERVAL_SECS} seconds.`,
@@ -662,6 +902,11 @@ function validateOptionalUrlInput(input: HTMLInputElement): boolean {
 }
 
 function validateIntervalInput(input: HTMLInputElement): boolean {
+    if (!input.value.trim()) {
+        clearInputError(input);
+        return true;
+    }
+
     const parsed = Number(input.value);
     if (!Number.isFinite(parsed) || parsed < MIN_MONITOR_INTERVAL_SECS) {
         setInputError(input, `Enter a value >= ${MIN_MONITOR_INTERVAL_SECS} seconds`);
@@ -673,6 +918,10 @@ function validateIntervalInput(input: HTMLInputElement): boolean {
 }
 
 function isValidUrl(candidate: string): boolean {
+    // URLs built from ${NAME} config variables are checked by the server once expanded
+    if (candidate.includes("${")) {
+        return true;
+    }
     try {
         const url = new URL(candidate);
         return url.protocol === "http:" || url.protocol === "https:";
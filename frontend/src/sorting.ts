@@ -0,0 +1,84 @@
+/**
+ * Keeps site cards in the configured `sort_by` order as uptime updates arrive,
+ * mirroring `sort_sites` in `uptime.rs`: ranks compare first and names, ignoring case,
+ * break ties. Cards are only reordered within their own group or category.
+ */
+
+const STATUS_RANKS: Record<string, number> = {
+  down: 0,
+  up: 2,
+  disabled: 3,
+};
+/** Rank of a site that has no completed check yet */
+const UNCHECKED_RANK = 1;
+
+function sortOrder(): string {
+  return document.body?.dataset?.sortBy ?? "config";
+}
+
+/**
+ * Store the sort rank of a card from a completed check.
+ */
+export function recordSortRank(
+  card: HTMLElement,
+  status: string,
+  uptimePercentage: number,
+  responseTimeMs?: number | null,
+): void {
+  let rank: number;
+  switch (sortOrder()) {
+    case "status":
+      rank = STATUS_RANKS[status] ?? UNCHECKED_RANK;
+      break;
+    case "uptime":
+      rank = Math.round(uptimePercentage * 100);
+      break;
+    case "response_time":
+      // Slowest first, and sites without a response before all
+      rank = typeof responseTimeMs === "number" ? -responseTimeMs : Number.NEGATIVE_INFINITY;
+      break;
+    default:
+      return;
+  }
+  card.dataset.sortRank = String(rank);
+}
+
+function cardRank(card: HTMLElement): number {
+  const rank = Number(card.dataset.sortRank);
+  if (!Number.isNaN(rank)) {
+    return rank;
+  }
+  return sortOrder() === "status" ? UNCHECKED_RANK : 0;
+}
+
+function compareCards(a: HTMLElement, b: HTMLElement): number {
+  const byRank = cardRank(a) - cardRank(b);
+  if (byRank !== 0 && !Number.isNaN(byRank)) {
+    return byRank;
+  }
+  const nameA = (a.dataset.siteName ?? "").toLowerCase();
+  const nameB = (b.dataset.siteName ?? "").toLowerCase();
+  return nameA < nameB ? -1 : nameA > nameB ? 1 : 0;
+}
+
+/**
+ * Reorder the site cards inside each of `containers`, unless sites keep the config order.
+ */
+export function sortSiteCards(containers: Iterable<HTMLElement>): void {
+  const order = sortOrder();
+  if (order === "config") {
+    return;
+  }
+
+  for (const container of containers) {
+    const cards = Array.from(container.children).filter(
+      (child): child is HTMLElement =>
+        child instanceof HTMLElement && child.classList.contains("site-card"),
+    );
+    const sorted = [...cards].sort(compareCards);
+    if (sorted.every((card, index) => card === cards[index])) {
+      continue;
+    }
+    sorted.forEach((card) => container.appendChild(card));
+  }
+}
@@ -1,101 +1,78 @@
-type ThemeMode = "dark" | "light";
-
-const THEME_STORAGE_KEY = "ironShieldTheme";
-let activeTheme: ThemeMode = "dark";
-let prefersLightQuery: MediaQueryList | null = null;
-let usingSystemPreference = true;
-
+type ThemeMode = "auto" | "dark" | "light";
+
+/** Cookie the server reads to render the page in this browser's theme (see `index.rs`). */
+const THEME_COOKIE = "iron_shield_theme";
+/** Where earlier versions kept the theme; migrated to the cookie on first load. */
+const LEGACY_THEME_STORAGE_KEY = "ironShieldTheme";
+const THEME_COOKIE_MAX_AGE_SECS = 60 * 60 * 24 * 365;
+
+/**
+ * Wires the theme select to the per-browser theme cookie.
+ *
+ * The server renders `data-theme` from the cookie or the configured `theme`, and `auto`
+ * follows `prefers-color-scheme` in CSS, so nothing needs to run before the first paint.
+ * Choosing "Dashboard default" clears the cookie so the configured theme applies again.
+ */
 export function initThemeManager(): void {
-  activeTheme = detectInitialTheme();
-  applyTheme(activeTheme);
-
-  if (usingSystemPreference) {
-    observeSystemPreference();
-  }
+  migrateStoredTheme();
 
   const select = document.getElementById("theme-mode-select") as HTMLSelectElement | null;
-  if (select) {
-    select.value = activeTheme;
-    select.addEventListener("change", () => {
-      const nextTheme = select.value === "light" ? "light" : "dark";
-      setTheme(nextTheme, { persist: true });
-    });
-  }
-}
-
-function detectInitialTheme(): ThemeMode {
-  if (typeof window === "undefined") {
-    return "dark";
-  }
-
-  const stored = getStoredTheme();
-  if (stored) {
-    usingSystemPreference = false;
-    return stored;
+  if (!select) {
+    return;
   }
 
-  usingSystemPreference = true;
-  return window.matchMedia?.("(prefers-color-scheme: light)").matches ? "light" : "dark";
+  select.value = readThemeCookie() ?? "";
+  select.addEventListener("change", () => {
+    const theme = parseTheme(select.value);
+    if (theme) {
+      writeThemeCookie(theme);
+      applyTheme(theme);
+    } else {
+      clearThemeCookie();
+      applyTheme(parseTheme(document.documentElement.dataset.defaultTheme) ?? "auto");
+    }
+  });
 }
 
 function applyTheme(theme: ThemeMode): void {
   document.documentElement.setAttribute("data-theme", theme);
 }
 
-function setTheme(theme: ThemeMode, options?: { persist?: boolean }): void {
-  activeTheme = theme;
-  applyTheme(theme);
-
-  if (options?.persist) {
-    usingSystemPreference = false;
-    try {
-      localStorage.setItem(THEME_STORAGE_KEY, theme);
-    } catch {
-      // Ignore persistence errors.
-    }
-  }
-
-  syncSelect(theme);
+function parseTheme(value: string | undefined): ThemeMode | null {
+  return value === "auto" || value === "dark" || value === "light" ? value : null;
 }
 
-function getStoredTheme(): ThemeMode | null {
-  try {
-    const stored = localStorage.getItem(THEME_STORAGE_KEY);
-    if (stored === "light" || stored === "dark") {
-      return stored;
-    }
-  } catch {
-    // Ignore storage access issues.
-  }
+function readThemeCookie(): ThemeMode | null {
+  const cookie = document.cookie
+    .split(";")
+    .map((entry) => entry.trim().split("="))
+    .find(([name]) => name === THEME_COOKIE);
+  return parseTheme(cookie?.[1]);
+}
 
-  return null;
+function writeThemeCookie(theme: ThemeMode): void {
+  document.cookie = `${THEME_COOKIE}=${theme}; path=/; max-age=${THEME_COOKIE_MAX_AGE_SECS}; SameSite=Lax`;
 }
 
-function observeSystemPreference(): void {
-  if (typeof window === "undefined" || typeof window.matchMedia !== "function") {
-    return;
-  }
+function clearThemeCookie(): void {
+  document.cookie = `${THEME_COOKIE}=; path=/; max-age=0; SameSite=Lax`;
+}
 
-  prefersLightQuery = window.matchMedia("(prefers-color-scheme: light)");
-  const handler = (event: MediaQueryList | MediaQueryListEvent): void => {
-    if (!usingSystemPreference) {
+/**
+ * Moves a theme chosen before it was kept in a cookie, so the choice is not lost.
+ */
+function migrateStoredTheme(): void {
+  try {
+    const stored = parseTheme(localStorage.getItem(LEGACY_THEME_STORAGE_KEY) ?? undefined);
+    if (stored === null) {
       return;
     }
-
-    const theme: ThemeMode = event.matches ? "light" : "dark";
-    setTheme(theme);
-  };
-
-  if (typeof prefersLightQuery.addEventListener === "function") {
-    prefersLightQuery.addEventListener("change", handler);
-  } else if (typeof prefersLightQuery.addListener === "function") {
-    prefersLightQuery.addListener(handler);
-  }
-}
-
-function syncSelect(theme: ThemeMode): void {
-  const select = document.getElementById("theme-mode-select") as HTMLSelectElement | null;
-  if (select && select.value !== theme) {
-    select.value = theme;
+    localStorage.removeItem(LEGACY_THEME_STORAGE_KEY);
+    if (readThemeCookie() === null) {
+      writeThemeCookie(stored);
+      applyTheme(stored);
+    }
+  } catch {
+    // Ignore storage access issues.
   }
 }
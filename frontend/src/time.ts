@@ -0,0 +1,29 @@
+/**
+ * Relative-time formatting shared by the dashboard widgets; mirrors `utils.rs`.
+ */
+
+/**
+ * `secs` as its two largest units, such as "45s", "12m", "3h 5m", or "2d 4h"; matches
+ * `format_duration` on the server.
+ */
+export function formatDuration(secs: number): string {
+  const days = Math.floor(secs / 86_400);
+  const hours = Math.floor(secs / 3600) % 24;
+  const minutes = Math.floor(secs / 60) % 60;
+  if (days > 0) {
+    return hours > 0 ? `${days}d ${hours}h` : `${days}d`;
+  }
+  if (hours > 0) {
+    return minutes > 0 ? `${hours}h ${minutes}m` : `${hours}h`;
+  }
+  return minutes > 0 ? `${minutes}m` : `${secs}s`;
+}
+
+/**
+ * The time from the Unix time `since` to `now` (both in seconds) put into the translated
+ * `template` in place of its `{}`, such as "checked 32s ago"; matches `format_relative`
+ * on the server.
+ */
+export function formatRelative(template: string, since: number, now: number): string {
+  return template.replace("{}", formatDuration(Math.max(now - since, 0)));
+}
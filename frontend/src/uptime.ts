@@ -1,5 +1,9 @@
 import { notifySiteStatusChange } from "./notifications.ts";
 import { prefersReducedMotion } from "./a11y.ts";
+import { t } from "./i18n.ts";
+import { recordOutage } from "./outages.ts";
+import { recordSortRank, sortSiteCards } from "./sorting.ts";
+import { formatRelative } from "./time.ts";
 
 /**
  * Data payload describing the current uptime status of a site card.
@@ -7,6 +11,8 @@ import { prefersReducedMotion } from "./a11y.ts";
 interface HistorySample {
   status: string;
   response_time_ms?: number | null;
+  /** Why the check failed, such as the first failing response assertion */
+  failure_reason?: string | null;
 }
 
 interface UptimeInfo {
@@ -16,14 +22,25 @@ interface UptimeInfo {
   history: HistorySample[];
   response_time_ms?: number | null;
   max_history_entries?: number;
+  /** Response times of the recent checks, oldest first, `null` for failed checks */
+  latency_ms?: (number | null)[];
+  /** Unix time in seconds the site went down, absent while it is not down */
+  down_since?: number | null;
+  /** Unix time in seconds the latest completed check started */
+  last_checked?: number | null;
+  /** Unix time in seconds the site entered its current status */
+  last_change?: number | null;
 }
 
 const DEFAULT_MAX_HISTORY_BARS = 50;
 let maxHistoryBars = resolveMaxHistoryBars();
 const HISTORY_ANIMATION_DURATION = 420;
-const STATUS_LABELS: Record<string, string> = {
-  disabled: "Paused",
-};
+/** Checks spanned by a sparkline; mirrors `LATENCY_SERIES_LENGTH` on the server. */
+const LATENCY_SERIES_LENGTH = 30;
+const SPARKLINE_WIDTH = 100;
+const SPARKLINE_HEIGHT = 24;
+const SPARKLINE_MARGIN = 2;
+const LAST_CHECKED_TICK_MS = 15_000;
 
 /**
  * Establish the SSE connection for uptime updates and update cards on new events.
@@ -33,7 +50,8 @@ export function initUptimeSSE(): void {
     return;
   }
 
-  const eventSource = new EventSource("/uptime");
+  const eventSource = new EventSource(resolveUptimeStreamUrl());
+  window.setInterval(refreshLastChecked, LAST_CHECKED_TICK_MS);
 
   eventSource.onmessage = (event) => {
     try {
@@ -42,11 +60,14 @@ export function initUptimeSSE(): void {
         return;
       }
 
+      const updatedLists = new Set<HTMLElement>();
       payload.forEach((entry) => {
         if (isUptimeInfo(entry)) {
-          updateSiteCard(entry);
+          updateSiteCard(entry).forEach((list) => updatedLists.add(list));
         }
       });
+      sortSiteCards(updatedLists);
+      refreshLastChecked();
     } catch (error) {
       console.error("Failed to parse uptime data", error);
     }
@@ -56,16 +77,46 @@ export function initUptimeSSE(): void {
     handleMaintenanceShutdown(eventSource, event.data);
   });
 
+  eventSource.addEventListener("config-updated", () => {
+    scheduleConfigRefresh();
+  });
+
+  eventSource.addEventListener("config-invalid", (event: MessageEvent) => {
+    showConfigErrorNotice(event.data);
+  });
+
   eventSource.onerror = (error) => {
     console.error("Uptime SSE connection error", error);
   };
 }
 
 /**
- * Apply an uptime update to the matching site card if it exists.
+ * Build the SSE endpoint URL, scoped to the current named dashboard and the page's
+ * tag and category filters, if any.
+ */
+function resolveUptimeStreamUrl(): string {
+  const params = new URLSearchParams();
+  const { dashboard, tag, category } = document.body.dataset;
+  if (dashboard) {
+    params.set("dashboard", dashboard);
+  }
+  if (tag) {
+    params.set("tag", tag);
+  }
+  if (category) {
+    params.set("category", category);
+  }
+  const query = params.toString();
+  return query ? `/uptime?${query}` : "/uptime";
+}
+
+/**
+ * Apply an uptime update to the matching site card if it exists, returning the lists
+ * of the cards that were updated.
  */
-function updateSiteCard(info: UptimeInfo): void {
+function updateSiteCard(info: UptimeInfo): HTMLElement[] {
   const siteCards = document.querySelectorAll<HTMLElement>(".site-card");
+  const lists: HTMLElement[] = [];
 
   siteCards.forEach((card) => {
     const siteName = card.querySelector<HTMLElement>(".site-name")?.textContent?.trim();
@@ -87,14 +138,25 @@ function updateSiteCard(info: UptimeInfo): void {
       const statusText = formatStatus(normalizedStatus);
       const percentage = info.uptime_percentage.toFixed(1);
       const averageResponse = formatAverageResponseLabel(info.history);
+      const lastChecked =
+        typeof info.last_checked === "number"
+          ? `data-checked-at="${info.last_checked}"`
+          : "hidden";
       uptimeElement.innerHTML = `
         <span class="status-text">${statusText}</span>
         <div class="uptime-details">
           <span class="uptime-percentage">${percentage}%</span>
           <span class="avg-response-time">${averageResponse}</span>
+          <span class="last-checked" ${lastChecked}></span>
         </div>
       `;
 
+      recordSortRank(card, normalizedStatus, info.uptime_percentage, info.response_time_ms);
+      recordOutage(info.site_id, normalizedStatus, info.down_since);
+      if (card.parentElement) {
+        lists.push(card.parentElement);
+      }
+
       notifySiteStatusChange({
         siteId: info.site_id,
         status: normalizedStatus,
@@ -106,9 +168,69 @@ function updateSiteCard(info: UptimeInfo): void {
       applyMaxHistoryOverride(info.max_history_entries);
       renderHistory(historyElement, info.history, info.site_id);
     }
+
+    const sparkline = card.querySelector<SVGPathElement>(".latency-sparkline path");
+    if (sparkline && info.latency_ms) {
+      sparkline.setAttribute("d", buildSparklinePath(info.latency_ms));
+    }
+  });
+
+  return lists;
+}
+
+/**
+ * Keep the "checked 32s ago" labels of the cards current between checks, like the
+ * server-rendered ones.
+ */
+function refreshLastChecked(): void {
+  const now = Math.floor(Date.now() / 1000);
+  const template = t("detail.checked_ago", "checked {} ago");
+  document.querySelectorAll<HTMLElement>(".last-checked[data-checked-at]").forEach((element) => {
+    const checkedAt = Number(element.dataset.checkedAt);
+    if (Number.isFinite(checkedAt)) {
+      element.textContent = formatRelative(template, checkedAt, now);
+    }
   });
 }
 
+/**
+ * Draw a latency series across the sparkline's view box like the server-rendered one:
+ * scaled between the fastest and slowest response, newest at the right edge, with a gap
+ * for each failed check.
+ */
+function buildSparklinePath(series: (number | null)[]): string {
+  const measured = series.filter((value): value is number => typeof value === "number");
+  if (measured.length === 0) {
+    return "";
+  }
+
+  const min = Math.min(...measured);
+  const max = Math.max(...measured);
+  const step = SPARKLINE_WIDTH / (LATENCY_SERIES_LENGTH - 1);
+  const offset = Math.max(LATENCY_SERIES_LENGTH - series.length, 0);
+  const segments: string[] = [];
+  let penDown = false;
+
+  series.forEach((value, index) => {
+    if (typeof value !== "number") {
+      penDown = false;
+      return;
+    }
+
+    const x = (offset + index) * step;
+    const y =
+      max === min
+        ? SPARKLINE_HEIGHT / 2
+        : SPARKLINE_HEIGHT -
+          SPARKLINE_MARGIN -
+          ((value - min) / (max - min)) * (SPARKLINE_HEIGHT - 2 * SPARKLINE_MARGIN);
+    segments.push(`${penDown ? "L" : "M"}${x.toFixed(1)} ${y.toFixed(1)}`);
+    penDown = true;
+  });
+
+  return segments.join(" ");
+}
+
 function renderHistory(element: HTMLElement, history: HistorySample[], siteId: string): void {
   const recentHistory = history.slice(-maxHistoryBars);
   const historyKey = createHistoryKey(recentHistory);
@@ -117,9 +239,9 @@ function renderHistory(element: HTMLElement, history: HistorySample[], siteId: s
     element.innerHTML = "";
     const placeholder = document.createElement("span");
     placeholder.className = "uptime-history-placeholder";
-    placeholder.textContent = "No history yet";
+    placeholder.textContent = t("history.empty", "No history yet");
     element.appendChild(placeholder);
-    element.setAttribute("aria-label", "No uptime history yet");
+    element.setAttribute("aria-label", t("history.empty_label", "No uptime history yet"));
     delete element.dataset.historyKey;
     delete element.dataset.pendingHistory;
     delete element.dataset.animating;
@@ -182,16 +304,20 @@ function buildHistoryBars(
     bar.id = anchorId;
     bar.setAttribute(
       "aria-label",
-      `${formatStatus(normalizedStatus)} – ${formatPopoverDetail(normalizedStatus, sample.response_time_ms)}`,
+      `${formatStatus(normalizedStatus)} – ${formatPopoverDetail(normalizedStatus, sample)}`,
     );
 
     const popover = document.createElement("dialog");
     popover.className = "history-bar-popover";
     popover.setAttribute("popover", "manual");
-    popover.innerHTML = `
-      <div class="popover-status">${formatStatus(normalizedStatus)}</div>
-      <div class="popover-label">${formatPopoverDetail(normalizedStatus, sample.response_time_ms)}</div>
-    `;
+    // Failure reasons can quote response headers and bodies, so they are set as text.
+    const statusLabel = document.createElement("div");
+    statusLabel.className = "popover-status";
+    statusLabel.textContent = formatStatus(normalizedStatus);
+    const detailLabel = document.createElement("div");
+    detailLabel.className = "popover-label";
+    detailLabel.textContent = formatPopoverDetail(normalizedStatus, sample);
+    popover.append(statusLabel, detailLabel);
 
     attachPopoverTriggers(bar, popover as PopoverElement);
 
@@ -279,46 +405,47 @@ function createHistoryKey(history: HistorySample[]): string {
   }
 
   return history
-    .map((sample) => `${sample.status}:${sample.response_time_ms ?? "na"}`)
+    .map(
+      (sample) =>
+        `${sample.status}:${sample.response_time_ms ?? "na"}:${sample.failure_reason ?? ""}`,
+    )
     .join("|");
 }
 
 function formatStatus(status: string): string {
   if (!status) {
-    return "Unknown";
+    return t("status.unknown", "Unknown");
   }
 
-  const normalized = status.toLowerCase();
-  if (STATUS_LABELS[normalized]) {
-    return STATUS_LABELS[normalized]!;
-  }
-
-  return `${status.charAt(0).toUpperCase()}${status.slice(1)}`;
+  return t(
+    `status.${status.toLowerCase()}`,
+    `${status.charAt(0).toUpperCase()}${status.slice(1)}`,
+  );
 }
 
-function formatPopoverDetail(status: string, responseTime?: number | null): string {
+function formatPopoverDetail(status: string, sample: HistorySample): string {
   if (status === "down") {
-    return "Unreachable";
+    return sample.failure_reason || t("detail.unreachable", "Unreachable");
   }
 
   if (status === "loading") {
-    return "Checking...";
+    return t("detail.checking", "Checking...");
   }
 
   if (status === "disabled") {
-    return "Monitoring paused";
+    return t("detail.paused", "Monitoring paused");
   }
 
-  return `Response ${formatResponseTime(responseTime)}`;
+  return `${t("detail.response", "Response")} ${formatResponseTime(sample.response_time_ms)}`;
 }
 
 function formatAverageResponseLabel(history: HistorySample[]): string {
   const average = calculateAverageResponseTime(history);
   if (average === null) {
-    return "Avg -- ms";
+    return `${t("response.average", "Avg")} -- ms`;
   }
 
-  return `Avg ${formatResponseTime(average)}`;
+  return `${t("response.average", "Avg")} ${formatResponseTime(average)}`;
 }
 
 function calculateAverageResponseTime(history: HistorySample[]): number | null {
@@ -336,7 +463,7 @@ function calculateAverageResponseTime(history: HistorySample[]): number | null {
 
 function formatResponseTime(value?: number | null): string {
   if (typeof value !== "number" || Number.isNaN(value) || value < 0) {
-    return "Not available";
+    return t("response.not_available", "Not available");
   }
 
   if (value >= 1000) {
@@ -405,6 +532,9 @@ function isUptimeInfo(value: unknown): value is UptimeInfo {
     typeof candidate.status === "string" &&
     typeof candidate.uptime_percentage === "number" &&
     isHistoryValid &&
+    (candidate.latency_ms === undefined ||
+      (Array.isArray(candidate.latency_ms) &&
+        candidate.latency_ms.every((value) => value === null || typeof value === "number"))) &&
     (candidate.response_time_ms === undefined ||
       candidate.response_time_ms === null ||
       typeof candidate.response_time_ms === "number")
@@ -421,10 +551,53 @@ function isHistorySample(value: unknown): value is HistorySample {
     typeof sample.status === "string" &&
     (sample.response_time_ms === undefined ||
       sample.response_time_ms === null ||
-      typeof sample.response_time_ms === "number")
+      typeof sample.response_time_ms === "number") &&
+    (sample.failure_reason === undefined ||
+      sample.failure_reason === null ||
+      typeof sample.failure_reason === "string")
   );
 }
 
+/** Delay before refreshing, so a save and the file watcher's reload refresh only once. */
+const CONFIG_REFRESH_DELAY_MS = 1000;
+let configRefreshTimer: ReturnType<typeof setTimeout> | null = null;
+
+/**
+ * Reload the page after the server's configuration changed so removed, added, and
+ * renamed sites are shown. Waits while the settings modal is open to avoid losing edits.
+ */
+function scheduleConfigRefresh(): void {
+  if (configRefreshTimer !== null) {
+    clearTimeout(configRefreshTimer);
+  }
+
+  configRefreshTimer = setTimeout(() => {
+    configRefreshTimer = null;
+    if (document.body.classList.contains("settings-modal-open")) {
+      scheduleConfigRefresh();
+      return;
+    }
+    window.location.reload();
+  }, CONFIG_REFRESH_DELAY_MS);
+}
+
+/**
+ * Show why the edited config file was rejected. The notice is rendered hidden with the
+ * page and cleared by the refresh that follows the next successful reload.
+ */
+function showConfigErrorNotice(rawMessage: unknown): void {
+  const notice = document.getElementById("config-error-notice");
+  if (!notice) {
+    return;
+  }
+
+  const message = notice.querySelector<HTMLElement>(".config-error-message");
+  if (message) {
+    message.textContent = typeof rawMessage === "string" ? rawMessage : "";
+  }
+  notice.hidden = false;
+}
+
 function handleMaintenanceShutdown(eventSource: EventSource, rawMessage: unknown): void {
   const fallback = "Server is shutting down for maintenance.";
   const message =
@@ -0,0 +1,282 @@
+//! Alert silencing
+//!
+//! A site that is known to be noisy, such as one being rebuilt, can be muted for a while
+//! without pausing its monitoring or removing the `notifiers` config:
+//!
+//! - `POST /api/alerts/{site}/silence?duration=2h` silences the site's notifications for
+//!   the given duration, replacing any earlier silence of it
+//! - `GET /api/alerts/silences` lists the active silences
+//! - `DELETE /api/alerts/{site}/silence` ends a silence early
+//!
+//! Durations combine whole numbers of days, hours, minutes, and seconds, such as `90m`,
+//! `1h30m`, or `2d`, and may be at most [`MAX_SILENCE_SECS`]. While a site is silenced,
+//! its incidents are still opened and closed, but neither the down nor the recovery
+//! notification is sent. Silences are kept in memory, so a restart clears them.
+
+use crate::error::{IronShieldError, Result};
+use crate::uptime::{unix_now, UptimeState};
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Json},
+};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tracing::{error, info};
+
+/// Longest a site can be silenced for, 30 days, so a forgotten silence does not mute a
+/// site for good
+pub const MAX_SILENCE_SECS: u64 = 30 * 24 * 60 * 60;
+
+/// A site whose notifications are muted
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Silence {
+    /// Name of the silenced site
+    pub site: String,
+    /// Unix timestamp the silence ends at
+    pub until: u64,
+}
+
+/// Query string of a request silencing a site
+#[derive(Debug, Deserialize)]
+pub struct SilenceQuery {
+    /// How long to silence the site for, such as `2h`
+    pub duration: String,
+}
+
+/// The active silences, by site name
+#[derive(Debug, Default)]
+pub struct Silences {
+    until: Mutex<HashMap<String, u64>>,
+}
+
+impl Silences {
+    /// Silence `site` until the Unix timestamp `until`, replacing any earlier silence.
+    pub fn silence(&self, site: &str, until: u64) {
+        self.until.lock().insert(site.to_string(), until);
+    }
+
+    /// End the silence of `site`, returning whether it was silenced at `now`.
+    pub fn clear(&self, site: &str, now: u64) -> bool {
+        self.until
+            .lock()
+            .remove(site)
+            .is_some_and(|until| until > now)
+    }
+
+    /// Whether the notifications of `site` are muted at the Unix timestamp `now`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use iron_shield::alerts::Silences;
+    ///
+    /// let silences = Silences::default();
+    /// silences.silence("Plex", 200);
+    ///
+    /// assert!(silences.is_silenced("Plex", 100));
+    /// assert!(!silences.is_silenced("Plex", 200));
+    /// assert!(!silences.is_silenced("NAS", 100));
+    /// ```
+    pub fn is_silenced(&self, site: &str, now: u64) -> bool {
+        self.until
+            .lock()
+            .get(site)
+            .is_some_and(|until| *until > now)
+    }
+
+    /// The silences still active at `now`, sorted by site name; expired ones are
+    /// forgotten.
+    pub fn active(&self, now: u64) -> Vec<Silence> {
+        let mut silences = self.until.lock();
+        silences.retain(|_, until| *until > now);
+        let mut active: Vec<Silence> = silences
+            .iter()
+            .map(|(site, until)| Silence {
+                site: site.clone(),
+                until: *until,
+            })
+            .collect();
+        active.sort_by(|a, b| a.site.cmp(&b.site));
+        active
+    }
+}
+
+/// Parse a silence duration such as `2h` or `1h30m` into seconds
+///
+/// # Errors
+///
+/// Returns [`IronShieldError::ConfigValidation`] for the `duration` field if the text is
+/// not a sequence of numbers with a `d`, `h`, `m`, or `s` unit, or is zero or longer
+/// than [`MAX_SILENCE_SECS`].
+///
+/// # Examples
+///
+/// ```
+/// use iron_shield::alerts::parse_duration;
+///
+/// assert_eq!(parse_duration("2h").unwrap(), 7200);
+/// assert_eq!(parse_duration("1h30m").unwrap(), 5400);
+/// assert_eq!(parse_duration(" 45s ").unwrap(), 45);
+/// assert!(parse_duration("2").is_err());
+/// assert!(parse_duration("0m").is_err());
+/// assert!(parse_duration("31d").is_err());
+/// ```
+pub fn parse_duration(text: &str) -> Result<u64> {
+    let invalid = |message: String| IronShieldError::ConfigValidation {
+        field: "duration".to_string(),
+        message,
+    };
+
+    let text = text.trim();
+    let mut total: u64 = 0;
+    let mut number = String::new();
+    for c in text.chars() {
+        if c.is_ascii_digit() {
+            number.push(c);
+            continue;
+        }
+        let unit = match c.to_ascii_lowercase() {
+            'd' => 24 * 60 * 60,
+            'h' => 60 * 60,
+            'm' => 60,
+            's' => 1,
+            _ => return Err(invalid(format!("\"{text}\" has an unknown unit '{c}'"))),
+        };
+        let value: u64 = std::mem::take(&mut number)
+            .parse()
+            .map_err(|_| invalid(format!("\"{text}\" needs a number before '{c}'")))?;
+        total = total.saturating_add(value.saturating_mul(unit));
+    }
+    if !number.is_empty() || text.is_empty() {
+        return Err(invalid(format!(
+            "\"{text}\" must end in a unit, such as \"30m\" or \"2h\""
+        )));
+    }
+    if total == 0 || total > MAX_SILENCE_SECS {
+        return Err(invalid(format!(
+            "\"{text}\" must be more than zero and at most 30 days"
+        )));
+    }
+    Ok(total)
+}
+
+/// The configured name of the site called `name`, ignoring case
+fn configured_site(state: &UptimeState, name: &str) -> Result<String> {
+    state
+        .config
+        .load()
+        .sites
+        .iter()
+        .find(|site| site.name.eq_ignore_ascii_case(name.trim()))
+        .map(|site| site.name.clone())
+        .ok_or_else(|| IronShieldError::SiteNotFound(name.to_string()))
+}
+
+/// Silence the notifications of a site
+///
+/// Handles `POST /api/alerts/{site}/silence?duration=2h` and responds with the
+/// [`Silence`].
+///
+/// # Errors
+///
+/// Responds with HTTP 404 if no site has the name and HTTP 400 if the duration is
+/// invalid.
+pub async fn silence_site(
+    State(state): State<Arc<UptimeState>>,
+    Path(name): Path<String>,
+    Query(query): Query<SilenceQuery>,
+) -> impl IntoResponse {
+    let result = parse_duration(&query.duration).and_then(|secs| {
+        let site = configured_site(&state, &name)?;
+        let until = unix_now().saturating_add(secs);
+        state.silences.silence(&site, until);
+        Ok(Silence { site, until })
+    });
+
+    match result {
+        Ok(silence) => {
+            info!(
+                "Silenced notifications of {} for {}",
+                silence.site,
+                query.duration.trim()
+            );
+            Json(silence).into_response()
+        }
+        Err(e) => {
+            error!("Error silencing site {name}: {e}");
+            e.into_response()
+        }
+    }
+}
+
+/// List the active silences
+///
+/// Handles `GET /api/alerts/silences`, responding with a JSON array of [`Silence`]
+/// sorted by site name.
+pub async fn list_silences(State(state): State<Arc<UptimeState>>) -> impl IntoResponse {
+    Json(state.silences.active(unix_now()))
+}
+
+/// End the silence of a site early
+///
+/// Handles `DELETE /api/alerts/{site}/silence` and responds with HTTP 204.
+///
+/// # Errors
+///
+/// Responds with HTTP 404 if no site has the name or the site is not silenced.
+pub async fn clear_silence(
+    State(state): State<Arc<UptimeState>>,
+    Path(name): Path<String>,
+) -> impl IntoResponse {
+    let result = configured_site(&state, &name).and_then(|site| {
+        if state.silences.clear(&site, unix_now()) {
+            Ok(site)
+        } else {
+            Err(IronShieldError::SilenceNotFound(site))
+        }
+    });
+
+    match result {
+        Ok(site) => {
+            info!("Cleared the silence of {site}");
+            StatusCode::NO_CONTENT.into_response()
+        }
+        Err(e) => {
+            error!("Error clearing the silence of {name}: {e}");
+            e.into_response()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn active_silences_forget_expired_ones() {
+        let silences = Silences::default();
+        silences.silence("Plex", 300);
+        silences.silence("NAS", 100);
+        silences.silence("Backup", 200);
+
+        assert_eq!(
+            silences.active(150),
+            [
+                Silence {
+                    site: "Backup".to_string(),
+                    until: 200,
+                },
+                Silence {
+                    site: "Plex".to_string(),
+                    until: 300,
+                },
+            ]
+        );
+        assert!(!silences.clear("NAS", 150));
+        assert!(silences.clear("Plex", 150));
+        assert!(!silences.is_silenced("Plex", 150));
+    }
+}
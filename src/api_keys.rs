@@ -0,0 +1,316 @@
+//! API keys for scripts and integrations
+//!
+//! Keys are listed under `api_keys` in the config file with only the SHA-256 hash of
+//! each key stored. While at least one key is configured, requests under `/api/` that
+//! change something must present a key, either as `Authorization: Bearer <key>` or in
+//! an `X-Api-Key` header; with `api_keys_protect_reads` set, reads under `/api/` need
+//! one too. Dashboard click telemetry stays open so the dashboard keeps working. With
+//! no keys configured the API is open, as it always was.
+//!
+//! Keys are created with `iron_shield --generate-api-key NAME` or `POST /api/keys`,
+//! which return the key once, listed with `GET /api/keys`, and revoked with
+//! `DELETE /api/keys/{name}`.
+
+use crate::config::{ApiKey, Config};
+use crate::error::IronShieldError;
+use crate::settings::update_config;
+use crate::uptime::UptimeState;
+use crate::utils::hex;
+use axum::{
+    extract::{Path, Request, State},
+    http::{header, HeaderMap, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+use tracing::{error, info, warn};
+
+/// Prefix of generated keys, so they are recognizable in scripts and secret scanners
+const KEY_PREFIX: &str = "ish_";
+
+/// Header a key can be sent in instead of `Authorization`
+const API_KEY_HEADER: &str = "x-api-key";
+
+/// Requests that stay open even when keys are configured
+const OPEN_PATHS: &[&str] = &["/api/telemetry/click"];
+
+/// Generate a new random API key
+///
+/// The key is [`KEY_PREFIX`] followed by 32 random bytes in hex.
+#[must_use]
+pub fn generate() -> String {
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    format!("{KEY_PREFIX}{}", hex(&bytes))
+}
+
+/// Hex-encoded SHA-256 hash of a key, as stored in the config file
+///
+/// # Examples
+///
+/// ```
+/// let hash = iron_shield::api_keys::hash("ish_example");
+///
+/// assert_eq!(hash.len(), 64);
+/// assert!(iron_shield::api_keys::is_hash(&hash));
+/// ```
+#[must_use]
+pub fn hash(key: &str) -> String {
+    hex(&Sha256::digest(key.as_bytes()))
+}
+
+/// Returns `true` when `value` looks like a hash made by [`hash`]
+#[must_use]
+pub fn is_hash(value: &str) -> bool {
+    value.len() == 64 && value.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// The configured key that `key` matches, if any
+///
+/// Hashes are compared in constant time so the response time does not reveal how much
+/// of a guessed key was right.
+#[must_use]
+pub fn find<'a>(keys: &'a [ApiKey], key: &str) -> Option<&'a ApiKey> {
+    let presented = hash(key);
+    keys.iter().find(|stored| {
+        constant_time_eq(
+            stored.hash.to_ascii_lowercase().as_bytes(),
+            presented.as_bytes(),
+        )
+    })
+}
+
+/// Whether a request needs an API key under `config`
+#[must_use]
+pub fn is_required(config: &Config, method: &Method, path: &str) -> bool {
+    if config.api_keys.is_empty() || !path.starts_with("/api/") || OPEN_PATHS.contains(&path) {
+        return false;
+    }
+    let reads = matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS);
+    !reads || config.api_keys_protect_reads
+}
+
+/// The key a request presents, from `Authorization: Bearer` or `X-Api-Key`
+#[must_use]
+pub fn presented(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .or_else(|| {
+            headers
+                .get(API_KEY_HEADER)
+                .and_then(|value| value.to_str().ok())
+        })
+        .map(str::trim)
+}
+
+/// Reject API requests that need a key and do not present a valid one
+///
+/// The configuration is read per request, so keys added or revoked through a config
+/// reload apply immediately. Rejected requests get HTTP 401 with a `WWW-Authenticate`
+/// header.
+pub async fn require_api_key(
+    State(state): State<Arc<UptimeState>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let config = state.config.load();
+    let allowed = !is_required(&config, request.method(), request.uri().path())
+        || presented(request.headers()).is_some_and(|key| find(&config.api_keys, key).is_some());
+    if allowed {
+        return next.run(request).await;
+    }
+
+    warn!(
+        "Rejected {} {} without a valid API key",
+        request.method(),
+        request.uri().path()
+    );
+    let mut response = IronShieldError::Unauthorized.into_response();
+    response.headers_mut().insert(
+        header::WWW_AUTHENTICATE,
+        header::HeaderValue::from_static("Bearer"),
+    );
+    response
+}
+
+/// Body of a request to create an API key
+#[derive(Debug, Deserialize)]
+pub struct NewApiKey {
+    /// Label of whoever will use the key
+    pub name: String,
+}
+
+/// A newly created API key; the only time the key itself is shown
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreatedApiKey {
+    /// Label of the key
+    pub name: String,
+    /// The key to send in `Authorization: Bearer` or `X-Api-Key`
+    pub key: String,
+}
+
+/// A configured API key as listed by the API, without its hash
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ApiKeySummary {
+    /// Label of the key
+    pub name: String,
+}
+
+/// List the names of the configured API keys
+pub async fn list_api_keys(State(state): State<Arc<UptimeState>>) -> impl IntoResponse {
+    Json(
+        state
+            .config
+            .load()
+            .api_keys
+            .iter()
+            .map(|key| ApiKeySummary {
+                name: key.name.clone(),
+            })
+            .collect::<Vec<_>>(),
+    )
+}
+
+/// Create an API key and save its hash to the configuration
+///
+/// Responds with HTTP 201 and a [`CreatedApiKey`]; the key cannot be retrieved later.
+/// The first key can be created without one, after which creating more needs a key.
+///
+/// # Errors
+///
+/// Responds with HTTP 409 if a key with the name exists, HTTP 400 if the name is
+/// empty, and HTTP 500 if the configuration cannot be saved.
+pub async fn create_api_key(
+    State(state): State<Arc<UptimeState>>,
+    Json(request): Json<NewApiKey>,
+) -> impl IntoResponse {
+    let name = request.name.trim().to_string();
+    let key = generate();
+
+    let result = update_config(&state, None, |config| {
+        if config.api_keys.iter().any(|existing| existing.name == name) {
+            return Err(IronShieldError::ApiKeyExists(name.clone()));
+        }
+        config.api_keys.push(ApiKey {
+            name: name.clone(),
+            hash: hash(&key),
+        });
+        Ok(())
+    });
+
+    match result {
+        Ok(_) => {
+            info!("Created API key {name}");
+            (StatusCode::CREATED, Json(CreatedApiKey { name, key })).into_response()
+        }
+        Err(e) => {
+            error!("Error creating API key {name}: {e}");
+            e.into_response()
+        }
+    }
+}
+
+/// Revoke an API key by removing it from the configuration
+///
+/// Responds with HTTP 204. Revoking the last key opens the API again.
+///
+/// # Errors
+///
+/// Responds with HTTP 404 if no key has the name and HTTP 500 if the configuration
+/// cannot be saved.
+pub async fn revoke_api_key(
+    State(state): State<Arc<UptimeState>>,
+    Path(name): Path<String>,
+) -> impl IntoResponse {
+    let result = update_config(&state, None, |config| {
+        let index = config
+            .api_keys
+            .iter()
+            .position(|key| key.name == name)
+            .ok_or_else(|| IronShieldError::ApiKeyNotFound(name.clone()))?;
+        config.api_keys.remove(index);
+        Ok(())
+    });
+
+    match result {
+        Ok(_) => {
+            info!("Revoked API key {name}");
+            StatusCode::NO_CONTENT.into_response()
+        }
+        Err(e) => {
+            error!("Error revoking API key {name}: {e}");
+            e.into_response()
+        }
+    }
+}
+
+/// Compare two byte strings without stopping at the first difference
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config_with_key(key: &str) -> Config {
+        Config {
+            api_keys: vec![ApiKey {
+                name: "scripts".to_string(),
+                hash: hash(key),
+            }],
+            ..Config::default()
+        }
+    }
+
+    #[test]
+    fn generated_keys_are_unique_and_match_only_their_own_hash() {
+        let key = generate();
+        let other = generate();
+        assert!(key.starts_with(KEY_PREFIX));
+        assert_ne!(key, other);
+
+        let config = config_with_key(&key);
+        assert_eq!(
+            find(&config.api_keys, &key).map(|found| found.name.as_str()),
+            Some("scripts")
+        );
+        assert!(find(&config.api_keys, &other).is_none());
+        assert!(find(&config.api_keys, "").is_none());
+    }
+
+    #[test]
+    fn keys_are_required_for_api_writes_and_optionally_reads() {
+        let open = Config::default();
+        assert!(!is_required(&open, &Method::POST, "/api/config"));
+
+        let mut config = config_with_key("ish_test");
+        assert!(is_required(&config, &Method::POST, "/api/config"));
+        assert!(is_required(&config, &Method::DELETE, "/api/keys/scripts"));
+        assert!(!is_required(&config, &Method::GET, "/api/config"));
+        assert!(!is_required(&config, &Method::POST, "/api/telemetry/click"));
+        assert!(!is_required(&config, &Method::GET, "/uptime"));
+
+        config.api_keys_protect_reads = true;
+        assert!(is_required(&config, &Method::GET, "/api/config"));
+        assert!(!is_required(&config, &Method::GET, "/"));
+    }
+
+    #[test]
+    fn presented_key_is_read_from_either_header() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(presented(&headers), None);
+
+        headers.insert(API_KEY_HEADER, "ish_header".parse().unwrap());
+        assert_eq!(presented(&headers), Some("ish_header"));
+
+        headers.insert(header::AUTHORIZATION, "Bearer ish_bearer".parse().unwrap());
+        assert_eq!(presented(&headers), Some("ish_bearer"));
+    }
+}
@@ -0,0 +1,461 @@
+//! Response assertions
+//!
+//! By default a site is up when it answers its check with a 2xx status. A site's
+//! `assertions` block replaces that rule with checks on the response:
+//!
+//! ```json5
+//! assertions: {
+//!     status: [200, 204],
+//!     headers: { "content-type": "application/json" },
+//!     body_contains: "healthy",
+//!     json_path: [{ path: "$.database.status", equals: "ok" }],
+//!     max_latency_ms: 500,
+//! }
+//! ```
+//!
+//! Assertions are evaluated in the order above, and the first one that fails is recorded
+//! as the reason the check failed. Body and JSON path assertions need the response body,
+//! so sites using them are checked with `GET` instead of `HEAD`.
+
+use reqwest::header::{HeaderMap, HeaderName};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// Largest response body, in bytes, read for body and JSON path assertions.
+pub const MAX_BODY_BYTES: usize = 1024 * 1024;
+
+/// Checks a site's response must pass for the site to count as up
+///
+/// # Fields
+///
+/// * `status` - Accepted status codes; any 2xx status when empty
+/// * `headers` - Response headers that must have exactly the given values
+/// * `body_contains` - Text the response body must contain
+/// * `json_path` - Values in a JSON response body, addressed like `$.items[0].name`
+/// * `max_latency_ms` - Longest the response may take, in milliseconds
+#[derive(Debug, Default, Deserialize, Serialize, Clone, PartialEq, Eq)]
+pub struct Assertions {
+    /// Accepted status codes; any 2xx status when empty
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub status: Vec<u16>,
+    /// Response headers that must be present with exactly these values
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub headers: BTreeMap<String, String>,
+    /// Text the response body must contain
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body_contains: Option<String>,
+    /// Values that must be present in a JSON response body
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub json_path: Vec<JsonPathAssertion>,
+    /// Longest the response may take, in milliseconds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_latency_ms: Option<u64>,
+}
+
+/// A value that must be present in a JSON response body
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+pub struct JsonPathAssertion {
+    /// Location of the value, such as `$.status` or `$.checks[0].healthy`
+    pub path: String,
+    /// Value the location must hold; when unset the location only has to exist
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub equals: Option<Value>,
+}
+
+/// The parts of a check's response that assertions are evaluated against
+#[derive(Debug)]
+pub struct CheckedResponse<'a> {
+    /// HTTP status code
+    pub status: u16,
+    /// Response headers
+    pub headers: &'a HeaderMap,
+    /// Response body, when it was read (see [`Assertions::needs_body`])
+    pub body: Option<&'a str>,
+    /// Time taken to receive the response, in milliseconds
+    pub latency_ms: u64,
+}
+
+/// One step of a parsed JSON path
+#[derive(Debug, PartialEq, Eq)]
+enum Segment {
+    Key(String),
+    Index(usize),
+}
+
+impl Assertions {
+    /// Returns `true` when evaluating the assertions requires the response body.
+    #[must_use]
+    pub fn needs_body(&self) -> bool {
+        self.body_contains.is_some() || !self.json_path.is_empty()
+    }
+
+    /// Check that every assertion can be evaluated, returning `(field, message)` for each
+    /// problem with `field` relative to the `assertions` block.
+    #[must_use]
+    pub fn validate(&self) -> Vec<(String, String)> {
+        let mut problems = Vec::new();
+        for (index, status) in self.status.iter().enumerate() {
+            if !(100..=599).contains(status) {
+                problems.push((
+                    format!("status[{index}]"),
+                    format!("{status} is not an HTTP status code"),
+                ));
+            }
+        }
+        for (name, value) in &self.headers {
+            if HeaderName::from_bytes(name.as_bytes()).is_err() {
+                problems.push((
+                    format!("headers.{name}"),
+                    "is not a valid header name".to_string(),
+                ));
+            } else if reqwest::header::HeaderValue::from_str(value).is_err() {
+                problems.push((
+                    format!("headers.{name}"),
+                    "is not a valid header value".to_string(),
+                ));
+            }
+        }
+        if self.body_contains.as_deref() == Some("") {
+            problems.push(("body_contains".to_string(), "cannot be empty".to_string()));
+        }
+        for (index, assertion) in self.json_path.iter().enumerate() {
+            if let Err(message) = parse_json_path(&assertion.path) {
+                problems.push((format!("json_path[{index}].path"), message));
+            }
+        }
+        if self.max_latency_ms == Some(0) {
+            problems.push((
+                "max_latency_ms".to_string(),
+                "must be at least 1".to_string(),
+            ));
+        }
+        problems
+    }
+
+    /// Evaluate the assertions against a response.
+    ///
+    /// # Errors
+    ///
+    /// Returns a description of the first assertion the response fails.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use iron_shield::assertions::{Assertions, CheckedResponse};
+    /// use reqwest::header::HeaderMap;
+    ///
+    /// let assertions = Assertions {
+    ///     body_contains: Some("healthy".to_string()),
+    ///     max_latency_ms: Some(500),
+    ///     ..Assertions::default()
+    /// };
+    /// let headers = HeaderMap::new();
+    /// let response = CheckedResponse {
+    ///     status: 200,
+    ///     headers: &headers,
+    ///     body: Some("degraded"),
+    ///     latency_ms: 120,
+    /// };
+    ///
+    /// assert_eq!(
+    ///     assertions.evaluate(&response),
+    ///     Err("body does not contain \"healthy\"".to_string())
+    /// );
+    /// ```
+    pub fn evaluate(&self, response: &CheckedResponse<'_>) -> Result<(), String> {
+        if self.status.is_empty() {
+            if !(200..300).contains(&response.status) {
+                return Err(format!("status {} is not successful", response.status));
+            }
+        } else if !self.status.contains(&response.status) {
+            let expected: Vec<String> = self.status.iter().map(u16::to_string).collect();
+            return Err(format!(
+                "status {} is not one of {}",
+                response.status,
+                expected.join(", ")
+            ));
+        }
+
+        for (name, expected) in &self.headers {
+            match response.headers.get(name.as_str()) {
+                None => return Err(format!("header {name} is missing")),
+                Some(actual) if actual.as_bytes() != expected.as_bytes() => {
+                    return Err(format!(
+                        "header {name} is \"{}\", expected \"{expected}\"",
+                        String::from_utf8_lossy(actual.as_bytes())
+                    ));
+                }
+                Some(_) => {}
+            }
+        }
+
+        let body = response.body.unwrap_or_default();
+        if let Some(needle) = &self.body_contains {
+            if !body.contains(needle.as_str()) {
+                return Err(format!("body does not contain \"{needle}\""));
+            }
+        }
+
+        if !self.json_path.is_empty() {
+            let document: Value =
+                serde_json::from_str(body).map_err(|e| format!("body is not valid JSON: {e}"))?;
+            for assertion in &self.json_path {
+                let path = &assertion.path;
+                let segments = parse_json_path(path)?;
+                let Some(actual) = lookup(&document, &segments) else {
+                    return Err(format!("{path} is missing"));
+                };
+                if let Some(expected) = &assertion.equals {
+                    if actual != expected {
+                        return Err(format!("{path} is {actual}, expected {expected}"));
+                    }
+                }
+            }
+        }
+
+        if let Some(max_latency_ms) = self.max_latency_ms {
+            if response.latency_ms > max_latency_ms {
+                return Err(format!(
+                    "response took {} ms, limit is {max_latency_ms} ms",
+                    response.latency_ms
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Parse a JSON path such as `$.items[0].name` into its segments.
+///
+/// The leading `$` is optional. Keys are separated by `.`, array indexes are written in
+/// brackets, and keys that are not plain identifiers can be quoted as `['a key']`.
+fn parse_json_path(path: &str) -> Result<Vec<Segment>, String> {
+    let trimmed = path.trim();
+    if trimmed.is_empty() {
+        return Err("cannot be empty".to_string());
+    }
+    let mut rest = trimmed.strip_prefix('$').unwrap_or(trimmed);
+    let mut segments = Vec::new();
+    let invalid = || format!("\"{path}\" is not a valid JSON path");
+
+    while !rest.is_empty() {
+        if let Some(after) = rest.strip_prefix('[') {
+            let end = after.find(']').ok_or_else(invalid)?;
+            let inner = &after[..end];
+            let quoted = inner
+                .strip_prefix('\'')
+                .and_then(|key| key.strip_suffix('\''))
+                .or_else(|| {
+                    inner
+                        .strip_prefix('"')
+                        .and_then(|key| key.strip_suffix('"'))
+                });
+            if let Some(key) = quoted {
+                segments.push(Segment::Key(key.to_string()));
+            } else {
+                segments.push(Segment::Index(inner.parse().map_err(|_| invalid())?));
+            }
+            rest = &after[end + 1..];
+        } else {
+            let after = if segments.is_empty() && rest.len() == trimmed.len() {
+                rest
+            } else {
+                rest.strip_prefix('.').ok_or_else(invalid)?
+            };
+            let end = after.find(['.', '[']).unwrap_or(after.len());
+            if end == 0 {
+                return Err(invalid());
+            }
+            segments.push(Segment::Key(after[..end].to_string()));
+            rest = &after[end..];
+        }
+    }
+    Ok(segments)
+}
+
+/// Follow `segments` from `document`, returning the value they address.
+fn lookup<'a>(document: &'a Value, segments: &[Segment]) -> Option<&'a Value> {
+    segments
+        .iter()
+        .try_fold(document, |value, segment| match segment {
+            Segment::Key(key) => value.get(key),
+            Segment::Index(index) => value.get(index),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn response<'a>(headers: &'a HeaderMap, body: Option<&'a str>) -> CheckedResponse<'a> {
+        CheckedResponse {
+            status: 200,
+            headers,
+            body,
+            latency_ms: 100,
+        }
+    }
+
+    #[test]
+    fn parses_json_paths() {
+        assert_eq!(
+            parse_json_path("$.checks[1].name"),
+            Ok(vec![
+                Segment::Key("checks".to_string()),
+                Segment::Index(1),
+                Segment::Key("name".to_string()),
+            ])
+        );
+        assert_eq!(
+            parse_json_path("status"),
+            Ok(vec![Segment::Key("status".to_string())])
+        );
+        assert_eq!(
+            parse_json_path("$['content-type']"),
+            Ok(vec![Segment::Key("content-type".to_string())])
+        );
+        assert_eq!(parse_json_path("$"), Ok(vec![]));
+        assert!(parse_json_path("$.").is_err());
+        assert!(parse_json_path("$.items[x]").is_err());
+        assert!(parse_json_path("$.items[0").is_err());
+        assert!(parse_json_path("$..name").is_err());
+        assert_eq!(parse_json_path(" "), Err("cannot be empty".to_string()));
+    }
+
+    #[test]
+    fn defaults_to_accepting_any_success_status() {
+        let headers = HeaderMap::new();
+        let assertions = Assertions::default();
+        assert_eq!(assertions.evaluate(&response(&headers, None)), Ok(()));
+
+        let failed = CheckedResponse {
+            status: 503,
+            ..response(&headers, None)
+        };
+        assert_eq!(
+            assertions.evaluate(&failed),
+            Err("status 503 is not successful".to_string())
+        );
+
+        let assertions = Assertions {
+            status: vec![503],
+            ..Assertions::default()
+        };
+        assert_eq!(assertions.evaluate(&failed), Ok(()));
+        assert_eq!(
+            assertions.evaluate(&response(&headers, None)),
+            Err("status 200 is not one of 503".to_string())
+        );
+    }
+
+    #[test]
+    fn reports_the_first_failing_assertion() {
+        let mut headers = HeaderMap::new();
+        headers.insert("content-type", "text/html".parse().unwrap());
+        let assertions = Assertions {
+            headers: BTreeMap::from([("Content-Type".to_string(), "application/json".to_string())]),
+            body_contains: Some("ok".to_string()),
+            max_latency_ms: Some(50),
+            ..Assertions::default()
+        };
+
+        assert_eq!(
+            assertions.evaluate(&response(&headers, Some("not found"))),
+            Err("header Content-Type is \"text/html\", expected \"application/json\"".to_string())
+        );
+
+        headers.insert("content-type", "application/json".parse().unwrap());
+        assert_eq!(
+            assertions.evaluate(&response(&headers, Some("not found"))),
+            Err("body does not contain \"ok\"".to_string())
+        );
+        assert_eq!(
+            assertions.evaluate(&response(&headers, Some("ok"))),
+            Err("response took 100 ms, limit is 50 ms".to_string())
+        );
+
+        headers.remove("content-type");
+        assert_eq!(
+            assertions.evaluate(&response(&headers, Some("ok"))),
+            Err("header Content-Type is missing".to_string())
+        );
+    }
+
+    #[test]
+    fn evaluates_json_path_assertions() {
+        let headers = HeaderMap::new();
+        let body = json!({ "status": "ok", "checks": [{ "healthy": true }] }).to_string();
+        let assertions = Assertions {
+            json_path: vec![
+                JsonPathAssertion {
+                    path: "$.status".to_string(),
+                    equals: Some(json!("ok")),
+                },
+                JsonPathAssertion {
+                    path: "$.checks[0].healthy".to_string(),
+                    equals: Some(json!(true)),
+                },
+                JsonPathAssertion {
+                    path: "$.checks[0]".to_string(),
+                    equals: None,
+                },
+            ],
+            ..Assertions::default()
+        };
+        assert!(assertions.needs_body());
+        assert_eq!(
+            assertions.evaluate(&response(&headers, Some(&body))),
+            Ok(())
+        );
+
+        let degraded = json!({ "status": "degraded", "checks": [] }).to_string();
+        assert_eq!(
+            assertions.evaluate(&response(&headers, Some(&degraded))),
+            Err("$.status is \"degraded\", expected \"ok\"".to_string())
+        );
+
+        let missing = json!({ "status": "ok", "checks": [] }).to_string();
+        assert_eq!(
+            assertions.evaluate(&response(&headers, Some(&missing))),
+            Err("$.checks[0].healthy is missing".to_string())
+        );
+
+        assert!(assertions
+            .evaluate(&response(&headers, Some("<html>")))
+            .unwrap_err()
+            .starts_with("body is not valid JSON"));
+    }
+
+    #[test]
+    fn validate_reports_unusable_assertions() {
+        let assertions = Assertions {
+            status: vec![200, 42],
+            headers: BTreeMap::from([("bad header".to_string(), "x".to_string())]),
+            body_contains: Some(String::new()),
+            json_path: vec![JsonPathAssertion {
+                path: "$.items[".to_string(),
+                equals: None,
+            }],
+            max_latency_ms: Some(0),
+        };
+        let fields: Vec<String> = assertions
+            .validate()
+            .into_iter()
+            .map(|(field, _)| field)
+            .collect();
+        assert_eq!(
+            fields,
+            vec![
+                "status[1]",
+                "headers.bad header",
+                "body_contains",
+                "json_path[0].path",
+                "max_latency_ms",
+            ]
+        );
+        assert!(Assertions::default().validate().is_empty());
+    }
+}
@@ -0,0 +1,274 @@
+//! Static asset caching helpers
+//!
+//! The frontend bundle is served from `/static` by `tower_http::services::ServeDir`,
+//! which already answers `If-Modified-Since` using file modification times. This module
+//! adds content fingerprints on top of that: every bundled file is hashed once at startup
+//! so responses can carry a strong `ETag`, and the combined fingerprint is exposed as an
+//! asset version that templates append to asset URLs (`/static/main.js?v=...`).
+//!
+//! Requests that carry the current asset version are safe to cache for a year because a
+//! rebuilt bundle changes the version and therefore the URL. Unversioned requests are
+//! revalidated on every use, which costs a `304 Not Modified` instead of a full download.
+
+use axum::{
+    extract::{Request, State},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::collections::{hash_map::DefaultHasher, HashMap};
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::Path;
+use std::sync::Arc;
+use tracing::warn;
+
+/// `Cache-Control` value for requests that reference the current asset version.
+pub const IMMUTABLE_CACHE_CONTROL: &str = "public, max-age=31536000, immutable";
+/// `Cache-Control` value for unversioned requests, which must be revalidated.
+pub const REVALIDATE_CACHE_CONTROL: &str = "public, no-cache";
+
+/// Content fingerprints for the bundled frontend assets
+///
+/// Maps each file path (relative to the static directory, using `/` separators)
+/// to a quoted strong `ETag`, and keeps a combined version string that changes
+/// whenever any bundled file changes.
+///
+/// # Examples
+///
+/// ```
+/// use iron_shield::assets::StaticAssets;
+///
+/// let assets = StaticAssets::default();
+/// assert!(assets.etag("main.js").is_none());
+/// assert!(!assets.version().is_empty());
+/// ```
+#[derive(Debug, Clone)]
+pub struct StaticAssets {
+    /// Quoted `ETag` values keyed by relative asset path
+    etags: HashMap<String, String>,
+    /// Short fingerprint of the whole bundle used for cache busting
+    version: String,
+}
+
+impl Default for StaticAssets {
+    fn default() -> Self {
+        Self::from_files(Vec::new())
+    }
+}
+
+impl StaticAssets {
+    /// Hash every file below `dir` and build the fingerprint table.
+    ///
+    /// Missing or unreadable directories produce an empty table so the server can still
+    /// start without a bundled frontend; assets are then served without `ETag` headers.
+    #[must_use]
+    pub fn load(dir: &Path) -> Self {
+        let mut files = Vec::new();
+        collect_files(dir, dir, &mut files);
+        Self::from_files(files)
+    }
+
+    fn from_files(mut files: Vec<(String, Vec<u8>)>) -> Self {
+        files.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let mut bundle_hasher = DefaultHasher::new();
+        let mut etags = HashMap::with_capacity(files.len());
+        for (path, contents) in files {
+            let digest = fingerprint(&contents);
+            path.hash(&mut bundle_hasher);
+            digest.hash(&mut bundle_hasher);
+            etags.insert(path, format!("\"{digest:016x}\""));
+        }
+
+        Self {
+            etags,
+            version: format!("{:08x}", bundle_hasher.finish() & 0xffff_ffff),
+        }
+    }
+
+    /// Returns the quoted `ETag` for an asset path relative to the static directory.
+    #[must_use]
+    pub fn etag(&self, path: &str) -> Option<&str> {
+        self.etags
+            .get(path.trim_start_matches('/'))
+            .map(String::as_str)
+    }
+
+    /// Returns the bundle version appended to asset URLs as `?v=...`.
+    #[must_use]
+    pub fn version(&self) -> &str {
+        &self.version
+    }
+}
+
+/// Middleware adding `ETag` and `Cache-Control` headers to static asset responses.
+///
+/// Answers `If-None-Match` requests for unchanged assets with `304 Not Modified`
+/// without touching the filesystem; everything else is delegated to the wrapped
+/// `ServeDir` service.
+pub async fn static_cache_headers(
+    State(assets): State<Arc<StaticAssets>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let etag = assets.etag(request.uri().path()).map(str::to_string);
+    let versioned = request
+        .uri()
+        .query()
+        .is_some_and(|query| query_has_version(query, assets.version()));
+    let cache_control = if versioned {
+        IMMUTABLE_CACHE_CONTROL
+    } else {
+        REVALIDATE_CACHE_CONTROL
+    };
+
+    if let Some(etag) = etag.as_deref() {
+        if if_none_match_matches(request.headers(), etag) {
+            let mut response = StatusCode::NOT_MODIFIED.into_response();
+            insert_cache_headers(response.headers_mut(), Some(etag), cache_control);
+            return response;
+        }
+    }
+
+    let mut response = next.run(request).await;
+    if response.status().is_success() {
+        insert_cache_headers(response.headers_mut(), etag.as_deref(), cache_control);
+    }
+    response
+}
+
+pub(crate) fn insert_cache_headers(
+    headers: &mut HeaderMap,
+    etag: Option<&str>,
+    cache_control: &str,
+) {
+    if let Some(value) = etag.and_then(|etag| HeaderValue::from_str(etag).ok()) {
+        headers.insert(header::ETAG, value);
+    }
+    headers.insert(
+        header::CACHE_CONTROL,
+        HeaderValue::from_str(cache_control)
+            .unwrap_or_else(|_| HeaderValue::from_static(REVALIDATE_CACHE_CONTROL)),
+    );
+}
+
+/// Checks whether an `If-None-Match` header lists the given `ETag` (or `*`).
+pub(crate) fn if_none_match_matches(headers: &HeaderMap, etag: &str) -> bool {
+    headers
+        .get_all(header::IF_NONE_MATCH)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(|candidate| candidate.trim().trim_start_matches("W/"))
+        .any(|candidate| candidate == "*" || candidate == etag)
+}
+
+fn query_has_version(query: &str, version: &str) -> bool {
+    query
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .any(|(key, value)| key == "v" && value == version)
+}
+
+pub(crate) fn fingerprint(contents: &[u8]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    contents.hash(&mut hasher);
+    hasher.finish()
+}
+
+fn collect_files(root: &Path, dir: &Path, files: &mut Vec<(String, Vec<u8>)>) {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(err) => {
+            warn!(
+                "Unable to read static asset directory {}: {err}",
+                dir.display()
+            );
+            return;
+        }
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            collect_files(root, &path, files);
+            continue;
+        }
+
+        let Ok(relative) = path.strip_prefix(root) else {
+            continue;
+        };
+        let key = relative
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+
+        match fs::read(&path) {
+            Ok(contents) => files.push((key, contents)),
+            Err(err) => warn!("Unable to fingerprint asset {}: {err}", path.display()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assets_with(files: &[(&str, &str)]) -> StaticAssets {
+        StaticAssets::from_files(
+            files
+                .iter()
+                .map(|(path, contents)| ((*path).to_string(), contents.as_bytes().to_vec()))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn load_fingerprints_nested_files() {
+        let dir = tempfile::tempdir().expect("Failed to create temporary asset directory");
+        fs::write(dir.path().join("main.js"), "console.log(1);").expect("write main.js");
+        fs::create_dir(dir.path().join("icons")).expect("create icons dir");
+        fs::write(dir.path().join("icons").join("app.svg"), "<svg/>").expect("write icon");
+
+        let assets = StaticAssets::load(dir.path());
+
+        assert!(assets.etag("main.js").is_some());
+        assert!(assets.etag("/icons/app.svg").is_some());
+        assert!(assets.etag("missing.css").is_none());
+    }
+
+    #[test]
+    fn version_changes_when_contents_change() {
+        let before = assets_with(&[("main.js", "a")]);
+        let after = assets_with(&[("main.js", "b")]);
+        let same = assets_with(&[("main.js", "a")]);
+
+        assert_ne!(before.version(), after.version());
+        assert_ne!(before.etag("main.js"), after.etag("main.js"));
+        assert_eq!(before.version(), same.version());
+    }
+
+    #[test]
+    fn if_none_match_accepts_lists_weak_tags_and_wildcards() {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::IF_NONE_MATCH,
+            HeaderValue::from_static("\"other\", W/\"abc\""),
+        );
+        assert!(if_none_match_matches(&headers, "\"abc\""));
+        assert!(!if_none_match_matches(&headers, "\"def\""));
+
+        headers.insert(header::IF_NONE_MATCH, HeaderValue::from_static("*"));
+        assert!(if_none_match_matches(&headers, "\"def\""));
+    }
+
+    #[test]
+    fn query_version_must_match_current_bundle() {
+        assert!(query_has_version("v=1234", "1234"));
+        assert!(query_has_version("foo=bar&v=1234", "1234"));
+        assert!(!query_has_version("v=9999", "1234"));
+        assert!(!query_has_version("version=1234", "1234"));
+    }
+}
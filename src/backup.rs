@@ -0,0 +1,281 @@
+//! Timestamped backups of the configuration file
+//!
+//! Before the settings API overwrites the config file, the previous version is copied to
+//! a `backups` directory next to it as `{stem}-{timestamp}.{extension}` (for example
+//! `backups/config-20240102T030405123Z.json5`). Only the newest
+//! `config_backup_retention` backups are kept. A backup can be restored through
+//! `POST /api/config/backups/{name}/restore` or with `iron_shield --restore-backup`, and
+//! `POST /api/config/rollback` restores the newest one to undo the last change.
+
+use crate::config::{write_atomic, Config};
+use crate::error::{IronShieldError, Result};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+/// Name of the directory, next to the config file, that holds backups.
+pub const BACKUP_DIR: &str = "backups";
+
+/// Timestamp format embedded in backup file names; sorts chronologically as text.
+const TIMESTAMP_FORMAT: &str = "%Y%m%dT%H%M%S%3fZ";
+
+/// A backup of the configuration file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Backup {
+    /// File name of the backup, used to select it for restoring
+    pub name: String,
+    /// When the backup was taken
+    pub created: DateTime<Utc>,
+    /// Full path of the backup file
+    #[serde(skip)]
+    pub path: PathBuf,
+}
+
+/// Directory holding the backups of `config_path`.
+#[must_use]
+pub fn backup_dir(config_path: &Path) -> PathBuf {
+    match config_path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent.join(BACKUP_DIR),
+        _ => PathBuf::from(BACKUP_DIR),
+    }
+}
+
+/// Split a config path into the file stem and extension used in backup names.
+fn name_parts(config_path: &Path) -> (String, String) {
+    let stem = config_path.file_stem().map_or_else(
+        || "config".to_string(),
+        |s| s.to_string_lossy().into_owned(),
+    );
+    let extension = config_path
+        .extension()
+        .map_or_else(String::new, |e| e.to_string_lossy().into_owned());
+    (stem, extension)
+}
+
+/// Parse a backup file name of `config_path`, returning when it was taken.
+fn parse_backup_name(config_path: &Path, name: &str) -> Option<DateTime<Utc>> {
+    let (stem, extension) = name_parts(config_path);
+    let timestamp = name.strip_prefix(&format!("{stem}-"))?;
+    let timestamp = if extension.is_empty() {
+        timestamp
+    } else {
+        timestamp.strip_suffix(&format!(".{extension}"))?
+    };
+    NaiveDateTime::parse_from_str(timestamp, TIMESTAMP_FORMAT)
+        .ok()
+        .map(|created| created.and_utc())
+}
+
+/// List the backups of `config_path`, newest first.
+///
+/// # Errors
+///
+/// Returns an error if the backup directory exists but cannot be read.
+pub fn list_backups(config_path: &Path) -> Result<Vec<Backup>> {
+    let dir = backup_dir(config_path);
+    let entries = match fs::read_dir(&dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => {
+            return Err(IronShieldError::from(format!(
+                "Failed to read backup directory {}: {e}",
+                dir.display()
+            )))
+        }
+    };
+
+    let mut backups: Vec<Backup> = entries
+        .filter_map(std::result::Result::ok)
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let created = parse_backup_name(config_path, &name)?;
+            Some(Backup {
+                name,
+                created,
+                path: entry.path(),
+            })
+        })
+        .collect();
+    backups.sort_by(|a, b| b.name.cmp(&a.name));
+    Ok(backups)
+}
+
+/// Copy the current config file into the backup directory, then prune old backups.
+///
+/// Nothing is done when `retention` is 0 or the config file does not exist yet.
+///
+/// # Errors
+///
+/// Returns an error if the backup directory cannot be created or the file cannot be
+/// copied. Failing to remove an old backup is only logged.
+pub fn create_backup(config_path: &Path, retention: usize) -> Result<Option<Backup>> {
+    if retention == 0 || !config_path.exists() {
+        return Ok(None);
+    }
+
+    let dir = backup_dir(config_path);
+    fs::create_dir_all(&dir).map_err(|e| {
+        IronShieldError::from(format!(
+            "Failed to create backup directory {}: {e}",
+            dir.display()
+        ))
+    })?;
+
+    let created = Utc::now();
+    let (stem, extension) = name_parts(config_path);
+    let timestamp = created.format(TIMESTAMP_FORMAT);
+    let name = if extension.is_empty() {
+        format!("{stem}-{timestamp}")
+    } else {
+        format!("{stem}-{timestamp}.{extension}")
+    };
+    let path = dir.join(&name);
+    fs::copy(config_path, &path)
+        .map_err(|e| IronShieldError::from(format!("Failed to back up config file: {e}")))?;
+    info!("Backed up configuration to {}", path.display());
+
+    for old in list_backups(config_path)?.into_iter().skip(retention) {
+        if let Err(e) = fs::remove_file(&old.path) {
+            warn!("Failed to remove old backup {}: {e}", old.path.display());
+        }
+    }
+
+    Ok(Some(Backup {
+        name,
+        created,
+        path,
+    }))
+}
+
+/// Replace the config file with the backup called `name` and load it.
+///
+/// The current file is backed up first, so a restore can itself be undone. If the
+/// restored file fails to load, the previous contents are put back and the error is
+/// returned.
+///
+/// # Errors
+///
+/// Returns an error if no backup called `name` exists, if a file cannot be read or
+/// written, or if the restored configuration fails to load or validate.
+pub fn restore_backup(config_path: &Path, name: &str, retention: usize) -> Result<Config> {
+    let backup = list_backups(config_path)?
+        .into_iter()
+        .find(|backup| backup.name == name)
+        .ok_or_else(|| IronShieldError::from(format!("No config backup named {name}")))?;
+    let contents = fs::read_to_string(&backup.path)?;
+    let previous = fs::read_to_string(config_path).ok();
+
+    create_backup(config_path, retention)?;
+    write_atomic(config_path, &contents)
+        .map_err(|e| IronShieldError::from(format!("Failed to write config file: {e}")))?;
+
+    match Config::load(&config_path.to_path_buf()) {
+        Ok(config) => {
+            info!("Restored configuration from backup {name}");
+            Ok(config)
+        }
+        Err(e) => {
+            if let Some(previous) = previous {
+                write_atomic(config_path, &previous).map_err(|rollback| {
+                    IronShieldError::from(format!(
+                        "Backup {name} is invalid ({e}) and the previous config could not be put back: {rollback}"
+                    ))
+                })?;
+            }
+            Err(e)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn create_backup_keeps_only_the_newest_backups() {
+        let dir = tempfile::tempdir().expect("Failed to create temporary directory");
+        let config_path = dir.path().join("config.json5");
+
+        assert_eq!(create_backup(&config_path, 2).expect("no file"), None);
+
+        for version in 0..4 {
+            fs::write(&config_path, format!("{{ site_name: \"v{version}\" }}"))
+                .expect("Failed to write config");
+            create_backup(&config_path, 2)
+                .expect("Backup should succeed")
+                .expect("Backup should be created");
+            std::thread::sleep(std::time::Duration::from_millis(5));
+        }
+
+        let backups = list_backups(&config_path).expect("Failed to list backups");
+        assert_eq!(backups.len(), 2);
+        assert!(backups[0].name.starts_with("config-"));
+        assert!(backups[0].name.ends_with(".json5"));
+        assert!(backups[0].created > backups[1].created);
+        assert_eq!(
+            fs::read_to_string(&backups[0].path).expect("Failed to read backup"),
+            "{ site_name: \"v3\" }"
+        );
+    }
+
+    #[test]
+    fn create_backup_is_disabled_by_zero_retention() {
+        let dir = tempfile::tempdir().expect("Failed to create temporary directory");
+        let config_path = dir.path().join("config.json5");
+        fs::write(&config_path, "{}").expect("Failed to write config");
+
+        assert_eq!(create_backup(&config_path, 0).expect("no-op"), None);
+        assert!(!backup_dir(&config_path).exists());
+    }
+
+    #[test]
+    fn restore_backup_replaces_config_and_backs_up_current_file() {
+        let dir = tempfile::tempdir().expect("Failed to create temporary directory");
+        let config_path = dir.path().join("config.json5");
+        fs::write(&config_path, "{ site_name: \"Original\" }").expect("Failed to write config");
+        let backup = create_backup(&config_path, 10)
+            .expect("Backup should succeed")
+            .expect("Backup should be created");
+        std::thread::sleep(std::time::Duration::from_millis(5));
+        fs::write(&config_path, "{ site_name: \"Broken edit\" }").expect("Failed to write");
+
+        let config =
+            restore_backup(&config_path, &backup.name, 10).expect("Restore should succeed");
+
+        assert_eq!(config.site_name, "Original");
+        assert_eq!(
+            fs::read_to_string(&config_path).expect("Failed to read config"),
+            "{ site_name: \"Original\" }"
+        );
+        let backups = list_backups(&config_path).expect("Failed to list backups");
+        assert_eq!(backups.len(), 2);
+        assert_eq!(
+            fs::read_to_string(&backups[0].path).expect("Failed to read backup"),
+            "{ site_name: \"Broken edit\" }"
+        );
+    }
+
+    #[test]
+    fn restore_backup_rejects_unknown_names_and_invalid_backups() {
+        let dir = tempfile::tempdir().expect("Failed to create temporary directory");
+        let config_path = dir.path().join("config.json5");
+        fs::write(
+            &config_path,
+            "{ site_name: \"Bad\", sites: [{ name: \"\" }] }",
+        )
+        .expect("Failed to write config");
+        let backup = create_backup(&config_path, 10)
+            .expect("Backup should succeed")
+            .expect("Backup should be created");
+        fs::write(&config_path, "{ site_name: \"Good\" }").expect("Failed to write config");
+
+        assert!(restore_backup(&config_path, "../config.json5", 10).is_err());
+        assert!(restore_backup(&config_path, &backup.name, 0).is_err());
+        assert_eq!(
+            fs::read_to_string(&config_path).expect("Failed to read config"),
+            "{ site_name: \"Good\" }"
+        );
+    }
+}
@@ -0,0 +1,335 @@
+//! Importing bookmarks exported from a browser
+//!
+//! Browsers export bookmarks as a Netscape bookmark file: an HTML document in which each
+//! folder is an `<H3>` heading followed by a `<DL>` list, and each bookmark is an `<A>`
+//! link. Importing one turns every `http` or `https` link into a [`Site`] whose category
+//! is the name of the folder holding it, so a dashboard can be seeded from existing
+//! bookmarks in one step. Links directly in the bookmarks toolbar or outside any folder
+//! get no category, and Firefox `TAGS` become the site's tags.
+//!
+//! Files are imported with `POST /api/config/import/bookmarks`, sending the exported
+//! HTML as the body, or with `iron_shield --import-bookmarks FILE [CONFIG]`. They are
+//! added like other imports (see the `import` module): bookmarks whose URL is already
+//! configured are skipped, and a bookmark whose title is taken by another site gets a
+//! numbered name such as `Docs (2)`.
+
+use crate::config::Site;
+use crate::import::{import_response, Converted};
+use crate::uptime::UptimeState;
+use axum::{extract::State, response::IntoResponse};
+use std::sync::Arc;
+
+/// A link read from a bookmark file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Bookmark {
+    /// Title of the link, with HTML entities decoded
+    pub title: String,
+    /// Target of the link
+    pub url: String,
+    /// Name of the innermost folder holding the link, if any
+    pub folder: Option<String>,
+    /// Tags from the link's `TAGS` attribute
+    pub tags: Vec<String>,
+}
+
+/// Read the links of a Netscape bookmark file
+///
+/// Parsing is lenient: unknown tags are ignored and an unclosed folder ends with the file.
+///
+/// # Examples
+///
+/// ```
+/// use iron_shield::bookmarks::parse;
+///
+/// let html = r#"<!DOCTYPE NETSCAPE-Bookmark-file-1>
+/// <DL><p>
+///     <DT><H3>Media</H3>
+///     <DL><p>
+///         <DT><A HREF="https://plex.example.com" TAGS="home,tv">Plex &amp; Friends</A>
+///     </DL><p>
+///     <DT><A HREF="https://example.com">Example</A>
+/// </DL><p>"#;
+///
+/// let bookmarks = parse(html);
+/// assert_eq!(bookmarks[0].title, "Plex & Friends");
+/// assert_eq!(bookmarks[0].folder.as_deref(), Some("Media"));
+/// assert_eq!(bookmarks[0].tags, ["home", "tv"]);
+/// assert_eq!(bookmarks[1].folder, None);
+/// ```
+#[must_use]
+pub fn parse(html: &str) -> Vec<Bookmark> {
+    let mut bookmarks = Vec::new();
+    // One entry per open `<DL>`: the folder it lists, or `None` for the toolbar and root
+    let mut folders: Vec<Option<String>> = Vec::new();
+    // Folder named by the last `<H3>`, waiting for the `<DL>` that lists its contents
+    let mut pending_folder: Option<Option<String>> = None;
+
+    let mut rest = html;
+    while let Some(start) = rest.find('<') {
+        let Some(end) = rest[start..].find('>') else {
+            break;
+        };
+        let tag = &rest[start + 1..start + end];
+        rest = &rest[start + end + 1..];
+        let text = || decode_entities(rest[..rest.find('<').unwrap_or(rest.len())].trim());
+
+        let (name, attributes) = tag.split_once(char::is_whitespace).unwrap_or((tag, ""));
+        match name.to_ascii_lowercase().as_str() {
+            "h3" => {
+                let toolbar = attribute(attributes, "personal_toolbar_folder").is_some();
+                pending_folder = Some((!toolbar).then(text).filter(|name| !name.is_empty()));
+            }
+            "dl" => folders.push(pending_folder.take().flatten()),
+            "/dl" => {
+                folders.pop();
+            }
+            "a" => {
+                let Some(url) = attribute(attributes, "href") else {
+                    continue;
+                };
+                bookmarks.push(Bookmark {
+                    title: text(),
+                    url,
+                    folder: folders.last().cloned().flatten(),
+                    tags: attribute(attributes, "tags")
+                        .map(|tags| {
+                            tags.split(',')
+                                .map(str::trim)
+                                .filter(|tag| !tag.is_empty())
+                                .map(str::to_string)
+                                .collect()
+                        })
+                        .unwrap_or_default(),
+                });
+            }
+            _ => {}
+        }
+    }
+    bookmarks
+}
+
+/// Sites for `bookmarks`, named after their titles and categorized by their folders
+#[must_use]
+pub fn convert(bookmarks: &[Bookmark]) -> Converted {
+    Converted {
+        sites: bookmarks
+            .iter()
+            .map(|bookmark| Site {
+                name: bookmark.title.clone(),
+                url: bookmark.url.clone(),
+                category: bookmark.folder.clone().unwrap_or_default(),
+                tags: bookmark.tags.clone(),
+                monitor_interval_secs: None,
+                monitor_timeout_secs: None,
+                monitor_retries: None,
+                monitor_user_agent: None,
+                disabled: false,
+                icon: None,
+                order: None,
+                assertions: None,
+                uptime_percentage: 0.0,
+            })
+            .collect(),
+        unsupported: Vec::new(),
+    }
+}
+
+/// Import a bookmark file sent as the request body
+///
+/// Handles `POST /api/config/import/bookmarks`. The sites are added, then the
+/// configuration is validated, saved, and applied like a full save. Responds with an
+/// [`crate::import::ImportSummary`].
+///
+/// # Errors
+///
+/// Responds with HTTP 400 if the imported sites make the configuration invalid and HTTP
+/// 500 if the configuration cannot be saved.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use axum::{Router, routing::post};
+/// use iron_shield::bookmarks::import_bookmarks;
+///
+/// let app = Router::new()
+///     .route("/api/config/import/bookmarks", post(import_bookmarks));
+/// ```
+pub async fn import_bookmarks(
+    State(state): State<Arc<UptimeState>>,
+    html: String,
+) -> impl IntoResponse {
+    import_response(&state, convert(&parse(&html)), "bookmarks")
+}
+
+/// Value of the attribute called `name` (compared case-insensitively), decoded.
+pub(crate) fn attribute(attributes: &str, name: &str) -> Option<String> {
+    let mut rest = attributes.trim_start();
+    while !rest.is_empty() {
+        let key_end = rest
+            .find(|c: char| c == '=' || c.is_whitespace())
+            .unwrap_or(rest.len());
+        let key = &rest[..key_end];
+        rest = rest[key_end..].trim_start();
+
+        let mut value = "";
+        if let Some(after_equals) = rest.strip_prefix('=') {
+            let after_equals = after_equals.trim_start();
+            let (raw, remaining) = match after_equals.chars().next() {
+                Some(quote @ ('"' | '\'')) => {
+                    let quoted = &after_equals[1..];
+                    let close = quoted.find(quote).unwrap_or(quoted.len());
+                    (&quoted[..close], quoted.get(close + 1..).unwrap_or(""))
+                }
+                _ => {
+                    let end = after_equals
+                        .find(char::is_whitespace)
+                        .unwrap_or(after_equals.len());
+                    after_equals.split_at(end)
+                }
+            };
+            value = raw;
+            rest = remaining.trim_start();
+        }
+
+        if key.eq_ignore_ascii_case(name) {
+            return Some(decode_entities(value));
+        }
+    }
+    None
+}
+
+/// `text` with the HTML character references browsers use in exports decoded.
+fn decode_entities(text: &str) -> String {
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        decoded.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let entity = rest[1..].find(';').map(|end| &rest[1..=end]);
+        let character = entity.and_then(|entity| match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            "nbsp" => Some('\u{a0}'),
+            _ => entity
+                .strip_prefix("#x")
+                .or_else(|| entity.strip_prefix("#X"))
+                .map_or_else(
+                    || entity.strip_prefix('#')?.parse().ok(),
+                    |hex| u32::from_str_radix(hex, 16).ok(),
+                )
+                .and_then(char::from_u32),
+        });
+        match (entity, character) {
+            (Some(entity), Some(character)) => {
+                decoded.push(character);
+                rest = &rest[entity.len() + 2..];
+            }
+            _ => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    decoded.push_str(rest);
+    decoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{Category, Config};
+
+    const EXPORT: &str = r#"<!DOCTYPE NETSCAPE-Bookmark-file-1>
+<META HTTP-EQUIV="Content-Type" CONTENT="text/html; charset=UTF-8">
+<TITLE>Bookmarks</TITLE>
+<H1>Bookmarks</H1>
+<DL><p>
+    <DT><H3 ADD_DATE="1700000000" PERSONAL_TOOLBAR_FOLDER="true">Bookmarks bar</H3>
+    <DL><p>
+        <DT><A HREF="https://grafana.example.com/" ADD_DATE="1700000000">Grafana</A>
+        <DT><H3>Media</H3>
+        <DL><p>
+            <DT><A HREF="https://plex.example.com" TAGS="tv, home">Plex</A>
+            <DT><A HREF="javascript:alert(1)">Bookmarklet</A>
+        </DL><p>
+        <DT><A HREF="https://docs.example.com">Docs</A>
+    </DL><p>
+    <DT><A href='https://other.example.com/docs'>Docs</A>
+    <DT><A HREF="https://wiki.example.com"></A>
+</DL><p>
+"#;
+
+    #[test]
+    fn folders_become_the_category_of_their_links() {
+        let bookmarks = parse(EXPORT);
+        let folders: Vec<(&str, Option<&str>)> = bookmarks
+            .iter()
+            .map(|bookmark| (bookmark.title.as_str(), bookmark.folder.as_deref()))
+            .collect();
+        assert_eq!(
+            folders,
+            [
+                ("Grafana", None),
+                ("Plex", Some("Media")),
+                ("Bookmarklet", Some("Media")),
+                ("Docs", None),
+                ("Docs", None),
+                ("", None),
+            ]
+        );
+        assert_eq!(bookmarks[1].tags, ["tv", "home"]);
+        assert_eq!(bookmarks[4].url, "https://other.example.com/docs");
+    }
+
+    #[test]
+    fn import_skips_known_urls_and_numbers_taken_names() {
+        let mut config = Config {
+            categories: vec![Category {
+                name: "Monitoring".to_string(),
+                order: None,
+                color: None,
+                icon: None,
+            }],
+            sites: vec![Site {
+                name: "Grafana".to_string(),
+                url: "https://GRAFANA.example.com".to_string(),
+                category: "Monitoring".to_string(),
+                tags: Vec::new(),
+                monitor_interval_secs: None,
+                monitor_timeout_secs: None,
+                monitor_retries: None,
+                monitor_user_agent: None,
+                disabled: false,
+                icon: None,
+                order: None,
+                assertions: None,
+                uptime_percentage: 0.0,
+            }],
+            ..Config::default()
+        };
+
+        let summary = crate::import::add_sites(&mut config, convert(&parse(EXPORT)).sites);
+
+        assert_eq!(
+            summary.added,
+            ["Plex", "Docs", "Docs (2)", "wiki.example.com"]
+        );
+        assert_eq!(
+            summary.skipped,
+            ["https://grafana.example.com/", "javascript:alert(1)"]
+        );
+        assert_eq!(summary.categories, ["Media"]);
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn entities_are_decoded() {
+        assert_eq!(
+            decode_entities("Tom &amp; Jerry &#39;s &#x263A; &unknown; & more"),
+            "Tom & Jerry 's \u{263a} &unknown; & more"
+        );
+    }
+}
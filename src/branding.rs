@@ -0,0 +1,283 @@
+//! Custom stylesheet and logo
+//!
+//! Instances can be branded without rebuilding the frontend bundle. The config's
+//! `custom_css` names a stylesheet linked after the bundled one, so it can override any
+//! rule or theme variable, and `logo` names an image shown above the sites:
+//!
+//! ```json5
+//! {
+//!     custom_css: "branding/theme.css",
+//!     logo: "branding/logo.svg",
+//! }
+//! ```
+//!
+//! Both are paths to local files, resolved against the directory holding the config
+//! file like `include` patterns. They are served at [`CUSTOM_CSS_PATH`] and
+//! [`LOGO_PATH`], which only ever read the configured file, with a content type fixed by
+//! the file extension and `nosniff`, so the routes cannot be used to read other files or
+//! to run scripts. Files are read on every request, so edits show up on the next reload
+//! of the page; responses carry an `ETag` so unchanged files are not downloaded again.
+//!
+//! The web app manifest at [`MANIFEST_PATH`] is generated from `site_name`, `theme`, and
+//! `logo`, so the dashboard can be installed to a phone's home screen under its own name
+//! and icon.
+//!
+//! Layout changes that only need different styling belong in `custom_css`; the markup of
+//! the dashboard page can be replaced through `templates_dir` (see the `custom_templates`
+//! module).
+
+use crate::config::{Config, Theme};
+use crate::uptime::UptimeState;
+use axum::{
+    extract::State,
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+};
+use std::path::Path;
+use std::sync::Arc;
+use tracing::warn;
+
+/// URL the configured `custom_css` stylesheet is served at
+pub const CUSTOM_CSS_PATH: &str = "/branding/custom.css";
+/// URL the configured `logo` image is served at
+pub const LOGO_PATH: &str = "/branding/logo";
+/// URL the web app manifest is served at
+pub const MANIFEST_PATH: &str = "/manifest.webmanifest";
+/// URL of the bundled icon, used when no `logo` is configured
+pub const DEFAULT_ICON_PATH: &str = "/static/favicon.svg";
+
+/// Policy sent with SVG logos, which may contain scripts when opened directly
+const SVG_CONTENT_SECURITY_POLICY: &str = "default-src 'none'; style-src 'unsafe-inline'; sandbox";
+
+/// Content type of a stylesheet or logo, chosen by extension.
+fn content_type(path: &str) -> Option<&'static str> {
+    let extension = Path::new(path).extension()?.to_str()?.to_ascii_lowercase();
+    Some(match extension.as_str() {
+        "css" => "text/css; charset=utf-8",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "svg" => "image/svg+xml",
+        "ico" => "image/x-icon",
+        _ => return None,
+    })
+}
+
+/// Check the `custom_css` setting: a local path to a `.css` file.
+///
+/// # Errors
+///
+/// Returns the reason the value is rejected.
+pub(crate) fn check_custom_css(path: &str) -> Result<(), String> {
+    check_local_path(path)?;
+    match content_type(path) {
+        Some(kind) if kind.starts_with("text/css") => Ok(()),
+        _ => Err(format!("\"{path}\" must be a .css file")),
+    }
+}
+
+/// Check the `logo` setting: a local path to a PNG, JPEG, GIF, WebP, SVG, or ICO image.
+///
+/// # Errors
+///
+/// Returns the reason the value is rejected.
+pub(crate) fn check_logo(path: &str) -> Result<(), String> {
+    check_local_path(path)?;
+    match content_type(path) {
+        Some(kind) if kind.starts_with("image/") => Ok(()),
+        _ => Err(format!(
+            "\"{path}\" must be a .png, .jpg, .gif, .webp, .svg, or .ico image"
+        )),
+    }
+}
+
+pub(crate) fn check_local_path(path: &str) -> Result<(), String> {
+    if path.trim().is_empty() {
+        return Err("cannot be empty".to_string());
+    }
+    if path.contains("://") {
+        return Err(format!("\"{path}\" must be a file path, not a URL"));
+    }
+    Ok(())
+}
+
+/// Background color of pages with the `light` theme, matching the bundled stylesheet
+const LIGHT_BACKGROUND: &str = "#f5f5f5";
+/// Background color of pages with the `dark` and `auto` themes
+const DARK_BACKGROUND: &str = "#1a1a1a";
+
+/// Color the browser paints around the page, such as the status bar of an installed app.
+#[must_use]
+pub fn theme_color(config: &Config) -> &'static str {
+    match config.theme {
+        Theme::Light => LIGHT_BACKGROUND,
+        Theme::Dark | Theme::Auto => DARK_BACKGROUND,
+    }
+}
+
+/// URL of the icon the dashboard is installed and shared with: the configured `logo`, or
+/// the bundled icon.
+#[must_use]
+pub fn icon_path(config: &Config) -> &'static str {
+    if config.logo.is_some() {
+        LOGO_PATH
+    } else {
+        DEFAULT_ICON_PATH
+    }
+}
+
+/// The web app manifest of the dashboard
+///
+/// Names the app after `site_name`, opens it at `/` without browser controls, and lists
+/// the configured `logo` (or the bundled icon) as its icon.
+///
+/// # Examples
+///
+/// ```
+/// use iron_shield::branding::web_app_manifest;
+/// use iron_shield::config::Config;
+///
+/// let config = Config {
+///     site_name: "Homelab".to_string(),
+///     logo: Some("branding/logo.png".to_string()),
+///     ..Config::default()
+/// };
+/// let manifest = web_app_manifest(&config);
+/// assert_eq!(manifest["name"], "Homelab");
+/// assert_eq!(manifest["display"], "standalone");
+/// assert_eq!(manifest["icons"][0]["src"], "/branding/logo");
+/// assert_eq!(manifest["icons"][0]["type"], "image/png");
+/// ```
+#[must_use]
+pub fn web_app_manifest(config: &Config) -> serde_json::Value {
+    let icon_type = config
+        .logo
+        .as_deref()
+        .map_or(Some("image/svg+xml"), content_type);
+    let mut icon = serde_json::json!({
+        "src": icon_path(config),
+        "sizes": "any",
+        "purpose": "any",
+    });
+    if let Some(icon_type) = icon_type {
+        icon["type"] = icon_type.into();
+    }
+    serde_json::json!({
+        "name": config.site_name,
+        "short_name": config.site_name,
+        "start_url": "/",
+        "scope": "/",
+        "display": "standalone",
+        "background_color": theme_color(config),
+        "theme_color": theme_color(config),
+        "icons": [icon],
+    })
+}
+
+/// Serve the web app manifest
+///
+/// Handles `GET /manifest.webmanifest` with the [`web_app_manifest`] of the current
+/// configuration.
+pub async fn manifest(State(state): State<Arc<UptimeState>>) -> Response {
+    let config = state.config.load();
+    let body = web_app_manifest(&config).to_string();
+    (
+        [(
+            header::CONTENT_TYPE,
+            HeaderValue::from_static("application/manifest+json"),
+        )],
+        body,
+    )
+        .into_response()
+}
+
+/// Serve the configured `custom_css` stylesheet
+///
+/// Handles `GET /branding/custom.css`.
+///
+/// # Errors
+///
+/// Responds with HTTP 404 if no stylesheet is configured or the file cannot be read.
+pub async fn custom_css(
+    State(state): State<Arc<UptimeState>>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    serve(&state, &headers, |config| config.custom_css.clone()).await
+}
+
+/// Serve the configured `logo` image
+///
+/// Handles `GET /branding/logo`.
+///
+/// # Errors
+///
+/// Responds with HTTP 404 if no logo is configured or the file cannot be read.
+pub async fn logo(State(state): State<Arc<UptimeState>>, headers: HeaderMap) -> impl IntoResponse {
+    serve(&state, &headers, |config| config.logo.clone()).await
+}
+
+/// Respond with the file that `setting` names, resolved against the config directory.
+async fn serve(
+    state: &UptimeState,
+    request_headers: &HeaderMap,
+    setting: impl FnOnce(&Config) -> Option<String>,
+) -> Response {
+    let Some(configured) = setting(&state.config.load()) else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+    let Some(kind) = content_type(&configured) else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+    let path = crate::config::include_base_dir(&state.config_file_path).join(&configured);
+    let contents = match tokio::fs::read(&path).await {
+        Ok(contents) => contents,
+        Err(err) => {
+            warn!("Unable to read branding file {}: {err}", path.display());
+            return StatusCode::NOT_FOUND.into_response();
+        }
+    };
+
+    let etag = format!("\"{:016x}\"", crate::assets::fingerprint(&contents));
+    let mut response = if crate::assets::if_none_match_matches(request_headers, &etag) {
+        StatusCode::NOT_MODIFIED.into_response()
+    } else {
+        (
+            [(header::CONTENT_TYPE, HeaderValue::from_static(kind))],
+            contents,
+        )
+            .into_response()
+    };
+    let headers = response.headers_mut();
+    crate::assets::insert_cache_headers(
+        headers,
+        Some(&etag),
+        crate::assets::REVALIDATE_CACHE_CONTROL,
+    );
+    headers.insert(
+        header::X_CONTENT_TYPE_OPTIONS,
+        HeaderValue::from_static("nosniff"),
+    );
+    if kind == "image/svg+xml" {
+        headers.insert(
+            header::CONTENT_SECURITY_POLICY,
+            HeaderValue::from_static(SVG_CONTENT_SECURITY_POLICY),
+        );
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn settings_must_name_local_files_of_the_right_kind() {
+        assert_eq!(check_custom_css("branding/theme.CSS"), Ok(()));
+        assert!(check_custom_css("theme.scss").is_err());
+        assert!(check_custom_css("https://cdn.example.com/theme.css").is_err());
+        assert_eq!(check_logo("logo.svg"), Ok(()));
+        assert!(check_logo("logo.html").is_err());
+        assert!(check_logo(" ").is_err());
+    }
+}
@@ -0,0 +1,531 @@
+//! Pluggable uptime check types
+//!
+//! Every uptime check goes through a [`Checker`], picked by the site's check type: the
+//! scheme of its URL. Built in are `http` and `https`, checked by [`HttpChecker`], which
+//! sends a `HEAD` request (or a `GET` when response assertions need the body).
+//!
+//! Other protocols, such as TCP, DNS, or a script, can be added as their own modules
+//! implementing [`Checker`] and registered under a scheme with [`register`]. A program
+//! embedding iron-shield can register its own checkers the same way before starting the
+//! server:
+//!
+//! ```
+//! use iron_shield::checkers::{register, CheckResult, Checker};
+//! use iron_shield::config::CheckSettings;
+//! use futures_util::future::BoxFuture;
+//!
+//! struct Unimplemented;
+//!
+//! impl Checker for Unimplemented {
+//!     fn check<'a>(&'a self, _url: &'a str, _settings: &'a CheckSettings) -> BoxFuture<'a, CheckResult> {
+//!         Box::pin(async { CheckResult::down("not implemented yet") })
+//!     }
+//! }
+//!
+//! register("dns", Unimplemented);
+//! assert!(iron_shield::checkers::is_registered("dns"));
+//! ```
+//!
+//! Site URLs are only accepted in the config when their scheme is `http`, `https`, or
+//! has a registered checker, so checkers must be registered before the config is loaded.
+
+use crate::assertions::{Assertions, CheckedResponse, MAX_BODY_BYTES};
+use crate::config::CheckSettings;
+use crate::otlp::SpanEvent;
+use crate::uptime::UptimeStatus;
+use futures_util::future::BoxFuture;
+use parking_lot::RwLock;
+use std::collections::HashMap;
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
+use tracing::debug;
+
+/// Result of a single uptime probe with the measured response time
+#[derive(Debug, Clone)]
+pub struct CheckResult {
+    /// `Up` or `Down`
+    pub status: UptimeStatus,
+    /// How long the last attempt took, in ms
+    pub response_time_ms: Option<u64>,
+    /// Why the last attempt failed
+    pub failure_reason: Option<String>,
+    /// Timings of the attempts' phases, for the check's trace span
+    pub events: Vec<SpanEvent>,
+    /// Seconds the `Date` header of the last response was ahead of the local clock
+    pub clock_offset_secs: Option<i64>,
+}
+
+impl CheckResult {
+    /// A check that failed for `reason`
+    #[must_use]
+    pub fn down(reason: impl Into<String>) -> Self {
+        Self {
+            status: UptimeStatus::Down,
+            response_time_ms: None,
+            failure_reason: Some(reason.into()),
+            events: Vec::new(),
+            clock_offset_secs: None,
+        }
+    }
+}
+
+/// Checks whether a site is up
+///
+/// Implementations get the site's URL and its check settings with the global
+/// `monitoring` defaults applied, and should honor `timeout_secs` and `retries` the way
+/// [`HttpChecker`] does.
+pub trait Checker: Send + Sync {
+    /// Check the site at `url` once, including any retries.
+    fn check<'a>(&'a self, url: &'a str, settings: &'a CheckSettings)
+        -> BoxFuture<'a, CheckResult>;
+}
+
+type Registry = RwLock<HashMap<String, Arc<dyn Checker>>>;
+
+/// The checkers by scheme, starting with the built-in ones
+fn registry() -> &'static Registry {
+    static REGISTRY: OnceLock<Registry> = OnceLock::new();
+    REGISTRY.get_or_init(|| {
+        let http: Arc<dyn Checker> = Arc::new(HttpChecker::new(reqwest::Client::new()));
+        RwLock::new(HashMap::from([
+            ("http".to_string(), Arc::clone(&http)),
+            ("https".to_string(), http),
+        ]))
+    })
+}
+
+/// Check sites whose URLs have the scheme `check_type` with `checker`, replacing any
+/// checker registered for it before.
+#[allow(dead_code)] // Only called by programs embedding the library
+pub fn register(check_type: &str, checker: impl Checker + 'static) {
+    registry()
+        .write()
+        .insert(check_type.to_ascii_lowercase(), Arc::new(checker));
+}
+
+/// Whether a checker is registered for URLs with the scheme `check_type`
+#[must_use]
+pub fn is_registered(check_type: &str) -> bool {
+    registry()
+        .read()
+        .contains_key(&check_type.to_ascii_lowercase())
+}
+
+/// Check the site at `url` with the checker registered for its scheme.
+///
+/// A URL that cannot be parsed or has no checker counts as down.
+pub async fn check(url: &str, settings: &CheckSettings) -> CheckResult {
+    let scheme = match url::Url::parse(url) {
+        Ok(parsed) => parsed.scheme().to_string(),
+        Err(err) => return CheckResult::down(format!("invalid URL: {err}")),
+    };
+    let checker = registry().read().get(&scheme).cloned();
+    match checker {
+        Some(checker) => checker.check(url, settings).await,
+        None => CheckResult::down(format!("no checker for {scheme}: URLs")),
+    }
+}
+
+/// Checks `http` and `https` sites with a request, retried up to `retries` times, that
+/// must succeed and pass the site's response assertions
+#[derive(Debug, Clone)]
+pub struct HttpChecker {
+    client: reqwest::Client,
+}
+
+impl HttpChecker {
+    /// A checker sending its requests with `client`
+    #[must_use]
+    pub fn new(client: reqwest::Client) -> Self {
+        Self { client }
+    }
+}
+
+impl Checker for HttpChecker {
+    fn check<'a>(
+        &'a self,
+        url: &'a str,
+        settings: &'a CheckSettings,
+    ) -> BoxFuture<'a, CheckResult> {
+        Box::pin(check_site_status(&self.client, url, settings))
+    }
+}
+
+/// Helper function to check the status of a website
+///
+/// This function requests the specified URL and determines the uptime status based on
+/// the response. Without response assertions the site is up when it answers a HEAD
+/// request with a success status; with them (see [`crate::assertions`]) the response must
+/// pass every assertion, and a GET request is used when they need the body.
+///
+/// # Arguments
+///
+/// * `client` - A reqwest HTTP client to use for the request
+/// * `url` - The URL of the site to check
+/// * `settings` - The site's timeout, retry count, user agent, and assertions
+///
+/// # Returns
+///
+/// A `CheckResult` containing the status, the response time, and the failure reason
+/// of the last attempt
+///
+/// # Note
+///
+/// Each attempt may take up to `settings.timeout_secs`. A failed attempt is retried up to
+/// `settings.retries` times before `UptimeStatus::Down` is returned for the request
+/// failure, including timeouts, connection errors, or failed assertions.
+async fn check_site_status(
+    client: &reqwest::Client,
+    url: &str,
+    settings: &CheckSettings,
+) -> CheckResult {
+    let assertions = settings.assertions.as_ref();
+    let method = if assertions.is_some_and(Assertions::needs_body) {
+        reqwest::Method::GET
+    } else {
+        reqwest::Method::HEAD
+    };
+
+    let mut attempt = 0;
+    let mut events = Vec::new();
+    let mut clock_offset_secs = None;
+    loop {
+        debug!("Checking site status: {url} (attempt {})", attempt + 1);
+        let start = Instant::now();
+        events.push(SpanEvent {
+            name: "attempt.start",
+            time_unix_nano: crate::uptime::unix_now_nanos(),
+            attributes: vec![
+                ("attempt", (attempt + 1).to_string()),
+                ("http.request.method", method.to_string()),
+            ],
+        });
+        let outcome = match client
+            .request(method.clone(), url)
+            .timeout(Duration::from_secs(settings.timeout_secs))
+            .header(reqwest::header::USER_AGENT, &settings.user_agent)
+            .send()
+            .await
+        {
+            Ok(response) => {
+                events.push(SpanEvent {
+                    name: "response.headers",
+                    time_unix_nano: crate::uptime::unix_now_nanos(),
+                    attributes: vec![(
+                        "http.response.status_code",
+                        response.status().as_u16().to_string(),
+                    )],
+                });
+                let now = i64::try_from(crate::uptime::unix_now()).unwrap_or(i64::MAX);
+                clock_offset_secs = response
+                    .headers()
+                    .get(reqwest::header::DATE)
+                    .and_then(|date| date.to_str().ok())
+                    .and_then(|date| crate::clock_drift::date_offset(date, now))
+                    .or(clock_offset_secs);
+                evaluate_response(response, assertions, start, &mut events).await
+            }
+            Err(e) => Err(format!("request failed: {e}")),
+        };
+
+        let response_time_ms = elapsed_ms(start);
+        let (status, failure_reason) = match outcome {
+            Ok(()) => {
+                debug!("Site {url} is UP");
+                (UptimeStatus::Up, None)
+            }
+            Err(reason) => {
+                debug!("Site {url} is DOWN: {reason}");
+                (UptimeStatus::Down, Some(reason))
+            }
+        };
+
+        if status == UptimeStatus::Up || attempt >= settings.retries {
+            return CheckResult {
+                status,
+                response_time_ms: Some(response_time_ms),
+                failure_reason,
+                events,
+                clock_offset_secs,
+            };
+        }
+        events.push(SpanEvent {
+            name: "attempt.failed",
+            time_unix_nano: crate::uptime::unix_now_nanos(),
+            attributes: vec![("reason", failure_reason.unwrap_or_default())],
+        });
+        attempt += 1;
+    }
+}
+
+/// Decide whether a check's response counts as up, returning why it does not.
+///
+/// The body is only read when an assertion needs it, and then at most
+/// [`MAX_BODY_BYTES`] of it; reading it is recorded in `events`.
+async fn evaluate_response(
+    mut response: reqwest::Response,
+    assertions: Option<&Assertions>,
+    start: Instant,
+    events: &mut Vec<SpanEvent>,
+) -> Result<(), String> {
+    let status = response.status().as_u16();
+    let Some(assertions) = assertions else {
+        return Assertions::default().evaluate(&CheckedResponse {
+            status,
+            headers: response.headers(),
+            body: None,
+            latency_ms: elapsed_ms(start),
+        });
+    };
+
+    let headers = response.headers().clone();
+    let body = if assertions.needs_body() {
+        let mut body = Vec::new();
+        while let Some(chunk) = response
+            .chunk()
+            .await
+            .map_err(|e| format!("failed to read body: {e}"))?
+        {
+            let remaining = MAX_BODY_BYTES - body.len();
+            body.extend_from_slice(&chunk[..chunk.len().min(remaining)]);
+            if body.len() == MAX_BODY_BYTES {
+                break;
+            }
+        }
+        events.push(SpanEvent {
+            name: "response.body",
+            time_unix_nano: crate::uptime::unix_now_nanos(),
+            attributes: vec![("bytes", body.len().to_string())],
+        });
+        Some(String::from_utf8_lossy(&body).into_owned())
+    } else {
+        None
+    };
+
+    assertions.evaluate(&CheckedResponse {
+        status,
+        headers: &headers,
+        body: body.as_deref(),
+        latency_ms: elapsed_ms(start),
+    })
+}
+
+/// Milliseconds since `start`, saturating at `u64::MAX`.
+fn elapsed_ms(start: Instant) -> u64 {
+    start.elapsed().as_millis().try_into().unwrap_or(u64::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::RwLock;
+
+    fn test_settings() -> CheckSettings {
+        crate::config::Site {
+            name: String::new(),
+            url: String::new(),
+            category: String::new(),
+            tags: vec![],
+            monitor_interval_secs: None,
+            monitor_timeout_secs: None,
+            monitor_retries: None,
+            monitor_user_agent: None,
+            disabled: false,
+            icon: None,
+            order: None,
+            assertions: None,
+            uptime_percentage: 0.0,
+        }
+        .check_settings(&crate::config::Monitoring::default())
+    }
+
+    #[tokio::test]
+    async fn check_site_status_retries_failures_and_sends_user_agent() {
+        use axum::http::{HeaderMap as RequestHeaders, StatusCode};
+
+        let attempts = Arc::new(AtomicUsize::new(0));
+        let user_agents = Arc::new(RwLock::new(Vec::new()));
+        let app = axum::Router::new().route(
+            "/",
+            axum::routing::get({
+                let attempts = Arc::clone(&attempts);
+                let user_agents = Arc::clone(&user_agents);
+                move |headers: RequestHeaders| async move {
+                    if let Some(agent) = headers.get(axum::http::header::USER_AGENT) {
+                        user_agents
+                            .write()
+                            .unwrap()
+                            .push(agent.to_str().unwrap().to_string());
+                    }
+                    // Every other request fails, starting with the first
+                    if attempts.fetch_add(1, Ordering::SeqCst).is_multiple_of(2) {
+                        StatusCode::SERVICE_UNAVAILABLE
+                    } else {
+                        StatusCode::OK
+                    }
+                }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        let client = reqwest::Client::new();
+
+        let mut settings = test_settings();
+        settings.user_agent = "homelab-probe/1.0".to_string();
+        let result = check_site_status(&client, &url, &settings).await;
+        assert_eq!(result.status, UptimeStatus::Down);
+        assert_eq!(attempts.load(Ordering::SeqCst), 1);
+
+        settings.retries = 1;
+        attempts.store(0, Ordering::SeqCst);
+        let result = check_site_status(&client, &url, &settings).await;
+        assert_eq!(result.status, UptimeStatus::Up);
+        assert_eq!(attempts.load(Ordering::SeqCst), 2);
+        assert!(user_agents
+            .read()
+            .unwrap()
+            .iter()
+            .all(|agent| agent == "homelab-probe/1.0"));
+    }
+
+    #[tokio::test]
+    async fn check_site_status_records_first_failing_assertion() {
+        use crate::assertions::{Assertions, JsonPathAssertion};
+
+        let app = axum::Router::new().route(
+            "/health",
+            axum::routing::get(|| async {
+                axum::Json(serde_json::json!({ "status": "degraded", "version": "1.2" }))
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/health", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        let client = reqwest::Client::new();
+
+        let mut settings = test_settings();
+        settings.assertions = Some(Assertions {
+            status: vec![200],
+            body_contains: Some("version".to_string()),
+            json_path: vec![JsonPathAssertion {
+                path: "$.status".to_string(),
+                equals: Some(serde_json::json!("ok")),
+            }],
+            ..Assertions::default()
+        });
+        let result = check_site_status(&client, &url, &settings).await;
+        assert_eq!(result.status, UptimeStatus::Down);
+        assert_eq!(
+            result.failure_reason.as_deref(),
+            Some("$.status is \"degraded\", expected \"ok\"")
+        );
+
+        settings.assertions = Some(Assertions {
+            json_path: vec![JsonPathAssertion {
+                path: "$.version".to_string(),
+                equals: Some(serde_json::json!("1.2")),
+            }],
+            ..Assertions::default()
+        });
+        let result = check_site_status(&client, &url, &settings).await;
+        assert_eq!(result.status, UptimeStatus::Up);
+        assert_eq!(result.failure_reason, None);
+
+        let missing = format!("{url}/missing");
+        let result = check_site_status(&client, &missing, &test_settings()).await;
+        assert_eq!(
+            result.failure_reason.as_deref(),
+            Some("status 404 is not successful")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_check_site_status_up() {
+        // This test requires a real server to test against
+        // For now, we can only test error conditions
+        let client = reqwest::Client::new();
+
+        // Test with a URL that should result in an error (nonexistent domain)
+        let result = check_site_status(
+            &client,
+            "http://definitely-not-a-real-domain-12345.com",
+            &test_settings(),
+        )
+        .await;
+        assert_eq!(result.status, UptimeStatus::Down);
+        assert!(result.response_time_ms.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_check_site_status_with_valid_url() {
+        // Test with a URL that should return success (HTTPbin is commonly used for testing)
+        let client = reqwest::Client::new();
+        let result =
+            check_site_status(&client, "https://httpbin.org/status/200", &test_settings()).await;
+        // Note: This might fail if no internet connection, but it's a good test when available
+        // For now, we'll just check that it doesn't panic
+        assert!(matches!(
+            result.status,
+            UptimeStatus::Up | UptimeStatus::Down
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_check_site_status_down_non_2xx() {
+        // Test with a URL that returns a 500 Internal Server Error
+        let client = reqwest::Client::new();
+        let result =
+            check_site_status(&client, "https://httpbin.org/status/500", &test_settings()).await;
+        assert_eq!(result.status, UptimeStatus::Down);
+    }
+
+    #[tokio::test]
+    async fn test_check_site_status_with_timeout() {
+        // Test with a URL that should timeout
+        let client = reqwest::Client::builder()
+            .timeout(std::time::Duration::from_millis(1)) // Very short timeout
+            .build()
+            .unwrap();
+
+        // Use a URL that will likely timeout with 1ms timeout
+        let result =
+            check_site_status(&client, "https://httpbin.org/delay/10", &test_settings()).await;
+        assert_eq!(result.status, UptimeStatus::Down);
+    }
+
+    #[tokio::test]
+    async fn registered_checkers_handle_their_scheme() {
+        struct Counting(Arc<AtomicUsize>);
+
+        impl Checker for Counting {
+            fn check<'a>(
+                &'a self,
+                url: &'a str,
+                _settings: &'a CheckSettings,
+            ) -> BoxFuture<'a, CheckResult> {
+                self.0.fetch_add(1, Ordering::SeqCst);
+                Box::pin(async move { CheckResult::down(format!("{url} is closed")) })
+            }
+        }
+
+        let calls = Arc::new(AtomicUsize::new(0));
+        assert!(!is_registered("tcp-test"));
+        let result = check("tcp-test://db:5432", &test_settings()).await;
+        assert_eq!(
+            result.failure_reason.as_deref(),
+            Some("no checker for tcp-test: URLs")
+        );
+
+        register("TCP-Test", Counting(Arc::clone(&calls)));
+        assert!(is_registered("tcp-test"));
+        let result = check("tcp-test://db:5432", &test_settings()).await;
+        assert_eq!(result.status, UptimeStatus::Down);
+        assert_eq!(
+            result.failure_reason.as_deref(),
+            Some("tcp-test://db:5432 is closed")
+        );
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+}
@@ -0,0 +1,156 @@
+//! Detection of a skewed system clock
+//!
+//! Every recorded check time, uptime window, and incident duration comes from the host
+//! clock, so a clock that is far off corrupts them without any error. Instead of asking
+//! a time server, the uptime checks compare the `Date` header of each response with the
+//! local time it arrived at. Once the median of the recent offsets is more than
+//! `clock_drift_warn_secs` off, a warning is logged and shown on the dashboard until the
+//! clock is back in line; `0` turns the comparison off.
+//!
+//! `Date` headers have a resolution of one second and some servers keep poor time
+//! themselves, so the median of several responses is compared rather than any single
+//! one, and the default threshold is a minute.
+
+use crate::utils::format_duration;
+use parking_lot::Mutex;
+use std::collections::VecDeque;
+use tracing::{info, warn};
+
+/// Number of recent `Date` offsets the median is taken over
+pub const DRIFT_SAMPLES: usize = 15;
+
+/// Number of offsets needed before the clock is judged at all
+pub const MIN_DRIFT_SAMPLES: usize = 3;
+
+/// How far the system clock is off, judged from the responses of monitored sites
+#[derive(Debug, Default)]
+pub struct ClockDrift {
+    state: Mutex<DriftState>,
+}
+
+#[derive(Debug, Default)]
+struct DriftState {
+    /// Seconds the `Date` of recent responses was ahead of the local clock, oldest first
+    offsets: VecDeque<i64>,
+    /// The median offset while it is beyond the threshold
+    skew: Option<i64>,
+}
+
+impl ClockDrift {
+    /// Record that a response's `Date` was `offset_secs` ahead of the local clock, and
+    /// log when the clock becomes skewed by more than `warn_secs` or recovers.
+    ///
+    /// A `warn_secs` of `0` turns detection off and forgets the recorded offsets.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use iron_shield::clock_drift::ClockDrift;
+    ///
+    /// let drift = ClockDrift::default();
+    /// for offset in [300, 301, 2, 299] {
+    ///     drift.record(offset, 60);
+    /// }
+    /// assert_eq!(drift.skew(), Some(300));
+    ///
+    /// drift.record(300, 0);
+    /// assert_eq!(drift.skew(), None);
+    /// ```
+    pub fn record(&self, offset_secs: i64, warn_secs: u64) {
+        let mut state = self.state.lock();
+        if warn_secs == 0 {
+            *state = DriftState::default();
+            return;
+        }
+
+        state.offsets.push_back(offset_secs);
+        while state.offsets.len() > DRIFT_SAMPLES {
+            state.offsets.pop_front();
+        }
+        let skew = median(&state.offsets).filter(|median| median.unsigned_abs() > warn_secs);
+
+        match (state.skew, skew) {
+            (None, Some(skew)) => warn!(
+                skew_secs = skew,
+                "System clock is {} according to the Date headers of monitored sites; \
+                 recorded check times and uptime windows will be off",
+                describe(skew)
+            ),
+            (Some(_), None) => info!("System clock agrees with monitored sites again"),
+            _ => {}
+        }
+        state.skew = skew;
+    }
+
+    /// Seconds the system clock is behind (positive) or ahead (negative) of monitored
+    /// sites, while that is beyond the threshold
+    pub fn skew(&self) -> Option<i64> {
+        self.state.lock().skew
+    }
+}
+
+/// Seconds the HTTP `Date` header value `date` is ahead of `now`, the Unix time the
+/// response arrived at, or `None` if it is not a valid date
+///
+/// # Examples
+///
+/// ```
+/// use iron_shield::clock_drift::date_offset;
+///
+/// assert_eq!(date_offset("Sun, 06 Nov 1994 08:49:37 GMT", 784_111_717), Some(60));
+/// assert_eq!(date_offset("yesterday", 784_111_717), None);
+/// ```
+#[must_use]
+pub fn date_offset(date: &str, now: i64) -> Option<i64> {
+    let date = chrono::DateTime::parse_from_rfc2822(date.trim()).ok()?;
+    Some(date.timestamp() - now)
+}
+
+/// "5m behind" or "5m ahead" for a clock `skew` seconds behind monitored sites
+fn describe(skew: i64) -> String {
+    let direction = if skew > 0 { "behind" } else { "ahead" };
+    format!("{} {direction}", format_duration(skew.unsigned_abs()))
+}
+
+/// The middle of `offsets` once there are enough of them
+fn median(offsets: &VecDeque<i64>) -> Option<i64> {
+    if offsets.len() < MIN_DRIFT_SAMPLES {
+        return None;
+    }
+    let mut sorted: Vec<i64> = offsets.iter().copied().collect();
+    sorted.sort_unstable();
+    Some(sorted[sorted.len() / 2])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn single_skewed_servers_do_not_trigger_a_warning() {
+        let drift = ClockDrift::default();
+        drift.record(1, 60);
+        drift.record(3600, 60);
+        assert_eq!(drift.skew(), None, "too few samples to judge");
+
+        drift.record(2, 60);
+        assert_eq!(drift.skew(), None);
+        drift.record(-3600, 60);
+        assert_eq!(drift.skew(), None);
+    }
+
+    #[test]
+    fn skew_clears_once_recent_offsets_agree() {
+        let drift = ClockDrift::default();
+        for _ in 0..DRIFT_SAMPLES {
+            drift.record(-120, 60);
+        }
+        assert_eq!(drift.skew(), Some(-120));
+        assert_eq!(describe(-120), "2m ahead");
+
+        for _ in 0..=DRIFT_SAMPLES / 2 {
+            drift.record(0, 60);
+        }
+        assert_eq!(drift.skew(), None);
+    }
+}
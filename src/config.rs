@@ -1,10 +1,15 @@
+use crate::migration::{migrate, CURRENT_CONFIG_VERSION};
+use arc_swap::ArcSwap;
+use chrono_tz::Tz;
 use notify::{recommended_watcher, Event, EventKind, RecursiveMode, Watcher};
-use serde::{Deserialize, Serialize};
+use parking_lot::{Mutex, RwLock};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs;
-use std::path::PathBuf;
-use std::sync::{Arc, RwLock};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use tokio::sync::mpsc;
-use tracing::{debug, error, info};
+use tracing::{debug, error, info, warn};
 
 /// Default configuration file name
 ///
@@ -15,6 +20,47 @@ pub const CONFIG_FILE: &str = "config.json5";
 pub const DEFAULT_MONITOR_INTERVAL_SECS: u64 = 5;
 /// Minimum supported number of seconds between uptime checks.
 pub const MIN_MONITOR_INTERVAL_SECS: u64 = 5;
+/// Default number of seconds an uptime check may take before the site counts as down.
+pub const DEFAULT_CHECK_TIMEOUT_SECS: u64 = 10;
+/// Default number of uptime checks run at the same time.
+pub const DEFAULT_CHECK_CONCURRENCY: usize = 10;
+/// Default `User-Agent` header sent with uptime checks.
+pub const DEFAULT_USER_AGENT: &str = concat!("iron-shield/", env!("CARGO_PKG_VERSION"));
+
+/// Prefix of environment variables that override config file values.
+///
+/// Path segments are separated by a double underscore, so `IRON_SHIELD__SITE_NAME` sets
+/// `site_name` and `IRON_SHIELD__SITES__0__URL` sets the URL of the first site.
+pub const ENV_OVERRIDE_PREFIX: &str = "IRON_SHIELD__";
+
+/// Environment variable naming the config file when none is given on the command line.
+pub const CONFIG_PATH_ENV: &str = "IRON_SHIELD_CONFIG";
+
+/// Default number of seconds to wait for in-flight work during shutdown.
+pub const DEFAULT_SHUTDOWN_TIMEOUT_SECS: u64 = 10;
+/// Default number of seconds between SSE keep-alive comments.
+pub const DEFAULT_SSE_KEEP_ALIVE_SECS: u64 = 15;
+
+/// Default number of seconds the kiosk view shows each category before rotating.
+pub const DEFAULT_KIOSK_ROTATE_SECS: u64 = 20;
+
+/// Default number of seconds between scheduled telemetry exports of the uptime snapshot.
+pub const DEFAULT_OPENTELEMETRY_EXPORT_INTERVAL_SECS: u64 = 60;
+
+/// Commented starter configuration written by `iron_shield --init`.
+pub const STARTER_CONFIG: &str = include_str!("../config.example.json5");
+
+/// Default IANA time zone of the dashboard clock.
+pub const DEFAULT_TIMEZONE: &str = "UTC";
+
+/// Default number of config file backups kept by the settings API.
+pub const DEFAULT_CONFIG_BACKUP_RETENTION: usize = 10;
+
+/// Default number of seconds a fetched site favicon is cached (one week).
+pub const DEFAULT_ICON_CACHE_TTL_SECS: u64 = 7 * 24 * 60 * 60;
+
+/// Default number of seconds the system clock may be off before a warning is shown.
+pub const DEFAULT_CLOCK_DRIFT_WARN_SECS: u64 = 60;
 
 /// Returns the default interval used to monitor a site.
 #[must_use]
@@ -22,6 +68,54 @@ pub fn default_monitor_interval_secs() -> u64 {
     DEFAULT_MONITOR_INTERVAL_SECS
 }
 
+/// Returns the default time allowed for draining requests and checks on shutdown.
+#[must_use]
+pub fn default_shutdown_timeout_secs() -> u64 {
+    DEFAULT_SHUTDOWN_TIMEOUT_SECS
+}
+
+/// Returns the default interval between SSE keep-alive comments.
+#[must_use]
+pub fn default_sse_keep_alive_secs() -> u64 {
+    DEFAULT_SSE_KEEP_ALIVE_SECS
+}
+
+/// Returns the default time the kiosk view shows each category.
+#[must_use]
+pub fn default_kiosk_rotate_secs() -> u64 {
+    DEFAULT_KIOSK_ROTATE_SECS
+}
+
+/// Returns the default interval between scheduled telemetry exports.
+#[must_use]
+pub fn default_opentelemetry_export_interval_secs() -> u64 {
+    DEFAULT_OPENTELEMETRY_EXPORT_INTERVAL_SECS
+}
+
+/// Returns the default time zone of the dashboard clock.
+#[must_use]
+pub fn default_timezone() -> String {
+    DEFAULT_TIMEZONE.to_string()
+}
+
+/// Returns the default number of config file backups to keep.
+#[must_use]
+pub fn default_config_backup_retention() -> usize {
+    DEFAULT_CONFIG_BACKUP_RETENTION
+}
+
+/// Returns the default time a fetched site favicon is cached.
+#[must_use]
+pub fn default_icon_cache_ttl_secs() -> u64 {
+    DEFAULT_ICON_CACHE_TTL_SECS
+}
+
+/// Returns the default clock skew tolerated before a warning is shown.
+#[must_use]
+pub fn default_clock_drift_warn_secs() -> u64 {
+    DEFAULT_CLOCK_DRIFT_WARN_SECS
+}
+
 /// Application configuration structure
 ///
 /// Contains all configuration parameters for the Iron Shield dashboard.
@@ -30,10 +124,64 @@ pub fn default_monitor_interval_secs() -> u64 {
 ///
 /// # Fields
 ///
+/// * `version` - Config format version; older files are migrated on load (see `migration`)
 /// * `site_name` - The name of the site displayed in the page title
 /// * `clock` - The format in which to display the clock
-/// * `opentelemetry_endpoint` - Optional HTTP endpoint to send uptime telemetry to
+/// * `timezone` - IANA time zone name (such as `Europe/Berlin`) the clock is shown in
+/// * `clocks` - Additional labelled clocks shown in a row under the main one
+/// * `theme` - Color scheme of the dashboard: `auto`, `dark`, or `light`
+/// * `language` - Language of the dashboard UI; see the `i18n` module
+/// * `layout` - How sites are laid out: `grid`, `list`, or `compact`
+/// * `sort_by` - Order of the sites: `config`, `name`, `status`, `uptime`, or
+///   `response_time`
+/// * `custom_css` - Optional stylesheet linked after the bundled one; see the `branding`
+///   module
+/// * `logo` - Optional image shown above the sites; see the `branding` module
+/// * `templates_dir` - Optional directory of templates replacing the built-in pages; see
+///   the `custom_templates` module
+/// * `icon_cache_ttl_secs` - How long fetched site favicons are kept; see the `favicons`
+///   module
+/// * `weather` - Optional weather widget shown next to the clock; see the `weather` module
+/// * `widgets` - Extra tiles with an embedded page or HTML snippet; see the `widgets`
+///   module
+/// * `opentelemetry_endpoint` - Optional OTLP collector to export uptime metrics to;
+///   see the `telemetry` module
+/// * `opentelemetry_transport` - Whether metrics are exported over OTLP/HTTP or OTLP/gRPC
+/// * `opentelemetry_export_interval_secs` - How often the uptime snapshot is exported
+/// * `opentelemetry_traces` - Whether every uptime check is also exported as a trace span
+/// * `opentelemetry_sampling` - Which snapshots and sites are exported; see the
+///   `telemetry` module
+/// * `statsd` - Optional StatsD daemon to report uptime to; see the `statsd` module
+/// * `graphite` - Optional Carbon daemon to push uptime to; see the `graphite` module
+/// * `influxdb` - Optional InfluxDB server to write check results to; see the
+///   `influxdb` module
+/// * `notifiers` - Push services told when a site goes down or recovers; see the
+///   `notifications` module
+/// * `public_url` - Address the dashboard is reached at, linked from notifications
+/// * `port` - Port the server listens on, unless given on the command line
+/// * `bind` - IP address the server listens on, unless given on the command line
 /// * `sites` - A vector of bookmarked sites to display on the dashboard
+/// * `access_log` - Whether to emit a structured log event for every HTTP request
+/// * `shutdown_timeout_secs` - How long shutdown waits for in-flight requests and checks
+/// * `sse_keep_alive_secs` - Interval between keep-alive comments on idle SSE streams
+/// * `clock_drift_warn_secs` - How far the system clock may be off before a warning is
+///   shown; see the `clock_drift` module
+/// * `dashboards` - Additional named dashboards served at `/d/{name}`
+/// * `kiosk_rotate_secs` - How long the `/kiosk` view shows each category; see the
+///   `kiosk` module
+/// * `monitoring` - Default uptime check settings that sites inherit and can override
+/// * `groups` - Optional collapsible sections grouping sites on the dashboard
+/// * `categories` - Optional category definitions with display order, color, and icon
+/// * `config_backup_retention` - How many backups of this file the settings API keeps
+/// * `include` - Glob patterns of additional files whose `sites` are appended to `sites`
+/// * `vars` - Variables that other values refer to as `${NAME}`; see the `variables` module
+/// * `api_keys` - Hashed keys that scripts must present to call the API; see the
+///   `api_keys` module
+/// * `api_keys_protect_reads` - Whether reading the API also needs a key
+///
+/// Credential-like values (`opentelemetry_endpoint`, `influxdb.token`,
+/// `weather.api_key`, and the notifiers' `token`) may contain secret references such as
+/// `${env:API_TOKEN}` or `file:/run/secrets/token`; see the `secrets` module.
 ///
 /// # Examples
 ///
@@ -51,29 +199,236 @@ pub fn default_monitor_interval_secs() -> u64 {
 ///             url: "https://google.com".to_string(),
 ///             category: "Search".to_string(),
 ///             tags: vec!["important".to_string()],
-///             monitor_interval_secs: iron_shield::config::DEFAULT_MONITOR_INTERVAL_SECS,
+///             monitor_interval_secs: Some(iron_shield::config::DEFAULT_MONITOR_INTERVAL_SECS),
+///             monitor_timeout_secs: None,
+///             monitor_retries: None,
+///             monitor_user_agent: None,
 ///             disabled: false,
+///             icon: None,
+///             order: None,
+///             assertions: None,
 ///             uptime_percentage: 0.0, // Not required when initializing manually
 ///         }
 ///     ],
+///     ..Config::default()
 /// };
 ///
 /// assert_eq!(config.site_name, "My Dashboard");
 /// ```
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct Config {
+    /// Version of the config format, upgraded by the `migration` module on load
+    #[serde(default = "crate::migration::default_config_version")]
+    pub version: u32,
     /// Name of the site displayed in the page title
     #[serde(default = "default_site_name")]
     pub site_name: String,
     /// Clock format to use (24-hour, 12-hour, or no clock)
     #[serde(default)]
     pub clock: Clock,
-    /// Optional endpoint to forward uptime telemetry snapshots to
+    /// IANA time zone name the dashboard clock is shown in
+    #[serde(default = "default_timezone")]
+    pub timezone: String,
+    /// Labelled clocks in other time zones, shown in a row under the main clock
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub clocks: Vec<WorldClock>,
+    /// Color scheme of the dashboard, which visitors can override per browser
+    #[serde(default)]
+    pub theme: Theme,
+    /// Language the dashboard UI is shown in
+    #[serde(default)]
+    pub language: crate::i18n::Language,
+    /// How the sites are laid out on the dashboard
+    #[serde(default)]
+    pub layout: Layout,
+    /// Order of the sites within each group and category
+    #[serde(default)]
+    pub sort_by: SortBy,
+    /// Path, relative to this file, of a stylesheet linked after the bundled one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub custom_css: Option<String>,
+    /// Path, relative to this file, of an image shown above the sites
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub logo: Option<String>,
+    /// Path, relative to this file, of a directory of templates replacing the built-in
+    /// pages
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub templates_dir: Option<String>,
+    /// Seconds a site favicon fetched by the server is cached before it is fetched again
+    #[serde(default = "default_icon_cache_ttl_secs")]
+    pub icon_cache_ttl_secs: u64,
+    /// Provider and location of the weather shown next to the clock
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub weather: Option<crate::weather::Weather>,
+    /// Tiles with an embedded page or HTML snippet, shown after the sites
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub widgets: Vec<crate::widgets::Widget>,
+    /// Optional base URL of an OTLP collector, such as `http://collector:4318`, that
+    /// uptime metrics are exported to
     #[serde(default)]
     pub opentelemetry_endpoint: Option<String>,
+    /// Protocol `opentelemetry_endpoint` is spoken to with
+    #[serde(default)]
+    pub opentelemetry_transport: TelemetryTransport,
+    /// Seconds between exports of the uptime snapshot to `opentelemetry_endpoint`
+    /// (0 disables them)
+    #[serde(default = "default_opentelemetry_export_interval_secs")]
+    pub opentelemetry_export_interval_secs: u64,
+    /// Whether every uptime check is also exported to `opentelemetry_endpoint` as a span
+    #[serde(default)]
+    pub opentelemetry_traces: bool,
+    /// Which uptime snapshots and sites are exported to `opentelemetry_endpoint`
+    #[serde(
+        default,
+        skip_serializing_if = "crate::telemetry::TelemetrySampling::is_default"
+    )]
+    pub opentelemetry_sampling: crate::telemetry::TelemetrySampling,
+    /// StatsD daemon every uptime cycle is reported to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub statsd: Option<crate::statsd::Statsd>,
+    /// Carbon daemon every uptime cycle is pushed to over the Graphite plaintext protocol
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub graphite: Option<crate::graphite::Graphite>,
+    /// InfluxDB server every uptime cycle is written to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub influxdb: Option<crate::influxdb::InfluxDb>,
+    /// Push services notified when a site goes down or recovers
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub notifiers: Vec<crate::notifications::Notifier>,
+    /// Address the dashboard is reached at, linked from notifications
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub public_url: Option<String>,
+    /// Port the server listens on; read at startup, so changes need a restart
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub port: Option<u16>,
+    /// IP address the server listens on; read at startup, so changes need a restart
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bind: Option<std::net::IpAddr>,
     /// List of bookmarked sites to display
     #[serde(default)]
     pub sites: Vec<Site>,
+    /// Whether every HTTP request is logged as a structured access-log event
+    #[serde(default)]
+    pub access_log: bool,
+    /// Seconds to wait for in-flight requests and uptime checks before forcing exit
+    #[serde(default = "default_shutdown_timeout_secs")]
+    pub shutdown_timeout_secs: u64,
+    /// Seconds between keep-alive comments on the uptime SSE stream (0 disables them)
+    #[serde(default = "default_sse_keep_alive_secs")]
+    pub sse_keep_alive_secs: u64,
+    /// Seconds the system clock may differ from the `Date` headers of monitored sites
+    /// before a warning is logged and shown (0 disables the comparison)
+    #[serde(default = "default_clock_drift_warn_secs")]
+    pub clock_drift_warn_secs: u64,
+    /// Named dashboards showing a subset of `sites`, served at `/d/{name}`
+    #[serde(default)]
+    pub dashboards: Vec<Dashboard>,
+    /// Seconds the kiosk view shows each category before the next (0 shows all at once)
+    #[serde(default = "default_kiosk_rotate_secs")]
+    pub kiosk_rotate_secs: u64,
+    /// Default uptime check settings inherited by sites
+    #[serde(default)]
+    pub monitoring: Monitoring,
+    /// Collapsible sections the dashboard groups sites into
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub groups: Vec<Group>,
+    /// Category definitions; when present, every site's category must be one of them
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub categories: Vec<Category>,
+    /// Number of timestamped backups kept when the settings API saves (0 disables them)
+    #[serde(default = "default_config_backup_retention")]
+    pub config_backup_retention: usize,
+    /// Glob patterns, relative to this file, of files whose `sites` are appended to `sites`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub include: Vec<String>,
+    /// Variables expanded into other values as `${NAME}`, before the environment is tried
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub vars: BTreeMap<String, String>,
+    /// Keys that write requests to `/api/` must present once any are configured
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub api_keys: Vec<ApiKey>,
+    /// Whether read requests to `/api/` need an API key too
+    #[serde(default)]
+    pub api_keys_protect_reads: bool,
+    /// Names of the sites that were loaded from included files (runtime only)
+    #[serde(skip)]
+    pub included_sites: Vec<String>,
+    /// Secret references resolved at load time, keyed by field path (runtime only)
+    #[serde(skip)]
+    pub secret_sources: BTreeMap<String, SecretSource>,
+    /// Templates of values that had variables expanded at load time, keyed by field path
+    /// (runtime only)
+    #[serde(skip)]
+    pub variable_sources: BTreeMap<String, VariableSource>,
+    /// Values replaced by `IRON_SHIELD__*` environment variables at load time, in the
+    /// order they were applied (runtime only)
+    #[serde(skip)]
+    pub override_sources: Vec<OverrideSource>,
+}
+
+/// The original secret reference of a config value that was resolved at load time
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SecretSource {
+    /// The reference as written in the config file, such as `${env:API_TOKEN}`
+    pub reference: String,
+    /// The value the reference resolved to
+    pub resolved: String,
+}
+
+/// The original template of a config value whose variables were expanded at load time
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VariableSource {
+    /// The value as written in the config file, such as `https://grafana.${BASE_DOMAIN}`
+    pub template: String,
+    /// The value with its variables expanded
+    pub expanded: String,
+}
+
+/// The file's value of a config field that an environment override replaced at load time
+#[derive(Debug, Clone, PartialEq)]
+pub struct OverrideSource {
+    /// Path of the replaced value, such as `["sites", "0", "url"]`; when the override had
+    /// to create fields missing from the file, the outermost of them
+    pub path: Vec<String>,
+    /// The value in the file, or `None` if the file did not set it
+    pub original: Option<serde_json::Value>,
+    /// The value after the override, as the loaded configuration serializes it
+    pub overridden: serde_json::Value,
+}
+
+impl OverrideSource {
+    /// Put the file's value back into the serialized config `root`, unless the value was
+    /// changed since loading.
+    fn restore(&self, root: &mut serde_json::Value) {
+        use serde_json::Value;
+
+        let Some((last, parents)) = self.path.split_last() else {
+            return;
+        };
+        let parent = parents.iter().try_fold(root, |current, key| match current {
+            Value::Object(map) => map.get_mut(key),
+            Value::Array(items) => items.get_mut(key.parse::<usize>().ok()?),
+            _ => None,
+        });
+        let current = match parent {
+            Some(Value::Object(map)) if map.get(last) == Some(&self.overridden) => {
+                if self.original.is_none() {
+                    map.remove(last);
+                    return;
+                }
+                map.get_mut(last)
+            }
+            Some(Value::Array(items)) => last
+                .parse::<usize>()
+                .ok()
+                .and_then(|index| items.get_mut(index))
+                .filter(|item| **item == self.overridden),
+            _ => None,
+        };
+        if let (Some(current), Some(original)) = (current, &self.original) {
+            current.clone_from(original);
+        }
+    }
 }
 
 /// Represents a bookmarked website in the dashboard
@@ -88,9 +443,19 @@ pub struct Config {
 /// * `category` - An optional category for grouping sites (defaults to empty string)
 /// * `tags` - A vector of tags for categorization and filtering
 /// * `monitor_interval_secs` - How often (in seconds) the site should be checked
+/// * `monitor_timeout_secs` - Seconds a check may take before the site counts as down
+/// * `monitor_retries` - Extra attempts made before a failed check marks the site down
+/// * `monitor_user_agent` - `User-Agent` header sent with the site's checks
 /// * `disabled` - Whether the site should be skipped by the uptime monitor
+/// * `icon` - Optional image URL or bundled icon name, such as `server` or `mdi:harddisk`,
+///   shown next to the site name
+/// * `order` - Optional display position; lower values are shown first
+/// * `assertions` - Optional checks the site's response must pass to count as up
 /// * `uptime_percentage` - The uptime percentage for display in the UI (not in config file)
 ///
+/// The `monitor_*` settings are optional; a site without them inherits the global
+/// [`Monitoring`] defaults (see [`Site::check_settings`]).
+///
 /// # Examples
 ///
 /// ```
@@ -101,8 +466,14 @@ pub struct Config {
 ///     url: "https://google.com".to_string(),
 ///     category: "Search Engines".to_string(),
 ///     tags: vec!["search".to_string(), "important".to_string()],
-///     monitor_interval_secs: iron_shield::config::DEFAULT_MONITOR_INTERVAL_SECS,
+///     monitor_interval_secs: Some(iron_shield::config::DEFAULT_MONITOR_INTERVAL_SECS),
+///     monitor_timeout_secs: None,
+///     monitor_retries: None,
+///     monitor_user_agent: None,
 ///     disabled: false,
+///     icon: None,
+///     order: None,
+///     assertions: None,
 ///     uptime_percentage: 99.9,
 /// };
 ///
@@ -121,30 +492,548 @@ pub struct Site {
     /// List of tags for categorization and filtering
     pub tags: Vec<String>,
     /// Number of seconds between uptime checks for this site
-    #[serde(default = "default_monitor_interval_secs")]
-    pub monitor_interval_secs: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub monitor_interval_secs: Option<u64>,
+    /// Number of seconds a check may take before the site counts as down
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub monitor_timeout_secs: Option<u64>,
+    /// Extra attempts made before a failed check marks the site down
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub monitor_retries: Option<u32>,
+    /// `User-Agent` header sent with uptime checks of this site
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub monitor_user_agent: Option<String>,
     /// Whether uptime checks are currently disabled for this site
     #[serde(default)]
     pub disabled: bool,
+    /// Image URL, server path, or bundled icon name (see the `icons` module)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub icon: Option<String>,
+    /// Display position on the dashboard; lower values come first (see [`sort_for_display`])
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub order: Option<i32>,
+    /// Checks the site's response must pass to count as up (see the `assertions` module)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub assertions: Option<crate::assertions::Assertions>,
     /// The uptime percentage for display in the UI (not in config file)
     /// This field is populated at runtime with data from the uptime monitoring system
     #[serde(default, skip_serializing, skip_deserializing)]
     pub uptime_percentage: f64,
 }
 
+impl Site {
+    /// The site's icon when it is an image URL or server path.
+    #[must_use]
+    pub fn icon_url(&self) -> Option<&str> {
+        self.icon
+            .as_deref()
+            .filter(|icon| crate::icons::is_image_url(icon))
+    }
+
+    /// SVG path data of the site's icon when it names a bundled icon.
+    #[must_use]
+    pub fn bundled_icon_path(&self) -> Option<&'static str> {
+        self.icon.as_deref().and_then(crate::icons::bundled_icon)
+    }
+
+    /// Where the dashboard loads the site's icon image from: a path on this server as
+    /// configured, or the favicon proxy (see [`crate::favicons`]) for an external icon
+    /// URL or no icon at all. `None` for bundled icons, which are drawn inline.
+    #[must_use]
+    pub fn icon_src(&self) -> Option<String> {
+        match self.icon.as_deref() {
+            Some(icon) if icon.starts_with('/') => Some(icon.to_string()),
+            Some(icon) if crate::icons::bundled_icon(icon).is_some() => None,
+            _ => Some(crate::favicons::icon_path(&self.name)),
+        }
+    }
+
+    /// The settings used to check this site, filling unset values from `defaults`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use iron_shield::config::{Monitoring, Site};
+    ///
+    /// let site = Site {
+    ///     name: "NAS".to_string(),
+    ///     url: "http://nas.local".to_string(),
+    ///     category: String::new(),
+    ///     tags: vec![],
+    ///     monitor_interval_secs: None,
+    ///     monitor_timeout_secs: Some(30),
+    ///     monitor_retries: None,
+    ///     monitor_user_agent: None,
+    ///     disabled: false,
+    ///     icon: None,
+    ///     order: None,
+    ///     assertions: None,
+    ///     uptime_percentage: 0.0,
+    /// };
+    /// let defaults = Monitoring {
+    ///     interval_secs: 60,
+    ///     ..Monitoring::default()
+    /// };
+    ///
+    /// let settings = site.check_settings(&defaults);
+    /// assert_eq!(settings.interval_secs, 60);
+    /// assert_eq!(settings.timeout_secs, 30);
+    /// ```
+    #[must_use]
+    pub fn check_settings(&self, defaults: &Monitoring) -> CheckSettings {
+        CheckSettings {
+            interval_secs: self.monitor_interval_secs.unwrap_or(defaults.interval_secs),
+            timeout_secs: self.monitor_timeout_secs.unwrap_or(defaults.timeout_secs),
+            retries: self.monitor_retries.unwrap_or(defaults.retries),
+            user_agent: self
+                .monitor_user_agent
+                .clone()
+                .unwrap_or_else(|| defaults.user_agent.clone()),
+            assertions: self.assertions.clone(),
+        }
+    }
+}
+
+/// Global uptime monitoring defaults
+///
+/// Every setting except `concurrency` can be overridden per site with the matching
+/// `monitor_*` field.
+///
+/// # Fields
+///
+/// * `interval_secs` - Seconds between checks of a site
+/// * `timeout_secs` - Seconds a check may take before the site counts as down
+/// * `concurrency` - Checks run at the same time
+/// * `retries` - Extra attempts made before a failed check marks a site down
+/// * `user_agent` - `User-Agent` header sent with checks
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+#[serde(default)]
+pub struct Monitoring {
+    /// Seconds between checks of a site
+    pub interval_secs: u64,
+    /// Seconds a check may take before the site counts as down
+    pub timeout_secs: u64,
+    /// Checks run at the same time
+    pub concurrency: usize,
+    /// Extra attempts made before a failed check marks a site down
+    pub retries: u32,
+    /// `User-Agent` header sent with checks
+    pub user_agent: String,
+}
+
+impl Default for Monitoring {
+    fn default() -> Self {
+        Monitoring {
+            interval_secs: default_monitor_interval_secs(),
+            timeout_secs: DEFAULT_CHECK_TIMEOUT_SECS,
+            concurrency: DEFAULT_CHECK_CONCURRENCY,
+            retries: 0,
+            user_agent: DEFAULT_USER_AGENT.to_string(),
+        }
+    }
+}
+
+/// The effective settings for checking one site (see [`Site::check_settings`])
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CheckSettings {
+    /// Seconds between checks
+    pub interval_secs: u64,
+    /// Seconds a check may take before the site counts as down
+    pub timeout_secs: u64,
+    /// Extra attempts made before a failed check marks the site down
+    pub retries: u32,
+    /// `User-Agent` header sent with checks
+    pub user_agent: String,
+    /// Checks the response must pass; any 2xx status counts as up when unset
+    pub assertions: Option<crate::assertions::Assertions>,
+}
+
+/// Check that a check interval, timeout, and user agent are usable, reporting each
+/// failure under `prefix` followed by the field name.
+fn validate_check_settings(
+    prefix: &str,
+    interval_secs: Option<u64>,
+    timeout_secs: Option<u64>,
+    user_agent: Option<&str>,
+    fail: &mut impl FnMut(String, String),
+) {
+    let [interval_field, timeout_field, user_agent_field] = if prefix == "monitoring" {
+        ["interval_secs", "timeout_secs", "user_agent"]
+    } else {
+        [
+            "monitor_interval_secs",
+            "monitor_timeout_secs",
+            "monitor_user_agent",
+        ]
+    };
+
+    if interval_secs.is_some_and(|secs| secs < MIN_MONITOR_INTERVAL_SECS) {
+        fail(
+            format!("{prefix}.{interval_field}"),
+            format!("must be at least {MIN_MONITOR_INTERVAL_SECS} seconds"),
+        );
+    }
+    if timeout_secs == Some(0) {
+        fail(
+            format!("{prefix}.{timeout_field}"),
+            "must be at least 1 second".to_string(),
+        );
+    }
+    if let Some(user_agent) = user_agent {
+        if user_agent.trim().is_empty() {
+            fail(
+                format!("{prefix}.{user_agent_field}"),
+                "cannot be empty".to_string(),
+            );
+        } else if reqwest::header::HeaderValue::from_str(user_agent).is_err() {
+            fail(
+                format!("{prefix}.{user_agent_field}"),
+                "is not a valid header value".to_string(),
+            );
+        }
+    }
+}
+
+/// Sort sites into the order they are shown on a dashboard.
+///
+/// Sites with an `order` come first, lowest value first, with equal values sorted by
+/// name. Sites without an `order` follow in the order they were listed.
+///
+/// # Examples
+///
+/// ```
+/// use iron_shield::config::{sort_for_display, Site};
+///
+/// let site = |name: &str, order: Option<i32>| Site {
+///     name: name.to_string(),
+///     url: "https://example.com".to_string(),
+///     category: String::new(),
+///     tags: vec![],
+///     monitor_interval_secs: Some(iron_shield::config::DEFAULT_MONITOR_INTERVAL_SECS),
+///     monitor_timeout_secs: None,
+///     monitor_retries: None,
+///     monitor_user_agent: None,
+///     disabled: false,
+///     icon: None,
+///     order,
+///     assertions: None,
+///     uptime_percentage: 0.0,
+/// };
+/// let mut sites = vec![site("Wiki", None), site("Mail", Some(2)), site("Chat", Some(1))];
+///
+/// sort_for_display(&mut sites);
+/// let names: Vec<&str> = sites.iter().map(|site| site.name.as_str()).collect();
+/// assert_eq!(names, ["Chat", "Mail", "Wiki"]);
+/// ```
+pub fn sort_for_display(sites: &mut [Site]) {
+    sites.sort_by(|a, b| match (a.order, b.order) {
+        (Some(x), Some(y)) => x.cmp(&y).then_with(|| a.name.cmp(&b.name)),
+        (Some(_), None) => std::cmp::Ordering::Less,
+        (None, Some(_)) => std::cmp::Ordering::Greater,
+        (None, None) => std::cmp::Ordering::Equal,
+    });
+}
+
+/// A category that sites are grouped and styled by
+///
+/// Categories are optional. Without them a site's `category` is a free-form label; once
+/// any are defined, every site's category must name one of them so grouping and colors
+/// stay consistent.
+///
+/// # Fields
+///
+/// * `name` - Name referenced by a site's `category`
+/// * `order` - Optional display position of the category's sites; lower values come first
+/// * `color` - Optional accent color as a hex value such as `#3b82f6`
+/// * `icon` - Optional image URL or bundled icon name (see the `icons` module)
+///
+/// # Examples
+///
+/// ```
+/// use iron_shield::config::Category;
+///
+/// let category = Category {
+///     name: "Media".to_string(),
+///     order: Some(1),
+///     color: Some("#e11d48".to_string()),
+///     icon: Some("media".to_string()),
+/// };
+///
+/// assert!(category.bundled_icon_path().is_some());
+/// ```
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct Category {
+    /// Name referenced by a site's `category`
+    pub name: String,
+    /// Display position of this category's sites; lower values come first
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub order: Option<i32>,
+    /// Accent color as a hex value such as `#3b82f6`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+    /// Image URL, server path, or bundled icon name
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub icon: Option<String>,
+}
+
+impl Category {
+    /// The category's icon when it is an image URL or server path.
+    #[must_use]
+    pub fn icon_url(&self) -> Option<&str> {
+        self.icon
+            .as_deref()
+            .filter(|icon| crate::icons::is_image_url(icon))
+    }
+
+    /// SVG path data of the category's icon when it names a bundled icon.
+    #[must_use]
+    pub fn bundled_icon_path(&self) -> Option<&'static str> {
+        self.icon.as_deref().and_then(crate::icons::bundled_icon)
+    }
+}
+
+/// Returns `true` when two site names would share an uptime history: names are compared
+/// ignoring case and surrounding whitespace, matching how the dashboard derives element ids.
+pub(crate) fn same_site_name(a: &str, b: &str) -> bool {
+    a.trim().to_lowercase() == b.trim().to_lowercase()
+}
+
+/// Check that `url` is an absolute `http` or `https` URL with a host, which is what uptime
+/// checks and telemetry can send requests to.
+///
+/// # Errors
+///
+/// Returns a message for a field error, such as `is not a valid URL: empty host`.
+pub(crate) fn check_web_url(url: &str) -> std::result::Result<(), String> {
+    let parsed = url::Url::parse(url).map_err(|err| format!("is not a valid URL: {err}"))?;
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return Err(format!(
+            "must be an http or https URL, not {}:",
+            parsed.scheme()
+        ));
+    }
+    if !parsed.has_host() {
+        return Err("is not a valid URL: empty host".to_string());
+    }
+    Ok(())
+}
+
+/// Check that `url` is something uptime checks can monitor: a URL accepted by
+/// [`check_web_url`], or one whose scheme has a registered checker (see
+/// [`crate::checkers`]).
+///
+/// # Errors
+///
+/// Returns the message of [`check_web_url`] for any other URL.
+pub(crate) fn check_site_url(url: &str) -> std::result::Result<(), String> {
+    let custom = url::Url::parse(url).is_ok_and(|parsed| {
+        !matches!(parsed.scheme(), "http" | "https")
+            && crate::checkers::is_registered(parsed.scheme())
+    });
+    if custom {
+        return Ok(());
+    }
+    check_web_url(url)
+}
+
+/// A site URL in a form where equivalent spellings (host case, a trailing slash) compare equal.
+pub(crate) fn normalized_url(url: &str) -> String {
+    url::Url::parse(url.trim()).map_or_else(
+        |_| url.trim().trim_end_matches('/').to_string(),
+        |parsed| parsed.as_str().trim_end_matches('/').to_string(),
+    )
+}
+
+/// A key that scripts and integrations present to call the API
+///
+/// Only the SHA-256 hash of the key is stored, so reading the config file does not reveal
+/// it. `iron_shield --generate-api-key NAME` and `POST /api/keys` create keys; see the
+/// [`crate::api_keys`] module.
+///
+/// # Examples
+///
+/// ```
+/// use iron_shield::config::ApiKey;
+///
+/// let key = ApiKey {
+///     name: "home-assistant".to_string(),
+///     hash: iron_shield::api_keys::hash("ish_example"),
+/// };
+///
+/// assert_eq!(key.hash.len(), 64);
+/// ```
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+pub struct ApiKey {
+    /// Label of whoever uses the key, such as `home-assistant`
+    pub name: String,
+    /// Hex-encoded SHA-256 hash of the key
+    pub hash: String,
+}
+
+/// Returns `true` for `#rgb`, `#rgba`, `#rrggbb`, and `#rrggbbaa` colors.
+fn is_hex_color(color: &str) -> bool {
+    color.strip_prefix('#').is_some_and(|hex| {
+        matches!(hex.len(), 3 | 4 | 6 | 8) && hex.chars().all(|c| c.is_ascii_hexdigit())
+    })
+}
+
+/// A named dashboard showing a subset of the configured sites
+///
+/// Dashboards reference sites from the top-level `sites` list by name, so every site is
+/// defined once and monitored by the same engine no matter how many dashboards show it.
+/// Each dashboard is served at `/d/{name}`.
+///
+/// # Fields
+///
+/// * `name` - URL segment identifying the dashboard
+/// * `title` - Optional page title (defaults to the global `site_name`)
+/// * `sites` - Names of the sites shown on this dashboard, in display order
+///
+/// # Examples
+///
+/// ```
+/// use iron_shield::config::Dashboard;
+///
+/// let dashboard = Dashboard {
+///     name: "homelab".to_string(),
+///     title: Some("Homelab".to_string()),
+///     sites: vec!["Router".to_string(), "NAS".to_string()],
+/// };
+///
+/// assert_eq!(dashboard.name, "homelab");
+/// ```
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct Dashboard {
+    /// URL segment identifying the dashboard
+    pub name: String,
+    /// Page title shown for this dashboard
+    #[serde(default)]
+    pub title: Option<String>,
+    /// Names of the sites shown on this dashboard
+    #[serde(default)]
+    pub sites: Vec<String>,
+}
+
+/// A collapsible section of the dashboard
+///
+/// Groups list sites from the top-level `sites` list by name. Grouped sites are shown in
+/// their group's section, in display order, and the remaining sites follow the groups.
+/// A site listed by more than one group is shown in the first.
+///
+/// # Fields
+///
+/// * `name` - Heading of the section
+/// * `sites` - Names of the sites in the section
+/// * `collapsed` - Whether the section starts collapsed; visitors can still expand it,
+///   and their choice is remembered by the browser
+///
+/// # Examples
+///
+/// ```
+/// use iron_shield::config::Group;
+///
+/// let group = Group {
+///     name: "Media".to_string(),
+///     sites: vec!["Plex".to_string(), "Jellyfin".to_string()],
+///     collapsed: true,
+/// };
+///
+/// assert!(group.sites.contains(&"Plex".to_string()));
+/// ```
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct Group {
+    /// Heading of the section
+    pub name: String,
+    /// Names of the sites in the section
+    #[serde(default)]
+    pub sites: Vec<String>,
+    /// Whether the section starts collapsed
+    #[serde(default)]
+    pub collapsed: bool,
+}
+
 impl Default for Config {
     /// Provides a default configuration with pre-filled values
     ///
     /// The default configuration includes:
+    /// - Version: `CURRENT_CONFIG_VERSION`
     /// - Site name: "Iron Shield Dashboard" (using the `default_site_name` function)
     /// - Clock: `Clock::None` (no clock displayed)
+    /// - Time zone: `DEFAULT_TIMEZONE` (UTC)
+    /// - World clocks: none
+    /// - Theme: `Theme::Auto` (follows the browser's color scheme)
+    /// - Language: `Language::En`
+    /// - Layout: `Layout::Grid` (cards)
+    /// - Sort order: `SortBy::Config` (as listed)
+    /// - Custom stylesheet and logo: none
+    /// - Icon cache TTL: `DEFAULT_ICON_CACHE_TTL_SECS` (one week)
+    /// - Weather widget: none
+    /// - Widgets: none
+    /// - Telemetry: disabled, over OTLP/HTTP, exported every
+    ///   `DEFAULT_OPENTELEMETRY_EXPORT_INTERVAL_SECS` once enabled
+    /// - StatsD, Graphite, and InfluxDB exports: none
+    /// - Notifiers: none
     /// - Sites: An empty vector of sites
+    /// - Access log: disabled
+    /// - Shutdown timeout: `DEFAULT_SHUTDOWN_TIMEOUT_SECS`
+    /// - SSE keep-alive interval: `DEFAULT_SSE_KEEP_ALIVE_SECS`
+    /// - Clock drift warning: `DEFAULT_CLOCK_DRIFT_WARN_SECS`
+    /// - Dashboards: none
+    /// - Kiosk rotation: `DEFAULT_KIOSK_ROTATE_SECS`
+    /// - Monitoring: `Monitoring::default()`
+    /// - Groups: none
+    /// - Categories: none (site categories are free-form)
+    /// - Config backup retention: `DEFAULT_CONFIG_BACKUP_RETENTION`
+    /// - Includes: none
+    /// - Variables: none
     fn default() -> Self {
         Config {
+            version: crate::migration::CURRENT_CONFIG_VERSION,
             site_name: default_site_name(),
             clock: Clock::None,
+            timezone: default_timezone(),
+            clocks: Vec::new(),
+            theme: Theme::Auto,
+            language: crate::i18n::Language::En,
+            layout: Layout::Grid,
+            sort_by: SortBy::Config,
+            custom_css: None,
+            logo: None,
+            templates_dir: None,
+            icon_cache_ttl_secs: DEFAULT_ICON_CACHE_TTL_SECS,
+            weather: None,
+            widgets: Vec::new(),
             opentelemetry_endpoint: None,
+            opentelemetry_transport: TelemetryTransport::Http,
+            opentelemetry_export_interval_secs: DEFAULT_OPENTELEMETRY_EXPORT_INTERVAL_SECS,
+            opentelemetry_traces: false,
+            opentelemetry_sampling: crate::telemetry::TelemetrySampling::default(),
+            statsd: None,
+            graphite: None,
+            influxdb: None,
+            notifiers: Vec::new(),
+            public_url: None,
+            port: None,
+            bind: None,
             sites: Vec::new(),
+            access_log: false,
+            shutdown_timeout_secs: DEFAULT_SHUTDOWN_TIMEOUT_SECS,
+            sse_keep_alive_secs: DEFAULT_SSE_KEEP_ALIVE_SECS,
+            clock_drift_warn_secs: DEFAULT_CLOCK_DRIFT_WARN_SECS,
+            dashboards: Vec::new(),
+            kiosk_rotate_secs: DEFAULT_KIOSK_ROTATE_SECS,
+            monitoring: Monitoring::default(),
+            groups: Vec::new(),
+            categories: Vec::new(),
+            config_backup_retention: DEFAULT_CONFIG_BACKUP_RETENTION,
+            include: Vec::new(),
+            included_sites: Vec::new(),
+            vars: BTreeMap::new(),
+            api_keys: Vec::new(),
+            api_keys_protect_reads: false,
+            secret_sources: BTreeMap::new(),
+            variable_sources: BTreeMap::new(),
+            override_sources: Vec::new(),
         }
     }
 }
@@ -152,9 +1041,17 @@ impl Default for Config {
 impl Config {
     /// Load the application configuration from the config.json5 file
     ///
-    /// This method reads the specified configuration file and parses it as JSON5 format
-    /// into a Config struct. It handles errors for file reading and parsing, providing
-    /// appropriate error messages with context about the file that failed to load.
+    /// This method reads the specified configuration file and parses it into a Config
+    /// struct, choosing JSON5, YAML, or TOML from the file extension (see [`ConfigFormat`]).
+    /// Files written for an older config `version` are migrated to the current format
+    /// first (see [`crate::migration`]).
+    /// The path may also be a `conf.d`-style directory, in which case every config file
+    /// in it is merged into one configuration (see [`load_fragments`]).
+    /// Environment variables starting with [`ENV_OVERRIDE_PREFIX`] are then layered on top,
+    /// so deployments can adjust settings without editing the file, and the sites of any
+    /// files matched by `include` are appended. It handles errors for file reading and
+    /// parsing, providing appropriate error messages with context about the file that
+    /// failed to load.
     ///
     /// # Arguments
     ///
@@ -169,7 +1066,13 @@ impl Config {
     ///
     /// This function returns an error if:
     /// - The configuration file cannot be read (e.g., file doesn't exist, no permissions)
-    /// - The configuration file contains invalid JSON5 syntax
+    /// - The configuration file contains invalid JSON5, YAML, or TOML syntax
+    /// - The configuration `version` is invalid or newer than this release supports
+    /// - An environment override gives a field a value of the wrong type
+    /// - An `include` pattern is invalid or a matched file cannot be read or parsed
+    /// - A value refers to a `${NAME}` variable defined neither in `vars` nor the environment
+    /// - A secret reference names an unset environment variable or unreadable file
+    /// - The parsed configuration fails [`Config::validate`]
     ///
     /// # Examples
     ///
@@ -182,22 +1085,67 @@ impl Config {
     /// // let config = Config::load(&config_path).unwrap();
     /// ```
     pub fn load(config_file_path: &PathBuf) -> crate::error::Result<Self> {
+        Self::load_with_env(config_file_path, std::env::vars())
+    }
+
+    /// Load the configuration, layering the given environment variables on top of the file.
+    pub(crate) fn load_with_env(
+        config_file_path: &PathBuf,
+        vars: impl IntoIterator<Item = (String, String)>,
+    ) -> crate::error::Result<Self> {
         tracing::debug!(
             "Loading application configuration from {:?}",
             config_file_path
         );
-        let config_str = fs::read_to_string(config_file_path).map_err(|e| {
+        let vars: Vec<(String, String)> = vars.into_iter().collect();
+        let overrides = env_overrides(vars.iter().cloned());
+        let parsed = if config_file_path.is_dir() {
+            apply_env_overrides(load_fragments(config_file_path)?, &overrides)
+        } else {
+            let config_str = fs::read_to_string(config_file_path).map_err(|e| {
+                crate::error::IronShieldError::Generic(format!(
+                    "Failed to read config file {}: {e}",
+                    config_file_path.display()
+                ))
+            })?;
+
+            let format = ConfigFormat::from_path(config_file_path);
+            format
+                .parse::<serde_json::Value>(&config_str)
+                .and_then(|mut value| {
+                    let from_version = migrate(&mut value)?;
+                    log_migration(config_file_path, from_version);
+                    // Without migrations or overrides, parse directly so format-specific
+                    // errors keep their locations
+                    if from_version == CURRENT_CONFIG_VERSION && overrides.is_empty() {
+                        format.parse::<Config>(&config_str)
+                    } else {
+                        apply_env_overrides(value, &overrides)
+                    }
+                })
+        };
+        let mut config: Config = parsed.map_err(|e| {
+            let kind = if config_file_path.is_dir() {
+                "directory"
+            } else {
+                "file"
+            };
             crate::error::IronShieldError::Generic(format!(
-                "Failed to read config file {}: {e}",
+                "Failed to parse config {kind} {}: {e}",
                 config_file_path.display()
             ))
         })?;
-        let config: Config = json5::from_str(&config_str).map_err(|e| {
-            crate::error::IronShieldError::Generic(format!(
-                "Failed to parse config file {}: {e}",
-                config_file_path.display()
-            ))
+        config.load_includes(config_file_path)?;
+        config.interpolate_variables_with(|name| {
+            vars.iter()
+                .find(|(var, _)| var == name)
+                .map(|(_, value)| value.clone())
         })?;
+        config.resolve_secrets()?;
+        config.validate()?;
+        for warning in config.warnings() {
+            warn!("Config {}: {warning}", config_file_path.display());
+        }
 
         tracing::info!(
             "Configuration loaded successfully from {}",
@@ -205,34 +1153,1462 @@ impl Config {
         );
         Ok(config)
     }
-}
 
-/// Provides a default site name if not specified in config
-///
-/// This function returns the default site name used when the configuration doesn't
-/// specify one. It's used by the serde `default` attribute on the `site_name` field.
-///
-/// # Returns
-///
-/// Returns the default site name as a String: "Iron Shield Dashboard"
-fn default_site_name() -> String {
-    "Iron Shield Dashboard".to_string()
-}
+    /// Check the configuration against the rules shared by loading, reloading, and saving.
+    ///
+    /// Every problem is reported with the path of the offending field, such as
+    /// `sites[3].url is not a valid URL`, rather than stopping at the first one.
+    ///
+    /// # Errors
+    ///
+    /// Returns `IronShieldError::ConfigInvalid` listing every failed rule:
+    /// - `site_name` is empty
+    /// - `timezone` is not an IANA time zone name such as `America/New_York`
+    /// - a world clock in `clocks` has an empty label or an unknown time zone
+    /// - `opentelemetry_endpoint` is set but empty or not a valid `http` or `https` URL
+    /// - `opentelemetry_sampling` has an `every` of 0 or an empty name in `exclude_sites`
+    /// - `statsd` has an empty host, a zero port, or a prefix with `:`, `|` or `@`
+    /// - `graphite` has an empty host, a zero port, or a prefix with whitespace
+    /// - `influxdb` has a URL that is not a valid `http` or `https` URL, or no bucket
+    /// - a notifier has an invalid URL, an ntfy notifier no topic, a Gotify notifier no
+    ///   token, a priority outside its service's range, or a template with an unknown
+    ///   or unterminated placeholder
+    /// - `public_url` is set but not a valid `http` or `https` URL
+    /// - `port` is 0
+    /// - a site has an empty name or one already used by another site (ignoring case and
+    ///   surrounding spaces, since names key the uptime history), an empty URL or one that
+    ///   is not a valid `http` or `https` URL with a host, or a
+    ///   `monitor_interval_secs` below `MIN_MONITOR_INTERVAL_SECS`, or an `icon` that is
+    ///   neither an image URL nor a bundled icon name
+    /// - a site or the `monitoring` block has a zero timeout or an empty or invalid user
+    ///   agent, or `monitoring` has an interval below `MIN_MONITOR_INTERVAL_SECS` or a
+    ///   `concurrency` of 0
+    /// - `custom_css` is not a path to a `.css` file, or `logo` not a path to an image
+    /// - `templates_dir` is empty or a URL rather than a path
+    /// - `weather` has an empty or malformed location, lacks an API key its provider
+    ///   needs, or refreshes more often than every `MIN_REFRESH_SECS`
+    /// - a widget has an empty title, or not exactly one of an `http` or `https`
+    ///   `iframe` URL and an `html` snippet
+    /// - a site's `category` is not one of `categories`, when any are defined
+    /// - a category has an empty or duplicate name, a color that is not a hex color, or
+    ///   an invalid icon
+    /// - a group has an empty or duplicate name
+    /// - a dashboard has an empty, duplicate, or non URL-safe name
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use iron_shield::config::Config;
+    ///
+    /// let config = Config {
+    ///     site_name: String::new(),
+    ///     ..Config::default()
+    /// };
+    ///
+    /// let err = config.validate().unwrap_err();
+    /// assert!(err.to_string().contains("site_name cannot be empty"));
+    /// ```
+    pub fn validate(&self) -> crate::error::Result<()> {
+        let mut errors = Vec::new();
+        let mut fail = |field: String, message: String| {
+            errors.push(ValidationError { field, message });
+        };
 
-/// Clock format options
-///
-/// Defines the format in which to display the time on the dashboard.
-/// The enum supports serialization and deserialization for configuration persistence.
-///
-/// # Variants
-///
-/// * `None` - No clock is displayed on the dashboard
-/// * `Hour24` - Time is displayed in 24-hour format (e.g., 13:00)
-/// * `Hour12` - Time is displayed in 12-hour format with AM/PM (e.g., 1:00 PM)
-///
-/// # Examples
-///
-/// ```
+        if self.site_name.trim().is_empty() {
+            fail("site_name".to_string(), "cannot be empty".to_string());
+        }
+
+        if self.timezone.parse::<Tz>().is_err() {
+            fail(
+                "timezone".to_string(),
+                format!("\"{}\" is not an IANA time zone name", self.timezone),
+            );
+        }
+        for (index, clock) in self.clocks.iter().enumerate() {
+            if clock.label.trim().is_empty() {
+                fail(
+                    format!("clocks[{index}].label"),
+                    "cannot be empty".to_string(),
+                );
+            }
+            if clock.timezone.parse::<Tz>().is_err() {
+                fail(
+                    format!("clocks[{index}].timezone"),
+                    format!("\"{}\" is not an IANA time zone name", clock.timezone),
+                );
+            }
+        }
+
+        if let Some(Err(message)) = self
+            .custom_css
+            .as_deref()
+            .map(crate::branding::check_custom_css)
+        {
+            fail("custom_css".to_string(), message);
+        }
+        if let Some(Err(message)) = self.logo.as_deref().map(crate::branding::check_logo) {
+            fail("logo".to_string(), message);
+        }
+        if let Some(Err(message)) = self
+            .templates_dir
+            .as_deref()
+            .map(crate::branding::check_local_path)
+        {
+            fail("templates_dir".to_string(), message);
+        }
+        if let Some(statsd) = &self.statsd {
+            for (field, message) in statsd.validate() {
+                fail(format!("statsd.{field}"), message);
+            }
+        }
+        for (field, message) in self.opentelemetry_sampling.validate() {
+            fail(format!("opentelemetry_sampling.{field}"), message);
+        }
+        if let Some(graphite) = &self.graphite {
+            for (field, message) in graphite.validate() {
+                fail(format!("graphite.{field}"), message);
+            }
+        }
+        if let Some(influxdb) = &self.influxdb {
+            for (field, message) in influxdb.validate() {
+                fail(format!("influxdb.{field}"), message);
+            }
+        }
+        if let Some(weather) = &self.weather {
+            for (field, message) in weather.validate() {
+                fail(format!("weather.{field}"), message);
+            }
+        }
+        for (index, notifier) in self.notifiers.iter().enumerate() {
+            for (field, message) in notifier.validate() {
+                fail(format!("notifiers[{index}].{field}"), message);
+            }
+        }
+        if let Some(public_url) = &self.public_url {
+            let valid = url::Url::parse(public_url.trim())
+                .is_ok_and(|url| matches!(url.scheme(), "http" | "https") && url.host().is_some());
+            if !valid {
+                fail(
+                    "public_url".to_string(),
+                    format!("\"{public_url}\" is not a valid http or https URL"),
+                );
+            }
+        }
+        if self.port == Some(0) {
+            fail(
+                "port".to_string(),
+                "must be between 1 and 65535".to_string(),
+            );
+        }
+        for (index, widget) in self.widgets.iter().enumerate() {
+            for (field, message) in widget.validate() {
+                fail(format!("widgets[{index}].{field}"), message);
+            }
+        }
+
+        if let Some(endpoint) = &self.opentelemetry_endpoint {
+            if endpoint.trim().is_empty() {
+                fail(
+                    "opentelemetry_endpoint".to_string(),
+                    "cannot be empty".to_string(),
+                );
+            } else if let Err(message) = check_web_url(endpoint) {
+                fail("opentelemetry_endpoint".to_string(), message);
+            }
+        }
+
+        for (index, site) in self.sites.iter().enumerate() {
+            if site.name.trim().is_empty() {
+                fail(
+                    format!("sites[{index}].name"),
+                    "cannot be empty".to_string(),
+                );
+            } else if let Some(first) = self.sites[..index]
+                .iter()
+                .position(|other| same_site_name(&other.name, &site.name))
+            {
+                fail(
+                    format!("sites[{index}].name"),
+                    format!(
+                        "\"{}\" duplicates sites[{first}].name \"{}\"",
+                        site.name, self.sites[first].name
+                    ),
+                );
+            }
+
+            if site.url.trim().is_empty() {
+                fail(format!("sites[{index}].url"), "cannot be empty".to_string());
+            } else if let Err(message) = check_site_url(&site.url) {
+                fail(format!("sites[{index}].url"), message);
+            }
+
+            if let Some(icon) = &site.icon {
+                if let Err(message) = crate::icons::validate(icon) {
+                    fail(format!("sites[{index}].icon"), message);
+                }
+            }
+
+            if !self.categories.is_empty()
+                && !site.category.is_empty()
+                && self.category(&site.category).is_none()
+            {
+                fail(
+                    format!("sites[{index}].category"),
+                    format!("\"{}\" is not defined in categories", site.category),
+                );
+            }
+
+            validate_check_settings(
+                &format!("sites[{index}]"),
+                site.monitor_interval_secs,
+                site.monitor_timeout_secs,
+                site.monitor_user_agent.as_deref(),
+                &mut fail,
+            );
+
+            if let Some(assertions) = &site.assertions {
+                for (field, message) in assertions.validate() {
+                    fail(format!("sites[{index}].assertions.{field}"), message);
+                }
+            }
+        }
+
+        validate_check_settings(
+            "monitoring",
+            Some(self.monitoring.interval_secs),
+            Some(self.monitoring.timeout_secs),
+            Some(&self.monitoring.user_agent),
+            &mut fail,
+        );
+        if self.monitoring.concurrency == 0 {
+            fail(
+                "monitoring.concurrency".to_string(),
+                "must be at least 1".to_string(),
+            );
+        }
+
+        for (index, category) in self.categories.iter().enumerate() {
+            if category.name.trim().is_empty() {
+                fail(
+                    format!("categories[{index}].name"),
+                    "cannot be empty".to_string(),
+                );
+            } else if let Some(first) = self.categories[..index]
+                .iter()
+                .position(|other| other.name.eq_ignore_ascii_case(&category.name))
+            {
+                fail(
+                    format!("categories[{index}].name"),
+                    format!("duplicates categories[{first}].name \"{}\"", category.name),
+                );
+            }
+
+            if let Some(color) = &category.color {
+                if !is_hex_color(color) {
+                    fail(
+                        format!("categories[{index}].color"),
+                        format!("\"{color}\" is not a hex color such as #3b82f6"),
+                    );
+                }
+            }
+
+            if let Some(icon) = &category.icon {
+                if let Err(message) = crate::icons::validate(icon) {
+                    fail(format!("categories[{index}].icon"), message);
+                }
+            }
+        }
+
+        for (index, group) in self.groups.iter().enumerate() {
+            if group.name.trim().is_empty() {
+                fail(
+                    format!("groups[{index}].name"),
+                    "cannot be empty".to_string(),
+                );
+            } else if let Some(first) = self.groups[..index]
+                .iter()
+                .position(|other| other.name == group.name)
+            {
+                fail(
+                    format!("groups[{index}].name"),
+                    format!("duplicates groups[{first}].name \"{}\"", group.name),
+                );
+            }
+        }
+
+        for (index, dashboard) in self.dashboards.iter().enumerate() {
+            if dashboard.name.is_empty() {
+                fail(
+                    format!("dashboards[{index}].name"),
+                    "cannot be empty".to_string(),
+                );
+            } else if !dashboard
+                .name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+            {
+                fail(
+                    format!("dashboards[{index}].name"),
+                    "may only contain letters, digits, '-' and '_'".to_string(),
+                );
+            } else if let Some(first) = self.dashboards[..index]
+                .iter()
+                .position(|other| other.name == dashboard.name)
+            {
+                fail(
+                    format!("dashboards[{index}].name"),
+                    format!("duplicates dashboards[{first}].name \"{}\"", dashboard.name),
+                );
+            }
+        }
+
+        for (index, key) in self.api_keys.iter().enumerate() {
+            if key.name.trim().is_empty() {
+                fail(
+                    format!("api_keys[{index}].name"),
+                    "cannot be empty".to_string(),
+                );
+            } else if let Some(first) = self.api_keys[..index]
+                .iter()
+                .position(|other| other.name == key.name)
+            {
+                fail(
+                    format!("api_keys[{index}].name"),
+                    format!("duplicates api_keys[{first}].name \"{}\"", key.name),
+                );
+            }
+
+            if !crate::api_keys::is_hash(&key.hash) {
+                fail(
+                    format!("api_keys[{index}].hash"),
+                    "is not a SHA-256 hash; generate one with `iron_shield --generate-api-key`"
+                        .to_string(),
+                );
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(crate::error::IronShieldError::ConfigInvalid(errors))
+        }
+    }
+
+    /// The category definition called `name`, compared case-insensitively.
+    #[must_use]
+    pub fn category(&self, name: &str) -> Option<&Category> {
+        self.categories
+            .iter()
+            .find(|category| category.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Sort sites so categories appear in their display order.
+    ///
+    /// Categories with an `order` come first, lowest first, followed by the others in
+    /// the order they are defined and then sites without a defined category. The sort is
+    /// stable, so sites keep their relative order (see [`sort_for_display`]) within a
+    /// category. Nothing changes when no categories are defined.
+    pub fn sort_by_category(&self, sites: &mut [Site]) {
+        if self.categories.is_empty() {
+            return;
+        }
+        let rank = |site: &Site| {
+            self.categories
+                .iter()
+                .position(|category| category.name.eq_ignore_ascii_case(&site.category))
+                .map_or((2, 0, usize::MAX), |position| {
+                    let category = &self.categories[position];
+                    match category.order {
+                        Some(order) => (0, order, position),
+                        None => (1, 0, position),
+                    }
+                })
+        };
+        sites.sort_by_key(rank);
+    }
+
+    /// The time zone of the dashboard clock, falling back to UTC if `timezone` is invalid.
+    #[must_use]
+    pub fn tz(&self) -> Tz {
+        self.timezone.parse().unwrap_or(Tz::UTC)
+    }
+
+    /// Find problems that do not stop the configuration from loading but are likely mistakes.
+    ///
+    /// Logged when the configuration is loaded or saved, and reported by
+    /// `iron_shield --check-config`:
+    /// - no sites are configured
+    /// - two sites share a URL, so the same site is checked twice
+    /// - a dashboard or group lists a site name that does not exist
+    /// - a site is listed by more than one group, so only the first shows it
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use iron_shield::config::Config;
+    ///
+    /// let warnings = Config::default().warnings();
+    /// assert_eq!(warnings[0].to_string(), "sites is empty; the dashboard will show no sites");
+    /// ```
+    #[must_use]
+    pub fn warnings(&self) -> Vec<ValidationError> {
+        let mut warnings = Vec::new();
+        let mut warn = |field: String, message: String| {
+            warnings.push(ValidationError { field, message });
+        };
+
+        if self.sites.is_empty() {
+            warn(
+                "sites".to_string(),
+                "is empty; the dashboard will show no sites".to_string(),
+            );
+        }
+
+        for (index, site) in self.sites.iter().enumerate() {
+            let url = normalized_url(&site.url);
+            if let Some(first) = self.sites[..index]
+                .iter()
+                .position(|other| normalized_url(&other.url) == url)
+            {
+                warn(
+                    format!("sites[{index}].url"),
+                    format!(
+                        "\"{}\" of \"{}\" is also used by \"{}\" (sites[{first}]), so it is checked twice",
+                        site.url, site.name, self.sites[first].name
+                    ),
+                );
+            }
+        }
+
+        for (index, dashboard) in self.dashboards.iter().enumerate() {
+            for name in &dashboard.sites {
+                if !self.sites.iter().any(|site| site.name == *name) {
+                    warn(
+                        format!("dashboards[{index}].sites"),
+                        format!("references unknown site \"{name}\""),
+                    );
+                }
+            }
+        }
+
+        for (index, group) in self.groups.iter().enumerate() {
+            for name in &group.sites {
+                if !self.sites.iter().any(|site| site.name == *name) {
+                    warn(
+                        format!("groups[{index}].sites"),
+                        format!("references unknown site \"{name}\""),
+                    );
+                } else if let Some(first) = self.groups[..index]
+                    .iter()
+                    .position(|other| other.sites.contains(name))
+                {
+                    warn(
+                        format!("groups[{index}].sites"),
+                        format!("lists \"{name}\", which is already shown in groups[{first}]"),
+                    );
+                }
+            }
+        }
+
+        warnings
+    }
+
+    /// Append the sites of every file matched by the `include` patterns.
+    ///
+    /// Patterns are resolved relative to the directory of `config_file_path` and matched
+    /// files are read in sorted order. Each included file uses the format implied by its
+    /// own extension and may only contribute `sites`; includes are not followed
+    /// recursively. Patterns that match nothing are logged and skipped.
+    fn load_includes(&mut self, config_file_path: &Path) -> crate::error::Result<()> {
+        /// Shape of an included file
+        #[derive(Deserialize)]
+        struct IncludedFile {
+            #[serde(default)]
+            sites: Vec<Site>,
+        }
+
+        let base_dir = include_base_dir(config_file_path);
+        for pattern in &self.include {
+            let full_pattern = base_dir.join(pattern);
+            let entries = glob::glob(&full_pattern.to_string_lossy()).map_err(|e| {
+                crate::error::IronShieldError::ConfigValidation {
+                    field: "include".to_string(),
+                    message: format!("\"{pattern}\" is not a valid glob pattern: {e}"),
+                }
+            })?;
+            let mut paths: Vec<PathBuf> = entries
+                .filter_map(|entry| match entry {
+                    Ok(path) => Some(path),
+                    Err(e) => {
+                        warn!("Skipping unreadable include match: {e}");
+                        None
+                    }
+                })
+                .filter(|path| path.is_file())
+                .collect();
+            paths.sort();
+
+            if paths.is_empty() {
+                warn!("Config include pattern \"{pattern}\" did not match any files");
+            }
+
+            for path in paths {
+                let contents = fs::read_to_string(&path).map_err(|e| {
+                    crate::error::IronShieldError::Generic(format!(
+                        "Failed to read included config file {}: {e}",
+                        path.display()
+                    ))
+                })?;
+                let included: IncludedFile = ConfigFormat::from_path(&path)
+                    .parse::<serde_json::Value>(&contents)
+                    .and_then(|mut value| {
+                        migrate(&mut value)?;
+                        serde_json::from_value(value).map_err(|e| e.to_string())
+                    })
+                    .map_err(|e| {
+                        crate::error::IronShieldError::Generic(format!(
+                            "Failed to parse included config file {}: {e}",
+                            path.display()
+                        ))
+                    })?;
+                debug!(
+                    "Included {} sites from {}",
+                    included.sites.len(),
+                    path.display()
+                );
+                self.included_sites
+                    .extend(included.sites.iter().map(|site| site.name.clone()));
+                self.sites.extend(included.sites);
+            }
+        }
+        Ok(())
+    }
+
+    /// Replace secret references in credential fields with the secrets they point to.
+    ///
+    /// The original references are recorded in `secret_sources` so
+    /// [`Config::for_persistence`] can write them back instead of the secrets. Fields
+    /// that no longer hold a reference are forgotten.
+    ///
+    /// # Errors
+    ///
+    /// Returns `IronShieldError::ConfigInvalid` naming each field whose reference could
+    /// not be resolved.
+    pub fn resolve_secrets(&mut self) -> crate::error::Result<()> {
+        let mut errors = Vec::new();
+        let mut sources = BTreeMap::new();
+        for (field, value) in self.secret_fields_mut() {
+            let Some(current) = value.as_deref() else {
+                continue;
+            };
+            if !crate::secrets::is_secret_reference(current) {
+                continue;
+            }
+            match crate::secrets::resolve(current) {
+                Ok(resolved) => {
+                    sources.insert(
+                        field.clone(),
+                        SecretSource {
+                            reference: current.to_string(),
+                            resolved: resolved.clone(),
+                        },
+                    );
+                    *value = Some(resolved);
+                }
+                Err(message) => errors.push(ValidationError {
+                    field,
+                    message: format!("has an unresolvable secret reference: {message}"),
+                }),
+            }
+        }
+
+        if !errors.is_empty() {
+            return Err(crate::error::IronShieldError::ConfigInvalid(errors));
+        }
+        self.secret_sources = sources;
+        Ok(())
+    }
+
+    /// Resolve the secret references of a configuration edited through the API or
+    /// settings page.
+    ///
+    /// A field keeping the reference it was loaded with, or the secret that reference
+    /// resolved to, gets that secret again. Any other reference is rejected, so a request
+    /// cannot have the server read a file or environment variable of its choosing into a
+    /// value it sends elsewhere; new references can only be added in the config file.
+    ///
+    /// # Errors
+    ///
+    /// Returns `IronShieldError::ConfigInvalid` naming each field with a new secret
+    /// reference.
+    pub(crate) fn resolve_edited_secrets(&mut self) -> crate::error::Result<()> {
+        let loaded = std::mem::take(&mut self.secret_sources);
+        let mut errors = Vec::new();
+        let mut sources = BTreeMap::new();
+        for (field, value) in self.secret_fields_mut() {
+            let Some(current) = value.as_deref() else {
+                continue;
+            };
+            if !crate::secrets::is_secret_reference(current) {
+                // A field the edit left alone still holds the secret it resolved to
+                if let Some(source) = loaded.get(&field) {
+                    if source.resolved == current {
+                        sources.insert(field, source.clone());
+                    }
+                }
+                continue;
+            }
+            match loaded.get(&field) {
+                Some(source) if source.reference == current => {
+                    *value = Some(source.resolved.clone());
+                    sources.insert(field, source.clone());
+                }
+                _ => errors.push(ValidationError {
+                    field,
+                    message: "has a new secret reference; add it to the config file instead"
+                        .to_string(),
+                }),
+            }
+        }
+
+        if !errors.is_empty() {
+            return Err(crate::error::IronShieldError::ConfigInvalid(errors));
+        }
+        self.secret_sources = sources;
+        Ok(())
+    }
+
+    /// Expand `${NAME}` variables in every value from `vars` or the environment.
+    ///
+    /// The templates are recorded in `variable_sources` so [`Config::for_persistence`] can
+    /// write them back instead of the expanded values; templates recorded earlier are kept,
+    /// so this can run again after editing an expanded configuration. `vars` and `include`
+    /// are not expanded. Run this before [`Config::resolve_secrets`], which leaves the secret
+    /// references inside expanded values for it to resolve.
+    ///
+    /// # Errors
+    ///
+    /// Returns `IronShieldError::ConfigInvalid` naming each field that refers to an
+    /// undefined variable or has a malformed reference.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use iron_shield::config::{Config, Site};
+    ///
+    /// let mut config: Config = serde_json::from_value(serde_json::json!({
+    ///     "vars": { "BASE_DOMAIN": "home.lan" },
+    ///     "sites": [{ "name": "Grafana", "url": "https://grafana.${BASE_DOMAIN}", "tags": [] }]
+    /// }))
+    /// .unwrap();
+    ///
+    /// config.interpolate_variables().unwrap();
+    /// assert_eq!(config.sites[0].url, "https://grafana.home.lan");
+    /// assert_eq!(
+    ///     config.for_persistence().sites[0].url,
+    ///     "https://grafana.${BASE_DOMAIN}"
+    /// );
+    /// ```
+    // Library API; the server expands from `vars` only when the configuration is edited
+    #[allow(dead_code)]
+    pub fn interpolate_variables(&mut self) -> crate::error::Result<()> {
+        self.interpolate_variables_with(|name| std::env::var(name).ok())
+    }
+
+    /// Expand variables, using `lookup_env` for names missing from `vars`.
+    pub(crate) fn interpolate_variables_with(
+        &mut self,
+        lookup_env: impl Fn(&str) -> Option<String>,
+    ) -> crate::error::Result<()> {
+        let mut value = serde_json::to_value(&*self).map_err(|e| {
+            crate::error::IronShieldError::from(format!("Failed to serialize config: {e}"))
+        })?;
+        let lookup = |name: &str| self.vars.get(name).cloned().or_else(|| lookup_env(name));
+        let mut errors = Vec::new();
+        let mut sources = self.variable_sources.clone();
+        let mut expanded_any = false;
+        if let serde_json::Value::Object(fields) = &mut value {
+            for (key, field) in fields
+                .iter_mut()
+                .filter(|(key, _)| !matches!(key.as_str(), "vars" | "include"))
+            {
+                visit_strings(field, &mut key.clone(), &mut |path, current| {
+                    if !crate::variables::has_variables(current) {
+                        return;
+                    }
+                    match crate::variables::expand(current, lookup) {
+                        Ok(expanded) => {
+                            expanded_any = true;
+                            sources.insert(
+                                path.to_string(),
+                                VariableSource {
+                                    template: std::mem::replace(current, expanded.clone()),
+                                    expanded,
+                                },
+                            );
+                        }
+                        Err(message) => errors.push(ValidationError {
+                            field: path.to_string(),
+                            message,
+                        }),
+                    }
+                });
+            }
+        }
+
+        if !errors.is_empty() {
+            return Err(crate::error::IronShieldError::ConfigInvalid(errors));
+        }
+        if expanded_any {
+            *self = self.with_values(value)?;
+        }
+        self.variable_sources = sources;
+        Ok(())
+    }
+
+    /// Expand `${NAME}` variables in a configuration edited through the API or settings page.
+    ///
+    /// Only `vars` are looked up, and other names are rejected as undefined, so a request
+    /// cannot have the server's environment copied into values it can read back or send
+    /// elsewhere. A value still written as the template the config file uses for the same
+    /// field, such as the URL of a site that moved up the list, keeps that template's
+    /// expansion, so variables the file takes from the environment survive the save.
+    ///
+    /// # Errors
+    ///
+    /// Returns the same errors as [`Config::interpolate_variables`].
+    pub(crate) fn interpolate_edited_variables(&mut self) -> crate::error::Result<()> {
+        let known: BTreeMap<(String, String), String> = self
+            .variable_sources
+            .iter()
+            .map(|(path, source)| {
+                (
+                    (field_of(path), source.template.clone()),
+                    source.expanded.clone(),
+                )
+            })
+            .collect();
+        let mut value = serde_json::to_value(&*self).map_err(|e| {
+            crate::error::IronShieldError::from(format!("Failed to serialize config: {e}"))
+        })?;
+        let mut kept = BTreeMap::new();
+        visit_strings(&mut value, &mut String::new(), &mut |path, current| {
+            if let Some(expanded) = known.get(&(field_of(path), current.clone())) {
+                kept.insert(
+                    path.to_string(),
+                    VariableSource {
+                        template: std::mem::replace(current, expanded.clone()),
+                        expanded: expanded.clone(),
+                    },
+                );
+            }
+        });
+
+        if !kept.is_empty() {
+            let mut sources = std::mem::take(&mut self.variable_sources);
+            *self = self.with_values(value)?;
+            sources.extend(kept);
+            self.variable_sources = sources;
+        }
+        self.interpolate_variables_with(|_| None)
+    }
+
+    /// `value` as a configuration, keeping the runtime-only state of this one.
+    fn with_values(&mut self, value: serde_json::Value) -> crate::error::Result<Config> {
+        let mut config: Config = serde_json::from_value(value).map_err(|e| {
+            crate::error::IronShieldError::from(format!("Failed to apply config variables: {e}"))
+        })?;
+        config.included_sites = std::mem::take(&mut self.included_sites);
+        config.secret_sources = std::mem::take(&mut self.secret_sources);
+        config.variable_sources = std::mem::take(&mut self.variable_sources);
+        config.override_sources = std::mem::take(&mut self.override_sources);
+        Ok(config)
+    }
+
+    /// Config fields that may hold secret references.
+    fn secret_fields_mut(&mut self) -> Vec<(String, &mut Option<String>)> {
+        let mut fields = vec![(
+            "opentelemetry_endpoint".to_string(),
+            &mut self.opentelemetry_endpoint,
+        )];
+        if let Some(influxdb) = &mut self.influxdb {
+            fields.push(("influxdb.token".to_string(), &mut influxdb.token));
+        }
+        if let Some(weather) = &mut self.weather {
+            fields.push(("weather.api_key".to_string(), &mut weather.api_key));
+        }
+        for (index, notifier) in self.notifiers.iter_mut().enumerate() {
+            fields.push((format!("notifiers[{index}].token"), notifier.token_mut()));
+        }
+        fields
+    }
+
+    /// Fingerprint of the settings that would be saved, used to detect concurrent edits.
+    ///
+    /// Two configurations have the same revision exactly when [`Config::for_persistence`]
+    /// serializes them identically, so runtime state such as uptime percentages and
+    /// resolved secrets does not change it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use iron_shield::config::Config;
+    ///
+    /// let config = Config::default();
+    /// let mut renamed = config.clone();
+    /// renamed.site_name = "Renamed".to_string();
+    ///
+    /// assert_eq!(config.revision(), Config::default().revision());
+    /// assert_ne!(config.revision(), renamed.revision());
+    /// ```
+    #[must_use]
+    pub fn revision(&self) -> String {
+        use std::hash::{Hash, Hasher};
+
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        serde_json::to_vec(&self.for_persistence())
+            .unwrap_or_default()
+            .hash(&mut hasher);
+        format!("{:016x}", hasher.finish())
+    }
+
+    /// Returns a copy suitable for writing back to the main config file or showing to users.
+    ///
+    /// Sites that came from included files are left out so they stay in (and are reloaded
+    /// from) the files that define them instead of being duplicated into the main file.
+    /// Fields still holding a resolved secret are switched back to their reference so
+    /// secrets are never written out, values still holding an expanded variable are
+    /// switched back to their template, and values still set by an `IRON_SHIELD__*`
+    /// environment override are switched back to what the file had, so an override stops
+    /// applying once its variable is removed.
+    #[must_use]
+    pub fn for_persistence(&self) -> Config {
+        let mut config = self.clone();
+
+        let sources = std::mem::take(&mut config.secret_sources);
+        for (field, value) in config.secret_fields_mut() {
+            if let Some(source) = sources.get(&field) {
+                if value.as_deref() == Some(source.resolved.as_str()) {
+                    *value = Some(source.reference.clone());
+                }
+            }
+        }
+
+        // Restored before included sites are dropped so field paths still line up, and
+        // variables before overrides, whose values were recorded before expansion
+        let sources = std::mem::take(&mut config.variable_sources);
+        let overrides = std::mem::take(&mut config.override_sources);
+        if !sources.is_empty() || !overrides.is_empty() {
+            if let Ok(mut value) = serde_json::to_value(&config) {
+                visit_strings(&mut value, &mut String::new(), &mut |path, current| {
+                    if let Some(source) = sources.get(path) {
+                        if *current == source.expanded {
+                            current.clone_from(&source.template);
+                        }
+                    }
+                });
+                // In the order applied, so a field an override created is removed whole
+                for source in &overrides {
+                    source.restore(&mut value);
+                }
+                if let Ok(mut restored) = serde_json::from_value::<Config>(value) {
+                    restored.included_sites = std::mem::take(&mut config.included_sites);
+                    config = restored;
+                }
+            }
+        }
+
+        config
+            .sites
+            .retain(|site| !self.included_sites.contains(&site.name));
+        config.included_sites.clear();
+        config
+    }
+
+    /// Directories and patterns a watcher must observe to notice included file changes.
+    ///
+    /// Each pattern is watched from its longest directory prefix without glob
+    /// metacharacters, recursively when the pattern contains `**`.
+    #[must_use]
+    pub fn include_watch_targets(
+        &self,
+        config_file_path: &Path,
+    ) -> Vec<(PathBuf, RecursiveMode, glob::Pattern)> {
+        let base_dir = include_base_dir(config_file_path);
+        self.include
+            .iter()
+            .filter_map(|pattern| {
+                let full_pattern = base_dir.join(pattern);
+                let compiled = glob::Pattern::new(&full_pattern.to_string_lossy()).ok()?;
+                let dir: PathBuf = full_pattern
+                    .components()
+                    .take_while(|component| {
+                        !component
+                            .as_os_str()
+                            .to_string_lossy()
+                            .contains(['*', '?', '['])
+                    })
+                    .collect();
+                let dir = if dir == full_pattern {
+                    dir.parent()
+                        .map_or_else(|| base_dir.clone(), Path::to_path_buf)
+                } else {
+                    dir
+                };
+                let mode = if pattern.contains("**") {
+                    RecursiveMode::Recursive
+                } else {
+                    RecursiveMode::NonRecursive
+                };
+                Some((dir, mode, compiled))
+            })
+            .collect()
+    }
+
+    /// Look up a named dashboard.
+    #[must_use]
+    pub fn dashboard(&self, name: &str) -> Option<&Dashboard> {
+        self.dashboards
+            .iter()
+            .find(|dashboard| dashboard.name == name)
+    }
+
+    /// The index of the first group listing the site called `name`.
+    #[must_use]
+    pub fn group_index(&self, name: &str) -> Option<usize> {
+        self.groups
+            .iter()
+            .position(|group| group.sites.iter().any(|site| site == name))
+    }
+
+    /// Resolve the sites shown on a dashboard, in the order the dashboard lists them.
+    ///
+    /// Names that do not match a configured site are skipped.
+    #[must_use]
+    pub fn dashboard_sites(&self, dashboard: &Dashboard) -> Vec<Site> {
+        dashboard
+            .sites
+            .iter()
+            .filter_map(|name| self.sites.iter().find(|site| site.name == *name))
+            .cloned()
+            .collect()
+    }
+}
+
+/// A single failed configuration rule
+///
+/// Displays as the field path followed by the problem, for example
+/// `sites[3].url is not a valid URL: relative URL without a base`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ValidationError {
+    /// Path of the offending field, such as `sites[3].url`
+    pub field: String,
+    /// Description of what is wrong with the field
+    pub message: String,
+}
+
+impl std::fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.field, self.message)
+    }
+}
+
+/// Write the starter configuration to a new file.
+///
+/// JSON5 files get [`STARTER_CONFIG`] verbatim, including its comments; YAML and TOML
+/// files get the same settings converted to that format, without comments.
+///
+/// # Errors
+///
+/// Returns an error if `path` already exists, so an existing configuration is never
+/// overwritten, or if the file cannot be created.
+///
+/// # Examples
+///
+/// ```
+/// use iron_shield::config::{write_starter_config, Config};
+///
+/// let dir = tempfile::tempdir().unwrap();
+/// let path = dir.path().join("config.json5");
+/// write_starter_config(&path).unwrap();
+///
+/// assert!(Config::load(&path).is_ok());
+/// assert!(write_starter_config(&path).is_err());
+/// ```
+pub fn write_starter_config(path: &Path) -> crate::error::Result<()> {
+    use std::io::Write;
+
+    let format = ConfigFormat::from_path(path);
+    let contents = match format {
+        ConfigFormat::Json5 => STARTER_CONFIG.to_string(),
+        _ => ConfigFormat::Json5
+            .parse::<Config>(STARTER_CONFIG)
+            .and_then(|config| format.serialize(&config))
+            .map_err(|e| {
+                crate::error::IronShieldError::Generic(format!(
+                    "Failed to convert starter config: {e}"
+                ))
+            })?,
+    };
+
+    let mut file = fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(path)
+        .map_err(|e| {
+            let reason = if e.kind() == std::io::ErrorKind::AlreadyExists {
+                "it already exists".to_string()
+            } else {
+                e.to_string()
+            };
+            crate::error::IronShieldError::Generic(format!(
+                "Refusing to write starter config to {}: {reason}",
+                path.display()
+            ))
+        })?;
+    file.write_all(contents.as_bytes())?;
+    Ok(())
+}
+
+/// Replace a file's contents without ever exposing a partially written file.
+///
+/// The contents are written to a temporary file in the same directory, flushed to disk,
+/// given the permissions of the file being replaced, and then renamed over it. A crash
+/// mid-write leaves the original untouched, and file watchers only ever observe the old
+/// or the new contents. Symlinks are followed so the link itself is preserved.
+///
+/// # Errors
+///
+/// Returns the underlying I/O error if the temporary file cannot be created, written,
+/// or renamed into place.
+///
+/// # Examples
+///
+/// ```
+/// use iron_shield::config::write_atomic;
+///
+/// let dir = tempfile::tempdir().unwrap();
+/// let path = dir.path().join("config.json5");
+/// write_atomic(&path, "{ site_name: \"Home\" }").unwrap();
+/// assert_eq!(std::fs::read_to_string(&path).unwrap(), "{ site_name: \"Home\" }");
+/// ```
+pub fn write_atomic(path: &Path, contents: &str) -> std::io::Result<()> {
+    use std::io::Write;
+
+    let target = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    let dir = include_base_dir(&target);
+    let mut temp_file = tempfile::Builder::new()
+        .prefix(".iron-shield-")
+        .suffix(".tmp")
+        .tempfile_in(&dir)?;
+    temp_file.write_all(contents.as_bytes())?;
+    temp_file.as_file().sync_all()?;
+
+    if let Ok(metadata) = fs::metadata(&target) {
+        temp_file
+            .as_file()
+            .set_permissions(metadata.permissions())?;
+    }
+
+    temp_file.persist(&target).map_err(|e| e.error)?;
+    Ok(())
+}
+
+/// Directory that relative `include` patterns are resolved against.
+///
+/// This is the directory containing the config file, or the config path itself when the
+/// configuration is loaded from a directory of fragments.
+pub(crate) fn include_base_dir(config_file_path: &Path) -> PathBuf {
+    if config_file_path.is_dir() {
+        return config_file_path.to_path_buf();
+    }
+    match config_file_path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+        _ => PathBuf::from("."),
+    }
+}
+
+/// Returns `true` for files in a config directory that contribute to the configuration.
+///
+/// Fragments are the visible files with a JSON5 (`.json5` or `.json`), YAML, or TOML
+/// extension; hidden files such as editor swap files and in-progress atomic writes are
+/// ignored.
+#[must_use]
+pub fn is_config_fragment(path: &Path) -> bool {
+    let visible = path
+        .file_name()
+        .is_some_and(|name| !name.to_string_lossy().starts_with('.'));
+    let extension = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(str::to_ascii_lowercase);
+    visible
+        && matches!(
+            extension.as_deref(),
+            Some("json5" | "json" | "yaml" | "yml" | "toml")
+        )
+}
+
+/// Merge every config fragment in `dir` into a single config value.
+///
+/// Fragments are read in file name order, so `10-base.json5` comes before
+/// `20-media.yaml`. Objects are merged key by key, arrays such as `sites` and
+/// `dashboards` are concatenated, and any other value in a later fragment replaces the
+/// earlier one.
+///
+/// # Errors
+///
+/// Returns an error if the directory cannot be listed or a fragment cannot be read or
+/// parsed.
+pub fn load_fragments(dir: &Path) -> crate::error::Result<serde_json::Value> {
+    let entries = fs::read_dir(dir).map_err(|e| {
+        crate::error::IronShieldError::Generic(format!(
+            "Failed to read config directory {}: {e}",
+            dir.display()
+        ))
+    })?;
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(std::result::Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file() && is_config_fragment(path))
+        .collect();
+    paths.sort();
+
+    if paths.is_empty() {
+        warn!("Config directory {} has no config files", dir.display());
+    }
+
+    let mut merged = serde_json::Value::Object(serde_json::Map::new());
+    for path in paths {
+        let contents = fs::read_to_string(&path).map_err(|e| {
+            crate::error::IronShieldError::Generic(format!(
+                "Failed to read config file {}: {e}",
+                path.display()
+            ))
+        })?;
+        let fragment: serde_json::Value = ConfigFormat::from_path(&path)
+            .parse(&contents)
+            .and_then(|mut fragment| {
+                let from_version = migrate(&mut fragment)?;
+                log_migration(&path, from_version);
+                Ok(fragment)
+            })
+            .map_err(|e| {
+                crate::error::IronShieldError::Generic(format!(
+                    "Failed to parse config file {}: {e}",
+                    path.display()
+                ))
+            })?;
+        debug!("Merging config fragment {}", path.display());
+        merge_fragment(&mut merged, fragment);
+    }
+    Ok(merged)
+}
+
+/// Note that a config file was loaded from an older format version.
+fn log_migration(path: &Path, from_version: u32) {
+    if from_version < CURRENT_CONFIG_VERSION {
+        info!(
+            "Migrated {} from config version {from_version} to {CURRENT_CONFIG_VERSION}; run `iron_shield --migrate-config` to update the file",
+            path.display()
+        );
+    }
+}
+
+/// Merge `fragment` into `base`, concatenating arrays and merging objects.
+fn merge_fragment(base: &mut serde_json::Value, fragment: serde_json::Value) {
+    match (base, fragment) {
+        (serde_json::Value::Object(base), serde_json::Value::Object(fragment)) => {
+            for (key, value) in fragment {
+                match base.get_mut(&key) {
+                    Some(existing) => merge_fragment(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (serde_json::Value::Array(base), serde_json::Value::Array(fragment)) => {
+            base.extend(fragment);
+        }
+        (base, fragment) => *base = fragment,
+    }
+}
+
+/// A field path with its list indices dropped, such as `sites[].url` for `sites[2].url`.
+fn field_of(path: &str) -> String {
+    let mut field = String::with_capacity(path.len());
+    let mut in_index = false;
+    for c in path.chars() {
+        match c {
+            '[' => {
+                in_index = true;
+                field.push_str("[]");
+            }
+            ']' => in_index = false,
+            _ if !in_index => field.push(c),
+            _ => {}
+        }
+    }
+    field
+}
+
+/// Call `visit` with the field path (such as `sites[3].url`) and contents of every string
+/// in a parsed config value. `path` is the path of `value` itself.
+fn visit_strings(
+    value: &mut serde_json::Value,
+    path: &mut String,
+    visit: &mut impl FnMut(&str, &mut String),
+) {
+    use serde_json::Value;
+
+    let parent_len = path.len();
+    match value {
+        Value::String(current) => visit(path, current),
+        Value::Array(items) => {
+            for (index, item) in items.iter_mut().enumerate() {
+                path.push_str(&format!("[{index}]"));
+                visit_strings(item, path, visit);
+                path.truncate(parent_len);
+            }
+        }
+        Value::Object(map) => {
+            for (key, item) in map.iter_mut() {
+                if !path.is_empty() {
+                    path.push('.');
+                }
+                path.push_str(key);
+                visit_strings(item, path, visit);
+                path.truncate(parent_len);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Apply environment overrides to a parsed config value and deserialize it, recording
+/// what each replaced in `override_sources`.
+fn apply_env_overrides(
+    mut value: serde_json::Value,
+    overrides: &[(Vec<String>, String)],
+) -> std::result::Result<Config, String> {
+    let mut replaced = Vec::new();
+    for (path, raw) in overrides {
+        let name = format!("{ENV_OVERRIDE_PREFIX}{}", path.join("__").to_uppercase());
+        // The outermost field the override creates, or the value it replaces
+        let len = (1..=path.len())
+            .find(|&len| value_at(&value, &path[..len]).is_none_or(serde_json::Value::is_null))
+            .unwrap_or(path.len());
+        let original = value_at(&value, &path[..len]).cloned();
+        if apply_env_override(&mut value, path, raw) {
+            info!("Applied configuration override from {name}");
+            replaced.push((path[..len].to_vec(), original));
+        } else {
+            warn!("Ignoring {name}: it does not match a configuration field");
+        }
+    }
+    let mut config: Config = serde_json::from_value(value).map_err(|e| {
+        if overrides.is_empty() {
+            e.to_string()
+        } else {
+            format!("{e} (after applying environment overrides)")
+        }
+    })?;
+
+    // Compared against the serialized config when saving, so recorded in that form
+    let serialized = serde_json::to_value(&config).map_err(|e| e.to_string())?;
+    config.override_sources = replaced
+        .into_iter()
+        .map(|(path, original)| OverrideSource {
+            overridden: value_at(&serialized, &path)
+                .cloned()
+                .unwrap_or(serde_json::Value::Null),
+            path,
+            original,
+        })
+        .collect();
+    Ok(config)
+}
+
+/// The value at `path` inside a parsed config document, if there is one.
+fn value_at<'a>(root: &'a serde_json::Value, path: &[String]) -> Option<&'a serde_json::Value> {
+    use serde_json::Value;
+
+    path.iter().try_fold(root, |current, key| match current {
+        Value::Object(map) => map.get(key),
+        Value::Array(items) => items.get(key.parse::<usize>().ok()?),
+        _ => None,
+    })
+}
+
+/// Collect `IRON_SHIELD__*` variables as lowercase config paths and raw values.
+fn env_overrides(vars: impl IntoIterator<Item = (String, String)>) -> Vec<(Vec<String>, String)> {
+    let mut overrides: Vec<(Vec<String>, String)> = vars
+        .into_iter()
+        .filter_map(|(name, value)| {
+            let path: Vec<String> = name
+                .strip_prefix(ENV_OVERRIDE_PREFIX)?
+                .split("__")
+                .map(str::to_ascii_lowercase)
+                .collect();
+            (!path.iter().any(String::is_empty)).then_some((path, value))
+        })
+        .collect();
+    // Apply in a stable order so parent values are set before nested ones
+    overrides.sort();
+    overrides
+}
+
+/// Set the value at `path` inside a parsed config document.
+///
+/// Values replacing an existing string are kept as strings; anything else is parsed as
+/// JSON when possible (`true`, `30`, `["a","b"]`) and falls back to a plain string.
+/// Returns `false` when the path runs through an array index that does not exist or
+/// through a scalar value.
+fn apply_env_override(root: &mut serde_json::Value, path: &[String], raw: &str) -> bool {
+    use serde_json::Value;
+
+    let Some((last, parents)) = path.split_last() else {
+        return false;
+    };
+    let mut current = root;
+    for key in parents {
+        if current.is_null() {
+            *current = Value::Object(serde_json::Map::new());
+        }
+        current = match current {
+            Value::Object(map) => map
+                .entry(key.clone())
+                .or_insert_with(|| Value::Object(serde_json::Map::new())),
+            Value::Array(items) => match key.parse::<usize>().ok().and_then(|i| items.get_mut(i)) {
+                Some(item) => item,
+                None => return false,
+            },
+            _ => return false,
+        };
+    }
+
+    let override_value = |existing: Option<&Value>| match existing {
+        Some(Value::String(_)) => Value::String(raw.to_string()),
+        _ => serde_json::from_str(raw).unwrap_or_else(|_| Value::String(raw.to_string())),
+    };
+    if current.is_null() {
+        *current = Value::Object(serde_json::Map::new());
+    }
+    match current {
+        Value::Object(map) => {
+            let value = override_value(map.get(last));
+            map.insert(last.clone(), value);
+            true
+        }
+        Value::Array(items) => match last.parse::<usize>().ok().and_then(|i| items.get_mut(i)) {
+            Some(item) => {
+                *item = override_value(Some(item));
+                true
+            }
+            None => false,
+        },
+        _ => false,
+    }
+}
+
+/// File formats the configuration can be written in
+///
+/// The format is chosen from the config file extension: `.yaml`/`.yml` is YAML, `.toml` is
+/// TOML, and anything else (including the default `config.json5`) is JSON5. All formats share
+/// the same field names and defaults.
+///
+/// # Examples
+///
+/// ```
+/// use iron_shield::config::{Config, ConfigFormat};
+/// use std::path::Path;
+///
+/// let format = ConfigFormat::from_path(Path::new("config.yaml"));
+/// assert_eq!(format, ConfigFormat::Yaml);
+///
+/// let config: Config = format.parse("site_name: Homelab\nsites: []\n").unwrap();
+/// assert_eq!(config.site_name, "Homelab");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigFormat {
+    /// JSON5, the default format
+    Json5,
+    /// YAML (`.yaml` or `.yml`)
+    Yaml,
+    /// TOML (`.toml`)
+    Toml,
+}
+
+impl ConfigFormat {
+    /// Detect the format from a config file's extension.
+    #[must_use]
+    pub fn from_path(path: &Path) -> Self {
+        match path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .map(str::to_ascii_lowercase)
+            .as_deref()
+        {
+            Some("yaml" | "yml") => ConfigFormat::Yaml,
+            Some("toml") => ConfigFormat::Toml,
+            _ => ConfigFormat::Json5,
+        }
+    }
+
+    /// Deserialize a value from text in this format.
+    ///
+    /// # Errors
+    ///
+    /// Returns a message describing the syntax or type error reported by the parser.
+    pub fn parse<T: DeserializeOwned>(self, contents: &str) -> std::result::Result<T, String> {
+        match self {
+            ConfigFormat::Json5 => json5::from_str(contents).map_err(|e| e.to_string()),
+            ConfigFormat::Yaml => serde_yaml::from_str(contents).map_err(|e| e.to_string()),
+            ConfigFormat::Toml => toml::from_str(contents).map_err(|e| e.to_string()),
+        }
+    }
+
+    /// Serialize a value as text in this format.
+    ///
+    /// # Errors
+    ///
+    /// Returns a message describing why the value could not be represented in this format.
+    pub fn serialize<T: Serialize>(self, value: &T) -> std::result::Result<String, String> {
+        match self {
+            ConfigFormat::Json5 => json5::to_string(value).map_err(|e| e.to_string()),
+            ConfigFormat::Yaml => serde_yaml::to_string(value).map_err(|e| e.to_string()),
+            ConfigFormat::Toml => toml::to_string_pretty(value).map_err(|e| e.to_string()),
+        }
+    }
+}
+
+/// Provides a default site name if not specified in config
+///
+/// This function returns the default site name used when the configuration doesn't
+/// specify one. It's used by the serde `default` attribute on the `site_name` field.
+///
+/// # Returns
+///
+/// Returns the default site name as a String: "Iron Shield Dashboard"
+fn default_site_name() -> String {
+    "Iron Shield Dashboard".to_string()
+}
+
+/// Clock format options
+///
+/// Defines the format in which to display the time on the dashboard.
+/// The enum supports serialization and deserialization for configuration persistence.
+///
+/// # Variants
+///
+/// * `None` - No clock is displayed on the dashboard
+/// * `Hour24` - Time is displayed in 24-hour format (e.g., 13:00)
+/// * `Hour12` - Time is displayed in 12-hour format with AM/PM (e.g., 1:00 PM)
+///
+/// # Examples
+///
+/// ```
 /// use iron_shield::config::Clock;
 ///
 /// let clock_format = Clock::Hour24;
@@ -255,6 +2631,256 @@ pub enum Clock {
     Hour12,
 }
 
+/// A labelled clock in another time zone, shown in the dashboard's world-clock row
+///
+/// Clocks use the format of the main `clock`, or 24-hour when it is `None`.
+///
+/// # Examples
+///
+/// ```
+/// use iron_shield::config::Config;
+///
+/// let config: Config = json5::from_str(
+///     r#"{ clocks: [{ label: "NYC", timezone: "America/New_York" }] }"#,
+/// )
+/// .unwrap();
+/// assert_eq!(config.clocks[0].label, "NYC");
+/// assert_eq!(config.clocks[0].tz(), chrono_tz::America::New_York);
+/// ```
+#[derive(Debug, Deserialize, Serialize, PartialEq, Eq, Clone)]
+pub struct WorldClock {
+    /// Name shown next to the time, such as a city
+    pub label: String,
+    /// IANA time zone name the clock is shown in
+    pub timezone: String,
+}
+
+impl WorldClock {
+    /// The clock's time zone, falling back to UTC if `timezone` is invalid.
+    #[must_use]
+    pub fn tz(&self) -> Tz {
+        self.timezone.parse().unwrap_or(Tz::UTC)
+    }
+}
+
+/// Color scheme of the dashboard
+///
+/// `auto` follows the browser's `prefers-color-scheme` setting, so a display switches to
+/// the dark scheme along with its operating system at night. Visitors can pick a
+/// different scheme for their own browser in the settings modal, which is remembered in
+/// a cookie and takes precedence over the configured one.
+///
+/// # Examples
+///
+/// ```
+/// use iron_shield::config::Theme;
+///
+/// assert_eq!(Theme::default(), Theme::Auto);
+/// assert_eq!("light".parse::<Theme>(), Ok(Theme::Light));
+/// assert_eq!(Theme::Dark.to_string(), "dark");
+/// ```
+#[derive(Debug, Default, Deserialize, Serialize, PartialEq, Eq, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum Theme {
+    /// Follow the browser's preferred color scheme
+    #[default]
+    Auto,
+    /// Light text on dark backgrounds
+    Dark,
+    /// Dark text on light backgrounds
+    Light,
+}
+
+impl std::fmt::Display for Theme {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Theme::Auto => "auto",
+            Theme::Dark => "dark",
+            Theme::Light => "light",
+        })
+    }
+}
+
+impl std::str::FromStr for Theme {
+    type Err = String;
+
+    fn from_str(theme: &str) -> std::result::Result<Self, Self::Err> {
+        match theme.trim().to_ascii_lowercase().as_str() {
+            "auto" => Ok(Theme::Auto),
+            "dark" => Ok(Theme::Dark),
+            "light" => Ok(Theme::Light),
+            _ => Err(format!(
+                "\"{theme}\" is not a theme; use \"auto\", \"dark\" or \"light\""
+            )),
+        }
+    }
+}
+
+/// How the sites are laid out on the dashboard
+///
+/// - `grid`: cards with tags, uptime history bars, and a response-time sparkline
+/// - `list`: one dense row per site with its tags and status
+/// - `compact`: small tiles with only the name and a status badge, for phones and
+///   wall-mounted kiosks
+///
+/// # Examples
+///
+/// ```
+/// use iron_shield::config::Layout;
+///
+/// assert_eq!(Layout::default(), Layout::Grid);
+/// assert_eq!("compact".parse::<Layout>(), Ok(Layout::Compact));
+/// assert_eq!(Layout::List.to_string(), "list");
+/// ```
+#[derive(Debug, Default, Deserialize, Serialize, PartialEq, Eq, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum Layout {
+    /// Cards with the full uptime details
+    #[default]
+    Grid,
+    /// One dense row per site
+    List,
+    /// Small tiles with only the name and status
+    Compact,
+}
+
+impl std::fmt::Display for Layout {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Layout::Grid => "grid",
+            Layout::List => "list",
+            Layout::Compact => "compact",
+        })
+    }
+}
+
+impl std::str::FromStr for Layout {
+    type Err = String;
+
+    fn from_str(layout: &str) -> std::result::Result<Self, Self::Err> {
+        match layout.trim().to_ascii_lowercase().as_str() {
+            "grid" => Ok(Layout::Grid),
+            "list" => Ok(Layout::List),
+            "compact" => Ok(Layout::Compact),
+            _ => Err(format!(
+                "\"{layout}\" is not a layout; use \"grid\", \"list\" or \"compact\""
+            )),
+        }
+    }
+}
+
+/// Order of the sites within each group and category on the dashboard
+///
+/// Except for `config`, sites with the same value are ordered by name, and the
+/// dashboard reorders the sites as uptime updates arrive.
+///
+/// - `config`: the order of the config file and the sites' `order` values
+/// - `name`: alphabetical, ignoring case
+/// - `status`: down sites first, then sites not checked yet, then up, then paused ones
+/// - `uptime`: lowest uptime percentage first
+/// - `response_time`: slowest latest response first, after sites without a response
+///
+/// # Examples
+///
+/// ```
+/// use iron_shield::config::SortBy;
+///
+/// assert_eq!(SortBy::default(), SortBy::Config);
+/// assert_eq!("response_time".parse::<SortBy>(), Ok(SortBy::ResponseTime));
+/// assert_eq!(SortBy::Status.to_string(), "status");
+/// ```
+#[derive(Debug, Default, Deserialize, Serialize, PartialEq, Eq, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum SortBy {
+    /// The order of the config file
+    #[default]
+    Config,
+    /// Alphabetical by name
+    Name,
+    /// Down sites first
+    Status,
+    /// Lowest uptime percentage first
+    Uptime,
+    /// Slowest latest response first
+    ResponseTime,
+}
+
+impl std::fmt::Display for SortBy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            SortBy::Config => "config",
+            SortBy::Name => "name",
+            SortBy::Status => "status",
+            SortBy::Uptime => "uptime",
+            SortBy::ResponseTime => "response_time",
+        })
+    }
+}
+
+impl std::str::FromStr for SortBy {
+    type Err = String;
+
+    fn from_str(sort_by: &str) -> std::result::Result<Self, Self::Err> {
+        match sort_by.trim().to_ascii_lowercase().as_str() {
+            "config" => Ok(SortBy::Config),
+            "name" => Ok(SortBy::Name),
+            "status" => Ok(SortBy::Status),
+            "uptime" => Ok(SortBy::Uptime),
+            "response_time" => Ok(SortBy::ResponseTime),
+            _ => Err(format!(
+                "\"{sort_by}\" is not a sort order; use \"config\", \"name\", \"status\", \"uptime\" or \"response_time\""
+            )),
+        }
+    }
+}
+
+/// Protocol telemetry is exported to the OTLP collector with
+///
+/// - `http`: OTLP/HTTP with protobuf bodies, usually on port 4318
+/// - `grpc`: OTLP/gRPC, usually on port 4317
+///
+/// # Examples
+///
+/// ```
+/// use iron_shield::config::TelemetryTransport;
+///
+/// assert_eq!(TelemetryTransport::default(), TelemetryTransport::Http);
+/// assert_eq!("gRPC".parse::<TelemetryTransport>(), Ok(TelemetryTransport::Grpc));
+/// assert_eq!(TelemetryTransport::Grpc.to_string(), "grpc");
+/// ```
+#[derive(Debug, Default, Deserialize, Serialize, PartialEq, Eq, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum TelemetryTransport {
+    /// OTLP/HTTP
+    #[default]
+    Http,
+    /// OTLP/gRPC
+    Grpc,
+}
+
+impl std::fmt::Display for TelemetryTransport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            TelemetryTransport::Http => "http",
+            TelemetryTransport::Grpc => "grpc",
+        })
+    }
+}
+
+impl std::str::FromStr for TelemetryTransport {
+    type Err = String;
+
+    fn from_str(transport: &str) -> std::result::Result<Self, Self::Err> {
+        match transport.trim().to_ascii_lowercase().as_str() {
+            "http" => Ok(TelemetryTransport::Http),
+            "grpc" => Ok(TelemetryTransport::Grpc),
+            _ => Err(format!(
+                "\"{transport}\" is not a telemetry transport; use \"http\" or \"grpc\""
+            )),
+        }
+    }
+}
+
 impl std::fmt::Display for Clock {
     /// Formats the Clock enum to its string representation
     ///
@@ -272,21 +2898,98 @@ impl std::fmt::Display for Clock {
     ///
     /// Returns `std::fmt::Result` indicating success or failure of the formatting operation
     ///
-    /// # Examples
+    /// # Examples
+    ///
+    /// ```
+    /// use iron_shield::config::Clock;
+    ///
+    /// assert_eq!(Clock::Hour24.to_string(), "24hour");
+    /// assert_eq!(Clock::Hour12.to_string(), "12hour");
+    /// assert_eq!(Clock::None.to_string(), "none");
+    /// ```
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Clock::Hour24 => f.write_str("24hour"),
+            Clock::Hour12 => f.write_str("12hour"),
+            Clock::None => f.write_str("none"),
+        }
+    }
+}
+
+/// The running configuration, shared by the config watcher and every request handler
+///
+/// Readers take a snapshot with [`SharedConfig::load`], which never blocks and cannot
+/// fail, and keep using it even if the configuration is replaced meanwhile. Changes go
+/// through [`SharedConfig::update`] or [`SharedConfig::store`], which run one at a time
+/// so a read-modify-write cannot lose a concurrent change.
+///
+/// # Examples
+///
+/// ```
+/// use iron_shield::config::{Config, SharedConfig};
+///
+/// let shared = SharedConfig::new(Config::default());
+/// let before = shared.load();
+///
+/// shared
+///     .update(|config| {
+///         config.site_name = "Homelab".to_string();
+///         Ok(())
+///     })
+///     .unwrap();
+///
+/// assert_eq!(shared.load().site_name, "Homelab");
+/// assert_eq!(before.site_name, "Iron Shield Dashboard");
+/// ```
+#[derive(Debug)]
+pub struct SharedConfig {
+    current: ArcSwap<Config>,
+    /// Held while a change is made; it guards no data, so a panicking writer leaves
+    /// nothing inconsistent behind
+    writer: Mutex<()>,
+}
+
+impl SharedConfig {
+    /// Share `config` as the running configuration.
+    #[must_use]
+    pub fn new(config: Config) -> Self {
+        Self {
+            current: ArcSwap::from_pointee(config),
+            writer: Mutex::new(()),
+        }
+    }
+
+    /// A snapshot of the running configuration
+    #[must_use]
+    pub fn load(&self) -> Arc<Config> {
+        self.current.load_full()
+    }
+
+    /// Replace the running configuration with `config`.
+    pub fn store(&self, config: Config) {
+        let _writer = self.writer.lock();
+        self.current.store(Arc::new(config));
+    }
+
+    /// Apply `edit` to a copy of the running configuration and make the copy the running
+    /// configuration, returning it with what `edit` returned.
+    ///
+    /// Other changes wait until `edit` returns, so it sees the latest configuration and
+    /// may do work that has to happen before the change is visible, such as saving it.
     ///
-    /// ```
-    /// use iron_shield::config::Clock;
+    /// # Errors
     ///
-    /// assert_eq!(Clock::Hour24.to_string(), "24hour");
-    /// assert_eq!(Clock::Hour12.to_string(), "12hour");
-    /// assert_eq!(Clock::None.to_string(), "none");
-    /// ```
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            Clock::Hour24 => f.write_str("24hour"),
-            Clock::Hour12 => f.write_str("12hour"),
-            Clock::None => f.write_str("none"),
-        }
+    /// Returns the error of `edit`, leaving the running configuration unchanged.
+    pub fn update<T>(
+        &self,
+        edit: impl FnOnce(&mut Config) -> crate::error::Result<T>,
+    ) -> crate::error::Result<(Arc<Config>, T)> {
+        let _writer = self.writer.lock();
+        let mut config = Config::clone(&self.current.load());
+        let edited = edit(&mut config)?;
+        let config = Arc::new(config);
+        self.current.store(Arc::clone(&config));
+        Ok((config, edited))
     }
 }
 
@@ -296,13 +2999,19 @@ impl std::fmt::Display for Clock {
 /// when changes are detected. It uses Rust's `notify` crate to monitor the filesystem
 /// for changes to the configuration file.
 ///
-/// The `ConfigWatcher` maintains an Arc<`RwLock`<Config>> for thread-safe access to the
+/// The `ConfigWatcher` maintains a [`SharedConfig`] for thread-safe access to the
 /// configuration from multiple parts of the application. When the config file changes,
-/// it automatically reloads the configuration in the background.
+/// it automatically reloads the configuration in the background. When the configuration
+/// is a directory of fragments, adding, changing, or removing a fragment reloads it.
+///
+/// A reload that fails to parse or validate is rejected: the last valid configuration
+/// stays active and the error is kept in `config_error` until a later reload succeeds, so
+/// the dashboard can show that the file on disk is not the one in use.
 ///
 /// # Fields
 ///
-/// * `config` - The configuration wrapped in Arc<`RwLock`<>> for thread-safe access
+/// * `config` - The running configuration, shared for thread-safe access
+/// * `config_error` - Why the most recent reload was rejected, if it was
 /// * `_watcher` - The file watcher that monitors the config file for changes (kept to prevent dropping)
 ///
 /// # Examples
@@ -313,12 +3022,14 @@ impl std::fmt::Display for Clock {
 ///
 /// // This would work with a real config file in practice
 /// // let config_path = PathBuf::from("config.json5");
-/// // let config_watcher = ConfigWatcher::new(&config_path).unwrap();
+/// // let config_watcher = ConfigWatcher::new(&config_path, |_| {}).unwrap();
 /// // let config = config_watcher.get_config();
 /// ```
 pub struct ConfigWatcher {
-    /// The actual configuration wrapped in `RwLock` for interior mutability
-    pub config: Arc<RwLock<Config>>,
+    /// The running configuration
+    pub config: Arc<SharedConfig>,
+    /// Why the most recent reload was rejected; `None` once the file loads again
+    pub config_error: Arc<RwLock<Option<String>>>,
     /// The file system watcher that automatically reloads config on changes
     /// Kept as a field to ensure it stays alive during the lifetime of `ConfigWatcher`
     _watcher: notify::RecommendedWatcher, // Keep watcher alive via ownership
@@ -329,14 +3040,19 @@ impl ConfigWatcher {
     ///
     /// This function loads the configuration from the specified file and starts a file watcher
     /// to automatically reload the configuration when changes are detected. The configuration
-    /// is loaded into an `RwLock` to allow safe concurrent access from multiple threads.
+    /// is loaded into a [`SharedConfig`] to allow safe concurrent access from multiple threads.
     ///
     /// The function spawns an asynchronous task that listens for file change events and
-    /// reloads the configuration when the file is modified, created, or removed.
+    /// reloads the configuration when the file is modified, created, or removed. Files
+    /// matching the `include` patterns present at startup are watched as well; changing
+    /// the patterns themselves takes effect after a restart.
     ///
     /// # Arguments
     ///
     /// * `config_path` - The path to the configuration file to watch and load
+    /// * `on_reload` - Called after each reload attempt with `Ok(())` once the new
+    ///   configuration has been swapped in, or with the error that got it rejected; the
+    ///   server uses it to tell open dashboards about the change
     ///
     /// # Returns
     ///
@@ -357,17 +3073,36 @@ impl ConfigWatcher {
     ///
     /// // This would work with a real config file in practice
     /// // let config_path = PathBuf::from("config.json5");
-    /// // let config_watcher = ConfigWatcher::new(&config_path).unwrap();
+    /// // let config_watcher = ConfigWatcher::new(&config_path, |_| {}).unwrap();
     /// ```
-    pub fn new(config_path: &PathBuf) -> crate::error::Result<Self> {
+    pub fn new(
+        config_path: &PathBuf,
+        on_reload: impl Fn(std::result::Result<(), &crate::error::IronShieldError>) + Send + 'static,
+    ) -> crate::error::Result<Self> {
         // Load initial configuration
         let config = Config::load(config_path)?;
-        let config_rwlock = Arc::new(RwLock::new(config));
+        let include_targets = config.include_watch_targets(config_path);
+        let include_patterns: Vec<glob::Pattern> = include_targets
+            .iter()
+            .map(|(_, _, pattern)| pattern.clone())
+            .collect();
+        let shared_config = Arc::new(SharedConfig::new(config));
+        let config_error = Arc::new(RwLock::new(None));
 
         // Create the config watcher
-        let watcher_config = config_rwlock.clone();
+        let watcher_config = shared_config.clone();
+        let watcher_config_error = config_error.clone();
         let (tx, rx) = mpsc::unbounded_channel();
-        let config_path_for_watcher = config_path.clone();
+        // Editors and `write_atomic` replace the file by renaming over it, which would end
+        // a watch on the file itself, so the containing directory is watched instead
+        // When the configuration is a directory, any fragment added to or removed from it
+        // triggers a reload
+        let config_dir = include_base_dir(config_path);
+        let watch_fragments = config_path.is_dir();
+        let config_path_for_watcher = config_path
+            .file_name()
+            .map_or_else(|| config_path.clone(), |name| config_dir.join(name));
+        let fragment_dir = config_dir.clone();
 
         let mut watcher =
             recommended_watcher(move |res: std::result::Result<Event, notify::Error>| {
@@ -376,7 +3111,17 @@ impl ConfigWatcher {
                         match event.kind {
                             EventKind::Modify(_) | EventKind::Create(_) | EventKind::Remove(_) => {
                                 for path in event.paths {
-                                    if path == config_path_for_watcher {
+                                    let is_config = if watch_fragments {
+                                        path.parent() == Some(fragment_dir.as_path())
+                                            && is_config_fragment(&path)
+                                    } else {
+                                        path == config_path_for_watcher
+                                    };
+                                    if is_config
+                                        || include_patterns
+                                            .iter()
+                                            .any(|pattern| pattern.matches_path(&path))
+                                    {
                                         debug!("Configuration file change detected: {:?}", path);
                                         if tx.send(()).is_err() {
                                             error!("Failed to send config reload signal");
@@ -391,150 +3136,1119 @@ impl ConfigWatcher {
                     Err(e) => error!("Watch error: {:?}", e),
                 }
             })
-            .map_err(|e| {
-                crate::error::IronShieldError::Generic(format!(
-                    "Failed to create file watcher: {e}"
-                ))
-            })?;
+            .map_err(crate::error::IronShieldError::Watcher)?;
 
-        // Add the config file to the watcher
+        // Add the config file's directory to the watcher
         watcher
-            .watch(config_path, RecursiveMode::NonRecursive)
-            .map_err(|e| {
-                crate::error::IronShieldError::Generic(format!("Failed to watch config file: {e}"))
-            })?;
+            .watch(&config_dir, RecursiveMode::NonRecursive)
+            .map_err(crate::error::IronShieldError::Watcher)?;
+
+        // Watch the directories of included files so new and changed files are merged
+        for (dir, mode, pattern) in &include_targets {
+            match watcher.watch(dir, *mode) {
+                Ok(()) => info!("Watching included config files matching {pattern}"),
+                Err(e) => warn!(
+                    "Failed to watch include directory {} (changes to {pattern} need a restart): {e}",
+                    dir.display()
+                ),
+            }
+        }
+
+        info!("Started config file watcher for: {}", config_path.display());
+
+        // Spawn a task to handle config reloads
+        tokio::spawn({
+            let config_inner = watcher_config;
+            let config_error_inner = watcher_config_error;
+            let mut reload_rx = rx;
+            let reload_config_path = config_path.clone(); // Clone for reload task
+            async move {
+                loop {
+                    if reload_rx.recv().await.is_some() {
+                        match reload_config(&reload_config_path, &config_inner, &config_error_inner)
+                        {
+                            Ok(()) => on_reload(Ok(())),
+                            Err(e) => {
+                                error!(
+                                    "Rejected reloaded configuration from {}; keeping the last valid configuration active: {e}",
+                                    reload_config_path.display()
+                                );
+                                on_reload(Err(&e));
+                            }
+                        }
+                    }
+                }
+            }
+        });
+
+        Ok(ConfigWatcher {
+            config: shared_config,
+            config_error,
+            _watcher: watcher,
+        })
+    }
+
+    /// Get a clone of the shared reload error, `None` while the file on disk is in use.
+    #[must_use]
+    pub fn get_config_error(&self) -> Arc<RwLock<Option<String>>> {
+        self.config_error.clone()
+    }
+
+    /// Get a clone of the `Arc<SharedConfig>` for sharing with other components
+    ///
+    /// This method provides access to the shared configuration, allowing multiple parts
+    /// of the application to read it concurrently with [`SharedConfig::load`] (though
+    /// modifications should typically go through the `ConfigWatcher` mechanism).
+    ///
+    /// # Returns
+    ///
+    /// Returns an `Arc<SharedConfig>` that can be shared across threads safely.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use iron_shield::config::ConfigWatcher;
+    /// use std::path::PathBuf;
+    ///
+    /// // Example of how to use the returned config
+    /// // let config_path = PathBuf::from("config.json5");
+    /// // let config_watcher = ConfigWatcher::new(&config_path, |_| {}).unwrap();
+    /// // let shared_config = config_watcher.get_config();
+    /// //
+    /// // println!("Site name: {}", shared_config.load().site_name);
+    /// ```
+    #[must_use]
+    pub fn get_config(&self) -> Arc<SharedConfig> {
+        self.config.clone()
+    }
+}
+
+/// Load `config_path` and swap it into `config` if it is valid.
+///
+/// When loading fails, `config` is left untouched and the error message is stored in
+/// `config_error`; a successful reload clears it.
+fn reload_config(
+    config_path: &PathBuf,
+    config: &SharedConfig,
+    config_error: &RwLock<Option<String>>,
+) -> crate::error::Result<()> {
+    let result = Config::load(config_path).map(|new_config| {
+        let number_of_sites = new_config.sites.len();
+        info!("Reloading configuration with {number_of_sites} sites");
+        config.store(new_config);
+        info!("Configuration updated successfully");
+    });
+
+    *config_error.write() = result.as_ref().err().map(ToString::to_string);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    #[test]
+    fn default_site_name_returns_expected_value() {
+        assert_eq!(default_site_name(), "Iron Shield Dashboard");
+    }
+
+    #[test]
+    fn clock_display_formats_all_variants() {
+        assert_eq!(Clock::Hour24.to_string(), "24hour");
+        assert_eq!(Clock::Hour12.to_string(), "12hour");
+        assert_eq!(Clock::None.to_string(), "none");
+        assert_eq!(Clock::default(), Clock::None);
+    }
+
+    #[test]
+    fn config_load_parses_complete_configuration() {
+        let mut temp_file =
+            NamedTempFile::new().expect("Failed to create temporary config file for test");
+        write!(
+            temp_file,
+            r#"{{
+                site_name: "Custom Site",
+                clock: "Hour12",
+                sites: [
+                    {{
+                        name: "Docs",
+                        url: "https://example.com",
+                        category: "Reference",
+                        tags: ["docs", "reference"],
+                    }},
+                ],
+            }}"#
+        )
+        .expect("Failed to write configuration contents");
+        temp_file
+            .flush()
+            .expect("Failed to flush configuration data");
+
+        let config = Config::load(&temp_file.path().to_path_buf())
+            .expect("Expected configuration to load successfully");
+
+        assert_eq!(config.site_name, "Custom Site");
+        assert_eq!(config.clock, Clock::Hour12);
+        assert_eq!(config.sites.len(), 1);
+        assert_eq!(config.sites[0].name, "Docs");
+        assert_eq!(config.sites[0].category, "Reference");
+        assert_eq!(
+            config.sites[0].tags,
+            vec!["docs".to_string(), "reference".to_string()]
+        );
+    }
+
+    #[test]
+    fn config_load_parses_dashboards_and_resolves_sites() {
+        let mut temp_file =
+            NamedTempFile::new().expect("Failed to create temporary config file for test");
+        write!(
+            temp_file,
+            r#"{{
+                sites: [
+                    {{ name: "Router", url: "http://192.168.1.1", tags: [] }},
+                    {{ name: "Jira", url: "https://jira.example.com", tags: [] }},
+                    {{ name: "NAS", url: "http://nas.local", tags: [] }},
+                ],
+                dashboards: [
+                    {{ name: "homelab", title: "Homelab", sites: ["NAS", "Router", "Missing"] }},
+                    {{ name: "work", sites: ["Jira"] }},
+                ],
+            }}"#
+        )
+        .expect("Failed to write configuration contents");
+        temp_file
+            .flush()
+            .expect("Failed to flush configuration data");
+
+        let config = Config::load(&temp_file.path().to_path_buf())
+            .expect("Expected configuration to load successfully");
+
+        assert_eq!(config.dashboards.len(), 2);
+        assert!(config.dashboard("missing").is_none());
+        let work = config.dashboard("work").expect("work dashboard");
+        assert_eq!(work.title, None);
+
+        let homelab = config.dashboard("homelab").expect("homelab dashboard");
+        assert_eq!(homelab.title.as_deref(), Some("Homelab"));
+        let names: Vec<String> = config
+            .dashboard_sites(homelab)
+            .into_iter()
+            .map(|site| site.name)
+            .collect();
+        assert_eq!(names, vec!["NAS".to_string(), "Router".to_string()]);
+    }
+
+    #[test]
+    fn config_format_detects_extension() {
+        assert_eq!(
+            ConfigFormat::from_path(Path::new("config.json5")),
+            ConfigFormat::Json5
+        );
+        assert_eq!(
+            ConfigFormat::from_path(Path::new("config.YML")),
+            ConfigFormat::Yaml
+        );
+        assert_eq!(
+            ConfigFormat::from_path(Path::new("/etc/iron/config.toml")),
+            ConfigFormat::Toml
+        );
+        assert_eq!(
+            ConfigFormat::from_path(Path::new("config")),
+            ConfigFormat::Json5
+        );
+    }
+
+    #[test]
+    fn config_load_parses_yaml_and_toml() {
+        let dir = tempfile::tempdir().expect("Failed to create temporary directory");
+        let yaml_path = dir.path().join("config.yaml");
+        fs::write(
+            &yaml_path,
+            "site_name: Homelab\nclock: Hour24\nsites:\n  - name: NAS\n    url: http://nas.local\n    tags: [storage]\n",
+        )
+        .expect("Failed to write YAML config");
+        let toml_path = dir.path().join("config.toml");
+        fs::write(
+            &toml_path,
+            "site_name = \"Work\"\n\n[[sites]]\nname = \"Jira\"\nurl = \"https://jira.example.com\"\ntags = []\n",
+        )
+        .expect("Failed to write TOML config");
+
+        let yaml = Config::load(&yaml_path).expect("YAML config should load");
+        assert_eq!(yaml.site_name, "Homelab");
+        assert_eq!(yaml.clock, Clock::Hour24);
+        assert_eq!(yaml.sites[0].name, "NAS");
+        assert_eq!(
+            yaml.sites[0].monitor_interval_secs,
+            Some(DEFAULT_MONITOR_INTERVAL_SECS)
+        );
+
+        let toml = Config::load(&toml_path).expect("TOML config should load");
+        assert_eq!(toml.site_name, "Work");
+        assert_eq!(toml.clock, Clock::None);
+        assert_eq!(toml.sites[0].url, "https://jira.example.com");
+        assert_eq!(toml.shutdown_timeout_secs, DEFAULT_SHUTDOWN_TIMEOUT_SECS);
+    }
+
+    #[test]
+    fn config_format_round_trips_config() {
+        let config = Config {
+            site_name: "Round Trip".to_string(),
+            clock: Clock::Hour12,
+            dashboards: vec![Dashboard {
+                name: "home".to_string(),
+                title: None,
+                sites: vec![],
+            }],
+            ..Config::default()
+        };
+
+        for format in [ConfigFormat::Json5, ConfigFormat::Yaml, ConfigFormat::Toml] {
+            let text = format
+                .serialize(&config)
+                .unwrap_or_else(|e| panic!("{format:?} serialization failed: {e}"));
+            let parsed: Config = format
+                .parse(&text)
+                .unwrap_or_else(|e| panic!("{format:?} parsing failed: {e}"));
+            assert_eq!(parsed.site_name, "Round Trip");
+            assert_eq!(parsed.clock, Clock::Hour12);
+            assert_eq!(parsed.dashboards, config.dashboards);
+        }
+    }
+
+    #[test]
+    fn config_load_applies_environment_overrides() {
+        let dir = tempfile::tempdir().expect("Failed to create temporary directory");
+        let path = dir.path().join("config.json5");
+        fs::write(
+            &path,
+            r#"{ site_name: "File", sites: [{ name: "NAS", url: "http://nas.local", tags: [] }] }"#,
+        )
+        .expect("Failed to write config");
+        let vars = [
+            ("IRON_SHIELD__SITE_NAME", "42"),
+            ("IRON_SHIELD__CLOCK", "Hour12"),
+            ("IRON_SHIELD__ACCESS_LOG", "true"),
+            ("IRON_SHIELD__SHUTDOWN_TIMEOUT_SECS", "3"),
+            ("IRON_SHIELD__SITES__0__URL", "http://nas.lan"),
+            ("IRON_SHIELD__SITES__0__TAGS", r#"["storage"]"#),
+            ("IRON_SHIELD__SITES__5__URL", "http://ignored"),
+            ("OTHER__SITE_NAME", "ignored"),
+        ]
+        .map(|(name, value)| (name.to_string(), value.to_string()));
+
+        let config = Config::load_with_env(&path, vars).expect("Overrides should apply");
+
+        assert_eq!(config.site_name, "42");
+        assert_eq!(config.clock, Clock::Hour12);
+        assert!(config.access_log);
+        assert_eq!(config.shutdown_timeout_secs, 3);
+        assert_eq!(config.sites.len(), 1);
+        assert_eq!(config.sites[0].url, "http://nas.lan");
+        assert_eq!(config.sites[0].tags, vec!["storage".to_string()]);
+    }
+
+    #[test]
+    fn saving_under_environment_overrides_keeps_the_file_values() {
+        let dir = tempfile::tempdir().expect("Failed to create temporary directory");
+        let path = dir.path().join("config.json5");
+        let file: Config = serde_json::from_value(serde_json::json!({
+            "site_name": "File",
+            "sites": [{ "name": "NAS", "url": "http://nas.local", "tags": ["storage"] }],
+        }))
+        .expect("Config should parse");
+        let written = ConfigFormat::Json5
+            .serialize(&file)
+            .expect("Config should serialize");
+        fs::write(&path, &written).expect("Failed to write config");
+        let vars = [
+            ("IRON_SHIELD__SITE_NAME", "Staging"),
+            ("IRON_SHIELD__ACCESS_LOG", "true"),
+            ("IRON_SHIELD__SITES__0__URL", "http://nas.staging"),
+            ("IRON_SHIELD__STATSD__HOST", "statsd.staging"),
+        ]
+        .map(|(name, value)| (name.to_string(), value.to_string()));
+
+        let mut config = Config::load_with_env(&path, vars).expect("Overrides should apply");
+        assert_eq!(config.site_name, "Staging");
+        assert!(config.statsd.is_some());
+
+        let saved = |config: &Config| {
+            ConfigFormat::Json5
+                .serialize(&config.for_persistence())
+                .expect("Config should serialize")
+        };
+        assert_eq!(saved(&config), written);
+
+        // Values edited after loading are saved, while the other overrides are not
+        config.site_name = "Edited".to_string();
+        let saved: Config = ConfigFormat::Json5
+            .parse(&saved(&config))
+            .expect("Saved config should parse");
+        assert_eq!(saved.site_name, "Edited");
+        assert_eq!(saved.sites[0].url, "http://nas.local");
+        assert!(!saved.access_log);
+        assert!(saved.statsd.is_none());
+    }
+
+    #[test]
+    fn config_load_expands_variables_and_persists_templates() {
+        let dir = tempfile::tempdir().expect("Failed to create temporary directory");
+        fs::write(
+            dir.path().join("extra.json5"),
+            r#"{ sites: [{ name: "NAS", url: "http://nas.${BASE_DOMAIN}", tags: [] }] }"#,
+        )
+        .expect("Failed to write include");
+        let path = dir.path().join("config.json5");
+        fs::write(
+            &path,
+            r#"{
+                vars: { BASE_DOMAIN: "home.lan" },
+                include: ["extra.json5"],
+                sites: [{ name: "Grafana", url: "https://grafana.${BASE_DOMAIN}:${GRAFANA_PORT}", tags: [] }],
+            }"#,
+        )
+        .expect("Failed to write config");
+        let vars = [("GRAFANA_PORT", "3000"), ("BASE_DOMAIN", "ignored.example")]
+            .map(|(name, value)| (name.to_string(), value.to_string()));
+
+        let mut config = Config::load_with_env(&path, vars).expect("Variables should expand");
+
+        assert_eq!(config.sites[0].url, "https://grafana.home.lan:3000");
+        assert_eq!(config.sites[1].url, "http://nas.home.lan");
+        assert_eq!(
+            config.for_persistence().sites[0].url,
+            "https://grafana.${BASE_DOMAIN}:${GRAFANA_PORT}"
+        );
+
+        // Values edited after loading are saved as they are
+        config.sites[0].url = "https://grafana.example.com".to_string();
+        assert_eq!(
+            config.for_persistence().sites[0].url,
+            "https://grafana.example.com"
+        );
+    }
+
+    #[test]
+    fn config_load_reports_undefined_variables() {
+        let dir = tempfile::tempdir().expect("Failed to create temporary directory");
+        let path = dir.path().join("config.json5");
+        fs::write(
+            &path,
+            r#"{ sites: [{ name: "Grafana", url: "https://grafana.${BASE_DOMAIN}", tags: [] }] }"#,
+        )
+        .expect("Failed to write config");
+
+        let err = Config::load_with_env(&path, Vec::new())
+            .expect_err("Undefined variables should be reported");
+
+        assert!(err
+            .to_string()
+            .contains("sites[0].url uses undefined variable BASE_DOMAIN"));
+    }
+
+    #[test]
+    fn config_load_reports_invalid_environment_override() {
+        let dir = tempfile::tempdir().expect("Failed to create temporary directory");
+        let path = dir.path().join("config.json5");
+        fs::write(&path, "{}").expect("Failed to write config");
+        let vars = [(
+            "IRON_SHIELD__SHUTDOWN_TIMEOUT_SECS".to_string(),
+            "soon".to_string(),
+        )];
+
+        let err = Config::load_with_env(&path, vars).expect_err("Override has the wrong type");
+
+        assert!(err.to_string().contains("environment overrides"));
+    }
+
+    #[test]
+    fn sites_inherit_and_override_monitoring_defaults() {
+        let mut temp_file = NamedTempFile::new().expect("Failed to create file");
+        write!(
+            temp_file,
+            r#"{{
+                version: 1,
+                monitoring: {{ interval_secs: 60, retries: 2, user_agent: "probe/1.0" }},
+                sites: [
+                    {{ name: "NAS", url: "http://nas.local", tags: [] }},
+                    {{
+                        name: "Slow",
+                        url: "http://slow.local",
+                        tags: [],
+                        monitor_interval_secs: 300,
+                        monitor_timeout_secs: 30,
+                        monitor_user_agent: "slow-probe",
+                    }},
+                ],
+            }}"#
+        )
+        .expect("Failed to write configuration contents");
+
+        let config = Config::load(&temp_file.path().to_path_buf()).expect("Config should load");
+
+        assert_eq!(config.monitoring.concurrency, DEFAULT_CHECK_CONCURRENCY);
+        assert_eq!(
+            config.sites[0].check_settings(&config.monitoring),
+            CheckSettings {
+                interval_secs: 60,
+                timeout_secs: DEFAULT_CHECK_TIMEOUT_SECS,
+                retries: 2,
+                user_agent: "probe/1.0".to_string(),
+                assertions: None,
+            }
+        );
+        assert_eq!(
+            config.sites[1].check_settings(&config.monitoring),
+            CheckSettings {
+                interval_secs: 300,
+                timeout_secs: 30,
+                retries: 2,
+                user_agent: "slow-probe".to_string(),
+                assertions: None,
+            }
+        );
+    }
+
+    #[test]
+    fn config_validate_checks_monitoring_settings() {
+        let mut config = Config {
+            monitoring: Monitoring {
+                interval_secs: 1,
+                timeout_secs: 0,
+                concurrency: 0,
+                retries: 3,
+                user_agent: "probe\n".to_string(),
+            },
+            sites: vec![category_site("nas", "")],
+            ..Config::default()
+        };
+        config.sites[0].monitor_timeout_secs = Some(0);
+        config.sites[0].monitor_user_agent = Some(" ".to_string());
+
+        let Err(crate::error::IronShieldError::ConfigInvalid(errors)) = config.validate() else {
+            panic!("Expected validation errors");
+        };
+        let fields: Vec<&str> = errors.iter().map(|error| error.field.as_str()).collect();
+        assert_eq!(
+            fields,
+            vec![
+                "sites[0].monitor_timeout_secs",
+                "sites[0].monitor_user_agent",
+                "monitoring.interval_secs",
+                "monitoring.timeout_secs",
+                "monitoring.user_agent",
+                "monitoring.concurrency",
+            ]
+        );
+    }
 
-        info!("Started config file watcher for: {}", config_path.display());
+    #[test]
+    fn site_urls_may_use_schemes_with_a_registered_checker() {
+        struct Unreachable;
 
-        // Spawn a task to handle config reloads
-        tokio::spawn({
-            let config_inner = watcher_config;
-            let mut reload_rx = rx;
-            let reload_config_path = config_path.clone(); // Clone for reload task
-            async move {
-                loop {
-                    if reload_rx.recv().await.is_some() {
-                        match Config::load(&reload_config_path) {
-                            Ok(new_config) => {
-                                let number_of_sites = new_config.sites.len();
-                                info!("Reloading configuration with {number_of_sites} sites");
-
-                                {
-                                    if let Ok(mut config_guard) = config_inner.write() {
-                                        *config_guard = new_config;
-                                        info!("Configuration updated successfully");
-                                    } else {
-                                        error!("Failed to acquire config write lock");
-                                    }
-                                }
-                            }
-                            Err(e) => {
-                                error!("Failed to reload configuration: {e}");
-                            }
-                        }
-                    }
-                }
+        impl crate::checkers::Checker for Unreachable {
+            fn check<'a>(
+                &'a self,
+                _url: &'a str,
+                _settings: &'a CheckSettings,
+            ) -> futures_util::future::BoxFuture<'a, crate::checkers::CheckResult> {
+                Box::pin(async { crate::checkers::CheckResult::down("unreachable") })
             }
-        });
+        }
 
-        Ok(ConfigWatcher {
-            config: config_rwlock,
-            _watcher: watcher,
-        })
+        assert_eq!(
+            check_site_url("ssh-config-test://nas.lan:22"),
+            Err("must be an http or https URL, not ssh-config-test:".to_string())
+        );
+        crate::checkers::register("ssh-config-test", Unreachable);
+        assert_eq!(check_site_url("ssh-config-test://nas.lan:22"), Ok(()));
+        assert!(check_site_url("javascript:alert(1)").is_err());
     }
 
-    /// Get a clone of the Arc<`RwLock`<Config>> for sharing with other components
-    ///
-    /// This method provides access to the shared configuration by returning a clone
-    /// of the Arc<wrapped around the `RwLock`<Config>>. This allows multiple parts of
-    /// the application to safely access and read the configuration concurrently.
-    ///
-    /// Since the return value is wrapped in Arc and `RwLock`, callers can use
-    /// `read().unwrap()` to read the config or `write().unwrap()` to modify it
-    /// (though modifications should typically go through the `ConfigWatcher` mechanism).
-    ///
-    /// # Returns
-    ///
-    /// Returns an `Arc<RwLock<Config>>` that can be shared across threads safely.
-    /// The Arc allows multiple owners of the same configuration data, while the
-    /// `RwLock` ensures safe concurrent access.
-    ///
-    /// # Examples
-    ///
-    /// ```
-    /// use iron_shield::config::ConfigWatcher;
-    /// use std::path::PathBuf;
-    ///
-    /// // Example of how to use the returned config
-    /// // let config_path = PathBuf::from("config.json5");
-    /// // let config_watcher = ConfigWatcher::new(&config_path).unwrap();
-    /// // let shared_config = config_watcher.get_config();
-    /// //
-    /// // {
-    /// //     let config_guard = shared_config.read().unwrap();
-    /// //     println!("Site name: {}", config_guard.site_name);
-    /// // }
-    /// ```
-    #[must_use]
-    pub fn get_config(&self) -> Arc<RwLock<Config>> {
-        self.config.clone()
-    }
-}
+    #[test]
+    fn load_and_settings_updates_reject_the_same_non_web_urls() {
+        let dir = tempfile::tempdir().expect("Failed to create temporary directory");
+        let path = dir.path().join("config.json5");
+        fs::write(
+            &path,
+            r#"{
+                opentelemetry_endpoint: "ftp://collector.lan",
+                sites: [
+                    { name: "Script", url: "javascript:alert(1)", tags: [] },
+                    { name: "Files", url: "file:///etc/passwd", tags: [] },
+                    { name: "NAS", url: "http://nas.lan", tags: [] },
+                ],
+            }"#,
+        )
+        .expect("Failed to write config");
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use std::io::Write;
-    use tempfile::NamedTempFile;
+        let Err(crate::error::IronShieldError::ConfigInvalid(loaded)) = Config::load(&path) else {
+            panic!("Expected the file to be rejected");
+        };
+        let fields: Vec<&str> = loaded.iter().map(|error| error.field.as_str()).collect();
+        assert_eq!(
+            fields,
+            ["opentelemetry_endpoint", "sites[0].url", "sites[1].url"]
+        );
+        assert_eq!(
+            loaded[1].message,
+            "must be an http or https URL, not javascript:"
+        );
 
-    #[test]
-    fn default_site_name_returns_expected_value() {
-        assert_eq!(default_site_name(), "Iron Shield Dashboard");
+        let mut update = crate::settings::ConfigUpdate {
+            site_name: "Dashboard".to_string(),
+            clock: "none".to_string(),
+            opentelemetry_endpoint: Some("ftp://collector.lan".to_string()),
+            sites: vec![crate::settings::SiteUpdate::from(&category_site(
+                "Script", "",
+            ))],
+        };
+        update.sites[0].url = "javascript:alert(1)".to_string();
+        let Err(crate::error::IronShieldError::ConfigInvalid(saved)) = update.validate() else {
+            panic!("Expected the update to be rejected");
+        };
+        assert_eq!(saved, loaded[..2]);
     }
 
     #[test]
-    fn clock_display_formats_all_variants() {
-        assert_eq!(Clock::Hour24.to_string(), "24hour");
-        assert_eq!(Clock::Hour12.to_string(), "12hour");
-        assert_eq!(Clock::None.to_string(), "none");
-        assert_eq!(Clock::default(), Clock::None);
+    fn reload_config_keeps_last_valid_config_and_records_the_error() {
+        let dir = tempfile::tempdir().expect("Failed to create temporary directory");
+        let path = dir.path().join("config.json5");
+        fs::write(&path, r#"{ site_name: "Before" }"#).expect("Failed to write config");
+        let config = SharedConfig::new(Config::load(&path).expect("Config should load"));
+        let config_error = RwLock::new(None);
+
+        fs::write(
+            &path,
+            r#"{ site_name: "Broken", sites: [{ name: "A", url: "not a url", tags: [] }] }"#,
+        )
+        .expect("Failed to write config");
+        let Err(crate::error::IronShieldError::ConfigInvalid(errors)) =
+            reload_config(&path, &config, &config_error)
+        else {
+            panic!("Expected the reload to be rejected");
+        };
+        assert_eq!(errors[0].field, "sites[0].url");
+        assert_eq!(config.load().site_name, "Before");
+        assert!(config_error
+            .read()
+            .as_deref()
+            .is_some_and(|error| error.contains("sites[0].url")));
+
+        fs::write(&path, r#"{ site_name: "After" }"#).expect("Failed to write config");
+        reload_config(&path, &config, &config_error).expect("Reload should succeed");
+        assert_eq!(config.load().site_name, "After");
+        assert_eq!(*config_error.read(), None);
     }
 
     #[test]
-    fn config_load_parses_complete_configuration() {
-        let mut temp_file =
-            NamedTempFile::new().expect("Failed to create temporary config file for test");
+    fn config_loads_and_validates_site_assertions() {
+        let mut temp_file = NamedTempFile::new().expect("Failed to create file");
         write!(
             temp_file,
             r#"{{
-                site_name: "Custom Site",
-                clock: "Hour12",
+                version: 1,
                 sites: [
                     {{
-                        name: "Docs",
-                        url: "https://example.com",
-                        category: "Reference",
-                        tags: ["docs", "reference"],
+                        name: "API",
+                        url: "http://api.local/health",
+                        tags: [],
+                        assertions: {{
+                            status: [200, 204],
+                            json_path: [{{ path: "$.status", equals: "ok" }}],
+                            max_latency_ms: 500,
+                        }},
                     }},
                 ],
             }}"#
         )
         .expect("Failed to write configuration contents");
-        temp_file
-            .flush()
-            .expect("Failed to flush configuration data");
 
-        let config = Config::load(&temp_file.path().to_path_buf())
-            .expect("Expected configuration to load successfully");
+        let mut config = Config::load(&temp_file.path().to_path_buf()).expect("Config should load");
+        let assertions = config.sites[0]
+            .check_settings(&config.monitoring)
+            .assertions
+            .expect("Assertions should be passed to the checker");
+        assert_eq!(assertions.status, vec![200, 204]);
+        assert_eq!(assertions.max_latency_ms, Some(500));
+        assert!(assertions.needs_body());
+
+        config.sites[0].assertions = Some(crate::assertions::Assertions {
+            status: vec![1000],
+            ..assertions
+        });
+        let Err(crate::error::IronShieldError::ConfigInvalid(errors)) = config.validate() else {
+            panic!("Expected validation errors");
+        };
+        assert_eq!(errors[0].field, "sites[0].assertions.status[0]");
+    }
+
+    #[test]
+    fn config_validate_reports_every_invalid_field() {
+        let site = |name: &str, url: &str, interval: u64| Site {
+            name: name.to_string(),
+            url: url.to_string(),
+            category: String::new(),
+            tags: vec![],
+            monitor_interval_secs: Some(interval),
+            monitor_timeout_secs: None,
+            monitor_retries: None,
+            monitor_user_agent: None,
+            disabled: false,
+            icon: None,
+            order: None,
+            assertions: None,
+            uptime_percentage: 0.0,
+        };
+        let config = Config {
+            site_name: " ".to_string(),
+            timezone: "Mars/Olympus_Mons".to_string(),
+            clocks: vec![
+                WorldClock {
+                    label: "Tokyo".to_string(),
+                    timezone: "Asia/Tokyo".to_string(),
+                },
+                WorldClock {
+                    label: " ".to_string(),
+                    timezone: "Berlin".to_string(),
+                },
+            ],
+            opentelemetry_endpoint: Some("collector".to_string()),
+            sites: vec![
+                site(
+                    "Docs",
+                    "https://docs.example.com",
+                    DEFAULT_MONITOR_INTERVAL_SECS,
+                ),
+                site("Docs", "not a url", 1),
+                site("", "", DEFAULT_MONITOR_INTERVAL_SECS),
+            ],
+            dashboards: vec![
+                Dashboard {
+                    name: "work".to_string(),
+                    title: None,
+                    sites: vec![],
+                },
+                Dashboard {
+                    name: "work".to_string(),
+                    title: None,
+                    sites: vec![],
+                },
+                Dashboard {
+                    name: "home lab".to_string(),
+                    title: None,
+                    sites: vec![],
+                },
+            ],
+            ..Config::default()
+        };
+
+        let Err(crate::error::IronShieldError::ConfigInvalid(errors)) = config.validate() else {
+            panic!("Expected validation to fail with field errors");
+        };
+        let fields: Vec<&str> = errors.iter().map(|error| error.field.as_str()).collect();
 
-        assert_eq!(config.site_name, "Custom Site");
-        assert_eq!(config.clock, Clock::Hour12);
-        assert_eq!(config.sites.len(), 1);
-        assert_eq!(config.sites[0].name, "Docs");
-        assert_eq!(config.sites[0].category, "Reference");
         assert_eq!(
-            config.sites[0].tags,
-            vec!["docs".to_string(), "reference".to_string()]
+            fields,
+            vec![
+                "site_name",
+                "timezone",
+                "clocks[1].label",
+                "clocks[1].timezone",
+                "opentelemetry_endpoint",
+                "sites[1].name",
+                "sites[1].url",
+                "sites[1].monitor_interval_secs",
+                "sites[2].name",
+                "sites[2].url",
+                "dashboards[1].name",
+                "dashboards[2].name",
+            ]
+        );
+        assert_eq!(
+            errors[1].to_string(),
+            "timezone \"Mars/Olympus_Mons\" is not an IANA time zone name"
+        );
+        assert_eq!(
+            errors[5].to_string(),
+            "sites[1].name \"Docs\" duplicates sites[0].name \"Docs\""
+        );
+        assert!(errors[6]
+            .to_string()
+            .starts_with("sites[1].url is not a valid URL"));
+        assert!(Config::default().validate().is_ok());
+    }
+
+    #[test]
+    fn config_warnings_report_duplicate_urls_and_unknown_dashboard_sites() {
+        let site = |name: &str, url: &str| Site {
+            name: name.to_string(),
+            url: url.to_string(),
+            category: String::new(),
+            tags: Vec::new(),
+            monitor_interval_secs: Some(DEFAULT_MONITOR_INTERVAL_SECS),
+            monitor_timeout_secs: None,
+            monitor_retries: None,
+            monitor_user_agent: None,
+            disabled: false,
+            icon: None,
+            order: None,
+            assertions: None,
+            uptime_percentage: 0.0,
+        };
+        let config = Config {
+            sites: vec![
+                site("Router", "http://192.168.1.1"),
+                site("Router admin", "HTTP://192.168.1.1/"),
+            ],
+            dashboards: vec![Dashboard {
+                name: "home".to_string(),
+                title: None,
+                sites: vec!["Router".to_string(), "NAS".to_string()],
+            }],
+            ..Config::default()
+        };
+
+        let warnings: Vec<String> = config.warnings().iter().map(ToString::to_string).collect();
+
+        assert_eq!(
+            warnings,
+            [
+                "sites[1].url \"HTTP://192.168.1.1/\" of \"Router admin\" is also used by \"Router\" (sites[0]), so it is checked twice",
+                "dashboards[0].sites references unknown site \"NAS\"",
+            ]
+        );
+    }
+
+    #[test]
+    fn groups_are_validated_and_checked_for_unknown_or_repeated_sites() {
+        let group = |name: &str, sites: &[&str]| Group {
+            name: name.to_string(),
+            sites: sites.iter().map(ToString::to_string).collect(),
+            collapsed: false,
+        };
+        let mut config = Config {
+            sites: vec![category_site("plex", ""), category_site("nas", "")],
+            groups: vec![
+                group("Media", &["plex"]),
+                group("Storage", &["nas", "plex", "backup"]),
+            ],
+            ..Config::default()
+        };
+
+        assert!(config.validate().is_ok());
+        assert_eq!(config.group_index("plex"), Some(0));
+        assert_eq!(config.group_index("nas"), Some(1));
+        assert_eq!(config.group_index("router"), None);
+        let warnings: Vec<String> = config.warnings().iter().map(ToString::to_string).collect();
+        assert_eq!(
+            warnings,
+            [
+                "groups[1].sites lists \"plex\", which is already shown in groups[0]",
+                "groups[1].sites references unknown site \"backup\"",
+            ]
+        );
+
+        config.groups.push(group("Media", &[]));
+        config.groups.push(group(" ", &[]));
+        let Err(crate::error::IronShieldError::ConfigInvalid(errors)) = config.validate() else {
+            panic!("Expected validation errors");
+        };
+        let fields: Vec<&str> = errors.iter().map(|error| error.field.as_str()).collect();
+        assert_eq!(fields, ["groups[2].name", "groups[3].name"]);
+    }
+
+    #[test]
+    fn config_load_rejects_invalid_configuration() {
+        let dir = tempfile::tempdir().expect("Failed to create temporary directory");
+        let path = dir.path().join("config.json5");
+        fs::write(
+            &path,
+            r#"{ sites: [{ name: "NAS", url: "nas.local", tags: [] }] }"#,
+        )
+        .expect("Failed to write config");
+
+        let err = Config::load(&path).expect_err("Invalid URL should be rejected");
+
+        assert!(err.to_string().contains("sites[0].url is not a valid URL"));
+    }
+
+    #[test]
+    fn config_load_merges_included_site_files() {
+        let dir = tempfile::tempdir().expect("Failed to create temporary directory");
+        fs::create_dir(dir.path().join("sites")).expect("Failed to create sites directory");
+        fs::write(
+            dir.path().join("sites").join("b-work.yaml"),
+            "sites:\n  - name: Jira\n    url: https://jira.example.com\n    tags: []\n",
+        )
+        .expect("Failed to write YAML include");
+        fs::write(
+            dir.path().join("sites").join("a-home.json5"),
+            r#"{ sites: [{ name: "NAS", url: "http://nas.local", tags: [] }] }"#,
+        )
+        .expect("Failed to write JSON5 include");
+        let path = dir.path().join("config.json5");
+        fs::write(
+            &path,
+            r#"{
+                include: ["sites/*.json5", "sites/*.yaml", "missing/*.json5"],
+                sites: [{ name: "Router", url: "http://router.local", tags: [] }],
+            }"#,
+        )
+        .expect("Failed to write config");
+
+        let config = Config::load(&path).expect("Config with includes should load");
+
+        let names: Vec<&str> = config.sites.iter().map(|site| site.name.as_str()).collect();
+        assert_eq!(names, vec!["Router", "NAS", "Jira"]);
+        assert_eq!(config.included_sites, vec!["NAS", "Jira"]);
+
+        let persisted = config.for_persistence();
+        assert_eq!(persisted.sites.len(), 1);
+        assert_eq!(persisted.include, config.include);
+        let reparsed: Config = json5::from_str(&json5::to_string(&persisted).expect("serialize"))
+            .expect("Persisted config should parse");
+        assert_eq!(reparsed.include, config.include);
+        assert!(reparsed.included_sites.is_empty());
+
+        let targets = config.include_watch_targets(&path);
+        assert_eq!(targets.len(), 3);
+        assert_eq!(targets[0].0, dir.path().join("sites"));
+        assert!(targets[0]
+            .2
+            .matches_path(&dir.path().join("sites").join("new.json5")));
+    }
+
+    #[test]
+    fn config_load_rejects_duplicate_sites_across_includes() {
+        let dir = tempfile::tempdir().expect("Failed to create temporary directory");
+        fs::write(
+            dir.path().join("extra.json5"),
+            r#"{ sites: [{ name: "Router", url: "http://router.lan", tags: [] }] }"#,
+        )
+        .expect("Failed to write include");
+        let path = dir.path().join("config.json5");
+        fs::write(
+            &path,
+            r#"{ include: ["extra.json5"], sites: [{ name: "Router", url: "http://router.local", tags: [] }] }"#,
+        )
+        .expect("Failed to write config");
+
+        let err = Config::load(&path).expect_err("Duplicate site names should be rejected");
+
+        assert!(err
+            .to_string()
+            .contains("sites[1].name \"Router\" duplicates sites[0].name \"Router\""));
+    }
+
+    #[test]
+    fn config_secret_references_are_resolved_and_persisted_as_references() {
+        let dir = tempfile::tempdir().expect("Failed to create temporary directory");
+        let secret_path = dir.path().join("endpoint");
+        fs::write(&secret_path, "https://collector.example.com/ingest\n")
+            .expect("Failed to write secret file");
+        let reference = format!("file:{}", secret_path.display());
+        let mut config = Config {
+            opentelemetry_endpoint: Some(reference.clone()),
+            ..Config::default()
+        };
+
+        config
+            .resolve_secrets()
+            .expect("Secret reference should resolve");
+
+        assert_eq!(
+            config.opentelemetry_endpoint.as_deref(),
+            Some("https://collector.example.com/ingest")
+        );
+        assert_eq!(
+            config.for_persistence().opentelemetry_endpoint,
+            Some(reference)
+        );
+
+        // A value changed after loading is persisted as-is
+        config.opentelemetry_endpoint = Some("https://other.example.com".to_string());
+        assert_eq!(
+            config.for_persistence().opentelemetry_endpoint.as_deref(),
+            Some("https://other.example.com")
+        );
+    }
+
+    #[test]
+    fn config_load_reports_unresolvable_secret_reference() {
+        let dir = tempfile::tempdir().expect("Failed to create temporary directory");
+        let path = dir.path().join("config.json5");
+        fs::write(
+            &path,
+            r#"{ opentelemetry_endpoint: "file:/nonexistent/iron-shield-secret" }"#,
+        )
+        .expect("Failed to write config");
+
+        let err = Config::load(&path).expect_err("Missing secret file should be reported");
+
+        assert!(err
+            .to_string()
+            .contains("opentelemetry_endpoint has an unresolvable secret reference"));
+    }
+
+    #[test]
+    fn write_atomic_replaces_contents_and_preserves_permissions() {
+        let dir = tempfile::tempdir().expect("Failed to create temporary directory");
+        let path = dir.path().join("config.json5");
+        fs::write(&path, "{ old: true }").expect("Failed to write original config");
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&path, fs::Permissions::from_mode(0o640))
+                .expect("Failed to set permissions");
+        }
+
+        write_atomic(&path, "{ new: true }").expect("Atomic write should succeed");
+
+        assert_eq!(
+            fs::read_to_string(&path).expect("Failed to read config"),
+            "{ new: true }"
+        );
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&path).expect("metadata").permissions().mode();
+            assert_eq!(mode & 0o777, 0o640);
+        }
+        let leftovers: Vec<_> = fs::read_dir(dir.path())
+            .expect("Failed to list directory")
+            .filter_map(std::result::Result::ok)
+            .filter(|entry| entry.file_name() != "config.json5")
+            .collect();
+        assert!(leftovers.is_empty(), "temporary files should be cleaned up");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn write_atomic_follows_symlinks() {
+        let dir = tempfile::tempdir().expect("Failed to create temporary directory");
+        let target = dir.path().join("real.json5");
+        let link = dir.path().join("config.json5");
+        fs::write(&target, "{}").expect("Failed to write target");
+        std::os::unix::fs::symlink(&target, &link).expect("Failed to create symlink");
+
+        write_atomic(&link, "{ linked: true }").expect("Atomic write should succeed");
+
+        assert!(fs::symlink_metadata(&link)
+            .expect("metadata")
+            .file_type()
+            .is_symlink());
+        assert_eq!(
+            fs::read_to_string(&target).expect("Failed to read target"),
+            "{ linked: true }"
+        );
+    }
+
+    #[test]
+    fn config_load_merges_fragments_from_directory() {
+        let dir = tempfile::tempdir().expect("Failed to create temporary directory");
+        fs::write(
+            dir.path().join("10-base.json5"),
+            r#"{ site_name: "Base", clock: "Hour24", sites: [{ name: "Router", url: "http://192.168.1.1", tags: [] }] }"#,
+        )
+        .expect("Failed to write base fragment");
+        fs::write(
+            dir.path().join("20-media.yaml"),
+            "site_name: Homelab\nsites:\n  - name: Jellyfin\n    url: http://jellyfin.local\n    tags: []\n",
+        )
+        .expect("Failed to write yaml fragment");
+        fs::write(
+            dir.path().join("30-dashboards.toml"),
+            "[[dashboards]]\nname = \"media\"\nsites = [\"Jellyfin\"]\n",
+        )
+        .expect("Failed to write toml fragment");
+        fs::write(
+            dir.path().join(".40-draft.json5"),
+            "{ site_name: \"Draft\" }",
+        )
+        .expect("Failed to write hidden fragment");
+        fs::write(dir.path().join("README.md"), "not config").expect("Failed to write readme");
+
+        let config = Config::load_with_env(&dir.path().to_path_buf(), Vec::new())
+            .expect("Expected configuration directory to load");
+
+        assert_eq!(config.site_name, "Homelab");
+        assert_eq!(config.clock, Clock::Hour24);
+        let names: Vec<&str> = config.sites.iter().map(|site| site.name.as_str()).collect();
+        assert_eq!(names, ["Router", "Jellyfin"]);
+        assert_eq!(config.dashboards.len(), 1);
+        assert!(is_config_fragment(Path::new("conf.d/sites.yml")));
+        assert!(!is_config_fragment(Path::new("conf.d/.iron-shield-a1.tmp")));
+        assert!(!is_config_fragment(Path::new("conf.d/.hidden.json5")));
+    }
+
+    #[test]
+    fn config_load_names_invalid_fragment() {
+        let dir = tempfile::tempdir().expect("Failed to create temporary directory");
+        fs::write(dir.path().join("sites.json5"), "{ sites: [").expect("Failed to write fragment");
+
+        let err = Config::load_with_env(&dir.path().to_path_buf(), Vec::new())
+            .expect_err("Invalid fragment should fail to load");
+
+        assert!(err.to_string().contains("sites.json5"));
+    }
+
+    #[test]
+    fn config_load_migrates_legacy_configuration() {
+        let dir = tempfile::tempdir().expect("Failed to create temporary directory");
+        let path = dir.path().join("config.json5");
+        fs::write(
+            &path,
+            r#"{ clock: "24hour", sites: [{ name: "Router", url: "http://192.168.1.1" }] }"#,
+        )
+        .expect("Failed to write legacy config");
+
+        let config = Config::load_with_env(&path, Vec::new())
+            .expect("Legacy configuration should be migrated");
+
+        assert_eq!(config.version, CURRENT_CONFIG_VERSION);
+        assert_eq!(config.clock, Clock::Hour24);
+        assert!(config.sites[0].tags.is_empty());
+
+        fs::write(&path, "{ version: 99 }").expect("Failed to write future config");
+        let err = Config::load_with_env(&path, Vec::new())
+            .expect_err("Newer configuration versions should be rejected");
+        assert!(err
+            .to_string()
+            .contains("newer than the latest supported version"));
+    }
+
+    #[test]
+    fn write_starter_config_creates_loadable_files_without_overwriting() {
+        let dir = tempfile::tempdir().expect("Failed to create temporary directory");
+        let json5_path = dir.path().join("config.json5");
+        let toml_path = dir.path().join("config.toml");
+
+        write_starter_config(&json5_path).expect("Starter config should be written");
+        write_starter_config(&toml_path).expect("Starter config should convert to TOML");
+
+        assert_eq!(
+            fs::read_to_string(&json5_path).expect("Failed to read starter config"),
+            STARTER_CONFIG
+        );
+        let json5 =
+            Config::load_with_env(&json5_path, Vec::new()).expect("Starter config should be valid");
+        let toml = Config::load_with_env(&toml_path, Vec::new())
+            .expect("Converted starter config should be valid");
+        assert_eq!(json5.version, CURRENT_CONFIG_VERSION);
+        assert_eq!(json5.sites.len(), toml.sites.len());
+        assert_eq!(json5.dashboards, toml.dashboards);
+
+        fs::write(&json5_path, "{ site_name: \"Mine\" }").expect("Failed to edit config");
+        let err = write_starter_config(&json5_path).expect_err("Existing file must be kept");
+        assert!(err.to_string().contains("already exists"));
+        assert_eq!(
+            fs::read_to_string(&json5_path).expect("Failed to read config"),
+            "{ site_name: \"Mine\" }"
         );
     }
 
@@ -566,6 +4280,14 @@ mod tests {
         assert_eq!(config.clock, Clock::None);
         assert_eq!(config.sites.len(), 1);
         assert_eq!(config.sites[0].category, "");
+        assert!(!config.access_log);
+        assert_eq!(config.shutdown_timeout_secs, DEFAULT_SHUTDOWN_TIMEOUT_SECS);
+        assert_eq!(config.sse_keep_alive_secs, DEFAULT_SSE_KEEP_ALIVE_SECS);
+        assert_eq!(config.clock_drift_warn_secs, DEFAULT_CLOCK_DRIFT_WARN_SECS);
+        assert_eq!(
+            config.config_backup_retention,
+            DEFAULT_CONFIG_BACKUP_RETENTION
+        );
     }
 
     #[test]
@@ -602,8 +4324,14 @@ mod tests {
             url: "https://test.com".to_string(),
             category: "Test Category".to_string(),
             tags: vec!["test".to_string(), "example".to_string()],
-            monitor_interval_secs: DEFAULT_MONITOR_INTERVAL_SECS,
+            monitor_interval_secs: Some(DEFAULT_MONITOR_INTERVAL_SECS),
+            monitor_timeout_secs: None,
+            monitor_retries: None,
+            monitor_user_agent: None,
             disabled: false,
+            icon: None,
+            order: None,
+            assertions: None,
             uptime_percentage: 99.5,
         };
 
@@ -611,7 +4339,10 @@ mod tests {
         assert_eq!(site.url, "https://test.com");
         assert_eq!(site.category, "Test Category");
         assert_eq!(site.tags, vec!["test".to_string(), "example".to_string()]);
-        assert_eq!(site.monitor_interval_secs, DEFAULT_MONITOR_INTERVAL_SECS);
+        assert_eq!(
+            site.monitor_interval_secs,
+            Some(DEFAULT_MONITOR_INTERVAL_SECS)
+        );
         assert!(!site.disabled);
         assert!((site.uptime_percentage - 99.5).abs() < f64::EPSILON);
     }
@@ -623,13 +4354,22 @@ mod tests {
             url: "https://test.com".to_string(),
             category: String::default(), // This will use the serde default
             tags: vec![],
-            monitor_interval_secs: default_monitor_interval_secs(),
+            monitor_interval_secs: Some(default_monitor_interval_secs()),
+            monitor_timeout_secs: None,
+            monitor_retries: None,
+            monitor_user_agent: None,
             disabled: Default::default(),
+            icon: None,
+            order: None,
+            assertions: None,
             uptime_percentage: Default::default(), // This uses the serde default (0.0)
         };
 
         assert_eq!(site.category, "");
-        assert_eq!(site.monitor_interval_secs, DEFAULT_MONITOR_INTERVAL_SECS);
+        assert_eq!(
+            site.monitor_interval_secs,
+            Some(DEFAULT_MONITOR_INTERVAL_SECS)
+        );
         assert!(!site.disabled);
         assert!((site.uptime_percentage - 0.0).abs() < f64::EPSILON);
     }
@@ -641,6 +4381,7 @@ mod tests {
         assert_eq!(config.site_name, "Iron Shield Dashboard");
         assert_eq!(config.clock, Clock::None);
         assert!(config.sites.is_empty());
+        assert!(config.dashboards.is_empty());
     }
 
     #[test]
@@ -651,11 +4392,110 @@ mod tests {
             url: "https://example.com".to_string(),
             category: "Test".to_string(),
             tags: vec![],
-            monitor_interval_secs: default_monitor_interval_secs(),
+            monitor_interval_secs: Some(default_monitor_interval_secs()),
+            monitor_timeout_secs: None,
+            monitor_retries: None,
+            monitor_user_agent: None,
             disabled: Default::default(),
+            icon: None,
+            order: None,
+            assertions: None,
             uptime_percentage: Default::default(),
         };
 
         assert!((site.uptime_percentage - 0.0).abs() < f64::EPSILON);
     }
+
+    fn category_site(name: &str, category: &str) -> Site {
+        Site {
+            name: name.to_string(),
+            url: format!("https://{name}.example.com"),
+            category: category.to_string(),
+            tags: vec![],
+            monitor_interval_secs: Some(DEFAULT_MONITOR_INTERVAL_SECS),
+            monitor_timeout_secs: None,
+            monitor_retries: None,
+            monitor_user_agent: None,
+            disabled: false,
+            icon: None,
+            order: None,
+            assertions: None,
+            uptime_percentage: 0.0,
+        }
+    }
+
+    fn category(name: &str, order: Option<i32>) -> Category {
+        Category {
+            name: name.to_string(),
+            order,
+            color: None,
+            icon: None,
+        }
+    }
+
+    #[test]
+    fn validate_checks_categories_and_site_references() {
+        let mut config = Config {
+            sites: vec![
+                category_site("plex", "Media"),
+                category_site("nas", "Storage"),
+            ],
+            ..Config::default()
+        };
+        assert!(
+            config.validate().is_ok(),
+            "free-form categories are allowed"
+        );
+
+        let mut media = category("Media", None);
+        media.color = Some("blue".to_string());
+        media.icon = Some("television".to_string());
+        config.categories = vec![media, category("media", None), category(" ", None)];
+
+        let Err(crate::error::IronShieldError::ConfigInvalid(errors)) = config.validate() else {
+            panic!("Expected validation errors");
+        };
+        let fields: Vec<&str> = errors.iter().map(|error| error.field.as_str()).collect();
+        assert_eq!(
+            fields,
+            vec![
+                "sites[1].category",
+                "categories[0].color",
+                "categories[0].icon",
+                "categories[1].name",
+                "categories[2].name",
+            ]
+        );
+
+        let mut media = category("Media", Some(1));
+        media.color = Some("#e11d48".to_string());
+        media.icon = Some("media".to_string());
+        config.categories = vec![media, category("storage", None)];
+        assert!(config.validate().is_ok());
+        assert_eq!(config.category("MEDIA").and_then(|c| c.order), Some(1));
+    }
+
+    #[test]
+    fn sort_by_category_orders_groups_and_keeps_site_order() {
+        let config = Config {
+            categories: vec![
+                category("Tools", None),
+                category("Media", Some(2)),
+                category("Network", Some(1)),
+            ],
+            ..Config::default()
+        };
+        let mut sites = vec![
+            category_site("a", "Media"),
+            category_site("b", "Other"),
+            category_site("c", "Tools"),
+            category_site("d", "network"),
+            category_site("e", "Media"),
+        ];
+
+        config.sort_by_category(&mut sites);
+
+        let names: Vec<&str> = sites.iter().map(|site| site.name.as_str()).collect();
+        assert_eq!(names, vec!["d", "a", "e", "c", "b"]);
+    }
 }
@@ -0,0 +1,195 @@
+//! Template overrides
+//!
+//! The page markup is compiled into the binary by Askama. To change it without
+//! rebuilding, the config's `templates_dir` names a directory of replacement templates:
+//!
+//! ```json5
+//! {
+//!     templates_dir: "templates",
+//! }
+//! ```
+//!
+//! The path is resolved against the directory holding the config file like `custom_css`.
+//! An `index.html` in it replaces the dashboard page, both `/` and the named dashboards at
+//! `/d/{name}`; every other page, and the dashboard page while the directory has no
+//! `index.html`, keeps the compiled-in template.
+//!
+//! Overrides are rendered with MiniJinja, which uses the Jinja2 syntax rather than
+//! Askama's, so the built-in `templates/index.html` is a guide to the markup the
+//! frontend expects but cannot be copied over unchanged. Other files of the directory
+//! can be pulled in with `{% include %}` and `{% extends %}`, and output is HTML-escaped
+//! unless marked `|safe`. `index.html` is rendered with:
+//!
+//! - `config` - the configuration, with secret references rather than secrets
+//! - `page_title`, `dashboard` - title of the page and name of the dashboard (empty on `/`)
+//! - `filter` - the `tag` and `category` the sites are filtered by
+//! - `theme`, `clock_format`, `current_time` - as the built-in page uses them
+//! - `world_clocks` - `label`, `timezone`, and `time` of each extra clock
+//! - `weather` - `location`, `temperature`, `unit`, and `description`, if configured
+//! - `groups` - the configured groups with sites on the page: `name`, `collapsed`, `sites`
+//! - `categories` - the other sites by category: `name` (empty for none) and `sites`
+//! - `outages` - `name`, `since`, and `duration` of each site that is down
+//! - `config_error`, `clock_drift_notice` - warnings the built-in page shows, if any
+//! - `config_json`, `config_revision`, `asset_version`, `max_history_entries` - what the
+//!   frontend bundle reads from the built-in page
+//!
+//! Each site has the `name`, `url`, `category`, `tags`, and `disabled` from the config,
+//! the `icon_src` or bundled `icon_path` of its icon, and what its card shows: `status`
+//! (`up`, `down`, `disabled`, or `unknown`), `status_label`, `uptime_percentage`,
+//! `average_response`, `last_checked` with its `checked_label`, `sparkline` (an SVG
+//! path), and `history`, whose bars have a `status` and a `label`. `t("key")` looks up UI
+//! text in the configured language (see the `i18n` module).
+//!
+//! A template that fails to load or render is logged and the built-in page is served
+//! instead, so a typo cannot take the dashboard down. Templates are read the first time
+//! they are rendered and kept until the server restarts or `templates_dir` changes.
+//! Built with the `dev-templates` feature (as `just dev` does), they are read from disk
+//! again on every request instead, so edits show up on the next reload of the page. That
+//! only applies to these overrides: the compiled-in Askama templates, including the
+//! fallback page, still need a rebuild to change.
+
+use minijinja::Environment;
+use parking_lot::Mutex;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tracing::{debug, error};
+
+/// Name of the template replacing the dashboard page
+pub const INDEX_TEMPLATE: &str = "index.html";
+
+/// Templates loaded from the configured `templates_dir`
+#[derive(Default)]
+pub struct TemplateOverrides {
+    /// Directory the templates are loaded from, with the templates read from it so far
+    loaded: Mutex<Option<(PathBuf, Arc<Environment<'static>>)>>,
+}
+
+impl std::fmt::Debug for TemplateOverrides {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let dir = self.loaded.lock().as_ref().map(|(dir, _)| dir.clone());
+        f.debug_struct("TemplateOverrides")
+            .field("dir", &dir)
+            .finish()
+    }
+}
+
+impl TemplateOverrides {
+    /// Render the template `name` from `dir` with `context`.
+    ///
+    /// Returns `None` when `dir` has no such template, or when it cannot be parsed or
+    /// rendered, which is logged, so the caller can fall back to the built-in page.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use iron_shield::custom_templates::TemplateOverrides;
+    ///
+    /// let dir = tempfile::tempdir().unwrap();
+    /// std::fs::write(dir.path().join("index.html"), "<h1>{{ title }}</h1>").unwrap();
+    ///
+    /// let overrides = TemplateOverrides::default();
+    /// let context = minijinja::context! { title => "Homelab & co" };
+    /// assert_eq!(
+    ///     overrides.render(dir.path(), "index.html", context.clone()).as_deref(),
+    ///     Some("<h1>Homelab &amp; co</h1>")
+    /// );
+    /// assert_eq!(overrides.render(dir.path(), "kiosk.html", context), None);
+    /// ```
+    #[must_use]
+    pub fn render(&self, dir: &Path, name: &str, context: minijinja::Value) -> Option<String> {
+        let environment = self.environment(dir);
+        let template = match environment.get_template(name) {
+            Ok(template) => template,
+            Err(err) if err.kind() == minijinja::ErrorKind::TemplateNotFound => {
+                debug!("No {name} override in {}", dir.display());
+                return None;
+            }
+            Err(err) => {
+                error!(
+                    "Unable to load template override {}: {err:#}",
+                    dir.join(name).display()
+                );
+                return None;
+            }
+        };
+        template
+            .render(context)
+            .inspect_err(|err| {
+                error!(
+                    "Unable to render template override {}: {err:#}",
+                    dir.join(name).display()
+                );
+            })
+            .ok()
+    }
+
+    /// The environment loading templates from `dir`, which keeps the templates it has
+    /// read until `dir` changes, unless built with the `dev-templates` feature.
+    fn environment(&self, dir: &Path) -> Arc<Environment<'static>> {
+        if cfg!(feature = "dev-templates") {
+            return Arc::new(environment(dir));
+        }
+        let mut loaded = self.loaded.lock();
+        match &*loaded {
+            Some((loaded_dir, environment)) if loaded_dir == dir => Arc::clone(environment),
+            _ => {
+                let environment = Arc::new(environment(dir));
+                *loaded = Some((dir.to_path_buf(), Arc::clone(&environment)));
+                environment
+            }
+        }
+    }
+}
+
+/// A template environment reading templates from `dir` as they are first needed
+fn environment(dir: &Path) -> Environment<'static> {
+    let mut environment = Environment::new();
+    environment.set_loader(minijinja::path_loader(dir));
+    environment
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn templates_include_neighbours_and_fall_back_when_broken() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("header.html"), "<h1>{{ title }}</h1>").unwrap();
+        std::fs::write(
+            dir.path().join("index.html"),
+            "{% include \"header.html\" %}{{ t(\"status.up\") }}",
+        )
+        .unwrap();
+        let overrides = TemplateOverrides::default();
+        let context = minijinja::context! {
+            title => "Homelab",
+            t => minijinja::Value::from_function(|key: &str| key.to_uppercase()),
+        };
+
+        assert_eq!(
+            overrides
+                .render(dir.path(), INDEX_TEMPLATE, context.clone())
+                .as_deref(),
+            Some("<h1>Homelab</h1>STATUS.UP")
+        );
+
+        // Read once, so later edits only show up after a restart, unless the templates
+        // are read on every request
+        std::fs::write(dir.path().join("index.html"), "{% if %}").unwrap();
+        assert_eq!(
+            overrides
+                .render(dir.path(), INDEX_TEMPLATE, context.clone())
+                .is_some(),
+            !cfg!(feature = "dev-templates")
+        );
+
+        let broken = TemplateOverrides::default();
+        assert_eq!(
+            broken.render(dir.path(), INDEX_TEMPLATE, context.clone()),
+            None
+        );
+        std::fs::write(dir.path().join("index.html"), "{{ missing.field }}").unwrap();
+        assert_eq!(broken.render(dir.path(), INDEX_TEMPLATE, context), None);
+    }
+}
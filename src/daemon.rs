@@ -0,0 +1,289 @@
+//! Running in the background
+//!
+//! For init systems without a process supervisor, `--daemon` starts the server detached
+//! from the terminal and returns at once, and `--pidfile PATH` records the server's PID
+//! so init scripts can signal it:
+//!
+//! ```bash
+//! iron_shield --daemon --pidfile /run/iron-shield.pid 3000 /etc/iron-shield/config.json5
+//! kill -TERM "$(cat /run/iron-shield.pid)"
+//! ```
+//!
+//! The background process is a copy of the program started in its own process group,
+//! with the same arguments except `--daemon` and with standard input on `/dev/null`, so
+//! closing the terminal or pressing Ctrl+C in it does not stop the server. Its logs and
+//! any other output go to the file given with `--log-file PATH`, appended to, or
+//! otherwise to `/dev/null`. It shuts down gracefully on `SIGTERM` or `SIGINT` like a
+//! foreground server.
+//!
+//! `--daemon` returns once the server has loaded its config and bound its listener, so
+//! the next command in an init script can rely on it. If the server exits before then,
+//! such as on an invalid config or a port in use, it exits with a non-zero status too.
+//!
+//! The pidfile is written by the server itself once it starts and removed when it shuts
+//! down. A pidfile naming a process that is still running makes the server refuse to
+//! start, so two servers cannot share one; a stale one is replaced.
+
+use crate::error::{IronShieldError, Result};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Environment variable naming the socket on which a server started by [`detach`]
+/// reports that it is ready
+pub const READY_SOCKET_ENV: &str = "IRON_SHIELD_READY_SOCKET";
+
+/// How long [`detach`] waits for the server to report that it is ready
+const READY_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Start this program again in the background with `args`, the command-line arguments
+/// after the program name without `--daemon`, and wait until it is ready, returning the
+/// PID of the new process.
+///
+/// Its standard output and error are appended to `log_file`, or discarded without one.
+///
+/// # Errors
+///
+/// Returns an error if `log_file` cannot be opened, if the program cannot be started or
+/// exits before it is ready, if it is not ready within a minute, or on platforms other
+/// than Unix.
+pub fn detach(args: &[String], log_file: Option<&Path>) -> Result<u32> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::net::UnixDatagram;
+        use std::os::unix::process::CommandExt;
+        use std::process::{Command, Stdio};
+
+        let (stdout, stderr) = match log_file {
+            Some(path) => {
+                let file = open_log_file(path)?;
+                let copy = file.try_clone().map_err(|e| {
+                    IronShieldError::from(format!(
+                        "Failed to open log file {}: {e}",
+                        path.display()
+                    ))
+                })?;
+                (Stdio::from(file), Stdio::from(copy))
+            }
+            None => (Stdio::null(), Stdio::null()),
+        };
+
+        // The server sends a datagram here once it is ready
+        let ready_error = |e: std::io::Error| {
+            IronShieldError::from(format!("Failed to wait for iron_shield to start: {e}"))
+        };
+        let dir = tempfile::tempdir().map_err(ready_error)?;
+        let socket_path = dir.path().join("ready.sock");
+        let socket = UnixDatagram::bind(&socket_path).map_err(ready_error)?;
+
+        let program = std::env::current_exe()
+            .map_err(|e| IronShieldError::from(format!("Failed to locate iron_shield: {e}")))?;
+        let mut child = Command::new(program)
+            .args(args)
+            .env(READY_SOCKET_ENV, &socket_path)
+            .stdin(Stdio::null())
+            .stdout(stdout)
+            .stderr(stderr)
+            .process_group(0)
+            .spawn()
+            .map_err(|e| {
+                IronShieldError::from(format!(
+                    "Failed to start iron_shield in the background: {e}"
+                ))
+            })?;
+
+        wait_until_ready(&socket, &mut child, READY_TIMEOUT).map_err(|e| match log_file {
+            Some(path) => IronShieldError::from(format!("{e}; see {}", path.display())),
+            None => IronShieldError::from(e),
+        })?;
+        Ok(child.id())
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = (args, log_file);
+        Err(IronShieldError::from(
+            "--daemon is only supported on Unix; run iron_shield as a service instead",
+        ))
+    }
+}
+
+/// Open `path` for appending log lines, creating it if needed.
+///
+/// # Errors
+///
+/// Returns an error naming the file if it cannot be opened.
+pub fn open_log_file(path: &Path) -> Result<std::fs::File> {
+    std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|e| {
+            IronShieldError::from(format!("Failed to open log file {}: {e}", path.display()))
+        })
+}
+
+/// Wait for a datagram on `socket`, failing if `child` exits or `timeout` passes first
+#[cfg(unix)]
+fn wait_until_ready(
+    socket: &std::os::unix::net::UnixDatagram,
+    child: &mut std::process::Child,
+    timeout: Duration,
+) -> std::result::Result<(), String> {
+    use std::io::ErrorKind;
+
+    let deadline = std::time::Instant::now() + timeout;
+    socket
+        .set_read_timeout(Some(Duration::from_millis(100)))
+        .map_err(|e| format!("Failed to wait for iron_shield: {e}"))?;
+    let mut buf = [0; 64];
+    loop {
+        match socket.recv(&mut buf) {
+            Ok(_) => return Ok(()),
+            Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {}
+            Err(e) => return Err(format!("Failed to wait for iron_shield: {e}")),
+        }
+        let status = child
+            .try_wait()
+            .map_err(|e| format!("Failed to wait for iron_shield: {e}"))?;
+        if let Some(status) = status {
+            return Err(format!(
+                "iron_shield exited with {status} before it was ready"
+            ));
+        }
+        if std::time::Instant::now() >= deadline {
+            return Err(format!(
+                "iron_shield was not ready within {}s; it is still running with PID {}",
+                timeout.as_secs(),
+                child.id()
+            ));
+        }
+    }
+}
+
+/// Tell the process that started this one with [`detach`], if any, that the server is
+/// ready.
+///
+/// Failures are logged rather than returned, since the server runs the same either way.
+pub fn report_ready() {
+    let Some(path) = std::env::var_os(READY_SOCKET_ENV) else {
+        return;
+    };
+    #[cfg(unix)]
+    {
+        let sent = std::os::unix::net::UnixDatagram::unbound()
+            .and_then(|socket| socket.send_to(b"READY", &path));
+        if let Err(e) = sent {
+            tracing::warn!("Failed to report that the server is ready: {e}");
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = path;
+}
+
+/// A file holding the PID of the running server, removed when dropped
+#[derive(Debug)]
+pub struct Pidfile {
+    path: PathBuf,
+}
+
+impl Pidfile {
+    /// Write the PID of this process to `path`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `path` names a process that is still running, or cannot be
+    /// written.
+    pub fn create(path: &Path) -> Result<Self> {
+        ensure_not_running(path)?;
+        std::fs::write(path, format!("{}\n", std::process::id())).map_err(|e| {
+            IronShieldError::from(format!("Failed to write pidfile {}: {e}", path.display()))
+        })?;
+        Ok(Self {
+            path: path.to_path_buf(),
+        })
+    }
+}
+
+impl Drop for Pidfile {
+    fn drop(&mut self) {
+        // Only remove the file if it still names this process
+        if read_pid(&self.path) == Some(std::process::id()) {
+            let _ = std::fs::remove_file(&self.path);
+        }
+    }
+}
+
+/// Check that the pidfile at `path` does not name a process that is still running.
+///
+/// # Errors
+///
+/// Returns an error naming the running process if it does.
+pub fn ensure_not_running(path: &Path) -> Result<()> {
+    match running_pid(path) {
+        Some(pid) => Err(IronShieldError::from(format!(
+            "iron_shield is already running with PID {pid} (from {})",
+            path.display()
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// The PID in the pidfile at `path`
+fn read_pid(path: &Path) -> Option<u32> {
+    std::fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+/// The PID in the pidfile at `path`, if that process is still running
+///
+/// Processes are looked up in `/proc`; where it does not exist, every recorded PID other
+/// than this process's counts as running.
+fn running_pid(path: &Path) -> Option<u32> {
+    let pid = read_pid(path).filter(|pid| *pid != std::process::id())?;
+    let proc = Path::new("/proc");
+    (!proc.is_dir() || proc.join(pid.to_string()).exists()).then_some(pid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pidfile_replaces_stale_pids_and_is_removed_on_drop() {
+        let dir = tempfile::tempdir().expect("Failed to create temporary directory");
+        let path = dir.path().join("iron-shield.pid");
+        std::fs::write(&path, "not a pid").expect("Failed to write pidfile");
+
+        let pidfile = Pidfile::create(&path).expect("Stale pidfile should be replaced");
+        assert_eq!(read_pid(&path), Some(std::process::id()));
+        drop(pidfile);
+        assert!(!path.exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn waiting_for_readiness_ends_on_a_report_or_an_exit() {
+        use std::os::unix::net::UnixDatagram;
+
+        let dir = tempfile::tempdir().expect("Failed to create temporary directory");
+        let socket = UnixDatagram::bind(dir.path().join("ready.sock"))
+            .expect("Failed to bind readiness socket");
+        let mut child = std::process::Command::new("sh")
+            .args(["-c", "exit 3"])
+            .spawn()
+            .expect("Failed to start sh");
+
+        let err = wait_until_ready(&socket, &mut child, Duration::from_secs(10))
+            .expect_err("An exited process is never ready");
+        assert!(err.contains("before it was ready"), "{err}");
+
+        std::env::set_var(READY_SOCKET_ENV, dir.path().join("ready.sock"));
+        report_ready();
+        std::env::remove_var(READY_SOCKET_ENV);
+        let mut child = std::process::Command::new("sleep")
+            .arg("10")
+            .spawn()
+            .expect("Failed to start sleep");
+        let ready = wait_until_ready(&socket, &mut child, Duration::from_secs(10));
+        let _ = child.kill();
+        ready.expect("A reported readiness is received");
+    }
+}
@@ -0,0 +1,361 @@
+//! Site favicons fetched and cached by the server
+//!
+//! Sites without a bundled or local `icon` are shown with their favicon, served from
+//! `/icons/{site}` so browsers never contact the sites' hosts just to draw the launcher.
+//! The server finds the icon once (the site's `icon` URL when it has one, otherwise the
+//! first `<link rel="icon">` on its page or `/favicon.ico`) and keeps it in an
+//! `icon-cache` directory next to the config file for `icon_cache_ttl_secs`. Sites
+//! without a usable icon get a generic globe, which is cached the same way so the site
+//! is not asked again on every page load.
+
+use crate::config::{same_site_name, Site};
+use crate::uptime::UptimeState;
+use axum::{
+    extract::{Path as UrlPath, State},
+    http::{header, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use tracing::{debug, warn};
+
+/// Name of the directory, next to the config file, that holds cached icons.
+pub const ICON_CACHE_DIR: &str = "icon-cache";
+
+/// Largest icon the server downloads and caches.
+const MAX_ICON_BYTES: usize = 256 * 1024;
+/// Largest part of a site's page read while looking for its icon links.
+const MAX_PAGE_BYTES: usize = 512 * 1024;
+/// How long each request made to find an icon may take.
+const FETCH_TIMEOUT: Duration = Duration::from_secs(5);
+/// `Cache-Control` of icon responses; the disk cache decides when icons are refetched.
+const ICON_CACHE_CONTROL: &str = "public, max-age=3600";
+/// Policy sent with SVG icons, which may contain scripts when opened directly.
+const SVG_CONTENT_SECURITY_POLICY: &str = "default-src 'none'; style-src 'unsafe-inline'; sandbox";
+
+/// URL path of the cached favicon of the site called `name`.
+///
+/// # Examples
+///
+/// ```
+/// use iron_shield::favicons::icon_path;
+///
+/// assert_eq!(icon_path("Home Assistant"), "/icons/Home%20Assistant");
+/// ```
+#[must_use]
+pub fn icon_path(name: &str) -> String {
+    let mut path = String::from("/icons/");
+    for byte in name.bytes() {
+        if byte.is_ascii_alphanumeric() || b"-._~".contains(&byte) {
+            path.push(char::from(byte));
+        } else {
+            path.push_str(&format!("%{byte:02X}"));
+        }
+    }
+    path
+}
+
+/// Directory holding the cached icons of the configuration at `config_path`.
+#[must_use]
+pub fn cache_dir(config_path: &Path) -> PathBuf {
+    match config_path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent.join(ICON_CACHE_DIR),
+        _ => PathBuf::from(ICON_CACHE_DIR),
+    }
+}
+
+/// Serve the favicon of a site
+///
+/// Handles `GET /icons/{site}`, where `site` is the site's name.
+///
+/// # Errors
+///
+/// Responds with HTTP 404 if no site has that name or the site uses a bundled or local
+/// icon.
+pub async fn site_icon(
+    State(state): State<Arc<UptimeState>>,
+    UrlPath(name): UrlPath<String>,
+) -> impl IntoResponse {
+    let config = state.config.load();
+    let site = config
+        .sites
+        .iter()
+        .find(|site| same_site_name(&site.name, &name))
+        .cloned();
+    let ttl = Duration::from_secs(config.icon_cache_ttl_secs);
+    let Some(site) = site.filter(|site| {
+        site.icon_src()
+            .is_some_and(|src| src.starts_with("/icons/"))
+    }) else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+
+    let cache_file = cache_dir(&state.config_file_path).join(cache_key(&site));
+    let icon = match read_cached(&cache_file, ttl).await {
+        Some(icon) => icon,
+        None => {
+            let icon = fetch_icon(&site).await;
+            if let Err(err) = write_cached(&cache_file, icon.as_ref()).await {
+                warn!("Unable to cache the icon of {}: {err}", site.name);
+            }
+            icon
+        }
+    };
+    let (content_type, body) = icon.unwrap_or_else(fallback_icon);
+    icon_response(&content_type, body)
+}
+
+/// An icon as its content type and bytes.
+type Icon = (String, Vec<u8>);
+
+/// Name of the cache file for `site`, which changes with its URL and icon.
+fn cache_key(site: &Site) -> String {
+    let source = format!(
+        "{}\n{}\n{}",
+        site.name.trim().to_lowercase(),
+        site.url,
+        site.icon.as_deref().unwrap_or_default()
+    );
+    format!("{:016x}", crate::assets::fingerprint(source.as_bytes()))
+}
+
+/// The cached icon at `path` if it is younger than `ttl`: `Some(None)` when the site was
+/// found to have no icon.
+async fn read_cached(path: &Path, ttl: Duration) -> Option<Option<Icon>> {
+    let modified = tokio::fs::metadata(path).await.ok()?.modified().ok()?;
+    if SystemTime::now()
+        .duration_since(modified)
+        .unwrap_or_default()
+        >= ttl
+    {
+        return None;
+    }
+    let contents = tokio::fs::read(path).await.ok()?;
+    // The first line holds the content type, empty when the site has no icon
+    let split = contents.iter().position(|byte| *byte == b'\n')?;
+    let content_type = String::from_utf8(contents[..split].to_vec()).ok()?;
+    if content_type.is_empty() {
+        return Some(None);
+    }
+    Some(Some((content_type, contents[split + 1..].to_vec())))
+}
+
+/// Store `icon`, or that the site has none, in the cache file at `path`.
+async fn write_cached(path: &Path, icon: Option<&Icon>) -> std::io::Result<()> {
+    if let Some(dir) = path.parent() {
+        tokio::fs::create_dir_all(dir).await?;
+    }
+    let mut contents = Vec::new();
+    if let Some((content_type, body)) = icon {
+        contents.extend_from_slice(content_type.as_bytes());
+        contents.push(b'\n');
+        contents.extend_from_slice(body);
+    } else {
+        contents.push(b'\n');
+    }
+    tokio::fs::write(path, contents).await
+}
+
+/// Download the icon of `site`, trying its `icon` URL or the icons its page links to,
+/// then `/favicon.ico`.
+async fn fetch_icon(site: &Site) -> Option<Icon> {
+    let client = reqwest::Client::builder()
+        .timeout(FETCH_TIMEOUT)
+        .build()
+        .ok()?;
+
+    let candidates = match site.icon_url() {
+        Some(icon) => vec![url::Url::parse(icon).ok()?],
+        None => {
+            let page = url::Url::parse(&site.url).ok()?;
+            let mut candidates = match download(&client, &page, MAX_PAGE_BYTES).await {
+                Some((content_type, body)) if content_type.starts_with("text/html") => {
+                    icon_links(&String::from_utf8_lossy(&body), &page)
+                }
+                _ => Vec::new(),
+            };
+            candidates.extend(page.join("/favicon.ico").ok());
+            candidates
+        }
+    };
+
+    for candidate in candidates {
+        match download(&client, &candidate, MAX_ICON_BYTES).await {
+            Some((content_type, body)) => {
+                if let Some(content_type) = image_type(&content_type, &candidate) {
+                    return Some((content_type, body));
+                }
+            }
+            None => debug!("No icon for {} at {candidate}", site.name),
+        }
+    }
+    None
+}
+
+/// The body of a successful response to `url` and its content type, if it is no larger
+/// than `limit`.
+async fn download(client: &reqwest::Client, url: &url::Url, limit: usize) -> Option<Icon> {
+    let mut response = client
+        .get(url.clone())
+        .send()
+        .await
+        .ok()?
+        .error_for_status()
+        .ok()?;
+    let content_type = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    let mut body = Vec::new();
+    while let Some(chunk) = response.chunk().await.ok()? {
+        if body.len() + chunk.len() > limit {
+            // Keep the start of a page, which is where icon links are; drop large images
+            if content_type.starts_with("text/html") {
+                break;
+            }
+            return None;
+        }
+        body.extend_from_slice(&chunk);
+    }
+    Some((content_type, body))
+}
+
+/// The content type to serve a downloaded icon with, or `None` if it is not an image.
+///
+/// Servers often send `.ico` files as `application/octet-stream`, so those are accepted
+/// by extension.
+fn image_type(content_type: &str, url: &url::Url) -> Option<String> {
+    let essence = content_type.split(';').next().unwrap_or_default().trim();
+    if essence.starts_with("image/") {
+        return Some(essence.to_string());
+    }
+    let is_ico = url.path().to_ascii_lowercase().ends_with(".ico");
+    (is_ico && (essence.is_empty() || essence == "application/octet-stream"))
+        .then(|| "image/x-icon".to_string())
+}
+
+/// Icon URLs linked from the `<link rel="icon">` tags of `html`, in page order.
+fn icon_links(html: &str, page: &url::Url) -> Vec<url::Url> {
+    let lowercase = html.to_ascii_lowercase();
+    let mut links = Vec::new();
+    let mut offset = 0;
+    while let Some(start) = lowercase[offset..].find("<link") {
+        let start = offset + start + "<link".len();
+        let Some(end) = lowercase[start..].find('>') else {
+            break;
+        };
+        let attributes = &html[start..start + end];
+        offset = start + end;
+
+        let is_icon = crate::bookmarks::attribute(attributes, "rel").is_some_and(|rel| {
+            rel.split_whitespace()
+                .any(|kind| kind.eq_ignore_ascii_case("icon"))
+        });
+        if let Some(href) = crate::bookmarks::attribute(attributes, "href").filter(|_| is_icon) {
+            links.extend(page.join(&href).ok());
+        }
+    }
+    links
+}
+
+/// The generic icon shown for sites without one of their own.
+fn fallback_icon() -> Icon {
+    let globe = crate::icons::bundled_icon("globe").unwrap_or_default();
+    let svg = format!(
+        r##"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 24 24" fill="none" stroke="#8a8f98" stroke-width="1.5" stroke-linecap="round" stroke-linejoin="round"><path d="{globe}"/></svg>"##
+    );
+    ("image/svg+xml".to_string(), svg.into_bytes())
+}
+
+fn icon_response(content_type: &str, body: Vec<u8>) -> Response {
+    let mut response = body.into_response();
+    let headers = response.headers_mut();
+    headers.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_str(content_type)
+            .unwrap_or_else(|_| HeaderValue::from_static("application/octet-stream")),
+    );
+    headers.insert(
+        header::CACHE_CONTROL,
+        HeaderValue::from_static(ICON_CACHE_CONTROL),
+    );
+    headers.insert(
+        header::X_CONTENT_TYPE_OPTIONS,
+        HeaderValue::from_static("nosniff"),
+    );
+    if content_type == "image/svg+xml" {
+        headers.insert(
+            header::CONTENT_SECURITY_POLICY,
+            HeaderValue::from_static(SVG_CONTENT_SECURITY_POLICY),
+        );
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_icon_links_relative_to_the_page() {
+        let page = url::Url::parse("https://grafana.example.com/login").unwrap();
+        let html = r#"<html><head>
+            <link rel="stylesheet" href="/app.css">
+            <LINK REL="shortcut icon" HREF="public/img/fav32.png">
+            <link href="https://cdn.example.com/apple.png" rel="apple-touch-icon">
+            <link rel=icon href="/favicon.svg" type="image/svg+xml">
+        </head></html>"#;
+
+        let links: Vec<String> = icon_links(html, &page)
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(
+            links,
+            [
+                "https://grafana.example.com/public/img/fav32.png",
+                "https://grafana.example.com/favicon.svg",
+            ]
+        );
+    }
+
+    #[test]
+    fn accepts_images_and_untyped_ico_files_only() {
+        let ico = url::Url::parse("https://example.com/favicon.ico").unwrap();
+        let page = url::Url::parse("https://example.com/").unwrap();
+        assert_eq!(
+            image_type("image/png; charset=binary", &page).as_deref(),
+            Some("image/png")
+        );
+        assert_eq!(
+            image_type("application/octet-stream", &ico).as_deref(),
+            Some("image/x-icon")
+        );
+        assert_eq!(image_type("text/html", &ico), None);
+        assert_eq!(image_type("application/octet-stream", &page), None);
+    }
+
+    #[tokio::test]
+    async fn cache_entries_expire_and_remember_missing_icons() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(ICON_CACHE_DIR).join("abc");
+
+        write_cached(&path, Some(&("image/png".to_string(), vec![1, 2, 3])))
+            .await
+            .unwrap();
+        assert_eq!(
+            read_cached(&path, Duration::from_secs(60)).await,
+            Some(Some(("image/png".to_string(), vec![1, 2, 3])))
+        );
+        assert_eq!(read_cached(&path, Duration::ZERO).await, None);
+
+        write_cached(&path, None).await.unwrap();
+        assert_eq!(
+            read_cached(&path, Duration::from_secs(60)).await,
+            Some(None)
+        );
+    }
+}
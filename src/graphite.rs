@@ -0,0 +1,203 @@
+//! Graphite export
+//!
+//! The config's `graphite` block names a Carbon daemon that every uptime cycle is
+//! pushed to over the Graphite plaintext protocol:
+//!
+//! ```json5
+//! graphite: {
+//!     host: "graphite.lan",
+//!     port: 2003,
+//!     prefix: "iron_shield",
+//! }
+//! ```
+//!
+//! Each monitored site with a completed check is sent as:
+//!
+//! - `{prefix}.{name}.up` - 1 if the site is up, 0 if it is down
+//! - `{prefix}.{name}.response_ms` - response time of the latest check, in ms
+//!
+//! where `{name}` is the site name made safe for a metric path the same way as for
+//! StatsD (see the `statsd` module). Each cycle opens a TCP connection, writes its lines,
+//! and closes it; failures are logged and the next cycle tries again.
+
+use crate::uptime::{snapshot_current_histories, UptimeHistory, UptimeState, UptimeStatus};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tracing::warn;
+
+/// Port Carbon listens on for the plaintext protocol when `port` is not set
+pub const DEFAULT_PORT: u16 = 2003;
+/// Prefix of the metric paths when `prefix` is not set
+pub const DEFAULT_PREFIX: &str = "iron_shield";
+
+/// How long connecting and writing may take
+const SEND_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Returns the default plaintext port of the Carbon daemon.
+#[must_use]
+pub fn default_port() -> u16 {
+    DEFAULT_PORT
+}
+
+/// Returns the default prefix of the metric paths.
+#[must_use]
+pub fn default_prefix() -> String {
+    DEFAULT_PREFIX.to_string()
+}
+
+/// Settings of the Graphite export
+///
+/// # Fields
+///
+/// * `host` - Host name or IP address of the Carbon daemon
+/// * `port` - TCP port of its plaintext listener
+/// * `prefix` - Prepended to every metric path, followed by a `.`
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+pub struct Graphite {
+    /// Host name or IP address of the daemon
+    pub host: String,
+    /// TCP port of the plaintext listener
+    #[serde(default = "default_port")]
+    pub port: u16,
+    /// Prefix of the metric paths
+    #[serde(default = "default_prefix")]
+    pub prefix: String,
+}
+
+impl Graphite {
+    /// Problems with these settings, as pairs of the field name and what is wrong with it
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use iron_shield::graphite::Graphite;
+    ///
+    /// let graphite = Graphite {
+    ///     host: "graphite.lan".to_string(),
+    ///     port: 2003,
+    ///     prefix: "iron_shield".to_string(),
+    /// };
+    /// assert!(graphite.validate().is_empty());
+    ///
+    /// let spaced = Graphite { prefix: "iron shield".to_string(), ..graphite };
+    /// assert_eq!(spaced.validate()[0].0, "prefix");
+    /// ```
+    #[must_use]
+    pub fn validate(&self) -> Vec<(String, String)> {
+        let mut problems = Vec::new();
+        if self.host.trim().is_empty() {
+            problems.push(("host".to_string(), "cannot be empty".to_string()));
+        }
+        if self.port == 0 {
+            problems.push((
+                "port".to_string(),
+                "must be between 1 and 65535".to_string(),
+            ));
+        }
+        if self.prefix.contains(char::is_whitespace) {
+            problems.push((
+                "prefix".to_string(),
+                format!("\"{}\" cannot contain whitespace", self.prefix),
+            ));
+        }
+        problems
+    }
+}
+
+/// Push the latest uptime snapshot to the configured Carbon daemon, if any.
+///
+/// Failures are logged and otherwise ignored; the next cycle sends its own values.
+pub async fn send_uptime_snapshot(state: &UptimeState) {
+    let Some(settings) = state.config.load().graphite.clone() else {
+        return;
+    };
+
+    let lines = uptime_lines(&settings.prefix, &snapshot_current_histories(state));
+    if lines.is_empty() {
+        return;
+    }
+    match tokio::time::timeout(SEND_TIMEOUT, send(&settings, lines.as_bytes())).await {
+        Ok(Ok(())) => {}
+        Ok(Err(err)) => warn!(
+            "Failed to send metrics to Graphite at {}:{}: {err}",
+            settings.host, settings.port
+        ),
+        Err(_) => warn!(
+            "Sending metrics to Graphite at {}:{} timed out",
+            settings.host, settings.port
+        ),
+    }
+}
+
+/// The plaintext protocol lines reporting `snapshot`, leaving out sites that are paused
+/// or have not finished a check.
+fn uptime_lines(prefix: &str, snapshot: &[UptimeHistory]) -> String {
+    let prefix = prefix.trim().trim_end_matches('.');
+    let prefix = if prefix.is_empty() {
+        String::new()
+    } else {
+        format!("{prefix}.")
+    };
+
+    let mut lines = String::new();
+    for history in snapshot {
+        let up = match history.status {
+            UptimeStatus::Up => 1,
+            UptimeStatus::Down => 0,
+            UptimeStatus::Loading | UptimeStatus::Disabled => continue,
+        };
+        let path = format!("{prefix}{}", crate::statsd::bucket_name(&history.site_id));
+        let timestamp = history.timestamp;
+        lines.push_str(&format!("{path}.up {up} {timestamp}\n"));
+        if let Some(ms) = history.response_time_ms {
+            lines.push_str(&format!("{path}.response_ms {ms} {timestamp}\n"));
+        }
+    }
+    lines
+}
+
+/// Write `lines` to the daemon over a new connection.
+async fn send(settings: &Graphite, lines: &[u8]) -> std::io::Result<()> {
+    let mut stream = tokio::net::TcpStream::connect((settings.host.trim(), settings.port)).await?;
+    stream.write_all(lines).await?;
+    stream.shutdown().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn uptime_lines_report_checked_sites() {
+        let history = |site: &str, status, response_time_ms| UptimeHistory {
+            site_id: site.to_string(),
+            status,
+            timestamp: 1_700_000_000,
+            history: Vec::new(),
+            uptime_percentage: 50.0,
+            response_time_ms,
+            max_history_entries: crate::uptime::MAX_HISTORY_ENTRIES,
+            latency_ms: Vec::new(),
+            down_since: None,
+            last_checked: None,
+            last_change: None,
+        };
+
+        let lines = uptime_lines(
+            "iron_shield",
+            &[
+                history("Home Assistant", UptimeStatus::Up, Some(85)),
+                history("NAS", UptimeStatus::Down, None),
+                history("Backup", UptimeStatus::Disabled, None),
+            ],
+        );
+
+        assert_eq!(
+            lines,
+            "iron_shield.home_assistant.up 1 1700000000\n\
+             iron_shield.home_assistant.response_ms 85 1700000000\n\
+             iron_shield.nas.up 0 1700000000\n"
+        );
+    }
+}
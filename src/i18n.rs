@@ -0,0 +1,665 @@
+//! Translations of the dashboard UI
+//!
+//! The config's `language` option picks the language the dashboard is shown in. Every
+//! piece of text the dashboard template and its scripts show has a key, such as
+//! `settings.title`, looked up with [`Language::translate`]. Translations are kept as
+//! one table per language in this module; a key missing from a table falls back to the
+//! English text, so a new string only has to be added to [`EN`] to appear everywhere.
+//!
+//! The strings the scripts need for text they build at runtime (status labels, response
+//! times) are listed in [`FRONTEND_KEYS`] and passed to the page as JSON by
+//! [`Language::frontend_json`].
+//!
+//! Site names, tags, categories, and other configured values are shown as written.
+
+use serde::{Deserialize, Serialize};
+
+/// Language of the dashboard UI
+///
+/// # Examples
+///
+/// ```
+/// use iron_shield::i18n::Language;
+///
+/// assert_eq!(Language::default(), Language::En);
+/// assert_eq!("de".parse::<Language>(), Ok(Language::De));
+/// assert_eq!(Language::De.translate("settings.title"), "Einstellungen");
+/// assert_eq!(Language::Fr.to_string(), "fr");
+/// ```
+#[derive(Debug, Default, Deserialize, Serialize, PartialEq, Eq, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum Language {
+    /// English
+    #[default]
+    En,
+    /// German
+    De,
+    /// Spanish
+    Es,
+    /// French
+    Fr,
+}
+
+impl std::fmt::Display for Language {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Language::En => "en",
+            Language::De => "de",
+            Language::Es => "es",
+            Language::Fr => "fr",
+        })
+    }
+}
+
+impl std::str::FromStr for Language {
+    type Err = String;
+
+    fn from_str(language: &str) -> std::result::Result<Self, Self::Err> {
+        match language.trim().to_ascii_lowercase().as_str() {
+            "en" => Ok(Language::En),
+            "de" => Ok(Language::De),
+            "es" => Ok(Language::Es),
+            "fr" => Ok(Language::Fr),
+            _ => Err(format!(
+                "\"{language}\" is not a supported language; use \"en\", \"de\", \"es\" or \"fr\""
+            )),
+        }
+    }
+}
+
+impl Language {
+    /// Translation table of the language
+    fn table(self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            Language::En => EN,
+            Language::De => DE,
+            Language::Es => ES,
+            Language::Fr => FR,
+        }
+    }
+
+    /// The text for `key` in this language, falling back to English and then to the key
+    /// itself.
+    #[must_use]
+    pub fn translate(self, key: &str) -> &str {
+        lookup(self.table(), key)
+            .or_else(|| lookup(EN, key))
+            .unwrap_or(key)
+    }
+
+    /// JSON object mapping each of [`FRONTEND_KEYS`] to its text in this language, safe
+    /// to embed in a `<script>` element.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use iron_shield::i18n::Language;
+    ///
+    /// assert!(Language::Es.frontend_json().contains(r#""status.down":"Caído""#));
+    /// ```
+    #[must_use]
+    pub fn frontend_json(self) -> String {
+        let strings: serde_json::Map<String, serde_json::Value> = FRONTEND_KEYS
+            .iter()
+            .map(|key| ((*key).to_string(), self.translate(key).into()))
+            .collect();
+        serde_json::Value::Object(strings)
+            .to_string()
+            .replace('<', "\\u003c")
+    }
+}
+
+fn lookup(table: &'static [(&'static str, &'static str)], key: &str) -> Option<&'static str> {
+    table
+        .iter()
+        .find(|(candidate, _)| *candidate == key)
+        .map(|(_, text)| *text)
+}
+
+/// Keys of the strings the dashboard scripts use for text they build at runtime
+pub const FRONTEND_KEYS: &[&str] = &[
+    "status.up",
+    "status.down",
+    "status.disabled",
+    "status.loading",
+    "status.unknown",
+    "outage.down_for",
+    "detail.unreachable",
+    "detail.checking",
+    "detail.paused",
+    "detail.response",
+    "detail.checked_ago",
+    "history.empty",
+    "history.empty_label",
+    "response.average",
+    "response.not_available",
+];
+
+/// English text of every key
+pub const EN: &[(&str, &str)] = &[
+    ("header.current_time", "Current time"),
+    ("header.world_clocks", "World clocks"),
+    ("header.weather_in", "Weather in"),
+    (
+        "notice.config_error",
+        "The config file was not applied; the last valid configuration is in use.",
+    ),
+    (
+        "notice.clock_behind",
+        "The server clock is {} behind the checked sites, so recorded times and uptime are off.",
+    ),
+    (
+        "notice.clock_ahead",
+        "The server clock is {} ahead of the checked sites, so recorded times and uptime are off.",
+    ),
+    ("outage.title", "Currently down"),
+    ("outage.down_for", "down for"),
+    ("nav.label", "Site shortcuts"),
+    ("filters.open", "Open filters"),
+    ("filters.region", "Search and filter sites"),
+    ("filters.title", "Filter sites"),
+    ("filters.close", "Close filters"),
+    (
+        "filters.description",
+        "Use search and tag filters to narrow the dashboard results. Press Escape to close this panel.",
+    ),
+    ("filters.by_tag", "Filter by tag"),
+    ("filters.tags", "Tag filters"),
+    ("filters.empty", "No sites match your search or filters."),
+    ("search.label", "Search sites"),
+    ("search.placeholder", "Search bookmarks..."),
+    ("search.hint", "Press / to focus, Enter to open the top match"),
+    ("search.results", "Matching sites"),
+    ("sites.label", "Monitored sites"),
+    ("sites.other", "Other sites"),
+    ("widgets.label", "Widgets"),
+    ("card.status_summary", "Status summary for"),
+    ("card.tags_for", "Tags for"),
+    ("card.history_for", "Uptime history for"),
+    ("card.loading_history", "Loading history…"),
+    ("settings.open", "Open settings"),
+    ("settings.title", "Settings"),
+    ("settings.description", "Dashboard configuration"),
+    ("settings.close", "Close settings"),
+    (
+        "settings.notifications",
+        "Enable system notifications for site status changes",
+    ),
+    ("settings.enable_alerts", "Enable alerts"),
+    ("settings.global", "Global Settings"),
+    ("settings.theme", "Theme"),
+    ("settings.theme_default", "Dashboard default"),
+    ("settings.theme_auto", "Auto"),
+    ("settings.theme_dark", "Dark"),
+    ("settings.theme_light", "Light"),
+    (
+        "settings.theme_help",
+        "Switch between light and dark backgrounds for the clock, buttons, and cards in this browser. Auto follows the system setting.",
+    ),
+    ("settings.site_name", "Dashboard Name"),
+    ("settings.clock_format", "Clock Format"),
+    ("settings.clock_none", "No Clock"),
+    ("settings.clock_24", "24 Hour Format"),
+    ("settings.clock_12", "12 Hour Format"),
+    ("settings.telemetry", "OpenTelemetry Endpoint"),
+    (
+        "settings.telemetry_help",
+        "Optional: forward uptime snapshots to an external collector for long-term history.",
+    ),
+    ("settings.sites", "Sites"),
+    ("settings.new_site", "Add New Site"),
+    ("settings.new_site_placeholder", "Site name"),
+    ("settings.url", "URL"),
+    ("settings.category", "Category"),
+    ("settings.category_placeholder", "Category (e.g., Work)"),
+    ("settings.tags", "Tags"),
+    ("settings.tags_placeholder", "Tags (comma separated)"),
+    ("settings.add_site", "Add Site"),
+    ("settings.test", "Test"),
+    (
+        "settings.tags_help",
+        "Tags can be comma separated and edited after creation.",
+    ),
+    ("settings.open_page", "Open Settings Page"),
+    ("settings.save", "Save Settings"),
+    ("settings.reset", "Reset"),
+    ("status.up", "Up"),
+    ("status.down", "Down"),
+    ("status.disabled", "Paused"),
+    ("status.loading", "Loading"),
+    ("status.unknown", "Unknown"),
+    ("detail.unreachable", "Unreachable"),
+    ("detail.checking", "Checking..."),
+    ("detail.paused", "Monitoring paused"),
+    ("detail.response", "Response"),
+    ("detail.checked_ago", "checked {} ago"),
+    ("history.empty", "No history yet"),
+    ("history.empty_label", "No uptime history yet"),
+    ("response.average", "Avg"),
+    ("response.not_available", "Not available"),
+    ("report.title", "Status report"),
+    ("report.generated", "generated"),
+    ("report.statuses", "Current status"),
+    ("report.site", "Site"),
+    ("report.status", "Status"),
+    ("report.uptime", "Uptime"),
+    ("report.response", "Avg response"),
+    ("report.checks", "Checks"),
+    ("report.incidents", "Recent incidents"),
+    ("report.no_incidents", "No incidents in the recorded checks."),
+    ("report.started", "Started"),
+    ("report.ended", "Ended"),
+    ("report.ongoing", "Ongoing"),
+    ("report.duration", "Duration"),
+    ("report.reason", "Reason"),
+];
+
+/// German text
+const DE: &[(&str, &str)] = &[
+    ("header.current_time", "Aktuelle Uhrzeit"),
+    ("header.world_clocks", "Weltuhren"),
+    ("header.weather_in", "Wetter in"),
+    (
+        "notice.config_error",
+        "Die Konfigurationsdatei wurde nicht übernommen; die letzte gültige Konfiguration wird verwendet.",
+    ),
+    (
+        "notice.clock_behind",
+        "Die Serveruhr geht gegenüber den geprüften Seiten {} nach; aufgezeichnete Zeiten und Verfügbarkeit stimmen nicht.",
+    ),
+    (
+        "notice.clock_ahead",
+        "Die Serveruhr geht gegenüber den geprüften Seiten {} vor; aufgezeichnete Zeiten und Verfügbarkeit stimmen nicht.",
+    ),
+    ("outage.title", "Derzeit nicht erreichbar"),
+    ("outage.down_for", "ausgefallen seit"),
+    ("nav.label", "Verknüpfungen"),
+    ("filters.open", "Filter öffnen"),
+    ("filters.region", "Seiten suchen und filtern"),
+    ("filters.title", "Seiten filtern"),
+    ("filters.close", "Filter schließen"),
+    (
+        "filters.description",
+        "Mit Suche und Tag-Filtern die Ergebnisse eingrenzen. Escape schließt dieses Feld.",
+    ),
+    ("filters.by_tag", "Nach Tag filtern"),
+    ("filters.tags", "Tag-Filter"),
+    (
+        "filters.empty",
+        "Keine Seiten passen zu deiner Suche oder deinen Filtern.",
+    ),
+    ("search.label", "Seiten suchen"),
+    ("search.placeholder", "Lesezeichen durchsuchen..."),
+    (
+        "search.hint",
+        "/ zum Fokussieren, Enter öffnet den besten Treffer",
+    ),
+    ("search.results", "Passende Seiten"),
+    ("sites.label", "Überwachte Seiten"),
+    ("sites.other", "Weitere Seiten"),
+    ("widgets.label", "Widgets"),
+    ("card.status_summary", "Statusübersicht für"),
+    ("card.tags_for", "Tags für"),
+    ("card.history_for", "Verfügbarkeitsverlauf für"),
+    ("card.loading_history", "Verlauf wird geladen…"),
+    ("settings.open", "Einstellungen öffnen"),
+    ("settings.title", "Einstellungen"),
+    ("settings.description", "Dashboard-Konfiguration"),
+    ("settings.close", "Einstellungen schließen"),
+    (
+        "settings.notifications",
+        "Systembenachrichtigungen bei Statusänderungen aktivieren",
+    ),
+    ("settings.enable_alerts", "Benachrichtigungen aktivieren"),
+    ("settings.global", "Allgemeine Einstellungen"),
+    ("settings.theme", "Design"),
+    ("settings.theme_default", "Dashboard-Standard"),
+    ("settings.theme_auto", "Automatisch"),
+    ("settings.theme_dark", "Dunkel"),
+    ("settings.theme_light", "Hell"),
+    (
+        "settings.theme_help",
+        "Wechselt in diesem Browser zwischen hellem und dunklem Hintergrund für Uhr, Schaltflächen und Karten. Automatisch folgt der Systemeinstellung.",
+    ),
+    ("settings.site_name", "Name des Dashboards"),
+    ("settings.clock_format", "Uhrzeitformat"),
+    ("settings.clock_none", "Keine Uhr"),
+    ("settings.clock_24", "24-Stunden-Format"),
+    ("settings.clock_12", "12-Stunden-Format"),
+    ("settings.telemetry", "OpenTelemetry-Endpunkt"),
+    (
+        "settings.telemetry_help",
+        "Optional: Verfügbarkeitsdaten zur Langzeitspeicherung an einen externen Collector senden.",
+    ),
+    ("settings.sites", "Seiten"),
+    ("settings.new_site", "Neue Seite hinzufügen"),
+    ("settings.new_site_placeholder", "Name der Seite"),
+    ("settings.url", "URL"),
+    ("settings.category", "Kategorie"),
+    ("settings.category_placeholder", "Kategorie (z. B. Arbeit)"),
+    ("settings.tags", "Tags"),
+    ("settings.tags_placeholder", "Tags (durch Komma getrennt)"),
+    ("settings.add_site", "Seite hinzufügen"),
+    ("settings.test", "Testen"),
+    (
+        "settings.tags_help",
+        "Tags werden durch Kommas getrennt und können später bearbeitet werden.",
+    ),
+    ("settings.open_page", "Einstellungsseite öffnen"),
+    ("settings.save", "Einstellungen speichern"),
+    ("settings.reset", "Zurücksetzen"),
+    ("status.up", "Online"),
+    ("status.down", "Offline"),
+    ("status.disabled", "Pausiert"),
+    ("status.loading", "Lädt"),
+    ("status.unknown", "Unbekannt"),
+    ("detail.unreachable", "Nicht erreichbar"),
+    ("detail.checking", "Wird geprüft..."),
+    ("detail.paused", "Überwachung pausiert"),
+    ("detail.response", "Antwort"),
+    ("detail.checked_ago", "vor {} geprüft"),
+    ("history.empty", "Noch kein Verlauf"),
+    ("history.empty_label", "Noch kein Verfügbarkeitsverlauf"),
+    ("response.average", "Ø"),
+    ("response.not_available", "Nicht verfügbar"),
+    ("report.title", "Statusbericht"),
+    ("report.generated", "erstellt"),
+    ("report.statuses", "Aktueller Status"),
+    ("report.site", "Seite"),
+    ("report.status", "Status"),
+    ("report.uptime", "Verfügbarkeit"),
+    ("report.response", "Ø Antwortzeit"),
+    ("report.checks", "Prüfungen"),
+    ("report.incidents", "Letzte Ausfälle"),
+    ("report.no_incidents", "Keine Ausfälle in den aufgezeichneten Prüfungen."),
+    ("report.started", "Beginn"),
+    ("report.ended", "Ende"),
+    ("report.ongoing", "Andauernd"),
+    ("report.duration", "Dauer"),
+    ("report.reason", "Grund"),
+];
+
+/// Spanish text
+const ES: &[(&str, &str)] = &[
+    ("header.current_time", "Hora actual"),
+    ("header.world_clocks", "Relojes mundiales"),
+    ("header.weather_in", "Tiempo en"),
+    (
+        "notice.config_error",
+        "No se aplicó el archivo de configuración; se usa la última configuración válida.",
+    ),
+    (
+        "notice.clock_behind",
+        "El reloj del servidor va {} atrasado respecto a los sitios comprobados; las horas registradas y la disponibilidad no son correctas.",
+    ),
+    (
+        "notice.clock_ahead",
+        "El reloj del servidor va {} adelantado respecto a los sitios comprobados; las horas registradas y la disponibilidad no son correctas.",
+    ),
+    ("outage.title", "Sitios caídos"),
+    ("outage.down_for", "caído desde hace"),
+    ("nav.label", "Accesos directos"),
+    ("filters.open", "Abrir filtros"),
+    ("filters.region", "Buscar y filtrar sitios"),
+    ("filters.title", "Filtrar sitios"),
+    ("filters.close", "Cerrar filtros"),
+    (
+        "filters.description",
+        "Usa la búsqueda y los filtros de etiquetas para acotar los resultados. Pulsa Escape para cerrar este panel.",
+    ),
+    ("filters.by_tag", "Filtrar por etiqueta"),
+    ("filters.tags", "Filtros de etiquetas"),
+    (
+        "filters.empty",
+        "Ningún sitio coincide con tu búsqueda o filtros.",
+    ),
+    ("search.label", "Buscar sitios"),
+    ("search.placeholder", "Buscar marcadores..."),
+    (
+        "search.hint",
+        "Pulsa / para enfocar, Enter para abrir el mejor resultado",
+    ),
+    ("search.results", "Sitios coincidentes"),
+    ("sites.label", "Sitios supervisados"),
+    ("sites.other", "Otros sitios"),
+    ("widgets.label", "Widgets"),
+    ("card.status_summary", "Resumen de estado de"),
+    ("card.tags_for", "Etiquetas de"),
+    ("card.history_for", "Historial de disponibilidad de"),
+    ("card.loading_history", "Cargando historial…"),
+    ("settings.open", "Abrir ajustes"),
+    ("settings.title", "Ajustes"),
+    ("settings.description", "Configuración del panel"),
+    ("settings.close", "Cerrar ajustes"),
+    (
+        "settings.notifications",
+        "Activar notificaciones del sistema cuando cambie el estado de un sitio",
+    ),
+    ("settings.enable_alerts", "Activar alertas"),
+    ("settings.global", "Ajustes generales"),
+    ("settings.theme", "Tema"),
+    ("settings.theme_default", "Predeterminado del panel"),
+    ("settings.theme_auto", "Automático"),
+    ("settings.theme_dark", "Oscuro"),
+    ("settings.theme_light", "Claro"),
+    (
+        "settings.theme_help",
+        "Cambia entre fondos claros y oscuros para el reloj, los botones y las tarjetas en este navegador. Automático sigue el ajuste del sistema.",
+    ),
+    ("settings.site_name", "Nombre del panel"),
+    ("settings.clock_format", "Formato del reloj"),
+    ("settings.clock_none", "Sin reloj"),
+    ("settings.clock_24", "Formato de 24 horas"),
+    ("settings.clock_12", "Formato de 12 horas"),
+    ("settings.telemetry", "Endpoint de OpenTelemetry"),
+    (
+        "settings.telemetry_help",
+        "Opcional: envía instantáneas de disponibilidad a un recolector externo para guardar el historial.",
+    ),
+    ("settings.sites", "Sitios"),
+    ("settings.new_site", "Añadir sitio nuevo"),
+    ("settings.new_site_placeholder", "Nombre del sitio"),
+    ("settings.url", "URL"),
+    ("settings.category", "Categoría"),
+    ("settings.category_placeholder", "Categoría (p. ej., Trabajo)"),
+    ("settings.tags", "Etiquetas"),
+    ("settings.tags_placeholder", "Etiquetas (separadas por comas)"),
+    ("settings.add_site", "Añadir sitio"),
+    ("settings.test", "Probar"),
+    (
+        "settings.tags_help",
+        "Las etiquetas se separan con comas y se pueden editar después.",
+    ),
+    ("settings.open_page", "Abrir página de ajustes"),
+    ("settings.save", "Guardar ajustes"),
+    ("settings.reset", "Restablecer"),
+    ("status.up", "Activo"),
+    ("status.down", "Caído"),
+    ("status.disabled", "En pausa"),
+    ("status.loading", "Cargando"),
+    ("status.unknown", "Desconocido"),
+    ("detail.unreachable", "Inaccesible"),
+    ("detail.checking", "Comprobando..."),
+    ("detail.paused", "Supervisión en pausa"),
+    ("detail.response", "Respuesta"),
+    ("detail.checked_ago", "comprobado hace {}"),
+    ("history.empty", "Sin historial todavía"),
+    ("history.empty_label", "Sin historial de disponibilidad todavía"),
+    ("response.average", "Media"),
+    ("response.not_available", "No disponible"),
+    ("report.title", "Informe de estado"),
+    ("report.generated", "generado"),
+    ("report.statuses", "Estado actual"),
+    ("report.site", "Sitio"),
+    ("report.status", "Estado"),
+    ("report.uptime", "Disponibilidad"),
+    ("report.response", "Respuesta media"),
+    ("report.checks", "Comprobaciones"),
+    ("report.incidents", "Incidentes recientes"),
+    ("report.no_incidents", "No hay incidentes en las comprobaciones registradas."),
+    ("report.started", "Inicio"),
+    ("report.ended", "Fin"),
+    ("report.ongoing", "En curso"),
+    ("report.duration", "Duración"),
+    ("report.reason", "Motivo"),
+];
+
+/// French text
+const FR: &[(&str, &str)] = &[
+    ("header.current_time", "Heure actuelle"),
+    ("header.world_clocks", "Horloges mondiales"),
+    ("header.weather_in", "Météo à"),
+    (
+        "notice.config_error",
+        "Le fichier de configuration n’a pas été appliqué ; la dernière configuration valide est utilisée.",
+    ),
+    (
+        "notice.clock_behind",
+        "L’horloge du serveur retarde de {} sur les sites vérifiés ; les heures enregistrées et la disponibilité sont faussées.",
+    ),
+    (
+        "notice.clock_ahead",
+        "L’horloge du serveur avance de {} sur les sites vérifiés ; les heures enregistrées et la disponibilité sont faussées.",
+    ),
+    ("outage.title", "Sites hors service"),
+    ("outage.down_for", "hors service depuis"),
+    ("nav.label", "Raccourcis"),
+    ("filters.open", "Ouvrir les filtres"),
+    ("filters.region", "Rechercher et filtrer les sites"),
+    ("filters.title", "Filtrer les sites"),
+    ("filters.close", "Fermer les filtres"),
+    (
+        "filters.description",
+        "Utilisez la recherche et les filtres par étiquette pour affiner les résultats. Appuyez sur Échap pour fermer ce panneau.",
+    ),
+    ("filters.by_tag", "Filtrer par étiquette"),
+    ("filters.tags", "Filtres par étiquette"),
+    (
+        "filters.empty",
+        "Aucun site ne correspond à votre recherche ou à vos filtres.",
+    ),
+    ("search.label", "Rechercher des sites"),
+    ("search.placeholder", "Rechercher dans les favoris..."),
+    (
+        "search.hint",
+        "Appuyez sur / pour rechercher, Entrée pour ouvrir le meilleur résultat",
+    ),
+    ("search.results", "Sites correspondants"),
+    ("sites.label", "Sites surveillés"),
+    ("sites.other", "Autres sites"),
+    ("widgets.label", "Widgets"),
+    ("card.status_summary", "Résumé de l’état de"),
+    ("card.tags_for", "Étiquettes de"),
+    ("card.history_for", "Historique de disponibilité de"),
+    ("card.loading_history", "Chargement de l’historique…"),
+    ("settings.open", "Ouvrir les réglages"),
+    ("settings.title", "Réglages"),
+    ("settings.description", "Configuration du tableau de bord"),
+    ("settings.close", "Fermer les réglages"),
+    (
+        "settings.notifications",
+        "Activer les notifications système lors des changements d’état",
+    ),
+    ("settings.enable_alerts", "Activer les alertes"),
+    ("settings.global", "Réglages généraux"),
+    ("settings.theme", "Thème"),
+    ("settings.theme_default", "Par défaut du tableau de bord"),
+    ("settings.theme_auto", "Automatique"),
+    ("settings.theme_dark", "Sombre"),
+    ("settings.theme_light", "Clair"),
+    (
+        "settings.theme_help",
+        "Bascule entre fonds clairs et sombres pour l’horloge, les boutons et les cartes dans ce navigateur. Automatique suit le réglage du système.",
+    ),
+    ("settings.site_name", "Nom du tableau de bord"),
+    ("settings.clock_format", "Format de l’horloge"),
+    ("settings.clock_none", "Pas d’horloge"),
+    ("settings.clock_24", "Format 24 heures"),
+    ("settings.clock_12", "Format 12 heures"),
+    ("settings.telemetry", "Point de collecte OpenTelemetry"),
+    (
+        "settings.telemetry_help",
+        "Facultatif : envoie les relevés de disponibilité à un collecteur externe pour conserver l’historique.",
+    ),
+    ("settings.sites", "Sites"),
+    ("settings.new_site", "Ajouter un site"),
+    ("settings.new_site_placeholder", "Nom du site"),
+    ("settings.url", "URL"),
+    ("settings.category", "Catégorie"),
+    ("settings.category_placeholder", "Catégorie (p. ex. Travail)"),
+    ("settings.tags", "Étiquettes"),
+    (
+        "settings.tags_placeholder",
+        "Étiquettes (séparées par des virgules)",
+    ),
+    ("settings.add_site", "Ajouter le site"),
+    ("settings.test", "Tester"),
+    (
+        "settings.tags_help",
+        "Les étiquettes sont séparées par des virgules et modifiables ensuite.",
+    ),
+    ("settings.open_page", "Ouvrir la page des réglages"),
+    ("settings.save", "Enregistrer les réglages"),
+    ("settings.reset", "Réinitialiser"),
+    ("status.up", "En ligne"),
+    ("status.down", "Hors ligne"),
+    ("status.disabled", "En pause"),
+    ("status.loading", "Chargement"),
+    ("status.unknown", "Inconnu"),
+    ("detail.unreachable", "Injoignable"),
+    ("detail.checking", "Vérification..."),
+    ("detail.paused", "Surveillance en pause"),
+    ("detail.response", "Réponse"),
+    ("detail.checked_ago", "vérifié il y a {}"),
+    ("history.empty", "Pas encore d’historique"),
+    ("history.empty_label", "Pas encore d’historique de disponibilité"),
+    ("response.average", "Moy."),
+    ("response.not_available", "Non disponible"),
+    ("report.title", "Rapport d'état"),
+    ("report.generated", "généré"),
+    ("report.statuses", "État actuel"),
+    ("report.site", "Site"),
+    ("report.status", "État"),
+    ("report.uptime", "Disponibilité"),
+    ("report.response", "Réponse moyenne"),
+    ("report.checks", "Vérifications"),
+    ("report.incidents", "Incidents récents"),
+    ("report.no_incidents", "Aucun incident dans les vérifications enregistrées."),
+    ("report.started", "Début"),
+    ("report.ended", "Fin"),
+    ("report.ongoing", "En cours"),
+    ("report.duration", "Durée"),
+    ("report.reason", "Raison"),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_language_translates_every_english_key() {
+        for language in [Language::De, Language::Es, Language::Fr] {
+            for (key, _) in EN {
+                assert!(
+                    lookup(language.table(), key).is_some(),
+                    "{language} is missing {key}"
+                );
+            }
+            for (key, _) in language.table() {
+                assert!(lookup(EN, key).is_some(), "{language} has unknown {key}");
+            }
+        }
+        for key in FRONTEND_KEYS {
+            assert!(
+                lookup(EN, key).is_some(),
+                "frontend key {key} is not defined"
+            );
+        }
+    }
+
+    #[test]
+    fn unknown_keys_fall_back_to_the_key() {
+        assert_eq!(Language::Fr.translate("no.such.key"), "no.such.key");
+        assert!("pt".parse::<Language>().unwrap_err().contains("\"pt\""));
+    }
+}
@@ -0,0 +1,231 @@
+//! Site icons
+//!
+//! A site's `icon` is either an image URL (`https://...` or a path on this server such
+//! as `/static/icons/nas.png`) or the name of one of the icons bundled with Iron Shield.
+//! Bundled icons are rendered as inline SVG, so they follow the theme's text color and
+//! work without network access.
+//!
+//! Names from the Material Design Icons and Font Awesome sets can be used with their
+//! set prefix, such as `mdi:router-wireless` or `fa:github`, so icons written for other
+//! dashboards keep working. They resolve to the closest bundled icon (see
+//! [`ICON_ALIASES`]), or to the bundled icon of the same name.
+
+/// Bundled icons as `(name, SVG path data)` pairs, drawn on a 24x24 grid as strokes.
+pub const BUNDLED_ICONS: &[(&str, &str)] = &[
+    ("calendar", "M4 5h16v16H4zM16 3v4M8 3v4M4 10h16"),
+    ("camera", "M3 7h4l2-3h6l2 3h4v13H3zM12 17a4 4 0 100-8 4 4 0 000 8z"),
+    ("chart", "M4 20V4M4 20h16M8 16v-4M12 16V8M16 16v-6"),
+    ("cloud", "M7 18h10a4 4 0 00.6-7.95A6 6 0 006.1 9.2 4.5 4.5 0 007 18z"),
+    ("code", "M8 8l-4 4 4 4M16 8l4 4-4 4M14 5l-4 14"),
+    ("container", "M3 7l9-4 9 4v10l-9 4-9-4zM3 7l9 4 9-4M12 11v10"),
+    (
+        "database",
+        "M4 6c0-1.7 3.6-3 8-3s8 1.3 8 3-3.6 3-8 3-8-1.3-8-3zM4 6v12c0 1.7 3.6 3 8 3s8-1.3 8-3V6M4 12c0 1.7 3.6 3 8 3s8-1.3 8-3",
+    ),
+    ("download", "M12 4v12M7 11l5 5 5-5M4 20h16"),
+    (
+        "github",
+        "M9 19c-5 1.5-5-2.5-7-3m14 6v-3.87a3.37 3.37 0 00-.94-2.61c3.14-.35 6.44-1.54 6.44-7A5.44 5.44 0 0020 4.77 5.07 5.07 0 0019.91 1S18.73.65 16 2.48a13.38 13.38 0 00-7 0C6.27.65 5.09 1 5.09 1A5.07 5.07 0 005 4.77a5.44 5.44 0 00-1.5 3.78c0 5.42 3.3 6.61 6.44 7A3.37 3.37 0 009 18.13V22",
+    ),
+    (
+        "globe",
+        "M12 3a9 9 0 100 18 9 9 0 000-18zM3 12h18M12 3c2.5 2.7 3.8 5.7 3.8 9s-1.3 6.3-3.8 9c-2.5-2.7-3.8-5.7-3.8-9S9.5 5.7 12 3z",
+    ),
+    ("home", "M3 11l9-7 9 7M5 10v10h5v-6h4v6h5V10"),
+    ("lock", "M5 11h14v10H5zM8 11V7a4 4 0 018 0v4"),
+    ("mail", "M3 6h18v12H3zM3 6l9 7 9-7"),
+    ("media", "M4 5h16v14H4zM10 9l5 3-5 3z"),
+    (
+        "music",
+        "M9 18V5l12-2v13M9 18a3 3 0 11-6 0 3 3 0 016 0zM21 16a3 3 0 11-6 0 3 3 0 016 0z",
+    ),
+    ("printer", "M6 9V3h12v6M6 18H4V9h16v9h-2M6 14h12v7H6z"),
+    (
+        "router",
+        "M4 14h16v6H4zM8 17h.01M12 17h.01M12 14V9M8.5 7.5a5 5 0 017 0M6 5a8.5 8.5 0 0112 0",
+    ),
+    ("server", "M4 4h16v6H4zM4 14h16v6H4zM8 7h.01M8 17h.01"),
+    ("shield", "M12 3l8 3v6c0 4.5-3.4 8.3-8 9-4.6-.7-8-4.5-8-9V6z"),
+    ("terminal", "M4 5h16v14H4zM7 9l3 3-3 3M12 15h5"),
+];
+
+/// Icon set prefixes accepted before an icon name, as in `mdi:router`: Material Design
+/// Icons and the Font Awesome styles.
+pub const ICON_SETS: &[&str] = &["fa", "fab", "far", "fas", "mdi"];
+
+/// Material Design Icons and Font Awesome names, without their set prefix, of the
+/// bundled icons they are drawn with.
+pub const ICON_ALIASES: &[(&str, &str)] = &[
+    ("access-point", "router"),
+    ("bar-chart", "chart"),
+    ("box", "container"),
+    ("calendar-days", "calendar"),
+    ("calendar-month", "calendar"),
+    ("cctv", "camera"),
+    ("chart-area", "chart"),
+    ("chart-bar", "chart"),
+    ("chart-line", "chart"),
+    ("cloud-outline", "cloud"),
+    ("code-tags", "code"),
+    ("console", "terminal"),
+    ("cube", "container"),
+    ("docker", "container"),
+    ("earth", "globe"),
+    ("email", "mail"),
+    ("email-outline", "mail"),
+    ("envelope", "mail"),
+    ("film", "media"),
+    ("folder-download", "download"),
+    ("hard-drive", "database"),
+    ("harddisk", "database"),
+    ("house", "home"),
+    ("key", "lock"),
+    ("kubernetes", "container"),
+    ("lock-outline", "lock"),
+    ("movie", "media"),
+    ("music-note", "music"),
+    ("nas", "database"),
+    ("network-wired", "router"),
+    ("play-circle", "media"),
+    ("plex", "media"),
+    ("print", "printer"),
+    ("printer-3d", "printer"),
+    ("router-network", "router"),
+    ("router-wireless", "router"),
+    ("security", "shield"),
+    ("server-network", "server"),
+    ("shield-check", "shield"),
+    ("shield-halved", "shield"),
+    ("television", "media"),
+    ("tv", "media"),
+    ("video", "camera"),
+    ("web", "globe"),
+    ("wifi", "router"),
+];
+
+/// SVG path data of the bundled icon called `name`, which may be prefixed with one of
+/// the [`ICON_SETS`].
+///
+/// # Examples
+///
+/// ```
+/// use iron_shield::icons::bundled_icon;
+///
+/// assert!(bundled_icon("server").is_some());
+/// assert_eq!(bundled_icon("mdi:router-wireless"), bundled_icon("router"));
+/// assert_eq!(bundled_icon("fa:github"), bundled_icon("github"));
+/// assert!(bundled_icon("mdi:no-such-icon").is_none());
+/// assert!(bundled_icon("https://example.com/favicon.ico").is_none());
+/// ```
+#[must_use]
+pub fn bundled_icon(name: &str) -> Option<&'static str> {
+    let name = match name.split_once(':') {
+        Some((set, icon)) if ICON_SETS.contains(&set) => ICON_ALIASES
+            .iter()
+            .find(|(alias, _)| *alias == icon)
+            .map_or(icon, |(_, bundled)| *bundled),
+        Some(_) => return None,
+        None => name,
+    };
+    BUNDLED_ICONS
+        .iter()
+        .find(|(icon, _)| *icon == name)
+        .map(|(_, path)| *path)
+}
+
+/// Returns `true` when `icon` is an image URL rather than a bundled icon name.
+#[must_use]
+pub fn is_image_url(icon: &str) -> bool {
+    icon.starts_with("http://") || icon.starts_with("https://") || icon.starts_with('/')
+}
+
+/// Check that `icon` is an HTTP(S) image URL, a path on this server, or a bundled name.
+///
+/// # Errors
+///
+/// Returns a message, suitable for a [`crate::config::ValidationError`], describing why
+/// the icon is not usable.
+pub fn validate(icon: &str) -> Result<(), String> {
+    if icon.trim().is_empty() {
+        return Err("cannot be empty".to_string());
+    }
+    if icon.starts_with("//") {
+        return Err(
+            "must be an http(s) URL, a path starting with '/', or a bundled icon name".to_string(),
+        );
+    }
+    if icon.starts_with('/') {
+        return Ok(());
+    }
+    if is_image_url(icon) {
+        return url::Url::parse(icon)
+            .map(|_| ())
+            .map_err(|err| format!("is not a valid URL: {err}"));
+    }
+    if bundled_icon(icon).is_some() {
+        return Ok(());
+    }
+    if let Some((set, _)) = icon
+        .split_once(':')
+        .filter(|(set, _)| ICON_SETS.contains(set))
+    {
+        return Err(format!(
+            "\"{icon}\" is not one of the {set} icons bundled with Iron Shield; use a bundled icon name or an image URL"
+        ));
+    }
+    let names: Vec<&str> = BUNDLED_ICONS.iter().map(|(name, _)| *name).collect();
+    Err(format!(
+        "\"{icon}\" is not a URL or a bundled icon name ({})",
+        names.join(", ")
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validates_urls_paths_and_bundled_names() {
+        assert_eq!(validate("https://example.com/icon.png"), Ok(()));
+        assert_eq!(validate("/static/icons/nas.png"), Ok(()));
+        assert_eq!(validate("server"), Ok(()));
+        assert_eq!(validate("mdi:harddisk"), Ok(()));
+        assert_eq!(validate("fas:terminal"), Ok(()));
+        assert!(validate("mdi:unicorn")
+            .unwrap_err()
+            .contains("not one of the mdi icons"));
+
+        assert_eq!(validate(" "), Err("cannot be empty".to_string()));
+        assert!(validate("https://")
+            .unwrap_err()
+            .contains("not a valid URL"));
+        assert!(validate("//evil.example/icon.png").is_err());
+        assert!(validate("javascript:alert(1)")
+            .unwrap_err()
+            .contains("not a URL or a bundled icon name"));
+    }
+
+    #[test]
+    fn bundled_icon_names_are_sorted_and_unique() {
+        let names: Vec<&str> = BUNDLED_ICONS.iter().map(|(name, _)| *name).collect();
+        let mut sorted = names.clone();
+        sorted.sort_unstable();
+        sorted.dedup();
+        assert_eq!(names, sorted);
+    }
+
+    #[test]
+    fn icon_aliases_are_sorted_and_point_at_bundled_icons() {
+        let aliases: Vec<&str> = ICON_ALIASES.iter().map(|(alias, _)| *alias).collect();
+        let mut sorted = aliases.clone();
+        sorted.sort_unstable();
+        sorted.dedup();
+        assert_eq!(aliases, sorted);
+        for (alias, bundled) in ICON_ALIASES {
+            assert!(
+                bundled_icon(bundled).is_some(),
+                "{alias} points at unknown icon {bundled}"
+            );
+        }
+    }
+}
@@ -0,0 +1,631 @@
+//! Importing sites from other tools
+//!
+//! Sites can be imported from another uptime monitor's configuration, so switching to
+//! iron-shield does not mean typing every service in again:
+//!
+//! - A [Gatus](https://gatus.io) YAML config: each entry of `endpoints` becomes a site,
+//!   with its `group` as the category, its `interval` and `client.timeout` as the check
+//!   interval and timeout, and its `[STATUS] == ...`, `[RESPONSE_TIME] < ...`, and
+//!   `[BODY]...` conditions as [`Assertions`].
+//! - An [Uptime Kuma](https://uptime.kuma.pet) JSON backup: each HTTP monitor in
+//!   `monitorList` becomes a site, with its parent group as the category, its tags,
+//!   interval, timeout, retries, accepted status codes, and keyword, and paused monitors
+//!   imported as disabled sites.
+//!
+//! Only HTTP checks can be imported; TCP, ICMP, DNS and similar checks are skipped. Settings
+//! iron-shield has no equivalent for are listed as unsupported in the [`ImportSummary`].
+//!
+//! Files are imported with `POST /api/config/import/monitors`, sending the file as the body,
+//! or with `iron_shield --import-monitors FILE [CONFIG]`. Browser bookmarks are imported
+//! the same way by the `bookmarks` module. Either way, sites whose URL is already
+//! configured are skipped, a site whose name is taken gets a numbered name such as
+//! `Docs (2)`, and when the config defines `categories`, a category is added for each
+//! new category name so the imported sites stay valid.
+
+use crate::assertions::{Assertions, JsonPathAssertion};
+use crate::config::{Category, Config, ConfigFormat, Site, MIN_MONITOR_INTERVAL_SECS};
+use crate::error::{IronShieldError, Result};
+use crate::settings::update_config;
+use crate::uptime::UptimeState;
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Json},
+};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+use tracing::{error, info};
+
+/// What importing a file changed
+#[derive(Debug, Default, Serialize, PartialEq, Eq)]
+pub struct ImportSummary {
+    /// Names of the sites that were added
+    pub added: Vec<String>,
+    /// URLs that were not imported, because they are already configured or are not
+    /// `http` or `https` URLs
+    pub skipped: Vec<String>,
+    /// Names of the categories that were added
+    pub categories: Vec<String>,
+    /// Settings of the imported file that have no equivalent and were left out
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub unsupported: Vec<String>,
+}
+
+/// Sites read from another tool's configuration
+#[derive(Debug, Default)]
+pub struct Converted {
+    /// The sites, named as in the original file
+    pub sites: Vec<Site>,
+    /// Settings that could not be converted, such as `API: condition "[CONNECTED] == true"`
+    pub unsupported: Vec<String>,
+}
+
+/// Add `sites` to `config`
+///
+/// Sites with a URL that is already configured or is not an `http` or `https` URL are
+/// skipped, a site without a name is named after its host, and a name that is taken gets
+/// a number. Returns what was added and skipped; the configuration is not validated.
+pub fn add_sites(config: &mut Config, sites: Vec<Site>) -> ImportSummary {
+    let mut summary = ImportSummary::default();
+    for mut site in sites {
+        let is_web_url = crate::config::check_web_url(&site.url).is_ok();
+        let known_url = crate::config::normalized_url(&site.url);
+        if !is_web_url
+            || config
+                .sites
+                .iter()
+                .any(|existing| crate::config::normalized_url(&existing.url) == known_url)
+        {
+            summary.skipped.push(site.url);
+            continue;
+        }
+
+        if !config.categories.is_empty()
+            && !site.category.is_empty()
+            && config.category(&site.category).is_none()
+        {
+            config.categories.push(Category {
+                name: site.category.clone(),
+                order: None,
+                color: None,
+                icon: None,
+            });
+            summary.categories.push(site.category.clone());
+        }
+
+        if site.name.trim().is_empty() {
+            site.name = url::Url::parse(&site.url)
+                .ok()
+                .and_then(|url| url.host_str().map(str::to_string))
+                .unwrap_or_else(|| site.url.clone());
+        }
+        site.name = unique_site_name(config, &site.name);
+        summary.added.push(site.name.clone());
+        config.sites.push(site);
+    }
+    summary
+}
+
+/// Add `converted` sites to the config file at `config_path`
+///
+/// The config file is backed up (see [`crate::backup`]) and rewritten in its own format,
+/// as the settings API would save it.
+///
+/// # Errors
+///
+/// Returns an error if the configuration is loaded from a directory, cannot be loaded,
+/// is invalid after the import, or cannot be backed up or written.
+pub fn import_file(config_path: &Path, converted: Converted) -> Result<ImportSummary> {
+    if config_path.is_dir() {
+        return Err(IronShieldError::from(format!(
+            "Configuration is loaded from the directory {}; edit its files instead",
+            config_path.display()
+        )));
+    }
+    let mut config = Config::load(&config_path.to_path_buf())?;
+    let mut summary = add_sites(&mut config, converted.sites);
+    summary.unsupported = converted.unsupported;
+    config.validate()?;
+
+    let contents = ConfigFormat::from_path(config_path)
+        .serialize(&config.for_persistence())
+        .map_err(|e| IronShieldError::from(format!("Failed to serialize config: {e}")))?;
+    crate::backup::create_backup(config_path, config.config_backup_retention)?;
+    crate::config::write_atomic(config_path, &contents)
+        .map_err(|e| IronShieldError::from(format!("Failed to write config file: {e}")))?;
+    Ok(summary)
+}
+
+/// Add `converted` sites to the running configuration and save it
+///
+/// Shared by the import endpoints; `source` names what is imported in log messages.
+pub(crate) fn import_response(
+    state: &UptimeState,
+    converted: Converted,
+    source: &str,
+) -> axum::response::Response {
+    info!("Importing {} sites from {source}", converted.sites.len());
+    let Converted { sites, unsupported } = converted;
+
+    match update_config(state, None, |config| Ok(add_sites(config, sites))) {
+        Ok((_, mut summary)) => {
+            info!(
+                "Imported {} sites from {source} and skipped {}",
+                summary.added.len(),
+                summary.skipped.len()
+            );
+            summary.unsupported = unsupported;
+            (StatusCode::OK, Json(summary)).into_response()
+        }
+        Err(e) => {
+            error!("Error importing {source}: {e}");
+            e.into_response()
+        }
+    }
+}
+
+/// Read sites from a Gatus config or an Uptime Kuma backup
+///
+/// A JSON document with a `monitorList` is read as an Uptime Kuma backup, and anything
+/// else as a Gatus YAML config.
+///
+/// # Errors
+///
+/// Returns a [`IronShieldError::ConfigValidation`] error if the file cannot be parsed or
+/// lists no monitors.
+///
+/// # Examples
+///
+/// ```
+/// use iron_shield::import::convert_monitors;
+///
+/// let gatus = r#"
+/// endpoints:
+///   - name: Website
+///     group: Public
+///     url: https://example.com/health
+///     interval: 1m
+///     conditions:
+///       - "[STATUS] == 200"
+///       - "[RESPONSE_TIME] < 300"
+/// "#;
+///
+/// let converted = convert_monitors(gatus).unwrap();
+/// let site = &converted.sites[0];
+/// assert_eq!(site.category, "Public");
+/// assert_eq!(site.monitor_interval_secs, Some(60));
+/// let assertions = site.assertions.as_ref().unwrap();
+/// assert_eq!(assertions.status, [200]);
+/// assert_eq!(assertions.max_latency_ms, Some(300));
+/// ```
+pub fn convert_monitors(contents: &str) -> Result<Converted> {
+    let kuma = serde_json::from_str::<Value>(contents)
+        .ok()
+        .filter(|value| value.get("monitorList").is_some());
+    if let Some(backup) = kuma {
+        return from_uptime_kuma(&backup);
+    }
+
+    let gatus: Value = serde_yaml::from_str(contents).map_err(|e| invalid_import(&e))?;
+    from_gatus(&gatus)
+}
+
+/// Import a Gatus config or Uptime Kuma backup sent as the request body
+///
+/// Handles `POST /api/config/import/monitors`. The sites are added, then the
+/// configuration is validated, saved, and applied like a full save. Responds with an
+/// [`ImportSummary`].
+///
+/// # Errors
+///
+/// Responds with HTTP 400 if the file cannot be read or the imported sites make the
+/// configuration invalid, and HTTP 500 if the configuration cannot be saved.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use axum::{Router, routing::post};
+/// use iron_shield::import::import_monitors;
+///
+/// let app = Router::new()
+///     .route("/api/config/import/monitors", post(import_monitors));
+/// ```
+pub async fn import_monitors(
+    State(state): State<Arc<UptimeState>>,
+    contents: String,
+) -> impl IntoResponse {
+    match convert_monitors(&contents) {
+        Ok(converted) => import_response(&state, converted, "monitor config"),
+        Err(e) => {
+            error!("Error reading imported monitor config: {e}");
+            e.into_response()
+        }
+    }
+}
+
+/// Sites for the `endpoints` of a Gatus config.
+fn from_gatus(config: &Value) -> Result<Converted> {
+    let endpoints = config
+        .get("endpoints")
+        .and_then(Value::as_array)
+        .ok_or_else(|| invalid_import("expected a Gatus config with `endpoints`"))?;
+
+    let mut converted = Converted::default();
+    for endpoint in endpoints {
+        let name = string(endpoint, "name").unwrap_or_default();
+        let mut assertions = Assertions::default();
+        for condition in endpoint
+            .get("conditions")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(Value::as_str)
+        {
+            if !gatus_condition(condition, &mut assertions) {
+                converted
+                    .unsupported
+                    .push(format!("{name}: condition \"{condition}\""));
+            }
+        }
+
+        let mut site = new_site(name, string(endpoint, "url").unwrap_or_default());
+        site.category = string(endpoint, "group").unwrap_or_default();
+        site.disabled = endpoint.get("enabled").and_then(Value::as_bool) == Some(false);
+        site.monitor_interval_secs = string(endpoint, "interval")
+            .and_then(|interval| go_duration_secs(&interval))
+            .map(|secs| secs.max(MIN_MONITOR_INTERVAL_SECS));
+        site.monitor_timeout_secs = endpoint
+            .get("client")
+            .and_then(|client| string(client, "timeout"))
+            .and_then(|timeout| go_duration_secs(&timeout))
+            .map(|secs| secs.max(1));
+        site.assertions = (assertions != Assertions::default()).then_some(assertions);
+        converted.sites.push(site);
+    }
+    Ok(converted)
+}
+
+/// Add the assertion for one Gatus condition, returning `false` if it has none.
+fn gatus_condition(condition: &str, assertions: &mut Assertions) -> bool {
+    let Some((left, operator, right)) = ["==", "<=", "<"].iter().find_map(|operator| {
+        condition
+            .split_once(operator)
+            .map(|(left, right)| (left.trim(), *operator, right.trim()))
+    }) else {
+        return false;
+    };
+
+    match (left, operator) {
+        ("[STATUS]", "==") => right
+            .parse()
+            .map(|status| assertions.status.push(status))
+            .is_ok(),
+        ("[RESPONSE_TIME]", "<" | "<=") => right
+            .parse()
+            .map(|ms: u64| assertions.max_latency_ms = Some(ms))
+            .is_ok(),
+        ("[BODY]", "==") => match right
+            .strip_prefix("pat(*")
+            .and_then(|pattern| pattern.strip_suffix("*)"))
+        {
+            Some(text) if !text.is_empty() && !text.contains('*') => {
+                assertions.body_contains = Some(text.to_string());
+                true
+            }
+            _ => false,
+        },
+        (body_path, "==") if body_path.starts_with("[BODY].") => {
+            let equals = serde_json::from_str(right).unwrap_or_else(|_| Value::from(right));
+            assertions.json_path.push(JsonPathAssertion {
+                path: format!("${}", &body_path["[BODY]".len()..]),
+                equals: Some(equals),
+            });
+            true
+        }
+        _ => false,
+    }
+}
+
+/// Seconds in a Go duration such as `30s`, `5m`, or `1h30m`, rounded up.
+fn go_duration_secs(duration: &str) -> Option<u64> {
+    let mut millis = 0u64;
+    let mut rest = duration.trim();
+    if rest.is_empty() {
+        return None;
+    }
+    while !rest.is_empty() {
+        let digits = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        let value: u64 = rest[..digits].parse().ok()?;
+        rest = &rest[digits..];
+        let unit = rest
+            .find(|c: char| c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        let factor = match &rest[..unit] {
+            "ms" => 1,
+            "s" => 1000,
+            "m" => 60_000,
+            "h" => 3_600_000,
+            _ => return None,
+        };
+        millis = millis.checked_add(value.checked_mul(factor)?)?;
+        rest = &rest[unit..];
+    }
+    Some(millis.div_ceil(1000))
+}
+
+/// Sites for the HTTP monitors of an Uptime Kuma backup.
+fn from_uptime_kuma(backup: &Value) -> Result<Converted> {
+    let monitors = backup
+        .get("monitorList")
+        .and_then(Value::as_array)
+        .ok_or_else(|| invalid_import("expected an Uptime Kuma backup with a `monitorList`"))?;
+    let groups: HashMap<i64, String> = monitors
+        .iter()
+        .filter(|monitor| string(monitor, "type").as_deref() == Some("group"))
+        .filter_map(|monitor| Some((monitor.get("id")?.as_i64()?, string(monitor, "name")?)))
+        .collect();
+
+    let mut converted = Converted::default();
+    for monitor in monitors {
+        let kind = string(monitor, "type").unwrap_or_else(|| "http".to_string());
+        if kind == "group" {
+            continue;
+        }
+        let name = string(monitor, "name").unwrap_or_default();
+        match kind.as_str() {
+            "http" | "keyword" => {}
+            "json-query" => converted.unsupported.push(format!(
+                "{name}: JSON query \"{}\"",
+                string(monitor, "jsonPath").unwrap_or_default()
+            )),
+            _ => {
+                converted
+                    .unsupported
+                    .push(format!("{name}: monitor type \"{kind}\""));
+                continue;
+            }
+        }
+
+        let mut site = new_site(name.clone(), string(monitor, "url").unwrap_or_default());
+        site.category = monitor
+            .get("parent")
+            .and_then(Value::as_i64)
+            .and_then(|parent| groups.get(&parent).cloned())
+            .unwrap_or_default();
+        site.tags = monitor
+            .get("tags")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(|tag| string(tag, "name"))
+            .collect();
+        site.disabled = match monitor.get("active") {
+            Some(Value::Bool(active)) => !active,
+            Some(active) => active.as_i64() == Some(0),
+            None => false,
+        };
+        site.monitor_interval_secs = monitor
+            .get("interval")
+            .and_then(Value::as_u64)
+            .map(|secs| secs.max(MIN_MONITOR_INTERVAL_SECS));
+        site.monitor_timeout_secs = monitor
+            .get("timeout")
+            .and_then(Value::as_f64)
+            .filter(|secs| *secs > 0.0)
+            // Kuma timeouts are fractional seconds well within `u64`
+            .map(|secs| {
+                #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+                let secs = secs.ceil() as u64;
+                secs
+            });
+        site.monitor_retries = monitor
+            .get("maxretries")
+            .and_then(Value::as_u64)
+            .filter(|retries| *retries > 0)
+            .and_then(|retries| u32::try_from(retries).ok());
+
+        let mut assertions = Assertions {
+            status: kuma_status_codes(monitor),
+            ..Assertions::default()
+        };
+        if kind == "keyword" {
+            if monitor.get("invertKeyword").and_then(Value::as_bool) == Some(true) {
+                converted
+                    .unsupported
+                    .push(format!("{name}: inverted keyword"));
+            } else {
+                assertions.body_contains =
+                    string(monitor, "keyword").filter(|keyword| !keyword.is_empty());
+            }
+        }
+        site.assertions = (assertions != Assertions::default()).then_some(assertions);
+        converted.sites.push(site);
+    }
+    Ok(converted)
+}
+
+/// The status codes an Uptime Kuma monitor accepts, or none for its default of any 2xx.
+fn kuma_status_codes(monitor: &Value) -> Vec<u16> {
+    let ranges: Vec<&str> = monitor
+        .get("accepted_statuscodes")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_str)
+        .collect();
+    if ranges.is_empty() || ranges == ["200-299"] {
+        return Vec::new();
+    }
+    ranges
+        .iter()
+        .filter_map(|range| {
+            let (start, end) = range.split_once('-').unwrap_or((range, range));
+            Some(start.trim().parse::<u16>().ok()?..=end.trim().parse::<u16>().ok()?)
+        })
+        .flatten()
+        .collect()
+}
+
+/// A site with `name` and `url` and every other setting inherited or unset.
+fn new_site(name: String, url: String) -> Site {
+    Site {
+        name,
+        url,
+        category: String::new(),
+        tags: Vec::new(),
+        monitor_interval_secs: None,
+        monitor_timeout_secs: None,
+        monitor_retries: None,
+        monitor_user_agent: None,
+        disabled: false,
+        icon: None,
+        order: None,
+        assertions: None,
+        uptime_percentage: 0.0,
+    }
+}
+
+/// The string field `key` of `value`, trimmed.
+fn string(value: &Value, key: &str) -> Option<String> {
+    value
+        .get(key)
+        .and_then(Value::as_str)
+        .map(|s| s.trim().to_string())
+}
+
+/// Error for an import file that cannot be read.
+fn invalid_import(message: &(impl std::fmt::Display + ?Sized)) -> IronShieldError {
+    IronShieldError::ConfigValidation {
+        field: "import".to_string(),
+        message: message.to_string(),
+    }
+}
+
+/// `name`, or `name (2)`, `name (3)`, ... if a site already uses it.
+fn unique_site_name(config: &Config, name: &str) -> String {
+    let taken = |candidate: &str| {
+        config
+            .sites
+            .iter()
+            .any(|site| crate::config::same_site_name(&site.name, candidate))
+    };
+    let mut candidate = name.to_string();
+    let mut number = 2;
+    while taken(&candidate) {
+        candidate = format!("{name} ({number})");
+        number += 1;
+    }
+    candidate
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gatus_endpoints_keep_their_checks_and_report_unsupported_conditions() {
+        let converted = convert_monitors(
+            r#"
+endpoints:
+  - name: API
+    group: Core
+    url: "https://api.example.com/health"
+    interval: 2s
+    client:
+      timeout: 1500ms
+    conditions:
+      - "[STATUS] == 200"
+      - "[BODY].status == UP"
+      - "[BODY] == pat(*healthy*)"
+      - "[CERTIFICATE_EXPIRATION] > 48h"
+  - name: Database
+    url: "tcp://db.example.com:5432"
+    enabled: false
+"#,
+        )
+        .unwrap();
+
+        let api = &converted.sites[0];
+        assert_eq!(api.category, "Core");
+        assert_eq!(api.monitor_interval_secs, Some(MIN_MONITOR_INTERVAL_SECS));
+        assert_eq!(api.monitor_timeout_secs, Some(2));
+        let assertions = api.assertions.as_ref().unwrap();
+        assert_eq!(assertions.status, [200]);
+        assert_eq!(assertions.json_path[0].path, "$.status");
+        assert_eq!(assertions.json_path[0].equals, Some(Value::from("UP")));
+        assert_eq!(assertions.body_contains.as_deref(), Some("healthy"));
+        assert_eq!(
+            converted.unsupported,
+            ["API: condition \"[CERTIFICATE_EXPIRATION] > 48h\""]
+        );
+        assert!(converted.sites[1].disabled);
+
+        let mut config = Config::default();
+        let summary = add_sites(&mut config, converted.sites);
+        assert_eq!(summary.added, ["API"]);
+        assert_eq!(summary.skipped, ["tcp://db.example.com:5432"]);
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn uptime_kuma_http_monitors_become_sites_in_their_groups() {
+        let backup = serde_json::json!({
+            "version": "1.23.0",
+            "monitorList": [
+                { "id": 1, "name": "Homelab", "type": "group", "active": true },
+                {
+                    "id": 2, "name": "Plex", "type": "keyword", "parent": 1,
+                    "url": "http://plex.lan:32400", "interval": 60, "timeout": 47.5,
+                    "maxretries": 2, "active": 0, "keyword": "MediaContainer",
+                    "accepted_statuscodes": ["200-299", "401"],
+                    "tags": [{ "name": "media", "value": "" }]
+                },
+                { "id": 3, "name": "Router", "type": "ping", "hostname": "192.168.1.1" },
+                {
+                    "id": 4, "name": "Wiki", "type": "http", "url": "https://wiki.lan",
+                    "accepted_statuscodes": ["200-299"], "active": true
+                }
+            ]
+        });
+
+        let converted = convert_monitors(&backup.to_string()).unwrap();
+
+        assert_eq!(converted.unsupported, ["Router: monitor type \"ping\""]);
+        let names: Vec<&str> = converted
+            .sites
+            .iter()
+            .map(|site| site.name.as_str())
+            .collect();
+        assert_eq!(names, ["Plex", "Wiki"]);
+
+        let plex = &converted.sites[0];
+        assert_eq!(plex.category, "Homelab");
+        assert_eq!(plex.tags, ["media"]);
+        assert!(plex.disabled);
+        assert_eq!(plex.monitor_interval_secs, Some(60));
+        assert_eq!(plex.monitor_timeout_secs, Some(48));
+        assert_eq!(plex.monitor_retries, Some(2));
+        let assertions = plex.assertions.as_ref().unwrap();
+        assert_eq!(assertions.status.len(), 101);
+        assert!(assertions.status.contains(&401));
+        assert_eq!(assertions.body_contains.as_deref(), Some("MediaContainer"));
+
+        let wiki = &converted.sites[1];
+        assert!(!wiki.disabled);
+        assert!(wiki.assertions.is_none());
+    }
+
+    #[test]
+    fn go_durations_are_rounded_up_to_seconds() {
+        assert_eq!(go_duration_secs("30s"), Some(30));
+        assert_eq!(go_duration_secs("1h30m"), Some(5400));
+        assert_eq!(go_duration_secs("1500ms"), Some(2));
+        assert_eq!(go_duration_secs("5 minutes"), None);
+        assert_eq!(go_duration_secs(""), None);
+    }
+}
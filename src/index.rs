@@ -1,13 +1,24 @@
-use crate::config::Clock;
-use crate::settings::{ConfigUpdate, SiteUpdate};
-use crate::uptime::UptimeState;
+use crate::config::{sort_for_display, Clock, Config, Layout, Site, Theme};
+use crate::custom_templates::INDEX_TEMPLATE;
+use crate::error::{IronShieldError, Result};
+use crate::kiosk::current_status;
+use crate::preferences::{request_cookie, Preferences, SigningKey};
+use crate::settings::ConfigUpdate;
+use crate::uptime::{
+    calculate_uptime_percentage, down_since, last_checked, latency_series, sort_sites, unix_now,
+    HistoryEntry, UptimeQuery, UptimeState, UptimeStatus, LATENCY_SERIES_LENGTH,
+};
 use crate::utils;
+use crate::weather::WeatherReport;
 use askama_axum::Template;
 use axum::{
-    extract::State,
-    http::StatusCode,
-    response::{Html, IntoResponse},
+    extract::{Path, Query, State},
+    http::HeaderMap,
+    response::Html,
 };
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::fmt::Write;
 use std::sync::Arc;
 use tracing::error;
 
@@ -20,7 +31,13 @@ use tracing::error;
 /// # Fields
 ///
 /// * `config` - The application configuration containing site name, clock format, and monitored sites
-/// * `current_time` - The current UTC time as a formatted string for display in the template
+/// * `page_title` - Title of the rendered page (the dashboard title or the global site name)
+/// * `categories` - Sites rendered as cards on this page outside any group, under a heading
+///   per category
+/// * `dashboard` - Name of the dashboard being rendered, or empty for the main page
+/// * `filter` - Tag and category the sites were filtered by, if any
+/// * `theme` - Color scheme of the page: the browser's [`THEME_COOKIE`] or the configured one
+/// * `current_time` - The current time in the configured time zone and clock format
 ///
 /// # Examples
 ///
@@ -38,13 +55,20 @@ use tracing::error;
 ///         url: "https://example.com".to_string(),
 ///         category: "Web".to_string(),
 ///         tags: vec!["important".to_string()],
-///         monitor_interval_secs: iron_shield::config::DEFAULT_MONITOR_INTERVAL_SECS,
+///         monitor_interval_secs: Some(iron_shield::config::DEFAULT_MONITOR_INTERVAL_SECS),
+///         monitor_timeout_secs: None,
+///         monitor_retries: None,
+///         monitor_user_agent: None,
 ///         disabled: false,
+///         icon: None,
+///         order: None,
+///         assertions: None,
 ///         uptime_percentage: 99.5,
 ///     }],
+///     ..Config::default()
 /// };
 ///
-/// let current_time = utils::get_current_time_string();
+/// let current_time = utils::get_current_time_string(&config.clock, config.tz());
 /// // Note: IndexTemplate is used internally by the generate_index function
 /// // and is not typically constructed directly in user code
 /// ```
@@ -52,13 +76,537 @@ use tracing::error;
 #[template(path = "index.html")]
 pub struct IndexTemplate {
     /// Application configuration containing site information and settings
-    config: crate::config::Config,
-    /// Current UTC time as a formatted string for display in the template
+    config: Config,
+    /// Title of the rendered page
+    page_title: String,
+    /// Collapsible groups of sites, shown before the ungrouped `sites`
+    groups: Vec<SiteGroup>,
+    /// Sites rendered as cards on this page outside any group, by category
+    categories: Vec<CategorySection>,
+    /// Name of the dashboard being rendered, or empty for the main page
+    dashboard: String,
+    /// Tag and category filters applied to the sites, passed on to the uptime stream
+    filter: IndexQuery,
+    /// Color scheme the page is rendered with
+    theme: Theme,
+    /// Current time in the configured time zone, formatted for display in the template
     current_time: String,
+    /// The configured world clocks with their current time
+    world_clocks: Vec<WorldClockTime>,
+    /// Current weather of the configured weather widget, if it could be fetched
+    weather: Option<WeatherReport>,
     /// JSON representation of the configuration for the frontend settings modal
     config_json: String,
+    /// [`Config::revision`] of the configuration in `config_json`, sent back when saving
+    config_revision: String,
     /// Maximum number of uptime history entries shown in the UI
     max_history_entries: usize,
+    /// Recorded uptime checks of each site, drawn as history bars and sparklines
+    history: HashMap<String, VecDeque<HistoryEntry>>,
+    /// Frontend bundle fingerprint appended to asset URLs for cache busting
+    asset_version: String,
+    /// Why the config file on disk was rejected, shown while the last valid one is in use
+    config_error: Option<String>,
+    /// Seconds the system clock is behind monitored sites, while it is noticeably off;
+    /// see [`crate::clock_drift`]
+    clock_skew: Option<i64>,
+}
+
+/// One recorded check of a site, drawn as a bar of its uptime history strip
+#[derive(Serialize)]
+struct HistoryBar {
+    /// Status of the check, used as the bar's class
+    status: &'static str,
+    /// Status and response time or failure reason, shown as the bar's tooltip
+    label: String,
+}
+
+/// A site of the page that is currently down, listed in the outage banner
+#[derive(Serialize)]
+struct Outage {
+    /// The site's name
+    name: String,
+    /// Unix timestamp in seconds since when the site has been down
+    since: u64,
+    /// How long the site has been down, formatted for display
+    duration: String,
+}
+
+/// A configured world clock with its current time, formatted for display
+#[derive(Serialize)]
+struct WorldClockTime {
+    /// Name shown next to the time
+    label: String,
+    /// IANA time zone name, passed to the frontend to keep the clock ticking
+    timezone: String,
+    /// Current time in the clock's time zone
+    time: String,
+}
+
+/// A configured group together with the sites of the page that belong to it
+struct SiteGroup {
+    /// Heading of the section
+    name: String,
+    /// Whether the section starts collapsed
+    collapsed: bool,
+    /// Sites in the section, in display order
+    sites: Vec<Site>,
+}
+
+/// A site with what its card shows, as template overrides receive it
+#[derive(Serialize)]
+struct SiteCard<'a> {
+    name: &'a str,
+    url: &'a str,
+    category: &'a str,
+    tags: &'a [String],
+    disabled: bool,
+    icon_src: Option<String>,
+    icon_path: Option<&'static str>,
+    status: &'static str,
+    status_label: &'static str,
+    uptime_percentage: String,
+    average_response: String,
+    last_checked: Option<u64>,
+    checked_label: Option<String>,
+    sparkline: String,
+    history: Vec<HistoryBar>,
+}
+
+/// The format clocks tick in on the frontend for `clock`, 24-hour when it is hidden.
+pub(crate) fn clock_format(clock: &Clock) -> &'static str {
+    match clock {
+        Clock::Hour12 => "12hour",
+        Clock::Hour24 | Clock::None => "24hour",
+    }
+}
+
+/// Cookie holding a browser's own choice of [`Theme`], which overrides the configured one
+pub const THEME_COOKIE: &str = "iron_shield_theme";
+
+/// The theme a page is rendered with: the one chosen in the request's [`THEME_COOKIE`]
+/// cookie, then the one in its [`Preferences`], or the configured one.
+pub(crate) fn page_theme(config: &Config, headers: &HeaderMap, key: &SigningKey) -> Theme {
+    request_cookie(headers, THEME_COOKIE)
+        .and_then(|theme| theme.parse().ok())
+        .or_else(|| Preferences::from_headers(headers, key).theme)
+        .unwrap_or(config.theme)
+}
+
+/// Query string of the dashboard pages
+///
+/// `/?tag=media&category=Infra` shows only the sites matching both filters, with the same
+/// rules as the uptime stream (see [`UptimeQuery::matches`]): a site matches `tag` if it
+/// has any of the comma-separated tags and `category` if it is in that category, both
+/// case-insensitively. Filtered views can be bookmarked, for example one per screen.
+#[derive(Debug, Default, Clone, Deserialize)]
+pub struct IndexQuery {
+    /// Comma-separated tags; a site is shown if it has any of them
+    #[serde(default)]
+    pub tag: String,
+    /// Category a site must belong to
+    #[serde(default)]
+    pub category: String,
+}
+
+impl IndexQuery {
+    /// These filters, or the ones in `preferences` when the URL sets none.
+    fn or_preferred(self, preferences: &Preferences) -> Self {
+        if self.tag.is_empty() && self.category.is_empty() {
+            Self {
+                tag: preferences.tag.clone(),
+                category: preferences.category.clone(),
+            }
+        } else {
+            self
+        }
+    }
+
+    /// The sites of `sites` that pass the filters.
+    fn apply(&self, sites: Vec<Site>) -> Vec<Site> {
+        let query = UptimeQuery {
+            dashboard: None,
+            tag: Some(self.tag.clone()),
+            category: Some(self.category.clone()),
+        };
+        sites
+            .into_iter()
+            .filter(|site| query.matches(site))
+            .collect()
+    }
+}
+
+/// Sites of one category, shown under the category's name
+pub(crate) struct CategorySection {
+    /// Heading of the section, or empty for sites without a category
+    pub(crate) name: String,
+    /// Sites in the section, in display order
+    pub(crate) sites: Vec<Site>,
+}
+
+impl IndexTemplate {
+    /// The format clocks tick in on the frontend; world clocks use 24-hour when the main
+    /// clock is hidden.
+    fn clock_format(&self) -> &'static str {
+        clock_format(&self.config.clock)
+    }
+
+    /// Text for `key` in the configured language; see [`crate::i18n`].
+    fn t<'a>(&self, key: &'a str) -> &'a str {
+        self.config.language.translate(key)
+    }
+
+    /// Whether the sites render as one list, without group or category headings.
+    fn is_flat(&self) -> bool {
+        self.groups.is_empty()
+            && self
+                .categories
+                .iter()
+                .all(|section| section.name.is_empty())
+    }
+
+    /// SVG path of the response-time sparkline of the site named `site`.
+    fn sparkline(&self, site: &str) -> String {
+        self.history
+            .get(site)
+            .map(|entries| sparkline_path(&latency_series(entries)))
+            .unwrap_or_default()
+    }
+
+    /// The last `max_history_entries` checks of the site named `site`, oldest first, as
+    /// the frontend draws them once the uptime stream connects.
+    fn history_bars(&self, site: &str) -> Vec<HistoryBar> {
+        let Some(entries) = self.history.get(site) else {
+            return Vec::new();
+        };
+        let skip = entries.len().saturating_sub(self.max_history_entries);
+        entries
+            .iter()
+            .skip(skip)
+            .map(|entry| {
+                let (status, detail) = match entry.status {
+                    UptimeStatus::Up => ("up", self.response_label(entry.response_time_ms)),
+                    UptimeStatus::Down => (
+                        "down",
+                        entry
+                            .failure_reason
+                            .clone()
+                            .unwrap_or_else(|| self.t("detail.unreachable").to_string()),
+                    ),
+                    UptimeStatus::Loading => ("loading", self.t("detail.checking").to_string()),
+                    UptimeStatus::Disabled => ("disabled", self.t("detail.paused").to_string()),
+                };
+                let status_key = format!("status.{status}");
+                HistoryBar {
+                    status,
+                    label: format!("{} – {detail}", self.t(&status_key)),
+                }
+            })
+            .collect()
+    }
+
+    /// Sites of the page whose latest completed check failed, down longest first.
+    fn outages(&self) -> Vec<Outage> {
+        let now = unix_now();
+        let mut outages: Vec<Outage> = self
+            .history
+            .iter()
+            .filter_map(|(name, entries)| {
+                let since = down_since(entries)?;
+                Some(Outage {
+                    name: name.clone(),
+                    since,
+                    duration: utils::format_duration(now.saturating_sub(since)),
+                })
+            })
+            .collect();
+        outages.sort_by(|a, b| a.since.cmp(&b.since).then_with(|| a.name.cmp(&b.name)));
+        outages
+    }
+
+    /// "Response 120 ms", or "Response 1.50 s" from one second on.
+    fn response_label(&self, response_time_ms: Option<u64>) -> String {
+        #[allow(clippy::cast_precision_loss)]
+        let time = match response_time_ms {
+            Some(ms) => format_response_time(ms as f64),
+            None => self.t("response.not_available").to_string(),
+        };
+        format!("{} {time}", self.t("detail.response"))
+    }
+
+    /// Status of `site` from its latest recorded check, so the card is colored before
+    /// the uptime stream connects; see [`current_status`].
+    fn status(&self, site: &Site) -> &'static str {
+        current_status(site, self.history.get(&site.name))
+    }
+
+    /// Text of the status of `site`, "Loading" until its first check completes.
+    fn status_label(&self, site: &Site) -> &'static str {
+        self.t(match self.status(site) {
+            "up" => "status.up",
+            "down" => "status.down",
+            "disabled" => "status.disabled",
+            _ => "status.loading",
+        })
+    }
+
+    /// Uptime of `site` over its recorded checks, formatted like the frontend does.
+    fn uptime_percentage(&self, site: &Site) -> String {
+        let percentage = self
+            .history
+            .get(&site.name)
+            .map_or(site.uptime_percentage, calculate_uptime_percentage);
+        format!("{percentage:.1}")
+    }
+
+    /// "Avg 120 ms" over the recorded response times of `site`, or "Avg -- ms" without
+    /// any; matches `formatAverageResponseLabel` in the frontend.
+    fn average_response_label(&self, site: &Site) -> String {
+        let times: Vec<u64> = self
+            .history
+            .get(&site.name)
+            .into_iter()
+            .flatten()
+            .filter_map(|entry| entry.response_time_ms)
+            .collect();
+        let average = if times.is_empty() {
+            "-- ms".to_string()
+        } else {
+            #[allow(clippy::cast_precision_loss)]
+            let average = times.iter().sum::<u64>() as f64 / times.len() as f64;
+            format_response_time(average)
+        };
+        format!("{} {average}", self.t("response.average"))
+    }
+
+    /// "The server clock is 5m behind the checked sites, ..." while the clock is skewed.
+    fn clock_drift_notice(&self) -> Option<String> {
+        let skew = self.clock_skew?;
+        let key = if skew > 0 {
+            "notice.clock_behind"
+        } else {
+            "notice.clock_ahead"
+        };
+        let duration = utils::format_duration(skew.unsigned_abs());
+        Some(self.t(key).replacen("{}", &duration, 1))
+    }
+
+    /// When the latest completed check of `site` started; see [`last_checked`].
+    fn last_checked(&self, site: &Site) -> Option<u64> {
+        last_checked(self.history.get(&site.name)?)
+    }
+
+    /// "checked 32s ago" for a check started at `checked_at`; matches `refreshLastChecked`
+    /// in the frontend.
+    fn checked_label(&self, checked_at: u64) -> String {
+        utils::format_relative(self.t("detail.checked_ago"), checked_at, unix_now())
+    }
+
+    /// What the card of `site` shows, for template overrides.
+    fn card<'a>(&self, site: &'a Site) -> SiteCard<'a> {
+        let last_checked = self.last_checked(site);
+        SiteCard {
+            name: &site.name,
+            url: &site.url,
+            category: &site.category,
+            tags: &site.tags,
+            disabled: site.disabled,
+            icon_src: site.icon_src(),
+            icon_path: site.bundled_icon_path(),
+            status: self.status(site),
+            status_label: self.status_label(site),
+            uptime_percentage: self.uptime_percentage(site),
+            average_response: self.average_response_label(site),
+            last_checked,
+            checked_label: last_checked.map(|checked_at| self.checked_label(checked_at)),
+            sparkline: self.sparkline(&site.name),
+            history: self.history_bars(&site.name),
+        }
+    }
+
+    /// What the cards of `sites` show, for template overrides.
+    fn cards<'a>(&self, sites: &'a [Site]) -> Vec<SiteCard<'a>> {
+        sites.iter().map(|site| self.card(site)).collect()
+    }
+
+    /// The variables a template override of this page is rendered with; see
+    /// [`crate::custom_templates`].
+    fn override_context(&self) -> minijinja::Value {
+        let groups: Vec<minijinja::Value> = self
+            .groups
+            .iter()
+            .map(|group| {
+                minijinja::context! {
+                    name => group.name,
+                    collapsed => group.collapsed,
+                    sites => self.cards(&group.sites),
+                }
+            })
+            .collect();
+        let categories: Vec<minijinja::Value> = self
+            .categories
+            .iter()
+            .map(|section| {
+                minijinja::context! {
+                    name => section.name,
+                    sites => self.cards(&section.sites),
+                }
+            })
+            .collect();
+        let language = self.config.language;
+        minijinja::context! {
+            config => self.config,
+            page_title => self.page_title,
+            dashboard => self.dashboard,
+            filter => minijinja::context! {
+                tag => self.filter.tag,
+                category => self.filter.category,
+            },
+            theme => self.theme,
+            clock_format => self.clock_format(),
+            current_time => self.current_time,
+            world_clocks => self.world_clocks,
+            weather => self.weather,
+            groups,
+            categories,
+            outages => self.outages(),
+            config_error => self.config_error,
+            clock_drift_notice => self.clock_drift_notice(),
+            config_json => self.config_json,
+            config_revision => self.config_revision,
+            asset_version => self.asset_version,
+            max_history_entries => self.max_history_entries,
+            t => minijinja::Value::from_function(move |key: &str| {
+                language.translate(key).to_string()
+            }),
+        }
+    }
+}
+
+/// `ms` as "120 ms", or "1.50 s" from one second on; matches `formatResponseTime` in the
+/// frontend.
+fn format_response_time(ms: f64) -> String {
+    if ms >= 1000.0 {
+        format!("{:.2} s", ms / 1000.0)
+    } else {
+        format!("{ms:.0} ms")
+    }
+}
+
+/// Width of the sparkline's SVG view box
+const SPARKLINE_WIDTH: f64 = 100.0;
+/// Height of the sparkline's SVG view box
+const SPARKLINE_HEIGHT: f64 = 24.0;
+
+/// SVG path drawing `series` as a line across the sparkline's view box, scaled between
+/// its fastest and slowest response, with the newest check at the right edge and a gap
+/// for each failed check.
+#[allow(clippy::cast_precision_loss)] // Latencies and positions are far below 2^52
+fn sparkline_path(series: &[Option<u64>]) -> String {
+    let measured = series.iter().flatten();
+    let (Some(&min), Some(&max)) = (measured.clone().min(), measured.max()) else {
+        return String::new();
+    };
+    let step = SPARKLINE_WIDTH / (LATENCY_SERIES_LENGTH - 1) as f64;
+    let offset = LATENCY_SERIES_LENGTH.saturating_sub(series.len());
+    let margin = 2.0;
+
+    let mut path = String::new();
+    let mut pen_down = false;
+    for (index, value) in series.iter().enumerate() {
+        let Some(value) = value else {
+            pen_down = false;
+            continue;
+        };
+        let x = (offset + index) as f64 * step;
+        let y = if max == min {
+            SPARKLINE_HEIGHT / 2.0
+        } else {
+            let scale = (value - min) as f64 / (max - min) as f64;
+            SPARKLINE_HEIGHT - margin - scale * (SPARKLINE_HEIGHT - 2.0 * margin)
+        };
+        let command = if pen_down { 'L' } else { 'M' };
+        let separator = if path.is_empty() { "" } else { " " };
+        let _ = write!(path, "{separator}{command}{x:.1} {y:.1}");
+        pen_down = true;
+    }
+    path
+}
+
+/// Split sites, already sorted with [`Config::sort_by_category`], into one section per
+/// category.
+///
+/// Categories are matched case-insensitively and headed by their defined name when they
+/// have one. Sections follow the category order, then free-form categories in the order
+/// they first appear; sites without a category come last.
+pub(crate) fn category_sections(config: &Config, sites: Vec<Site>) -> Vec<CategorySection> {
+    let mut sections: Vec<CategorySection> = Vec::new();
+    for site in sites {
+        let name = site.category.trim();
+        let name = config
+            .category(name)
+            .map_or_else(|| name.to_string(), |category| category.name.clone());
+        match sections
+            .iter_mut()
+            .find(|section| section.name.eq_ignore_ascii_case(&name))
+        {
+            Some(section) => section.sites.push(site),
+            None => sections.push(CategorySection {
+                name,
+                sites: vec![site],
+            }),
+        }
+    }
+    sections.sort_by_key(|section| section.name.is_empty());
+    sections
+}
+
+/// Split sites into the configured groups and the sites outside any group.
+///
+/// Sites keep their relative order. Groups without any of the given sites are left out,
+/// so a dashboard only shows the groups it has sites for.
+fn group_sites(config: &Config, sites: Vec<Site>) -> (Vec<SiteGroup>, Vec<Site>) {
+    let mut groups: Vec<SiteGroup> = config
+        .groups
+        .iter()
+        .map(|group| SiteGroup {
+            name: group.name.clone(),
+            collapsed: group.collapsed,
+            sites: Vec::new(),
+        })
+        .collect();
+    let mut ungrouped = Vec::new();
+    for site in sites {
+        match config.group_index(&site.name) {
+            Some(index) => groups[index].sites.push(site),
+            None => ungrouped.push(site),
+        }
+    }
+    groups.retain(|group| !group.sites.is_empty());
+    (groups, ungrouped)
+}
+
+/// Sort `sites` for display with [`sort_for_display`], the configured [`sort_sites`]
+/// order, and [`Config::sort_by_category`], returning them with their recorded checks.
+pub(crate) fn sort_with_history(
+    state: &UptimeState,
+    config: &Config,
+    mut sites: Vec<Site>,
+) -> (Vec<Site>, HashMap<String, VecDeque<HistoryEntry>>) {
+    sort_for_display(&mut sites);
+    let history = {
+        let history = state.history.read();
+        sort_sites(&mut sites, config.sort_by, &history);
+        sites
+            .iter()
+            .filter_map(|site| {
+                let entries = history.get(&site.name)?;
+                Some((site.name.clone(), entries.clone()))
+            })
+            .collect()
+    };
+    config.sort_by_category(&mut sites);
+    (sites, history)
 }
 
 /// Generates the index template with loaded configuration
@@ -74,6 +622,9 @@ pub struct IndexTemplate {
 /// # Arguments
 ///
 /// * `State(state)` - The uptime state containing the shared configuration
+/// * `Query(filter)` - Optional tag and category filters (see [`IndexQuery`])
+/// * `headers` - Request headers, whose [`THEME_COOKIE`] and [`Preferences`] cookies
+///   override the theme, layout, and filters
 ///
 /// # Returns
 ///
@@ -82,9 +633,7 @@ pub struct IndexTemplate {
 ///
 /// # Errors
 ///
-/// This function returns an HTTP 500 error response if:
-/// - The configuration read lock cannot be acquired
-/// - The template cannot be rendered
+/// This function returns an HTTP 500 error response if the template cannot be rendered.
 ///
 /// # Examples
 ///
@@ -94,94 +643,171 @@ pub struct IndexTemplate {
 /// use axum::{Router, routing::get};
 /// use iron_shield::index::generate_index;
 /// use iron_shield::uptime::UptimeState;
-/// use std::sync::{Arc, RwLock};
-/// use std::collections::HashMap;
+/// use std::sync::Arc;
+/// use std::collections::{HashMap, VecDeque};
 ///
 /// // Assuming you have an uptime_state set up
 /// let app = Router::new()
 ///     .route("/", get(generate_index));
 /// ```
-pub async fn generate_index(State(state): State<Arc<UptimeState>>) -> impl IntoResponse {
+pub async fn generate_index(
+    State(state): State<Arc<UptimeState>>,
+    Query(filter): Query<IndexQuery>,
+    headers: HeaderMap,
+) -> Result<Html<String>> {
     tracing::debug!("Generating index template");
 
-    // Get the config from the shared state
-    match state.config.read() {
-        Ok(config_guard) => {
-            let config = config_guard.clone(); // Clone the config to avoid holding the lock
-            drop(config_guard); // Explicitly drop the lock as soon as possible
-
-            // Get current UTC time from utility function
-            let current_time = utils::get_current_time_string();
-
-            let config_for_client = ConfigUpdate {
-                site_name: config.site_name.clone(),
-                clock: config.clock.to_string(),
-                opentelemetry_endpoint: config.opentelemetry_endpoint.clone(),
-                sites: config
-                    .sites
-                    .iter()
-                    .map(|site| SiteUpdate {
-                        name: site.name.clone(),
-                        url: site.url.clone(),
-                        category: site.category.clone(),
-                        tags: site.tags.clone(),
-                        monitor_interval_secs: site.monitor_interval_secs,
-                        disabled: site.disabled,
-                    })
-                    .collect(),
-            };
+    // Cloned because the page template owns its configuration
+    let config = Config::clone(&state.config.load());
 
-            let config_json = match serde_json::to_string(&config_for_client) {
-                Ok(json) => json,
-                Err(e) => {
-                    error!("Failed to serialize config for settings modal: {e}");
-                    return (
-                        StatusCode::INTERNAL_SERVER_ERROR,
-                        "Failed to serialize configuration",
-                    )
-                        .into_response();
-                }
-            };
+    let page_title = config.site_name.clone();
+    let sites = config.sites.clone();
+    render_index(
+        &state,
+        config,
+        page_title,
+        sites,
+        String::new(),
+        filter,
+        &headers,
+    )
+    .await
+}
 
-            let template = IndexTemplate {
-                config,
-                current_time,
-                config_json,
-                max_history_entries: crate::uptime::MAX_HISTORY_ENTRIES,
-            };
-            match template.render() {
-                Ok(html) => Html(html).into_response(),
-                Err(e) => {
-                    tracing::error!("Template rendering error: {e}");
-                    (
-                        StatusCode::INTERNAL_SERVER_ERROR,
-                        "Template rendering error",
-                    )
-                        .into_response()
-                }
-            }
-        }
-        Err(e) => {
-            tracing::error!("Configuration read lock error: {e}");
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                "Configuration read lock error",
-            )
-                .into_response()
+/// Generates the page for a named dashboard
+///
+/// Renders the same template as [`generate_index`], limited to the sites listed by the
+/// dashboard and titled with the dashboard title. The page subscribes to
+/// `/uptime?dashboard={name}` so the client only receives updates for its own sites.
+///
+/// # Arguments
+///
+/// * `State(state)` - The uptime state containing the shared configuration
+/// * `Path(name)` - The dashboard name taken from `/d/{name}`
+/// * `Query(filter)` - Optional tag and category filters (see [`IndexQuery`])
+/// * `headers` - Request headers, whose [`THEME_COOKIE`] and [`Preferences`] cookies
+///   override the theme, layout, and filters
+///
+/// # Errors
+///
+/// Returns HTTP 404 when no dashboard with the given name is configured, and HTTP 500
+/// if the template cannot be rendered.
+pub async fn generate_dashboard(
+    State(state): State<Arc<UptimeState>>,
+    Path(name): Path<String>,
+    Query(filter): Query<IndexQuery>,
+    headers: HeaderMap,
+) -> Result<Html<String>> {
+    tracing::debug!("Generating dashboard template for {name}");
+
+    let config = Config::clone(&state.config.load());
+    let Some(dashboard) = config.dashboard(&name) else {
+        return Err(IronShieldError::DashboardNotFound(name));
+    };
+    let page_title = dashboard
+        .title
+        .clone()
+        .unwrap_or_else(|| config.site_name.clone());
+    let sites = config.dashboard_sites(dashboard);
+    render_index(&state, config, page_title, sites, name, filter, &headers).await
+}
+
+/// Renders the index template for the given sites, sorted by [`sort_for_display`] and
+/// the configured [`sort_sites`] order, then grouped by category with
+/// [`Config::sort_by_category`]. Sites listed by a
+/// configured group are rendered in that group's collapsible section and the others
+/// under a heading for their category (see [`category_sections`]). Each card starts with
+/// the status, uptime, and average response time of the site's recorded checks, so a
+/// fresh page is colored before the first uptime event arrives.
+///
+/// The settings modal always receives the full configuration so saving from a
+/// dashboard or filtered page does not drop sites that are not shown on it; the filter
+/// is passed to the page separately. It is built from [`Config::for_persistence`] so
+/// secret references are shown instead of the secrets, while the page itself is rendered
+/// from the running configuration, including environment overrides. The page uses the
+/// theme from [`page_theme`], the layout from the request's [`Preferences`] when it sets
+/// one, and their tag and category filters when the URL sets none. It shows the weather
+/// from [`crate::weather::current`] when a widget is configured.
+async fn render_index(
+    state: &UptimeState,
+    mut config: Config,
+    page_title: String,
+    sites: Vec<Site>,
+    dashboard: String,
+    filter: IndexQuery,
+    headers: &HeaderMap,
+) -> Result<Html<String>> {
+    let weather = crate::weather::current(state).await;
+    // Rendered in the format the clock script ticks in, so first paint matches it
+    let current_time = utils::get_current_time_string(&config.clock, config.tz());
+    let world_clocks = config
+        .clocks
+        .iter()
+        .map(|clock| WorldClockTime {
+            label: clock.label.clone(),
+            timezone: clock.timezone.clone(),
+            time: utils::get_current_time_string(&config.clock, clock.tz()),
+        })
+        .collect();
+    let preferences = Preferences::from_headers(headers, &state.preferences_key);
+    let theme = page_theme(&config, headers, &state.preferences_key);
+    let filter = filter.or_preferred(&preferences);
+    let sites = filter.apply(sites);
+    let config_revision = config.revision();
+    let (sites, history) = sort_with_history(state, &config, sites);
+    let (groups, sites) = group_sites(&config, sites);
+    let categories = category_sections(&config, sites);
+
+    let config_json = serde_json::to_string(&ConfigUpdate::from(&config.for_persistence()))
+        .map_err(IronShieldError::JsonParse)
+        .inspect_err(|e| error!("Failed to serialize config for settings modal: {e}"))?;
+
+    // The settings modal keeps the configured layout; only this page uses the preferred one
+    if let Some(layout) = preferences.layout {
+        config.layout = layout;
+    }
+
+    let template = IndexTemplate {
+        config,
+        page_title,
+        groups,
+        categories,
+        dashboard,
+        filter,
+        theme,
+        current_time,
+        world_clocks,
+        weather,
+        config_json,
+        config_revision,
+        max_history_entries: crate::uptime::MAX_HISTORY_ENTRIES,
+        history,
+        asset_version: state.asset_version.clone(),
+        config_error: state.config_error.read().clone(),
+        clock_skew: state.clock_drift.skew(),
+    };
+    if let Some(dir) = template.config.templates_dir.as_deref() {
+        let dir = crate::config::include_base_dir(&state.config_file_path).join(dir);
+        let context = template.override_context();
+        if let Some(html) = state.templates.render(&dir, INDEX_TEMPLATE, context) {
+            return Ok(Html(html));
         }
     }
+    let html = template
+        .render()
+        .map_err(IronShieldError::TemplateRender)
+        .inspect_err(|e| tracing::error!("{e}"))?;
+    Ok(Html(html))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::config::{Config, Site};
-    use axum::http::StatusCode;
+    use axum::http::{header, StatusCode};
+    use axum::response::IntoResponse;
     use http_body_util::BodyExt;
-    use std::collections::HashMap;
-    use std::sync::RwLock;
-    use tokio::sync::broadcast;
-    use tokio_util::sync::CancellationToken;
+    use std::collections::{HashMap, VecDeque};
 
     /// Helper function to build a configuration for testing
     ///
@@ -206,17 +832,24 @@ mod tests {
                 url: "https://docs.example.com".to_string(),
                 category: "Reference".to_string(),
                 tags: vec!["docs".to_string()],
-                monitor_interval_secs: crate::config::DEFAULT_MONITOR_INTERVAL_SECS,
+                monitor_interval_secs: Some(crate::config::DEFAULT_MONITOR_INTERVAL_SECS),
+                monitor_timeout_secs: None,
+                monitor_retries: None,
+                monitor_user_agent: None,
                 disabled: false,
+                icon: None,
+                order: None,
+                assertions: None,
                 uptime_percentage: 99.9,
             }],
+            ..Config::default()
         }
     }
 
     /// Helper function to build an uptime state for testing
     ///
     /// Creates an uptime state containing the given configuration wrapped in
-    /// the necessary Arc and `RwLock` structures for sharing between threads.
+    /// the shared structures used across threads.
     ///
     /// # Arguments
     ///
@@ -226,13 +859,9 @@ mod tests {
     ///
     /// Returns an `Arc<UptimeState>` instance suitable for testing
     fn build_state(config: Config) -> Arc<UptimeState> {
-        let (shutdown_events, _) = broadcast::channel(1);
         Arc::new(UptimeState {
-            config: Arc::new(RwLock::new(config)),
-            history: Arc::new(RwLock::new(HashMap::new())),
-            config_file_path: std::path::PathBuf::from("test-config.json5"),
-            shutdown_events,
-            shutdown_token: CancellationToken::new(),
+            asset_version: "test".to_string(),
+            ..UptimeState::new(config, std::path::PathBuf::from("test-config.json5"))
         })
     }
 
@@ -245,10 +874,23 @@ mod tests {
     fn index_template_renders_site_name_and_clock() {
         let config = build_config("Test Dashboard", Clock::Hour12);
         let template = IndexTemplate {
+            page_title: config.site_name.clone(),
+            groups: Vec::new(),
+            categories: category_sections(&config, config.sites.clone()),
+            dashboard: String::new(),
+            filter: IndexQuery::default(),
+            theme: Theme::Auto,
             config,
             current_time: "10:00:00 UTC".to_string(),
+            world_clocks: Vec::new(),
+            weather: None,
             config_json: "{}".to_string(),
+            config_revision: String::new(),
             max_history_entries: crate::uptime::MAX_HISTORY_ENTRIES,
+            history: HashMap::new(),
+            asset_version: "abc123".to_string(),
+            config_error: None,
+            clock_skew: None,
         };
 
         let rendered = template
@@ -267,6 +909,433 @@ mod tests {
             rendered.contains("https://docs.example.com"),
             "rendered template should include site URL"
         );
+        assert!(
+            rendered.contains("/static/main.js?v=abc123"),
+            "rendered template should version asset URLs"
+        );
+    }
+
+    #[test]
+    /// Test that the weather report renders in the header even without a clock
+    fn index_template_renders_weather_widget() {
+        let config = build_config("Weather", Clock::None);
+        let template = IndexTemplate {
+            page_title: config.site_name.clone(),
+            groups: Vec::new(),
+            categories: category_sections(&config, config.sites.clone()),
+            dashboard: String::new(),
+            filter: IndexQuery::default(),
+            theme: Theme::Auto,
+            config,
+            current_time: "10:00 UTC".to_string(),
+            world_clocks: Vec::new(),
+            weather: Some(WeatherReport {
+                location: "Berlin".to_string(),
+                temperature: 11.6,
+                unit: "°C",
+                description: "light rain".to_string(),
+            }),
+            config_json: "{}".to_string(),
+            config_revision: String::new(),
+            max_history_entries: crate::uptime::MAX_HISTORY_ENTRIES,
+            history: HashMap::new(),
+            asset_version: "test".to_string(),
+            config_error: None,
+            clock_skew: None,
+        };
+
+        let rendered = template
+            .render()
+            .expect("Template rendering should succeed in test");
+
+        assert!(rendered.contains("<header role=\"banner\">"));
+        assert!(!rendered.contains("id=\"clock-container\""));
+        assert!(rendered.contains("aria-label=\"Weather in Berlin\""));
+        assert!(rendered.contains("<span class=\"weather-temperature\">12°C</span>"));
+        assert!(rendered.contains("<span class=\"weather-description\">light rain</span>"));
+    }
+
+    #[test]
+    /// Test that the list and compact layouts leave out the details they do not show
+    fn index_template_renders_configured_layout() {
+        for (layout, history, sparkline) in [
+            (Layout::Grid, true, true),
+            (Layout::List, true, false),
+            (Layout::Compact, false, false),
+        ] {
+            let mut config = build_config("Layout", Clock::None);
+            config.layout = layout;
+            let template = IndexTemplate {
+                page_title: config.site_name.clone(),
+                groups: Vec::new(),
+                categories: category_sections(&config, config.sites.clone()),
+                dashboard: String::new(),
+                filter: IndexQuery::default(),
+                theme: Theme::Auto,
+                config,
+                current_time: "10:00 UTC".to_string(),
+                world_clocks: Vec::new(),
+                weather: None,
+                config_json: "{}".to_string(),
+                config_revision: String::new(),
+                max_history_entries: crate::uptime::MAX_HISTORY_ENTRIES,
+                history: HashMap::new(),
+                asset_version: "test".to_string(),
+                config_error: None,
+                clock_skew: None,
+            };
+
+            let rendered = template
+                .render()
+                .expect("Template rendering should succeed in test");
+
+            assert!(rendered.contains(&format!("<body class=\"layout-{layout}\"")));
+            assert_eq!(rendered.contains("class=\"uptime-history\""), history);
+            assert_eq!(rendered.contains("class=\"latency-sparkline\""), sparkline);
+            assert!(rendered.contains("<span class=\"status-text\">"));
+        }
+    }
+
+    #[test]
+    /// Test that the configured language translates the page and the frontend strings
+    fn index_template_renders_configured_language() {
+        let mut config = build_config("Sprache", Clock::None);
+        config.language = crate::i18n::Language::De;
+        let template = IndexTemplate {
+            page_title: config.site_name.clone(),
+            groups: Vec::new(),
+            categories: category_sections(&config, config.sites.clone()),
+            dashboard: String::new(),
+            filter: IndexQuery::default(),
+            theme: Theme::Auto,
+            config,
+            current_time: "10:00 UTC".to_string(),
+            world_clocks: Vec::new(),
+            weather: None,
+            config_json: "{}".to_string(),
+            config_revision: String::new(),
+            max_history_entries: crate::uptime::MAX_HISTORY_ENTRIES,
+            history: HashMap::new(),
+            asset_version: "test".to_string(),
+            config_error: None,
+            clock_skew: None,
+        };
+
+        let rendered = template
+            .render()
+            .expect("Template rendering should succeed in test");
+
+        assert!(rendered.contains("<html lang=\"de\""));
+        assert!(rendered.contains("<h2 id=\"settings-modal-title\">Einstellungen</h2>"));
+        assert!(rendered.contains("<span class=\"status-text\">Lädt</span>"));
+        assert!(rendered.contains("\"status.down\":\"Offline\""));
+        assert!(!rendered.contains("Global Settings"));
+    }
+
+    #[test]
+    /// Test that widgets render as sandboxed frames and sanitized HTML tiles
+    fn index_template_renders_widgets() {
+        let mut config = build_config("Widgets", Clock::None);
+        config.widgets = vec![
+            crate::widgets::Widget {
+                title: "Calendar".to_string(),
+                iframe: Some("https://calendar.example.com/embed".to_string()),
+                html: None,
+                height: Some(400),
+            },
+            crate::widgets::Widget {
+                title: "Notes".to_string(),
+                iframe: None,
+                html: Some("<p>Hi<script>alert(1)</script></p>".to_string()),
+                height: None,
+            },
+        ];
+        let template = IndexTemplate {
+            page_title: config.site_name.clone(),
+            groups: Vec::new(),
+            categories: category_sections(&config, config.sites.clone()),
+            dashboard: String::new(),
+            filter: IndexQuery::default(),
+            theme: Theme::Auto,
+            config,
+            current_time: "10:00 UTC".to_string(),
+            world_clocks: Vec::new(),
+            weather: None,
+            config_json: "{}".to_string(),
+            config_revision: String::new(),
+            max_history_entries: crate::uptime::MAX_HISTORY_ENTRIES,
+            history: HashMap::new(),
+            asset_version: "test".to_string(),
+            config_error: None,
+            clock_skew: None,
+        };
+
+        let rendered = template
+            .render()
+            .expect("Template rendering should succeed in test");
+
+        assert!(rendered.contains("<h2 class=\"widget-title\">Calendar</h2>"));
+        assert!(rendered.contains("src=\"https://calendar.example.com/embed\""));
+        assert!(rendered.contains("height=\"400\""));
+        assert!(rendered.contains("sandbox=\"allow-scripts allow-forms allow-popups\""));
+        assert!(!rendered.contains("allow-same-origin"));
+        assert!(rendered.contains("max-height: 300px\"><p>Hi</p></div>"));
+        assert!(!rendered.contains("alert(1)"));
+    }
+
+    #[test]
+    /// Test that site icons render as proxied images for URLs and inline SVG for bundled names
+    fn index_template_renders_site_icons() {
+        let mut config = build_config("Icons", Clock::None);
+        config.sites[0].icon = Some("server".to_string());
+        let mut with_image = config.sites[0].clone();
+        with_image.name = "Router".to_string();
+        with_image.icon = Some("https://router.local/favicon.png".to_string());
+        config.sites.push(with_image);
+        let template = IndexTemplate {
+            page_title: config.site_name.clone(),
+            groups: Vec::new(),
+            categories: category_sections(&config, config.sites.clone()),
+            dashboard: String::new(),
+            filter: IndexQuery::default(),
+            theme: Theme::Auto,
+            config,
+            current_time: "10:00 UTC".to_string(),
+            world_clocks: Vec::new(),
+            weather: None,
+            config_json: "{}".to_string(),
+            config_revision: String::new(),
+            max_history_entries: crate::uptime::MAX_HISTORY_ENTRIES,
+            history: HashMap::new(),
+            asset_version: "abc123".to_string(),
+            config_error: None,
+            clock_skew: None,
+        };
+
+        let rendered = template
+            .render()
+            .expect("Template rendering should succeed in test");
+
+        let server_path = crate::icons::bundled_icon("server").expect("bundled icon");
+        assert!(rendered.contains(&format!("d=\"{server_path}\"")));
+        // External icons are proxied so browsers do not contact the icon's host
+        assert!(rendered.contains("<img class=\"site-icon\" src=\"/icons/Router\""));
+        assert!(!rendered.contains("router.local"));
+        assert!(!rendered.contains("src=\"/icons/Docs\""));
+    }
+
+    #[test]
+    /// Test that defined categories render as a colored badge and free-form ones do not
+    fn index_template_renders_category_badges() {
+        let mut config = build_config("Categories", Clock::None);
+        config.categories.push(crate::config::Category {
+            name: config.sites[0].category.clone(),
+            order: None,
+            color: Some("#e11d48".to_string()),
+            icon: Some("media".to_string()),
+        });
+        let mut uncategorized = config.sites[0].clone();
+        uncategorized.name = "Scratch".to_string();
+        uncategorized.category = "Unlisted".to_string();
+        config.sites.push(uncategorized);
+        let template = IndexTemplate {
+            page_title: config.site_name.clone(),
+            groups: Vec::new(),
+            categories: category_sections(&config, config.sites.clone()),
+            dashboard: String::new(),
+            filter: IndexQuery::default(),
+            theme: Theme::Auto,
+            config,
+            current_time: "10:00 UTC".to_string(),
+            world_clocks: Vec::new(),
+            weather: None,
+            config_json: "{}".to_string(),
+            config_revision: String::new(),
+            max_history_entries: crate::uptime::MAX_HISTORY_ENTRIES,
+            history: HashMap::new(),
+            asset_version: "abc123".to_string(),
+            config_error: None,
+            clock_skew: None,
+        };
+
+        let rendered = template
+            .render()
+            .expect("Template rendering should succeed in test");
+
+        assert_eq!(rendered.matches("class=\"tag site-category\"").count(), 1);
+        assert!(rendered.contains("style=\"--category-color: #e11d48\""));
+        assert!(rendered.contains("<svg class=\"category-icon\""));
+        assert!(!rendered.contains("data-category=\"Unlisted\""));
+    }
+
+    #[tokio::test]
+    /// Test that grouped sites render in collapsible sections before the ungrouped ones
+    async fn generate_index_renders_site_groups() {
+        let mut config = build_config("Grouped", Clock::None);
+        for name in ["Plex", "Jellyfin", "Router"] {
+            let mut site = config.sites[0].clone();
+            site.name = name.to_string();
+            config.sites.push(site);
+        }
+        config.groups = vec![
+            crate::config::Group {
+                name: "Media".to_string(),
+                sites: vec!["Jellyfin".to_string(), "Plex".to_string()],
+                collapsed: true,
+            },
+            crate::config::Group {
+                name: "Network".to_string(),
+                sites: vec!["Router".to_string()],
+                collapsed: false,
+            },
+            crate::config::Group {
+                name: "Empty".to_string(),
+                sites: vec!["Missing".to_string()],
+                collapsed: false,
+            },
+        ];
+        let state = build_state(config);
+
+        let response = generate_index(State(state), Query(IndexQuery::default()), HeaderMap::new())
+            .await
+            .into_response();
+        let body_bytes = response
+            .into_body()
+            .collect()
+            .await
+            .expect("Failed to collect response body")
+            .to_bytes();
+        let body = String::from_utf8(body_bytes.to_vec()).expect("Body should be UTF-8");
+
+        assert!(body.contains("<details class=\"site-group\" data-group=\"Media\">"));
+        assert!(body.contains("<details class=\"site-group\" data-group=\"Network\" open>"));
+        assert!(!body.contains("data-group=\"Empty\""));
+        let position = |needle: &str| body.find(needle).expect("should be rendered");
+        // Sites keep config order within a group and ungrouped sites come last
+        assert!(position("data-site-name=\"Plex\"") < position("data-site-name=\"Jellyfin\""));
+        assert!(
+            position("data-group=\"Network\"") < position("data-category-section=\"Reference\"")
+        );
+        assert!(
+            position("data-category-section=\"Reference\"") < position("data-site-name=\"Docs\"")
+        );
+    }
+
+    #[tokio::test]
+    /// Test that sites render under a heading per category in the category order
+    async fn generate_index_renders_category_headings() {
+        let mut config = build_config("Categorized", Clock::None);
+        for (name, category) in [
+            ("Plex", "media"),
+            ("Scratch", ""),
+            ("Router", "Network"),
+            ("Jellyfin", "Media"),
+        ] {
+            let mut site = config.sites[0].clone();
+            site.name = name.to_string();
+            site.category = category.to_string();
+            config.sites.push(site);
+        }
+        config.categories.push(crate::config::Category {
+            name: "Media".to_string(),
+            order: Some(1),
+            color: None,
+            icon: None,
+        });
+        let state = build_state(config);
+
+        let response = generate_index(State(state), Query(IndexQuery::default()), HeaderMap::new())
+            .await
+            .into_response();
+        let body_bytes = response
+            .into_body()
+            .collect()
+            .await
+            .expect("Failed to collect response body")
+            .to_bytes();
+        let body = String::from_utf8(body_bytes.to_vec()).expect("Body should be UTF-8");
+
+        assert!(body.contains("class=\"sites-grouped\""));
+        assert!(!body.contains("data-category-section=\"media\""));
+        let position = |needle: &str| body.find(needle).expect("should be rendered");
+        // Defined categories first, then free-form ones as they appear, then the rest
+        let order = [
+            "data-category-section=\"Media\"",
+            "data-site-name=\"Plex\"",
+            "data-site-name=\"Jellyfin\"",
+            "data-category-section=\"Reference\"",
+            "data-category-section=\"Network\"",
+            "site-group-ungrouped",
+            "data-site-name=\"Scratch\"",
+        ];
+        for pair in order.windows(2) {
+            assert!(position(pair[0]) < position(pair[1]), "{pair:?}");
+        }
+    }
+
+    #[test]
+    /// Test that a page whose sites have no category renders a single flat list
+    fn index_template_without_categories_renders_flat_list() {
+        let mut config = build_config("Flat", Clock::None);
+        config.sites[0].category = String::new();
+        let template = IndexTemplate {
+            page_title: config.site_name.clone(),
+            groups: Vec::new(),
+            categories: category_sections(&config, config.sites.clone()),
+            dashboard: String::new(),
+            filter: IndexQuery::default(),
+            theme: Theme::Auto,
+            config,
+            current_time: "10:00 UTC".to_string(),
+            world_clocks: Vec::new(),
+            weather: None,
+            config_json: "{}".to_string(),
+            config_revision: String::new(),
+            max_history_entries: crate::uptime::MAX_HISTORY_ENTRIES,
+            history: HashMap::new(),
+            asset_version: "abc123".to_string(),
+            config_error: None,
+            clock_skew: None,
+        };
+
+        let rendered = template
+            .render()
+            .expect("Template rendering should succeed in test");
+
+        assert!(!rendered.contains("sites-grouped"));
+        assert!(rendered.contains("data-site-name=\"Docs\""));
+    }
+
+    #[tokio::test]
+    /// Test that the dashboard lists ordered sites first and keeps config order otherwise
+    async fn generate_index_sorts_sites_by_display_order() {
+        let mut config = build_config("Ordered", Clock::None);
+        for (name, order) in [("Wiki", None), ("Mail", Some(2)), ("Chat", Some(1))] {
+            let mut site = config.sites[0].clone();
+            site.name = name.to_string();
+            site.order = order;
+            config.sites.push(site);
+        }
+        let state = build_state(config);
+
+        let response = generate_index(State(state), Query(IndexQuery::default()), HeaderMap::new())
+            .await
+            .into_response();
+        let body_bytes = response
+            .into_body()
+            .collect()
+            .await
+            .expect("Failed to collect response body")
+            .to_bytes();
+        let body = String::from_utf8(body_bytes.to_vec()).expect("Body should be UTF-8");
+
+        let position = |name: &str| {
+            body.find(&format!("data-site-name=\"{name}\""))
+                .expect("site should be rendered")
+        };
+        assert!(position("Chat") < position("Mail"));
+        assert!(position("Mail") < position("Docs"));
+        assert!(position("Docs") < position("Wiki"));
     }
 
     #[tokio::test]
@@ -279,7 +1348,9 @@ mod tests {
         let config = build_config("Async Dashboard", Clock::Hour24);
         let state = build_state(config);
 
-        let response = generate_index(State(state)).await.into_response();
+        let response = generate_index(State(state), Query(IndexQuery::default()), HeaderMap::new())
+            .await
+            .into_response();
         assert_eq!(response.status(), StatusCode::OK);
 
         let (parts, body) = response.into_parts();
@@ -300,5 +1371,708 @@ mod tests {
             "response should include site entry"
         );
         assert_eq!(parts.status, StatusCode::OK);
+        assert!(
+            body_string.contains("class=\"config-error-notice\" role=\"alert\" hidden>"),
+            "notice should stay hidden while the config file is in use"
+        );
+    }
+
+    #[tokio::test]
+    /// Test that a rejected config reload is shown on the dashboard
+    async fn generate_index_shows_rejected_config_reload() {
+        let state = build_state(build_config("Dashboard", Clock::None));
+        *state.config_error.write() =
+            Some("Invalid configuration: sites[0].url is not a valid URL".to_string());
+
+        let response = generate_index(State(state), Query(IndexQuery::default()), HeaderMap::new())
+            .await
+            .into_response();
+        let body_bytes = response
+            .into_body()
+            .collect()
+            .await
+            .expect("Failed to collect response body")
+            .to_bytes();
+        let body_string =
+            String::from_utf8(body_bytes.to_vec()).expect("Body should contain valid UTF-8");
+
+        assert!(body_string.contains("sites[0].url is not a valid URL"));
+        assert!(body_string.contains("class=\"config-error-notice\" role=\"alert\">"));
+    }
+
+    #[tokio::test]
+    /// Test that `?tag=` and `?category=` limit the sites and are passed to the page
+    async fn generate_index_filters_sites_by_tag_and_category() {
+        let mut config = build_config("Filtered", Clock::None);
+        for (name, category, tag) in [
+            ("Plex", "Media", "home"),
+            ("Grafana", "Infra", "home"),
+            ("Prometheus", "infra", "work"),
+        ] {
+            let mut site = config.sites[0].clone();
+            site.name = name.to_string();
+            site.category = category.to_string();
+            site.tags = vec![tag.to_string()];
+            config.sites.push(site);
+        }
+        let state = build_state(config);
+
+        let filter = IndexQuery {
+            tag: "HOME, docs".to_string(),
+            category: "infra".to_string(),
+        };
+        let response = generate_index(State(state), Query(filter), HeaderMap::new())
+            .await
+            .into_response();
+        let body_bytes = response
+            .into_body()
+            .collect()
+            .await
+            .expect("Failed to collect response body")
+            .to_bytes();
+        let body = String::from_utf8(body_bytes.to_vec()).expect("Body should be UTF-8");
+
+        assert!(body.contains("data-site-name=\"Grafana\""));
+        for hidden in ["Docs", "Plex", "Prometheus"] {
+            assert!(!body.contains(&format!("data-site-name=\"{hidden}\"")));
+        }
+        assert!(body.contains("data-tag=\"HOME, docs\" data-category=\"infra\""));
+    }
+
+    #[tokio::test]
+    /// Test that configured world clocks render in a row in the main clock's format
+    async fn generate_index_renders_world_clocks() {
+        let mut config = build_config("World", Clock::None);
+        config.clocks = vec![crate::config::WorldClock {
+            label: "Tokyo".to_string(),
+            timezone: "Asia/Tokyo".to_string(),
+        }];
+        let state = build_state(config);
+
+        let render = |state: Arc<UptimeState>| async move {
+            let response =
+                generate_index(State(state), Query(IndexQuery::default()), HeaderMap::new())
+                    .await
+                    .into_response();
+            let body_bytes = response
+                .into_body()
+                .collect()
+                .await
+                .expect("Failed to collect response body")
+                .to_bytes();
+            String::from_utf8(body_bytes.to_vec()).expect("Body should be UTF-8")
+        };
+
+        let body = render(state.clone()).await;
+        assert!(!body.contains("id=\"clock-container\""));
+        assert!(body.contains("id=\"world-clocks\" class=\"world-clocks\" aria-label=\"World clocks\" data-format=\"24hour\""));
+        assert!(body.contains("<span class=\"world-clock-label\">Tokyo</span>"));
+        let tokyo_time = |body: &str| {
+            let start = body
+                .find("data-timezone=\"Asia/Tokyo\">")
+                .expect("Tokyo clock should render")
+                + "data-timezone=\"Asia/Tokyo\">".len();
+            let end = start + body[start..].find("</time>").expect("Clock should close");
+            body[start..end].to_string()
+        };
+        let time = tokyo_time(&body);
+        assert_eq!(time.len(), 5, "24-hour time expected, got {time}");
+        assert_eq!(&time[2..3], ":");
+
+        state
+            .config
+            .update(|config| {
+                config.clock = Clock::Hour12;
+                Ok(())
+            })
+            .unwrap();
+        let body = render(state).await;
+        assert!(body.contains("id=\"time\"\n                    data-format=\"12hour\""));
+        assert!(body.contains("data-format=\"12hour\">"));
+        let time = tokyo_time(&body);
+        assert!(
+            time.ends_with(" AM") || time.ends_with(" PM"),
+            "12-hour time expected, got {time}"
+        );
+    }
+
+    #[test]
+    /// Test that sparklines span the series length, scale to the range, and break on failures
+    fn sparkline_path_scales_latency_and_breaks_at_failed_checks() {
+        assert_eq!(sparkline_path(&[]), "");
+        assert_eq!(sparkline_path(&[None, None]), "");
+        assert_eq!(
+            sparkline_path(&[Some(100), Some(200), None, Some(150)]),
+            "M89.7 22.0 L93.1 2.0 M100.0 12.0"
+        );
+    }
+
+    #[tokio::test]
+    /// Test that each card renders the sparkline of its site's recorded response times
+    async fn generate_index_renders_latency_sparklines() {
+        let state = build_state(build_config("Latency", Clock::None));
+        let entry = |status, response_time_ms| HistoryEntry {
+            status,
+            response_time_ms,
+            failure_reason: None,
+            checked_at: 0,
+        };
+        state.history.write().insert(
+            "Docs".to_string(),
+            [
+                entry(UptimeStatus::Up, Some(40)),
+                entry(UptimeStatus::Up, Some(80)),
+                entry(UptimeStatus::Loading, None),
+            ]
+            .into(),
+        );
+
+        let response = generate_index(State(state), Query(IndexQuery::default()), HeaderMap::new())
+            .await
+            .into_response();
+        let body_bytes = response
+            .into_body()
+            .collect()
+            .await
+            .expect("Failed to collect response body")
+            .to_bytes();
+        let body = String::from_utf8(body_bytes.to_vec()).expect("Body should be UTF-8");
+
+        assert!(body.contains("<path d=\"M96.6 22.0 L100.0 2.0\" />"));
+    }
+
+    #[tokio::test]
+    /// Test that cards say how long ago their latest completed check was, in the configured language
+    async fn generate_index_renders_when_sites_were_last_checked() {
+        let mut config = build_config("Checked", Clock::None);
+        config.language = crate::i18n::Language::Fr;
+        let state = build_state(config);
+        let render = |state: Arc<UptimeState>| async move {
+            let response =
+                generate_index(State(state), Query(IndexQuery::default()), HeaderMap::new())
+                    .await
+                    .into_response();
+            let body_bytes = response
+                .into_body()
+                .collect()
+                .await
+                .expect("Failed to collect response body")
+                .to_bytes();
+            String::from_utf8(body_bytes.to_vec()).expect("Body should be UTF-8")
+        };
+
+        let body = render(state.clone()).await;
+        assert!(body.contains("<span class=\"last-checked\" hidden></span>"));
+
+        let checked_at = unix_now() - 3600;
+        let entry = |status, checked_at| HistoryEntry {
+            status,
+            response_time_ms: None,
+            failure_reason: None,
+            checked_at,
+        };
+        state.history.write().insert(
+            "Docs".to_string(),
+            [
+                entry(UptimeStatus::Up, checked_at),
+                entry(UptimeStatus::Loading, checked_at + 60),
+            ]
+            .into(),
+        );
+
+        let body = render(state).await;
+        assert!(body.contains(&format!(
+            "<span class=\"last-checked\" data-checked-at=\"{checked_at}\">vérifié il y a 1h</span>"
+        )));
+    }
+
+    #[tokio::test]
+    /// Test that a system clock skewed from the checked sites is called out on the dashboard
+    async fn generate_index_warns_about_a_skewed_clock() {
+        let state = build_state(build_config("Drift", Clock::None));
+        let render = |state: Arc<UptimeState>| async move {
+            let response =
+                generate_index(State(state), Query(IndexQuery::default()), HeaderMap::new())
+                    .await
+                    .into_response();
+            let body_bytes = response
+                .into_body()
+                .collect()
+                .await
+                .expect("Failed to collect response body")
+                .to_bytes();
+            String::from_utf8(body_bytes.to_vec()).expect("Body should be UTF-8")
+        };
+
+        assert!(!render(state.clone()).await.contains("clock-drift-notice"));
+
+        for _ in 0..crate::clock_drift::MIN_DRIFT_SAMPLES {
+            state.clock_drift.record(300, 60);
+        }
+        let body = render(state).await;
+        assert!(body.contains("id=\"clock-drift-notice\""));
+        assert!(body.contains("5m"));
+    }
+
+    #[tokio::test]
+    /// Test that recorded checks render as history bars before the uptime stream connects
+    async fn generate_index_renders_history_bars() {
+        let mut config = build_config("History", Clock::None);
+        config.language = crate::i18n::Language::De;
+        let state = build_state(config);
+        let entry = |status, response_time_ms, failure_reason: Option<&str>| HistoryEntry {
+            status,
+            response_time_ms,
+            failure_reason: failure_reason.map(str::to_string),
+            checked_at: 0,
+        };
+        let mut entries: VecDeque<HistoryEntry> =
+            std::iter::repeat_with(|| entry(UptimeStatus::Up, Some(1500), None))
+                .take(crate::uptime::MAX_HISTORY_ENTRIES)
+                .collect();
+        entries.push_back(entry(UptimeStatus::Down, None, Some("status <503>")));
+        entries.push_back(entry(UptimeStatus::Up, Some(42), None));
+        state.history.write().insert("Docs".to_string(), entries);
+
+        let response = generate_index(State(state), Query(IndexQuery::default()), HeaderMap::new())
+            .await
+            .into_response();
+        let body_bytes = response
+            .into_body()
+            .collect()
+            .await
+            .expect("Failed to collect response body")
+            .to_bytes();
+        let body = String::from_utf8(body_bytes.to_vec()).expect("Body should be UTF-8");
+
+        assert_eq!(
+            body.matches("class=\"history-bar-wrapper\"").count(),
+            crate::uptime::MAX_HISTORY_ENTRIES
+        );
+        assert!(body.contains("class=\"history-bar up\" title=\"Online – Antwort 1.50 s\""));
+        assert!(body.contains("class=\"history-bar down\" title=\"Offline – status &lt;503&gt;\""));
+        assert!(body.contains("title=\"Online – Antwort 42 ms\""));
+        assert!(!body.contains("uptime-history-placeholder"));
+    }
+
+    #[tokio::test]
+    /// Test that sites whose latest completed check failed are listed in the outage banner
+    async fn generate_index_renders_outage_banner() {
+        let state = build_state(build_config("Outages", Clock::None));
+        let render = || {
+            let state = Arc::clone(&state);
+            async move {
+                let response =
+                    generate_index(State(state), Query(IndexQuery::default()), HeaderMap::new())
+                        .await
+                        .into_response();
+                let body_bytes = response
+                    .into_body()
+                    .collect()
+                    .await
+                    .expect("Failed to collect response body")
+                    .to_bytes();
+                String::from_utf8(body_bytes.to_vec()).expect("Body should be UTF-8")
+            }
+        };
+
+        let body = render().await;
+        assert!(body.contains("aria-label=\"Currently down\" hidden>"));
+
+        let since = unix_now() - 3700;
+        let entry = |status, checked_at| HistoryEntry {
+            status,
+            response_time_ms: None,
+            failure_reason: None,
+            checked_at,
+        };
+        state.history.write().insert(
+            "Docs".to_string(),
+            VecDeque::from([
+                entry(UptimeStatus::Up, since - 60),
+                entry(UptimeStatus::Down, since),
+                entry(UptimeStatus::Loading, since + 60),
+            ]),
+        );
+
+        let body = render().await;
+        assert!(!body.contains("aria-label=\"Currently down\" hidden>"));
+        assert!(body.contains(&format!(
+            "<li class=\"outage\" data-site=\"Docs\" data-down-since=\"{since}\">"
+        )));
+        assert!(body.contains("<span class=\"outage-duration\">down for 1h 1m</span>"));
+    }
+
+    #[tokio::test]
+    /// Test that the page links the web app manifest and describes itself for link previews
+    async fn generate_index_renders_manifest_and_share_tags() {
+        let mut config = build_config("Homelab", Clock::None);
+        config.logo = Some("logo.png".to_string());
+        let response = generate_index(
+            State(build_state(config)),
+            Query(IndexQuery::default()),
+            HeaderMap::new(),
+        )
+        .await
+        .into_response();
+        let body_bytes = response
+            .into_body()
+            .collect()
+            .await
+            .expect("Failed to collect response body")
+            .to_bytes();
+        let body = String::from_utf8(body_bytes.to_vec()).expect("Body should be UTF-8");
+
+        assert!(body.contains("<link rel=\"manifest\" href=\"/manifest.webmanifest\" />"));
+        assert!(body.contains("<meta property=\"og:title\" content=\"Homelab\" />"));
+        assert!(body.contains("<link rel=\"apple-touch-icon\" href=\"/branding/logo\" />"));
+        assert!(body.contains("<meta name=\"theme-color\" content=\"#1a1a1a\" />"));
+    }
+
+    #[tokio::test]
+    /// Test that cards show the latest recorded status instead of loading until the
+    /// stream connects
+    async fn generate_index_renders_recorded_statuses() {
+        let state = build_state(build_config("Statuses", Clock::None));
+        let render = || {
+            let state = Arc::clone(&state);
+            async move {
+                let response =
+                    generate_index(State(state), Query(IndexQuery::default()), HeaderMap::new())
+                        .await
+                        .into_response();
+                let body_bytes = response
+                    .into_body()
+                    .collect()
+                    .await
+                    .expect("Failed to collect response body")
+                    .to_bytes();
+                String::from_utf8(body_bytes.to_vec()).expect("Body should be UTF-8")
+            }
+        };
+
+        let body = render().await;
+        assert!(body.contains("<div class=\"uptime\" role=\"status\""));
+        assert!(body.contains("<span class=\"status-text\">Loading</span>"));
+        assert!(body.contains("Avg -- ms"));
+
+        let entry = |status, response_time_ms| HistoryEntry {
+            status,
+            response_time_ms,
+            failure_reason: None,
+            checked_at: 0,
+        };
+        state.history.write().insert(
+            "Docs".to_string(),
+            VecDeque::from([
+                entry(UptimeStatus::Down, None),
+                entry(UptimeStatus::Up, Some(100)),
+                entry(UptimeStatus::Up, Some(1900)),
+                entry(UptimeStatus::Up, Some(200)),
+                entry(UptimeStatus::Loading, None),
+            ]),
+        );
+
+        let body = render().await;
+        assert!(body.contains("<div class=\"uptime up\" role=\"status\""));
+        assert!(body.contains("<span class=\"status-text\">Up</span>"));
+        assert!(body.contains("<span class=\"uptime-percentage\">75.0%</span>"));
+        assert!(body.contains("Avg 733 ms"));
+    }
+
+    #[tokio::test]
+    /// Test that the theme cookie overrides the configured theme and bad values are ignored
+    async fn generate_index_renders_cookie_or_configured_theme() {
+        let mut config = build_config("Themed", Clock::None);
+        config.theme = Theme::Light;
+        let state = build_state(config);
+
+        let render = |cookie: Option<&'static str>| {
+            let state = Arc::clone(&state);
+            async move {
+                let mut headers = HeaderMap::new();
+                if let Some(cookie) = cookie {
+                    headers.insert(header::COOKIE, cookie.parse().expect("valid header"));
+                }
+                let response = generate_index(State(state), Query(IndexQuery::default()), headers)
+                    .await
+                    .into_response();
+                let body_bytes = response
+                    .into_body()
+                    .collect()
+                    .await
+                    .expect("Failed to collect response body")
+                    .to_bytes();
+                String::from_utf8(body_bytes.to_vec()).expect("Body should be UTF-8")
+            }
+        };
+
+        assert!(render(None)
+            .await
+            .contains("<html lang=\"en\" data-theme=\"light\" data-default-theme=\"light\">"));
+        assert!(render(Some("session=abc; iron_shield_theme=dark"))
+            .await
+            .contains("data-theme=\"dark\" data-default-theme=\"light\""));
+        assert!(render(Some("iron_shield_theme=neon"))
+            .await
+            .contains("data-theme=\"light\""));
+    }
+
+    #[tokio::test]
+    /// Test that a browser's preferences set the layout and filters when the URL has none
+    async fn generate_index_applies_browser_preferences() {
+        let config = build_config("Preferred", Clock::None);
+        let state = build_state(config);
+
+        let response = crate::preferences::patch_preferences(
+            State(Arc::clone(&state)),
+            HeaderMap::new(),
+            axum::Json(serde_json::json!({ "layout": "compact", "category": "Nowhere" })),
+        )
+        .await;
+        let cookie = response.headers()[header::SET_COOKIE]
+            .to_str()
+            .expect("ASCII cookie")
+            .split(';')
+            .next()
+            .expect("cookie pair")
+            .to_string();
+
+        let render = |filter: IndexQuery| {
+            let state = Arc::clone(&state);
+            let mut headers = HeaderMap::new();
+            headers.insert(header::COOKIE, cookie.parse().expect("valid header"));
+            async move {
+                let response = generate_index(State(state), Query(filter), headers)
+                    .await
+                    .into_response();
+                let body_bytes = response
+                    .into_body()
+                    .collect()
+                    .await
+                    .expect("Failed to collect response body")
+                    .to_bytes();
+                String::from_utf8(body_bytes.to_vec()).expect("Body should be UTF-8")
+            }
+        };
+
+        let preferred = render(IndexQuery::default()).await;
+        assert!(preferred.contains("<body class=\"layout-compact\""));
+        assert!(preferred.contains("data-category=\"Nowhere\""));
+        assert!(!preferred.contains("data-site-name="));
+
+        let filtered = render(IndexQuery {
+            tag: String::new(),
+            category: "Reference".to_string(),
+        })
+        .await;
+        assert!(filtered.contains("data-category=\"Reference\""));
+        assert!(filtered.contains("data-site-name="));
+    }
+
+    #[tokio::test]
+    /// Test that named dashboards render only their own sites and unknown names 404
+    async fn generate_dashboard_filters_sites_and_rejects_unknown_names() {
+        let mut config = build_config("Main", Clock::None);
+        config.sites.push(Site {
+            name: "Router".to_string(),
+            url: "http://router.local".to_string(),
+            category: String::new(),
+            tags: vec![],
+            monitor_interval_secs: Some(crate::config::DEFAULT_MONITOR_INTERVAL_SECS),
+            monitor_timeout_secs: None,
+            monitor_retries: None,
+            monitor_user_agent: None,
+            disabled: false,
+            icon: None,
+            order: None,
+            assertions: None,
+            uptime_percentage: 0.0,
+        });
+        config.dashboards.push(crate::config::Dashboard {
+            name: "homelab".to_string(),
+            title: Some("Homelab".to_string()),
+            sites: vec!["Router".to_string()],
+        });
+        let state = build_state(config);
+
+        let response = generate_dashboard(
+            State(Arc::clone(&state)),
+            Path("homelab".to_string()),
+            Query(IndexQuery::default()),
+            HeaderMap::new(),
+        )
+        .await
+        .into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body_bytes = response
+            .into_body()
+            .collect()
+            .await
+            .expect("Failed to collect response body")
+            .to_bytes();
+        let body_string =
+            String::from_utf8(body_bytes.to_vec()).expect("Body should contain valid UTF-8");
+
+        assert!(body_string.contains("<title>Homelab</title>"));
+        assert!(body_string.contains("data-dashboard=\"homelab\""));
+        assert!(body_string.contains("http://router.local"));
+        assert!(
+            !body_string.contains("href=\"https://docs.example.com"),
+            "sites outside the dashboard should not be rendered as cards"
+        );
+
+        let missing = generate_dashboard(
+            State(state),
+            Path("missing".to_string()),
+            Query(IndexQuery::default()),
+            HeaderMap::new(),
+        )
+        .await
+        .into_response();
+        assert_eq!(missing.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    /// Test that the page is rendered from the running configuration, so environment
+    /// overrides apply, also after a settings patch, while the settings modal gets the
+    /// saved values
+    async fn generate_index_renders_environment_overrides() {
+        let dir = tempfile::tempdir().expect("temporary directory");
+        let path = dir.path().join("config.json5");
+        std::fs::write(&path, r#"{ site_name: "Home" }"#).unwrap();
+        let config = Config::load_with_env(
+            &path,
+            [
+                ("IRON_SHIELD__LANGUAGE".to_string(), "de".to_string()),
+                ("IRON_SHIELD__SITE_NAME".to_string(), "Lab".to_string()),
+            ],
+        )
+        .unwrap();
+        let state = Arc::new(UptimeState::new(config, path));
+        let render = |state: Arc<UptimeState>| async move {
+            let response =
+                generate_index(State(state), Query(IndexQuery::default()), HeaderMap::new())
+                    .await
+                    .into_response();
+            let body_bytes = response
+                .into_body()
+                .collect()
+                .await
+                .expect("Failed to collect response body")
+                .to_bytes();
+            String::from_utf8(body_bytes.to_vec()).expect("Body should be UTF-8")
+        };
+
+        let body = render(state.clone()).await;
+        assert!(body.contains(r#"<html lang="de""#));
+        assert!(body.contains("<title>Lab</title>"));
+        assert!(body.contains(r#""site_name":"Home""#));
+
+        let patch = serde_json::json!({ "clock": "12hour" });
+        assert!(crate::settings::patch_settings(
+            State(state.clone()),
+            HeaderMap::new(),
+            axum::Json(patch.as_object().unwrap().clone()),
+        )
+        .await
+        .is_ok());
+        let body = render(state).await;
+        assert!(body.contains(r#"<html lang="de""#));
+        assert!(body.contains("<title>Lab</title>"));
+    }
+
+    #[tokio::test]
+    /// Test that widgets are rendered with their variables expanded, as configured
+    async fn generate_index_renders_widgets_from_the_running_config() {
+        let dir = tempfile::tempdir().expect("temporary directory");
+        let path = dir.path().join("config.json5");
+        std::fs::write(
+            &path,
+            r#"{
+                vars: { BASE_DOMAIN: "home.lan" },
+                widgets: [
+                    { title: "Calendar", iframe: "https://calendar.${BASE_DOMAIN}/embed" },
+                    { title: "Notes", html: "<p>Mail: admin@${BASE_DOMAIN}</p>" },
+                ],
+            }"#,
+        )
+        .unwrap();
+        let state = Arc::new(UptimeState::new(Config::load(&path).unwrap(), path));
+
+        let response = generate_index(State(state), Query(IndexQuery::default()), HeaderMap::new())
+            .await
+            .into_response();
+        let body_bytes = response
+            .into_body()
+            .collect()
+            .await
+            .expect("Failed to collect response body")
+            .to_bytes();
+        let body = String::from_utf8(body_bytes.to_vec()).expect("Body should be UTF-8");
+
+        assert!(body.contains(r#"src="https://calendar.home.lan/embed""#));
+        assert!(body.contains("<p>Mail: admin@home.lan</p>"));
+    }
+
+    #[tokio::test]
+    /// Test that an `index.html` in `templates_dir` replaces the page, and that the
+    /// built-in page is served while it is missing or broken
+    async fn generate_index_renders_template_override() {
+        let dir = tempfile::tempdir().expect("temporary directory");
+        let mut config = build_config("Overrides", Clock::None);
+        config.language = crate::i18n::Language::De;
+        config.templates_dir = Some(dir.path().to_string_lossy().into_owned());
+        let render = |state: Arc<UptimeState>| async move {
+            let response =
+                generate_index(State(state), Query(IndexQuery::default()), HeaderMap::new())
+                    .await
+                    .into_response();
+            let body_bytes = response
+                .into_body()
+                .collect()
+                .await
+                .expect("Failed to collect response body")
+                .to_bytes();
+            String::from_utf8(body_bytes.to_vec()).expect("Body should be UTF-8")
+        };
+        let state = build_state(config.clone());
+        state.history.write().insert(
+            "Docs".to_string(),
+            VecDeque::from([HistoryEntry {
+                status: UptimeStatus::Up,
+                response_time_ms: Some(42),
+                failure_reason: None,
+                checked_at: 0,
+            }]),
+        );
+
+        assert!(render(state.clone())
+            .await
+            .contains("<title>Overrides</title>"));
+
+        std::fs::write(
+            dir.path().join("index.html"),
+            "<h1>{{ page_title }}</h1>\
+             {% for section in categories %}<h2>{{ section.name }}</h2>\
+             {% for site in section.sites %}<p class=\"{{ site.status }}\">{{ site.name }}: \
+             {{ site.status_label }}, {{ site.uptime_percentage }}%, {{ site.average_response }}\
+             </p>{% endfor %}{% endfor %}<footer>{{ t(\"settings.title\") }}</footer>",
+        )
+        .unwrap();
+        let body = render(state).await;
+        assert_eq!(
+            body,
+            format!(
+                "<h1>Overrides</h1><h2>Reference</h2><p class=\"up\">Docs: Online, 100.0%, {} 42 ms</p><footer>{}</footer>",
+                crate::i18n::Language::De.translate("response.average"),
+                crate::i18n::Language::De.translate("settings.title"),
+            )
+        );
+
+        std::fs::write(dir.path().join("index.html"), "{% for site in %}").unwrap();
+        let body = render(build_state(config)).await;
+        assert!(body.contains("<title>Overrides</title>"));
+        assert!(body.contains("data-site-name=\"Docs\""));
     }
 }
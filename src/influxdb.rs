@@ -0,0 +1,253 @@
+//! InfluxDB export
+//!
+//! The config's `influxdb` block names an InfluxDB 2 server, or anything that accepts its
+//! write API such as VictoriaMetrics, that the results of every uptime cycle are written
+//! to in line protocol, so the uptime history can be kept in an existing time series
+//! database:
+//!
+//! ```json5
+//! influxdb: {
+//!     url: "http://influxdb:8086",
+//!     org: "homelab",
+//!     bucket: "iron_shield",
+//!     token: "${env:INFLUXDB_TOKEN}",
+//! }
+//! ```
+//!
+//! Each monitored site with a completed check becomes one point of the
+//! `iron_shield_check` measurement, tagged with `site` and `dashboard` (the `site_name`
+//! setting), with the fields `up` (1 or 0), `response_time_ms`, and
+//! `uptime_percentage`, at second precision. The token may be a secret reference (see
+//! the `secrets` module) and is sent as `Authorization: Token ...`.
+
+use crate::error::{IronShieldError, Result};
+use crate::uptime::{snapshot_current_histories, UptimeHistory, UptimeState, UptimeStatus};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tracing::warn;
+
+/// Path of the InfluxDB 2 write API, relative to `url`
+pub const WRITE_PATH: &str = "/api/v2/write";
+/// Measurement the check results are written to
+pub const MEASUREMENT: &str = "iron_shield_check";
+
+/// How long the server gets to accept a write
+const WRITE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Settings of the InfluxDB export
+///
+/// # Fields
+///
+/// * `url` - Base URL of the server, such as `http://influxdb:8086`
+/// * `org` - Organization owning `bucket`; VictoriaMetrics ignores it
+/// * `bucket` - Bucket (or database) the points are written to
+/// * `token` - API token with write access to `bucket`; may be a secret reference
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+pub struct InfluxDb {
+    /// Base URL of the server
+    pub url: String,
+    /// Organization owning the bucket
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub org: Option<String>,
+    /// Bucket the points are written to
+    pub bucket: String,
+    /// API token with write access to the bucket
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+}
+
+impl InfluxDb {
+    /// Problems with these settings, as pairs of the field name and what is wrong with it
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use iron_shield::influxdb::InfluxDb;
+    ///
+    /// let influxdb = InfluxDb {
+    ///     url: "http://influxdb:8086".to_string(),
+    ///     org: Some("homelab".to_string()),
+    ///     bucket: "iron_shield".to_string(),
+    ///     token: None,
+    /// };
+    /// assert!(influxdb.validate().is_empty());
+    ///
+    /// let bucketless = InfluxDb { bucket: " ".to_string(), ..influxdb };
+    /// assert_eq!(bucketless.validate()[0].0, "bucket");
+    /// ```
+    #[must_use]
+    pub fn validate(&self) -> Vec<(String, String)> {
+        let mut problems = Vec::new();
+        let valid_url = url::Url::parse(self.url.trim())
+            .is_ok_and(|url| matches!(url.scheme(), "http" | "https") && url.host().is_some());
+        if !valid_url {
+            problems.push((
+                "url".to_string(),
+                format!("\"{}\" is not a valid http or https URL", self.url),
+            ));
+        }
+        if self.bucket.trim().is_empty() {
+            problems.push(("bucket".to_string(), "cannot be empty".to_string()));
+        }
+        problems
+    }
+
+    /// URL of the write API for this server, organization, and bucket
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use iron_shield::influxdb::InfluxDb;
+    ///
+    /// let influxdb = InfluxDb {
+    ///     url: "http://influxdb:8086/".to_string(),
+    ///     org: Some("home lab".to_string()),
+    ///     bucket: "iron_shield".to_string(),
+    ///     token: None,
+    /// };
+    /// assert_eq!(
+    ///     influxdb.write_url(),
+    ///     "http://influxdb:8086/api/v2/write?bucket=iron_shield&precision=s&org=home+lab"
+    /// );
+    /// ```
+    #[must_use]
+    pub fn write_url(&self) -> String {
+        let mut query = url::form_urlencoded::Serializer::new(String::new());
+        query.append_pair("bucket", self.bucket.trim());
+        query.append_pair("precision", "s");
+        if let Some(org) = self.org.as_deref().filter(|org| !org.trim().is_empty()) {
+            query.append_pair("org", org.trim());
+        }
+        format!(
+            "{}{WRITE_PATH}?{}",
+            self.url.trim().trim_end_matches('/'),
+            query.finish()
+        )
+    }
+}
+
+/// Write the latest uptime snapshot to the configured InfluxDB server, if any.
+///
+/// Failures are logged and otherwise ignored; the next cycle writes its own points.
+pub async fn write_uptime_snapshot(state: &UptimeState) {
+    let config = state.config.load();
+    let Some(settings) = config.influxdb.clone() else {
+        return;
+    };
+    let dashboard_name = config.site_name.clone();
+
+    let body = uptime_lines(&dashboard_name, &snapshot_current_histories(state));
+    if body.is_empty() {
+        return;
+    }
+    if let Err(err) = write(&settings, body).await {
+        warn!("Failed to write uptime to InfluxDB: {err}");
+    }
+}
+
+/// The line protocol points reporting `snapshot`, one per line, leaving out sites that
+/// are paused or have not finished a check.
+fn uptime_lines(dashboard_name: &str, snapshot: &[UptimeHistory]) -> String {
+    let mut lines = String::new();
+    for history in snapshot {
+        let up = match history.status {
+            UptimeStatus::Up => 1,
+            UptimeStatus::Down => 0,
+            UptimeStatus::Loading | UptimeStatus::Disabled => continue,
+        };
+        let mut fields = format!("up={up}i");
+        if let Some(ms) = history.response_time_ms {
+            fields.push_str(&format!(",response_time_ms={ms}i"));
+        }
+        fields.push_str(&format!(",uptime_percentage={}", history.uptime_percentage));
+        lines.push_str(&format!(
+            "{MEASUREMENT},dashboard={},site={} {fields} {}\n",
+            escape_tag(dashboard_name),
+            escape_tag(&history.site_id),
+            history.timestamp
+        ));
+    }
+    lines
+}
+
+/// `value` as a line protocol tag value, with commas, equals signs, and spaces escaped
+/// and line breaks turned into escaped spaces; an empty value, which line protocol does
+/// not allow, becomes `_`.
+fn escape_tag(value: &str) -> String {
+    if value.is_empty() {
+        return "_".to_string();
+    }
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            ',' | '=' | ' ' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '\n' | '\r' => escaped.push_str("\\ "),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Post line protocol `body` to the write API of `settings`.
+async fn write(settings: &InfluxDb, body: String) -> Result<()> {
+    let mut request = reqwest::Client::new()
+        .post(settings.write_url())
+        .timeout(WRITE_TIMEOUT)
+        .header(reqwest::header::CONTENT_TYPE, "text/plain; charset=utf-8")
+        .body(body);
+    if let Some(token) = settings.token.as_deref().filter(|token| !token.is_empty()) {
+        request = request.header(reqwest::header::AUTHORIZATION, format!("Token {token}"));
+    }
+
+    let error = |err: reqwest::Error| IronShieldError::Telemetry {
+        endpoint: settings.url.clone(),
+        source: err.without_url(),
+    };
+    request
+        .send()
+        .await
+        .map_err(error)?
+        .error_for_status()
+        .map(|_| ())
+        .map_err(error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn uptime_lines_write_one_point_per_checked_site() {
+        let history = |site: &str, status, response_time_ms| UptimeHistory {
+            site_id: site.to_string(),
+            status,
+            timestamp: 1_700_000_000,
+            history: Vec::new(),
+            uptime_percentage: 99.5,
+            response_time_ms,
+            max_history_entries: crate::uptime::MAX_HISTORY_ENTRIES,
+            latency_ms: Vec::new(),
+            down_since: None,
+            last_checked: None,
+            last_change: None,
+        };
+
+        let lines = uptime_lines(
+            "Home Lab",
+            &[
+                history("Plex, Media", UptimeStatus::Up, Some(120)),
+                history("NAS", UptimeStatus::Down, None),
+                history("Router", UptimeStatus::Loading, None),
+            ],
+        );
+
+        assert_eq!(
+            lines,
+            "iron_shield_check,dashboard=Home\\ Lab,site=Plex\\,\\ Media up=1i,response_time_ms=120i,uptime_percentage=99.5 1700000000\n\
+             iron_shield_check,dashboard=Home\\ Lab,site=NAS up=0i,uptime_percentage=99.5 1700000000\n"
+        );
+    }
+}
@@ -0,0 +1,258 @@
+//! Kiosk view
+//!
+//! `/kiosk` shows the status of each site and the clock for a display that runs
+//! unattended, such as a wall-mounted TV: there is no toolbar, search, settings, or
+//! links, the tiles scale with the screen, and the page ignores the pointer. When the
+//! sites span several categories the view shows one category at a time and moves on to
+//! the next every `kiosk_rotate_secs` seconds; 0 shows all of them at once.
+//!
+//! Statuses arrive over the same `/uptime` stream as the dashboard, and the page reloads
+//! itself after the configuration changes. The query string scopes the view to a named
+//! dashboard and overrides the rotation for one screen:
+//!
+//! ```text
+//! /kiosk?dashboard=homelab&rotate=30
+//! ```
+
+use crate::config::{Config, Site, Theme};
+use crate::index::{
+    category_sections, clock_format, page_theme, sort_with_history, CategorySection,
+};
+use crate::uptime::{HistoryEntry, UptimeState, UptimeStatus};
+use crate::utils;
+use askama_axum::Template;
+use axum::{
+    extract::{Query, State},
+    http::{HeaderMap, StatusCode},
+    response::{Html, IntoResponse},
+};
+use serde::Deserialize;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+
+/// Query string of a kiosk request
+#[derive(Debug, Default, Deserialize)]
+pub struct KioskQuery {
+    /// Name of the dashboard whose sites are shown, all sites if unset
+    #[serde(default)]
+    pub dashboard: Option<String>,
+    /// Seconds each category is shown, overriding `kiosk_rotate_secs`
+    #[serde(default)]
+    pub rotate: Option<u64>,
+}
+
+/// Template of the kiosk view
+#[derive(Template)]
+#[template(path = "kiosk.html")]
+pub struct KioskTemplate {
+    /// Application configuration, for the clock, language, and sort order
+    config: Config,
+    /// Title of the rendered page
+    page_title: String,
+    /// Sites of the view by category, each shown in turn
+    categories: Vec<CategorySection>,
+    /// Name of the dashboard being shown, or empty for all sites
+    dashboard: String,
+    /// Color scheme the page is rendered with
+    theme: Theme,
+    /// Current time in the configured time zone, formatted for display
+    current_time: String,
+    /// Seconds each category is shown, 0 to show all of them at once
+    rotate_secs: u64,
+    /// Recorded uptime checks of each site, for the statuses shown before the stream
+    /// connects
+    history: HashMap<String, VecDeque<HistoryEntry>>,
+    /// Frontend bundle fingerprint appended to asset URLs for cache busting
+    asset_version: String,
+}
+
+impl KioskTemplate {
+    /// The format the clock ticks in on the frontend.
+    fn clock_format(&self) -> &'static str {
+        clock_format(&self.config.clock)
+    }
+
+    /// Text for `key` in the configured language; see [`crate::i18n`].
+    fn t<'a>(&self, key: &'a str) -> &'a str {
+        self.config.language.translate(key)
+    }
+
+    /// Whether the categories are shown one at a time.
+    fn rotates(&self) -> bool {
+        self.rotate_secs > 0 && self.categories.len() > 1
+    }
+
+    /// Status of `site`; see [`current_status`].
+    fn status(&self, site: &Site) -> &'static str {
+        current_status(site, self.history.get(&site.name))
+    }
+
+    /// Text of the status of `site` in the configured language.
+    fn status_label(&self, site: &Site) -> &'static str {
+        self.t(match self.status(site) {
+            "up" => "status.up",
+            "down" => "status.down",
+            "disabled" => "status.disabled",
+            _ => "status.unknown",
+        })
+    }
+}
+
+/// Status of `site` from its latest completed check in `entries`: `up`, `down`,
+/// `disabled`, or `unknown` before its first check.
+pub(crate) fn current_status(
+    site: &Site,
+    entries: Option<&VecDeque<HistoryEntry>>,
+) -> &'static str {
+    if site.disabled {
+        return "disabled";
+    }
+    let latest = entries.and_then(|entries| {
+        entries
+            .iter()
+            .rev()
+            .find(|entry| entry.status != UptimeStatus::Loading)
+    });
+    match latest.map(|entry| entry.status) {
+        Some(UptimeStatus::Up) => "up",
+        Some(UptimeStatus::Down) => "down",
+        Some(UptimeStatus::Disabled) => "disabled",
+        Some(UptimeStatus::Loading) | None => "unknown",
+    }
+}
+
+/// Render the kiosk view
+///
+/// Handles `GET /kiosk`, showing every site or, with `?dashboard=name`, the sites of
+/// that dashboard, titled with its title. `?rotate=secs` overrides the configured
+/// `kiosk_rotate_secs`. The theme follows the [`crate::index::THEME_COOKIE`] cookie like
+/// the dashboard does.
+///
+/// # Errors
+///
+/// Returns HTTP 404 when the requested dashboard is not configured, and HTTP 500 if the
+/// template cannot be rendered.
+pub async fn kiosk_page(
+    State(state): State<Arc<UptimeState>>,
+    Query(query): Query<KioskQuery>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    let config = Config::clone(&state.config.load());
+
+    let (page_title, sites, dashboard) = match query.dashboard.filter(|name| !name.is_empty()) {
+        Some(name) => {
+            let Some(dashboard) = config.dashboard(&name) else {
+                return (StatusCode::NOT_FOUND, "Dashboard not found").into_response();
+            };
+            let title = dashboard
+                .title
+                .clone()
+                .unwrap_or_else(|| config.site_name.clone());
+            (title, config.dashboard_sites(dashboard), name)
+        }
+        None => (
+            config.site_name.clone(),
+            config.sites.clone(),
+            String::new(),
+        ),
+    };
+
+    let (sites, history) = sort_with_history(&state, &config, sites);
+    let template = KioskTemplate {
+        page_title,
+        categories: category_sections(&config, sites),
+        dashboard,
+        theme: page_theme(&config, &headers, &state.preferences_key),
+        current_time: utils::get_current_time_string(&config.clock, config.tz()),
+        rotate_secs: query.rotate.unwrap_or(config.kiosk_rotate_secs),
+        history,
+        asset_version: state.asset_version.clone(),
+        config,
+    };
+    match template
+        .render()
+        .map_err(crate::error::IronShieldError::TemplateRender)
+    {
+        Ok(html) => Html(html).into_response(),
+        Err(e) => {
+            tracing::error!("{e}");
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Template rendering error",
+            )
+                .into_response()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn site(name: &str, category: &str, disabled: bool) -> Site {
+        Site {
+            name: name.to_string(),
+            url: format!("https://{}.lan", name.to_lowercase()),
+            category: category.to_string(),
+            tags: Vec::new(),
+            monitor_interval_secs: None,
+            monitor_timeout_secs: None,
+            monitor_retries: None,
+            monitor_user_agent: None,
+            disabled,
+            icon: None,
+            order: None,
+            assertions: None,
+            uptime_percentage: 0.0,
+        }
+    }
+
+    fn template(sites: Vec<Site>, rotate_secs: u64) -> KioskTemplate {
+        let config = Config {
+            site_name: "Wall".to_string(),
+            sites,
+            ..Config::default()
+        };
+        let entry = |status| HistoryEntry {
+            status,
+            response_time_ms: None,
+            failure_reason: None,
+            checked_at: 0,
+        };
+        KioskTemplate {
+            page_title: config.site_name.clone(),
+            categories: category_sections(&config, config.sites.clone()),
+            dashboard: String::new(),
+            theme: Theme::Dark,
+            current_time: "12:00".to_string(),
+            rotate_secs,
+            history: HashMap::from([(
+                "Plex".to_string(),
+                VecDeque::from([entry(UptimeStatus::Down), entry(UptimeStatus::Loading)]),
+            )]),
+            asset_version: "test".to_string(),
+            config,
+        }
+    }
+
+    #[test]
+    fn renders_one_section_per_category_without_links() {
+        let sites = vec![
+            site("Plex", "Media", false),
+            site("Router", "Network", false),
+            site("Backup", "Network", true),
+        ];
+        let rendered = template(sites.clone(), 20).render().unwrap();
+
+        assert_eq!(rendered.matches("class=\"kiosk-section\"").count(), 2);
+        assert!(rendered.contains("data-rotate-secs=\"20\""));
+        assert!(rendered.contains("data-category=\"Network\" hidden"));
+        assert!(!rendered.contains("<a "));
+        assert!(rendered.contains("<div class=\"uptime down\""));
+        assert!(rendered.contains("<div class=\"uptime unknown\""));
+        assert!(rendered.contains("<div class=\"uptime disabled\""));
+
+        let rendered = template(sites, 0).render().unwrap();
+        assert!(!rendered.contains(" hidden"));
+    }
+}
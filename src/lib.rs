@@ -8,13 +8,47 @@
 //!
 //! The library is organized into several modules that handle different aspects of the application:
 //!
+//! - `alerts`: Silences the notifications of noisy sites for a while
+//! - `api_keys`: Checks the API keys that scripts present and creates and revokes them
+//! - `assertions`: Evaluates the response assertions declared for a site's uptime checks
+//! - `assets`: Adds cache headers and fingerprints to the bundled frontend assets
+//! - `backup`: Keeps timestamped backups of the config file and restores them
+//! - `bookmarks`: Imports bookmark files exported from a browser as sites
+//! - `branding`: Serves the custom stylesheet and logo named in the config
+//! - `checkers`: Runs uptime checks through the checker registered for each URL scheme
+//! - `clock_drift`: Warns when the system clock disagrees with monitored sites
 //! - `config`: Handles application configuration and settings
+//! - `daemon`: Starts the server in the background and keeps its pidfile
 //! - `error`: Defines custom error types for consistent error handling
+//! - `favicons`: Fetches, caches, and serves the favicons of sites
+//! - `graphite`: Pushes each uptime cycle to Graphite over its plaintext protocol
+//! - `i18n`: Translates the dashboard UI into the configured language
+//! - `icons`: Resolves site icons to image URLs or bundled SVG icons
+//! - `import`: Imports sites from Gatus and Uptime Kuma configurations
 //! - `index`: Renders the main dashboard page
+//! - `influxdb`: Writes each uptime cycle to InfluxDB in line protocol
+//! - `kiosk`: Renders the non-interactive `/kiosk` view for wall-mounted displays
+//! - `migration`: Upgrades config files written for older format versions
+//! - `notifications`: Sends ntfy and Gotify push notifications when a site goes down or
+//!   recovers
+//! - `oneshot`: Checks every site once and reports it like a Nagios plugin
+//! - `otlp`: Encodes metrics in the OpenTelemetry protocol's protobuf format
+//! - `preferences`: Keeps each browser's theme, layout, and filters in a signed cookie
+//! - `report`: Renders `/report`, a print-friendly status summary that needs no scripts
+//! - `search`: Finds sites by name, URL, tag, and category for the launcher search box
+//! - `secrets`: Resolves `${env:...}` and `file:` secret references in config values
+//! - `self_monitor`: Reports the memory use, connections, and check load of the process
 //! - `server`: Runs the web server and manages routes
-//! - `settings`: Handles the settings page and API
+//! - `settings`: Handles the settings API and the settings modal's data
+//! - `settings_page`: Renders the standalone `/settings` page and saves its form
+//! - `statsd`: Reports each uptime cycle to a StatsD daemon over UDP
+//! - `systemd`: Tells systemd when the server is ready and answers its watchdog
+//! - `tags`: Lists, renames, merges, and removes site tags
 //! - `uptime`: Manages uptime monitoring and status updates
 //! - `utils`: Provides utility functions used throughout the application
+//! - `variables`: Expands `${NAME}` variables in config values
+//! - `weather`: Fetches and caches the weather shown next to the clock
+//! - `widgets`: Defines dashboard tiles embedding a page or sanitized HTML snippet
 //!
 //! ## Getting Started
 //!
@@ -66,6 +100,67 @@
 /// can represent various error conditions that may occur during application operation.
 pub mod error;
 
+/// Favicon module
+///
+/// Fetches each site's favicon on the server, keeps it in an on-disk cache, and serves it
+/// from `/icons/{site}`, so browsers do not contact every site's host for its icon.
+pub mod favicons;
+
+/// Alert silencing module
+///
+/// Mutes the notifications of a site for a while through the API, without pausing its
+/// monitoring.
+pub mod alerts;
+
+/// API key module
+///
+/// Checks the keys that scripts present to call the API, and creates, lists, and
+/// revokes them.
+pub mod api_keys;
+
+/// Response assertions module
+///
+/// Evaluates the `assertions` block of a site (status codes, headers, body text, JSON
+/// values, and latency) against the response to an uptime check.
+pub mod assertions;
+
+/// Static asset caching module
+///
+/// Fingerprints the bundled frontend files at startup and provides the middleware that
+/// adds `ETag` and `Cache-Control` headers to `/static` responses, so browsers can keep
+/// the JS/CSS bundle cached between page loads.
+pub mod assets;
+
+/// Config backup module
+///
+/// Copies the config file to a timestamped backup before the settings API overwrites
+/// it, prunes old backups, and restores a chosen backup.
+pub mod backup;
+
+/// Bookmark import module
+///
+/// Reads bookmark files exported from a browser and adds their links as sites, with each
+/// folder becoming a category.
+pub mod bookmarks;
+
+/// Branding module
+///
+/// Serves the stylesheet and logo that the config's `custom_css` and `logo` point at, so
+/// an instance can be restyled without rebuilding the frontend bundle.
+pub mod branding;
+
+/// Checkers module
+///
+/// Defines the `Checker` trait uptime checks run through and the registry of checkers by
+/// URL scheme, with the built-in HTTP checker.
+pub mod checkers;
+
+/// Clock drift module
+///
+/// Compares the `Date` headers of uptime check responses with the system clock and warns
+/// when the clock is skewed, since that silently corrupts every recorded timestamp.
+pub mod clock_drift;
+
 /// Configuration management module
 ///
 /// Handles application configuration loading, validation, and watching. The config module
@@ -74,6 +169,42 @@ pub mod error;
 /// options including site names, clock formats, and bookmarked sites.
 pub mod config;
 
+/// Custom templates module
+///
+/// Renders the page templates found in the config's `templates_dir` in place of the
+/// compiled-in ones, so the markup can be changed without rebuilding.
+pub mod custom_templates;
+
+/// Daemon module
+///
+/// Starts the server detached from the terminal for `--daemon` and writes the pidfile
+/// named by `--pidfile`, removing it again on shutdown.
+pub mod daemon;
+
+/// Graphite module
+///
+/// Pushes each site's status and response time to a Carbon daemon over the Graphite
+/// plaintext protocol after every uptime cycle.
+pub mod graphite;
+
+/// Translation module
+///
+/// Holds the text of the dashboard UI in each supported language, picked with the
+/// config's `language` option.
+pub mod i18n;
+
+/// Site icon module
+///
+/// Validates site `icon` values and provides the SVG icons bundled with the dashboard,
+/// so sites can show an icon without hosting an image.
+pub mod icons;
+
+/// Site import module
+///
+/// Converts Gatus configs and Uptime Kuma backups to sites, and adds imported sites to
+/// the configuration without duplicating the ones already there.
+pub mod import;
+
 /// Index page generation module
 ///
 /// Responsible for rendering the main dashboard page. This module combines configuration
@@ -81,6 +212,54 @@ pub mod config;
 /// site bookmarks and current time display.
 pub mod index;
 
+/// InfluxDB module
+///
+/// Writes the check results of every uptime cycle in line protocol to an InfluxDB 2 or
+/// VictoriaMetrics server, so the uptime history can live in an existing database.
+pub mod influxdb;
+
+/// Kiosk module
+///
+/// Renders `/kiosk`, a chrome-less view of site statuses and the clock for displays that
+/// run unattended, rotating through the site categories.
+pub mod kiosk;
+
+/// Config migration module
+///
+/// Upgrades configuration values written for an older config `version` to the current
+/// format before they are deserialized, and rewrites outdated config files on request.
+pub mod migration;
+
+/// Notifications module
+///
+/// Tells the configured push services, such as ntfy and Gotify, when a site goes down or
+/// comes back up.
+pub mod notifications;
+
+/// One-shot check module
+///
+/// Checks every configured site once for `--check` and summarizes the results with
+/// Nagios plugin exit codes.
+pub mod oneshot;
+
+/// OTLP module
+///
+/// Encodes the uptime and click metrics sent by the `telemetry` module as OTLP
+/// `ExportMetricsServiceRequest` messages.
+pub mod otlp;
+
+/// Preferences module
+///
+/// Stores a browser's own theme, layout, and filters in a signed cookie that the
+/// dashboard pages apply over the configured values.
+pub mod preferences;
+
+/// Report module
+///
+/// Renders `/report`, a static summary of current statuses, uptime, and recent
+/// incidents for printing, emailing, or archiving.
+pub mod report;
+
 /// Server operations module
 ///
 /// Contains the main web server implementation using the Axum framework. This module
@@ -103,9 +282,50 @@ pub mod uptime;
 /// dynamic updates to site configurations and settings.
 pub mod settings;
 
+/// Settings page module
+///
+/// Renders `/settings`, a server-rendered page listing the settings and sites as a
+/// plain form, and saves the submitted form with the same validation as the settings API.
+pub mod settings_page;
+
+/// Site search module
+///
+/// Finds sites by fuzzy matching their names, URLs, tags, and categories for the
+/// dashboard's launcher search box.
+pub mod search;
+
+/// Secret reference module
+///
+/// Resolves `${env:NAME}`, `${file:/path}`, and `file:/path` references in configuration
+/// values at load time, so credentials can be kept out of the config file.
+pub mod secrets;
+
+/// Self-monitoring module
+///
+/// Tracks open uptime streams, queued and running checks, and cycle durations, and
+/// reports them with the process's memory use at `/api/self` and as telemetry metrics.
+pub mod self_monitor;
+
+/// StatsD module
+///
+/// Sends each site's status and response time to a StatsD daemon after every uptime
+/// cycle, for Graphite and Datadog agent setups.
+pub mod statsd;
+
+/// systemd module
+///
+/// Sends `READY=1`, `STOPPING=1`, and watchdog pings to systemd when the server runs as
+/// a `Type=notify` service.
+pub mod systemd;
+
+/// Tag management module
+///
+/// Lists the tags in use and renames, merges, and removes a tag on every site at once.
+pub mod tags;
+
 /// Telemetry helpers
 ///
-/// Provides utilities for shipping uptime snapshots to external collectors so the
+/// Exports uptime and click metrics to an OpenTelemetry collector over OTLP/HTTP so the
 /// dashboard can maintain a long-term history outside of the local runtime.
 pub mod telemetry;
 
@@ -114,3 +334,21 @@ pub mod telemetry;
 /// Contains common utility functions used throughout the application, such as time
 /// formatting utilities that are used for displaying current time on the dashboard.
 pub mod utils;
+
+/// Config variable module
+///
+/// Expands `${NAME}` references in configuration values from the config's `vars` block
+/// or the environment, so values shared by many sites are written once.
+pub mod variables;
+
+/// Weather widget module
+///
+/// Fetches the current weather from the configured provider and caches it for the
+/// dashboard header.
+pub mod weather;
+
+/// Widgets module
+///
+/// Defines extra dashboard tiles that embed a page in a sandboxed frame or show an HTML
+/// snippet sanitized to safe markup.
+pub mod widgets;
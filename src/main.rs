@@ -6,8 +6,40 @@
 //!
 //! The application can be launched with optional command-line arguments:
 //!
-//! - First argument: Port number (defaults to 3000)
-//! - Second argument: Path to configuration file (defaults to "config.json5")
+//! - `--port PORT`: Port number (defaults to 3000)
+//! - `--bind ADDRESS`: IP address to listen on (defaults to 0.0.0.0)
+//! - `--config PATH`: Path to configuration file or `conf.d`-style directory of config
+//!   files (defaults to "config.json5")
+//!
+//! The port and config path may also be given as the first and second positional
+//! arguments. Without them, `IRON_SHIELD_CONFIG` names the config file, and the port and
+//! address come from the config file's `port` and `bind` settings, then the
+//! `IRON_SHIELD_PORT` and `IRON_SHIELD_BIND` environment variables.
+//!
+//! The server can run in the background on init systems without a supervisor:
+//!
+//! - `--daemon`: Detach from the terminal and return once the server is ready, exiting
+//!   with a non-zero status if it fails to start
+//! - `--pidfile PATH`: Write the server's PID to `PATH`, removed again on shutdown
+//! - `--log-file PATH`: Append logs to `PATH` instead of writing them to standard
+//!   output; without it, a `--daemon` server's logs are discarded
+//!
+//! Config files can be checked and managed without starting the server:
+//!
+//! - `--init [CONFIG]`: Write a commented starter config, refusing to overwrite a file
+//! - `--check-config [CONFIG]`: Load and validate the config, print a summary, and exit
+//!   with a non-zero status if it is invalid
+//! - `--check [CONFIG]`: Check every site once, print a summary, and exit with a Nagios
+//!   plugin status: 0 if all sites are up, 1 if some are down, 2 if all are down
+//! - `--list-backups [CONFIG]`: Print the available backups, newest first
+//! - `--restore-backup NAME [CONFIG]`: Replace the config file with a backup
+//! - `--migrate-config [CONFIG]`: Rewrite an outdated config file in the current format
+//! - `--generate-api-key NAME`: Print a new API key and the `api_keys` entry holding its
+//!   hash
+//! - `--import-bookmarks FILE [CONFIG]`: Add the links of a browser bookmark export as
+//!   sites
+//! - `--import-monitors FILE [CONFIG]`: Add the HTTP checks of a Gatus config or an
+//!   Uptime Kuma backup as sites
 //!
 //! ## Example Usage
 //!
@@ -20,24 +52,79 @@
 //!
 //! # Run with a specific port and configuration file
 //! cargo run 8080 my-config.json5
+//! cargo run -- --port 8080 --config my-config.json5
+//!
+//! # Listen only on localhost, with the config path from the environment
+//! IRON_SHIELD_CONFIG=/etc/iron-shield/config.json5 cargo run -- --bind 127.0.0.1
+//!
+//! # Create a starter configuration file
+//! cargo run -- --init my-config.json5
+//!
+//! # Run in the background, recording the PID for init scripts
+//! cargo run -- --daemon --pidfile /run/iron-shield.pid --log-file /var/log/iron-shield.log \
+//!     8080 my-config.json5
+//!
+//! # Restore the config file from a backup
+//! cargo run -- --restore-backup config-20240102T030405123Z.json5 my-config.json5
 //! ```
 //!
 //! The application includes comprehensive logging using the tracing framework.
 //! Log levels can be controlled through the `RUST_LOG` environment variable.
+//! Logs are written as text, or with `--log-format json` (or `IRON_SHIELD_LOG_FORMAT=json`)
+//! as one JSON object per line with the event's fields, such as `site`, `status`,
+//! `latency_ms`, and `route`, at the top level, for shipping to Loki or ELK.
 
+use std::collections::BTreeMap;
 use std::env;
 use std::path::PathBuf;
+use std::sync::Arc;
 use tokio_util::sync::CancellationToken;
-use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
+use tracing_subscriber::{
+    fmt::writer::BoxMakeWriter, layer::SubscriberExt, util::SubscriberInitExt, EnvFilter,
+};
 
+mod alerts;
+mod api_keys;
+mod assertions;
+mod assets;
+mod backup;
+mod bookmarks;
+mod branding;
+mod checkers;
+mod clock_drift;
 mod config;
+mod custom_templates;
+mod daemon;
 mod error;
+mod favicons;
+mod graphite;
+mod i18n;
+mod icons;
+mod import;
 mod index;
+mod influxdb;
+mod kiosk;
+mod migration;
+mod notifications;
+mod oneshot;
+mod otlp;
+mod preferences;
+mod report;
+mod search;
+mod secrets;
+mod self_monitor;
 mod server;
 mod settings;
+mod settings_page;
+mod statsd;
+mod systemd;
+mod tags;
 mod telemetry;
 mod uptime;
 mod utils;
+mod variables;
+mod weather;
+mod widgets;
 
 use crate::error::IronShieldError;
 
@@ -70,8 +157,16 @@ use crate::error::IronShieldError;
 ///
 /// # Command Line Arguments
 ///
-/// * First argument (optional): Port number to run the server on (defaults to 3000)
-/// * Second argument (optional): Path to the configuration file (defaults to "config.json5")
+/// * `--port PORT` or the first argument (optional): Port number to run the server on
+///   (defaults to 3000)
+/// * `--bind ADDRESS` (optional): IP address to listen on (defaults to 0.0.0.0)
+/// * `--config PATH` or the second argument (optional): Path to the configuration file
+///   (defaults to "config.json5")
+///
+/// A named flag wins over the positional argument, which wins over the config file's
+/// `port` and `bind` settings, which win over the `IRON_SHIELD_PORT` and
+/// `IRON_SHIELD_BIND` environment variables. The config path likewise comes from
+/// `--config`, then the second argument, then `IRON_SHIELD_CONFIG`.
 ///
 /// # Examples
 ///
@@ -84,31 +179,392 @@ use crate::error::IronShieldError;
 ///
 /// # Run on port 8080 with custom config file
 /// cargo run 8080 /path/to/config.json5
+/// cargo run -- --port 8080 --config /path/to/config.json5
+///
+/// # Listen only on localhost; `--port` wins over the positional 8080
+/// cargo run -- --bind 127.0.0.1 --port 9090 8080
 /// ```
 #[tokio::main]
 async fn main() -> Result<(), IronShieldError> {
+    let mut args: Vec<String> = env::args().collect();
+    let daemon = take_flag(&mut args, "--daemon");
+    let pidfile = take_option(&mut args, "--pidfile")?.map(PathBuf::from);
+    let port_flag = take_option(&mut args, "--port")?;
+    let bind_flag = take_option(&mut args, "--bind")?;
+    let config_flag = take_option(&mut args, "--config")?;
+    let log_format_flag = take_option(&mut args, "--log-format")?;
+    let log_file = take_option(&mut args, "--log-file")?.map(PathBuf::from);
+
+    if daemon {
+        let mut child_args = args[1..].to_vec();
+        for (flag, value) in [
+            ("--port", &port_flag),
+            ("--bind", &bind_flag),
+            ("--config", &config_flag),
+            ("--log-format", &log_format_flag),
+            (
+                "--log-file",
+                &log_file.as_ref().map(|path| path.display().to_string()),
+            ),
+        ] {
+            if let Some(value) = value {
+                child_args.push(flag.to_string());
+                child_args.push(value.clone());
+            }
+        }
+        if let Some(path) = &pidfile {
+            // Fail here rather than in the background, where nobody sees the error
+            daemon::ensure_not_running(path)?;
+            child_args.push("--pidfile".to_string());
+            child_args.push(path.display().to_string());
+        }
+        let pid = daemon::detach(&child_args, log_file.as_deref())?;
+        println!("Started iron_shield in the background with PID {pid}");
+        return Ok(());
+    }
+
+    let log_format = log_format_flag
+        .or_else(|| env::var(LOG_FORMAT_ENV).ok())
+        .map(|format| LogFormat::parse(&format))
+        .transpose()?
+        .unwrap_or(LogFormat::Text);
+
+    // `--check` prints a plugin status line first, so its logs go to stderr
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| "info".into());
+    let writer = if let Some(path) = &log_file {
+        BoxMakeWriter::new(Arc::new(daemon::open_log_file(path)?))
+    } else if args.get(1).is_some_and(|arg| arg == "--check") {
+        BoxMakeWriter::new(std::io::stderr)
+    } else {
+        BoxMakeWriter::new(std::io::stdout)
+    };
+    let (text_layer, json_layer) = match log_format {
+        LogFormat::Text => (
+            Some(
+                tracing_subscriber::fmt::layer()
+                    .with_ansi(log_file.is_none())
+                    .with_writer(writer),
+            ),
+            None,
+        ),
+        // One object per line with the event's fields at the top level, for Loki and ELK
+        LogFormat::Json => (
+            None,
+            Some(
+                tracing_subscriber::fmt::layer()
+                    .json()
+                    .flatten_event(true)
+                    .with_writer(writer),
+            ),
+        ),
+    };
     tracing_subscriber::registry()
-        .with(EnvFilter::try_from_default_env().unwrap_or_else(|_| "info".into()))
-        .with(tracing_subscriber::fmt::layer())
+        .with(filter)
+        .with(text_layer)
+        .with(json_layer)
         .init();
 
-    let port = env::args()
-        .nth(1)
-        .and_then(|s| s.parse().ok())
-        .unwrap_or(3000);
-
-    let mut config_file_path: Option<PathBuf> = None;
+    // Subcommands take the config path positionally or from `--config`
+    let config_arg = |index: usize| args.get(index).or(config_flag.as_ref());
 
-    if let Some(arg2) = env::args().nth(2) {
-        config_file_path = Some(PathBuf::from(arg2));
+    match args.get(1).map(String::as_str) {
+        Some("--init") => return init_config(config_arg(2)),
+        Some("--check-config") => check_config(config_arg(2)),
+        Some("--check") => check_sites(config_arg(2)).await,
+        Some("--list-backups") => return list_backups(config_arg(2)),
+        Some("--restore-backup") => {
+            let name = args.get(2).ok_or_else(|| {
+                IronShieldError::from("Usage: iron_shield --restore-backup NAME [CONFIG]")
+            })?;
+            return restore_backup(name, config_arg(3));
+        }
+        Some("--migrate-config") => return migrate_config(config_arg(2)),
+        Some("--generate-api-key") => {
+            let name = args.get(2).ok_or_else(|| {
+                IronShieldError::from("Usage: iron_shield --generate-api-key NAME")
+            })?;
+            generate_api_key(name);
+            return Ok(());
+        }
+        Some("--import-bookmarks") => {
+            let file = args.get(2).ok_or_else(|| {
+                IronShieldError::from("Usage: iron_shield --import-bookmarks FILE [CONFIG]")
+            })?;
+            return import_sites(file, config_arg(3), |html| {
+                Ok(bookmarks::convert(&bookmarks::parse(html)))
+            });
+        }
+        Some("--import-monitors") => {
+            let file = args.get(2).ok_or_else(|| {
+                IronShieldError::from("Usage: iron_shield --import-monitors FILE [CONFIG]")
+            })?;
+            return import_sites(file, config_arg(3), import::convert_monitors);
+        }
+        _ => {}
     }
 
+    // Named flags win over positional arguments, which win over the config file and env
+    let port = match port_flag {
+        Some(port) => Some(
+            port.trim()
+                .parse()
+                .ok()
+                .filter(|port| *port != 0)
+                .ok_or_else(|| {
+                    IronShieldError::from(format!("--port must be between 1 and 65535, not {port}"))
+                })?,
+        ),
+        None => args.get(1).and_then(|s| s.parse().ok()),
+    };
+    let bind = bind_flag
+        .map(|bind| {
+            bind.trim().parse().map_err(|_| {
+                IronShieldError::from(format!("--bind must be an IP address, not {bind}"))
+            })
+        })
+        .transpose()?;
+    let listen = server::Listen { port, bind };
+
+    let config_file_path = Some(config_path_arg(config_arg(2)));
+
     tracing::info!("Starting Iron Shield application");
     tracing::debug!("Application initialized with tracing enabled");
 
+    // Held until the server stops, which removes the file again
+    let _pidfile = pidfile
+        .map(|path| daemon::Pidfile::create(&path))
+        .transpose()?;
+
     let cancel_token = CancellationToken::new();
-    server::run(port, config_file_path, cancel_token).await?;
+    server::run(listen, config_file_path, cancel_token).await?;
 
     tracing::info!("Iron Shield application shutting down");
     Ok(())
 }
+
+/// Environment variable choosing the log format when `--log-format` is not given.
+const LOG_FORMAT_ENV: &str = "IRON_SHIELD_LOG_FORMAT";
+
+/// How log events are written
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LogFormat {
+    /// Human-readable lines
+    Text,
+    /// One JSON object per line
+    Json,
+}
+
+impl LogFormat {
+    fn parse(format: &str) -> Result<Self, IronShieldError> {
+        match format.trim().to_ascii_lowercase().as_str() {
+            "text" => Ok(LogFormat::Text),
+            "json" => Ok(LogFormat::Json),
+            _ => Err(IronShieldError::from(format!(
+                "--log-format must be \"text\" or \"json\", not \"{format}\""
+            ))),
+        }
+    }
+}
+
+/// Remove `flag` from `args`, returning whether it was given.
+fn take_flag(args: &mut Vec<String>, flag: &str) -> bool {
+    let before = args.len();
+    args.retain(|arg| arg != flag);
+    args.len() != before
+}
+
+/// Remove `option` and the value following it from `args`, returning the value.
+fn take_option(args: &mut Vec<String>, option: &str) -> Result<Option<String>, IronShieldError> {
+    let Some(index) = args.iter().position(|arg| arg == option) else {
+        return Ok(None);
+    };
+    if index + 1 >= args.len() {
+        return Err(IronShieldError::from(format!(
+            "Usage: iron_shield {option} VALUE"
+        )));
+    }
+    let value = args.remove(index + 1);
+    args.remove(index);
+    Ok(Some(value))
+}
+
+/// Write the commented starter config to a new file.
+fn init_config(config_arg: Option<&String>) -> Result<(), IronShieldError> {
+    let config_path = config_path_arg(config_arg);
+    config::write_starter_config(&config_path)?;
+    println!(
+        "Wrote starter config to {}; edit it, then run `iron_shield --check-config {}`",
+        config_path.display(),
+        config_path.display()
+    );
+    Ok(())
+}
+
+/// Load and validate the config file, print a summary, and exit non-zero if it is invalid.
+fn check_config(config_arg: Option<&String>) -> ! {
+    let config_path = config_path_arg(config_arg);
+    let config = match config::Config::load(&config_path) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("Config {} is invalid: {e}", config_path.display());
+            std::process::exit(1);
+        }
+    };
+
+    let disabled = config.sites.iter().filter(|site| site.disabled).count();
+    let mut intervals: BTreeMap<u64, usize> = BTreeMap::new();
+    for site in config.sites.iter().filter(|site| !site.disabled) {
+        *intervals
+            .entry(site.check_settings(&config.monitoring).interval_secs)
+            .or_default() += 1;
+    }
+
+    println!(
+        "Config {} is valid (version {})",
+        config_path.display(),
+        config.version
+    );
+    println!(
+        "  Sites: {} ({disabled} disabled, {} from includes)",
+        config.sites.len(),
+        config.included_sites.len()
+    );
+    if !intervals.is_empty() {
+        let intervals: Vec<String> = intervals
+            .iter()
+            .map(|(secs, count)| format!("{secs}s x {count}"))
+            .collect();
+        println!("  Check intervals: {}", intervals.join(", "));
+    }
+    println!("  Dashboards: {}", config.dashboards.len());
+
+    let warnings = config.warnings();
+    if !warnings.is_empty() {
+        println!("  Warnings:");
+        for warning in warnings {
+            println!("    {warning}");
+        }
+    }
+    std::process::exit(0);
+}
+
+/// Check every site once, print the report, and exit with its Nagios plugin status.
+async fn check_sites(config_arg: Option<&String>) -> ! {
+    let config_path = config_path_arg(config_arg);
+    let config = match config::Config::load(&config_path) {
+        Ok(config) => config,
+        Err(e) => {
+            println!(
+                "{} - config {} is invalid: {e}",
+                oneshot::Outcome::Unknown.label(),
+                config_path.display()
+            );
+            std::process::exit(oneshot::Outcome::Unknown.exit_code());
+        }
+    };
+
+    let (outcome, report) = oneshot::summarize(&oneshot::check_all(&config).await);
+    print!("{report}");
+    std::process::exit(outcome.exit_code());
+}
+
+/// Rewrite the config file in the current format version, backing up the old file.
+fn migrate_config(config_arg: Option<&String>) -> Result<(), IronShieldError> {
+    let config_path = config_path_arg(config_arg);
+    let rewritten = migration::migrate_file(&config_path, config::DEFAULT_CONFIG_BACKUP_RETENTION)?;
+    if rewritten.is_empty() {
+        println!(
+            "{} is already at config version {}",
+            config_path.display(),
+            migration::CURRENT_CONFIG_VERSION
+        );
+    }
+    for path in rewritten {
+        println!(
+            "Migrated {} to config version {}",
+            path.display(),
+            migration::CURRENT_CONFIG_VERSION
+        );
+    }
+    Ok(())
+}
+
+/// Print a new API key and the config entry that allows it.
+///
+/// Only the entry goes into the config file; the key itself is shown this once.
+fn generate_api_key(name: &str) {
+    let key = api_keys::generate();
+    let entry = serde_json::json!({ "name": name, "hash": api_keys::hash(&key) });
+    println!("API key for {name}: {key}");
+    println!("Add this entry to `api_keys` in the config file:");
+    println!("  {entry}");
+}
+
+/// Add the sites `convert` reads from `file` to the config file.
+fn import_sites(
+    file: &str,
+    config_arg: Option<&String>,
+    convert: impl FnOnce(&str) -> Result<import::Converted, IronShieldError>,
+) -> Result<(), IronShieldError> {
+    let config_path = config_path_arg(config_arg);
+    let contents = std::fs::read_to_string(file)
+        .map_err(|e| IronShieldError::from(format!("Failed to read {file}: {e}")))?;
+    let summary = import::import_file(&config_path, convert(&contents)?)?;
+    println!(
+        "Imported {} sites into {}",
+        summary.added.len(),
+        config_path.display()
+    );
+    if !summary.categories.is_empty() {
+        println!("  Added categories: {}", summary.categories.join(", "));
+    }
+    if !summary.skipped.is_empty() {
+        println!(
+            "  Skipped {} URLs that are already configured or are not web pages",
+            summary.skipped.len()
+        );
+    }
+    if !summary.unsupported.is_empty() {
+        println!("  Not imported:");
+        for setting in summary.unsupported {
+            println!("    {setting}");
+        }
+    }
+    Ok(())
+}
+
+/// Resolve the config file path given on the command line, then `IRON_SHIELD_CONFIG`,
+/// or the default.
+fn config_path_arg(arg: Option<&String>) -> PathBuf {
+    arg.map(PathBuf::from)
+        .or_else(|| env::var_os(config::CONFIG_PATH_ENV).map(PathBuf::from))
+        .unwrap_or_else(|| PathBuf::from(config::CONFIG_FILE))
+}
+
+/// Print the backups of the config file, newest first.
+fn list_backups(config_arg: Option<&String>) -> Result<(), IronShieldError> {
+    let config_path = config_path_arg(config_arg);
+    let backups = backup::list_backups(&config_path)?;
+    if backups.is_empty() {
+        println!("No backups of {}", config_path.display());
+    }
+    for backup in backups {
+        println!("{}\t{}", backup.name, backup.created.to_rfc3339());
+    }
+    Ok(())
+}
+
+/// Replace the config file with the named backup.
+///
+/// The current file is backed up first, using its configured retention when it still
+/// loads and the default retention otherwise.
+fn restore_backup(name: &str, config_arg: Option<&String>) -> Result<(), IronShieldError> {
+    let config_path = config_path_arg(config_arg);
+    let retention = config::Config::load(&config_path)
+        .map_or(config::DEFAULT_CONFIG_BACKUP_RETENTION, |config| {
+            config.config_backup_retention
+        });
+    backup::restore_backup(&config_path, name, retention)?;
+    println!("Restored {} from backup {name}", config_path.display());
+    Ok(())
+}
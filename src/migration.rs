@@ -0,0 +1,266 @@
+//! Versioned configuration migrations
+//!
+//! Config files carry a `version` number. Files written for an older release (including
+//! unversioned files, treated as version 0) are upgraded to the current shape on load, one
+//! step at a time, before they are deserialized, so a format change never turns an old
+//! config into a parse error. `iron_shield --migrate-config` rewrites a file in the
+//! current format after backing it up.
+//!
+//! | Version | Changes                                                               |
+//! |---------|-----------------------------------------------------------------------|
+//! | 0       | Unversioned; `clock` may use the settings API spelling (`"24hour"`),  |
+//! |         | sites may omit `tags` or give them as a comma-separated string        |
+//! | 1       | Current format with explicit `version`, `tags`, and intervals         |
+
+use crate::config::{ConfigFormat, DEFAULT_MONITOR_INTERVAL_SECS};
+use crate::error::{IronShieldError, Result};
+use serde_json::{Map, Value};
+use std::fs;
+use std::path::Path;
+use tracing::info;
+
+/// Version of the config format written by this release.
+pub const CURRENT_CONFIG_VERSION: u32 = 1;
+
+/// A migration upgrading a config object by one version.
+type Migration = fn(&mut Map<String, Value>);
+
+/// Migrations in order; the migration at index `n` upgrades version `n` to `n + 1`.
+const MIGRATIONS: [Migration; CURRENT_CONFIG_VERSION as usize] = [v0_explicit_site_fields];
+
+/// Returns the default config version for newly created configurations.
+#[must_use]
+pub fn default_config_version() -> u32 {
+    CURRENT_CONFIG_VERSION
+}
+
+/// Read the `version` of a parsed config value; a missing version is version 0.
+///
+/// # Errors
+///
+/// Returns a message if `version` is present but not a non-negative integer.
+pub fn config_version(value: &Value) -> std::result::Result<u32, String> {
+    match value.get("version") {
+        None => Ok(0),
+        Some(version) => version
+            .as_u64()
+            .and_then(|version| u32::try_from(version).ok())
+            .ok_or_else(|| format!("version must be a non-negative integer, found {version}")),
+    }
+}
+
+/// Upgrade a parsed config value to [`CURRENT_CONFIG_VERSION`] in place.
+///
+/// Returns the version the value had before migrating.
+///
+/// # Errors
+///
+/// Returns a message if the value is not an object, has an invalid `version`, or was
+/// written by a newer release than this one.
+///
+/// # Examples
+///
+/// ```
+/// use iron_shield::migration::{migrate, CURRENT_CONFIG_VERSION};
+///
+/// let mut value = serde_json::json!({
+///     "clock": "24hour",
+///     "sites": [{ "name": "Router", "url": "http://192.168.1.1" }]
+/// });
+///
+/// assert_eq!(migrate(&mut value).unwrap(), 0);
+/// assert_eq!(value["version"], CURRENT_CONFIG_VERSION);
+/// assert_eq!(value["clock"], "Hour24");
+/// assert_eq!(value["sites"][0]["tags"], serde_json::json!([]));
+/// ```
+pub fn migrate(value: &mut Value) -> std::result::Result<u32, String> {
+    let from_version = config_version(value)?;
+    if from_version > CURRENT_CONFIG_VERSION {
+        return Err(format!(
+            "config version {from_version} is newer than the latest supported version {CURRENT_CONFIG_VERSION}"
+        ));
+    }
+    let object = value
+        .as_object_mut()
+        .ok_or_else(|| "config must be an object".to_string())?;
+
+    for migration in &MIGRATIONS[from_version as usize..] {
+        migration(object);
+    }
+    object.insert("version".to_string(), Value::from(CURRENT_CONFIG_VERSION));
+    Ok(from_version)
+}
+
+/// Rewrite a config file, or each fragment of a config directory, in the current format.
+///
+/// Files that are already current are left untouched; others are backed up (see
+/// [`crate::backup`]) and rewritten with the migrated contents. Environment overrides,
+/// includes, and secret references are not applied, so the file keeps its own values.
+/// Comments in the original file are not preserved.
+///
+/// Returns the paths of the files that were rewritten.
+///
+/// # Errors
+///
+/// Returns an error if a file cannot be read, parsed, migrated, backed up, or written.
+pub fn migrate_file(path: &Path, backup_retention: usize) -> Result<Vec<std::path::PathBuf>> {
+    if path.is_dir() {
+        let mut fragments: Vec<_> = fs::read_dir(path)?
+            .filter_map(std::result::Result::ok)
+            .map(|entry| entry.path())
+            .filter(|fragment| fragment.is_file() && crate::config::is_config_fragment(fragment))
+            .collect();
+        fragments.sort();
+        let mut rewritten = Vec::new();
+        for fragment in fragments {
+            rewritten.extend(migrate_file(&fragment, backup_retention)?);
+        }
+        return Ok(rewritten);
+    }
+
+    let format = ConfigFormat::from_path(path);
+    let contents = fs::read_to_string(path)?;
+    let mut value: Value = format.parse(&contents).map_err(|e| {
+        IronShieldError::from(format!(
+            "Failed to parse config file {}: {e}",
+            path.display()
+        ))
+    })?;
+    let from_version = migrate(&mut value).map_err(|e| {
+        IronShieldError::from(format!(
+            "Failed to migrate config file {}: {e}",
+            path.display()
+        ))
+    })?;
+    if from_version == CURRENT_CONFIG_VERSION {
+        return Ok(Vec::new());
+    }
+
+    let migrated = format.serialize(&value).map_err(|e| {
+        IronShieldError::from(format!(
+            "Failed to serialize config file {}: {e}",
+            path.display()
+        ))
+    })?;
+    crate::backup::create_backup(path, backup_retention)?;
+    crate::config::write_atomic(path, &migrated)
+        .map_err(|e| IronShieldError::from(format!("Failed to write config file: {e}")))?;
+    info!(
+        "Migrated {} from config version {from_version} to {CURRENT_CONFIG_VERSION}",
+        path.display()
+    );
+    Ok(vec![path.to_path_buf()])
+}
+
+/// Version 0 to 1: spell out site defaults and accept the settings API clock names.
+fn v0_explicit_site_fields(config: &mut Map<String, Value>) {
+    if let Some(clock) = config.get_mut("clock") {
+        let renamed = match clock.as_str() {
+            Some("24hour") => Some("Hour24"),
+            Some("12hour") => Some("Hour12"),
+            Some("none") => Some("None"),
+            _ => None,
+        };
+        if let Some(renamed) = renamed {
+            *clock = Value::from(renamed);
+        }
+    }
+
+    let Some(Value::Array(sites)) = config.get_mut("sites") else {
+        return;
+    };
+    for site in sites.iter_mut().filter_map(Value::as_object_mut) {
+        match site.get("tags") {
+            None | Some(Value::Null) => {
+                site.insert("tags".to_string(), Value::Array(Vec::new()));
+            }
+            Some(Value::String(tags)) => {
+                let tags = tags
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|tag| !tag.is_empty())
+                    .map(Value::from)
+                    .collect();
+                site.insert("tags".to_string(), Value::Array(tags));
+            }
+            Some(_) => {}
+        }
+        site.entry("monitor_interval_secs")
+            .or_insert_with(|| Value::from(DEFAULT_MONITOR_INTERVAL_SECS));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn migrates_unversioned_config_to_current_shape() {
+        let mut value = json!({
+            "clock": "12hour",
+            "sites": [
+                { "name": "A", "url": "http://a.local", "tags": "media, home" },
+                { "name": "B", "url": "http://b.local", "tags": ["x"], "monitor_interval_secs": 30 }
+            ]
+        });
+
+        assert_eq!(migrate(&mut value), Ok(0));
+
+        assert_eq!(value["version"], CURRENT_CONFIG_VERSION);
+        assert_eq!(value["clock"], "Hour12");
+        assert_eq!(value["sites"][0]["tags"], json!(["media", "home"]));
+        assert_eq!(
+            value["sites"][0]["monitor_interval_secs"],
+            DEFAULT_MONITOR_INTERVAL_SECS
+        );
+        assert_eq!(value["sites"][1]["tags"], json!(["x"]));
+        assert_eq!(value["sites"][1]["monitor_interval_secs"], 30);
+    }
+
+    #[test]
+    fn leaves_current_config_unchanged_and_rejects_newer_versions() {
+        let mut current = json!({ "version": CURRENT_CONFIG_VERSION, "clock": "none" });
+        assert_eq!(migrate(&mut current), Ok(CURRENT_CONFIG_VERSION));
+        assert_eq!(current["clock"], "none");
+
+        let mut newer = json!({ "version": CURRENT_CONFIG_VERSION + 1 });
+        assert!(migrate(&mut newer).unwrap_err().contains("newer"));
+
+        let mut invalid = json!({ "version": "one" });
+        assert!(migrate(&mut invalid)
+            .unwrap_err()
+            .contains("non-negative integer"));
+    }
+
+    #[test]
+    fn migrate_file_rewrites_legacy_files_once() {
+        let dir = tempfile::tempdir().expect("Failed to create temporary directory");
+        let path = dir.path().join("config.yaml");
+        fs::write(
+            &path,
+            "clock: 24hour\nsites:\n  - name: A\n    url: http://a.local\n",
+        )
+        .expect("Failed to write config");
+
+        assert_eq!(
+            migrate_file(&path, 5).expect("Migration should succeed"),
+            vec![path.clone()]
+        );
+        let migrated: Value = ConfigFormat::Yaml
+            .parse(&fs::read_to_string(&path).expect("Failed to read config"))
+            .expect("Migrated file should parse");
+        assert_eq!(migrated["version"], CURRENT_CONFIG_VERSION);
+        assert_eq!(migrated["clock"], "Hour24");
+        assert_eq!(
+            crate::backup::list_backups(&path)
+                .expect("Failed to list backups")
+                .len(),
+            1
+        );
+
+        assert!(migrate_file(&path, 5)
+            .expect("Second migration should succeed")
+            .is_empty());
+    }
+}
@@ -0,0 +1,697 @@
+//! Push notifications
+//!
+//! The config's `notifiers` list names the push services told when a site goes down or
+//! comes back up. Each entry has a `type`:
+//!
+//! ```json5
+//! notifiers: [
+//!     // ntfy.sh or a self-hosted ntfy server; `token` is only needed for protected topics
+//!     { type: "ntfy", url: "https://ntfy.sh", topic: "homelab-alerts" },
+//!     // Gotify, with the token of the application the messages are posted as
+//!     { type: "gotify", url: "https://gotify.lan", token: "${env:GOTIFY_TOKEN}" },
+//! ]
+//! ```
+//!
+//! Notifications follow incidents rather than individual checks. The first failed check
+//! of a site opens an incident with a new ID (see [`IncidentTracker`]) and sends one
+//! [`Severity::Critical`] notification; further failures while it is open send nothing.
+//! The next successful check closes the incident with a [`Severity::Info`] notification
+//! saying how long the site was down, such as "Plex recovered after 12m". Incident IDs
+//! count up from 1 each time the server starts.
+//!
+//! Sites can be silenced for a while through the API (see the `alerts` module); their
+//! incidents are still tracked, but no notifications are sent for them.
+//!
+//! Each notifier's `priorities` map the severities to the service's own priority scale;
+//! tokens may be secret references (see the `secrets` module).
+//!
+//! A notifier's `templates` replace the built-in title and message, for services or
+//! chat channels that expect their own formatting:
+//!
+//! ```json5
+//! templates: {
+//!     title: "[{status}] {site}",
+//!     message: "{reason} {duration}\n{dashboard_url}",
+//! }
+//! ```
+//!
+//! Templates refer to these placeholders in braces; `{{` and `}}` stand for literal
+//! braces:
+//!
+//! - `site` - name of the site
+//! - `status` - `down` or `up`
+//! - `duration` - how long the site was down, such as `12m`; empty for outages
+//! - `reason` - why the check failed, or how fast the site responded once it recovered
+//! - `incident` - ID of the incident
+//! - `dashboard_url` - the config's `public_url`, or empty if it is not set
+
+use crate::uptime::{HistoryEntry, UptimeState, UptimeStatus};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use tracing::{debug, warn};
+
+/// Placeholders notification templates can refer to
+pub const PLACEHOLDERS: [&str; 6] = [
+    "site",
+    "status",
+    "duration",
+    "reason",
+    "incident",
+    "dashboard_url",
+];
+
+/// Server [`Ntfy`] notifiers publish to when `url` is not set
+pub const DEFAULT_NTFY_URL: &str = "https://ntfy.sh";
+
+/// How long a push service gets to accept a notification
+const SEND_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How urgent a notification is
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    /// A site went down
+    Critical,
+    /// A site recovered
+    Info,
+}
+
+/// Priority a notifier sends each severity with, on the scale of its service
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+pub struct Priorities {
+    /// Priority of notifications about a site going down
+    pub critical: u8,
+    /// Priority of notifications about a site recovering
+    pub info: u8,
+}
+
+impl Priorities {
+    /// The priority for `severity`
+    #[must_use]
+    pub fn of(self, severity: Severity) -> u8 {
+        match severity {
+            Severity::Critical => self.critical,
+            Severity::Info => self.info,
+        }
+    }
+}
+
+/// Returns the default ntfy priorities: `high` (4) for outages and `default` (3) for
+/// recoveries.
+#[must_use]
+pub fn default_ntfy_priorities() -> Priorities {
+    Priorities {
+        critical: 4,
+        info: 3,
+    }
+}
+
+/// Returns the default Gotify priorities: 8, which Android shows as a pop-up, for outages
+/// and 4 for recoveries.
+#[must_use]
+pub fn default_gotify_priorities() -> Priorities {
+    Priorities {
+        critical: 8,
+        info: 4,
+    }
+}
+
+/// Title and message templates of a notifier; unset ones use the built-in text
+#[derive(Debug, Deserialize, Serialize, Clone, Default, PartialEq, Eq)]
+pub struct Templates {
+    /// Template of the notification title
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// Template of the notification message
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+impl Templates {
+    /// Whether neither template is set
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.title.is_none() && self.message.is_none()
+    }
+
+    /// Problems with these templates, as pairs of the field name and what is wrong with it
+    #[must_use]
+    pub fn validate(&self) -> Vec<(String, String)> {
+        [("title", &self.title), ("message", &self.message)]
+            .into_iter()
+            .filter_map(|(field, template)| {
+                let template = template.as_deref()?;
+                render(template, |name| {
+                    PLACEHOLDERS.contains(&name).then(String::new)
+                })
+                .err()
+                .map(|message| (field.to_string(), message))
+            })
+            .collect()
+    }
+
+    /// The title and message of `notification` from these templates, linking to
+    /// `dashboard_url`
+    #[must_use]
+    pub fn apply(&self, notification: &Notification, dashboard_url: &str) -> (String, String) {
+        let fill = |template: Option<&String>, built_in: &String| {
+            template
+                .and_then(|template| {
+                    render(template, |name| {
+                        notification.placeholder(name, dashboard_url)
+                    })
+                    .ok()
+                })
+                .unwrap_or_else(|| built_in.clone())
+        };
+        (
+            fill(self.title.as_ref(), &notification.title),
+            fill(self.message.as_ref(), &notification.message),
+        )
+    }
+}
+
+/// Fill the `{name}` placeholders of `template` with `lookup`
+///
+/// `{{` and `}}` stand for literal braces.
+///
+/// # Errors
+///
+/// Returns a message naming a placeholder `lookup` does not know, or describing an
+/// unterminated placeholder or a lone `}`.
+///
+/// # Examples
+///
+/// ```
+/// use iron_shield::notifications::render;
+///
+/// let lookup = |name: &str| (name == "site").then(|| "Plex".to_string());
+///
+/// assert_eq!(render("*{site}* is down {{!}}", lookup).unwrap(), "*Plex* is down {!}");
+/// assert!(render("{host} is down", lookup).is_err());
+/// assert!(render("{site is down", lookup).is_err());
+/// ```
+pub fn render(template: &str, lookup: impl Fn(&str) -> Option<String>) -> Result<String, String> {
+    let mut rendered = String::with_capacity(template.len());
+    let mut chars = template.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                rendered.push('{');
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                rendered.push('}');
+            }
+            '{' => {
+                let mut name = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) => name.push(c),
+                        None => return Err(format!("\"{{{name}\" is missing its closing '}}'")),
+                    }
+                }
+                let value = lookup(name.trim()).ok_or_else(|| {
+                    format!(
+                        "\"{{{name}}}\" is not one of {}",
+                        PLACEHOLDERS.map(|name| format!("{{{name}}}")).join(", ")
+                    )
+                })?;
+                rendered.push_str(&value);
+            }
+            '}' => return Err("has a '}' without a '{'; write \"}}\" for a brace".to_string()),
+            c => rendered.push(c),
+        }
+    }
+    Ok(rendered)
+}
+
+/// Returns the default ntfy server.
+#[must_use]
+pub fn default_ntfy_url() -> String {
+    DEFAULT_NTFY_URL.to_string()
+}
+
+/// A push service notified about outages
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum Notifier {
+    /// An ntfy server
+    Ntfy(Ntfy),
+    /// A Gotify server
+    Gotify(Gotify),
+}
+
+/// Settings of an ntfy notifier
+///
+/// # Fields
+///
+/// * `url` - Base URL of the ntfy server, `https://ntfy.sh` by default
+/// * `topic` - Topic the notifications are published to
+/// * `token` - Access token for protected topics; may be a secret reference
+/// * `priorities` - ntfy priorities (1 to 5) of outages and recoveries
+/// * `templates` - Optional title and message templates
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+pub struct Ntfy {
+    /// Base URL of the server
+    #[serde(default = "default_ntfy_url")]
+    pub url: String,
+    /// Topic the notifications are published to
+    pub topic: String,
+    /// Access token for protected topics
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+    /// Priority of each severity
+    #[serde(default = "default_ntfy_priorities")]
+    pub priorities: Priorities,
+    /// Title and message templates
+    #[serde(default, skip_serializing_if = "Templates::is_empty")]
+    pub templates: Templates,
+}
+
+/// Settings of a Gotify notifier
+///
+/// # Fields
+///
+/// * `url` - Base URL of the Gotify server
+/// * `token` - Token of the Gotify application the messages are posted as; may be a
+///   secret reference
+/// * `priorities` - Gotify priorities (0 to 10) of outages and recoveries
+/// * `templates` - Optional title and message templates
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+pub struct Gotify {
+    /// Base URL of the server
+    pub url: String,
+    /// Application token
+    pub token: Option<String>,
+    /// Priority of each severity
+    #[serde(default = "default_gotify_priorities")]
+    pub priorities: Priorities,
+    /// Title and message templates
+    #[serde(default, skip_serializing_if = "Templates::is_empty")]
+    pub templates: Templates,
+}
+
+impl Notifier {
+    /// Problems with these settings, as pairs of the field name and what is wrong with it
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use iron_shield::notifications::{
+    ///     default_ntfy_priorities, Notifier, Ntfy, Priorities, Templates,
+    /// };
+    ///
+    /// let ntfy = Ntfy {
+    ///     url: "https://ntfy.sh".to_string(),
+    ///     topic: "homelab-alerts".to_string(),
+    ///     token: None,
+    ///     priorities: default_ntfy_priorities(),
+    ///     templates: Templates::default(),
+    /// };
+    /// assert!(Notifier::Ntfy(ntfy.clone()).validate().is_empty());
+    ///
+    /// let urgent = Ntfy {
+    ///     priorities: Priorities { critical: 9, info: 3 },
+    ///     ..ntfy
+    /// };
+    /// assert_eq!(Notifier::Ntfy(urgent).validate()[0].0, "priorities.critical");
+    /// ```
+    #[must_use]
+    pub fn validate(&self) -> Vec<(String, String)> {
+        let mut problems = Vec::new();
+        let (url, priorities, range, templates) = match self {
+            Notifier::Ntfy(ntfy) => {
+                if ntfy.topic.trim().is_empty() || ntfy.topic.contains('/') {
+                    problems.push((
+                        "topic".to_string(),
+                        "must be a topic name without '/'".to_string(),
+                    ));
+                }
+                (&ntfy.url, ntfy.priorities, 1..=5, &ntfy.templates)
+            }
+            Notifier::Gotify(gotify) => {
+                if gotify
+                    .token
+                    .as_deref()
+                    .is_none_or(|token| token.trim().is_empty())
+                {
+                    problems.push(("token".to_string(), "is required by gotify".to_string()));
+                }
+                (&gotify.url, gotify.priorities, 0..=10, &gotify.templates)
+            }
+        };
+        let valid_url = url::Url::parse(url.trim())
+            .is_ok_and(|url| matches!(url.scheme(), "http" | "https") && url.host().is_some());
+        if !valid_url {
+            problems.push((
+                "url".to_string(),
+                format!("\"{url}\" is not a valid http or https URL"),
+            ));
+        }
+        for (field, priority) in [("critical", priorities.critical), ("info", priorities.info)] {
+            if !range.contains(&priority) {
+                problems.push((
+                    format!("priorities.{field}"),
+                    format!("must be between {} and {}", range.start(), range.end()),
+                ));
+            }
+        }
+        for (field, message) in templates.validate() {
+            problems.push((format!("templates.{field}"), message));
+        }
+        problems
+    }
+
+    /// The secret-capable token of this notifier
+    pub(crate) fn token_mut(&mut self) -> &mut Option<String> {
+        match self {
+            Notifier::Ntfy(ntfy) => &mut ntfy.token,
+            Notifier::Gotify(gotify) => &mut gotify.token,
+        }
+    }
+
+    /// Send `notification` through this notifier's service, linking to `dashboard_url`
+    /// if it is not empty.
+    async fn send(&self, notification: &Notification, dashboard_url: &str) -> reqwest::Result<()> {
+        let client = reqwest::Client::new();
+        let request = match self {
+            Notifier::Ntfy(ntfy) => {
+                let tag = match notification.severity {
+                    Severity::Critical => "rotating_light",
+                    Severity::Info => "white_check_mark",
+                };
+                let (title, message) = ntfy.templates.apply(notification, dashboard_url);
+                let mut body = serde_json::json!({
+                    "topic": ntfy.topic.trim(),
+                    "title": title,
+                    "message": message,
+                    "priority": ntfy.priorities.of(notification.severity),
+                    "tags": [tag],
+                });
+                if !dashboard_url.is_empty() {
+                    body["click"] = dashboard_url.into();
+                }
+                let request = client.post(ntfy.url.trim()).json(&body);
+                match ntfy.token.as_deref().filter(|token| !token.is_empty()) {
+                    Some(token) => request.bearer_auth(token),
+                    None => request,
+                }
+            }
+            Notifier::Gotify(gotify) => {
+                let (title, message) = gotify.templates.apply(notification, dashboard_url);
+                client
+                    .post(format!(
+                        "{}/message",
+                        gotify.url.trim().trim_end_matches('/')
+                    ))
+                    .header("X-Gotify-Key", gotify.token.as_deref().unwrap_or_default())
+                    .json(&serde_json::json!({
+                        "title": title,
+                        "message": message,
+                        "priority": gotify.priorities.of(notification.severity),
+                    }))
+            }
+        };
+        request
+            .timeout(SEND_TIMEOUT)
+            .send()
+            .await?
+            .error_for_status()
+            .map(|_| ())
+    }
+
+    /// Name of the service, for log messages
+    fn service(&self) -> &'static str {
+        match self {
+            Notifier::Ntfy(_) => "ntfy",
+            Notifier::Gotify(_) => "Gotify",
+        }
+    }
+}
+
+/// A message about an incident opening or closing
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Notification {
+    /// Name of the site the notification is about
+    pub site: String,
+    /// ID of the incident the notification is about
+    pub incident_id: u64,
+    /// How urgent the notification is
+    pub severity: Severity,
+    /// Headline, such as `Plex is down`
+    pub title: String,
+    /// Details, such as why the check failed
+    pub message: String,
+    /// Why the check failed, or how fast the site responded once it recovered
+    pub reason: String,
+    /// How long the site was down, for recoveries
+    pub down_secs: Option<u64>,
+}
+
+impl Notification {
+    /// Value of the template placeholder `name`, or `None` for an unknown placeholder
+    fn placeholder(&self, name: &str, dashboard_url: &str) -> Option<String> {
+        Some(match name {
+            "site" => self.site.clone(),
+            "status" => match self.severity {
+                Severity::Critical => "down".to_string(),
+                Severity::Info => "up".to_string(),
+            },
+            "duration" => self
+                .down_secs
+                .map(crate::utils::format_duration)
+                .unwrap_or_default(),
+            "reason" => self.reason.clone(),
+            "incident" => self.incident_id.to_string(),
+            "dashboard_url" => dashboard_url.to_string(),
+            _ => return None,
+        })
+    }
+}
+
+/// An outage of a site that has not recovered yet
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OpenIncident {
+    /// ID of the incident, unique while the server runs
+    pub id: u64,
+    /// Unix timestamp of the check that opened it
+    pub started_at: u64,
+}
+
+/// The open incident of each site, so an outage is reported once rather than on every
+/// failed check
+#[derive(Debug, Default)]
+pub struct IncidentTracker {
+    open: Mutex<HashMap<String, OpenIncident>>,
+    last_id: AtomicU64,
+}
+
+impl IncidentTracker {
+    /// The open incidents, by site name
+    pub fn open_incidents(&self) -> HashMap<String, OpenIncident> {
+        self.open.lock().clone()
+    }
+
+    /// Record the completed check `entry` of `site`, returning the notification to send
+    /// if it opened or closed an incident.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use iron_shield::notifications::{IncidentTracker, Severity};
+    /// use iron_shield::uptime::{HistoryEntry, UptimeStatus};
+    ///
+    /// let check = |status, checked_at| HistoryEntry {
+    ///     status,
+    ///     response_time_ms: None,
+    ///     failure_reason: None,
+    ///     checked_at,
+    /// };
+    /// let incidents = IncidentTracker::default();
+    ///
+    /// let opened = incidents.record("Plex", &check(UptimeStatus::Down, 100)).unwrap();
+    /// assert_eq!((opened.incident_id, opened.severity), (1, Severity::Critical));
+    /// assert!(incidents.record("Plex", &check(UptimeStatus::Down, 160)).is_none());
+    ///
+    /// let closed = incidents.record("Plex", &check(UptimeStatus::Up, 820)).unwrap();
+    /// assert_eq!(closed.incident_id, 1);
+    /// assert_eq!(closed.title, "Plex recovered after 12m");
+    /// ```
+    pub fn record(&self, site: &str, entry: &HistoryEntry) -> Option<Notification> {
+        let mut open = self.open.lock();
+        match entry.status {
+            UptimeStatus::Down if !open.contains_key(site) => {
+                let incident = OpenIncident {
+                    id: self.last_id.fetch_add(1, Ordering::Relaxed) + 1,
+                    started_at: entry.checked_at,
+                };
+                open.insert(site.to_string(), incident);
+                let reason = entry
+                    .failure_reason
+                    .as_deref()
+                    .unwrap_or("The uptime check failed");
+                Some(Notification {
+                    site: site.to_string(),
+                    incident_id: incident.id,
+                    severity: Severity::Critical,
+                    title: format!("{site} is down"),
+                    message: format!("{reason} (incident #{})", incident.id),
+                    reason: reason.to_string(),
+                    down_secs: None,
+                })
+            }
+            UptimeStatus::Up => {
+                let incident = open.remove(site)?;
+                let down_secs = entry.checked_at.saturating_sub(incident.started_at);
+                let duration = crate::utils::format_duration(down_secs);
+                let response = entry.response_time_ms.map_or_else(
+                    || "The uptime check succeeded".to_string(),
+                    |ms| format!("Responded in {ms} ms"),
+                );
+                Some(Notification {
+                    site: site.to_string(),
+                    incident_id: incident.id,
+                    severity: Severity::Info,
+                    title: format!("{site} recovered after {duration}"),
+                    message: format!("{response} (incident #{})", incident.id),
+                    reason: response,
+                    down_secs: Some(down_secs),
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Send `notification` through every configured notifier in the background.
+///
+/// Failures are logged; a notification that cannot be delivered is not retried.
+pub fn dispatch(state: &UptimeState, notification: Notification) {
+    let config = state.config.load();
+    let notifiers = config.notifiers.clone();
+    let dashboard_url = config.public_url.clone().unwrap_or_default();
+    if notifiers.is_empty() {
+        return;
+    }
+    if state
+        .silences
+        .is_silenced(&notification.site, crate::uptime::unix_now())
+    {
+        debug!(
+            site = %notification.site,
+            "Not sending silenced notification: {}",
+            notification.title
+        );
+        return;
+    }
+
+    debug!(
+        site = %notification.site,
+        severity = ?notification.severity,
+        "Sending notification: {}",
+        notification.title
+    );
+    tokio::spawn(async move {
+        for notifier in notifiers {
+            if let Err(err) = notifier.send(&notification, dashboard_url.trim()).await {
+                warn!(
+                    "Failed to send {} notification for {}: {}",
+                    notifier.service(),
+                    notification.site,
+                    err.without_url()
+                );
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn notifiers_deserialize_by_type_with_default_priorities() {
+        let notifiers: Vec<Notifier> = json5::from_str(
+            r#"[
+                { type: "ntfy", topic: "alerts" },
+                { type: "gotify", url: "https://gotify.lan", token: "abc", priorities: { critical: 10, info: 1 } },
+            ]"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            notifiers,
+            [
+                Notifier::Ntfy(Ntfy {
+                    url: DEFAULT_NTFY_URL.to_string(),
+                    topic: "alerts".to_string(),
+                    token: None,
+                    priorities: default_ntfy_priorities(),
+                    templates: Templates::default(),
+                }),
+                Notifier::Gotify(Gotify {
+                    url: "https://gotify.lan".to_string(),
+                    token: Some("abc".to_string()),
+                    priorities: Priorities {
+                        critical: 10,
+                        info: 1,
+                    },
+                    templates: Templates::default(),
+                }),
+            ]
+        );
+        assert!(notifiers
+            .iter()
+            .all(|notifier| notifier.validate().is_empty()));
+    }
+
+    #[test]
+    fn templates_fill_placeholders_and_fall_back_to_built_in_text() {
+        let notification = Notification {
+            site: "Plex".to_string(),
+            incident_id: 3,
+            severity: Severity::Info,
+            title: "Plex recovered after 12m".to_string(),
+            message: "Responded in 85 ms (incident #3)".to_string(),
+            reason: "Responded in 85 ms".to_string(),
+            down_secs: Some(720),
+        };
+        let templates = Templates {
+            title: Some("[{status}] {site}".to_string()),
+            message: None,
+        };
+
+        assert_eq!(
+            templates.apply(&notification, "https://home.lan"),
+            (
+                "[up] Plex".to_string(),
+                "Responded in 85 ms (incident #3)".to_string()
+            )
+        );
+        let templates = Templates {
+            title: None,
+            message: Some("#{incident}: down for {duration}, {dashboard_url}".to_string()),
+        };
+        assert_eq!(
+            templates.apply(&notification, "https://home.lan").1,
+            "#3: down for 12m, https://home.lan"
+        );
+        assert!(templates.validate().is_empty());
+
+        let invalid = Templates {
+            title: Some("{host} is {status}".to_string()),
+            message: Some("{reason".to_string()),
+        };
+        let fields: Vec<_> = invalid
+            .validate()
+            .into_iter()
+            .map(|(field, _)| field)
+            .collect();
+        assert_eq!(fields, ["title", "message"]);
+    }
+}
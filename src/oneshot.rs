@@ -0,0 +1,206 @@
+//! One-shot checks
+//!
+//! `iron_shield --check [CONFIG]` checks every configured site once, prints a summary,
+//! and exits with the status codes of a Nagios plugin, so iron-shield can run as a
+//! check script inside other monitoring systems or a CI job:
+//!
+//! - `0` (OK) - every checked site is up
+//! - `1` (WARNING) - some sites are down
+//! - `2` (CRITICAL) - every checked site is down
+//! - `3` (UNKNOWN) - the config could not be loaded
+//!
+//! The first line of the output is the plugin status line, such as
+//! `WARNING - 4 of 5 sites up | up=4;;;0;5`, followed by one line per site with its
+//! status, response time, and why it failed. Paused sites are listed but not checked.
+//! Each site is checked with its own timeout, retries, user agent, and assertions, at
+//! most `monitoring.concurrency` at a time.
+
+use crate::config::{Config, Site};
+use crate::uptime::{check_now, UptimeStatus};
+use futures_util::stream::{self, StreamExt};
+
+/// Exit status of the check, in the Nagios plugin convention
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    /// Every checked site is up
+    Ok,
+    /// Some sites are down
+    Warning,
+    /// Every checked site is down
+    Critical,
+    /// The check could not run
+    Unknown,
+}
+
+impl Outcome {
+    /// The process exit code of this outcome
+    #[must_use]
+    pub fn exit_code(self) -> i32 {
+        match self {
+            Outcome::Ok => 0,
+            Outcome::Warning => 1,
+            Outcome::Critical => 2,
+            Outcome::Unknown => 3,
+        }
+    }
+
+    /// The label of this outcome in the status line
+    #[must_use]
+    pub fn label(self) -> &'static str {
+        match self {
+            Outcome::Ok => "OK",
+            Outcome::Warning => "WARNING",
+            Outcome::Critical => "CRITICAL",
+            Outcome::Unknown => "UNKNOWN",
+        }
+    }
+}
+
+/// Result of checking one site
+#[derive(Debug, Clone, PartialEq)]
+pub struct SiteResult {
+    /// Name of the site
+    pub name: String,
+    /// `Up` or `Down`, or `Disabled` for a paused site that was not checked
+    pub status: UptimeStatus,
+    /// Response time of the check, in ms
+    pub response_time_ms: Option<u64>,
+    /// Why the check failed
+    pub failure_reason: Option<String>,
+}
+
+/// Check every site of `config` once, in the configured order.
+pub async fn check_all(config: &Config) -> Vec<SiteResult> {
+    let concurrency = config.monitoring.concurrency.max(1);
+    stream::iter(&config.sites)
+        .map(|site| check_one(site, config))
+        .buffered(concurrency)
+        .collect()
+        .await
+}
+
+async fn check_one(site: &Site, config: &Config) -> SiteResult {
+    if site.disabled {
+        return SiteResult {
+            name: site.name.clone(),
+            status: UptimeStatus::Disabled,
+            response_time_ms: None,
+            failure_reason: None,
+        };
+    }
+    let entry = check_now(&site.url, &site.check_settings(&config.monitoring)).await;
+    SiteResult {
+        name: site.name.clone(),
+        status: entry.status,
+        response_time_ms: entry.response_time_ms,
+        failure_reason: entry.failure_reason,
+    }
+}
+
+/// The outcome of `results` and the report to print: the status line with performance
+/// data, then a table of the sites
+///
+/// # Examples
+///
+/// ```
+/// use iron_shield::oneshot::{summarize, Outcome, SiteResult};
+/// use iron_shield::uptime::UptimeStatus;
+///
+/// let result = |name: &str, status| SiteResult {
+///     name: name.to_string(),
+///     status,
+///     response_time_ms: Some(80),
+///     failure_reason: None,
+/// };
+///
+/// let (outcome, report) = summarize(&[
+///     result("Plex", UptimeStatus::Up),
+///     result("NAS", UptimeStatus::Down),
+/// ]);
+/// assert_eq!(outcome, Outcome::Warning);
+/// assert!(report.starts_with("WARNING - 1 of 2 sites up | up=1;;;0;2\n"));
+/// ```
+#[must_use]
+pub fn summarize(results: &[SiteResult]) -> (Outcome, String) {
+    let checked = results
+        .iter()
+        .filter(|result| result.status != UptimeStatus::Disabled)
+        .count();
+    let up = results
+        .iter()
+        .filter(|result| result.status == UptimeStatus::Up)
+        .count();
+    let outcome = if up == checked {
+        Outcome::Ok
+    } else if up == 0 {
+        Outcome::Critical
+    } else {
+        Outcome::Warning
+    };
+
+    let mut report = format!(
+        "{} - {up} of {checked} sites up | up={up};;;0;{checked}\n",
+        outcome.label()
+    );
+    let width = results
+        .iter()
+        .map(|result| result.name.chars().count())
+        .max()
+        .unwrap_or(0);
+    for result in results {
+        let status = match result.status {
+            UptimeStatus::Up => "UP",
+            UptimeStatus::Down => "DOWN",
+            UptimeStatus::Disabled => "PAUSED",
+            UptimeStatus::Loading => "UNKNOWN",
+        };
+        let response = result
+            .response_time_ms
+            .filter(|_| result.status != UptimeStatus::Disabled)
+            .map_or_else(|| "-".to_string(), |ms| format!("{ms} ms"));
+        let line = format!(
+            "{:<width$}  {status:<7} {response:>8}  {}",
+            result.name,
+            result.failure_reason.as_deref().unwrap_or_default()
+        );
+        report.push_str(line.trim_end());
+        report.push('\n');
+    }
+    (outcome, report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn summarize_reports_outcome_and_table() {
+        let result = |name: &str, status, reason: Option<&str>| SiteResult {
+            name: name.to_string(),
+            status,
+            response_time_ms: Some(120),
+            failure_reason: reason.map(str::to_string),
+        };
+
+        let (outcome, report) = summarize(&[
+            result(
+                "Router",
+                UptimeStatus::Down,
+                Some("request failed: timeout"),
+            ),
+            result("Backup", UptimeStatus::Disabled, None),
+        ]);
+
+        assert_eq!(outcome, Outcome::Critical);
+        assert_eq!(outcome.exit_code(), 2);
+        assert_eq!(
+            report,
+            "CRITICAL - 0 of 1 sites up | up=0;;;0;1\n\
+             Router  DOWN      120 ms  request failed: timeout\n\
+             Backup  PAUSED         -\n"
+        );
+
+        let (outcome, _) = summarize(&[result("Router", UptimeStatus::Up, None)]);
+        assert_eq!(outcome, Outcome::Ok);
+    }
+}
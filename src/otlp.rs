@@ -0,0 +1,512 @@
+//! OTLP metrics and traces encoding
+//!
+//! Builds the `ExportMetricsServiceRequest` and `ExportTraceServiceRequest` messages of
+//! the OpenTelemetry protocol in its protobuf wire format, which every OTLP collector
+//! accepts, such as the OpenTelemetry Collector or Grafana Alloy, either as the body of
+//! an OTLP/HTTP request or framed as a gRPC message for OTLP/gRPC. Only the parts of the
+//! schema iron-shield sends are covered: a resource with string attributes, one
+//! instrumentation scope, gauges and delta sums of number data points, and spans without
+//! parents or links.
+//!
+//! The messages are written by hand rather than generated from the `.proto` files; the
+//! field numbers below follow `opentelemetry/proto/metrics/v1/metrics.proto`,
+//! `opentelemetry/proto/trace/v1/trace.proto`, and
+//! `opentelemetry/proto/common/v1/common.proto`.
+
+/// Path collectors receive metrics at over OTLP/HTTP
+pub const METRICS_PATH: &str = "/v1/metrics";
+/// Path collectors receive traces at over OTLP/HTTP
+pub const TRACES_PATH: &str = "/v1/traces";
+/// Path of the `Export` method of the gRPC `MetricsService`
+pub const GRPC_EXPORT_PATH: &str =
+    "/opentelemetry.proto.collector.metrics.v1.MetricsService/Export";
+/// Path of the `Export` method of the gRPC `TraceService`
+pub const GRPC_TRACE_EXPORT_PATH: &str =
+    "/opentelemetry.proto.collector.trace.v1.TraceService/Export";
+/// Name of the instrumentation scope the metrics are reported under
+pub const SCOPE_NAME: &str = "iron_shield";
+
+/// Protobuf wire type of varint fields
+const VARINT: u8 = 0;
+/// Protobuf wire type of 64-bit fields
+const FIXED64: u8 = 1;
+/// Protobuf wire type of strings, bytes, and embedded messages
+const LENGTH_DELIMITED: u8 = 2;
+
+/// `AGGREGATION_TEMPORALITY_DELTA` of the OTLP schema
+const AGGREGATION_TEMPORALITY_DELTA: u64 = 1;
+/// `SPAN_KIND_CLIENT` of the OTLP schema, for spans of outgoing requests
+const SPAN_KIND_CLIENT: u64 = 3;
+
+/// Value of a data point
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Value {
+    /// A whole number, such as 1 for a site that is up
+    Int(i64),
+    /// A measurement, such as a response time
+    Double(f64),
+}
+
+/// One measurement of a metric
+#[derive(Debug, Clone, PartialEq)]
+pub struct DataPoint {
+    /// Attributes telling the data points of a metric apart, such as the site name
+    pub attributes: Vec<(&'static str, String)>,
+    /// When the value was measured, in nanoseconds since the Unix epoch
+    pub time_unix_nano: u64,
+    /// The measured value
+    pub value: Value,
+}
+
+/// How the data points of a metric combine
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    /// The latest value of something, such as a site's status
+    Gauge,
+    /// A count of events since the previous export, such as clicks
+    DeltaCounter,
+}
+
+/// A named metric with its data points
+#[derive(Debug, Clone, PartialEq)]
+pub struct Metric {
+    /// Name of the metric, such as `iron_shield.site.up`
+    pub name: &'static str,
+    /// What the metric measures
+    pub description: &'static str,
+    /// Unit of the values in UCUM notation, such as `ms`, or empty for none
+    pub unit: &'static str,
+    /// How the data points combine
+    pub kind: Kind,
+    /// Measurements of the metric
+    pub data_points: Vec<DataPoint>,
+}
+
+/// Something that happened at a point in time during a span
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpanEvent {
+    /// Name of the event, such as `response.headers`
+    pub name: &'static str,
+    /// When it happened, in nanoseconds since the Unix epoch
+    pub time_unix_nano: u64,
+    /// Details of the event
+    pub attributes: Vec<(&'static str, String)>,
+}
+
+/// Outcome of a span
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SpanStatus {
+    /// The operation succeeded
+    Ok,
+    /// The operation failed, with a description of why
+    Error(String),
+}
+
+/// A timed operation, such as one uptime check
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Span {
+    /// ID of the trace the span belongs to
+    pub trace_id: [u8; 16],
+    /// ID of the span
+    pub span_id: [u8; 8],
+    /// Name of the operation
+    pub name: String,
+    /// When the operation started, in nanoseconds since the Unix epoch
+    pub start_time_unix_nano: u64,
+    /// When the operation ended, in nanoseconds since the Unix epoch
+    pub end_time_unix_nano: u64,
+    /// Attributes describing the operation, such as the URL requested
+    pub attributes: Vec<(&'static str, String)>,
+    /// What happened during the operation, in order
+    pub events: Vec<SpanEvent>,
+    /// Whether the operation succeeded
+    pub status: SpanStatus,
+}
+
+/// The OTLP/HTTP metrics URL of `endpoint`
+///
+/// `endpoint` is the collector's base URL, to which [`METRICS_PATH`] is appended like
+/// the `OTEL_EXPORTER_OTLP_ENDPOINT` variable of the OpenTelemetry SDKs; a URL that
+/// already ends in it is used as is.
+///
+/// # Examples
+///
+/// ```
+/// use iron_shield::otlp::metrics_url;
+///
+/// assert_eq!(metrics_url("http://collector:4318"), "http://collector:4318/v1/metrics");
+/// assert_eq!(metrics_url("http://collector:4318/"), "http://collector:4318/v1/metrics");
+/// assert_eq!(
+///     metrics_url("https://otlp.example.com/v1/metrics"),
+///     "https://otlp.example.com/v1/metrics"
+/// );
+/// ```
+#[must_use]
+pub fn metrics_url(endpoint: &str) -> String {
+    signal_url(endpoint, METRICS_PATH)
+}
+
+/// The OTLP/HTTP traces URL of `endpoint`
+///
+/// Like [`metrics_url`], [`TRACES_PATH`] is appended to the collector's base URL; an
+/// endpoint written as the metrics URL has its path replaced.
+///
+/// # Examples
+///
+/// ```
+/// use iron_shield::otlp::traces_url;
+///
+/// assert_eq!(traces_url("http://collector:4318"), "http://collector:4318/v1/traces");
+/// assert_eq!(
+///     traces_url("https://otlp.example.com/v1/metrics"),
+///     "https://otlp.example.com/v1/traces"
+/// );
+/// ```
+#[must_use]
+pub fn traces_url(endpoint: &str) -> String {
+    signal_url(endpoint, TRACES_PATH)
+}
+
+/// `endpoint` with the OTLP/HTTP path of a signal, `path`, in place of any signal path
+/// it already ends in
+fn signal_url(endpoint: &str, path: &str) -> String {
+    let endpoint = endpoint.trim().trim_end_matches('/');
+    let base = [METRICS_PATH, TRACES_PATH]
+        .iter()
+        .find_map(|signal| endpoint.strip_suffix(signal))
+        .unwrap_or(endpoint);
+    format!("{base}{path}")
+}
+
+/// The OTLP/gRPC export URL of `endpoint`
+///
+/// `endpoint` is the collector's gRPC address as a URL, such as `http://collector:4317`;
+/// gRPC methods live at fixed paths, so any path in it is replaced by
+/// [`GRPC_EXPORT_PATH`].
+///
+/// # Examples
+///
+/// ```
+/// use iron_shield::otlp::grpc_url;
+///
+/// assert_eq!(
+///     grpc_url("http://collector:4317/"),
+///     "http://collector:4317/opentelemetry.proto.collector.metrics.v1.MetricsService/Export"
+/// );
+/// ```
+#[must_use]
+pub fn grpc_url(endpoint: &str) -> String {
+    grpc_method_url(endpoint, GRPC_EXPORT_PATH)
+}
+
+/// The OTLP/gRPC trace export URL of `endpoint`, like [`grpc_url`] for metrics
+#[must_use]
+pub fn grpc_traces_url(endpoint: &str) -> String {
+    grpc_method_url(endpoint, GRPC_TRACE_EXPORT_PATH)
+}
+
+/// `endpoint` with its path replaced by the gRPC method path `method`
+fn grpc_method_url(endpoint: &str, method: &str) -> String {
+    let endpoint = endpoint.trim();
+    match url::Url::parse(endpoint) {
+        Ok(mut url) => {
+            url.set_path(method);
+            url.set_query(None);
+            url.to_string()
+        }
+        Err(_) => format!("{}{method}", endpoint.trim_end_matches('/')),
+    }
+}
+
+/// Wrap an encoded message in the length-prefixed frame gRPC sends messages in: a zero
+/// byte for "not compressed", then the message length as a big-endian `u32`.
+///
+/// # Panics
+///
+/// Panics if the message is 4 GiB or larger, which gRPC cannot carry.
+#[must_use]
+pub fn grpc_frame(message: &[u8]) -> Vec<u8> {
+    let length = u32::try_from(message.len()).expect("gRPC messages are below 4 GiB");
+    let mut frame = Vec::with_capacity(message.len() + 5);
+    frame.push(0);
+    frame.extend_from_slice(&length.to_be_bytes());
+    frame.extend_from_slice(message);
+    frame
+}
+
+/// Encode an `ExportMetricsServiceRequest` with one resource, described by
+/// `resource_attributes`, reporting `metrics` under the [`SCOPE_NAME`] scope.
+#[must_use]
+pub fn encode_metrics_request(
+    resource_attributes: &[(&'static str, String)],
+    metrics: &[Metric],
+) -> Vec<u8> {
+    let mut scope_metrics = Vec::new();
+    write_message(&mut scope_metrics, 1, &encode_scope());
+    for metric in metrics
+        .iter()
+        .filter(|metric| !metric.data_points.is_empty())
+    {
+        write_message(&mut scope_metrics, 2, &encode_metric(metric));
+    }
+
+    let mut resource_metrics = Vec::new();
+    write_message(
+        &mut resource_metrics,
+        1,
+        &encode_resource(resource_attributes),
+    );
+    write_message(&mut resource_metrics, 2, &scope_metrics);
+
+    let mut request = Vec::new();
+    write_message(&mut request, 1, &resource_metrics);
+    request
+}
+
+/// Encode an `ExportTraceServiceRequest` with one resource, described by
+/// `resource_attributes`, reporting `spans` under the [`SCOPE_NAME`] scope.
+#[must_use]
+pub fn encode_traces_request(
+    resource_attributes: &[(&'static str, String)],
+    spans: &[Span],
+) -> Vec<u8> {
+    let mut scope_spans = Vec::new();
+    write_message(&mut scope_spans, 1, &encode_scope());
+    for span in spans {
+        write_message(&mut scope_spans, 2, &encode_span(span));
+    }
+
+    let mut resource_spans = Vec::new();
+    write_message(
+        &mut resource_spans,
+        1,
+        &encode_resource(resource_attributes),
+    );
+    write_message(&mut resource_spans, 2, &scope_spans);
+
+    let mut request = Vec::new();
+    write_message(&mut request, 1, &resource_spans);
+    request
+}
+
+/// `Resource` message with string attributes
+fn encode_resource(attributes: &[(&'static str, String)]) -> Vec<u8> {
+    let mut resource = Vec::new();
+    for (key, value) in attributes {
+        write_message(&mut resource, 1, &encode_key_value(key, value));
+    }
+    resource
+}
+
+/// `InstrumentationScope` message naming iron-shield and its version
+fn encode_scope() -> Vec<u8> {
+    let mut scope = Vec::new();
+    write_string(&mut scope, 1, SCOPE_NAME);
+    write_string(&mut scope, 2, env!("CARGO_PKG_VERSION"));
+    scope
+}
+
+/// `Span` message of a client span
+fn encode_span(span: &Span) -> Vec<u8> {
+    let mut out = Vec::new();
+    write_message(&mut out, 1, &span.trace_id);
+    write_message(&mut out, 2, &span.span_id);
+    write_string(&mut out, 5, &span.name);
+    write_varint_field(&mut out, 6, SPAN_KIND_CLIENT);
+    write_fixed64_field(&mut out, 7, span.start_time_unix_nano);
+    write_fixed64_field(&mut out, 8, span.end_time_unix_nano);
+    for (key, value) in &span.attributes {
+        write_message(&mut out, 9, &encode_key_value(key, value));
+    }
+    for event in &span.events {
+        let mut encoded = Vec::new();
+        write_fixed64_field(&mut encoded, 1, event.time_unix_nano);
+        write_string(&mut encoded, 2, event.name);
+        for (key, value) in &event.attributes {
+            write_message(&mut encoded, 3, &encode_key_value(key, value));
+        }
+        write_message(&mut out, 11, &encoded);
+    }
+
+    // `Status` with `STATUS_CODE_OK` (1) or `STATUS_CODE_ERROR` (2) and its message
+    let mut status = Vec::new();
+    match &span.status {
+        SpanStatus::Ok => write_varint_field(&mut status, 3, 1),
+        SpanStatus::Error(message) => {
+            write_string(&mut status, 2, message);
+            write_varint_field(&mut status, 3, 2);
+        }
+    }
+    write_message(&mut out, 15, &status);
+    out
+}
+
+/// `Metric` message
+fn encode_metric(metric: &Metric) -> Vec<u8> {
+    let mut points = Vec::new();
+    for point in &metric.data_points {
+        write_message(&mut points, 1, &encode_data_point(point, metric.kind));
+    }
+
+    let mut out = Vec::new();
+    write_string(&mut out, 1, metric.name);
+    write_string(&mut out, 2, metric.description);
+    if !metric.unit.is_empty() {
+        write_string(&mut out, 3, metric.unit);
+    }
+    match metric.kind {
+        Kind::Gauge => write_message(&mut out, 5, &points),
+        Kind::DeltaCounter => {
+            write_varint_field(&mut points, 2, AGGREGATION_TEMPORALITY_DELTA);
+            write_varint_field(&mut points, 3, 1);
+            write_message(&mut out, 7, &points);
+        }
+    }
+    out
+}
+
+/// `NumberDataPoint` message; counters start and end at the same time, since each
+/// export carries the events of one moment.
+fn encode_data_point(point: &DataPoint, kind: Kind) -> Vec<u8> {
+    let mut out = Vec::new();
+    if kind == Kind::DeltaCounter {
+        write_fixed64_field(&mut out, 2, point.time_unix_nano);
+    }
+    write_fixed64_field(&mut out, 3, point.time_unix_nano);
+    match point.value {
+        Value::Double(value) => write_fixed64_field(&mut out, 4, value.to_bits()),
+        Value::Int(value) => write_fixed64_field(&mut out, 6, value.cast_unsigned()),
+    }
+    for (key, value) in &point.attributes {
+        write_message(&mut out, 7, &encode_key_value(key, value));
+    }
+    out
+}
+
+/// `KeyValue` message with a string value
+fn encode_key_value(key: &str, value: &str) -> Vec<u8> {
+    let mut any_value = Vec::new();
+    write_string(&mut any_value, 1, value);
+    let mut out = Vec::new();
+    write_string(&mut out, 1, key);
+    write_message(&mut out, 2, &any_value);
+    out
+}
+
+fn write_varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        // Truncation keeps the low seven bits, the continuation bit is set explicitly
+        #[allow(clippy::cast_possible_truncation)]
+        out.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    #[allow(clippy::cast_possible_truncation)]
+    out.push(value as u8);
+}
+
+fn write_key(out: &mut Vec<u8>, field: u32, wire_type: u8) {
+    write_varint(out, u64::from(field) << 3 | u64::from(wire_type));
+}
+
+fn write_varint_field(out: &mut Vec<u8>, field: u32, value: u64) {
+    write_key(out, field, VARINT);
+    write_varint(out, value);
+}
+
+fn write_fixed64_field(out: &mut Vec<u8>, field: u32, value: u64) {
+    write_key(out, field, FIXED64);
+    out.extend_from_slice(&value.to_le_bytes());
+}
+
+fn write_message(out: &mut Vec<u8>, field: u32, message: &[u8]) {
+    write_key(out, field, LENGTH_DELIMITED);
+    write_varint(out, message.len() as u64);
+    out.extend_from_slice(message);
+}
+
+fn write_string(out: &mut Vec<u8>, field: u32, value: &str) {
+    write_message(out, field, value.as_bytes());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encodes_wire_format() {
+        let mut varint = Vec::new();
+        write_varint(&mut varint, 300);
+        assert_eq!(varint, [0xac, 0x02]);
+
+        assert_eq!(
+            encode_key_value("a", "b"),
+            [0x0a, 0x01, b'a', 0x12, 0x03, 0x0a, 0x01, b'b']
+        );
+
+        let point = DataPoint {
+            attributes: Vec::new(),
+            time_unix_nano: 1,
+            value: Value::Int(-1),
+        };
+        let mut expected = vec![0x19, 1, 0, 0, 0, 0, 0, 0, 0, 0x31];
+        expected.extend_from_slice(&[0xff; 8]);
+        assert_eq!(encode_data_point(&point, Kind::Gauge), expected);
+
+        assert_eq!(grpc_frame(b"ab"), [0, 0, 0, 0, 2, b'a', b'b']);
+    }
+
+    #[test]
+    fn leaves_out_metrics_without_data_points() {
+        let metric = |data_points| Metric {
+            name: "iron_shield.site.up",
+            description: "Whether the site is up",
+            unit: "",
+            kind: Kind::Gauge,
+            data_points,
+        };
+        let empty = encode_metrics_request(&[], &[metric(Vec::new())]);
+        let reported = encode_metrics_request(
+            &[],
+            &[metric(vec![DataPoint {
+                attributes: vec![("site.name", "Plex".to_string())],
+                time_unix_nano: 0,
+                value: Value::Int(1),
+            }])],
+        );
+        let contains = |haystack: &[u8], needle: &[u8]| {
+            haystack
+                .windows(needle.len())
+                .any(|window| window == needle)
+        };
+        assert!(!contains(&empty, b"iron_shield.site.up"));
+        assert!(contains(&reported, b"iron_shield.site.up"));
+        assert!(contains(&reported, b"Plex"));
+    }
+
+    #[test]
+    fn encodes_span_ids_times_and_error_status() {
+        let span = Span {
+            trace_id: [1; 16],
+            span_id: [2; 8],
+            name: "check".to_string(),
+            start_time_unix_nano: 5,
+            end_time_unix_nano: 9,
+            attributes: Vec::new(),
+            events: Vec::new(),
+            status: SpanStatus::Error("timed out".to_string()),
+        };
+
+        let mut expected = vec![0x0a, 16];
+        expected.extend_from_slice(&[1; 16]);
+        expected.extend_from_slice(&[0x12, 8]);
+        expected.extend_from_slice(&[2; 8]);
+        expected.extend_from_slice(&[0x2a, 5]);
+        expected.extend_from_slice(b"check");
+        expected.extend_from_slice(&[0x30, 3, 0x39, 5, 0, 0, 0, 0, 0, 0, 0]);
+        expected.extend_from_slice(&[0x41, 9, 0, 0, 0, 0, 0, 0, 0]);
+        expected.extend_from_slice(&[0x7a, 13, 0x12, 9]);
+        expected.extend_from_slice(b"timed out");
+        expected.extend_from_slice(&[0x18, 2]);
+        assert_eq!(encode_span(&span), expected);
+    }
+}
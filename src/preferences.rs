@@ -0,0 +1,392 @@
+//! Per-browser preferences
+//!
+//! Each browser viewing the dashboard can keep its own theme, layout, and tag and
+//! category filters without a config of its own. They are kept in a signed cookie,
+//! [`PREFERENCES_COOKIE`], which the dashboard pages read on every request:
+//!
+//! - `GET /preferences` returns this browser's preferences
+//! - `PATCH /preferences` with `{ "layout": "compact", "tag": "media" }` changes the
+//!   given preferences; `null` clears one
+//! - `DELETE /preferences` clears them all
+//!
+//! The cookie holds the preferences as JSON with an HMAC-SHA256 signature, so a page
+//! only uses values this server wrote. The signing key is kept in [`KEY_FILE`] next to
+//! the config file and created on first start; deleting it invalidates every browser's
+//! preferences. The endpoints are outside `/api/` because they change nothing on the
+//! server, so they need no API key.
+
+use crate::config::{Layout, Theme};
+use crate::error::{IronShieldError, Result};
+use crate::uptime::UptimeState;
+use crate::utils::{from_hex, hex};
+use axum::{
+    extract::State,
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Json, Response},
+};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tracing::{error, warn};
+
+/// Name of the cookie holding a browser's preferences
+pub const PREFERENCES_COOKIE: &str = "iron_shield_preferences";
+/// Name of the file next to the config file that holds the cookie signing key
+pub const KEY_FILE: &str = ".iron_shield_cookie_key";
+/// How long a browser keeps its preferences cookie (one year)
+const COOKIE_MAX_AGE_SECS: u64 = 365 * 24 * 60 * 60;
+/// Longest cookie value written, so browsers do not drop it
+const MAX_COOKIE_VALUE_LEN: usize = 3800;
+
+/// A browser's own choices, each overriding the configured value when set
+///
+/// # Fields
+///
+/// * `theme` - Color scheme, instead of the configured `theme`
+/// * `layout` - How sites are laid out, instead of the configured `layout`
+/// * `tag` - Comma-separated tags the dashboard is filtered by when the URL sets no filter
+/// * `category` - Category the dashboard is filtered by when the URL sets no filter
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Preferences {
+    /// Color scheme of the dashboard in this browser
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub theme: Option<Theme>,
+    /// How sites are laid out in this browser
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub layout: Option<Layout>,
+    /// Comma-separated tags a site must have one of
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub tag: String,
+    /// Category a site must belong to
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub category: String,
+}
+
+impl Preferences {
+    /// The preferences in the request's [`PREFERENCES_COOKIE`] cookie, or none when it
+    /// is missing or its signature does not match.
+    #[must_use]
+    pub fn from_headers(headers: &HeaderMap, key: &SigningKey) -> Self {
+        request_cookie(headers, PREFERENCES_COOKIE)
+            .and_then(|value| key.verify(value))
+            .and_then(|json| serde_json::from_slice(&json).ok())
+            .unwrap_or_default()
+    }
+
+    /// Whether no preference is set.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+}
+
+/// Value of the cookie `name` sent with a request, if any.
+pub(crate) fn request_cookie<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers
+        .get_all(header::COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|cookies| cookies.split(';'))
+        .filter_map(|cookie| cookie.trim().split_once('='))
+        .find(|(cookie, _)| *cookie == name)
+        .map(|(_, value)| value)
+}
+
+/// Secret key signing the preferences cookie
+#[derive(Clone)]
+pub struct SigningKey([u8; 32]);
+
+impl std::fmt::Debug for SigningKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("SigningKey(..)")
+    }
+}
+
+impl SigningKey {
+    /// A new random key.
+    #[must_use]
+    pub fn random() -> Self {
+        let mut key = [0; 32];
+        rand::thread_rng().fill_bytes(&mut key);
+        Self(key)
+    }
+
+    /// The key stored in [`KEY_FILE`] next to the config at `config_path`, created there
+    /// if it does not exist yet.
+    ///
+    /// When the file cannot be read or written, a random key is used and preferences
+    /// are lost when the server restarts.
+    #[must_use]
+    pub fn load_or_create(config_path: &Path) -> Self {
+        let path = key_path(config_path);
+        match std::fs::read_to_string(&path) {
+            Ok(contents) => match from_hex(contents.trim()).and_then(|key| key.try_into().ok()) {
+                Some(key) => return Self(key),
+                None => warn!("Ignoring malformed cookie key in {}", path.display()),
+            },
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => warn!("Failed to read cookie key {}: {e}", path.display()),
+        }
+
+        let key = Self::random();
+        if let Err(e) = write_key_file(&path, &key) {
+            warn!(
+                "Failed to store cookie key in {}; browser preferences reset on restart: {e}",
+                path.display()
+            );
+        }
+        key
+    }
+
+    /// `payload` with its signature appended, as a cookie value.
+    fn sign(&self, payload: &[u8]) -> String {
+        format!("{}.{}", hex(payload), hex(&self.mac(payload)))
+    }
+
+    /// The payload of a value made by [`SigningKey::sign`] with this key.
+    fn verify(&self, value: &str) -> Option<Vec<u8>> {
+        let (payload, signature) = value.split_once('.')?;
+        let payload = from_hex(payload)?;
+        let signature = from_hex(signature)?;
+        let expected = self.mac(&payload);
+        let matches = signature.len() == expected.len()
+            && signature
+                .iter()
+                .zip(expected)
+                .fold(0, |diff, (a, b)| diff | (a ^ b))
+                == 0;
+        matches.then_some(payload)
+    }
+
+    /// HMAC-SHA256 of `message`.
+    fn mac(&self, message: &[u8]) -> [u8; 32] {
+        let mut block = [0; 64];
+        block[..32].copy_from_slice(&self.0);
+        let inner = Sha256::new()
+            .chain_update(block.map(|byte| byte ^ 0x36))
+            .chain_update(message)
+            .finalize();
+        Sha256::new()
+            .chain_update(block.map(|byte| byte ^ 0x5c))
+            .chain_update(inner)
+            .finalize()
+            .into()
+    }
+}
+
+/// Where the signing key of the config at `config_path` is stored.
+fn key_path(config_path: &Path) -> PathBuf {
+    let directory = if config_path.is_dir() {
+        config_path
+    } else {
+        config_path.parent().unwrap_or_else(|| Path::new("."))
+    };
+    directory.join(KEY_FILE)
+}
+
+/// Write `key` to `path`, readable only by its owner from the moment it is created.
+fn write_key_file(path: &Path, key: &SigningKey) -> std::io::Result<()> {
+    use std::io::Write;
+
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(path)?;
+    // The mode only applies to new files, so tighten a replaced one too
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        file.set_permissions(std::fs::Permissions::from_mode(0o600))?;
+    }
+    file.write_all(hex(&key.0).as_bytes())
+}
+
+/// `Set-Cookie` header storing `preferences`, or clearing the cookie when they are empty.
+fn set_cookie(preferences: &Preferences, key: &SigningKey) -> Result<HeaderValue> {
+    let value = if preferences.is_empty() {
+        format!("{PREFERENCES_COOKIE}=; Path=/; Max-Age=0; HttpOnly; SameSite=Lax")
+    } else {
+        let signed = key.sign(&serde_json::to_vec(preferences)?);
+        if signed.len() > MAX_COOKIE_VALUE_LEN {
+            return Err(IronShieldError::ConfigValidation {
+                field: "preferences".to_string(),
+                message: "are too long to store in a cookie".to_string(),
+            });
+        }
+        format!(
+            "{PREFERENCES_COOKIE}={signed}; Path=/; Max-Age={COOKIE_MAX_AGE_SECS}; HttpOnly; SameSite=Lax"
+        )
+    };
+    HeaderValue::from_str(&value).map_err(|e| IronShieldError::Generic(e.to_string()))
+}
+
+/// `preferences` with the fields of the JSON object `changes` replaced; `null` clears a
+/// field.
+///
+/// # Errors
+///
+/// Returns [`IronShieldError::ConfigValidation`] if `changes` is not an object, names an
+/// unknown preference, or has a value of the wrong kind.
+///
+/// # Examples
+///
+/// ```
+/// use iron_shield::config::Layout;
+/// use iron_shield::preferences::{apply_changes, Preferences};
+/// use serde_json::json;
+///
+/// let preferences = Preferences {
+///     tag: "media".to_string(),
+///     ..Preferences::default()
+/// };
+/// let changed = apply_changes(&preferences, json!({ "layout": "list", "tag": null })).unwrap();
+/// assert_eq!(changed.layout, Some(Layout::List));
+/// assert!(changed.tag.is_empty());
+///
+/// assert!(apply_changes(&changed, json!({ "layout": "tiles" })).is_err());
+/// ```
+pub fn apply_changes(preferences: &Preferences, changes: serde_json::Value) -> Result<Preferences> {
+    let invalid = |message: String| IronShieldError::ConfigValidation {
+        field: "preferences".to_string(),
+        message,
+    };
+    let serde_json::Value::Object(changes) = changes else {
+        return Err(invalid("must be a JSON object".to_string()));
+    };
+    let serde_json::Value::Object(mut merged) = serde_json::to_value(preferences)? else {
+        return Err(invalid("could not be read".to_string()));
+    };
+    for (name, value) in changes {
+        if value.is_null() {
+            merged.remove(&name);
+        } else {
+            merged.insert(name, value);
+        }
+    }
+    serde_json::from_value(serde_json::Value::Object(merged)).map_err(|e| invalid(e.to_string()))
+}
+
+/// This browser's preferences
+///
+/// Handles `GET /preferences`, responding with the [`Preferences`] in the request's
+/// cookie as JSON.
+pub async fn get_preferences(
+    State(state): State<Arc<UptimeState>>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    Json(Preferences::from_headers(&headers, &state.preferences_key))
+}
+
+/// Change this browser's preferences
+///
+/// Handles `PATCH /preferences` with a JSON object of the preferences to change (see
+/// [`apply_changes`]), storing the result in the cookie and responding with it.
+///
+/// # Errors
+///
+/// Responds with HTTP 400 if a preference is unknown or invalid, or the preferences are
+/// too long to store in a cookie.
+pub async fn patch_preferences(
+    State(state): State<Arc<UptimeState>>,
+    headers: HeaderMap,
+    Json(changes): Json<serde_json::Value>,
+) -> Response {
+    let current = Preferences::from_headers(&headers, &state.preferences_key);
+    let preferences = match apply_changes(&current, changes) {
+        Ok(preferences) => preferences,
+        Err(e) => return e.into_response(),
+    };
+    match set_cookie(&preferences, &state.preferences_key) {
+        Ok(cookie) => ([(header::SET_COOKIE, cookie)], Json(preferences)).into_response(),
+        Err(e) => {
+            error!("Failed to store preferences: {e}");
+            e.into_response()
+        }
+    }
+}
+
+/// Clear this browser's preferences
+///
+/// Handles `DELETE /preferences`, responding with HTTP 204 and a cookie that expires
+/// immediately.
+pub async fn delete_preferences(State(state): State<Arc<UptimeState>>) -> Response {
+    match set_cookie(&Preferences::default(), &state.preferences_key) {
+        Ok(cookie) => (StatusCode::NO_CONTENT, [(header::SET_COOKIE, cookie)]).into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signed_preferences_round_trip_and_reject_tampering() {
+        let key = SigningKey::random();
+        let preferences = Preferences {
+            theme: Some(Theme::Light),
+            layout: Some(Layout::Compact),
+            tag: "media".to_string(),
+            category: String::new(),
+        };
+        let cookie = set_cookie(&preferences, &key).unwrap();
+        let cookie = cookie.to_str().unwrap();
+        assert!(cookie.contains("HttpOnly"));
+        let value = cookie
+            .split(';')
+            .next()
+            .and_then(|pair| pair.split_once('='))
+            .map(|(_, value)| value.to_string())
+            .unwrap();
+
+        let headers = |value: &str| {
+            let mut headers = HeaderMap::new();
+            headers.insert(
+                header::COOKIE,
+                format!("other=1; {PREFERENCES_COOKIE}={value}")
+                    .parse()
+                    .unwrap(),
+            );
+            headers
+        };
+        assert_eq!(
+            Preferences::from_headers(&headers(&value), &key),
+            preferences
+        );
+
+        let (payload, signature) = value.split_once('.').unwrap();
+        let forged = serde_json::to_vec(&Preferences {
+            layout: Some(Layout::List),
+            ..preferences
+        })
+        .unwrap();
+        let forged = format!("{}.{signature}", hex(&forged));
+        assert_ne!(payload, forged);
+        assert!(Preferences::from_headers(&headers(&forged), &key).is_empty());
+        assert!(Preferences::from_headers(&headers(&value), &SigningKey::random()).is_empty());
+    }
+
+    #[test]
+    fn key_is_created_once_next_to_the_config() {
+        let directory = tempfile::tempdir().unwrap();
+        let config_path = directory.path().join("config.json5");
+
+        let key = SigningKey::load_or_create(&config_path);
+        assert!(directory.path().join(KEY_FILE).exists());
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let metadata = std::fs::metadata(directory.path().join(KEY_FILE)).unwrap();
+            assert_eq!(metadata.permissions().mode() & 0o777, 0o600);
+        }
+        let reloaded = SigningKey::load_or_create(&config_path);
+        assert_eq!(key.0, reloaded.0);
+    }
+}
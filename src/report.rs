@@ -0,0 +1,425 @@
+//! Status report
+//!
+//! `GET /report` renders a print-friendly summary of the dashboard as plain HTML: the
+//! current status of each site, its uptime and average response time over the recorded
+//! checks, and the most recent incidents. The page has its styles inline and loads no
+//! scripts or bundled assets, so it can be saved, printed, or emailed as a weekly
+//! snapshot and still reads the same later.
+//!
+//! `?dashboard=name` limits the report to the sites of a named dashboard:
+//!
+//! ```text
+//! /report?dashboard=homelab
+//! ```
+
+use crate::config::{sort_for_display, Config, Site};
+use crate::kiosk::current_status;
+use crate::uptime::{
+    calculate_uptime_percentage, unix_now, HistoryEntry, UptimeState, UptimeStatus,
+};
+use crate::utils::format_duration;
+use askama_axum::Template;
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::{Html, IntoResponse},
+};
+use chrono::DateTime;
+use chrono_tz::Tz;
+use serde::Deserialize;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+
+/// Most incidents listed in a report, newest first
+pub const MAX_REPORT_INCIDENTS: usize = 20;
+
+/// Query string of a report request
+#[derive(Debug, Default, Deserialize)]
+pub struct ReportQuery {
+    /// Name of the dashboard whose sites are reported, all sites if unset
+    #[serde(default)]
+    pub dashboard: Option<String>,
+}
+
+/// One site's line in the report
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReportRow {
+    /// Name of the site
+    pub name: String,
+    /// URL of the site
+    pub url: String,
+    /// Category of the site, empty if it has none
+    pub category: String,
+    /// Status from the latest completed check; see [`current_status`]
+    pub status: &'static str,
+    /// Percentage of the recorded completed checks that succeeded, if any completed
+    pub uptime_percentage: Option<f64>,
+    /// Mean response time of the recorded successful checks, in milliseconds
+    pub average_response_ms: Option<u64>,
+    /// Number of recorded completed checks
+    pub checks: usize,
+}
+
+/// A run of failed checks of one site
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Incident {
+    /// Name of the site that was down
+    pub site: String,
+    /// Unix timestamp of the first failed check
+    pub started_at: u64,
+    /// Unix timestamp of the check that succeeded again, `None` while the site is down
+    pub ended_at: Option<u64>,
+    /// Why the first failed check failed, if recorded
+    pub reason: Option<String>,
+}
+
+/// Template of the status report
+#[derive(Template)]
+#[template(path = "report.html")]
+pub struct ReportTemplate {
+    /// Application configuration, for the language and time zone
+    config: Config,
+    /// Title of the report, the dashboard title or the global site name
+    page_title: String,
+    /// When the report was rendered, formatted in the configured time zone
+    generated_at: String,
+    /// One row per reported site, in display order
+    rows: Vec<ReportRow>,
+    /// Most recent incidents of the reported sites, newest first
+    incidents: Vec<Incident>,
+    /// Unix timestamp the report was rendered at, for the length of ongoing incidents
+    now: u64,
+}
+
+impl ReportTemplate {
+    /// Text for `key` in the configured language; see [`crate::i18n`].
+    fn t<'a>(&self, key: &'a str) -> &'a str {
+        self.config.language.translate(key)
+    }
+
+    /// Number of reported sites whose latest check has `status`.
+    fn count(&self, status: &str) -> usize {
+        self.rows.iter().filter(|row| row.status == status).count()
+    }
+
+    /// Text of `status`, as set by [`current_status`], in the configured language.
+    fn status_label(&self, status: &str) -> &'static str {
+        self.t(match status {
+            "up" => "status.up",
+            "down" => "status.down",
+            "disabled" => "status.disabled",
+            _ => "status.unknown",
+        })
+    }
+
+    /// Uptime of `row` as "99.5%", or a dash before its first completed check.
+    fn uptime(&self, row: &ReportRow) -> String {
+        row.uptime_percentage
+            .map_or_else(|| "–".to_string(), |uptime| format!("{uptime:.1}%"))
+    }
+
+    /// Average response time of `row` as "120 ms", or a dash without successful checks.
+    fn response(&self, row: &ReportRow) -> String {
+        row.average_response_ms
+            .map_or_else(|| "–".to_string(), |ms| format!("{ms} ms"))
+    }
+
+    /// When `incident` started, in the configured time zone.
+    fn started(&self, incident: &Incident) -> String {
+        format_timestamp(incident.started_at, self.config.tz())
+    }
+
+    /// When `incident` ended, in the configured time zone, or "Ongoing".
+    fn ended(&self, incident: &Incident) -> String {
+        incident.ended_at.map_or_else(
+            || self.t("report.ongoing").to_string(),
+            |ended_at| format_timestamp(ended_at, self.config.tz()),
+        )
+    }
+
+    /// How long `incident` lasted, or has lasted so far.
+    fn duration(&self, incident: &Incident) -> String {
+        let end = incident.ended_at.unwrap_or(self.now);
+        format_duration(end.saturating_sub(incident.started_at))
+    }
+
+    /// Why `incident` started, or "Unreachable" when no reason was recorded.
+    fn reason<'a>(&'a self, incident: &'a Incident) -> &'a str {
+        incident
+            .reason
+            .as_deref()
+            .unwrap_or_else(|| self.t("detail.unreachable"))
+    }
+}
+
+/// `secs` since the Unix epoch as "2024-05-01 14:05 CEST" in `timezone`.
+fn format_timestamp(secs: u64, timezone: Tz) -> String {
+    i64::try_from(secs)
+        .ok()
+        .and_then(|secs| DateTime::from_timestamp(secs, 0))
+        .map(|time| {
+            time.with_timezone(&timezone)
+                .format("%Y-%m-%d %H:%M %Z")
+                .to_string()
+        })
+        .unwrap_or_default()
+}
+
+/// The report line of `site` from its recorded checks.
+fn report_row(site: &Site, entries: Option<&VecDeque<HistoryEntry>>) -> ReportRow {
+    let completed = entries.map_or(0, |entries| {
+        entries
+            .iter()
+            .filter(|entry| matches!(entry.status, UptimeStatus::Up | UptimeStatus::Down))
+            .count()
+    });
+    let response_times: Vec<u64> = entries
+        .into_iter()
+        .flatten()
+        .filter(|entry| entry.status == UptimeStatus::Up)
+        .filter_map(|entry| entry.response_time_ms)
+        .collect();
+    let average_response_ms = u64::try_from(response_times.len())
+        .ok()
+        .filter(|count| *count > 0)
+        .map(|count| response_times.iter().sum::<u64>() / count);
+    ReportRow {
+        name: site.name.clone(),
+        url: site.url.clone(),
+        category: site.category.trim().to_string(),
+        status: current_status(site, entries),
+        uptime_percentage: entries
+            .filter(|_| completed > 0)
+            .map(calculate_uptime_percentage),
+        average_response_ms,
+        checks: completed,
+    }
+}
+
+/// The runs of failed checks in `entries`, the recorded checks of the site `site`,
+/// oldest first.
+///
+/// An incident starts at the first failed check and ends at the next successful one;
+/// checks in progress are skipped, and a pause ends the incident without an end time
+/// being known, so it is left out. The last incident has no end while the site is
+/// still down.
+///
+/// # Examples
+///
+/// ```
+/// use iron_shield::report::incidents;
+/// use iron_shield::uptime::{HistoryEntry, UptimeStatus};
+/// use std::collections::VecDeque;
+///
+/// let entry = |status, checked_at| HistoryEntry {
+///     status,
+///     response_time_ms: None,
+///     failure_reason: None,
+///     checked_at,
+/// };
+/// let history = VecDeque::from([
+///     entry(UptimeStatus::Down, 100),
+///     entry(UptimeStatus::Up, 160),
+///     entry(UptimeStatus::Down, 220),
+///     entry(UptimeStatus::Loading, 280),
+/// ]);
+///
+/// let incidents = incidents("NAS", &history);
+/// assert_eq!(incidents.len(), 2);
+/// assert_eq!((incidents[0].started_at, incidents[0].ended_at), (100, Some(160)));
+/// assert_eq!((incidents[1].started_at, incidents[1].ended_at), (220, None));
+/// ```
+#[must_use]
+pub fn incidents(site: &str, entries: &VecDeque<HistoryEntry>) -> Vec<Incident> {
+    let mut incidents = Vec::new();
+    let mut open: Option<Incident> = None;
+    for entry in entries {
+        match entry.status {
+            UptimeStatus::Down => {
+                if open.is_none() && entry.checked_at > 0 {
+                    open = Some(Incident {
+                        site: site.to_string(),
+                        started_at: entry.checked_at,
+                        ended_at: None,
+                        reason: entry.failure_reason.clone(),
+                    });
+                }
+            }
+            UptimeStatus::Up => {
+                if let Some(mut incident) = open.take() {
+                    incident.ended_at = Some(entry.checked_at);
+                    incidents.push(incident);
+                }
+            }
+            UptimeStatus::Disabled => open = None,
+            UptimeStatus::Loading => {}
+        }
+    }
+    incidents.extend(open);
+    incidents
+}
+
+/// Render the status report
+///
+/// Handles `GET /report`, reporting every site or, with `?dashboard=name`, the sites of
+/// that dashboard, titled with its title. Sites are listed in display order, and the
+/// [`MAX_REPORT_INCIDENTS`] most recent incidents among them are listed newest first.
+///
+/// # Errors
+///
+/// Returns HTTP 404 when the requested dashboard is not configured, and HTTP 500 if the
+/// template cannot be rendered.
+pub async fn report_page(
+    State(state): State<Arc<UptimeState>>,
+    Query(query): Query<ReportQuery>,
+) -> impl IntoResponse {
+    let config = Config::clone(&state.config.load());
+
+    let (page_title, mut sites) = match query.dashboard.filter(|name| !name.is_empty()) {
+        Some(name) => {
+            let Some(dashboard) = config.dashboard(&name) else {
+                return (StatusCode::NOT_FOUND, "Dashboard not found").into_response();
+            };
+            let title = dashboard
+                .title
+                .clone()
+                .unwrap_or_else(|| config.site_name.clone());
+            (title, config.dashboard_sites(dashboard))
+        }
+        None => (config.site_name.clone(), config.sites.clone()),
+    };
+    sort_for_display(&mut sites);
+    config.sort_by_category(&mut sites);
+
+    let history: HashMap<String, VecDeque<HistoryEntry>> = {
+        let history = state.history.read();
+        sites
+            .iter()
+            .filter_map(|site| Some((site.name.clone(), history.get(&site.name)?.clone())))
+            .collect()
+    };
+    let rows = sites
+        .iter()
+        .map(|site| report_row(site, history.get(&site.name)))
+        .collect();
+    let mut incidents: Vec<Incident> = history
+        .iter()
+        .flat_map(|(site, entries)| incidents(site, entries))
+        .collect();
+    incidents.sort_by(|a, b| {
+        b.started_at
+            .cmp(&a.started_at)
+            .then_with(|| a.site.cmp(&b.site))
+    });
+    incidents.truncate(MAX_REPORT_INCIDENTS);
+
+    let template = ReportTemplate {
+        page_title,
+        generated_at: format_timestamp(unix_now(), config.tz()),
+        rows,
+        incidents,
+        now: unix_now(),
+        config,
+    };
+    match template
+        .render()
+        .map_err(crate::error::IronShieldError::TemplateRender)
+    {
+        Ok(html) => Html(html).into_response(),
+        Err(e) => {
+            tracing::error!("{e}");
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Template rendering error",
+            )
+                .into_response()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn site(name: &str, disabled: bool) -> Site {
+        Site {
+            name: name.to_string(),
+            url: format!("https://{}.lan", name.to_lowercase()),
+            category: String::new(),
+            tags: Vec::new(),
+            monitor_interval_secs: None,
+            monitor_timeout_secs: None,
+            monitor_retries: None,
+            monitor_user_agent: None,
+            disabled,
+            icon: None,
+            order: None,
+            assertions: None,
+            uptime_percentage: 0.0,
+        }
+    }
+
+    fn entry(status: UptimeStatus, response_time_ms: Option<u64>, checked_at: u64) -> HistoryEntry {
+        HistoryEntry {
+            status,
+            response_time_ms,
+            failure_reason: (status == UptimeStatus::Down).then(|| "HTTP 502".to_string()),
+            checked_at,
+        }
+    }
+
+    #[test]
+    fn rows_summarize_recorded_checks() {
+        let history = VecDeque::from([
+            entry(UptimeStatus::Up, Some(100), 60),
+            entry(UptimeStatus::Down, None, 120),
+            entry(UptimeStatus::Up, Some(300), 180),
+            entry(UptimeStatus::Up, Some(200), 240),
+            entry(UptimeStatus::Loading, None, 300),
+        ]);
+        let row = report_row(&site("Plex", false), Some(&history));
+        assert_eq!(row.status, "up");
+        assert_eq!(row.uptime_percentage, Some(75.0));
+        assert_eq!(row.average_response_ms, Some(200));
+        assert_eq!(row.checks, 4);
+
+        let row = report_row(&site("Backup", true), None);
+        assert_eq!(row.status, "disabled");
+        assert_eq!(row.uptime_percentage, None);
+        assert_eq!(row.average_response_ms, None);
+    }
+
+    #[test]
+    fn renders_without_scripts_or_bundled_assets() {
+        let config = Config {
+            site_name: "Homelab".to_string(),
+            timezone: "UTC".to_string(),
+            ..Config::default()
+        };
+        let history = VecDeque::from([
+            entry(UptimeStatus::Up, Some(90), 1_700_000_000),
+            entry(UptimeStatus::Down, None, 1_700_000_060),
+        ]);
+        let template = ReportTemplate {
+            page_title: config.site_name.clone(),
+            generated_at: format_timestamp(1_700_003_660, config.tz()),
+            rows: vec![
+                report_row(&site("Plex", false), Some(&history)),
+                report_row(&site("Router", false), None),
+            ],
+            incidents: incidents("Plex", &history),
+            now: 1_700_003_660,
+            config,
+        };
+        let rendered = template.render().unwrap();
+
+        assert!(!rendered.contains("<script"));
+        assert!(!rendered.contains("/static/"));
+        assert!(rendered.contains("2023-11-14 22:14 UTC"));
+        assert!(rendered.contains("<td class=\"status down\">Down</td>"));
+        assert!(rendered.contains("<td class=\"status unknown\">Unknown</td>"));
+        assert!(rendered.contains("50.0%"));
+        assert!(rendered.contains("HTTP 502"));
+        assert!(rendered.contains("1h"));
+    }
+}
@@ -0,0 +1,259 @@
+//! Site search
+//!
+//! `GET /api/search?q=...` finds sites by name, URL, tag, and category, so the
+//! dashboard's search box can act as a launcher: type a few letters and press Enter to
+//! open the best match. Matching is fuzzy and case-insensitive:
+//!
+//! - each word of the query must match a name, URL, tag, or category
+//! - a word matches a field exactly, as a prefix, as a substring, or as a subsequence of
+//!   its letters (`grf` matches "Grafana"), scoring in that order
+//! - names count most, then tags and categories, then URLs
+//!
+//! Results are sorted best first, ties by name, and capped at `limit` (default
+//! [`DEFAULT_LIMIT`], at most [`MAX_LIMIT`]).
+
+use crate::config::Site;
+use crate::uptime::UptimeState;
+use axum::{
+    extract::{Query, State},
+    response::{IntoResponse, Json},
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+/// Number of results returned when the request does not set `limit`
+pub const DEFAULT_LIMIT: usize = 10;
+/// Largest `limit` a request may ask for
+pub const MAX_LIMIT: usize = 100;
+
+/// Query string of a search request
+#[derive(Debug, Default, Deserialize)]
+pub struct SearchQuery {
+    /// Text to search for
+    #[serde(default)]
+    pub q: String,
+    /// Maximum number of results
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
+
+/// A site matching a search
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct SearchResult {
+    /// The site's name
+    pub name: String,
+    /// The site's URL
+    pub url: String,
+    /// The site's category, empty if it has none
+    pub category: String,
+    /// The site's tags
+    pub tags: Vec<String>,
+    /// Where the dashboard loads the site's icon image from, if it has one
+    pub icon: Option<String>,
+    /// How well the site matches; higher is better
+    pub score: u32,
+}
+
+/// Score of `term` against `text`, if it matches; both must be lowercase.
+fn match_score(term: &str, text: &str) -> Option<u32> {
+    if text == term {
+        return Some(100);
+    }
+    if text.starts_with(term) {
+        return Some(80);
+    }
+    if let Some(position) = text.find(term) {
+        let word_start = !text[..position]
+            .chars()
+            .next_back()
+            .is_some_and(char::is_alphanumeric);
+        return Some(if word_start { 70 } else { 50 });
+    }
+
+    // Subsequence match, losing a point for each skipped character
+    let mut skipped = 0;
+    let mut chars = text.chars();
+    for wanted in term.chars() {
+        loop {
+            let found = chars.next()?;
+            if found == wanted {
+                break;
+            }
+            skipped += 1;
+        }
+    }
+    Some(30_u32.saturating_sub(skipped).max(1))
+}
+
+/// Score of `site` for the lowercase, whitespace-separated `terms`, if all match.
+fn site_score(site: &Site, terms: &[String]) -> Option<u32> {
+    let name = site.name.to_lowercase();
+    let url = site.url.to_lowercase();
+    let url = url
+        .strip_prefix("https://")
+        .or_else(|| url.strip_prefix("http://"))
+        .unwrap_or(&url);
+    let labels: Vec<String> = site
+        .tags
+        .iter()
+        .chain(std::iter::once(&site.category))
+        .filter(|label| !label.is_empty())
+        .map(|label| label.to_lowercase())
+        .collect();
+
+    terms.iter().try_fold(0, |total, term| {
+        let best = [
+            match_score(term, &name).map(|score| score * 3),
+            labels
+                .iter()
+                .filter_map(|label| match_score(term, label))
+                .max()
+                .map(|score| score * 2),
+            match_score(term, url),
+        ]
+        .into_iter()
+        .flatten()
+        .max()?;
+        Some(total + best)
+    })
+}
+
+/// The sites matching `query`, best first, at most `limit` of them
+///
+/// # Examples
+///
+/// ```
+/// use iron_shield::config::Site;
+/// use iron_shield::search::search;
+///
+/// let site = |name: &str, tags: &[&str]| Site {
+///     name: name.to_string(),
+///     url: format!("https://{}.home.lan", name.to_lowercase()),
+///     category: String::new(),
+///     tags: tags.iter().map(|tag| tag.to_string()).collect(),
+///     monitor_interval_secs: None,
+///     monitor_timeout_secs: None,
+///     monitor_retries: None,
+///     monitor_user_agent: None,
+///     disabled: false,
+///     icon: None,
+///     order: None,
+///     assertions: None,
+///     uptime_percentage: 0.0,
+/// };
+/// let sites = [site("Grafana", &["monitoring"]), site("Plex", &["media"])];
+///
+/// let results = search(&sites, "grf", 10);
+/// assert_eq!(results[0].name, "Grafana");
+/// assert_eq!(search(&sites, "media", 10)[0].name, "Plex");
+/// assert!(search(&sites, "zzz", 10).is_empty());
+/// ```
+#[must_use]
+pub fn search(sites: &[Site], query: &str, limit: usize) -> Vec<SearchResult> {
+    let terms: Vec<String> = query.split_whitespace().map(str::to_lowercase).collect();
+    if terms.is_empty() {
+        return Vec::new();
+    }
+
+    let mut results: Vec<SearchResult> = sites
+        .iter()
+        .filter_map(|site| {
+            site_score(site, &terms).map(|score| SearchResult {
+                name: site.name.clone(),
+                url: site.url.clone(),
+                category: site.category.clone(),
+                tags: site.tags.clone(),
+                icon: site.icon_src(),
+                score,
+            })
+        })
+        .collect();
+    results.sort_by(|a, b| {
+        b.score
+            .cmp(&a.score)
+            .then_with(|| a.name.to_lowercase().cmp(&b.name.to_lowercase()))
+    });
+    results.truncate(limit);
+    results
+}
+
+/// Search the configured sites
+///
+/// Handles `GET /api/search?q=...&limit=...`, responding with a JSON array of
+/// [`SearchResult`]. An empty query matches nothing.
+pub async fn search_sites(
+    State(state): State<Arc<UptimeState>>,
+    Query(query): Query<SearchQuery>,
+) -> impl IntoResponse {
+    let limit = query.limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT);
+    Json(search(&state.config.load().sites, &query.q, limit))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn site(name: &str, url: &str, category: &str, tags: &[&str]) -> Site {
+        Site {
+            name: name.to_string(),
+            url: url.to_string(),
+            category: category.to_string(),
+            tags: tags.iter().map(|tag| (*tag).to_string()).collect(),
+            monitor_interval_secs: None,
+            monitor_timeout_secs: None,
+            monitor_retries: None,
+            monitor_user_agent: None,
+            disabled: false,
+            icon: None,
+            order: None,
+            assertions: None,
+            uptime_percentage: 0.0,
+        }
+    }
+
+    fn names(results: &[SearchResult]) -> Vec<&str> {
+        results.iter().map(|result| result.name.as_str()).collect()
+    }
+
+    #[test]
+    fn ranks_name_matches_above_tags_and_urls() {
+        let sites = [
+            site("Router", "http://plex-router.lan", "Network", &[]),
+            site(
+                "Jellyfin",
+                "http://jellyfin.lan",
+                "Media",
+                &["plex-alternative"],
+            ),
+            site("Plex", "http://media.lan", "Media", &[]),
+            site("Plexamp", "http://amp.lan", "Media", &[]),
+        ];
+
+        assert_eq!(
+            names(&search(&sites, "plex", 10)),
+            ["Plex", "Plexamp", "Jellyfin", "Router"]
+        );
+        assert_eq!(names(&search(&sites, "PLEX", 2)), ["Plex", "Plexamp"]);
+    }
+
+    #[test]
+    fn every_word_must_match_some_field() {
+        let sites = [
+            site(
+                "Grafana",
+                "https://grafana.lan",
+                "Monitoring",
+                &["dashboards"],
+            ),
+            site("Prometheus", "https://prom.lan", "Monitoring", &["metrics"]),
+        ];
+
+        assert_eq!(names(&search(&sites, "mon grf", 10)), ["Grafana"]);
+        assert_eq!(
+            names(&search(&sites, "monitoring", 10)),
+            ["Grafana", "Prometheus"]
+        );
+        assert!(search(&sites, "grafana metrics", 10).is_empty());
+        assert!(search(&sites, "   ", 10).is_empty());
+    }
+}
@@ -0,0 +1,158 @@
+//! Secret references in configuration values
+//!
+//! Credentials should not have to live in the config file itself. A config value can
+//! instead reference where the secret is kept, and the reference is resolved when the
+//! configuration is loaded:
+//!
+//! - `${env:NAME}` is replaced by the value of the environment variable `NAME`
+//! - `${file:/path}` is replaced by the contents of the file at `/path`
+//! - a value starting with `file:` (such as `file:/run/secrets/token`) is replaced
+//!   entirely by the contents of that file
+//!
+//! `${...}` references can be embedded in a larger value, for example
+//! `https://collector.example.com/ingest?token=${env:COLLECTOR_TOKEN}`. Trailing newlines
+//! are trimmed from file contents so Docker and Kubernetes secret files work as-is.
+//!
+//! The config module keeps the original reference next to the resolved value so the
+//! reference, not the secret, is written back when the configuration is saved.
+
+use std::fs;
+
+/// Prefix marking a value that is read entirely from a file.
+const FILE_PREFIX: &str = "file:";
+
+/// Returns `true` when a value contains a secret reference.
+///
+/// # Examples
+///
+/// ```
+/// use iron_shield::secrets::is_secret_reference;
+///
+/// assert!(is_secret_reference("${env:API_TOKEN}"));
+/// assert!(is_secret_reference("file:/run/secrets/token"));
+/// assert!(!is_secret_reference("https://collector.example.com"));
+/// ```
+#[must_use]
+pub fn is_secret_reference(value: &str) -> bool {
+    value.starts_with(FILE_PREFIX) || value.contains("${env:") || value.contains("${file:")
+}
+
+/// Resolve every secret reference in a value.
+///
+/// Values without references are returned unchanged.
+///
+/// # Errors
+///
+/// Returns a message naming the missing environment variable or unreadable file, or
+/// describing an unterminated or unsupported `${...}` reference.
+///
+/// # Examples
+///
+/// ```
+/// use iron_shield::secrets::resolve;
+///
+/// assert_eq!(resolve("plain value").unwrap(), "plain value");
+/// assert!(resolve("${env:IRON_SHIELD_DOC_MISSING_VAR}").is_err());
+/// ```
+pub fn resolve(value: &str) -> Result<String, String> {
+    resolve_with(value, |name| std::env::var(name).ok())
+}
+
+/// Resolve secret references using `lookup_env` for environment variables.
+fn resolve_with(
+    value: &str,
+    lookup_env: impl Fn(&str) -> Option<String>,
+) -> Result<String, String> {
+    if let Some(path) = value.strip_prefix(FILE_PREFIX) {
+        return read_secret_file(path);
+    }
+
+    let mut resolved = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(start) = rest.find("${") {
+        resolved.push_str(&rest[..start]);
+        let reference = &rest[start + 2..];
+        let Some(end) = reference.find('}') else {
+            return Err(format!("unterminated secret reference in \"{value}\""));
+        };
+        let (kind, target) = reference[..end].split_once(':').ok_or_else(|| {
+            format!(
+                "secret reference \"${{{}}}\" needs a source such as env: or file:",
+                &reference[..end]
+            )
+        })?;
+        match kind {
+            "env" => {
+                let secret = lookup_env(target)
+                    .ok_or_else(|| format!("environment variable {target} is not set"))?;
+                resolved.push_str(&secret);
+            }
+            "file" => resolved.push_str(&read_secret_file(target)?),
+            other => return Err(format!("unsupported secret source \"{other}\"")),
+        }
+        rest = &reference[end + 1..];
+    }
+    resolved.push_str(rest);
+    Ok(resolved)
+}
+
+fn read_secret_file(path: &str) -> Result<String, String> {
+    fs::read_to_string(path)
+        .map(|contents| contents.trim_end_matches(['\r', '\n']).to_string())
+        .map_err(|e| format!("failed to read secret file {path}: {e}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lookup(name: &str) -> Option<String> {
+        (name == "API_TOKEN").then(|| "s3cret".to_string())
+    }
+
+    #[test]
+    fn resolves_embedded_environment_references() {
+        assert_eq!(
+            resolve_with("https://collector/ingest?token=${env:API_TOKEN}", lookup),
+            Ok("https://collector/ingest?token=s3cret".to_string())
+        );
+        assert_eq!(
+            resolve_with("no references", lookup),
+            Ok("no references".to_string())
+        );
+    }
+
+    #[test]
+    fn resolves_file_references_and_trims_trailing_newlines() {
+        let dir = tempfile::tempdir().expect("Failed to create temporary directory");
+        let path = dir.path().join("token");
+        fs::write(&path, "from-file\n").expect("Failed to write secret file");
+        let path = path.to_string_lossy();
+
+        assert_eq!(
+            resolve_with(&format!("file:{path}"), lookup),
+            Ok("from-file".to_string())
+        );
+        assert_eq!(
+            resolve_with(&format!("Bearer ${{file:{path}}}"), lookup),
+            Ok("Bearer from-file".to_string())
+        );
+    }
+
+    #[test]
+    fn reports_missing_and_malformed_references() {
+        assert_eq!(
+            resolve_with("${env:MISSING}", lookup),
+            Err("environment variable MISSING is not set".to_string())
+        );
+        assert!(resolve_with("${env:API_TOKEN", lookup)
+            .unwrap_err()
+            .contains("unterminated"));
+        assert!(resolve_with("${vault:token}", lookup)
+            .unwrap_err()
+            .contains("unsupported secret source"));
+        assert!(resolve_with("file:/nonexistent/secret", lookup)
+            .unwrap_err()
+            .contains("failed to read secret file"));
+    }
+}
@@ -0,0 +1,234 @@
+//! Health of the iron-shield process itself
+//!
+//! When sites turn slow or checks stop arriving, the cause can be the monitor rather than
+//! the sites: a host short on memory, a pile of open dashboards, or more sites than the
+//! `monitoring.concurrency` limit gets through in time.
+//! `GET /api/self` reports what is needed to tell:
+//!
+//! - `uptime_secs` - how long the process has been running
+//! - `memory_bytes` - resident memory of the process, where the OS reports it (Linux)
+//! - `sse_connections` - open `/uptime` streams
+//! - `queued_checks` - checks that are due but waiting for a free concurrency slot
+//! - `running_checks` - checks in flight
+//! - `cycles` and `last_cycle_ms` - number of completed uptime cycles and how long the
+//!   latest one took, from starting its due checks until the last of them finished
+//! - `telemetry_queue` - exports waiting to be sent to the OpenTelemetry collector
+//!
+//! With telemetry enabled, the same numbers are exported with every uptime snapshot as
+//! the `iron_shield.process.*` metrics (see [`crate::telemetry`]).
+
+use crate::uptime::UptimeState;
+use axum::{extract::State, response::Json};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Counters describing the work the process is doing
+#[derive(Debug)]
+pub struct SelfMonitor {
+    started: Instant,
+    sse_connections: Arc<AtomicUsize>,
+    queued_checks: Arc<AtomicUsize>,
+    running_checks: Arc<AtomicUsize>,
+    cycles: AtomicU64,
+    last_cycle_ms: AtomicU64,
+    ticks: AtomicU64,
+}
+
+impl Default for SelfMonitor {
+    fn default() -> Self {
+        Self {
+            started: Instant::now(),
+            sse_connections: Arc::default(),
+            queued_checks: Arc::default(),
+            running_checks: Arc::default(),
+            cycles: AtomicU64::new(0),
+            last_cycle_ms: AtomicU64::new(0),
+            ticks: AtomicU64::new(0),
+        }
+    }
+}
+
+/// Counts one item, such as an open connection, for as long as it is kept
+#[derive(Debug)]
+#[must_use = "the item is only counted until this is dropped"]
+pub struct Tracked(Arc<AtomicUsize>);
+
+impl Tracked {
+    fn new(counter: &Arc<AtomicUsize>) -> Self {
+        counter.fetch_add(1, Ordering::Relaxed);
+        Self(Arc::clone(counter))
+    }
+}
+
+impl Drop for Tracked {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+impl SelfMonitor {
+    /// Count an `/uptime` stream as open until the returned guard is dropped.
+    pub fn sse_connected(&self) -> Tracked {
+        Tracked::new(&self.sse_connections)
+    }
+
+    /// Count a due check as queued until the returned guard is dropped.
+    pub fn check_queued(&self) -> Tracked {
+        Tracked::new(&self.queued_checks)
+    }
+
+    /// Count a check as running until the returned guard is dropped.
+    pub fn check_started(&self) -> Tracked {
+        Tracked::new(&self.running_checks)
+    }
+
+    /// Record that an uptime cycle finished all its checks in `duration`.
+    pub fn record_cycle(&self, duration: Duration) {
+        let ms = u64::try_from(duration.as_millis()).unwrap_or(u64::MAX);
+        self.last_cycle_ms.store(ms, Ordering::Relaxed);
+        self.cycles.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Record that the uptime monitor finished a tick, whether or not a check was due.
+    pub fn record_tick(&self) {
+        self.ticks.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Ticks the uptime monitor has finished since the process started
+    #[must_use]
+    pub fn ticks(&self) -> u64 {
+        self.ticks.load(Ordering::Relaxed)
+    }
+
+    /// Whether a check is queued or running, which the monitor's tick waits for
+    #[must_use]
+    pub fn checking(&self) -> bool {
+        self.queued_checks.load(Ordering::Relaxed) > 0
+            || self.running_checks.load(Ordering::Relaxed) > 0
+    }
+
+    /// The current counters; `telemetry_queue` is left for the caller to fill in.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use iron_shield::self_monitor::SelfMonitor;
+    /// use std::time::Duration;
+    ///
+    /// let monitor = SelfMonitor::default();
+    /// let connection = monitor.sse_connected();
+    /// monitor.record_cycle(Duration::from_millis(1_250));
+    ///
+    /// let status = monitor.status();
+    /// assert_eq!(status.sse_connections, 1);
+    /// assert_eq!((status.cycles, status.last_cycle_ms), (1, Some(1_250)));
+    ///
+    /// drop(connection);
+    /// assert_eq!(monitor.status().sse_connections, 0);
+    /// ```
+    #[must_use]
+    pub fn status(&self) -> SelfStatus {
+        let cycles = self.cycles.load(Ordering::Relaxed);
+        SelfStatus {
+            uptime_secs: self.started.elapsed().as_secs(),
+            memory_bytes: memory_bytes(),
+            sse_connections: self.sse_connections.load(Ordering::Relaxed),
+            queued_checks: self.queued_checks.load(Ordering::Relaxed),
+            running_checks: self.running_checks.load(Ordering::Relaxed),
+            cycles,
+            last_cycle_ms: (cycles > 0).then(|| self.last_cycle_ms.load(Ordering::Relaxed)),
+            telemetry_queue: 0,
+        }
+    }
+}
+
+/// Health of the process, as returned by `GET /api/self`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SelfStatus {
+    /// Seconds since the process started
+    pub uptime_secs: u64,
+    /// Resident memory of the process, if the OS reports it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memory_bytes: Option<u64>,
+    /// Open `/uptime` streams
+    pub sse_connections: usize,
+    /// Checks waiting for a free concurrency slot
+    pub queued_checks: usize,
+    /// Checks in flight
+    pub running_checks: usize,
+    /// Uptime cycles completed since the process started
+    pub cycles: u64,
+    /// How long the latest completed uptime cycle took, in milliseconds
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_cycle_ms: Option<u64>,
+    /// Telemetry exports waiting to be sent
+    pub telemetry_queue: usize,
+}
+
+/// Return the health of the iron-shield process.
+pub async fn get_self_status(State(state): State<Arc<UptimeState>>) -> Json<SelfStatus> {
+    Json(self_status(&state))
+}
+
+/// The health of the process running `state`
+#[must_use]
+pub fn self_status(state: &UptimeState) -> SelfStatus {
+    SelfStatus {
+        telemetry_queue: state.telemetry.len(),
+        ..state.self_monitor.status()
+    }
+}
+
+/// Resident memory of this process, read from `/proc/self/status`
+#[cfg(target_os = "linux")]
+fn memory_bytes() -> Option<u64> {
+    resident_bytes(&std::fs::read_to_string("/proc/self/status").ok()?)
+}
+
+/// Resident memory of this process, which is only read on Linux
+#[cfg(not(target_os = "linux"))]
+fn memory_bytes() -> Option<u64> {
+    None
+}
+
+/// The `VmRSS` line of a `/proc/<pid>/status` file, in bytes
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn resident_bytes(status: &str) -> Option<u64> {
+    let line = status
+        .lines()
+        .find_map(|line| line.strip_prefix("VmRSS:"))?;
+    let kilobytes = line.trim().strip_suffix("kB")?.trim().parse::<u64>().ok()?;
+    Some(kilobytes * 1024)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resident_memory_is_read_from_proc_status() {
+        let status = "Name:\tiron_shield\nVmPeak:\t  90000 kB\nVmRSS:\t   12345 kB\nThreads:\t8\n";
+        assert_eq!(resident_bytes(status), Some(12_345 * 1024));
+        assert_eq!(resident_bytes("Name:\tiron_shield\n"), None);
+    }
+
+    #[test]
+    fn checks_are_counted_while_queued_and_running() {
+        let monitor = SelfMonitor::default();
+        let queued = monitor.check_queued();
+        let other = monitor.check_queued();
+        assert_eq!(monitor.status().queued_checks, 2);
+
+        drop(queued);
+        let running = monitor.check_started();
+        let status = monitor.status();
+        assert_eq!((status.queued_checks, status.running_checks), (1, 1));
+
+        drop((other, running));
+        let status = monitor.status();
+        assert_eq!((status.queued_checks, status.running_checks), (0, 0));
+        assert_eq!(status.last_cycle_ms, None);
+    }
+}
@@ -2,27 +2,52 @@
 //!
 //! This module contains the main server functionality for the Iron Shield application.
 //! It handles setting up and running the web server, configuring routes, serving static files,
-//! and managing graceful shutdowns.
+//! and managing graceful shutdowns. Shutdown stops accepting connections, closes open
+//! SSE streams, and waits up to the configured `shutdown_timeout_secs` for in-flight
+//! requests and uptime checks before returning.
 //!
 //! The server uses Axum as the web framework and provides endpoints for:
-//! - Main dashboard page
-//! - Configuration API
+//! - Main dashboard page and named dashboards
+//! - Configuration API, including listing and restoring config backups
+//! - API key management, with keys required for API calls once any are configured
 //! - Uptime monitoring stream
 //! - Static file serving
 
-use crate::config::{ConfigWatcher, CONFIG_FILE};
-use crate::error::Result;
-use crate::index::generate_index;
-use crate::settings::save_config;
+use crate::alerts::{clear_silence, list_silences, silence_site};
+use crate::api_keys::{create_api_key, list_api_keys, require_api_key, revoke_api_key};
+use crate::assets::{static_cache_headers, StaticAssets};
+use crate::bookmarks::import_bookmarks;
+use crate::branding::{custom_css, logo, manifest, CUSTOM_CSS_PATH, LOGO_PATH, MANIFEST_PATH};
+use crate::config::{Config, ConfigWatcher, SharedConfig, CONFIG_FILE};
+use crate::error::{IronShieldError, Result};
+use crate::favicons::site_icon;
+use crate::import::import_monitors;
+use crate::index::{generate_dashboard, generate_index};
+use crate::kiosk::kiosk_page;
+use crate::preferences::{delete_preferences, get_preferences, patch_preferences};
+use crate::report::report_page;
+use crate::search::search_sites;
+use crate::self_monitor::get_self_status;
+use crate::settings::{
+    add_site, check_site, delete_site, duplicate_site, get_config, list_config_backups,
+    patch_settings, patch_site, preview_config, restore_config_backup, rollback_config,
+    save_config, toggle_site_disabled,
+};
+use crate::settings_page::{save_settings_page, settings_page};
+use crate::tags::{delete_tag, list_tags, merge_tags, rename_tag};
 use crate::telemetry::track_site_click;
-use crate::uptime::{uptime_stream, UptimeState};
+use crate::uptime::{uptime_stream, ServerEvent, UptimeState};
 use axum::{
-    routing::{get, post},
+    extract::{ConnectInfo, MatchedPath, Request, State},
+    middleware::{self, Next},
+    response::Response,
+    routing::{delete, get, patch, post},
     Router,
 };
-use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
 use std::path::PathBuf;
-use std::sync::{Arc, RwLock};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tokio::{signal, sync::broadcast};
 use tokio_util::sync::CancellationToken;
 use tower_http::services::ServeDir;
@@ -31,21 +56,152 @@ use tracing::info;
 /// Default location for the bundled frontend assets.
 const FRONTEND_DIST_DEFAULT: &str = "frontend/dist";
 
-/// Run the web server on the specified port with graceful shutdown capabilities.
+/// Port the server listens on when none is configured.
+pub const DEFAULT_PORT: u16 = 3000;
+/// Environment variable holding the port to listen on when none is configured.
+pub const PORT_ENV: &str = "IRON_SHIELD_PORT";
+/// Environment variable holding the IP address to listen on when none is configured.
+pub const BIND_ENV: &str = "IRON_SHIELD_BIND";
+
+/// Address to listen on, each part of which may be left unset
+///
+/// The address the server binds to is resolved from the command line first, then the
+/// config file's `port` and `bind`, then the `IRON_SHIELD_PORT` and `IRON_SHIELD_BIND`
+/// environment variables, and finally `0.0.0.0:3000`.
+///
+/// # Examples
+///
+/// ```
+/// use iron_shield::server::Listen;
+///
+/// let flags = Listen::from(8080);
+/// let file = Listen { port: Some(9000), bind: Some("127.0.0.1".parse().unwrap()) };
+///
+/// assert_eq!(flags.or(file).address().to_string(), "127.0.0.1:8080");
+/// assert_eq!(Listen::default().address().to_string(), "0.0.0.0:3000");
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Listen {
+    /// Port to listen on
+    pub port: Option<u16>,
+    /// IP address to listen on
+    pub bind: Option<IpAddr>,
+}
+
+impl Listen {
+    /// The address given by `IRON_SHIELD_PORT` and `IRON_SHIELD_BIND`
+    ///
+    /// # Errors
+    ///
+    /// Returns [`IronShieldError::ConfigValidation`] naming the variable if it is set but
+    /// not a valid port or IP address.
+    pub fn from_env() -> Result<Self> {
+        Self::from_vars(|name| std::env::var(name).ok())
+    }
+
+    fn from_vars(var: impl Fn(&str) -> Option<String>) -> Result<Self> {
+        let invalid = |name: &str, value: &str, expected: &str| IronShieldError::ConfigValidation {
+            field: name.to_string(),
+            message: format!("\"{value}\" is not {expected}"),
+        };
+        let port = var(PORT_ENV)
+            .map(|value| {
+                value
+                    .trim()
+                    .parse()
+                    .ok()
+                    .filter(|port| *port != 0)
+                    .ok_or_else(|| invalid(PORT_ENV, &value, "a port between 1 and 65535"))
+            })
+            .transpose()?;
+        let bind = var(BIND_ENV)
+            .map(|value| {
+                value
+                    .trim()
+                    .parse()
+                    .map_err(|_| invalid(BIND_ENV, &value, "an IP address"))
+            })
+            .transpose()?;
+        Ok(Self { port, bind })
+    }
+
+    /// This address, with its unset parts taken from `fallback`
+    #[must_use]
+    pub fn or(self, fallback: Listen) -> Listen {
+        Listen {
+            port: self.port.or(fallback.port),
+            bind: self.bind.or(fallback.bind),
+        }
+    }
+
+    /// The socket address to bind, defaulting to `0.0.0.0:3000`
+    #[must_use]
+    pub fn address(self) -> SocketAddr {
+        SocketAddr::new(
+            self.bind.unwrap_or(IpAddr::V4(Ipv4Addr::UNSPECIFIED)),
+            self.port.unwrap_or(DEFAULT_PORT),
+        )
+    }
+}
+
+impl From<u16> for Listen {
+    fn from(port: u16) -> Self {
+        Listen {
+            port: Some(port),
+            bind: None,
+        }
+    }
+}
+
+/// Run the web server on the specified address with graceful shutdown capabilities.
 ///
 /// This function initializes the web server, sets up routes, configures static file serving,
-/// and starts listening on the specified port. It handles configuration loading through
+/// and starts listening on the specified address. It handles configuration loading through
 /// the `ConfigWatcher` which monitors the config file for changes.
 ///
 /// The server serves the following endpoints:
-/// - / - Main dashboard page
-/// - /api/config - Settings API endpoint for updating configuration
+/// - / - Main dashboard page; `?tag=` and `?category=` show only the matching sites
+/// - /d/{name} - Named dashboards defined in the `dashboards` config section
+/// - /settings - Server-rendered settings page (`GET`) and its form submission (`POST`)
+/// - /api/config - Settings API endpoint for reading (`GET`) and updating (`POST`) the
+///   configuration; `POST /api/config?dry_run=true` only reports what would change
+/// - /api/config/rollback - `POST` endpoint restoring the configuration before the last
+///   change
+/// - /api/check - `POST` endpoint checking a URL once and returning the result
+/// - /api/sites/{name}/disabled - `POST` endpoint pausing or resuming monitoring of a site
+/// - /api/config/preview - `POST` endpoint describing what saving a configuration would change
+/// - /api/config/settings - `PATCH` endpoint updating the global settings but not the sites
+/// - /api/config/sites - `POST` endpoint adding one site
+/// - /api/config/import/bookmarks - `POST` endpoint adding the links of a browser
+///   bookmark export as sites
+/// - /api/config/import/monitors - `POST` endpoint adding the HTTP checks of a Gatus
+///   config or Uptime Kuma backup as sites
+/// - /api/config/sites/{name} - `PATCH` and `DELETE` endpoints updating or removing one site
+/// - /api/config/sites/{name}/duplicate - `POST` endpoint adding a copy of a site under a
+///   new name
+/// - /api/search - `GET` endpoint finding sites by name, URL, tag, or category for `?q=`
+/// - /api/tags - `GET` endpoint listing the tags in use with their usage counts
+/// - /api/tags/merge - `POST` endpoint merging several tags into one
+/// - /api/tags/{tag} - `PATCH` and `DELETE` endpoints renaming or removing a tag on every
+///   site
+/// - /api/keys - `GET` endpoint listing and `POST` endpoint creating API keys
+/// - /api/keys/{name} - `DELETE` endpoint revoking an API key
+/// - /api/alerts/silences - `GET` endpoint listing the sites whose notifications are
+///   silenced
+/// - /api/alerts/{site}/silence - `POST` endpoint silencing a site's notifications for
+///   `?duration=`, and `DELETE` endpoint ending the silence early
 /// - /uptime - Server-Sent Events endpoint for real-time uptime updates
-/// - /static/\* - Static file serving for CSS, JS, and assets
+/// - /icons/{site} - The favicon of a site, fetched and cached by the server
+/// - /branding/custom.css and /branding/logo - The stylesheet and logo named by the
+///   `custom_css` and `logo` config settings
+/// - /manifest.webmanifest - The web app manifest, named after `site_name`
+/// - /static/\* - Static file serving for CSS, JS, and assets (with `ETag` and
+///   `Cache-Control` headers from the `assets` module)
 ///
 /// # Arguments
 ///
-/// * `port` - The TCP port number on which to bind the server
+/// * `listen` - The port and IP address given on the command line, or just a port; unset
+///   parts fall back to the config file and the environment as described on [`Listen`]
 /// * `config_file_path_option` - Optional path to the configuration file
 ///   (uses default path if None is provided)
 /// * `cancel_token` - A cancellation token for graceful shutdown signals
@@ -59,6 +215,7 @@ const FRONTEND_DIST_DEFAULT: &str = "frontend/dist";
 ///
 /// This function returns an error if:
 /// - The configuration file cannot be loaded or watched
+/// - `IRON_SHIELD_PORT` or `IRON_SHIELD_BIND` is set to an invalid value
 /// - The server cannot bind to the specified address
 /// - The server encounters an error during serving
 ///
@@ -77,7 +234,7 @@ const FRONTEND_DIST_DEFAULT: &str = "frontend/dist";
 /// # }
 /// ```
 pub async fn run(
-    port: u16,
+    listen: impl Into<Listen>,
     config_file_path_option: Option<PathBuf>,
     cancel_token: CancellationToken,
 ) -> Result<()> {
@@ -88,68 +245,254 @@ pub async fn run(
         config_file_path_option.unwrap_or_else(|| std::path::Path::new(CONFIG_FILE).to_path_buf());
 
     // Create the config watcher which handles loading and watching the config file
-    let config_watcher = ConfigWatcher::new(&config_path)?;
-    let config_rwlock = config_watcher.get_config(); // Get the Arc<RwLock<Config>>
+    // Open dashboards are told when the watcher reloads the config so they can refresh,
+    // or when it rejects an invalid edit so they can say the file is not in use
+    let (server_events_tx, _) = broadcast::channel(16);
+    let reload_events = server_events_tx.clone();
+    let config_watcher = ConfigWatcher::new(&config_path, move |result| {
+        let event = match result {
+            Ok(()) => ServerEvent::ConfigUpdated,
+            Err(e) => ServerEvent::ConfigInvalid(e.to_string()),
+        };
+        // Sending only fails when no dashboard is connected
+        let _ = reload_events.send(event);
+    })?;
+    let shared_config = config_watcher.get_config();
 
     info!("Configuration loaded and watcher initialized successfully");
 
-    // Create uptime state with the config from ConfigWatcher
-    let history_map = Arc::new(RwLock::new(HashMap::new()));
-    let (shutdown_tx, _) = broadcast::channel(16);
-
-    let uptime_state = Arc::new(UptimeState {
-        config: config_rwlock,
-        history: history_map,
-        config_file_path: config_path.clone(), // Clone for UptimeState
-        shutdown_events: shutdown_tx.clone(),
-        shutdown_token: cancel_token.clone(),
-    });
-
     let static_dir = resolve_static_dir();
     info!(
         "Serving static assets from: {}",
         static_dir.to_string_lossy()
     );
+    let static_assets = Arc::new(StaticAssets::load(&static_dir));
+
+    // Create uptime state with the config from ConfigWatcher
+    let uptime_state = Arc::new(UptimeState {
+        config: shared_config,
+        config_error: config_watcher.get_config_error(),
+        server_events: server_events_tx.clone(),
+        shutdown_token: cancel_token.clone(),
+        asset_version: static_assets.version().to_string(),
+        preferences_key: crate::preferences::SigningKey::load_or_create(&config_path),
+        ..UptimeState::new(Config::default(), config_path.clone())
+    });
+
+    let static_service = Router::new()
+        .fallback_service(ServeDir::new(static_dir))
+        .layer(middleware::from_fn_with_state(
+            static_assets,
+            static_cache_headers,
+        ));
 
     let app = Router::new()
         .route("/", get(generate_index))
-        .route("/api/config", post(save_config))
+        .route("/d/:name", get(generate_dashboard))
+        .route("/kiosk", get(kiosk_page))
+        .route("/report", get(report_page))
+        .route(
+            "/preferences",
+            get(get_preferences)
+                .patch(patch_preferences)
+                .delete(delete_preferences),
+        )
+        .route("/settings", get(settings_page).post(save_settings_page))
+        .route("/api/config", get(get_config).post(save_config))
+        .route("/api/config/preview", post(preview_config))
+        .route("/api/config/settings", patch(patch_settings))
+        .route("/api/config/sites", post(add_site))
+        .route(
+            "/api/config/sites/:name",
+            patch(patch_site).delete(delete_site),
+        )
+        .route("/api/config/sites/:name/duplicate", post(duplicate_site))
+        .route("/api/config/import/bookmarks", post(import_bookmarks))
+        .route("/api/config/import/monitors", post(import_monitors))
+        .route("/api/config/rollback", post(rollback_config))
+        .route("/api/check", post(check_site))
+        .route("/api/sites/:name/disabled", post(toggle_site_disabled))
+        .route("/api/config/backups", get(list_config_backups))
+        .route(
+            "/api/config/backups/:name/restore",
+            post(restore_config_backup),
+        )
+        .route("/api/search", get(search_sites))
+        .route("/api/tags", get(list_tags))
+        .route("/api/tags/merge", post(merge_tags))
+        .route("/api/tags/:tag", patch(rename_tag).delete(delete_tag))
+        .route("/api/keys", get(list_api_keys).post(create_api_key))
+        .route("/api/keys/:name", delete(revoke_api_key))
+        .route("/api/alerts/silences", get(list_silences))
+        .route(
+            "/api/alerts/:site/silence",
+            post(silence_site).delete(clear_silence),
+        )
         .route("/api/telemetry/click", post(track_site_click))
+        .route("/api/self", get(get_self_status))
         .route("/uptime", get(uptime_stream))
-        .nest_service("/static", ServeDir::new(static_dir))
+        .route(CUSTOM_CSS_PATH, get(custom_css))
+        .route(LOGO_PATH, get(logo))
+        .route(MANIFEST_PATH, get(manifest))
+        .route("/icons/:site", get(site_icon))
+        .nest("/static", static_service)
+        .layer(middleware::from_fn_with_state(
+            uptime_state.clone(),
+            require_api_key,
+        ))
+        .layer(middleware::from_fn_with_state(
+            uptime_state.clone(),
+            log_requests,
+        ))
         .with_state(uptime_state.clone());
 
     tracing::debug!("Routes configured");
 
-    let addr = format!("0.0.0.0:{port}");
-    let address = addr.parse::<std::net::SocketAddr>().map_err(|e| {
-        crate::error::IronShieldError::Generic(format!("Failed to parse address: {e}"))
-    })?;
+    let configured = {
+        let config = uptime_state.config.load();
+        Listen {
+            port: config.port,
+            bind: config.bind,
+        }
+    };
+    let address = listen
+        .into()
+        .or(configured)
+        .or(Listen::from_env()?)
+        .address();
     tracing::info!("Binding server to address: {address}");
 
-    tracing::info!("Site launched on: http://{addr}");
+    tracing::info!("Site launched on: http://{address}");
 
     // Spawn the shutdown signal handler
-    tokio::spawn(shutdown_signal(cancel_token.clone(), shutdown_tx.clone()));
+    tokio::spawn(shutdown_signal(
+        cancel_token.clone(),
+        server_events_tx.clone(),
+    ));
 
-    let listener = tokio::net::TcpListener::bind(address).await.map_err(|e| {
-        crate::error::IronShieldError::Generic(format!("Failed to bind to address: {e}"))
-    })?;
-    if let Err(e) = axum::serve(listener, app.into_make_service())
-        .with_graceful_shutdown(async move {
-            cancel_token.cancelled().await;
-        })
+    // Export queued telemetry in the background; shutdown waits for its final flush
+    uptime_state
+        .background_tasks
+        .spawn(crate::telemetry::export_queued(Arc::clone(&uptime_state)));
+
+    // Check the sites whether or not a dashboard is open; `/uptime` streams only
+    // subscribe to the results
+    uptime_state
+        .background_tasks
+        .spawn(crate::uptime::run_monitor(Arc::clone(&uptime_state)));
+
+    let listener = tokio::net::TcpListener::bind(address)
         .await
-    {
-        return Err(crate::error::IronShieldError::Generic(format!(
-            "Server error: {e}"
-        )));
+        .map_err(|source| crate::error::IronShieldError::Bind { address, source })?;
+
+    // Config is loaded and the listener bound, so a `Type=notify` unit can start
+    crate::systemd::notify(&format!("READY=1\nSTATUS=Serving on {address}"));
+    crate::daemon::report_ready();
+    if let Some(interval) = crate::systemd::watchdog_interval() {
+        info!(
+            "Sending systemd watchdog pings every {}ms",
+            interval.as_millis()
+        );
+        tokio::spawn(crate::systemd::run_watchdog(
+            Arc::clone(&uptime_state),
+            interval,
+        ));
+    }
+    let server = axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .with_graceful_shutdown(cancel_token.clone().cancelled_owned());
+
+    // Once the listener stops accepting connections, wait for in-flight requests and
+    // uptime checks, but never longer than the configured shutdown timeout
+    let background_tasks = uptime_state.background_tasks.clone();
+    let drain = async move {
+        server
+            .await
+            .map_err(|e| crate::error::IronShieldError::ServerRun(axum::Error::new(e)))?;
+        background_tasks.close();
+        background_tasks.wait().await;
+        Ok::<(), crate::error::IronShieldError>(())
+    };
+
+    tokio::select! {
+        result = drain => result?,
+        timeout_secs = shutdown_deadline(cancel_token, Arc::clone(&uptime_state.config)) => {
+            tracing::warn!(
+                "In-flight work did not finish within {timeout_secs}s; forcing shutdown"
+            );
+        }
     }
 
     tracing::info!("Server shutdown complete");
     Ok(())
 }
 
+/// Middleware emitting a structured access-log event for every request.
+///
+/// Logging is controlled by the `access_log` config flag, which is read per request so
+/// it can be toggled through a config reload without restarting the server. Each event
+/// records the method, path, matched route (such as `/api/config/sites/:name`), response
+/// status, latency, the peer address of the connection, and the `X-Forwarded-For`
+/// header when the server sits behind a proxy.
+async fn log_requests(
+    State(state): State<Arc<UptimeState>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if !state.config.load().access_log {
+        return next.run(request).await;
+    }
+
+    let method = request.method().clone();
+    let path = request.uri().path().to_string();
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|route| route.as_str().to_string());
+    let client_ip = request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| addr.ip().to_string());
+    let forwarded_for = request
+        .headers()
+        .get("x-forwarded-for")
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string);
+    let start = Instant::now();
+
+    let response = next.run(request).await;
+
+    let latency_ms = u64::try_from(start.elapsed().as_millis()).unwrap_or(u64::MAX);
+    info!(
+        target: "iron_shield::access",
+        method = %method,
+        path = %path,
+        route = route.as_deref(),
+        status = response.status().as_u16(),
+        latency_ms,
+        client_ip = client_ip.as_deref().unwrap_or("unknown"),
+        forwarded_for = forwarded_for.as_deref(),
+        "request completed"
+    );
+
+    response
+}
+
+/// Resolves once shutdown has been requested and the configured drain timeout has elapsed.
+///
+/// The timeout is read when the shutdown starts so a reloaded config takes effect without
+/// a restart. Returns the number of seconds that were waited.
+async fn shutdown_deadline(cancel_token: CancellationToken, config: Arc<SharedConfig>) -> u64 {
+    cancel_token.cancelled().await;
+
+    let timeout_secs = config.load().shutdown_timeout_secs;
+    info!("Waiting up to {timeout_secs}s for in-flight requests and uptime checks");
+    tokio::time::sleep(Duration::from_secs(timeout_secs)).await;
+    timeout_secs
+}
+
 fn resolve_static_dir() -> PathBuf {
     std::env::var("FRONTEND_DIST_DIR")
         .map_or_else(|_| PathBuf::from(FRONTEND_DIST_DEFAULT), PathBuf::from)
@@ -176,7 +519,7 @@ fn resolve_static_dir() -> PathBuf {
 /// This function is designed to be spawned as a separate task using `tokio::spawn`.
 async fn shutdown_signal(
     cancel_token: CancellationToken,
-    shutdown_events: broadcast::Sender<String>,
+    server_events: broadcast::Sender<ServerEvent>,
 ) {
     // Handle Ctrl+C
     let ctrl_c = async {
@@ -207,8 +550,105 @@ async fn shutdown_signal(
     }
 
     tracing::info!("Received shutdown signal, starting graceful shutdown");
-    if let Err(err) = shutdown_events.send("Server is shutting down for maintenance".to_string()) {
+    crate::systemd::notify("STOPPING=1");
+    if let Err(err) = server_events.send(ServerEvent::Maintenance(
+        "Server is shutting down for maintenance".to_string(),
+    )) {
         tracing::warn!("Failed to notify clients about shutdown: {err}");
     }
     cancel_token.cancel();
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, http::StatusCode};
+    use std::io::Write;
+    use std::sync::Mutex;
+    use tower::ServiceExt;
+
+    /// Collects what a `fmt` subscriber writes, one JSON event per line
+    #[derive(Clone, Default)]
+    struct Captured(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Captured {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl Captured {
+        fn access_events(&self) -> Vec<serde_json::Value> {
+            String::from_utf8(self.0.lock().unwrap().clone())
+                .unwrap()
+                .lines()
+                .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+                .filter(|event| event["target"] == "iron_shield::access")
+                .collect()
+        }
+    }
+
+    fn build_state(access_log: bool) -> Arc<UptimeState> {
+        let config = Config {
+            access_log,
+            ..Config::default()
+        };
+        Arc::new(UptimeState {
+            asset_version: "test".to_string(),
+            ..UptimeState::new(config, PathBuf::from("test-config.json5"))
+        })
+    }
+
+    #[tokio::test]
+    async fn access_log_flag_is_read_per_request() {
+        let captured = Captured::default();
+        let subscriber = tracing_subscriber::fmt()
+            .json()
+            .with_writer({
+                let captured = captured.clone();
+                move || captured.clone()
+            })
+            .finish();
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let state = build_state(true);
+        let app = Router::new()
+            .route("/api/tags/:tag", get(|| async { StatusCode::ACCEPTED }))
+            .layer(middleware::from_fn_with_state(
+                Arc::clone(&state),
+                log_requests,
+            ));
+        let request = || {
+            Request::builder()
+                .uri("/api/tags/media")
+                .header("x-forwarded-for", "203.0.113.7")
+                .body(Body::empty())
+                .unwrap()
+        };
+
+        let response = app.clone().oneshot(request()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::ACCEPTED);
+        let events = captured.access_events();
+        assert_eq!(events.len(), 1);
+        let fields = &events[0]["fields"];
+        assert_eq!(fields["method"], "GET");
+        assert_eq!(fields["path"], "/api/tags/media");
+        assert_eq!(fields["route"], "/api/tags/:tag");
+        assert_eq!(fields["status"], 202);
+        assert!(fields["latency_ms"].is_u64());
+        assert_eq!(fields["forwarded_for"], "203.0.113.7");
+
+        state.config.store(Config {
+            access_log: false,
+            ..Config::default()
+        });
+        let response = app.oneshot(request()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::ACCEPTED);
+        assert_eq!(captured.access_events().len(), 1, "nothing logged once off");
+    }
+}
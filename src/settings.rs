@@ -1,16 +1,14 @@
-use crate::config::{Clock, Config};
-use crate::error::Result;
+use crate::config::{Clock, Config, ConfigFormat, Layout, Site, Theme, WorldClock};
+use crate::error::{IronShieldError, Result};
 use crate::uptime::UptimeState;
 use axum::{
-    extract::{Json, State},
-    http::StatusCode,
-    response::IntoResponse,
+    extract::{Json, Path, Query, State},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
 };
 use serde::{Deserialize, Serialize};
-use std::fs;
 use std::sync::Arc;
-use tracing::{error, info, warn};
-use url::Url;
+use tracing::{error, info};
 
 /// Structure to receive configuration updates from the API
 ///
@@ -41,8 +39,14 @@ use url::Url;
 ///             url: "https://example.com".to_string(),
 ///             category: "Web".to_string(),
 ///             tags: vec!["important".to_string()],
-///             monitor_interval_secs: iron_shield::config::DEFAULT_MONITOR_INTERVAL_SECS,
+///             monitor_interval_secs: Some(iron_shield::config::DEFAULT_MONITOR_INTERVAL_SECS),
+///             monitor_timeout_secs: None,
+///             monitor_retries: None,
+///             monitor_user_agent: None,
 ///             disabled: false,
+///             icon: None,
+///             order: None,
+///             assertions: None,
 ///         }
 ///     ],
 /// };
@@ -65,8 +69,9 @@ pub struct ConfigUpdate {
 impl ConfigUpdate {
     /// Validates the configuration update
     ///
-    /// This method checks that all required fields are properly set and that URLs have valid formats.
-    /// It performs comprehensive validation to ensure the configuration is safe to save.
+    /// This method checks the clock format, rejects secret references, and then applies
+    /// the same rules as [`Config::validate`], so the settings API and config loading
+    /// reject the same input.
     ///
     /// # Returns
     ///
@@ -74,12 +79,17 @@ impl ConfigUpdate {
     ///
     /// # Errors
     ///
-    /// This function returns an `IronShieldError` if:
+    /// This function returns `IronShieldError::ConfigValidation` if the clock format is
+    /// invalid (not "24hour", "12hour", or "none"), and otherwise
+    /// `IronShieldError::ConfigInvalid` naming each invalid field if:
     /// - The site name is empty.
-    /// - The clock format is invalid (not "24hour", "12hour", or "none").
-    /// - A site name or URL is empty.
-    /// - A site URL has an invalid format.
+    /// - A site name is empty or duplicated, or a URL is empty or not an `http` or
+    ///   `https` URL.
     /// - The monitoring interval is shorter than the supported minimum.
+    /// - The telemetry endpoint is empty or not an `http` or `https` URL, or is a secret
+    ///   reference the configuration was not loaded with.
+    /// - A value refers to a `${NAME}` variable; use [`ConfigUpdate::validate_against`]
+    ///   to expand the config's `vars`.
     ///
     /// # Examples
     ///
@@ -97,8 +107,14 @@ impl ConfigUpdate {
     ///             url: "https://example.com".to_string(),
     ///             category: "Web".to_string(),
     ///             tags: vec!["important".to_string()],
-    ///             monitor_interval_secs: iron_shield::config::DEFAULT_MONITOR_INTERVAL_SECS,
+    ///             monitor_interval_secs: Some(iron_shield::config::DEFAULT_MONITOR_INTERVAL_SECS),
+    ///             monitor_timeout_secs: None,
+    ///             monitor_retries: None,
+    ///             monitor_user_agent: None,
     ///             disabled: false,
+    ///             icon: None,
+    ///             order: None,
+    ///             assertions: None,
     ///         }
     ///     ],
     /// };
@@ -108,62 +124,72 @@ impl ConfigUpdate {
     /// config_update.site_name = "".to_string(); // Invalid - empty site name
     /// assert!(config_update.validate().is_err());
     /// ```
+    // Library API; the server validates against the running configuration instead
+    #[allow(dead_code)]
     pub fn validate(&self) -> Result<()> {
-        if self.site_name.trim().is_empty() {
-            return Err(crate::error::IronShieldError::from(
-                "Site name cannot be empty",
-            ));
-        }
-
-        match self.clock.as_str() {
-            "24hour" | "12hour" | "none" => {}
-            _ => return Err(crate::error::IronShieldError::from("Invalid clock format")),
-        }
+        self.validate_against(Config::default()).map(|_| ())
+    }
 
-        for site in &self.sites {
-            if site.name.trim().is_empty() {
-                return Err(crate::error::IronShieldError::from(
-                    "Site name cannot be empty",
-                ));
-            }
-            if site.url.trim().is_empty() {
-                return Err(crate::error::IronShieldError::from(
-                    "Site URL cannot be empty",
-                ));
-            }
+    /// Builds the configuration that saving this update over `base` would produce, with
+    /// variables expanded and secrets resolved, and validates it.
+    ///
+    /// Unlike [`ConfigUpdate::validate`], checks that depend on settings outside the
+    /// update, such as `base`'s `vars` and category definitions, see the real values.
+    /// Variables are expanded from `vars` only, never from the environment, except
+    /// where a value keeps a template the config file already uses for that field.
+    /// Likewise, secret references are only resolved where a field keeps the one it was
+    /// loaded with.
+    ///
+    /// # Errors
+    ///
+    /// Returns the same errors as [`ConfigUpdate::validate`].
+    pub fn validate_against(&self, base: Config) -> Result<Config> {
+        let mut config = self.apply_to(base)?;
+        config.interpolate_edited_variables()?;
+        config.resolve_edited_secrets()?;
+        config.validate()?;
+        Ok(config)
+    }
 
-            // Validate URL format
-            if Url::parse(&site.url).is_err() {
-                return Err(crate::error::IronShieldError::from(format!(
-                    "Invalid URL format: {}",
-                    site.url
-                )));
-            }
+    /// Builds the configuration that saving this update would produce.
+    ///
+    /// Fields that are not editable through the settings API (dashboards, access log,
+    /// timeouts, ...) are carried over from `base`. The result is not validated; use
+    /// [`ConfigUpdate::validate_against`] to build a validated configuration.
+    ///
+    /// # Errors
+    ///
+    /// Returns `IronShieldError::ConfigValidation` if the clock format is not one of
+    /// "24hour", "12hour", or "none".
+    pub fn apply_to(&self, base: Config) -> Result<Config> {
+        let clock = parse_clock(&self.clock)?;
 
-            if site.monitor_interval_secs < crate::config::MIN_MONITOR_INTERVAL_SECS {
-                return Err(crate::error::IronShieldError::from(format!(
-                    "Monitor interval for {} must be at least {} seconds",
-                    site.name,
-                    crate::config::MIN_MONITOR_INTERVAL_SECS
-                )));
-            }
-        }
+        let sites = self.sites.iter().map(Site::from).collect();
 
-        if let Some(endpoint) = &self.opentelemetry_endpoint {
-            if endpoint.trim().is_empty() {
-                return Err(crate::error::IronShieldError::from(
-                    "OpenTelemetry endpoint cannot be empty",
-                ));
-            }
+        Ok(Config {
+            site_name: self.site_name.clone(),
+            clock,
+            opentelemetry_endpoint: self
+                .opentelemetry_endpoint
+                .as_ref()
+                .map(|endpoint| endpoint.trim().to_string()),
+            sites,
+            ..base
+        })
+    }
+}
 
-            if let Err(err) = Url::parse(endpoint) {
-                return Err(crate::error::IronShieldError::from(format!(
-                    "Invalid OpenTelemetry endpoint: {err}",
-                )));
-            }
+impl From<&Config> for ConfigUpdate {
+    /// The editable settings of `config`, which should come from
+    /// [`Config::for_persistence`] so secret references and variable templates are shown
+    /// instead of the values they resolve to.
+    fn from(config: &Config) -> Self {
+        ConfigUpdate {
+            site_name: config.site_name.clone(),
+            clock: config.clock.to_string(),
+            opentelemetry_endpoint: config.opentelemetry_endpoint.clone(),
+            sites: config.sites.iter().map(SiteUpdate::from).collect(),
         }
-
-        Ok(())
     }
 }
 
@@ -179,7 +205,13 @@ impl ConfigUpdate {
 /// * `category` - The category of the site for organizational purposes
 /// * `tags` - A vector of tags to associate with the site
 /// * `monitor_interval_secs` - Desired number of seconds between checks
+/// * `monitor_timeout_secs` - Seconds a check may take before the site counts as down
+/// * `monitor_retries` - Extra attempts made before a failed check marks the site down
+/// * `monitor_user_agent` - `User-Agent` header sent with the site's checks
 /// * `disabled` - Whether this site should be skipped by uptime monitoring
+/// * `icon` - Optional image URL or bundled icon name shown next to the site name
+/// * `order` - Optional display position; lower values are shown first
+/// * `assertions` - Optional checks the site's response must pass to count as up
 ///
 /// # Examples
 ///
@@ -191,8 +223,14 @@ impl ConfigUpdate {
 ///     url: "https://example.com".to_string(),
 ///     category: "Web Services".to_string(),
 ///     tags: vec!["important".to_string(), "external".to_string()],
-///     monitor_interval_secs: iron_shield::config::DEFAULT_MONITOR_INTERVAL_SECS,
+///     monitor_interval_secs: Some(iron_shield::config::DEFAULT_MONITOR_INTERVAL_SECS),
+///     monitor_timeout_secs: None,
+///     monitor_retries: None,
+///     monitor_user_agent: None,
 ///     disabled: false,
+///     icon: None,
+///     order: None,
+///     assertions: None,
 /// };
 ///
 /// assert_eq!(site_update.name, "Example Site");
@@ -205,22 +243,329 @@ pub struct SiteUpdate {
     /// The URL to monitor
     pub url: String,
     /// The category of the site for organizational purposes
+    #[serde(default)]
     pub category: String,
     /// A vector of tags to associate with the site
+    #[serde(default)]
     pub tags: Vec<String>,
-    /// Desired number of seconds between uptime checks for this site
-    #[serde(default = "crate::config::default_monitor_interval_secs")]
-    pub monitor_interval_secs: u64,
+    /// Desired number of seconds between uptime checks; unset inherits `monitoring`
+    #[serde(default)]
+    pub monitor_interval_secs: Option<u64>,
+    /// Seconds a check may take before the site counts as down; unset inherits `monitoring`
+    #[serde(default)]
+    pub monitor_timeout_secs: Option<u64>,
+    /// Extra attempts made before a failed check marks the site down
+    #[serde(default)]
+    pub monitor_retries: Option<u32>,
+    /// `User-Agent` header sent with uptime checks of this site
+    #[serde(default)]
+    pub monitor_user_agent: Option<String>,
     /// Whether uptime monitoring is temporarily disabled for this site
     #[serde(default)]
     pub disabled: bool,
+    /// Image URL, server path, or bundled icon name shown next to the site name
+    #[serde(default)]
+    pub icon: Option<String>,
+    /// Display position on the dashboard; lower values come first
+    #[serde(default)]
+    pub order: Option<i32>,
+    /// Checks the site's response must pass to count as up
+    #[serde(default)]
+    pub assertions: Option<crate::assertions::Assertions>,
+}
+
+impl SiteUpdate {
+    /// Returns a copy with the fields present in `patch` replaced.
+    ///
+    /// `patch` is a partial `SiteUpdate` as a JSON object: keys that are left out keep
+    /// their current value, `null` clears an optional field, and nested values such as
+    /// `assertions` are replaced as a whole.
+    ///
+    /// # Errors
+    ///
+    /// Returns `IronShieldError::ConfigValidation` if `patch` names a field `SiteUpdate`
+    /// does not have or gives a field a value of the wrong type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use iron_shield::settings::SiteUpdate;
+    ///
+    /// let site = SiteUpdate {
+    ///     name: "NAS".to_string(),
+    ///     url: "http://nas.local".to_string(),
+    ///     category: "Storage".to_string(),
+    ///     tags: vec!["home".to_string()],
+    ///     monitor_interval_secs: None,
+    ///     monitor_timeout_secs: None,
+    ///     monitor_retries: None,
+    ///     monitor_user_agent: None,
+    ///     disabled: false,
+    ///     icon: Some("server".to_string()),
+    ///     order: None,
+    ///     assertions: None,
+    /// };
+    /// let patch = serde_json::json!({ "url": "http://nas.lan", "icon": null });
+    ///
+    /// let patched = site.patched(patch.as_object().unwrap()).unwrap();
+    /// assert_eq!(patched.url, "http://nas.lan");
+    /// assert_eq!(patched.icon, None);
+    /// assert_eq!(patched.tags, vec!["home".to_string()]);
+    /// ```
+    pub fn patched(&self, patch: &serde_json::Map<String, serde_json::Value>) -> Result<Self> {
+        patched(self, patch, "site", "is not a site field")
+    }
+}
+
+/// Returns a copy of `current` with the top-level fields present in `patch` replaced.
+///
+/// Errors name the patched key, or `what` when the result does not deserialize.
+fn patched<T: Serialize + serde::de::DeserializeOwned>(
+    current: &T,
+    patch: &serde_json::Map<String, serde_json::Value>,
+    what: &str,
+    unknown_key: &str,
+) -> Result<T> {
+    let invalid = |field: String, message: String| {
+        crate::error::IronShieldError::ConfigValidation { field, message }
+    };
+    let mut value = serde_json::to_value(current)
+        .map_err(|e| invalid(what.to_string(), format!("could not be serialized: {e}")))?;
+    let serde_json::Value::Object(fields) = &mut value else {
+        return Err(invalid(what.to_string(), "is not an object".to_string()));
+    };
+    for (key, field) in patch {
+        let Some(current) = fields.get_mut(key) else {
+            return Err(invalid(key.clone(), unknown_key.to_string()));
+        };
+        current.clone_from(field);
+    }
+    serde_json::from_value(value).map_err(|e| invalid(what.to_string(), e.to_string()))
+}
+
+/// The global settings that can be changed without sending the sites
+///
+/// Returned by and partially sent to `PATCH /api/config/settings` (see
+/// [`patch_settings`]).
+///
+/// # Examples
+///
+/// ```
+/// use iron_shield::config::Config;
+/// use iron_shield::settings::GlobalSettings;
+///
+/// let settings = GlobalSettings::from(&Config::default());
+/// let patch = serde_json::json!({ "clock": "12hour", "timezone": "Europe/Berlin" });
+///
+/// let patched = settings.patched(patch.as_object().unwrap()).unwrap();
+/// assert_eq!(patched.clock, "12hour");
+/// assert_eq!(patched.site_name, settings.site_name);
+/// ```
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+pub struct GlobalSettings {
+    /// The name of the site to display in the UI
+    pub site_name: String,
+    /// The clock format as a string ("24hour", "12hour", or "none")
+    pub clock: String,
+    /// IANA time zone name the dashboard clock is shown in
+    pub timezone: String,
+    /// Labelled clocks in other time zones shown under the main clock
+    #[serde(default)]
+    pub clocks: Vec<WorldClock>,
+    /// Color scheme of the dashboard ("auto", "dark", or "light")
+    #[serde(default)]
+    pub theme: Theme,
+    /// How the sites are laid out ("grid", "list", or "compact")
+    #[serde(default)]
+    pub layout: Layout,
+    /// Optional OpenTelemetry endpoint to forward uptime snapshots to
+    #[serde(default)]
+    pub opentelemetry_endpoint: Option<String>,
+}
+
+impl GlobalSettings {
+    /// Returns a copy with the fields present in `patch` replaced; `null` clears the
+    /// telemetry endpoint.
+    ///
+    /// # Errors
+    ///
+    /// Returns `IronShieldError::ConfigValidation` naming a key that is not a global
+    /// setting, or if a value has the wrong type.
+    pub fn patched(&self, patch: &serde_json::Map<String, serde_json::Value>) -> Result<Self> {
+        patched(self, patch, "settings", "is not a global setting")
+    }
+
+    /// Write these settings to `config`.
+    ///
+    /// Variables and secret references are left as written; see
+    /// [`Config::interpolate_variables`] and [`Config::resolve_secrets`].
+    ///
+    /// # Errors
+    ///
+    /// Returns `IronShieldError::ConfigValidation` if the clock format is not one of
+    /// "24hour", "12hour", or "none".
+    pub fn apply_to(&self, config: &mut Config) -> Result<()> {
+        config.clock = parse_clock(&self.clock)?;
+        config.site_name.clone_from(&self.site_name);
+        config.timezone.clone_from(&self.timezone);
+        config.clocks.clone_from(&self.clocks);
+        config.theme = self.theme;
+        config.layout = self.layout;
+        config.opentelemetry_endpoint = self
+            .opentelemetry_endpoint
+            .as_ref()
+            .map(|endpoint| endpoint.trim().to_string());
+        Ok(())
+    }
+}
+
+impl From<&Config> for GlobalSettings {
+    /// The global settings of `config`; when they are shown to a client, `config` should
+    /// come from [`Config::for_persistence`] so secret references and variable templates
+    /// are shown instead of the values they resolve to.
+    fn from(config: &Config) -> Self {
+        GlobalSettings {
+            site_name: config.site_name.clone(),
+            clock: config.clock.to_string(),
+            timezone: config.timezone.clone(),
+            clocks: config.clocks.clone(),
+            theme: config.theme,
+            layout: config.layout,
+            opentelemetry_endpoint: config.opentelemetry_endpoint.clone(),
+        }
+    }
+}
+
+/// Parse a clock format as used by the settings API.
+fn parse_clock(clock: &str) -> Result<Clock> {
+    match clock {
+        "24hour" => Ok(Clock::Hour24),
+        "12hour" => Ok(Clock::Hour12),
+        "none" => Ok(Clock::None),
+        _ => Err(crate::error::IronShieldError::ConfigValidation {
+            field: "clock".to_string(),
+            message: "must be one of \"24hour\", \"12hour\" or \"none\"".to_string(),
+        }),
+    }
+}
+
+impl From<&Site> for SiteUpdate {
+    fn from(site: &Site) -> Self {
+        SiteUpdate {
+            name: site.name.clone(),
+            url: site.url.clone(),
+            category: site.category.clone(),
+            tags: site.tags.clone(),
+            monitor_interval_secs: site.monitor_interval_secs,
+            monitor_timeout_secs: site.monitor_timeout_secs,
+            monitor_retries: site.monitor_retries,
+            monitor_user_agent: site.monitor_user_agent.clone(),
+            disabled: site.disabled,
+            icon: site.icon.clone(),
+            order: site.order,
+            assertions: site.assertions.clone(),
+        }
+    }
+}
+
+impl From<&SiteUpdate> for Site {
+    fn from(site_update: &SiteUpdate) -> Self {
+        Site {
+            name: site_update.name.clone(),
+            url: site_update.url.clone(),
+            category: site_update.category.clone(),
+            tags: site_update.tags.clone(),
+            monitor_interval_secs: site_update.monitor_interval_secs,
+            monitor_timeout_secs: site_update.monitor_timeout_secs,
+            monitor_retries: site_update.monitor_retries,
+            monitor_user_agent: site_update.monitor_user_agent.clone(),
+            disabled: site_update.disabled,
+            icon: site_update
+                .icon
+                .as_ref()
+                .map(|icon| icon.trim().to_string())
+                .filter(|icon| !icon.is_empty()),
+            order: site_update.order,
+            assertions: site_update.assertions.clone(),
+            uptime_percentage: 0.0, // Initialize to 0.0, will be updated by uptime service
+        }
+    }
+}
+
+/// Apply `edit` to the running configuration, then validate, save, and activate the result.
+///
+/// The change is made through [`SharedConfig::update`](crate::config::SharedConfig::update),
+/// so concurrent edits are applied one after another instead of overwriting each other.
+/// When `if_match` is given, the edit is only made if it lists the current
+/// [`Config::revision`]. The file is backed up and written as described for [`save_config`], open
+/// dashboards are told to refresh, and an earlier rejected reload is cleared.
+///
+/// Returns the new configuration and what `edit` returned.
+pub(crate) fn update_config<T>(
+    state: &UptimeState,
+    if_match: Option<&str>,
+    edit: impl FnOnce(&mut Config) -> Result<T>,
+) -> Result<(Arc<Config>, T)> {
+    if state.config_file_path.is_dir() {
+        return Err(crate::error::IronShieldError::from(format!(
+            "Configuration is loaded from the directory {}; edit its files instead",
+            state.config_file_path.display()
+        )));
+    }
+
+    let (new_config, edited) = state.config.update(|new_config| {
+        if let Some(if_match) = if_match {
+            let current = new_config.revision();
+            if !if_match_allows(if_match, &current) {
+                return Err(crate::error::IronShieldError::RevisionMismatch {
+                    expected: if_match.to_string(),
+                    current,
+                });
+            }
+        }
+        let edited = edit(new_config)?;
+        new_config.validate()?;
+        for warning in new_config.warnings() {
+            tracing::warn!("Saved configuration: {warning}");
+        }
+
+        // Write the updated configuration to the file
+        let config_json = ConfigFormat::from_path(&state.config_file_path)
+            .serialize(&new_config.for_persistence())
+            .map_err(|e| {
+                crate::error::IronShieldError::from(format!("Failed to serialize config: {e}"))
+            })?;
+
+        crate::backup::create_backup(&state.config_file_path, new_config.config_backup_retention)?;
+        crate::config::write_atomic(&state.config_file_path, &config_json).map_err(|e| {
+            crate::error::IronShieldError::from(format!("Failed to write config file: {e}"))
+        })?;
+        Ok(edited)
+    })?;
+    info!("Configuration updated successfully in memory");
+
+    // The file on disk is valid again, so an earlier rejected reload no longer applies
+    *state.config_error.write() = None;
+    // Sending only fails when no dashboard is connected
+    let _ = state
+        .server_events
+        .send(crate::uptime::ServerEvent::ConfigUpdated);
+
+    Ok((new_config, edited))
 }
 
-/// Saves the configuration to the config.json5 file
+/// Saves the configuration to the config file
 ///
 /// This function handles the API request to update and save the application configuration.
 /// It validates the incoming configuration data, converts it to the appropriate format,
 /// writes it to the configuration file, and updates the in-memory configuration.
+/// The previous file is first copied to a timestamped backup (see [`crate::backup`]), and
+/// the new file is written atomically (see [`crate::config::write_atomic`]) in the same
+/// format (JSON5, YAML, or TOML) it was loaded from.
+/// Sites that were loaded from `include`d files are not written to the main file; they
+/// remain managed in (and are reloaded from) their own files, and secret references such
+/// as `${env:API_TOKEN}` are written back instead of the secrets they resolve to. A save
+/// can keep the references the file already has, but not add new ones.
 ///
 /// The function performs validation of the configuration data before saving, converts
 /// string-based clock format to the appropriate enum, and updates both the file system
@@ -233,14 +578,27 @@ pub struct SiteUpdate {
 ///
 /// # Returns
 ///
-/// An HTTP response indicating success (200 OK) or failure (500 Internal Server Error).
-/// On success, returns the message "Configuration saved successfully".
-/// On failure, returns the error message.
+/// An HTTP response indicating success (200 OK) or failure (400 Bad Request, 409
+/// Conflict, or 500 Internal Server Error).
+/// On success, returns the message "Configuration saved successfully" with the new
+/// revision as its `ETag`.
+/// On failure, returns an [`ErrorResponse`](crate::error::ErrorResponse) listing every
+/// invalid field.
+///
+/// With `?dry_run=true` nothing is written and the running configuration is left alone:
+/// the configuration is validated exactly as for a save, the telemetry endpoint it names
+/// is checked for reachability, and the response is a [`DryRunReport`] of what the save
+/// would change. This lets a CI pipeline check a managed configuration before applying it.
 ///
 /// # Errors
 ///
-/// This function returns an HTTP 500 error response if:
-/// - The configuration fails validation
+/// This function returns an HTTP 400 error response if the configuration fails
+/// validation, including an unknown clock format, an HTTP 409 error response if an
+/// `If-Match` header does not list the current revision (see [`get_config`]), because
+/// the configuration was changed since the client loaded it, and an HTTP 500 error
+/// response if:
+/// - The configuration is loaded from a directory of fragments, which are edited by hand
+/// - The previous configuration file cannot be backed up
 /// - The configuration cannot be serialized to JSON5 format
 /// - The configuration file cannot be written to disk
 /// - The in-memory configuration cannot be updated due to a lock error
@@ -258,97 +616,845 @@ pub struct SiteUpdate {
 /// ```
 pub async fn save_config(
     State(state): State<Arc<UptimeState>>,
+    Query(query): Query<SaveQuery>,
+    headers: HeaderMap,
     Json(payload): Json<ConfigUpdate>,
-) -> impl IntoResponse {
+) -> Result<Response> {
+    let if_match = headers
+        .get(header::IF_MATCH)
+        .and_then(|value| value.to_str().ok());
+    if query.dry_run {
+        let report = dry_run(&state, if_match, &payload)
+            .await
+            .inspect_err(|e| error!("Configuration dry run failed: {e}"))?;
+        return Ok(Json(report).into_response());
+    }
+
     tracing::info!("Saving configuration");
+    // Settings that are not editable through the API are carried over unchanged, and the
+    // update's values may depend on the current `vars` and category definitions
+    let (config, ()) = update_config(&state, if_match, |config| {
+        *config = payload.validate_against(config.clone())?;
+        Ok(())
+    })
+    .inspect_err(|e| error!("Error saving configuration: {e}"))?;
+    crate::telemetry::queue_uptime_snapshot(&state);
 
-    let result = (|| -> Result<(Option<String>, String)> {
-        // Validate the configuration update
-        payload.validate()?;
+    Ok((
+        StatusCode::OK,
+        [(header::ETAG, etag(&config.revision()))],
+        "Configuration saved successfully",
+    )
+        .into_response())
+}
 
-        // Convert the clock format string to the Clock enum
-        let clock = match payload.clock.as_str() {
-            "24hour" => Clock::Hour24,
-            "12hour" => Clock::Hour12,
-            "none" => Clock::None,
-            _ => return Err(crate::error::IronShieldError::from("Invalid clock format")),
-        };
+/// Query string of `POST /api/config`
+#[derive(Debug, Default, Deserialize)]
+pub struct SaveQuery {
+    /// Validate and report the changes without saving them
+    #[serde(default)]
+    pub dry_run: bool,
+}
 
-        let telemetry_endpoint = payload
-            .opentelemetry_endpoint
-            .as_ref()
-            .map(|endpoint| endpoint.trim().to_string());
+/// Response to a dry-run save (see [`save_config`])
+#[derive(Debug, Serialize)]
+pub struct DryRunReport {
+    /// What the save would change
+    #[serde(flatten)]
+    pub preview: ConfigPreview,
+    /// Revision the save would replace, for use in `If-Match`
+    pub revision: String,
+    /// Reachability of the telemetry endpoint, if one is configured
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub telemetry: Option<TelemetryCheck>,
+}
 
-        // Convert SiteUpdate to Site
-        let sites: Vec<crate::config::Site> = payload
-            .sites
-            .into_iter()
-            .map(|site_update| crate::config::Site {
-                name: site_update.name,
-                url: site_update.url,
-                category: site_update.category,
-                tags: site_update.tags,
-                monitor_interval_secs: site_update.monitor_interval_secs,
-                disabled: site_update.disabled,
-                uptime_percentage: 0.0, // Initialize to 0.0, will be updated by uptime service
+/// Whether a telemetry endpoint answered a [`DryRunReport`] check
+#[derive(Debug, Serialize)]
+pub struct TelemetryCheck {
+    /// The endpoint that was checked
+    pub endpoint: String,
+    /// Whether it answered
+    pub reachable: bool,
+    /// Why it could not be reached
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Validate `payload` as [`update_config`] would and describe the result, changing nothing.
+async fn dry_run(
+    state: &UptimeState,
+    if_match: Option<&str>,
+    payload: &ConfigUpdate,
+) -> Result<DryRunReport> {
+    if state.config_file_path.is_dir() {
+        return Err(crate::error::IronShieldError::from(format!(
+            "Configuration is loaded from the directory {}; edit its files instead",
+            state.config_file_path.display()
+        )));
+    }
+    let current = state.config.load();
+    let revision = current.revision();
+    if let Some(if_match) = if_match {
+        if !if_match_allows(if_match, &revision) {
+            return Err(crate::error::IronShieldError::RevisionMismatch {
+                expected: if_match.to_string(),
+                current: revision,
+            });
+        }
+    }
+    let proposed = payload.validate_against(Config::clone(&current))?;
+    let saved = ConfigUpdate::from(&proposed.for_persistence());
+    let preview = ConfigPreview::between(&ConfigUpdate::from(&current.for_persistence()), &saved);
+
+    // The endpoint is reported as saved so a secret interpolated into it is not echoed
+    let telemetry = match (
+        proposed.opentelemetry_endpoint,
+        saved.opentelemetry_endpoint,
+    ) {
+        (Some(endpoint), Some(saved_endpoint)) if !endpoint.trim().is_empty() => {
+            let error = match crate::telemetry::check_reachable(
+                &endpoint,
+                proposed.opentelemetry_transport,
+            )
+            .await
+            {
+                Ok(()) => None,
+                Err(crate::error::IronShieldError::Telemetry { source, .. }) => {
+                    Some(source.to_string())
+                }
+                Err(e) => Some(e.to_string()),
+            };
+            Some(TelemetryCheck {
+                endpoint: saved_endpoint,
+                reachable: error.is_none(),
+                error,
             })
-            .collect();
+        }
+        _ => None,
+    };
 
-        // Create a new configuration based on the payload
-        let new_config = Config {
-            site_name: payload.site_name,
-            clock,
-            opentelemetry_endpoint: telemetry_endpoint.clone(),
-            sites,
-        };
+    Ok(DryRunReport {
+        preview,
+        revision,
+        telemetry,
+    })
+}
 
-        let telemetry_dashboard = new_config.site_name.clone();
+/// Return the editable settings and their revision
+///
+/// Handles `GET /api/config`. The body is the [`ConfigUpdate`] the settings modal edits,
+/// built from [`Config::for_persistence`], and the `ETag` header holds the quoted
+/// [`Config::revision`]. Sending that value back in an `If-Match` header when saving
+/// makes [`save_config`] reject the save if someone else changed the configuration in
+/// the meantime.
+pub async fn get_config(State(state): State<Arc<UptimeState>>) -> impl IntoResponse {
+    let config = state.config.load();
+    (
+        [(header::ETAG, etag(&config.revision()))],
+        Json(ConfigUpdate::from(&config.for_persistence())),
+    )
+}
 
-        // Write the updated configuration to the file
-        let config_json = json5::to_string(&new_config).map_err(|e| {
-            crate::error::IronShieldError::from(format!("Failed to serialize config: {e}"))
-        })?;
+/// What saving a [`ConfigUpdate`] would change, returned by [`preview_config`]
+///
+/// Sites are matched by name (ignoring case), so a renamed site is listed as removed
+/// under its old name and added under its new one.
+#[derive(Debug, Default, Serialize, PartialEq, Eq)]
+pub struct ConfigPreview {
+    /// One sentence per change, such as `Remove site "NAS"`, in the order they appear
+    pub changes: Vec<String>,
+    /// Global settings that would change, such as `clock`
+    pub changed_settings: Vec<String>,
+    /// Names of the sites that would be added
+    pub added_sites: Vec<String>,
+    /// Names of the sites that would be removed
+    pub removed_sites: Vec<String>,
+    /// Sites that would be kept but edited
+    pub changed_sites: Vec<SiteChange>,
+}
 
-        fs::write(&state.config_file_path, config_json).map_err(|e| {
-            crate::error::IronShieldError::from(format!("Failed to write config file: {e}"))
-        })?;
+/// A site that a [`ConfigPreview`] keeps with different values
+#[derive(Debug, Serialize, PartialEq, Eq)]
+pub struct SiteChange {
+    /// Name of the site
+    pub name: String,
+    /// Fields that would change, such as `url` or `tags`
+    pub fields: Vec<String>,
+}
 
-        // Update the config in memory
-        {
-            let mut config_guard = state.config.write().map_err(|_| {
-                crate::error::IronShieldError::from("Failed to acquire config write lock")
-            })?;
-            *config_guard = new_config;
-            info!("Configuration updated successfully in memory");
+impl ConfigPreview {
+    /// The changes from `current` to `proposed`, both as saved (see
+    /// [`Config::for_persistence`]) so variable templates are compared, not their values.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use iron_shield::config::Config;
+    /// use iron_shield::settings::{ConfigPreview, ConfigUpdate};
+    ///
+    /// let current = ConfigUpdate::from(&Config::default());
+    /// let mut proposed = current.clone();
+    /// proposed.clock = "12hour".to_string();
+    ///
+    /// let preview = ConfigPreview::between(&current, &proposed);
+    /// assert_eq!(preview.changed_settings, vec!["clock"]);
+    /// assert_eq!(preview.changes, vec!["Change clock from \"none\" to \"12hour\""]);
+    /// ```
+    #[must_use]
+    pub fn between(current: &ConfigUpdate, proposed: &ConfigUpdate) -> Self {
+        let mut preview = ConfigPreview::default();
+
+        let settings = [
+            ("site_name", &current.site_name, &proposed.site_name),
+            ("clock", &current.clock, &proposed.clock),
+        ];
+        for (field, from, to) in settings {
+            if from != to {
+                preview.changed_settings.push(field.to_string());
+                preview
+                    .changes
+                    .push(format!("Change {field} from \"{from}\" to \"{to}\""));
+            }
+        }
+        if current.opentelemetry_endpoint != proposed.opentelemetry_endpoint {
+            preview
+                .changed_settings
+                .push("opentelemetry_endpoint".to_string());
+            preview
+                .changes
+                .push(match &proposed.opentelemetry_endpoint {
+                    Some(endpoint) => format!("Send telemetry to \"{endpoint}\""),
+                    None => "Stop sending telemetry".to_string(),
+                });
         }
 
-        Ok((telemetry_endpoint, telemetry_dashboard))
-    })();
-
-    match result {
-        Ok((telemetry_endpoint, dashboard_name)) => {
-            if let Some(endpoint) = telemetry_endpoint {
-                let state_clone = Arc::clone(&state);
-                tokio::spawn(async move {
-                    if let Err(err) = crate::telemetry::send_uptime_snapshot(
-                        state_clone,
-                        dashboard_name,
-                        endpoint,
-                    )
-                    .await
-                    {
-                        warn!("Failed to send uptime snapshot: {err}");
-                    }
+        let find = |sites: &[SiteUpdate], name: &str| {
+            sites
+                .iter()
+                .find(|site| crate::config::same_site_name(&site.name, name))
+                .cloned()
+        };
+        for site in &current.sites {
+            if find(&proposed.sites, &site.name).is_none() {
+                preview.removed_sites.push(site.name.clone());
+                preview
+                    .changes
+                    .push(format!("Remove site \"{}\"", site.name));
+            }
+        }
+        for site in &proposed.sites {
+            let Some(existing) = find(&current.sites, &site.name) else {
+                preview.added_sites.push(site.name.clone());
+                preview.changes.push(format!("Add site \"{}\"", site.name));
+                continue;
+            };
+            let fields = changed_fields(&existing, site);
+            if !fields.is_empty() {
+                preview.changes.push(format!(
+                    "Change {} of site \"{}\"",
+                    fields.join(", "),
+                    site.name
+                ));
+                preview.changed_sites.push(SiteChange {
+                    name: site.name.clone(),
+                    fields,
                 });
             }
+        }
+        preview
+    }
+}
+
+/// Names of the top-level fields whose values differ between `from` and `to`.
+fn changed_fields(from: &SiteUpdate, to: &SiteUpdate) -> Vec<String> {
+    let (Ok(serde_json::Value::Object(from)), Ok(serde_json::Value::Object(to))) =
+        (serde_json::to_value(from), serde_json::to_value(to))
+    else {
+        return Vec::new();
+    };
+    from.iter()
+        .filter(|(field, value)| to.get(*field) != Some(*value))
+        .map(|(field, _)| field.clone())
+        .collect()
+}
+
+/// Show what saving a configuration would change
+///
+/// Handles `POST /api/config/preview`. The [`ConfigUpdate`] body is validated exactly as
+/// [`save_config`] would, then compared with the current configuration; nothing is
+/// written. Responds with a [`ConfigPreview`] and the current revision as the `ETag`, so
+/// the reviewed save can be sent with a matching `If-Match` header.
+///
+/// # Errors
+///
+/// Responds with HTTP 400 and an [`ErrorResponse`](crate::error::ErrorResponse) if the
+/// configuration is invalid.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use axum::{Router, routing::post};
+/// use iron_shield::settings::preview_config;
+///
+/// let app = Router::new()
+///     .route("/api/config/preview", post(preview_config));
+/// ```
+pub async fn preview_config(
+    State(state): State<Arc<UptimeState>>,
+    Json(payload): Json<ConfigUpdate>,
+) -> Result<impl IntoResponse> {
+    let config = state.config.load();
+    let proposed = payload.validate_against(Config::clone(&config))?;
+    // Both sides as they would be saved, so normalized values do not show as changes
+    let preview = ConfigPreview::between(
+        &ConfigUpdate::from(&config.for_persistence()),
+        &ConfigUpdate::from(&proposed.for_persistence()),
+    );
+    Ok(([(header::ETAG, etag(&config.revision()))], Json(preview)))
+}
+
+/// The `ETag` header value for a config revision.
+fn etag(revision: &str) -> HeaderValue {
+    HeaderValue::from_str(&format!("\"{revision}\""))
+        .unwrap_or_else(|_| HeaderValue::from_static("\"\""))
+}
+
+/// Whether an `If-Match` header value lists `revision` (quoted or not) or is `*`.
+fn if_match_allows(if_match: &str, revision: &str) -> bool {
+    if_match
+        .split(',')
+        .map(|candidate| candidate.trim().trim_matches('"'))
+        .any(|candidate| candidate == "*" || candidate == revision)
+}
+
+/// Update the global settings without touching the sites
+///
+/// Handles `PATCH /api/config/settings`. The JSON body is a partial [`GlobalSettings`]:
+/// `site_name`, `clock`, `timezone`, `clocks`, `theme`, and `opentelemetry_endpoint`
+/// that are present are changed and everything else, including every site and every
+/// environment override, is kept as it is, so changing the clock cannot overwrite a
+/// concurrent site edit. An `If-Match` header is honored as for [`save_config`]. Responds
+/// with the resulting settings and the new revision as the `ETag`.
+///
+/// # Errors
+///
+/// Responds with HTTP 400 if a key is not a global setting or a value is invalid, HTTP
+/// 409 if `If-Match` does not list the current revision, and HTTP 500 if the
+/// configuration cannot be saved.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use axum::{Router, routing::patch};
+/// use iron_shield::settings::patch_settings;
+///
+/// let app = Router::new()
+///     .route("/api/config/settings", patch(patch_settings));
+/// ```
+pub async fn patch_settings(
+    State(state): State<Arc<UptimeState>>,
+    headers: HeaderMap,
+    Json(patch): Json<serde_json::Map<String, serde_json::Value>>,
+) -> Result<impl IntoResponse> {
+    tracing::info!("Updating global settings");
+
+    let if_match = headers
+        .get(header::IF_MATCH)
+        .and_then(|value| value.to_str().ok());
+    let (config, ()) = update_config(&state, if_match, |config| {
+        // Patched over the running values, so the settings the patch leaves alone keep
+        // their environment overrides, expanded variables, and resolved secrets
+        GlobalSettings::from(&*config)
+            .patched(&patch)?
+            .apply_to(config)?;
+        config.interpolate_edited_variables()?;
+        config.resolve_edited_secrets()
+    })
+    .inspect_err(|e| error!("Error updating global settings: {e}"))?;
+
+    Ok((
+        [(header::ETAG, etag(&config.revision()))],
+        Json(GlobalSettings::from(&config.for_persistence())),
+    ))
+}
+
+/// Update one site from a partial `SiteUpdate`
+///
+/// Handles `PATCH /api/config/sites/{name}`. Only the fields present in the JSON body are
+/// changed (see [`SiteUpdate::patched`]), so clients editing different sites, or
+/// different fields of one site, do not overwrite each other's changes the way whole
+/// configurations sent to [`save_config`] do. The result is validated, saved, and
+/// applied like a full save, and the updated site is returned as a `SiteUpdate`.
+///
+/// # Errors
+///
+/// Responds with HTTP 404 if no site has the given name, HTTP 400 if the patch or the
+/// resulting configuration is invalid or the site comes from an `include`d file, and
+/// HTTP 500 if the configuration cannot be saved.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use axum::{Router, routing::patch};
+/// use iron_shield::settings::patch_site;
+///
+/// let app = Router::new()
+///     .route("/api/config/sites/:name", patch(patch_site));
+/// ```
+pub async fn patch_site(
+    State(state): State<Arc<UptimeState>>,
+    Path(name): Path<String>,
+    Json(patch): Json<serde_json::Map<String, serde_json::Value>>,
+) -> Result<impl IntoResponse> {
+    tracing::info!("Updating site {name}");
+
+    let (config, index) = update_config(&state, None, |config| {
+        let index = editable_site_index(config, &name)?;
+        let patched = SiteUpdate::from(&config.sites[index]).patched(&patch)?;
+        config.sites[index] = Site::from(&patched);
+        // Fields the patch left alone keep their expanded values and recorded templates
+        config.interpolate_edited_variables()?;
+        Ok(index)
+    })
+    .inspect_err(|e| error!("Error updating site {name}: {e}"))?;
+
+    Ok(Json(SiteUpdate::from(&config.sites[index])))
+}
+
+/// Pause or resume monitoring of one site
+///
+/// Handles `POST /api/sites/{name}/disabled` by flipping the site's `disabled` flag, so
+/// a site can be paused from the dashboard without sending and revalidating the whole
+/// configuration. The change is saved like a full save and takes effect on the next
+/// tick of every monitoring loop. Responds with the updated site as a `SiteUpdate`.
+///
+/// # Errors
+///
+/// Responds with HTTP 404 if no site has the given name, HTTP 400 if the site comes from
+/// an `include`d file, and HTTP 500 if the configuration cannot be saved.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use axum::{Router, routing::post};
+/// use iron_shield::settings::toggle_site_disabled;
+///
+/// let app = Router::new()
+///     .route("/api/sites/:name/disabled", post(toggle_site_disabled));
+/// ```
+pub async fn toggle_site_disabled(
+    State(state): State<Arc<UptimeState>>,
+    Path(name): Path<String>,
+) -> Result<impl IntoResponse> {
+    let (config, index) = update_config(&state, None, |config| {
+        let index = editable_site_index(config, &name)?;
+        config.sites[index].disabled = !config.sites[index].disabled;
+        Ok(index)
+    })
+    .inspect_err(|e| error!("Error toggling site {name}: {e}"))?;
+
+    let site = SiteUpdate::from(&config.sites[index]);
+    tracing::info!(
+        "Monitoring of site {name} {}",
+        if site.disabled { "paused" } else { "resumed" }
+    );
+    Ok(Json(site))
+}
+
+/// Add one site to the configuration
+///
+/// Handles `POST /api/config/sites`. The site is appended after the configured sites,
+/// then the configuration is validated, saved, and applied like a full save, so adding a
+/// bookmark does not require sending the whole configuration. Responds with HTTP 201 and
+/// the added site.
+///
+/// # Errors
+///
+/// Responds with HTTP 409 if a site with the same name (ignoring case) exists, HTTP 400
+/// if the site is invalid, and HTTP 500 if the configuration cannot be saved.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use axum::{Router, routing::post};
+/// use iron_shield::settings::add_site;
+///
+/// let app = Router::new()
+///     .route("/api/config/sites", post(add_site));
+/// ```
+pub async fn add_site(
+    State(state): State<Arc<UptimeState>>,
+    Json(site): Json<SiteUpdate>,
+) -> Result<impl IntoResponse> {
+    tracing::info!("Adding site {}", site.name);
+
+    let (config, index) = update_config(&state, None, |config| {
+        if config
+            .sites
+            .iter()
+            .any(|existing| crate::config::same_site_name(&existing.name, &site.name))
+        {
+            return Err(crate::error::IronShieldError::SiteExists(site.name.clone()));
+        }
+        config.sites.push(Site::from(&site));
+        config.interpolate_edited_variables()?;
+        Ok(config.sites.len() - 1)
+    })
+    .inspect_err(|e| error!("Error adding site {}: {e}", site.name))?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(SiteUpdate::from(&config.sites[index])),
+    ))
+}
+
+/// Name and optional URL of the copy made by [`duplicate_site`]
+#[derive(Debug, Deserialize)]
+pub struct SiteCopy {
+    /// Name of the new site
+    pub name: String,
+    /// URL of the new site; the original's URL is kept when unset
+    #[serde(default)]
+    pub url: Option<String>,
+}
+
+/// Add a copy of a site under a new name
+///
+/// Handles `POST /api/config/sites/{name}/duplicate`. The copy keeps the original's
+/// category, tags, icon, and check options, including `${NAME}` variables as written,
+/// and is appended after the configured sites with the name and, when given, the URL
+/// from the body. It is validated, saved, and applied like [`add_site`]. Responds with
+/// HTTP 201 and the new site.
+///
+/// # Errors
+///
+/// Responds with HTTP 404 if no site has the given name, HTTP 409 if a site with the new
+/// name (ignoring case) exists, HTTP 400 if the copy is invalid, and HTTP 500 if the
+/// configuration cannot be saved.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use axum::{Router, routing::post};
+/// use iron_shield::settings::duplicate_site;
+///
+/// let app = Router::new()
+///     .route("/api/config/sites/:name/duplicate", post(duplicate_site));
+/// ```
+pub async fn duplicate_site(
+    State(state): State<Arc<UptimeState>>,
+    Path(name): Path<String>,
+    Json(copy): Json<SiteCopy>,
+) -> Result<impl IntoResponse> {
+    tracing::info!("Duplicating site {name} as {}", copy.name);
+
+    let (config, index) = update_config(&state, None, |config| {
+        // Copy the site as written so variables are kept; sites from included files
+        // are only in the running configuration
+        let persisted = config.for_persistence();
+        let mut site = persisted
+            .sites
+            .iter()
+            .chain(&config.sites)
+            .find(|site| site.name == name)
+            .cloned()
+            .ok_or_else(|| crate::error::IronShieldError::SiteNotFound(name.clone()))?;
+        if config
+            .sites
+            .iter()
+            .any(|existing| crate::config::same_site_name(&existing.name, &copy.name))
+        {
+            return Err(crate::error::IronShieldError::SiteExists(copy.name.clone()));
+        }
+        site.name.clone_from(&copy.name);
+        if let Some(url) = &copy.url {
+            site.url = url.trim().to_string();
+        }
+        config.sites.push(site);
+        config.interpolate_edited_variables()?;
+        Ok(config.sites.len() - 1)
+    })
+    .inspect_err(|e| error!("Error duplicating site {name}: {e}"))?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(SiteUpdate::from(&config.sites[index])),
+    ))
+}
+
+/// Remove one site from the configuration
+///
+/// Handles `DELETE /api/config/sites/{name}`. The site is removed along with its entries
+/// in `groups` and `dashboards`, the configuration is saved and applied like a full save,
+/// and the site's uptime history is dropped so it no longer appears in SSE updates.
+/// Responds with HTTP 204 on success.
+///
+/// # Errors
+///
+/// Responds with HTTP 404 if no site has the given name, HTTP 400 if the site comes from
+/// an `include`d file, and HTTP 500 if the configuration cannot be saved.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use axum::{Router, routing::delete};
+/// use iron_shield::settings::delete_site;
+///
+/// let app = Router::new()
+///     .route("/api/config/sites/:name", delete(delete_site));
+/// ```
+pub async fn delete_site(
+    State(state): State<Arc<UptimeState>>,
+    Path(name): Path<String>,
+) -> Result<impl IntoResponse> {
+    tracing::info!("Removing site {name}");
 
-            (StatusCode::OK, "Configuration saved successfully").into_response()
+    update_config(&state, None, |config| {
+        let index = editable_site_index(config, &name)?;
+        config.sites.remove(index);
+        for group in &mut config.groups {
+            group.sites.retain(|site| *site != name);
         }
-        Err(e) => {
-            error!("Error saving configuration: {e}");
-            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response()
+        for dashboard in &mut config.dashboards {
+            dashboard.sites.retain(|site| *site != name);
         }
+        Ok(())
+    })
+    .inspect_err(|e| error!("Error removing site {name}: {e}"))?;
+
+    state.history.write().remove(&name);
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Index of the site called `name`, which must be defined in the main config file.
+fn editable_site_index(config: &Config, name: &str) -> Result<usize> {
+    let index = config
+        .sites
+        .iter()
+        .position(|site| site.name == name)
+        .ok_or_else(|| crate::error::IronShieldError::SiteNotFound(name.to_string()))?;
+    if config
+        .included_sites
+        .iter()
+        .any(|included| included == name)
+    {
+        return Err(crate::error::IronShieldError::ConfigValidation {
+            field: format!("sites[{index}]"),
+            message: "is defined in an included file; edit that file instead".to_string(),
+        });
+    }
+    Ok(index)
+}
+
+/// A URL and check options to probe once with [`check_site`]
+///
+/// The fields are named like the matching [`SiteUpdate`] fields, and other fields are
+/// ignored, so a site being edited can be sent as it is. Unset options inherit the
+/// configured `monitoring` defaults.
+#[derive(Deserialize, Clone)]
+pub struct CheckRequest {
+    /// The URL to check; may use `${NAME}` variables from `vars`
+    pub url: String,
+    /// Seconds the check may take before the site counts as down
+    #[serde(default)]
+    pub monitor_timeout_secs: Option<u64>,
+    /// Extra attempts made before a failed check counts as down
+    #[serde(default)]
+    pub monitor_retries: Option<u32>,
+    /// `User-Agent` header sent with the check
+    #[serde(default)]
+    pub monitor_user_agent: Option<String>,
+    /// Checks the response must pass to count as up
+    #[serde(default)]
+    pub assertions: Option<crate::assertions::Assertions>,
+}
+
+/// Check a URL once, right now
+///
+/// Handles `POST /api/check`. The [`CheckRequest`] is validated like a site, then probed
+/// exactly as the monitoring loop would, without saving anything or recording history,
+/// so a new site can be tried before it is added. Responds with a
+/// [`crate::uptime::HistoryEntry`] holding the status, the response time, and why the
+/// check failed.
+///
+/// `${NAME}` references are only expanded from the config's `vars`, never from the
+/// environment, so a request cannot have the server send its environment variables to a
+/// URL of the caller's choosing.
+///
+/// # Errors
+///
+/// Responds with HTTP 400 and an [`ErrorResponse`](crate::error::ErrorResponse) if the
+/// URL or an option is invalid; field paths name the request fields, such as `url` or
+/// `assertions.status`. A site that cannot be reached is not an error: it is reported as
+/// `down` with the reason.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use axum::{Router, routing::post};
+/// use iron_shield::settings::check_site;
+///
+/// let app = Router::new()
+///     .route("/api/check", post(check_site));
+/// ```
+pub async fn check_site(
+    State(state): State<Arc<UptimeState>>,
+    Json(request): Json<CheckRequest>,
+) -> Result<impl IntoResponse> {
+    tracing::info!("Checking {} on request", request.url);
+
+    let (url, settings) = check_target(&state, &request)?;
+    let result = crate::uptime::check_now(&url, &settings).await;
+    Ok(Json(result))
+}
+
+/// The expanded URL and check settings for `request`, validated like a configured site.
+fn check_target(
+    state: &UptimeState,
+    request: &CheckRequest,
+) -> Result<(String, crate::config::CheckSettings)> {
+    let mut config = Config::default();
+    {
+        let running = state.config.load();
+        config.vars.clone_from(&running.vars);
+        config.monitoring = running.monitoring.clone();
+    }
+    config.sites = vec![Site {
+        name: "check".to_string(),
+        url: request.url.clone(),
+        category: String::new(),
+        tags: Vec::new(),
+        monitor_interval_secs: None,
+        monitor_timeout_secs: request.monitor_timeout_secs,
+        monitor_retries: request.monitor_retries,
+        monitor_user_agent: request.monitor_user_agent.clone(),
+        disabled: false,
+        icon: None,
+        order: None,
+        assertions: request.assertions.clone(),
+        uptime_percentage: 0.0,
+    }];
+
+    config
+        .interpolate_variables_with(|_| None)
+        .and_then(|()| config.validate())
+        .map_err(|e| match e {
+            // Report fields of the request rather than of the site built from it
+            crate::error::IronShieldError::ConfigInvalid(errors) => {
+                crate::error::IronShieldError::ConfigInvalid(
+                    errors
+                        .into_iter()
+                        .map(|error| crate::config::ValidationError {
+                            field: error
+                                .field
+                                .strip_prefix("sites[0].")
+                                .map_or(error.field.clone(), str::to_string),
+                            message: error.message,
+                        })
+                        .collect(),
+                )
+            }
+            e => e,
+        })?;
+
+    let site = &config.sites[0];
+    Ok((site.url.clone(), site.check_settings(&config.monitoring)))
+}
+
+/// List the backups of the configuration file, newest first
+///
+/// Responds with a JSON array of `{ "name", "created" }` objects; `name` is what
+/// [`restore_config_backup`] expects.
+///
+/// # Errors
+///
+/// Responds with HTTP 500 if the backup directory cannot be read.
+pub async fn list_config_backups(
+    State(state): State<Arc<UptimeState>>,
+) -> Result<impl IntoResponse> {
+    let backups = crate::backup::list_backups(&state.config_file_path)
+        .inspect_err(|e| error!("Error listing configuration backups: {e}"))?;
+    Ok(Json(backups))
+}
+
+/// Restore the configuration file from a backup and apply it
+///
+/// The current file is backed up before it is replaced, so a restore can be undone.
+///
+/// # Errors
+///
+/// Responds with HTTP 404 if no backup has the given name, HTTP 400 if the backup does
+/// not load as a valid configuration, and HTTP 500 if it cannot be written.
+pub async fn restore_config_backup(
+    State(state): State<Arc<UptimeState>>,
+    Path(name): Path<String>,
+) -> Result<impl IntoResponse> {
+    tracing::info!("Restoring configuration backup {name}");
+
+    let known = crate::backup::list_backups(&state.config_file_path)
+        .map(|backups| backups.iter().any(|backup| backup.name == name));
+    if matches!(known, Ok(false)) {
+        return Err(IronShieldError::BackupNotFound(name));
     }
+
+    apply_backup(&state, &name)
+        .inspect_err(|e| error!("Error restoring configuration backup: {e}"))?;
+    Ok((StatusCode::OK, "Configuration restored successfully"))
+}
+
+/// Undo the last change to the configuration file
+///
+/// Handles `POST /api/config/rollback` by restoring the newest backup, which holds the
+/// configuration as it was before the last save, restore, or rollback, so a bad save
+/// from the settings modal can be reverted in one call. Like any restore, the current
+/// file is backed up first, so rolling back twice returns to where the first rollback
+/// started.
+///
+/// # Errors
+///
+/// Responds with HTTP 404 if there are no backups to roll back to (for example when
+/// `config_backup_retention` is 0), HTTP 400 if the backup does not load as a valid
+/// configuration, and HTTP 500 if it cannot be read or written.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use axum::{Router, routing::post};
+/// use iron_shield::settings::rollback_config;
+///
+/// let app = Router::new()
+///     .route("/api/config/rollback", post(rollback_config));
+/// ```
+pub async fn rollback_config(State(state): State<Arc<UptimeState>>) -> Result<impl IntoResponse> {
+    tracing::info!("Rolling back the last configuration change");
+
+    let backup = crate::backup::list_backups(&state.config_file_path)
+        .inspect_err(|e| error!("Error listing configuration backups: {e}"))?
+        .into_iter()
+        .next()
+        .ok_or(IronShieldError::NoBackups)?;
+
+    apply_backup(&state, &backup.name)
+        .inspect_err(|e| error!("Error rolling back configuration: {e}"))?;
+    Ok((
+        StatusCode::OK,
+        format!("Configuration rolled back to backup {}", backup.name),
+    ))
+}
+
+/// Restore the backup called `name` to the config file and make it the running
+/// configuration, telling open dashboards to refresh.
+fn apply_backup(state: &UptimeState, name: &str) -> Result<()> {
+    let retention = state.config.load().config_backup_retention;
+    let restored = crate::backup::restore_backup(&state.config_file_path, name, retention)?;
+    state.config.store(restored);
+    *state.config_error.write() = None;
+    // Sending only fails when no dashboard is connected
+    let _ = state
+        .server_events
+        .send(crate::uptime::ServerEvent::ConfigUpdated);
+    Ok(())
 }
 
 #[cfg(test)]
@@ -366,8 +1472,14 @@ mod tests {
                 url: "https://example.com".to_string(),
                 category: "Web".to_string(),
                 tags: vec!["test".to_string()],
-                monitor_interval_secs: crate::config::DEFAULT_MONITOR_INTERVAL_SECS,
+                monitor_interval_secs: Some(crate::config::DEFAULT_MONITOR_INTERVAL_SECS),
+                monitor_timeout_secs: None,
+                monitor_retries: None,
+                monitor_user_agent: None,
                 disabled: false,
+                icon: None,
+                order: None,
+                assertions: None,
             }],
         };
 
@@ -395,7 +1507,10 @@ mod tests {
             sites: vec![],
         };
 
-        assert!(config_update.validate().is_err());
+        assert!(matches!(
+            config_update.validate(),
+            Err(crate::error::IronShieldError::ConfigValidation { field, .. }) if field == "clock"
+        ));
     }
 
     #[test]
@@ -409,8 +1524,14 @@ mod tests {
                 url: "https://example.com".to_string(),
                 category: "Web".to_string(),
                 tags: vec!["test".to_string()],
-                monitor_interval_secs: crate::config::DEFAULT_MONITOR_INTERVAL_SECS,
+                monitor_interval_secs: Some(crate::config::DEFAULT_MONITOR_INTERVAL_SECS),
+                monitor_timeout_secs: None,
+                monitor_retries: None,
+                monitor_user_agent: None,
                 disabled: false,
+                icon: None,
+                order: None,
+                assertions: None,
             }],
         };
 
@@ -422,6 +1543,41 @@ mod tests {
         assert!(config_update.validate().is_err());
     }
 
+    #[test]
+    fn test_config_update_validate_rejects_names_differing_only_in_case() {
+        let site = |name: &str, url: &str| SiteUpdate {
+            name: name.to_string(),
+            url: url.to_string(),
+            category: String::new(),
+            tags: Vec::new(),
+            monitor_interval_secs: Some(crate::config::DEFAULT_MONITOR_INTERVAL_SECS),
+            monitor_timeout_secs: None,
+            monitor_retries: None,
+            monitor_user_agent: None,
+            disabled: false,
+            icon: None,
+            order: None,
+            assertions: None,
+        };
+        let config_update = ConfigUpdate {
+            site_name: "Test Site".to_string(),
+            clock: "24hour".to_string(),
+            opentelemetry_endpoint: None,
+            sites: vec![
+                site("Router", "http://router.lan"),
+                site(" router ", "http://192.168.1.1"),
+            ],
+        };
+
+        let err = config_update
+            .validate()
+            .expect_err("Names differing only in case should be rejected");
+
+        assert!(err
+            .to_string()
+            .contains("sites[1].name \" router \" duplicates sites[0].name \"Router\""));
+    }
+
     #[test]
     fn test_config_update_validate_invalid_url() {
         let config_update = ConfigUpdate {
@@ -433,8 +1589,14 @@ mod tests {
                 url: "invalid-url".to_string(),
                 category: "Web".to_string(),
                 tags: vec!["test".to_string()],
-                monitor_interval_secs: crate::config::DEFAULT_MONITOR_INTERVAL_SECS,
+                monitor_interval_secs: Some(crate::config::DEFAULT_MONITOR_INTERVAL_SECS),
+                monitor_timeout_secs: None,
+                monitor_retries: None,
+                monitor_user_agent: None,
                 disabled: false,
+                icon: None,
+                order: None,
+                assertions: None,
             }],
         };
 
@@ -454,8 +1616,14 @@ mod tests {
                 url: "https://example.com".to_string(),
                 category: "Web".to_string(),
                 tags: vec!["test".to_string()],
-                monitor_interval_secs: invalid_interval,
+                monitor_interval_secs: Some(invalid_interval),
+                monitor_timeout_secs: None,
+                monitor_retries: None,
+                monitor_user_agent: None,
                 disabled: false,
+                icon: None,
+                order: None,
+                assertions: None,
             }],
         };
 
@@ -500,8 +1668,14 @@ mod tests {
             url: "https://test.com".to_string(),
             category: "Test Category".to_string(),
             tags: vec!["tag1".to_string(), "tag2".to_string()],
-            monitor_interval_secs: crate::config::DEFAULT_MONITOR_INTERVAL_SECS,
+            monitor_interval_secs: Some(crate::config::DEFAULT_MONITOR_INTERVAL_SECS),
+            monitor_timeout_secs: None,
+            monitor_retries: None,
+            monitor_user_agent: None,
             disabled: false,
+            icon: None,
+            order: None,
+            assertions: None,
         };
 
         assert_eq!(site_update.name, "Test Site");
@@ -526,4 +1700,80 @@ mod tests {
         assert_eq!(config_update.clock, "12hour");
         assert!(config_update.sites.is_empty());
     }
+
+    #[test]
+    fn preview_lists_added_removed_and_changed_sites() {
+        let site = |name: &str, url: &str| SiteUpdate {
+            name: name.to_string(),
+            url: url.to_string(),
+            category: String::new(),
+            tags: Vec::new(),
+            monitor_interval_secs: None,
+            monitor_timeout_secs: None,
+            monitor_retries: None,
+            monitor_user_agent: None,
+            disabled: false,
+            icon: None,
+            order: None,
+            assertions: None,
+        };
+        let current = ConfigUpdate {
+            site_name: "Home".to_string(),
+            clock: "24hour".to_string(),
+            opentelemetry_endpoint: None,
+            sites: vec![
+                site("NAS", "http://nas.lan"),
+                site("Router", "http://router.lan"),
+                site("Printer", "http://printer.lan"),
+            ],
+        };
+        let mut proposed = current.clone();
+        proposed.sites.remove(2);
+        proposed.sites[0].url = "http://nas.home.lan".to_string();
+        proposed.sites[0].disabled = true;
+        // Names match ignoring case, like duplicate detection
+        proposed.sites[1].name = "router".to_string();
+        proposed.sites.push(site("Grafana", "http://grafana.lan"));
+
+        let preview = ConfigPreview::between(&current, &proposed);
+
+        assert!(preview.changed_settings.is_empty());
+        assert_eq!(preview.removed_sites, vec!["Printer"]);
+        assert_eq!(preview.added_sites, vec!["Grafana"]);
+        assert_eq!(
+            preview.changed_sites,
+            vec![
+                SiteChange {
+                    name: "NAS".to_string(),
+                    fields: vec!["disabled".to_string(), "url".to_string()],
+                },
+                SiteChange {
+                    name: "router".to_string(),
+                    fields: vec!["name".to_string()],
+                },
+            ]
+        );
+        assert_eq!(
+            preview.changes,
+            vec![
+                "Remove site \"Printer\"",
+                "Change disabled, url of site \"NAS\"",
+                "Change name of site \"router\"",
+                "Add site \"Grafana\"",
+            ]
+        );
+        assert_eq!(
+            ConfigPreview::between(&current, &current),
+            ConfigPreview::default()
+        );
+    }
+
+    #[test]
+    fn if_match_accepts_quoted_unquoted_listed_and_wildcard_revisions() {
+        assert!(if_match_allows("\"abc\"", "abc"));
+        assert!(if_match_allows("abc", "abc"));
+        assert!(if_match_allows("\"old\", \"abc\"", "abc"));
+        assert!(if_match_allows("*", "abc"));
+        assert!(!if_match_allows("\"old\"", "abc"));
+    }
 }
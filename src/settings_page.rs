@@ -0,0 +1,621 @@
+//! Server-rendered settings page
+//!
+//! `/settings` lists the general settings and every site in a table of plain form
+//! fields, so the configuration can be edited on devices where the modal on the
+//! dashboard is awkward to use, or without JavaScript. The form is posted back to
+//! `/settings` and saved exactly like the settings API saves (see
+//! [`crate::settings::save_config`]); when it is rejected the page is shown again with
+//! the submitted values and each problem next to its field.
+
+use crate::config::{Config, Theme, ValidationError};
+use crate::error::{IronShieldError, Result};
+use crate::settings::{ConfigUpdate, SiteUpdate};
+use crate::uptime::UptimeState;
+use askama_axum::Template;
+use axum::{
+    extract::{Form, Query, State},
+    http::{HeaderMap, StatusCode},
+    response::{Html, IntoResponse, Redirect, Response},
+};
+use serde::Deserialize;
+use std::sync::Arc;
+use tracing::error;
+
+/// Template of the settings page
+#[derive(Template)]
+#[template(path = "settings.html")]
+pub struct SettingsTemplate {
+    /// Title of the dashboard the settings belong to
+    page_title: String,
+    /// Frontend bundle fingerprint appended to asset URLs for cache busting
+    asset_version: String,
+    /// Color scheme, chosen as for the dashboard (see [`crate::index::THEME_COOKIE`])
+    theme: Theme,
+    /// [`Config::revision`] the form was filled from, checked when it is saved
+    revision: String,
+    /// Values shown in the form
+    form: SettingsForm,
+    /// Names of sites loaded from `include`d files, which are edited in those files
+    included_sites: Vec<String>,
+    /// Whether the configuration was loaded from a directory and cannot be saved here
+    read_only: bool,
+    /// Whether API keys are configured, so saving the form needs one
+    requires_api_key: bool,
+    /// Confirmation shown after a successful save
+    notice: Option<String>,
+    /// Why the last save was rejected
+    error_message: Option<String>,
+    /// Every rejected field, with paths such as `sites[2].url` or `new_site.name`
+    errors: Vec<ValidationError>,
+    /// Why the config file on disk was rejected, shown while the last valid one is in use
+    config_error: Option<String>,
+}
+
+impl SettingsTemplate {
+    /// The message for the general setting `field`, if it was rejected.
+    fn error(&self, field: &str) -> Option<&str> {
+        self.errors
+            .iter()
+            .find(|error| error.field == field)
+            .map(|error| error.message.as_str())
+    }
+
+    /// Prefix of the field names of the site row at `index`.
+    #[allow(clippy::unused_self)]
+    fn row_prefix(&self, index: &usize) -> String {
+        format!("sites[{index}]")
+    }
+
+    /// The row for adding a site.
+    fn new_site(&self) -> &SiteRow {
+        &self.form.new_site
+    }
+}
+
+/// The values of the settings form, as typed
+#[derive(Debug, Clone, Default, PartialEq)]
+struct SettingsForm {
+    site_name: String,
+    clock: String,
+    opentelemetry_endpoint: String,
+    /// One row per site in the main config file, in config order
+    sites: Vec<SiteRow>,
+    /// The empty row for adding a site
+    new_site: SiteRow,
+}
+
+/// One row of the sites table
+#[derive(Debug, Clone, Default, PartialEq)]
+struct SiteRow {
+    name: String,
+    url: String,
+    category: String,
+    /// Comma-separated tags
+    tags: String,
+    /// Seconds between checks, or empty to inherit `monitoring.interval_secs`
+    monitor_interval_secs: String,
+    disabled: bool,
+    /// Whether the site is removed when the form is saved
+    remove: bool,
+    /// Rejected fields of this row as `(field, message)`, such as `("url", "cannot be empty")`
+    errors: Vec<(String, String)>,
+}
+
+impl SiteRow {
+    /// The message for `field` of this row, if it was rejected.
+    fn error(&self, field: &str) -> Option<&str> {
+        self.errors
+            .iter()
+            .find(|(name, _)| name == field)
+            .map(|(_, message)| message.as_str())
+    }
+
+    /// Applies the row to `site`, keeping the fields the page does not show.
+    fn apply_to(&self, site: &mut SiteUpdate, field: &str, errors: &mut Vec<ValidationError>) {
+        site.name = self.name.trim().to_string();
+        site.url = self.url.trim().to_string();
+        site.category = self.category.trim().to_string();
+        site.tags = self
+            .tags
+            .split(',')
+            .map(str::trim)
+            .filter(|tag| !tag.is_empty())
+            .map(str::to_string)
+            .collect();
+        site.disabled = self.disabled;
+        let interval = self.monitor_interval_secs.trim();
+        if interval.is_empty() {
+            site.monitor_interval_secs = None;
+        } else if let Ok(secs) = interval.parse() {
+            site.monitor_interval_secs = Some(secs);
+        } else {
+            errors.push(ValidationError {
+                field: format!("{field}.monitor_interval_secs"),
+                message: "must be a whole number of seconds".to_string(),
+            });
+        }
+    }
+}
+
+impl From<&SiteUpdate> for SiteRow {
+    fn from(site: &SiteUpdate) -> Self {
+        SiteRow {
+            name: site.name.clone(),
+            url: site.url.clone(),
+            category: site.category.clone(),
+            tags: site.tags.join(", "),
+            monitor_interval_secs: site
+                .monitor_interval_secs
+                .map(|secs| secs.to_string())
+                .unwrap_or_default(),
+            disabled: site.disabled,
+            remove: false,
+            errors: Vec::new(),
+        }
+    }
+}
+
+impl From<&ConfigUpdate> for SettingsForm {
+    fn from(update: &ConfigUpdate) -> Self {
+        SettingsForm {
+            site_name: update.site_name.clone(),
+            clock: update.clock.clone(),
+            opentelemetry_endpoint: update.opentelemetry_endpoint.clone().unwrap_or_default(),
+            sites: update.sites.iter().map(SiteRow::from).collect(),
+            new_site: SiteRow::default(),
+        }
+    }
+}
+
+impl SettingsForm {
+    /// Read the submitted form fields.
+    ///
+    /// Site rows are named `sites[{index}].{field}` and the new site
+    /// `new_site.{field}`; unchecked checkboxes are simply missing.
+    fn from_fields(fields: &[(String, String)]) -> Self {
+        let mut form = SettingsForm::default();
+        for (key, value) in fields {
+            let (row, field) = if let Some(field) = key.strip_prefix("new_site.") {
+                (&mut form.new_site, field)
+            } else if let Some((index, field)) = site_path(key) {
+                if form.sites.len() <= index {
+                    form.sites.resize_with(index + 1, SiteRow::default);
+                }
+                (&mut form.sites[index], field)
+            } else {
+                match key.as_str() {
+                    "site_name" => form.site_name.clone_from(value),
+                    "clock" => form.clock.clone_from(value),
+                    "opentelemetry_endpoint" => form.opentelemetry_endpoint.clone_from(value),
+                    _ => {}
+                }
+                continue;
+            };
+            match field {
+                "name" => row.name.clone_from(value),
+                "url" => row.url.clone_from(value),
+                "category" => row.category.clone_from(value),
+                "tags" => row.tags.clone_from(value),
+                "monitor_interval_secs" => row.monitor_interval_secs.clone_from(value),
+                "disabled" => row.disabled = true,
+                "remove" => row.remove = true,
+                _ => {}
+            }
+        }
+        form
+    }
+
+    /// Whether anything was typed into the new site row.
+    fn adds_site(&self) -> bool {
+        !self.new_site.name.trim().is_empty() || !self.new_site.url.trim().is_empty()
+    }
+
+    /// Apply the form to `base`, the editable settings it was filled from.
+    ///
+    /// Sites marked for removal are left out and the new site is appended. Fields the
+    /// page does not show, such as icons and assertions, keep their values.
+    ///
+    /// # Errors
+    ///
+    /// Returns `IronShieldError::ConfigInvalid` for values that are not numbers where
+    /// numbers are expected.
+    fn apply_to(&self, base: &ConfigUpdate) -> Result<ConfigUpdate> {
+        let mut errors = Vec::new();
+        let mut update = ConfigUpdate {
+            site_name: self.site_name.trim().to_string(),
+            clock: self.clock.clone(),
+            opentelemetry_endpoint: Some(self.opentelemetry_endpoint.trim().to_string())
+                .filter(|endpoint| !endpoint.is_empty()),
+            sites: Vec::new(),
+        };
+        for (index, (row, site)) in self.sites.iter().zip(&base.sites).enumerate() {
+            if row.remove {
+                continue;
+            }
+            let mut site = site.clone();
+            row.apply_to(&mut site, &format!("sites[{index}]"), &mut errors);
+            update.sites.push(site);
+        }
+        if self.adds_site() {
+            let mut site = SiteUpdate {
+                name: String::new(),
+                url: String::new(),
+                category: String::new(),
+                tags: Vec::new(),
+                monitor_interval_secs: None,
+                monitor_timeout_secs: None,
+                monitor_retries: None,
+                monitor_user_agent: None,
+                disabled: false,
+                icon: None,
+                order: None,
+                assertions: None,
+            };
+            self.new_site.apply_to(&mut site, "new_site", &mut errors);
+            update.sites.push(site);
+        }
+
+        if errors.is_empty() {
+            Ok(update)
+        } else {
+            Err(IronShieldError::ConfigInvalid(errors))
+        }
+    }
+
+    /// Rewrite the site paths of `error`, which refer to the sites returned by
+    /// [`SettingsForm::apply_to`], to the rows of the form they came from.
+    fn with_form_paths(&self, error: IronShieldError) -> IronShieldError {
+        let IronShieldError::ConfigInvalid(errors) = error else {
+            return error;
+        };
+        let kept: Vec<usize> = (0..self.sites.len())
+            .filter(|&row| !self.sites[row].remove)
+            .collect();
+        // Messages can refer to other sites too, as in "duplicates sites[0].name"
+        let rewrite = |text: &str| {
+            let mut rewritten = String::with_capacity(text.len());
+            let mut rest = text;
+            while let Some(start) = rest.find("sites[") {
+                rewritten.push_str(&rest[..start]);
+                let after = &rest[start + "sites[".len()..];
+                match after
+                    .split_once(']')
+                    .and_then(|(index, tail)| Some((index.parse::<usize>().ok()?, tail)))
+                {
+                    Some((index, tail)) => {
+                        match kept.get(index) {
+                            Some(row) => rewritten.push_str(&format!("sites[{row}]")),
+                            None => rewritten.push_str("new_site"),
+                        }
+                        rest = tail;
+                    }
+                    None => {
+                        rewritten.push_str("sites[");
+                        rest = after;
+                    }
+                }
+            }
+            rewritten.push_str(rest);
+            rewritten
+        };
+        let errors = errors
+            .into_iter()
+            .map(|error| ValidationError {
+                field: rewrite(&error.field),
+                message: rewrite(&error.message),
+            })
+            .collect();
+        IronShieldError::ConfigInvalid(errors)
+    }
+
+    /// Show each of `errors` next to its row field, when it has a form path such as
+    /// `sites[2].url` or `new_site.name`.
+    fn attach_errors(&mut self, errors: &[ValidationError]) {
+        for error in errors {
+            let (row, field) = if let Some(field) = error.field.strip_prefix("new_site.") {
+                (Some(&mut self.new_site), field)
+            } else if let Some((index, field)) = site_path(&error.field) {
+                (self.sites.get_mut(index), field)
+            } else {
+                continue;
+            };
+            if let Some(row) = row {
+                row.errors.push((field.to_string(), error.message.clone()));
+            }
+        }
+    }
+}
+
+/// The index and field of a path such as `sites[2].url`.
+fn site_path(path: &str) -> Option<(usize, &str)> {
+    let (index, field) = path.strip_prefix("sites[")?.split_once("].")?;
+    Some((index.parse().ok()?, field))
+}
+
+/// Query string of `GET /settings`
+#[derive(Debug, Default, Deserialize)]
+pub struct SettingsQuery {
+    /// Set after a successful save to show a confirmation
+    #[serde(default)]
+    saved: bool,
+}
+
+/// Render the settings page
+///
+/// Handles `GET /settings` with the current configuration. Secret references and
+/// `${NAME}` variables are shown as written in the config file.
+///
+/// # Errors
+///
+/// Responds with HTTP 500 if the page cannot be rendered.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use axum::{Router, routing::get};
+/// use iron_shield::settings_page::{settings_page, save_settings_page};
+///
+/// let app = Router::new()
+///     .route("/settings", get(settings_page).post(save_settings_page));
+/// ```
+pub async fn settings_page(
+    State(state): State<Arc<UptimeState>>,
+    Query(query): Query<SettingsQuery>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    tracing::debug!("Generating settings page");
+
+    let config = state.config.load();
+    let form = SettingsForm::from(&ConfigUpdate::from(&config.for_persistence()));
+    let mut page = settings_template(&state, &config, form, &headers);
+    if query.saved {
+        page.notice = Some("Settings saved.".to_string());
+    }
+    render(StatusCode::OK, &page)
+}
+
+/// Save the settings page
+///
+/// Handles the form posted by [`settings_page`]. The form is applied to the
+/// configuration it was filled from, then validated, saved, and activated like a save
+/// through the settings API, and the browser is redirected back to the page. While API
+/// keys are configured the form must include one in its `api_key` field, since a
+/// browser form cannot send the header the API expects.
+///
+/// # Errors
+///
+/// When the form is rejected the page is shown again with the submitted values and the
+/// problems next to the fields: with HTTP 400 for invalid values, HTTP 401 without a
+/// valid API key, HTTP 409 if the configuration was changed since the page was loaded,
+/// and HTTP 500 if it cannot be saved.
+pub async fn save_settings_page(
+    State(state): State<Arc<UptimeState>>,
+    headers: HeaderMap,
+    Form(fields): Form<Vec<(String, String)>>,
+) -> impl IntoResponse {
+    tracing::info!("Saving configuration from the settings page");
+
+    let mut form = SettingsForm::from_fields(&fields);
+    let revision = fields
+        .iter()
+        .find(|(key, _)| key == "revision")
+        .map(|(_, value)| format!("\"{value}\""));
+    let api_key = fields
+        .iter()
+        .find(|(key, _)| key == "api_key")
+        .map(|(_, value)| value.as_str());
+    let result = crate::settings::update_config(&state, revision.as_deref(), |config| {
+        if !config.api_keys.is_empty()
+            && api_key
+                .and_then(|key| crate::api_keys::find(&config.api_keys, key))
+                .is_none()
+        {
+            return Err(IronShieldError::Unauthorized);
+        }
+        let base = ConfigUpdate::from(&config.for_persistence());
+        let update = form.apply_to(&base)?;
+        let removed: Vec<&str> = form
+            .sites
+            .iter()
+            .zip(&base.sites)
+            .filter(|(row, _)| row.remove)
+            .map(|(_, site)| site.name.as_str())
+            .collect();
+        for group in &mut config.groups {
+            group.sites.retain(|site| !removed.contains(&site.as_str()));
+        }
+        for dashboard in &mut config.dashboards {
+            dashboard
+                .sites
+                .retain(|site| !removed.contains(&site.as_str()));
+        }
+        *config = update
+            .validate_against(config.clone())
+            .map_err(|e| form.with_form_paths(e))?;
+        Ok(())
+    });
+
+    let e = match result {
+        Ok(_) => return Redirect::to("/settings?saved=true").into_response(),
+        Err(e) => e,
+    };
+    error!("Error saving configuration from the settings page: {e}");
+
+    let config = state.config.load();
+    let status = e.status();
+    let errors = match &e {
+        IronShieldError::ConfigInvalid(errors) => errors.clone(),
+        IronShieldError::ConfigValidation { field, message } => vec![ValidationError {
+            field: field.clone(),
+            message: message.clone(),
+        }],
+        _ => Vec::new(),
+    };
+    form.attach_errors(&errors);
+    let mut page = settings_template(&state, &config, form, &headers);
+    page.error_message = Some(match &e {
+        IronShieldError::RevisionMismatch { .. } => {
+            "The configuration was changed since this page was loaded. Reload the page to see the changes, then make your edits again.".to_string()
+        }
+        IronShieldError::Unauthorized => {
+            "The settings were not saved. Enter a valid API key and save again.".to_string()
+        }
+        IronShieldError::ConfigInvalid(_) | IronShieldError::ConfigValidation { .. } => {
+            "The settings were not saved. Fix the highlighted fields and save again.".to_string()
+        }
+        e => format!("The settings were not saved: {e}"),
+    });
+    if let Some(revision) = revision {
+        // Keep the revision the form was filled from, so a conflict is not overwritten
+        page.revision = revision.trim_matches('"').to_string();
+    }
+    page.errors = errors;
+    render(status, &page)
+}
+
+/// The settings page for `config` showing `form`.
+fn settings_template(
+    state: &UptimeState,
+    config: &Config,
+    form: SettingsForm,
+    headers: &HeaderMap,
+) -> SettingsTemplate {
+    SettingsTemplate {
+        page_title: config.site_name.clone(),
+        asset_version: state.asset_version.clone(),
+        theme: crate::index::page_theme(config, headers, &state.preferences_key),
+        revision: config.revision(),
+        form,
+        included_sites: config.included_sites.clone(),
+        read_only: state.config_file_path.is_dir(),
+        requires_api_key: !config.api_keys.is_empty(),
+        notice: None,
+        error_message: None,
+        errors: Vec::new(),
+        config_error: state.config_error.read().clone(),
+    }
+}
+
+/// Render `page` with `status`.
+fn render(status: StatusCode, page: &SettingsTemplate) -> Response {
+    match page.render().map_err(IronShieldError::TemplateRender) {
+        Ok(html) => (status, Html(html)).into_response(),
+        Err(e) => {
+            error!("{e}");
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Template rendering error",
+            )
+                .into_response()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fields(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(key, value)| ((*key).to_string(), (*value).to_string()))
+            .collect()
+    }
+
+    fn base() -> ConfigUpdate {
+        ConfigUpdate {
+            site_name: "Home".to_string(),
+            clock: "24hour".to_string(),
+            opentelemetry_endpoint: None,
+            sites: ["NAS", "Router"]
+                .iter()
+                .map(|name| SiteUpdate {
+                    name: (*name).to_string(),
+                    url: format!("http://{}.lan", name.to_lowercase()),
+                    category: String::new(),
+                    tags: Vec::new(),
+                    monitor_interval_secs: None,
+                    monitor_timeout_secs: None,
+                    monitor_retries: None,
+                    monitor_user_agent: None,
+                    disabled: false,
+                    icon: Some("server".to_string()),
+                    order: None,
+                    assertions: None,
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn applies_rows_removals_and_the_new_site() {
+        let form = SettingsForm::from_fields(&fields(&[
+            ("site_name", " Lab "),
+            ("clock", "12hour"),
+            ("opentelemetry_endpoint", ""),
+            ("sites[0].name", "NAS"),
+            ("sites[0].url", "http://nas.lan"),
+            ("sites[0].tags", "storage, , home"),
+            ("sites[0].monitor_interval_secs", "30"),
+            ("sites[0].disabled", "on"),
+            ("sites[1].name", "Router"),
+            ("sites[1].url", "http://router.lan"),
+            ("sites[1].remove", "on"),
+            ("new_site.name", "Printer"),
+            ("new_site.url", "http://printer.lan"),
+        ]));
+
+        let update = form.apply_to(&base()).expect("Form should apply");
+
+        assert_eq!(update.site_name, "Lab");
+        assert_eq!(update.clock, "12hour");
+        assert_eq!(update.opentelemetry_endpoint, None);
+        let names: Vec<&str> = update.sites.iter().map(|site| site.name.as_str()).collect();
+        assert_eq!(names, vec!["NAS", "Printer"]);
+        assert_eq!(update.sites[0].tags, vec!["storage", "home"]);
+        assert_eq!(update.sites[0].monitor_interval_secs, Some(30));
+        assert!(update.sites[0].disabled);
+        // Fields the page does not show are kept
+        assert_eq!(update.sites[0].icon.as_deref(), Some("server"));
+        assert!(!update.sites[1].disabled);
+    }
+
+    #[test]
+    fn maps_errors_back_to_form_rows() {
+        let mut form = SettingsForm::from_fields(&fields(&[
+            ("sites[0].name", "NAS"),
+            ("sites[0].remove", "on"),
+            ("sites[1].name", "Router"),
+            ("sites[1].monitor_interval_secs", "soon"),
+            ("new_site.name", "Printer"),
+        ]));
+        let Err(err) = form.apply_to(&base()) else {
+            panic!("An interval that is not a number should be rejected");
+        };
+        assert_eq!(
+            err.to_string(),
+            "Invalid configuration: sites[1].monitor_interval_secs must be a whole number of seconds"
+        );
+
+        let error = |field: &str| ValidationError {
+            field: field.to_string(),
+            message: "cannot be empty".to_string(),
+        };
+        let IronShieldError::ConfigInvalid(errors) =
+            form.with_form_paths(IronShieldError::ConfigInvalid(vec![
+                error("sites[0].url"),
+                error("sites[1].url"),
+                error("site_name"),
+            ]))
+        else {
+            panic!("Errors should stay a validation failure");
+        };
+        form.attach_errors(&errors);
+
+        let fields: Vec<&str> = errors.iter().map(|error| error.field.as_str()).collect();
+        assert_eq!(fields, vec!["sites[1].url", "new_site.url", "site_name"]);
+        assert_eq!(form.sites[1].error("url"), Some("cannot be empty"));
+        assert_eq!(form.new_site.error("url"), Some("cannot be empty"));
+        assert_eq!(form.sites[0].error("url"), None);
+    }
+}
@@ -0,0 +1,256 @@
+//! StatsD export
+//!
+//! For monitoring stacks built on Graphite or the Datadog agent rather than OTLP, the
+//! config's `statsd` block names a StatsD daemon that every uptime cycle is reported to
+//! over UDP:
+//!
+//! ```json5
+//! statsd: {
+//!     host: "127.0.0.1",
+//!     port: 8125,
+//!     prefix: "iron_shield",
+//! }
+//! ```
+//!
+//! Each monitored site with a completed check is sent as:
+//!
+//! - `{prefix}.site.{name}.up` - gauge of 1 if the site is up, 0 if it is down
+//! - `{prefix}.site.{name}.response_time` - timer of the latest response time, in ms
+//!
+//! where `{name}` is the site name in lowercase with every character other than ASCII
+//! letters, digits, `-` and `_` replaced by `_`, since StatsD separates buckets with `.`
+//! and fields with `:` and `|`. UDP is fire-and-forget, so an unreachable daemon only
+//! shows up as a debug log line.
+
+use crate::uptime::{snapshot_current_histories, UptimeHistory, UptimeState, UptimeStatus};
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+/// Port StatsD daemons listen on when `port` is not set
+pub const DEFAULT_PORT: u16 = 8125;
+/// Prefix of the metric names when `prefix` is not set
+pub const DEFAULT_PREFIX: &str = "iron_shield";
+
+/// Largest datagram sent, which fits the payload of an Ethernet frame; longer reports
+/// are split over several datagrams
+const MAX_DATAGRAM_BYTES: usize = 1432;
+
+/// Returns the default port of the StatsD daemon.
+#[must_use]
+pub fn default_port() -> u16 {
+    DEFAULT_PORT
+}
+
+/// Returns the default prefix of the metric names.
+#[must_use]
+pub fn default_prefix() -> String {
+    DEFAULT_PREFIX.to_string()
+}
+
+/// Settings of the StatsD export
+///
+/// # Fields
+///
+/// * `host` - Host name or IP address of the StatsD daemon
+/// * `port` - UDP port of the daemon
+/// * `prefix` - Prepended to every metric name, followed by a `.`
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+pub struct Statsd {
+    /// Host name or IP address of the daemon
+    pub host: String,
+    /// UDP port of the daemon
+    #[serde(default = "default_port")]
+    pub port: u16,
+    /// Prefix of the metric names
+    #[serde(default = "default_prefix")]
+    pub prefix: String,
+}
+
+impl Statsd {
+    /// Problems with these settings, as pairs of the field name and what is wrong with it
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use iron_shield::statsd::Statsd;
+    ///
+    /// let statsd = Statsd {
+    ///     host: "127.0.0.1".to_string(),
+    ///     port: 8125,
+    ///     prefix: "iron_shield".to_string(),
+    /// };
+    /// assert!(statsd.validate().is_empty());
+    ///
+    /// let portless = Statsd { port: 0, ..statsd };
+    /// assert_eq!(portless.validate()[0].0, "port");
+    /// ```
+    #[must_use]
+    pub fn validate(&self) -> Vec<(String, String)> {
+        let mut problems = Vec::new();
+        if self.host.trim().is_empty() {
+            problems.push(("host".to_string(), "cannot be empty".to_string()));
+        }
+        if self.port == 0 {
+            problems.push((
+                "port".to_string(),
+                "must be between 1 and 65535".to_string(),
+            ));
+        }
+        if self.prefix.contains([':', '|', '@']) {
+            problems.push((
+                "prefix".to_string(),
+                format!("\"{}\" cannot contain ':', '|' or '@'", self.prefix),
+            ));
+        }
+        problems
+    }
+}
+
+/// Send the latest uptime snapshot to the configured StatsD daemon, if any.
+///
+/// Failures are logged at debug level and otherwise ignored, like lost UDP datagrams.
+pub async fn send_uptime_snapshot(state: &UptimeState) {
+    let Some(settings) = state.config.load().statsd.clone() else {
+        return;
+    };
+
+    let lines = uptime_lines(&settings.prefix, &snapshot_current_histories(state));
+    if let Err(err) = send(&settings, &lines).await {
+        debug!(
+            "Failed to send StatsD metrics to {}:{}: {err}",
+            settings.host, settings.port
+        );
+    }
+}
+
+/// The StatsD lines reporting `snapshot`, leaving out sites that are paused or have not
+/// finished a check.
+fn uptime_lines(prefix: &str, snapshot: &[UptimeHistory]) -> Vec<String> {
+    let prefix = prefix.trim().trim_end_matches('.');
+    let prefix = if prefix.is_empty() {
+        String::new()
+    } else {
+        format!("{prefix}.")
+    };
+
+    let mut lines = Vec::new();
+    for history in snapshot {
+        let up = match history.status {
+            UptimeStatus::Up => 1,
+            UptimeStatus::Down => 0,
+            UptimeStatus::Loading | UptimeStatus::Disabled => continue,
+        };
+        let bucket = format!("{prefix}site.{}", bucket_name(&history.site_id));
+        lines.push(format!("{bucket}.up:{up}|g"));
+        if let Some(ms) = history.response_time_ms {
+            lines.push(format!("{bucket}.response_time:{ms}|ms"));
+        }
+    }
+    lines
+}
+
+/// `name` as a single StatsD bucket segment, which is also a valid Graphite path
+/// segment.
+pub(crate) fn bucket_name(name: &str) -> String {
+    name.trim()
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c.to_ascii_lowercase()
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// Send `lines` to the daemon, packing as many into each datagram as fit.
+async fn send(settings: &Statsd, lines: &[String]) -> std::io::Result<()> {
+    if lines.is_empty() {
+        return Ok(());
+    }
+
+    let socket = tokio::net::UdpSocket::bind("0.0.0.0:0").await?;
+    socket
+        .connect((settings.host.trim(), settings.port))
+        .await?;
+    for datagram in datagrams(lines) {
+        socket.send(datagram.as_bytes()).await?;
+    }
+    Ok(())
+}
+
+/// Join `lines` into newline-separated datagrams of at most [`MAX_DATAGRAM_BYTES`];
+/// a longer line is sent on its own.
+fn datagrams(lines: &[String]) -> Vec<String> {
+    let mut datagrams = Vec::new();
+    let mut current = String::new();
+    for line in lines {
+        if !current.is_empty() && current.len() + 1 + line.len() > MAX_DATAGRAM_BYTES {
+            datagrams.push(std::mem::take(&mut current));
+        }
+        if !current.is_empty() {
+            current.push('\n');
+        }
+        current.push_str(line);
+    }
+    if !current.is_empty() {
+        datagrams.push(current);
+    }
+    datagrams
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn uptime_lines_report_checked_sites() {
+        let history = |site: &str, status, response_time_ms| UptimeHistory {
+            site_id: site.to_string(),
+            status,
+            timestamp: 2,
+            history: Vec::new(),
+            uptime_percentage: 50.0,
+            response_time_ms,
+            max_history_entries: crate::uptime::MAX_HISTORY_ENTRIES,
+            latency_ms: Vec::new(),
+            down_since: None,
+            last_checked: None,
+            last_change: None,
+        };
+        let lines = uptime_lines(
+            "homelab.",
+            &[
+                history("Plex Media", UptimeStatus::Up, Some(120)),
+                history("NAS", UptimeStatus::Down, None),
+                history("Backup", UptimeStatus::Disabled, None),
+            ],
+        );
+
+        assert_eq!(
+            lines,
+            [
+                "homelab.site.plex_media.up:1|g",
+                "homelab.site.plex_media.response_time:120|ms",
+                "homelab.site.nas.up:0|g",
+            ]
+        );
+        assert_eq!(
+            uptime_lines("", &[history("NAS", UptimeStatus::Down, None)]),
+            ["site.nas.up:0|g"]
+        );
+    }
+
+    #[test]
+    fn datagrams_stay_below_the_size_limit() {
+        let line = "x".repeat(600);
+        let lines = vec![line.clone(), line.clone(), line];
+
+        let datagrams = datagrams(&lines);
+
+        assert_eq!(datagrams.len(), 2);
+        assert_eq!(datagrams[0].len(), 1201);
+        assert!(datagrams.iter().all(|d| d.len() <= MAX_DATAGRAM_BYTES));
+    }
+}
@@ -0,0 +1,184 @@
+//! systemd integration
+//!
+//! Under a `Type=notify` service, systemd sets `NOTIFY_SOCKET` and waits for the server
+//! to report that it is ready. The server sends `READY=1` once the config is loaded and
+//! the listener is bound, and `STOPPING=1` when it starts shutting down.
+//!
+//! With `WatchdogSec=` set, systemd also sets `WATCHDOG_USEC` and restarts the service
+//! if it stops hearing from it. The server then sends `WATCHDOG=1` at half that
+//! interval for as long as the uptime monitor keeps going: once it has finished no tick
+//! for [`MONITOR_STALL`] while no check is in flight, the pings stop, so a stalled
+//! runtime or monitor gets the service restarted:
+//!
+//! ```ini
+//! [Service]
+//! Type=notify
+//! ExecStart=/usr/local/bin/iron_shield 3000 /etc/iron-shield/config.json5
+//! WatchdogSec=30
+//! ```
+//!
+//! Without `NOTIFY_SOCKET`, as when not started by systemd, nothing is sent.
+
+use crate::uptime::UptimeState;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Send `state`, such as `READY=1`, to the systemd notification socket, returning
+/// whether it was sent.
+///
+/// Failures are logged rather than returned, since the server runs the same either way.
+pub fn notify(state: &str) -> bool {
+    let Some(socket) = std::env::var_os("NOTIFY_SOCKET") else {
+        return false;
+    };
+    match send(&socket, state) {
+        Ok(()) => true,
+        Err(e) => {
+            tracing::warn!("Failed to notify systemd of {state}: {e}");
+            false
+        }
+    }
+}
+
+#[cfg(unix)]
+fn send(socket: &std::ffi::OsStr, state: &str) -> std::io::Result<()> {
+    use std::os::unix::ffi::OsStrExt;
+    use std::os::unix::net::UnixDatagram;
+
+    let datagram = UnixDatagram::unbound()?;
+    // A leading `@` names a socket in Linux's abstract namespace
+    match socket.as_bytes().strip_prefix(b"@") {
+        #[cfg(target_os = "linux")]
+        Some(name) => {
+            use std::os::linux::net::SocketAddrExt;
+            let address = std::os::unix::net::SocketAddr::from_abstract_name(name)?;
+            datagram.send_to_addr(state.as_bytes(), &address)?;
+        }
+        _ => {
+            datagram.send_to(state.as_bytes(), socket)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn send(_socket: &std::ffi::OsStr, _state: &str) -> std::io::Result<()> {
+    Err(std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        "systemd notifications need Unix sockets",
+    ))
+}
+
+/// How often to send `WATCHDOG=1`, if systemd's watchdog is enabled for this process
+pub fn watchdog_interval() -> Option<Duration> {
+    let usec = std::env::var("WATCHDOG_USEC").ok()?;
+    let pid = std::env::var("WATCHDOG_PID").ok();
+    ping_interval(&usec, pid.as_deref(), std::process::id())
+}
+
+/// Half the watchdog timeout of `usec` microseconds, unless `watchdog_pid` names a
+/// process other than `own_pid`
+///
+/// # Examples
+///
+/// ```
+/// use iron_shield::systemd::ping_interval;
+/// use std::time::Duration;
+///
+/// assert_eq!(ping_interval("30000000", None, 42), Some(Duration::from_secs(15)));
+/// assert_eq!(ping_interval("30000000", Some("42"), 42), Some(Duration::from_secs(15)));
+/// assert_eq!(ping_interval("30000000", Some("7"), 42), None);
+/// assert_eq!(ping_interval("0", None, 42), None);
+/// ```
+#[must_use]
+pub fn ping_interval(usec: &str, watchdog_pid: Option<&str>, own_pid: u32) -> Option<Duration> {
+    if let Some(pid) = watchdog_pid {
+        if pid.trim().parse::<u32>().ok()? != own_pid {
+            return None;
+        }
+    }
+    let usec: u64 = usec.trim().parse().ok().filter(|usec| *usec > 0)?;
+    Some(Duration::from_micros(usec / 2))
+}
+
+/// How long the uptime monitor may go without finishing a tick, while no check is in
+/// flight, before the watchdog pings stop
+pub const MONITOR_STALL: Duration = Duration::from_secs(5);
+
+/// When the uptime monitor was last seen making progress
+struct Progress {
+    ticks: u64,
+    at: Instant,
+}
+
+impl Progress {
+    /// Note the monitor's state at `now`, returning whether it has made progress within
+    /// [`MONITOR_STALL`]. A tick waits for its checks, which are bounded by their
+    /// timeouts, so a check in flight counts as progress.
+    fn observe(&mut self, ticks: u64, checking: bool, now: Instant) -> bool {
+        if ticks != self.ticks || checking {
+            self.ticks = ticks;
+            self.at = now;
+        }
+        now.duration_since(self.at) <= MONITOR_STALL
+    }
+}
+
+/// Send `WATCHDOG=1` every `interval` while the uptime monitor makes progress, until
+/// shutdown.
+pub async fn run_watchdog(state: Arc<UptimeState>, interval: Duration) {
+    let mut ticker = tokio::time::interval(interval);
+    let mut progress = Progress {
+        ticks: state.self_monitor.ticks(),
+        at: Instant::now(),
+    };
+    loop {
+        tokio::select! {
+            () = state.shutdown_token.cancelled() => return,
+            _ = ticker.tick() => {}
+        }
+        let monitor = &state.self_monitor;
+        if progress.observe(monitor.ticks(), monitor.checking(), Instant::now()) {
+            notify("WATCHDOG=1");
+        } else {
+            tracing::warn!("Uptime monitor has stalled; withholding the systemd watchdog ping");
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn send_writes_state_to_socket() {
+        let dir = tempfile::tempdir().expect("Failed to create temporary directory");
+        let path = dir.path().join("notify.sock");
+        let receiver =
+            std::os::unix::net::UnixDatagram::bind(&path).expect("Failed to bind socket");
+
+        send(path.as_os_str(), "READY=1").expect("Failed to send notification");
+
+        let mut buf = [0; 64];
+        let len = receiver.recv(&mut buf).expect("Failed to receive");
+        assert_eq!(&buf[..len], b"READY=1");
+    }
+
+    #[test]
+    fn watchdog_progress_needs_ticks_or_checks_in_flight() {
+        let start = Instant::now();
+        let later = |secs| start + Duration::from_secs(secs);
+        let mut progress = Progress {
+            ticks: 0,
+            at: start,
+        };
+
+        assert!(progress.observe(0, false, later(5)));
+        assert!(!progress.observe(0, false, later(6)));
+        assert!(progress.observe(1, false, later(7)));
+        // A slow check holds up the tick without stalling the monitor
+        assert!(progress.observe(1, true, later(20)));
+        assert!(progress.observe(1, false, later(25)));
+        assert!(!progress.observe(1, false, later(26)));
+    }
+}
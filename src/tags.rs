@@ -0,0 +1,312 @@
+//! Tag management
+//!
+//! Tags are free-form labels on each site, so keeping them consistent across many sites
+//! by editing the whole configuration is tedious. These endpoints change a tag on every
+//! site at once:
+//!
+//! - `GET /api/tags` lists the tags in use with the number of sites having each
+//! - `PATCH /api/tags/{tag}` with `{ "name": "media" }` renames a tag; renaming it to a
+//!   tag a site already has merges the two on that site
+//! - `POST /api/tags/merge` with `{ "tags": ["k8s", "kube"], "into": "kubernetes" }`
+//!   replaces several tags with one
+//! - `DELETE /api/tags/{tag}` removes a tag from every site
+//!
+//! Changes are saved like any other settings API edit. Sites from `include`d files are
+//! counted but not changed, since they are edited in their own files.
+
+use crate::config::{Config, Site};
+use crate::error::{IronShieldError, Result};
+use crate::settings::update_config;
+use crate::uptime::UptimeState;
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Json},
+};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use tracing::{error, info};
+
+/// A tag and how many sites have it
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct TagUsage {
+    /// The tag
+    pub name: String,
+    /// Number of sites with the tag
+    pub sites: usize,
+}
+
+/// Body of a request renaming a tag
+#[derive(Debug, Deserialize)]
+pub struct TagRename {
+    /// The tag's new name
+    pub name: String,
+}
+
+/// Body of a request merging tags
+#[derive(Debug, Deserialize)]
+pub struct TagMerge {
+    /// Tags to replace
+    pub tags: Vec<String>,
+    /// Tag that replaces them
+    pub into: String,
+}
+
+/// The tags of `sites` with their usage counts, sorted by name
+///
+/// # Examples
+///
+/// ```
+/// use iron_shield::config::Site;
+/// use iron_shield::tags::usage;
+///
+/// let site = |tags: &[&str]| Site {
+///     name: "Example".to_string(),
+///     url: "https://example.com".to_string(),
+///     category: String::new(),
+///     tags: tags.iter().map(|tag| tag.to_string()).collect(),
+///     monitor_interval_secs: None,
+///     monitor_timeout_secs: None,
+///     monitor_retries: None,
+///     monitor_user_agent: None,
+///     disabled: false,
+///     icon: None,
+///     order: None,
+///     assertions: None,
+///     uptime_percentage: 0.0,
+/// };
+///
+/// let tags = usage(&[site(&["media", "home"]), site(&["media"])]);
+/// assert_eq!(tags[0].name, "home");
+/// assert_eq!(tags[1].sites, 2);
+/// ```
+#[must_use]
+pub fn usage(sites: &[Site]) -> Vec<TagUsage> {
+    let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+    for site in sites {
+        for tag in &site.tags {
+            *counts.entry(tag.as_str()).or_default() += 1;
+        }
+    }
+    counts
+        .into_iter()
+        .map(|(name, sites)| TagUsage {
+            name: name.to_string(),
+            sites,
+        })
+        .collect()
+}
+
+/// Replace `tags` with `into` on every editable site, or remove them when `into` is `None`
+///
+/// A site ends up with each tag once, in its original position. Returns the number of
+/// sites changed.
+///
+/// # Errors
+///
+/// Returns [`IronShieldError::TagNotFound`] if no editable site has any of `tags`, and
+/// [`IronShieldError::ConfigValidation`] if `into` is empty.
+pub fn retag(config: &mut Config, tags: &[String], into: Option<&str>) -> Result<usize> {
+    if into.is_some_and(|into| into.trim().is_empty()) {
+        return Err(IronShieldError::ConfigValidation {
+            field: "name".to_string(),
+            message: "cannot be empty".to_string(),
+        });
+    }
+
+    let included = &config.included_sites;
+    let mut changed = 0;
+    for site in config
+        .sites
+        .iter_mut()
+        .filter(|site| !included.contains(&site.name))
+    {
+        if !site.tags.iter().any(|tag| tags.contains(tag)) {
+            continue;
+        }
+        let mut retagged: Vec<String> = Vec::with_capacity(site.tags.len());
+        for tag in site.tags.drain(..) {
+            let tag = match into {
+                Some(into) if tags.contains(&tag) => into.trim().to_string(),
+                None if tags.contains(&tag) => continue,
+                _ => tag,
+            };
+            if !retagged.contains(&tag) {
+                retagged.push(tag);
+            }
+        }
+        site.tags = retagged;
+        changed += 1;
+    }
+
+    if changed == 0 {
+        return Err(IronShieldError::TagNotFound(tags.join(", ")));
+    }
+    Ok(changed)
+}
+
+/// List the tags in use with their usage counts
+///
+/// Handles `GET /api/tags`, responding with a JSON array of [`TagUsage`] sorted by name.
+pub async fn list_tags(State(state): State<Arc<UptimeState>>) -> impl IntoResponse {
+    Json(usage(&state.config.load().sites))
+}
+
+/// Rename a tag on every site
+///
+/// Handles `PATCH /api/tags/{tag}` and responds with the tags in use afterwards.
+///
+/// # Errors
+///
+/// Responds with HTTP 404 if no site has the tag, HTTP 400 if the new name is empty,
+/// and HTTP 500 if the configuration cannot be saved.
+pub async fn rename_tag(
+    State(state): State<Arc<UptimeState>>,
+    Path(tag): Path<String>,
+    Json(rename): Json<TagRename>,
+) -> impl IntoResponse {
+    retag_response(
+        &state,
+        &[tag],
+        Some(&rename.name),
+        &format!("renaming to \"{}\"", rename.name),
+    )
+}
+
+/// Merge several tags into one on every site
+///
+/// Handles `POST /api/tags/merge` and responds with the tags in use afterwards.
+///
+/// # Errors
+///
+/// Responds with HTTP 404 if no site has any of the tags, HTTP 400 if the merged tag is
+/// empty, and HTTP 500 if the configuration cannot be saved.
+pub async fn merge_tags(
+    State(state): State<Arc<UptimeState>>,
+    Json(merge): Json<TagMerge>,
+) -> impl IntoResponse {
+    retag_response(
+        &state,
+        &merge.tags,
+        Some(&merge.into),
+        &format!("merging into \"{}\"", merge.into),
+    )
+}
+
+/// Remove a tag from every site
+///
+/// Handles `DELETE /api/tags/{tag}` and responds with HTTP 204.
+///
+/// # Errors
+///
+/// Responds with HTTP 404 if no site has the tag and HTTP 500 if the configuration
+/// cannot be saved.
+pub async fn delete_tag(
+    State(state): State<Arc<UptimeState>>,
+    Path(tag): Path<String>,
+) -> impl IntoResponse {
+    let response = retag_response(&state, &[tag], None, "removing");
+    if response.status() == StatusCode::OK {
+        StatusCode::NO_CONTENT.into_response()
+    } else {
+        response
+    }
+}
+
+/// Apply [`retag`] to the running configuration, responding with the resulting tags.
+fn retag_response(
+    state: &UptimeState,
+    tags: &[String],
+    into: Option<&str>,
+    action: &str,
+) -> axum::response::Response {
+    match update_config(state, None, |config| retag(config, tags, into)) {
+        Ok((config, changed)) => {
+            info!(
+                "Changed tags {} on {changed} sites by {action}",
+                tags.join(", ")
+            );
+            Json(usage(&config.sites)).into_response()
+        }
+        Err(e) => {
+            error!("Error {action} tags {}: {e}", tags.join(", "));
+            e.into_response()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(sites: &[(&str, &[&str])]) -> Config {
+        Config {
+            sites: sites
+                .iter()
+                .map(|(name, tags)| Site {
+                    name: (*name).to_string(),
+                    url: "https://example.com".to_string(),
+                    category: String::new(),
+                    tags: tags.iter().map(|tag| (*tag).to_string()).collect(),
+                    monitor_interval_secs: None,
+                    monitor_timeout_secs: None,
+                    monitor_retries: None,
+                    monitor_user_agent: None,
+                    disabled: false,
+                    icon: None,
+                    order: None,
+                    assertions: None,
+                    uptime_percentage: 0.0,
+                })
+                .collect(),
+            ..Config::default()
+        }
+    }
+
+    fn tags_of(config: &Config) -> Vec<Vec<&str>> {
+        config
+            .sites
+            .iter()
+            .map(|site| site.tags.iter().map(String::as_str).collect())
+            .collect()
+    }
+
+    #[test]
+    fn merging_keeps_each_tag_once_in_place() {
+        let mut config = config(&[
+            ("Plex", &["k8s", "media", "kube"]),
+            ("Grafana", &["kubernetes", "k8s"]),
+            ("Router", &["network"]),
+        ]);
+
+        let tags = ["k8s".to_string(), "kube".to_string()];
+        assert_eq!(retag(&mut config, &tags, Some("kubernetes")).unwrap(), 2);
+        assert_eq!(
+            tags_of(&config),
+            [
+                vec!["kubernetes", "media"],
+                vec!["kubernetes"],
+                vec!["network"]
+            ]
+        );
+    }
+
+    #[test]
+    fn deleting_skips_included_sites_and_reports_unknown_tags() {
+        let mut config = config(&[("Plex", &["media"]), ("Jellyfin", &["media"])]);
+        config.included_sites.push("Jellyfin".to_string());
+
+        assert_eq!(retag(&mut config, &["media".to_string()], None).unwrap(), 1);
+        assert_eq!(tags_of(&config), [vec![], vec!["media"]]);
+
+        assert!(matches!(
+            retag(&mut config, &["missing".to_string()], None),
+            Err(IronShieldError::TagNotFound(_))
+        ));
+        assert!(matches!(
+            retag(&mut config, &["media".to_string()], Some(" ")),
+            Err(IronShieldError::ConfigValidation { .. })
+        ));
+    }
+}
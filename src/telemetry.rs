@@ -1,106 +1,513 @@
-use crate::error::Result;
-use crate::uptime::{snapshot_current_histories, UptimeState};
+//! Uptime telemetry
+//!
+//! When `opentelemetry_endpoint` is set, iron-shield exports metrics to it with protobuf
+//! encoding (see [`crate::otlp`]), so any OpenTelemetry collector can receive them. The
+//! `opentelemetry_transport` setting picks the protocol:
+//!
+//! - `http` (default): OTLP/HTTP. The endpoint is the collector's base URL, such as
+//!   `http://collector:4318`; metrics are posted to its `/v1/metrics` path.
+//! - `grpc`: OTLP/gRPC, for collectors that only listen on the gRPC port. The endpoint
+//!   is the collector's address, such as `http://collector:4317`, and is spoken to over
+//!   cleartext HTTP/2; an `https` endpoint negotiates HTTP/2 over TLS instead.
+//!
+//! Every `opentelemetry_export_interval_secs` (60 by default), and after the settings are
+//! saved, the latest check of every monitored site is exported as:
+//!
+//! - `iron_shield.site.up` - 1 if the site is up, 0 if it is down
+//! - `iron_shield.site.response_time` - response time of the latest check, in `ms`
+//! - `iron_shield.site.uptime` - share of the recorded checks that succeeded, in `%`
+//! - `iron_shield.site.checks` and `iron_shield.site.failures` - number of recorded
+//!   checks and of failed ones
+//! - `iron_shield.site.response_time.avg` and `iron_shield.site.response_time.max` -
+//!   mean and highest response time of the recorded successful checks, in `ms`
+//! - `iron_shield.site.incident.duration` - for sites with an open incident (see the
+//!   `notifications` module), how long it has been open, in `s`, with the incident's ID
+//!   in the `incident.id` attribute
+//!
+//! The recorded checks are the rolling window of the last
+//! [`MAX_HISTORY_ENTRIES`](crate::uptime::MAX_HISTORY_ENTRIES) checks the dashboard
+//! keeps per site, so the window statistics match the uptime bars.
+//!
+//! Every snapshot also describes the iron-shield process itself (see
+//! [`crate::self_monitor`]), without any `site.name` attribute:
+//!
+//! - `iron_shield.process.memory` - resident memory of the process, in `By`, where the OS
+//!   reports it
+//! - `iron_shield.process.sse_connections` - open uptime streams
+//! - `iron_shield.process.checks.queued` and `iron_shield.process.checks.running` -
+//!   uptime checks waiting for a concurrency slot and in flight
+//! - `iron_shield.process.cycle.duration` - how long the latest uptime cycle took, in `ms`
+//!
+//! Each click on a site link adds 1 to the `iron_shield.site.clicks` counter. Data
+//! points carry the `site.name` attribute, and the resource is described by
+//! `service.name` (`iron_shield`) and `service.instance.id` (the `site_name` setting).
+//!
+//! With `opentelemetry_traces` enabled, every uptime check is also exported as a client
+//! span named `uptime check` to the same endpoint (at `/v1/traces` over OTLP/HTTP), so
+//! slow checks can be inspected in Tempo or Jaeger. The span carries the `site.name` and
+//! `url.full` attributes, events marking when each attempt started, received its
+//! response headers, finished reading the body, or failed, and an error status with the
+//! failure reason when the site was down.
+//!
+//! The `opentelemetry_sampling` block trims what snapshots report, since a collector
+//! billed by data point gets expensive with many sites:
+//!
+//! ```json5
+//! opentelemetry_sampling: {
+//!     mode: "changes",
+//!     every: 5,
+//!     exclude_sites: ["Speedtest"],
+//! }
+//! ```
+//!
+//! - `mode` - `all` (default) reports every site, `changes` only the sites whose status
+//!   changed since the previous exported snapshot, and `failures` only the sites that
+//!   are down
+//! - `every` - export only every Nth snapshot (1 by default, which exports all of them)
+//! - `exclude_sites` - names of sites never reported, ignoring case
+//!
+//! A snapshot left with no sites only reports the process metrics, which sampling never
+//! trims. Clicks are always exported, and check spans unless their site is excluded.
+//!
+//! Snapshots and clicks are not sent where they happen but queued in a
+//! [`TelemetryQueue`], which [`export_queued`] drains in the background: what queued up
+//! within a second is sent as one batch, and a failed batch is retried with exponential
+//! backoff, so a collector that is briefly down loses nothing. The queue holds at most
+//! [`QUEUE_CAPACITY`] exports; beyond that the oldest are dropped with a warning.
+
+use crate::config::TelemetryTransport;
+use crate::error::{IronShieldError, Result};
+use crate::notifications::OpenIncident;
+use crate::otlp::{self, DataPoint, Kind, Metric, Span, SpanEvent, SpanStatus, Value};
+use crate::self_monitor::{self_status, SelfStatus};
+use crate::uptime::{
+    snapshot_current_histories, unix_now, HistoryEntry, UptimeHistory, UptimeState, UptimeStatus,
+};
 use axum::{
     extract::{Json, State},
-    http::{HeaderMap, StatusCode},
+    http::StatusCode,
     response::IntoResponse,
 };
+use parking_lot::Mutex;
+use rand::RngCore;
 use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::Instant;
 use tracing::{debug, warn};
 
+/// Most exports the queue holds before dropping the oldest
+pub const QUEUE_CAPACITY: usize = 1_000;
+/// Most queued exports sent in one request
+const MAX_BATCH: usize = 100;
+/// How long the exporter waits after being woken for more exports to batch with
+const BATCH_DELAY: Duration = Duration::from_secs(1);
+/// Wait before retrying the first failed export, doubled after each further failure
+const FIRST_RETRY_DELAY: Duration = Duration::from_secs(1);
+/// Longest wait between retries
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(300);
+/// How long the collector gets to answer an export
+const EXPORT_TIMEOUT: Duration = Duration::from_secs(10);
+
 #[derive(Deserialize, Serialize, Clone)]
 pub struct SiteClickEvent {
     pub site_name: String,
     pub site_url: String,
 }
 
-#[derive(Serialize)]
-struct HeaderRecord {
-    name: String,
-    value: String,
-}
-
-#[derive(Serialize)]
-struct ClickTelemetryPayload {
-    dashboard_name: String,
-    generated_at: u64,
-    site_name: String,
-    site_url: String,
-    headers: Vec<HeaderRecord>,
+/// Where telemetry is exported to, read from the configuration
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TelemetryDestination {
+    /// The collector endpoint
+    pub endpoint: String,
+    /// Protocol the collector is spoken to with
+    pub transport: TelemetryTransport,
+    /// Name of the dashboard, reported as `service.instance.id`
+    pub dashboard_name: String,
 }
 
-/// Payload delivered to the OpenTelemetry collector endpoint.
-#[derive(Serialize)]
-struct UptimeTelemetryPayload {
-    /// Dashboard name to make it easier to identify the sender.
-    dashboard_name: String,
-    /// Unix timestamp indicating when the payload was generated.
-    generated_at: u64,
-    /// Latest status for each monitored site.
-    uptime: Vec<TelemetrySiteStatus>,
+/// Which sites an exported uptime snapshot reports
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SamplingMode {
+    /// Every monitored site
+    #[default]
+    All,
+    /// Sites whose status changed since the previous exported snapshot
+    Changes,
+    /// Sites that are down
+    Failures,
 }
 
-#[derive(Serialize)]
-struct TelemetrySiteStatus {
-    site_id: String,
-    status: crate::uptime::UptimeStatus,
-    timestamp: u64,
-    response_time_ms: Option<u64>,
+/// Returns the default sampling rate, which exports every snapshot.
+#[must_use]
+pub fn default_sampling_every() -> u32 {
+    1
 }
 
-/// Push the latest uptime snapshot to the configured endpoint.
+/// What uptime snapshots are exported to the collector
 ///
-/// This function is meant to be called on a best-effort basis, so callers should
-/// handle errors gracefully and avoid blocking user flows when the endpoint is
-/// unreachable.
+/// # Fields
 ///
-/// # Errors
+/// * `mode` - Which sites each snapshot reports
+/// * `every` - Export only every Nth snapshot
+/// * `exclude_sites` - Names of sites never reported, ignoring case
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+pub struct TelemetrySampling {
+    /// Which sites each snapshot reports
+    #[serde(default)]
+    pub mode: SamplingMode,
+    /// Export only every Nth snapshot
+    #[serde(default = "default_sampling_every")]
+    pub every: u32,
+    /// Sites never reported
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exclude_sites: Vec<String>,
+}
+
+impl Default for TelemetrySampling {
+    fn default() -> Self {
+        Self {
+            mode: SamplingMode::All,
+            every: default_sampling_every(),
+            exclude_sites: Vec::new(),
+        }
+    }
+}
+
+impl TelemetrySampling {
+    /// Whether these settings export everything, as when the block is left out
+    #[must_use]
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Problems with these settings, as pairs of the field name and what is wrong with it
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use iron_shield::telemetry::TelemetrySampling;
+    ///
+    /// assert!(TelemetrySampling::default().validate().is_empty());
+    ///
+    /// let never = TelemetrySampling { every: 0, ..TelemetrySampling::default() };
+    /// assert_eq!(never.validate()[0].0, "every");
+    /// ```
+    #[must_use]
+    pub fn validate(&self) -> Vec<(String, String)> {
+        let mut problems = Vec::new();
+        if self.every == 0 {
+            problems.push(("every".to_string(), "must be at least 1".to_string()));
+        }
+        for (index, site) in self.exclude_sites.iter().enumerate() {
+            if site.trim().is_empty() {
+                problems.push((
+                    format!("exclude_sites[{index}]"),
+                    "cannot be empty".to_string(),
+                ));
+            }
+        }
+        problems
+    }
+
+    /// Whether the site named `name` is left out of every snapshot
+    fn excludes(&self, name: &str) -> bool {
+        self.exclude_sites
+            .iter()
+            .any(|site| site.trim().eq_ignore_ascii_case(name.trim()))
+    }
+}
+
+/// Attributes of the resource the metrics of the dashboard named `dashboard_name` are
+/// reported for.
+fn resource_attributes(dashboard_name: String) -> [(&'static str, String); 2] {
+    [
+        ("service.name", "iron_shield".to_string()),
+        ("service.instance.id", dashboard_name),
+    ]
+}
+
+/// Queue the trace span of an uptime check of `site` at `url` that started at
+/// `start_time_unix_nano` and ended now, if telemetry and `opentelemetry_traces` are
+/// enabled and the site is not excluded from sampling.
 ///
-/// Returns an error if the HTTP request cannot be sent or if the endpoint responds
-/// with a non-success status code.
-pub async fn send_uptime_snapshot(
-    state: Arc<UptimeState>,
-    dashboard_name: String,
-    endpoint: String,
-) -> Result<()> {
-    if endpoint.trim().is_empty() {
-        return Ok(());
+/// `failure_reason` is `None` for a site that is up; `events` are the timings recorded
+/// during the check.
+pub fn queue_check_span(
+    state: &UptimeState,
+    site: &str,
+    url: &str,
+    start_time_unix_nano: u64,
+    failure_reason: Option<&str>,
+    events: Vec<SpanEvent>,
+) {
+    let config = state.config.load();
+    let traced = config.opentelemetry_traces && !config.opentelemetry_sampling.excludes(site);
+    if !traced || telemetry_destination(state).is_none() {
+        return;
+    }
+
+    let mut trace_id = [0; 16];
+    let mut span_id = [0; 8];
+    rand::thread_rng().fill_bytes(&mut trace_id);
+    rand::thread_rng().fill_bytes(&mut span_id);
+    state.telemetry.push_span(Span {
+        trace_id,
+        span_id,
+        name: "uptime check".to_string(),
+        start_time_unix_nano,
+        end_time_unix_nano: crate::uptime::unix_now_nanos(),
+        attributes: vec![
+            ("site.name", site.to_string()),
+            ("url.full", url.to_string()),
+        ],
+        events,
+        status: failure_reason.map_or(SpanStatus::Ok, |reason| {
+            SpanStatus::Error(reason.to_string())
+        }),
+    });
+}
+
+/// Queue the latest uptime snapshot for export, if telemetry is enabled and the
+/// `opentelemetry_sampling` settings keep it.
+pub fn queue_uptime_snapshot(state: &UptimeState) {
+    if telemetry_destination(state).is_none() {
+        return;
     }
+    let sampling = state.config.load().opentelemetry_sampling.clone();
 
-    let snapshot = snapshot_current_histories(&state);
+    let snapshot = snapshot_current_histories(state);
 
     if snapshot.is_empty() {
         debug!("No uptime history available; skipping telemetry payload");
     }
 
-    let payload = UptimeTelemetryPayload {
-        dashboard_name,
-        generated_at: current_timestamp(),
-        uptime: snapshot
-            .into_iter()
-            .filter(|history| {
-                !matches!(
-                    history.status,
-                    crate::uptime::UptimeStatus::Loading | crate::uptime::UptimeStatus::Disabled
-                )
-            })
-            .map(|history| TelemetrySiteStatus {
-                site_id: history.site_id,
-                status: history.status,
-                timestamp: history.timestamp,
-                response_time_ms: history.response_time_ms,
-            })
-            .collect(),
+    let now = unix_now();
+    let mut metrics = process_metrics(&self_status(state), now);
+    if let Some(snapshot) = state.telemetry.sample(&sampling, snapshot) {
+        let incidents = state.incidents.open_incidents();
+        metrics.extend(uptime_metrics(&snapshot, &incidents, now));
+    }
+    state.telemetry.push(metrics);
+}
+
+/// The health metrics of the process described by `status` at the Unix time `now`; see
+/// [`crate::self_monitor`].
+fn process_metrics(status: &SelfStatus, now: u64) -> Vec<Metric> {
+    let point = |value: u64| {
+        vec![DataPoint {
+            attributes: Vec::new(),
+            time_unix_nano: now.saturating_mul(1_000_000_000),
+            value: Value::Int(i64::try_from(value).unwrap_or(i64::MAX)),
+        }]
     };
+    let gauge = |name, description, unit, data_points| Metric {
+        name,
+        description,
+        unit,
+        kind: Kind::Gauge,
+        data_points,
+    };
+    let count = |count: usize| point(u64::try_from(count).unwrap_or(u64::MAX));
+
+    let mut metrics = vec![
+        gauge(
+            "iron_shield.process.sse_connections",
+            "Open uptime streams",
+            "",
+            count(status.sse_connections),
+        ),
+        gauge(
+            "iron_shield.process.checks.queued",
+            "Uptime checks waiting for a free concurrency slot",
+            "",
+            count(status.queued_checks),
+        ),
+        gauge(
+            "iron_shield.process.checks.running",
+            "Uptime checks in flight",
+            "",
+            count(status.running_checks),
+        ),
+    ];
+    if let Some(bytes) = status.memory_bytes {
+        metrics.push(gauge(
+            "iron_shield.process.memory",
+            "Resident memory of the iron-shield process",
+            "By",
+            point(bytes),
+        ));
+    }
+    if let Some(ms) = status.last_cycle_ms {
+        metrics.push(gauge(
+            "iron_shield.process.cycle.duration",
+            "How long the latest uptime cycle took to finish its checks",
+            "ms",
+            point(ms),
+        ));
+    }
+    metrics
+}
 
-    post_telemetry(&endpoint, &payload).await
+/// Statistics of the completed checks in a site's recorded history
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct WindowStats {
+    /// Number of completed checks
+    checks: usize,
+    /// Number of failed checks
+    failures: usize,
+    /// Mean response time of the successful checks, in ms
+    average_ms: Option<f64>,
+    /// Highest response time of the successful checks, in ms
+    max_ms: Option<u64>,
 }
 
-/// Receive click tracking events from the frontend and forward them to the telemetry collector.
+impl WindowStats {
+    fn of(history: &[HistoryEntry]) -> Self {
+        let completed = history
+            .iter()
+            .filter(|entry| matches!(entry.status, UptimeStatus::Up | UptimeStatus::Down));
+        let response_times: Vec<u64> = completed
+            .clone()
+            .filter(|entry| entry.status == UptimeStatus::Up)
+            .filter_map(|entry| entry.response_time_ms)
+            .collect();
+        #[allow(clippy::cast_precision_loss)] // Response times are far below 2^52 ms
+        let average_ms = (!response_times.is_empty())
+            .then(|| response_times.iter().sum::<u64>() as f64 / response_times.len() as f64);
+        Self {
+            checks: completed.clone().count(),
+            failures: completed
+                .filter(|entry| entry.status == UptimeStatus::Down)
+                .count(),
+            average_ms,
+            max_ms: response_times.iter().copied().max(),
+        }
+    }
+}
+
+/// The uptime metrics of the sites in `snapshot`, leaving out sites that are paused or
+/// have not finished a check; `incidents` are the open incidents by site and `now` the
+/// current Unix time, which their durations are measured to.
+fn uptime_metrics(
+    snapshot: &[UptimeHistory],
+    incidents: &HashMap<String, OpenIncident>,
+    now: u64,
+) -> Vec<Metric> {
+    let mut up = Vec::new();
+    let mut response_time = Vec::new();
+    let mut uptime = Vec::new();
+    let mut checks = Vec::new();
+    let mut failures = Vec::new();
+    let mut average = Vec::new();
+    let mut max = Vec::new();
+    let mut incident_duration = Vec::new();
+    for history in snapshot {
+        let value = match history.status {
+            UptimeStatus::Up => 1,
+            UptimeStatus::Down => 0,
+            UptimeStatus::Loading | UptimeStatus::Disabled => continue,
+        };
+        let point = |value| DataPoint {
+            attributes: vec![("site.name", history.site_id.clone())],
+            time_unix_nano: history.timestamp.saturating_mul(1_000_000_000),
+            value,
+        };
+        up.push(point(Value::Int(value)));
+        if let Some(ms) = history.response_time_ms {
+            #[allow(clippy::cast_precision_loss)] // Response times are far below 2^52 ms
+            response_time.push(point(Value::Double(ms as f64)));
+        }
+        uptime.push(point(Value::Double(history.uptime_percentage)));
+
+        let stats = WindowStats::of(&history.history);
+        let count = |count: usize| Value::Int(i64::try_from(count).unwrap_or(i64::MAX));
+        checks.push(point(count(stats.checks)));
+        failures.push(point(count(stats.failures)));
+        if let Some(ms) = stats.average_ms {
+            average.push(point(Value::Double(ms)));
+        }
+        if let Some(ms) = stats.max_ms {
+            #[allow(clippy::cast_precision_loss)]
+            max.push(point(Value::Double(ms as f64)));
+        }
+        if let Some(incident) = incidents.get(&history.site_id) {
+            let secs = now.saturating_sub(incident.started_at);
+            let mut point = point(Value::Int(i64::try_from(secs).unwrap_or(i64::MAX)));
+            point
+                .attributes
+                .push(("incident.id", incident.id.to_string()));
+            incident_duration.push(point);
+        }
+    }
+
+    vec![
+        Metric {
+            name: "iron_shield.site.up",
+            description: "Whether the latest check of the site succeeded",
+            unit: "",
+            kind: Kind::Gauge,
+            data_points: up,
+        },
+        Metric {
+            name: "iron_shield.site.response_time",
+            description: "Response time of the latest check of the site",
+            unit: "ms",
+            kind: Kind::Gauge,
+            data_points: response_time,
+        },
+        Metric {
+            name: "iron_shield.site.uptime",
+            description: "Share of the recorded checks of the site that succeeded",
+            unit: "%",
+            kind: Kind::Gauge,
+            data_points: uptime,
+        },
+        Metric {
+            name: "iron_shield.site.checks",
+            description: "Number of recorded checks of the site",
+            unit: "",
+            kind: Kind::Gauge,
+            data_points: checks,
+        },
+        Metric {
+            name: "iron_shield.site.failures",
+            description: "Number of recorded checks of the site that failed",
+            unit: "",
+            kind: Kind::Gauge,
+            data_points: failures,
+        },
+        Metric {
+            name: "iron_shield.site.response_time.avg",
+            description: "Mean response time of the recorded successful checks of the site",
+            unit: "ms",
+            kind: Kind::Gauge,
+            data_points: average,
+        },
+        Metric {
+            name: "iron_shield.site.response_time.max",
+            description: "Highest response time of the recorded successful checks of the site",
+            unit: "ms",
+            kind: Kind::Gauge,
+            data_points: max,
+        },
+        Metric {
+            name: "iron_shield.site.incident.duration",
+            description: "How long the open incident of the site has been open",
+            unit: "s",
+            kind: Kind::Gauge,
+            data_points: incident_duration,
+        },
+    ]
+}
+
+/// Receive click tracking events from the frontend and count them at the telemetry
+/// collector.
 pub async fn track_site_click(
     State(state): State<Arc<UptimeState>>,
-    headers: HeaderMap,
     Json(payload): Json<SiteClickEvent>,
 ) -> impl IntoResponse {
     if payload.site_name.trim().is_empty() || payload.site_url.trim().is_empty() {
@@ -111,63 +518,413 @@ pub async fn track_site_click(
             .into_response();
     }
 
-    if let Some((endpoint, dashboard_name)) = telemetry_destination(&state) {
-        let header_records = header_records(&headers);
-        let click = payload.clone();
-        tokio::spawn(async move {
-            let telemetry_payload = ClickTelemetryPayload {
-                dashboard_name,
-                generated_at: current_timestamp(),
-                site_name: click.site_name,
-                site_url: click.site_url,
-                headers: header_records,
-            };
+    if telemetry_destination(&state).is_some() {
+        state.telemetry.push(vec![Metric {
+            name: "iron_shield.site.clicks",
+            description: "Clicks on the link of the site",
+            unit: "",
+            kind: Kind::DeltaCounter,
+            data_points: vec![DataPoint {
+                attributes: vec![
+                    ("site.name", payload.site_name),
+                    ("url.full", payload.site_url),
+                ],
+                time_unix_nano: current_timestamp().saturating_mul(1_000_000_000),
+                value: Value::Int(1),
+            }],
+        }]);
+    }
+
+    StatusCode::ACCEPTED.into_response()
+}
+
+/// Exports waiting to be sent to the telemetry collector, each the metrics of one uptime
+/// snapshot or click, or the span of one uptime check
+#[derive(Debug, Default)]
+pub struct TelemetryQueue {
+    pending: Mutex<VecDeque<Vec<Metric>>>,
+    spans: Mutex<VecDeque<Span>>,
+    wake: tokio::sync::Notify,
+    /// Number of uptime snapshots offered for export so far
+    snapshots: AtomicU64,
+    /// Status of each site in the previous exported snapshot
+    exported_status: Mutex<HashMap<String, UptimeStatus>>,
+}
+
+impl TelemetryQueue {
+    /// The part of `snapshot` that `sampling` exports, or `None` if the snapshot is
+    /// skipped or reports no sites.
+    ///
+    /// Each call counts towards `sampling.every`, so call it once per snapshot.
+    fn sample(
+        &self,
+        sampling: &TelemetrySampling,
+        snapshot: Vec<UptimeHistory>,
+    ) -> Option<Vec<UptimeHistory>> {
+        let index = self.snapshots.fetch_add(1, Ordering::Relaxed);
+        if !index.is_multiple_of(u64::from(sampling.every.max(1))) {
+            return None;
+        }
+
+        let mut exported_status = self.exported_status.lock();
+        let sampled: Vec<UptimeHistory> = snapshot
+            .into_iter()
+            .filter(|history| !sampling.excludes(&history.site_id))
+            .filter(|history| {
+                let previous = exported_status.insert(history.site_id.clone(), history.status);
+                match sampling.mode {
+                    SamplingMode::All => true,
+                    SamplingMode::Changes => previous != Some(history.status),
+                    SamplingMode::Failures => history.status == UptimeStatus::Down,
+                }
+            })
+            .collect();
+        (sampling.mode == SamplingMode::All || !sampled.is_empty()).then_some(sampled)
+    }
+
+    /// Queue an export and wake the exporter, dropping the oldest export if the queue is
+    /// full.
+    pub fn push(&self, metrics: Vec<Metric>) {
+        let mut pending = self.pending.lock();
+        pending.push_back(metrics);
+        drop_overflow(&mut pending);
+        drop(pending);
+        self.wake.notify_one();
+    }
+
+    /// Queue the span of a check and wake the exporter, dropping the oldest span if the
+    /// queue is full.
+    pub fn push_span(&self, span: Span) {
+        let mut spans = self.spans.lock();
+        spans.push_back(span);
+        drop_overflow(&mut spans);
+        drop(spans);
+        self.wake.notify_one();
+    }
+
+    /// Number of exports and spans waiting to be sent
+    pub(crate) fn len(&self) -> usize {
+        self.pending.lock().len() + self.spans.lock().len()
+    }
+
+    /// Take up to [`MAX_BATCH`] of the oldest exports, or `None` if none are waiting.
+    fn take_batch(&self) -> Option<Vec<Vec<Metric>>> {
+        let mut pending = self.pending.lock();
+        let count = pending.len().min(MAX_BATCH);
+        (count > 0).then(|| pending.drain(..count).collect())
+    }
+
+    /// Put a batch that failed to send back in front of the queue, so it is retried
+    /// before newer exports.
+    fn requeue(&self, batch: Vec<Vec<Metric>>) {
+        let mut pending = self.pending.lock();
+        for metrics in batch.into_iter().rev() {
+            pending.push_front(metrics);
+        }
+        drop_overflow(&mut pending);
+    }
+
+    /// Take up to [`MAX_BATCH`] of the oldest spans, or `None` if none are waiting.
+    fn take_spans(&self) -> Option<Vec<Span>> {
+        let mut spans = self.spans.lock();
+        let count = spans.len().min(MAX_BATCH);
+        (count > 0).then(|| spans.drain(..count).collect())
+    }
+
+    /// Put spans that failed to send back in front of the queue.
+    fn requeue_spans(&self, batch: Vec<Span>) {
+        let mut spans = self.spans.lock();
+        for span in batch.into_iter().rev() {
+            spans.push_front(span);
+        }
+        drop_overflow(&mut spans);
+    }
+}
+
+/// Drop the oldest exports beyond [`QUEUE_CAPACITY`].
+fn drop_overflow<T>(pending: &mut VecDeque<T>) {
+    let overflow = pending.len().saturating_sub(QUEUE_CAPACITY);
+    if overflow > 0 {
+        pending.drain(..overflow);
+        warn!("Telemetry queue is full; dropped the {overflow} oldest exports");
+    }
+}
 
-            if let Err(err) = post_telemetry(&endpoint, &telemetry_payload).await {
-                warn!("Failed to send click telemetry: {err}");
+/// Send the exports queued in `state.telemetry` until shutdown.
+///
+/// Waits a second after being woken so exports queued together go out in one
+/// request. A failed batch is put back and retried after a delay that doubles with each
+/// failure, up to five minutes, and resets once an export succeeds. Exports queued while
+/// telemetry is disabled are dropped. On shutdown the queue is flushed once more without
+/// retrying.
+///
+/// Every `opentelemetry_export_interval_secs` the uptime snapshot is queued as well, also
+/// while a retry is pending. This schedule, not the uptime checks, triggers snapshot
+/// exports, so collectors receive one evenly spaced time series however many dashboards
+/// are open.
+pub async fn export_queued(state: Arc<UptimeState>) {
+    let mut retry_delay = FIRST_RETRY_DELAY;
+    let mut retry_at = None;
+    let mut last_snapshot = Instant::now();
+    loop {
+        let snapshot_at = export_interval(&state).map(|interval| last_snapshot + interval);
+        tokio::select! {
+            () = state.shutdown_token.cancelled() => break,
+            () = state.telemetry.wake.notified(), if retry_at.is_none() => {
+                tokio::select! {
+                    () = state.shutdown_token.cancelled() => break,
+                    () = tokio::time::sleep(BATCH_DELAY) => {}
+                }
+            }
+            () = sleep_until(snapshot_at) => {
+                last_snapshot = Instant::now();
+                queue_uptime_snapshot(&state);
+                if retry_at.is_some() {
+                    continue;
+                }
+            }
+            () = sleep_until(retry_at) => retry_at = None,
+        }
+
+        match flush(&state).await {
+            Ok(()) => retry_delay = FIRST_RETRY_DELAY,
+            Err(err) => {
+                warn!(
+                    "Failed to export telemetry, retrying in {}s: {err}",
+                    retry_delay.as_secs()
+                );
+                retry_at = Some(Instant::now() + retry_delay);
+                retry_delay = (retry_delay * 2).min(MAX_RETRY_DELAY);
             }
-        });
+        }
     }
 
-    StatusCode::ACCEPTED.into_response()
+    if let Err(err) = flush(&state).await {
+        warn!(
+            "Dropping {} queued telemetry exports at shutdown: {err}",
+            state.telemetry.len()
+        );
+    }
+}
+
+/// Export the queued metrics, then the queued spans, in batches.
+///
+/// Stops at the first batch that fails to export, which is put back in the queue, and
+/// returns its error. Exports queued while telemetry is disabled are dropped.
+async fn flush(state: &UptimeState) -> Result<()> {
+    while let Some(batch) = state.telemetry.take_batch() {
+        let Some(destination) = telemetry_destination(state) else {
+            debug!(
+                "Telemetry is disabled; dropping {} queued exports",
+                batch.len()
+            );
+            continue;
+        };
+        if let Err(err) = export(destination, &merge_batch(&batch)).await {
+            state.telemetry.requeue(batch);
+            return Err(err);
+        }
+    }
+    while let Some(spans) = state.telemetry.take_spans() {
+        let Some(destination) = telemetry_destination(state) else {
+            debug!(
+                "Telemetry is disabled; dropping {} queued spans",
+                spans.len()
+            );
+            continue;
+        };
+        if let Err(err) = export_spans(destination, &spans).await {
+            state.telemetry.requeue_spans(spans);
+            return Err(err);
+        }
+    }
+    Ok(())
+}
+
+/// The configured time between scheduled snapshot exports, or `None` if they are off.
+fn export_interval(state: &UptimeState) -> Option<Duration> {
+    let secs = state.config.load().opentelemetry_export_interval_secs;
+    (secs > 0).then(|| Duration::from_secs(secs))
+}
+
+/// Sleep until `deadline`, or forever without one.
+async fn sleep_until(deadline: Option<Instant>) {
+    match deadline {
+        Some(deadline) => tokio::time::sleep_until(deadline).await,
+        None => std::future::pending().await,
+    }
 }
 
+/// Combine the exports of a batch into one list of metrics.
+///
+/// Data points of metrics with the same name are sent under one metric, and clicks on the
+/// same link are added up into one data point at the time of the latest click.
+fn merge_batch(batch: &[Vec<Metric>]) -> Vec<Metric> {
+    let mut merged: Vec<Metric> = Vec::new();
+    for metric in batch.iter().flatten() {
+        let Some(existing) = merged
+            .iter_mut()
+            .find(|existing| existing.name == metric.name)
+        else {
+            merged.push(metric.clone());
+            continue;
+        };
+        for point in &metric.data_points {
+            let same_counter = if metric.kind == Kind::DeltaCounter {
+                existing
+                    .data_points
+                    .iter_mut()
+                    .find(|existing| existing.attributes == point.attributes)
+            } else {
+                None
+            };
+            match (same_counter, point.value) {
+                (Some(existing), Value::Int(count)) => {
+                    if let Value::Int(total) = &mut existing.value {
+                        *total += count;
+                    }
+                    existing.time_unix_nano = existing.time_unix_nano.max(point.time_unix_nano);
+                }
+                _ => existing.data_points.push(point.clone()),
+            }
+        }
+    }
+    merged
+}
+
+/// How long [`check_reachable`] waits for the endpoint to answer.
+const REACHABILITY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Check that a telemetry endpoint answers requests over `transport`.
+///
+/// Any response counts, since collectors commonly reject a `GET` while accepting the
+/// snapshots posted to them; only a failure to connect or a timeout is an error. gRPC
+/// endpoints are asked over HTTP/2, so a port that only speaks HTTP/1 is reported.
+///
+/// # Errors
+///
+/// Returns an error if the endpoint cannot be reached within five seconds.
+pub async fn check_reachable(endpoint: &str, transport: TelemetryTransport) -> Result<()> {
+    client(transport)
+        .map_err(|err| telemetry_error(endpoint, err))?
+        .get(endpoint)
+        .timeout(REACHABILITY_TIMEOUT)
+        .send()
+        .await
+        .map(|_| ())
+        .map_err(|err| telemetry_error(endpoint, err))
+}
+
+/// Where telemetry goes, or `None` when no endpoint is configured.
 #[must_use]
-pub fn telemetry_destination(state: &Arc<UptimeState>) -> Option<(String, String)> {
-    let config_guard = state.config.read().ok()?;
-    let endpoint = config_guard.opentelemetry_endpoint.clone()?;
+pub fn telemetry_destination(state: &UptimeState) -> Option<TelemetryDestination> {
+    let config = state.config.load();
+    let endpoint = config.opentelemetry_endpoint.clone()?;
     if endpoint.trim().is_empty() {
         return None;
     }
-    Some((endpoint, config_guard.site_name.clone()))
+    Some(TelemetryDestination {
+        endpoint,
+        transport: config.opentelemetry_transport,
+        dashboard_name: config.site_name.clone(),
+    })
+}
+
+/// Export `metrics` to `destination` over its transport.
+async fn export(destination: TelemetryDestination, metrics: &[Metric]) -> Result<()> {
+    let request =
+        otlp::encode_metrics_request(&resource_attributes(destination.dashboard_name), metrics);
+    let endpoint = &destination.endpoint;
+    match destination.transport {
+        TelemetryTransport::Http => post_otlp(endpoint, otlp::metrics_url(endpoint), request).await,
+        TelemetryTransport::Grpc => {
+            post_otlp_grpc(endpoint, otlp::grpc_url(endpoint), request).await
+        }
+    }
+}
+
+/// Export `spans` to `destination` over its transport.
+async fn export_spans(destination: TelemetryDestination, spans: &[Span]) -> Result<()> {
+    let request =
+        otlp::encode_traces_request(&resource_attributes(destination.dashboard_name), spans);
+    let endpoint = &destination.endpoint;
+    match destination.transport {
+        TelemetryTransport::Http => post_otlp(endpoint, otlp::traces_url(endpoint), request).await,
+        TelemetryTransport::Grpc => {
+            post_otlp_grpc(endpoint, otlp::grpc_traces_url(endpoint), request).await
+        }
+    }
 }
 
-fn header_records(headers: &HeaderMap) -> Vec<HeaderRecord> {
-    headers
-        .iter()
-        .map(|(name, value)| HeaderRecord {
-            name: name.as_str().to_string(),
-            value: String::from_utf8_lossy(value.as_bytes()).to_string(),
-        })
-        .collect()
+/// HTTP client for `transport`; gRPC needs HTTP/2 even without TLS to negotiate it.
+fn client(transport: TelemetryTransport) -> reqwest::Result<reqwest::Client> {
+    match transport {
+        TelemetryTransport::Http => Ok(reqwest::Client::new()),
+        TelemetryTransport::Grpc => reqwest::Client::builder().http2_prior_knowledge().build(),
+    }
 }
 
-async fn post_telemetry(endpoint: &str, payload: &impl Serialize) -> Result<()> {
+/// Post an encoded export request to `url`, the OTLP/HTTP URL of a signal at `endpoint`.
+async fn post_otlp(endpoint: &str, url: String, request: Vec<u8>) -> Result<()> {
     let client = reqwest::Client::new();
     let response = client
-        .post(endpoint)
-        .json(payload)
+        .post(url)
+        .timeout(EXPORT_TIMEOUT)
+        .header(reqwest::header::CONTENT_TYPE, "application/x-protobuf")
+        .body(request)
         .send()
         .await
-        .map_err(|err| {
-            crate::error::IronShieldError::Generic(format!("Failed to send telemetry: {err}"))
-        })?;
-
-    response.error_for_status().map(|_| ()).map_err(|err| {
-        crate::error::IronShieldError::Generic(format!(
-            "Telemetry endpoint responded with error status: {err}"
-        ))
-    })
+        .map_err(|err| telemetry_error(endpoint, err))?;
+
+    response
+        .error_for_status()
+        .map(|_| ())
+        .map_err(|err| telemetry_error(endpoint, err))
+}
+
+/// Call an OTLP/gRPC `Export` method of the collector at `endpoint`, at `url`, with an
+/// encoded export request.
+///
+/// gRPC reports failures in a `grpc-status` header or trailer while answering HTTP 200,
+/// so besides the HTTP status the header is checked; a collector that accepted the
+/// export sends it as a trailer, which is not read.
+async fn post_otlp_grpc(endpoint: &str, url: String, request: Vec<u8>) -> Result<()> {
+    let response = client(TelemetryTransport::Grpc)
+        .map_err(|err| telemetry_error(endpoint, err))?
+        .post(url)
+        .timeout(EXPORT_TIMEOUT)
+        .header(reqwest::header::CONTENT_TYPE, "application/grpc")
+        .header(reqwest::header::TE, "trailers")
+        .body(otlp::grpc_frame(&request))
+        .send()
+        .await
+        .map_err(|err| telemetry_error(endpoint, err))?
+        .error_for_status()
+        .map_err(|err| telemetry_error(endpoint, err))?;
+
+    let header = |name| {
+        response
+            .headers()
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+    };
+    match header("grpc-status") {
+        None | Some("0") => Ok(()),
+        Some(status) => Err(IronShieldError::TelemetryRejected {
+            endpoint: endpoint.to_string(),
+            status: status.to_string(),
+            message: header("grpc-message").unwrap_or_default().to_string(),
+        }),
+    }
+}
+
+/// Wrap a failed telemetry request, dropping the URL from the error so a token in the
+/// endpoint does not end up in the logs.
+fn telemetry_error(endpoint: &str, err: reqwest::Error) -> IronShieldError {
+    IronShieldError::Telemetry {
+        endpoint: endpoint.to_string(),
+        source: err.without_url(),
+    }
 }
 
 fn current_timestamp() -> u64 {
@@ -176,3 +933,212 @@ fn current_timestamp() -> u64 {
         .unwrap_or_default()
         .as_secs()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn uptime_metrics_skip_sites_without_a_completed_check() {
+        let history = |site: &str, status, response_time_ms| UptimeHistory {
+            site_id: site.to_string(),
+            status,
+            timestamp: 2,
+            history: Vec::new(),
+            uptime_percentage: 50.0,
+            response_time_ms,
+            max_history_entries: crate::uptime::MAX_HISTORY_ENTRIES,
+            latency_ms: Vec::new(),
+            down_since: None,
+            last_checked: None,
+            last_change: None,
+        };
+        let metrics = uptime_metrics(
+            &[
+                history("Plex", UptimeStatus::Up, Some(120)),
+                history("NAS", UptimeStatus::Down, None),
+                history("Backup", UptimeStatus::Disabled, None),
+                history("Router", UptimeStatus::Loading, None),
+            ],
+            &HashMap::new(),
+            2,
+        );
+
+        let up: Vec<_> = metrics[0]
+            .data_points
+            .iter()
+            .map(|point| (point.attributes[0].1.as_str(), point.value))
+            .collect();
+        assert_eq!(up, [("Plex", Value::Int(1)), ("NAS", Value::Int(0))]);
+        assert_eq!(metrics[0].data_points[0].time_unix_nano, 2_000_000_000);
+        assert_eq!(metrics[1].data_points.len(), 1);
+        assert_eq!(metrics[1].data_points[0].value, Value::Double(120.0));
+        assert_eq!(metrics[2].data_points.len(), 2);
+    }
+
+    #[test]
+    fn uptime_metrics_report_window_stats_and_open_incidents() {
+        let check = |status, response_time_ms| HistoryEntry {
+            status,
+            response_time_ms,
+            failure_reason: None,
+            checked_at: 100,
+        };
+        let nas = UptimeHistory {
+            site_id: "NAS".to_string(),
+            status: UptimeStatus::Down,
+            timestamp: 2,
+            history: vec![
+                check(UptimeStatus::Up, Some(100)),
+                check(UptimeStatus::Up, Some(300)),
+                check(UptimeStatus::Down, Some(5000)),
+                check(UptimeStatus::Loading, None),
+            ],
+            uptime_percentage: 66.7,
+            response_time_ms: Some(5000),
+            max_history_entries: crate::uptime::MAX_HISTORY_ENTRIES,
+            latency_ms: Vec::new(),
+            down_since: Some(100),
+            last_checked: None,
+            last_change: None,
+        };
+        let incidents = HashMap::from([(
+            "NAS".to_string(),
+            OpenIncident {
+                id: 7,
+                started_at: 100,
+            },
+        )]);
+
+        let metrics = uptime_metrics(&[nas], &incidents, 160);
+        let value = |name: &str| {
+            let metric = metrics.iter().find(|metric| metric.name == name).unwrap();
+            metric.data_points[0].value
+        };
+
+        assert_eq!(value("iron_shield.site.checks"), Value::Int(3));
+        assert_eq!(value("iron_shield.site.failures"), Value::Int(1));
+        assert_eq!(
+            value("iron_shield.site.response_time.avg"),
+            Value::Double(200.0)
+        );
+        assert_eq!(
+            value("iron_shield.site.response_time.max"),
+            Value::Double(300.0)
+        );
+        assert_eq!(value("iron_shield.site.incident.duration"), Value::Int(60));
+        let incident = &metrics[7].data_points[0];
+        assert_eq!(incident.attributes[1], ("incident.id", "7".to_string()));
+    }
+
+    #[test]
+    fn sampling_keeps_changes_of_every_nth_snapshot_and_skips_excluded_sites() {
+        let history = |site: &str, status| UptimeHistory {
+            site_id: site.to_string(),
+            status,
+            timestamp: 2,
+            history: Vec::new(),
+            uptime_percentage: 50.0,
+            response_time_ms: None,
+            max_history_entries: crate::uptime::MAX_HISTORY_ENTRIES,
+            latency_ms: Vec::new(),
+            down_since: None,
+            last_checked: None,
+            last_change: None,
+        };
+        let sites = |plex, nas| {
+            vec![
+                history("Plex", plex),
+                history("NAS", nas),
+                history("Speedtest", UptimeStatus::Down),
+            ]
+        };
+        let sampled = |snapshot: Option<Vec<UptimeHistory>>| {
+            snapshot.map(|snapshot| {
+                snapshot
+                    .into_iter()
+                    .map(|history| history.site_id)
+                    .collect::<Vec<_>>()
+            })
+        };
+        let queue = TelemetryQueue::default();
+        let sampling = TelemetrySampling {
+            mode: SamplingMode::Changes,
+            every: 2,
+            exclude_sites: vec!["speedtest".to_string()],
+        };
+
+        let first = queue.sample(&sampling, sites(UptimeStatus::Up, UptimeStatus::Up));
+        assert_eq!(sampled(first).unwrap(), ["Plex", "NAS"]);
+        let skipped = queue.sample(&sampling, sites(UptimeStatus::Up, UptimeStatus::Down));
+        assert!(skipped.is_none());
+        let third = queue.sample(&sampling, sites(UptimeStatus::Up, UptimeStatus::Down));
+        assert_eq!(sampled(third).unwrap(), ["NAS"]);
+        queue.sample(&sampling, sites(UptimeStatus::Up, UptimeStatus::Down));
+        let unchanged = queue.sample(&sampling, sites(UptimeStatus::Up, UptimeStatus::Down));
+        assert!(unchanged.is_none());
+
+        let failures = TelemetrySampling {
+            mode: SamplingMode::Failures,
+            ..TelemetrySampling::default()
+        };
+        let down = TelemetryQueue::default()
+            .sample(&failures, sites(UptimeStatus::Up, UptimeStatus::Down));
+        assert_eq!(sampled(down).unwrap(), ["NAS", "Speedtest"]);
+    }
+
+    fn clicks(site: &str, time_unix_nano: u64) -> Vec<Metric> {
+        vec![Metric {
+            name: "iron_shield.site.clicks",
+            description: "Clicks on the link of the site",
+            unit: "",
+            kind: Kind::DeltaCounter,
+            data_points: vec![DataPoint {
+                attributes: vec![("site.name", site.to_string())],
+                time_unix_nano,
+                value: Value::Int(1),
+            }],
+        }]
+    }
+
+    #[test]
+    fn queue_drops_the_oldest_exports_when_full() {
+        let queue = TelemetryQueue::default();
+        for time in 0..=QUEUE_CAPACITY as u64 {
+            queue.push(clicks("Plex", time));
+        }
+        assert_eq!(queue.len(), QUEUE_CAPACITY);
+
+        let batch = queue.take_batch().unwrap();
+        assert_eq!(batch.len(), MAX_BATCH);
+        assert_eq!(batch[0][0].data_points[0].time_unix_nano, 1);
+
+        queue.push(clicks("NAS", 0));
+        queue.requeue(batch);
+        assert_eq!(queue.len(), QUEUE_CAPACITY);
+        let retried = queue.take_batch().unwrap();
+        assert_eq!(retried[0][0].data_points[0].time_unix_nano, 2);
+    }
+
+    #[test]
+    fn merge_batch_adds_up_clicks_per_site() {
+        let merged = merge_batch(&[clicks("Plex", 1), clicks("NAS", 2), clicks("Plex", 3)]);
+
+        assert_eq!(merged.len(), 1);
+        let points: Vec<_> = merged[0]
+            .data_points
+            .iter()
+            .map(|point| {
+                (
+                    point.attributes[0].1.as_str(),
+                    point.time_unix_nano,
+                    point.value,
+                )
+            })
+            .collect();
+        assert_eq!(
+            points,
+            [("Plex", 3, Value::Int(2)), ("NAS", 2, Value::Int(1))]
+        );
+    }
+}
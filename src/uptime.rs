@@ -1,22 +1,31 @@
-use crate::config::Config;
-use crate::telemetry::{self, telemetry_destination};
-use axum::{extract::State, response::Sse};
+use crate::checkers::{self, CheckResult};
+use crate::config::{CheckSettings, Config, SharedConfig, Site, SortBy};
+use crate::notifications;
+use crate::telemetry;
+use axum::{
+    extract::{Query, State},
+    http::HeaderMap,
+    response::{sse::KeepAlive, Sse},
+};
+use parking_lot::RwLock;
 use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, VecDeque};
 use std::convert::Infallible;
-use std::sync::{Arc, RwLock};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tokio::sync::Semaphore;
-use tokio_stream::{
-    wrappers::{BroadcastStream, UnboundedReceiverStream},
-    StreamExt,
-};
+use tokio_stream::{wrappers::BroadcastStream, StreamExt};
 use tokio_util::sync::CancellationToken;
-use tracing::{debug, error, info, warn};
+use tokio_util::task::TaskTracker;
+use tracing::{debug, error, info};
 
 /// Maximum number of historical uptime entries retained per site.
 pub const MAX_HISTORY_ENTRIES: usize = 50;
 
+/// Number of recent checks in a site's latency series, drawn as a sparkline.
+pub const LATENCY_SERIES_LENGTH: usize = 30;
+
 /// Represents the uptime status of a monitored website
 ///
 /// This enum is used to track the current status of a website during uptime monitoring.
@@ -68,6 +77,10 @@ pub enum UptimeStatus {
 /// * `history` - A collection of the last `MAX_HISTORY_ENTRIES` status checks for trend analysis
 /// * `uptime_percentage` - Calculated percentage of "up" time in the history (excluding Loading statuses)
 /// * `max_history_entries` - Maximum number of history samples retained (mirrors `MAX_HISTORY_ENTRIES`)
+/// * `latency_ms` - Response times of the recent checks for a sparkline (see [`latency_series`])
+/// * `down_since` - When the site went down, if its latest completed check failed
+/// * `last_checked` - When the latest completed check started
+/// * `last_change` - When the site entered its current status
 ///
 /// # Examples
 ///
@@ -83,19 +96,29 @@ pub enum UptimeStatus {
 ///         HistoryEntry {
 ///             status: UptimeStatus::Up,
 ///             response_time_ms: Some(150),
+///             failure_reason: None,
+///             checked_at: 1234567830,
 ///         },
 ///         HistoryEntry {
 ///             status: UptimeStatus::Up,
 ///             response_time_ms: Some(160),
+///             failure_reason: None,
+///             checked_at: 1234567860,
 ///         },
 ///         HistoryEntry {
 ///             status: UptimeStatus::Down,
 ///             response_time_ms: None,
+///             failure_reason: None,
+///             checked_at: 1234567890,
 ///         },
 ///     ],
 ///     uptime_percentage: 66.67,
 ///     response_time_ms: Some(180),
 ///     max_history_entries: iron_shield::uptime::MAX_HISTORY_ENTRIES,
+///     latency_ms: vec![Some(150), Some(160), None],
+///     down_since: Some(1234567890),
+///     last_checked: Some(1234567890),
+///     last_change: Some(1234567890),
 /// };
 ///
 /// println!("Site {} has {}% uptime", history.site_id, history.uptime_percentage);
@@ -106,6 +129,12 @@ pub struct HistoryEntry {
     pub status: UptimeStatus,
     /// Optional response time in milliseconds (only present for completed checks)
     pub response_time_ms: Option<u64>,
+    /// Why the check failed, such as the first failing response assertion
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub failure_reason: Option<String>,
+    /// Unix timestamp in seconds when the check started, 0 if unknown
+    #[serde(default)]
+    pub checked_at: u64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -124,13 +153,32 @@ pub struct UptimeHistory {
     pub response_time_ms: Option<u64>,
     /// Maximum number of history entries retained for this site
     pub max_history_entries: usize,
+    /// Response times of the recent checks for a sparkline (see [`latency_series`])
+    #[serde(default)]
+    pub latency_ms: Vec<Option<u64>>,
+    /// Unix timestamp in seconds since when the site has been down (see [`down_since`])
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub down_since: Option<u64>,
+    /// Unix timestamp in seconds when the latest completed check started (see
+    /// [`last_checked`])
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_checked: Option<u64>,
+    /// Unix timestamp in seconds since when the site has had its current status (see
+    /// [`last_change`])
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_change: Option<u64>,
 }
 
-/// Result of a single uptime probe with the measured response time.
-#[derive(Debug, Clone, Copy)]
-struct SiteCheckResult {
-    status: UptimeStatus,
-    response_time_ms: Option<u64>,
+/// A notice broadcast to every connected SSE client
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ServerEvent {
+    /// The server is shutting down; carries the message shown on dashboards
+    Maintenance(String),
+    /// A new configuration was loaded, so dashboards should refresh their site list
+    ConfigUpdated,
+    /// An edited config file was rejected and the last valid configuration kept; carries
+    /// the reason shown on dashboards
+    ConfigInvalid(String),
 }
 
 /// Shared state for the uptime monitoring service with historical data
@@ -149,40 +197,86 @@ struct SiteCheckResult {
 /// ```
 /// use iron_shield::uptime::UptimeState;
 /// use iron_shield::config::Config;
-/// use std::collections::HashMap;
-/// use std::sync::{Arc, RwLock};
 /// use std::path::PathBuf;
-/// use tokio::sync::broadcast;
-/// use tokio_util::sync::CancellationToken;
 ///
-/// let config = Arc::new(RwLock::new(Config {
+/// let config = Config {
 ///     site_name: "Test Site".to_string(),
-///     clock: iron_shield::config::Clock::None,
-///     opentelemetry_endpoint: None,
-///     sites: vec![],
-/// }));
-/// let history = Arc::new(RwLock::new(HashMap::new()));
-/// let (shutdown_events, _) = broadcast::channel(1);
-/// let shutdown_token = CancellationToken::new();
-/// let uptime_state = UptimeState {
-///     config,
-///     history,
-///     config_file_path: PathBuf::from("config.json5"),
-///     shutdown_events,
-///     shutdown_token,
+///     ..Config::default()
 /// };
+/// let uptime_state = UptimeState::new(config, PathBuf::from("config.json5"));
+/// assert!(uptime_state.history.read().is_empty());
 /// ```
 pub struct UptimeState {
-    /// Thread-safe access to the application configuration
-    pub config: Arc<RwLock<Config>>,
+    /// The running application configuration
+    pub config: Arc<SharedConfig>,
+    /// Why the config file on disk was rejected on reload, while the last valid
+    /// configuration stays in use
+    pub config_error: Arc<RwLock<Option<String>>>,
     /// Thread-safe map of site histories (`site_id` -> `VecDeque` of `UptimeStatus`)
+    ///
+    /// The lock cannot be poisoned, and its guards cannot be held across an `.await` in
+    /// a spawned task, since they are not `Send`.
     pub history: Arc<RwLock<HashMap<String, VecDeque<HistoryEntry>>>>,
     /// Path to the configuration file for reloading purposes
     pub config_file_path: std::path::PathBuf,
-    /// Broadcast channel used to notify connected SSE clients about shutdowns
-    pub shutdown_events: tokio::sync::broadcast::Sender<String>,
+    /// Broadcast channel used to notify connected SSE clients about shutdowns and
+    /// configuration changes
+    pub server_events: tokio::sync::broadcast::Sender<ServerEvent>,
+    /// Results of the uptime monitor, forwarded to every open `/uptime` stream
+    pub uptime_updates: tokio::sync::broadcast::Sender<Vec<UptimeHistory>>,
     /// Cancellation token to gracefully stop background uptime tasks
     pub shutdown_token: CancellationToken,
+    /// Fingerprint of the frontend bundle appended to asset URLs for cache busting
+    pub asset_version: String,
+    /// Tracks uptime monitoring tasks so shutdown can wait for in-flight checks
+    pub background_tasks: TaskTracker,
+    /// Last SSE event ID handed out; shared so IDs keep increasing across reconnects
+    pub last_event_id: AtomicU64,
+    /// Last report of the weather widget, kept for its refresh interval
+    pub weather: crate::weather::WeatherCache,
+    /// Page templates read from the configured `templates_dir`
+    pub templates: crate::custom_templates::TemplateOverrides,
+    /// Key signing the per-browser preferences cookie
+    pub preferences_key: crate::preferences::SigningKey,
+    /// Telemetry exports waiting to be sent to the collector
+    pub telemetry: crate::telemetry::TelemetryQueue,
+    /// Open incidents, which notifications are sent for
+    pub incidents: crate::notifications::IncidentTracker,
+    /// Sites whose notifications are muted for a while
+    pub silences: crate::alerts::Silences,
+    /// How far the system clock is off from monitored sites, if noticeably
+    pub clock_drift: crate::clock_drift::ClockDrift,
+    /// Open connections and check load of the process, reported at `/api/self`
+    pub self_monitor: crate::self_monitor::SelfMonitor,
+}
+
+impl UptimeState {
+    /// State for `config`, read from `config_file_path`, before any check has run
+    ///
+    /// Nothing is connected to its channels yet, its caches are empty and it signs
+    /// preferences with a fresh random key.
+    pub fn new(config: Config, config_file_path: std::path::PathBuf) -> Self {
+        Self {
+            config: Arc::new(SharedConfig::new(config)),
+            config_error: Arc::new(RwLock::new(None)),
+            history: Arc::new(RwLock::new(HashMap::new())),
+            config_file_path,
+            server_events: tokio::sync::broadcast::channel(16).0,
+            uptime_updates: tokio::sync::broadcast::channel(64).0,
+            shutdown_token: CancellationToken::new(),
+            asset_version: String::new(),
+            background_tasks: TaskTracker::new(),
+            last_event_id: AtomicU64::new(0),
+            weather: crate::weather::WeatherCache::default(),
+            templates: crate::custom_templates::TemplateOverrides::default(),
+            preferences_key: crate::preferences::SigningKey::random(),
+            telemetry: crate::telemetry::TelemetryQueue::default(),
+            incidents: crate::notifications::IncidentTracker::default(),
+            silences: crate::alerts::Silences::default(),
+            clock_drift: crate::clock_drift::ClockDrift::default(),
+            self_monitor: crate::self_monitor::SelfMonitor::default(),
+        }
+    }
 }
 
 /// Snapshot the current uptime histories for all tracked sites.
@@ -190,13 +284,7 @@ pub struct UptimeState {
 /// This helper clones the per-site history map so other tasks are not blocked while
 /// telemetry payloads are being built.
 pub fn snapshot_current_histories(state: &UptimeState) -> Vec<UptimeHistory> {
-    let history_guard = match state.history.read() {
-        Ok(guard) => guard,
-        Err(err) => {
-            error!("Failed to acquire history read lock for snapshot: {err}");
-            return Vec::new();
-        }
-    };
+    let history_guard = state.history.read();
 
     history_guard
         .iter()
@@ -218,351 +306,482 @@ pub fn snapshot_current_histories(state: &UptimeState) -> Vec<UptimeHistory> {
         .collect()
 }
 
-/// Handles the uptime monitoring stream endpoint using Server-Sent Events (SSE)
-///
-/// This function creates a real-time stream of uptime status updates for all configured sites.
-/// It periodically checks the status of each site (every 5 seconds by default) and pushes updates
-/// to connected clients via Server-Sent Events. The function limits concurrent site checks to
-/// prevent overwhelming the system with too many HTTP requests at once.
-///
-/// The implementation initializes all sites with a "Loading" status and then begins periodic checks.
-/// It maintains a history of the last `MAX_HISTORY_ENTRIES` status checks for each site and calculates the uptime
-/// percentage based on successful checks (excluding "Loading" statuses from the calculation).
-///
-/// # Arguments
-///
-/// * `state` - Shared uptime state containing configuration and historical data
-///
-/// # Returns
+/// Query parameters accepted by the uptime SSE endpoint
 ///
-/// An SSE stream that continuously sends `UptimeHistory` data for all monitored sites
+/// All filters are optional and combine with each other, so
+/// `/uptime?dashboard=work&tag=prod` streams the production sites of the `work` dashboard.
 ///
-/// # Panics
-///
-/// This function might panic if `semaphore.acquire().await.unwrap()` fails.
-/// However, this should not happen in practice as the semaphore is always available.
-///
-/// # Examples
-///
-/// Using this in an Axum router:
-///
-/// ```rust,no_run
-/// use axum::{Router, routing::get};
-/// use iron_shield::uptime::{uptime_stream, UptimeState};
-/// use std::sync::{Arc, RwLock};
-/// use std::collections::HashMap;
-/// use std::collections::VecDeque;
+/// # Fields
 ///
-/// // Assuming you have an uptime_state set up
-/// let app = Router::new()
-///     .route("/uptime", get(uptime_stream));
-/// ```
-#[allow(clippy::too_many_lines)]
-pub async fn uptime_stream(
-    State(state): State<Arc<UptimeState>>,
-) -> Sse<impl tokio_stream::Stream<Item = Result<axum::response::sse::Event, Infallible>>> {
-    // Clone the config and history for use in the stream
-    let config = state.config.clone();
-    let history_map = state.history.clone();
-    let client = reqwest::Client::new();
-
-    // Create a semaphore to limit concurrent site checks
-    let semaphore = Arc::new(Semaphore::new(10)); // Limit to 10 concurrent checks
+/// * `dashboard` - Only stream updates for the sites shown on this named dashboard
+/// * `tag` - Only stream sites carrying one of these comma-separated tags
+/// * `category` - Only stream sites in this category
+#[derive(Debug, Default, Clone, Deserialize)]
+pub struct UptimeQuery {
+    /// Name of the dashboard whose sites should be streamed
+    pub dashboard: Option<String>,
+    /// Comma-separated tags; a site matches if it has any of them (case-insensitive)
+    pub tag: Option<String>,
+    /// Category a site must belong to (case-insensitive)
+    pub category: Option<String>,
+}
 
-    // Create a channel to send updates from the checker task
-    let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
-    let shutdown_token = state.shutdown_token.clone();
-    let shutdown_receiver = state.shutdown_events.subscribe();
-
-    {
-        if let Ok(config_guard) = config.read() {
-            let sites_to_initialize = config_guard.sites.clone();
-            drop(config_guard); // Release the read lock immediately
-
-            for site in &sites_to_initialize {
-                if let Ok(mut history_guard) = history_map.write() {
-                    history_guard
-                        .entry(site.name.clone())
-                        .or_insert_with(VecDeque::new);
-                } else {
-                    error!(
-                        "Failed to acquire history write lock for initialization of site: {}",
-                        site.name
-                    );
-                }
-            }
-        } else {
-            error!("Failed to acquire config read lock for initial history setup");
-        }
+impl UptimeQuery {
+    /// Select the configured sites this stream should monitor.
+    ///
+    /// An unknown dashboard name selects no sites rather than falling back to all of them.
+    #[must_use]
+    pub fn select_sites(&self, config: &Config) -> Vec<Site> {
+        let sites = match self.dashboard.as_deref() {
+            Some(name) => config
+                .dashboard(name)
+                .map(|dashboard| config.dashboard_sites(dashboard))
+                .unwrap_or_default(),
+            None => config.sites.clone(),
+        };
+        sites
+            .into_iter()
+            .filter(|site| self.matches(site))
+            .collect()
     }
 
-    // Spawn a task to periodically check sites and send updates
-    let shutdown_token_for_task = shutdown_token.clone();
-    let telemetry_state = Arc::clone(&state);
-    tokio::spawn(async move {
-        info!("Starting uptime monitoring service");
-
-        let mut ticker = tokio::time::interval(Duration::from_secs(1));
-        let mut last_check_times: HashMap<String, Instant> = HashMap::new();
-
-        loop {
-            tokio::select! {
-                () = shutdown_token_for_task.cancelled() => {
-                    info!("Stopping uptime monitoring service due to shutdown signal");
-                    break;
-                }
-                _ = ticker.tick() => {}
-            }
+    /// Checks whether a site passes the `tag` and `category` filters.
+    #[must_use]
+    pub fn matches(&self, site: &Site) -> bool {
+        let wanted_tags: Vec<&str> = self
+            .tag
+            .as_deref()
+            .map(|tags| {
+                tags.split(',')
+                    .map(str::trim)
+                    .filter(|tag| !tag.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default();
+        let tag_matches = wanted_tags.is_empty()
+            || wanted_tags
+                .iter()
+                .any(|tag| site.tags.iter().any(|own| own.eq_ignore_ascii_case(tag)));
+        let category_matches = self
+            .category
+            .as_deref()
+            .map(str::trim)
+            .filter(|category| !category.is_empty())
+            .is_none_or(|category| site.category.eq_ignore_ascii_case(category));
+        tag_matches && category_matches
+    }
+}
 
-            let now = Instant::now();
+/// Parses the `Last-Event-ID` header sent by reconnecting `EventSource` clients.
+fn parse_last_event_id(headers: &HeaderMap) -> Option<u64> {
+    headers
+        .get("last-event-id")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse().ok())
+}
 
-            let sites_snapshot = {
-                match config.read() {
-                    Ok(guard) => guard.sites.clone(),
-                    Err(e) => {
-                        error!("Failed to acquire config read lock: {e}");
-                        continue;
-                    }
-                }
-            };
+/// Builds the snapshot replayed to reconnecting clients, limited to configured sites.
+fn replay_snapshot(state: &UptimeState, query: &UptimeQuery) -> Vec<UptimeHistory> {
+    let site_names: Vec<String> = query
+        .select_sites(&state.config.load())
+        .into_iter()
+        .map(|site| site.name)
+        .collect();
+
+    let mut snapshot: Vec<UptimeHistory> = snapshot_current_histories(state)
+        .into_iter()
+        .filter(|history| site_names.contains(&history.site_id) && !history.history.is_empty())
+        .collect();
+    snapshot.sort_by_key(|history| {
+        site_names
+            .iter()
+            .position(|name| *name == history.site_id)
+            .unwrap_or(usize::MAX)
+    });
+    snapshot
+}
 
-            last_check_times
-                .retain(|site_name, _| sites_snapshot.iter().any(|site| site.name == *site_name));
+/// Checks every configured site for as long as the server runs
+///
+/// The server starts this once, so sites are checked, incidents are tracked, and
+/// notifications and metrics are sent whether or not a dashboard is open. Every second,
+/// the sites whose check interval has passed are checked, at most
+/// `monitoring.concurrency` at a time, with the interval, timeout, retries, and user agent
+/// from [`Site::check_settings`].
+///
+/// Each site starts out with a "Loading" status. The history keeps the last
+/// `MAX_HISTORY_ENTRIES` checks of a site, and every change to it is broadcast on
+/// [`UptimeState::uptime_updates`] to the open `/uptime` streams. Once all checks of a
+/// cycle have finished, the uptime snapshot is sent to StatsD, Graphite, and InfluxDB
+/// where they are configured.
+///
+/// Returns once the shutdown token is cancelled.
+pub async fn run_monitor(state: Arc<UptimeState>) {
+    info!("Starting uptime monitoring service");
 
-            let disabled_updates = {
-                let mut history_guard = match history_map.write() {
-                    Ok(guard) => guard,
-                    Err(e) => {
-                        error!("Failed to acquire history write lock: {e}");
-                        continue;
-                    }
-                };
-
-                let mut updates = Vec::new();
-                for site in &sites_snapshot {
-                    let site_history = history_guard
-                        .entry(site.name.clone())
-                        .or_insert_with(VecDeque::new);
-
-                    if site.disabled {
-                        last_check_times.remove(&site.name);
-                        let last_status = site_history.back().map(|entry| entry.status);
-                        if last_status != Some(UptimeStatus::Disabled) {
-                            apply_final_status(site_history, UptimeStatus::Disabled, None);
-                            let uptime_percentage = calculate_uptime_percentage(site_history);
-                            updates.push(create_uptime_history(
-                                &site.name,
-                                UptimeStatus::Disabled,
-                                site_history,
-                                uptime_percentage,
-                                None,
-                            ));
-                        }
-                    }
-                }
-                updates
-            };
+    let mut ticker = tokio::time::interval(Duration::from_secs(1));
+    let mut last_check_times: HashMap<String, Instant> = HashMap::new();
 
-            if !disabled_updates.is_empty() {
-                if let Err(err) = tx.send(disabled_updates) {
-                    info!(
-                        error = %err,
-                        "SSE client disconnected before receiving disabled updates; stopping monitoring loop"
-                    );
-                    break;
-                }
+    loop {
+        tokio::select! {
+            () = state.shutdown_token.cancelled() => {
+                info!("Stopping uptime monitoring service due to shutdown signal");
+                break;
             }
+            _ = ticker.tick() => {}
+        }
 
-            let mut sites_due = Vec::new();
-            for site in &sites_snapshot {
-                if site.disabled {
-                    continue;
-                }
+        run_cycle(&state, &mut last_check_times).await;
+        state.self_monitor.record_tick();
+    }
+}
 
-                let interval_secs = site
-                    .monitor_interval_secs
-                    .max(crate::config::MIN_MONITOR_INTERVAL_SECS);
-                let required_interval = Duration::from_secs(interval_secs);
+/// Checks the sites that are due, given when each of them was last checked, and returns
+/// once all of those checks have finished.
+#[allow(clippy::too_many_lines)]
+async fn run_cycle(state: &Arc<UptimeState>, last_check_times: &mut HashMap<String, Instant>) {
+    let now = Instant::now();
 
-                let should_check = match last_check_times.get(&site.name) {
-                    Some(&last_time) => now.duration_since(last_time) >= required_interval,
-                    None => true,
-                };
+    let (sites_snapshot, monitoring) = {
+        let config = state.config.load();
+        (config.sites.clone(), config.monitoring.clone())
+    };
 
-                if should_check {
-                    last_check_times.insert(site.name.clone(), now);
-                    sites_due.push(site.clone());
-                }
-            }
+    last_check_times
+        .retain(|site_name, _| sites_snapshot.iter().any(|site| site.name == *site_name));
 
-            if sites_due.is_empty() {
-                continue;
-            }
+    let disabled_updates = {
+        let mut history_guard = state.history.write();
 
-            let loading_updates = {
-                let mut history_guard = match history_map.write() {
-                    Ok(guard) => guard,
-                    Err(e) => {
-                        error!("Failed to acquire history write lock: {e}");
-                        continue;
-                    }
-                };
-
-                let mut updates = Vec::new();
-                for site in &sites_due {
-                    let site_history = history_guard
-                        .entry(site.name.clone())
-                        .or_insert_with(VecDeque::new);
-                    site_history.push_back(HistoryEntry {
-                        status: UptimeStatus::Loading,
-                        response_time_ms: None,
-                    });
-                    if site_history.len() > MAX_HISTORY_ENTRIES {
-                        site_history.pop_front();
-                    }
+        let mut updates = Vec::new();
+        for site in &sites_snapshot {
+            let site_history = history_guard.entry(site.name.clone()).or_default();
 
+            if site.disabled {
+                last_check_times.remove(&site.name);
+                let last_status = site_history.back().map(|entry| entry.status);
+                if last_status != Some(UptimeStatus::Disabled) {
+                    apply_final_status(site_history, UptimeStatus::Disabled, None, None);
                     let uptime_percentage = calculate_uptime_percentage(site_history);
                     updates.push(create_uptime_history(
                         &site.name,
-                        UptimeStatus::Loading,
+                        UptimeStatus::Disabled,
                         site_history,
                         uptime_percentage,
                         None,
                     ));
                 }
+            }
+        }
+        updates
+    };
 
-                updates
-            };
+    if !disabled_updates.is_empty() {
+        publish(state, disabled_updates);
+    }
 
-            if let Err(err) = tx.send(loading_updates) {
-                info!(
-                    error = %err,
-                    "SSE client disconnected before receiving loading updates; stopping monitoring loop"
-                );
-                break;
-            }
+    let mut sites_due = Vec::new();
+    for site in &sites_snapshot {
+        if site.disabled {
+            continue;
+        }
 
-            let mut tasks = Vec::new();
-            for site in sites_due {
-                let client = client.clone();
-                let url = site.url.clone();
-                let tx = tx.clone();
-                let history_map = history_map.clone();
-                let site_name = site.name.clone();
-                let semaphore = semaphore.clone();
-
-                let task = tokio::spawn(async move {
-                    let _permit = semaphore.acquire().await.unwrap();
-                    debug!("Starting uptime check for site: {site_name}");
-
-                    let SiteCheckResult {
-                        status,
-                        response_time_ms,
-                    } = check_site_status(&client, &url).await;
-                    debug!(
-                        "Uptime check completed for site: {site_name}, status: {status:?}, response_time_ms={response_time_ms:?}"
-                    );
-
-                    {
-                        let mut history_guard = match history_map.write() {
-                            Ok(guard) => guard,
-                            Err(e) => {
-                                error!("Failed to acquire history write lock: {e}");
-                                return;
-                            }
-                        };
-                        let site_history = history_guard
-                            .entry(site_name.clone())
-                            .or_insert_with(VecDeque::new);
-                        apply_final_status(site_history, status, response_time_ms);
-                    }
+        let settings = site.check_settings(&monitoring);
+        let required_interval = Duration::from_secs(
+            settings
+                .interval_secs
+                .max(crate::config::MIN_MONITOR_INTERVAL_SECS),
+        );
 
-                    {
-                        let history_guard = match history_map.read() {
-                            Ok(guard) => guard,
-                            Err(e) => {
-                                error!("Failed to acquire history read lock: {e}");
-                                return;
-                            }
-                        };
-                        if let Some(site_history) = history_guard.get(&site_name) {
-                            let latest_response_time =
-                                site_history.back().and_then(|entry| entry.response_time_ms);
-                            let uptime_percentage = calculate_uptime_percentage(site_history);
-
-                            debug!(
-                                "Updated uptime stats: site={site_name}, status={status:?}, percentage={uptime_percentage:.2}%"
-                            );
-
-                            let data = create_uptime_history(
-                                &site_name,
-                                status,
-                                site_history,
-                                uptime_percentage,
-                                latest_response_time,
-                            );
-
-                            if let Err(err) = tx.send(vec![data]) {
-                                info!(
-                                    site = %site_name,
-                                    error = %err,
-                                    "SSE client disconnected before receiving uptime update; skipping send"
-                                );
-                            }
-                        }
-                    }
-                });
+        let should_check = match last_check_times.get(&site.name) {
+            Some(&last_time) => now.duration_since(last_time) >= required_interval,
+            None => true,
+        };
 
-                tasks.push(task);
-            }
+        if should_check {
+            last_check_times.insert(site.name.clone(), now);
+            sites_due.push((site.clone(), settings));
+        }
+    }
 
-            for task in tasks {
-                let _ = task.await;
-            }
+    if sites_due.is_empty() {
+        return;
+    }
 
-            if let Some((endpoint, dashboard_name)) = telemetry_destination(&telemetry_state) {
-                let state_for_snapshot = Arc::clone(&telemetry_state);
-                tokio::spawn(async move {
-                    if let Err(err) = telemetry::send_uptime_snapshot(
-                        state_for_snapshot,
-                        dashboard_name,
-                        endpoint,
-                    )
-                    .await
-                    {
-                        warn!("Failed to send telemetry snapshot after uptime cycle: {err}");
-                    }
-                });
+    let loading_updates = {
+        let mut history_guard = state.history.write();
+
+        let mut updates = Vec::new();
+        for (site, _) in &sites_due {
+            let site_history = history_guard.entry(site.name.clone()).or_default();
+            site_history.push_back(HistoryEntry {
+                status: UptimeStatus::Loading,
+                response_time_ms: None,
+                failure_reason: None,
+                checked_at: unix_now(),
+            });
+            if site_history.len() > MAX_HISTORY_ENTRIES {
+                site_history.pop_front();
             }
+
+            let uptime_percentage = calculate_uptime_percentage(site_history);
+            updates.push(create_uptime_history(
+                &site.name,
+                UptimeStatus::Loading,
+                site_history,
+                uptime_percentage,
+                None,
+            ));
         }
-    });
+
+        updates
+    };
+
+    publish(state, loading_updates);
+
+    // Limit concurrent site checks so many sites do not overwhelm the host with requests
+    let semaphore = Arc::new(Semaphore::new(monitoring.concurrency.max(1)));
+    let background_tasks = state.background_tasks.clone();
+    let cycle_started = Instant::now();
+    let mut tasks = Vec::new();
+    for (site, settings) in sites_due {
+        let url = site.url.clone();
+        let site_name = site.name.clone();
+        let semaphore = semaphore.clone();
+        let state = Arc::clone(state);
+        let queued = state.self_monitor.check_queued();
+
+        let task = background_tasks.spawn(async move {
+            let _permit = semaphore.acquire().await.unwrap();
+            drop(queued);
+            let _running = state.self_monitor.check_started();
+            debug!("Starting uptime check for site: {site_name}");
+
+            let started_at = unix_now_nanos();
+            let CheckResult {
+                status,
+                response_time_ms,
+                failure_reason,
+                events,
+                clock_offset_secs,
+            } = checkers::check(&url, &settings).await;
+            if let Some(offset) = clock_offset_secs {
+                let warn_secs = state.config.load().clock_drift_warn_secs;
+                state.clock_drift.record(offset, warn_secs);
+            }
+            telemetry::queue_check_span(
+                &state,
+                &site_name,
+                &url,
+                started_at,
+                failure_reason.as_deref(),
+                events,
+            );
+            debug!(
+                site = %site_name,
+                status = ?status,
+                latency_ms = response_time_ms,
+                failure_reason = failure_reason.as_deref(),
+                "Uptime check completed"
+            );
+
+            let (notification, update) = {
+                let mut history_guard = state.history.write();
+                let site_history = history_guard.entry(site_name.clone()).or_default();
+                apply_final_status(site_history, status, response_time_ms, failure_reason);
+                let notification = site_history
+                    .back()
+                    .and_then(|entry| state.incidents.record(&site_name, entry));
+
+                let latest_response_time =
+                    site_history.back().and_then(|entry| entry.response_time_ms);
+                let uptime_percentage = calculate_uptime_percentage(site_history);
+                debug!(
+                    "Updated uptime stats: site={site_name}, status={status:?}, percentage={uptime_percentage:.2}%"
+                );
+                let update = create_uptime_history(
+                    &site_name,
+                    status,
+                    site_history,
+                    uptime_percentage,
+                    latest_response_time,
+                );
+                (notification, update)
+            };
+            if let Some(notification) = notification {
+                notifications::dispatch(&state, notification);
+            }
+            publish(&state, vec![update]);
+        });
+
+        tasks.push(task);
+    }
+
+    for task in tasks {
+        let _ = task.await;
+    }
+    state.self_monitor.record_cycle(cycle_started.elapsed());
+
+    crate::statsd::send_uptime_snapshot(state).await;
+    crate::graphite::send_uptime_snapshot(state).await;
+    crate::influxdb::write_uptime_snapshot(state).await;
+}
+
+/// Sends `updates` to every open `/uptime` stream.
+fn publish(state: &UptimeState, updates: Vec<UptimeHistory>) {
+    // Sending only fails when no dashboard is connected
+    let _ = state.uptime_updates.send(updates);
+}
+
+/// Handles the uptime monitoring stream endpoint using Server-Sent Events (SSE)
+///
+/// This function creates a real-time stream of uptime status updates for the configured
+/// sites. The stream only forwards what [`run_monitor`] broadcasts; opening it does not
+/// check anything, so any number of open dashboards cost the same checks as one.
+///
+/// The current snapshot of the sites is sent first, so the dashboard does not show stale
+/// data until each site's next check completes. Every update carries the full retained
+/// history of its site, so the snapshot also replays anything a reconnecting client
+/// (one sending a `Last-Event-ID` header) missed, and it is sent again if the stream
+/// falls too far behind the monitor. Every update carries an incrementing event ID.
+/// While the stream is idle, a keep-alive comment is sent every `sse_keep_alive_secs`
+/// seconds (read when the client connects; `0` disables keep-alives).
+///
+/// Passing `?dashboard={name}` limits the stream to the sites of that named dashboard, and
+/// `?tag=...&category=...` further limits it to matching sites, so clients only receive
+/// what they display.
+///
+/// # Arguments
+///
+/// * `state` - Shared uptime state containing configuration and historical data
+/// * `query` - Optional filters selecting which sites are streamed
+/// * `headers` - Request headers, inspected for `Last-Event-ID`
+///
+/// # Returns
+///
+/// An SSE stream that continuously sends `UptimeHistory` data for the selected sites
+///
+/// # Examples
+///
+/// Using this in an Axum router:
+///
+/// ```rust,no_run
+/// use axum::{Router, routing::get};
+/// use iron_shield::uptime::{uptime_stream, UptimeState};
+/// use std::sync::Arc;
+/// use std::collections::HashMap;
+/// use std::collections::VecDeque;
+///
+/// // Assuming you have an uptime_state set up
+/// let app = Router::new()
+///     .route("/uptime", get(uptime_stream));
+/// ```
+pub async fn uptime_stream(
+    State(state): State<Arc<UptimeState>>,
+    Query(query): Query<UptimeQuery>,
+    headers: HeaderMap,
+) -> Sse<impl tokio_stream::Stream<Item = Result<axum::response::sse::Event, Infallible>>> {
+    let shutdown_token = state.shutdown_token.clone();
+    let server_events = state.server_events.subscribe();
+    let keep_alive_secs = state.config.load().sse_keep_alive_secs;
+
+    // Subscribe before taking the snapshot so no result falls between the two
+    let updates = state.uptime_updates.subscribe();
+    let snapshot = replay_snapshot(&state, &query);
+    if let Some(last_event_id) = parse_last_event_id(&headers) {
+        info!(
+            last_event_id,
+            sites = snapshot.len(),
+            "SSE client reconnected; replaying current uptime snapshot"
+        );
+    }
 
     info!("Client connected to uptime SSE stream");
 
-    // Convert the receiving end of the channel into a stream
-    let uptime_stream = UnboundedReceiverStream::new(rx).map(|uptime_data| {
-        if let Ok(event) = axum::response::sse::Event::default().json_data(&uptime_data) {
-            Ok(event)
-        } else {
-            error!("Failed to serialize uptime data for SSE");
-            Ok(axum::response::sse::Event::default().data("Error"))
+    let update_state = Arc::clone(&state);
+    let monitor_updates = BroadcastStream::new(updates).map(move |result| match result {
+        Ok(updates) => {
+            let site_names: Vec<String> = query
+                .select_sites(&update_state.config.load())
+                .into_iter()
+                .map(|site| site.name)
+                .collect();
+            updates
+                .into_iter()
+                .filter(|history| site_names.contains(&history.site_id))
+                .collect()
+        }
+        Err(err) => {
+            debug!("SSE client fell behind the uptime monitor ({err}); resending snapshot");
+            replay_snapshot(&update_state, &query)
         }
     });
 
-    let maintenance_stream =
-        BroadcastStream::new(shutdown_receiver).filter_map(|result| match result {
-            Ok(message) => Some(Ok(axum::response::sse::Event::default()
-                .event("maintenance")
-                .data(message))),
+    let event_state = Arc::clone(&state);
+    let connection = state.self_monitor.sse_connected();
+    let uptime_stream = tokio_stream::iter([snapshot])
+        .chain(monitor_updates)
+        .filter(|uptime_data| !uptime_data.is_empty())
+        .map(move |uptime_data| {
+            // Owned by the stream, so the connection is counted until the client goes away
+            let _connection = &connection;
+            let event_id = event_state.last_event_id.fetch_add(1, Ordering::Relaxed) + 1;
+            if let Ok(event) = axum::response::sse::Event::default()
+                .id(event_id.to_string())
+                .json_data(&uptime_data)
+            {
+                Ok(event)
+            } else {
+                error!("Failed to serialize uptime data for SSE");
+                Ok(axum::response::sse::Event::default().data("Error"))
+            }
+        });
+
+    let server_event_stream =
+        BroadcastStream::new(server_events).filter_map(|result| match result {
+            Ok(ServerEvent::Maintenance(message)) => {
+                Some(Ok(axum::response::sse::Event::default()
+                    .event("maintenance")
+                    .data(message)))
+            }
+            Ok(ServerEvent::ConfigUpdated) => Some(Ok(axum::response::sse::Event::default()
+                .event("config-updated")
+                .data("{}"))),
+            Ok(ServerEvent::ConfigInvalid(message)) => {
+                Some(Ok(axum::response::sse::Event::default()
+                    .event("config-invalid")
+                    .data(message)))
+            }
             Err(e) => {
-                error!("Failed to read shutdown notification for SSE: {e}");
+                error!("Failed to read server notification for SSE: {e}");
                 None
             }
         });
 
-    let stream = uptime_stream.merge(maintenance_stream);
-
-    Sse::new(stream)
+    // End the stream once shutdown starts so graceful shutdown does not wait on open
+    // dashboards. Pending events are preferred over the shutdown marker so the
+    // maintenance notice is still delivered before the connection closes.
+    let shutdown_marker =
+        futures_util::stream::once(shutdown_token.cancelled_owned()).map(|()| None);
+    let stream = futures_util::stream::select_with_strategy(
+        uptime_stream.merge(server_event_stream).map(Some),
+        shutdown_marker,
+        |(): &mut ()| futures_util::stream::PollNext::Left,
+    )
+    .map_while(std::convert::identity);
+
+    // Idle connections are dropped by some proxies and browsers when every site has a
+    // long check interval, so send a comment line whenever the stream has been quiet
+    let sse = Sse::new(stream);
+    if keep_alive_secs == 0 {
+        sse
+    } else {
+        sse.keep_alive(
+            KeepAlive::new()
+                .interval(Duration::from_secs(keep_alive_secs))
+                .text("keep-alive"),
+        )
+    }
 }
 
 /// Helper function to calculate the uptime percentage based on site history
@@ -614,6 +833,133 @@ pub fn calculate_uptime_percentage(site_history: &VecDeque<HistoryEntry>) -> f64
     }
 }
 
+/// Response times of a site's last [`LATENCY_SERIES_LENGTH`] completed checks, oldest
+/// first, with `None` for failed checks
+///
+/// Checks still in progress and paused entries are skipped, so the series only shows
+/// measured latency and gaps where the site was down.
+///
+/// # Examples
+///
+/// ```
+/// use iron_shield::uptime::{latency_series, HistoryEntry, UptimeStatus};
+/// use std::collections::VecDeque;
+///
+/// let entry = |status, response_time_ms| HistoryEntry {
+///     status,
+///     response_time_ms,
+///     failure_reason: None,
+///     checked_at: 0,
+/// };
+/// let history = VecDeque::from([
+///     entry(UptimeStatus::Up, Some(120)),
+///     entry(UptimeStatus::Down, None),
+///     entry(UptimeStatus::Loading, None),
+/// ]);
+///
+/// assert_eq!(latency_series(&history), [Some(120), None]);
+/// ```
+#[must_use]
+pub fn latency_series(site_history: &VecDeque<HistoryEntry>) -> Vec<Option<u64>> {
+    let completed: Vec<Option<u64>> = site_history
+        .iter()
+        .filter(|entry| matches!(entry.status, UptimeStatus::Up | UptimeStatus::Down))
+        .map(|entry| {
+            entry
+                .response_time_ms
+                .filter(|_| entry.status == UptimeStatus::Up)
+        })
+        .collect();
+    let skip = completed.len().saturating_sub(LATENCY_SERIES_LENGTH);
+    completed[skip..].to_vec()
+}
+
+/// Reorder `sites` by `sort_by`, using their recorded uptime `history`
+///
+/// The sort is stable and [`SortBy::Config`] leaves the sites as they are. The other
+/// orders break ties by name, ignoring case, as the dashboard does when it reorders the
+/// sites on uptime updates. A site's status and response time are those of its latest
+/// completed check; sites without one sort as not checked yet.
+///
+/// # Examples
+///
+/// ```
+/// use iron_shield::config::{Site, SortBy};
+/// use iron_shield::uptime::{sort_sites, HistoryEntry, UptimeStatus};
+/// use std::collections::{HashMap, VecDeque};
+///
+/// let site = |name: &str| Site {
+///     name: name.to_string(),
+///     url: "https://example.com".to_string(),
+///     category: String::new(),
+///     tags: vec![],
+///     monitor_interval_secs: None,
+///     monitor_timeout_secs: None,
+///     monitor_retries: None,
+///     monitor_user_agent: None,
+///     disabled: false,
+///     icon: None,
+///     order: None,
+///     assertions: None,
+///     uptime_percentage: 0.0,
+/// };
+/// let entry = |status| HistoryEntry {
+///     status,
+///     response_time_ms: None,
+///     failure_reason: None,
+///     checked_at: 0,
+/// };
+/// let history = HashMap::from([
+///     ("Mail".to_string(), VecDeque::from([entry(UptimeStatus::Up)])),
+///     ("Wiki".to_string(), VecDeque::from([entry(UptimeStatus::Down)])),
+/// ]);
+/// let mut sites = vec![site("Mail"), site("Chat"), site("Wiki")];
+///
+/// sort_sites(&mut sites, SortBy::Status, &history);
+/// let names: Vec<&str> = sites.iter().map(|site| site.name.as_str()).collect();
+/// assert_eq!(names, ["Wiki", "Chat", "Mail"]);
+/// ```
+pub fn sort_sites(
+    sites: &mut [Site],
+    sort_by: SortBy,
+    history: &HashMap<String, VecDeque<HistoryEntry>>,
+) {
+    let latest = |site: &Site| {
+        history.get(&site.name).and_then(|entries| {
+            entries
+                .iter()
+                .rev()
+                .find(|entry| entry.status != UptimeStatus::Loading)
+        })
+    };
+    let rank = |site: &Site| -> u64 {
+        match sort_by {
+            SortBy::Config | SortBy::Name => 0,
+            SortBy::Status => match latest(site).map(|entry| entry.status) {
+                _ if site.disabled => 3,
+                Some(UptimeStatus::Down) => 0,
+                None | Some(UptimeStatus::Loading) => 1,
+                Some(UptimeStatus::Up) => 2,
+                Some(UptimeStatus::Disabled) => 3,
+            },
+            // Whole hundredths of a percent, so the percentages compare as integers
+            #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+            SortBy::Uptime => history.get(&site.name).map_or(0, |entries| {
+                (calculate_uptime_percentage(entries) * 100.0).round() as u64
+            }),
+            SortBy::ResponseTime => {
+                u64::MAX
+                    - latest(site)
+                        .and_then(|entry| entry.response_time_ms)
+                        .unwrap_or(u64::MAX)
+            }
+        }
+    };
+    if sort_by != SortBy::Config {
+        sites.sort_by_cached_key(|site| (rank(site), site.name.to_lowercase()));
+    }
+}
+
 /// Helper function to create a `UptimeHistory` instance with current data
 ///
 /// This function creates a new `UptimeHistory` struct populated with the current status,
@@ -640,15 +986,141 @@ pub(crate) fn create_uptime_history(
     UptimeHistory {
         site_id: site_name.to_string(),
         status: current_status,
-        timestamp: std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap_or_else(|_| std::time::Duration::from_secs(0))
-            .as_secs(),
+        timestamp: unix_now(),
         history: site_history.iter().cloned().collect(),
         uptime_percentage,
         response_time_ms,
         max_history_entries: MAX_HISTORY_ENTRIES,
+        latency_ms: latency_series(site_history),
+        down_since: down_since(site_history),
+        last_checked: last_checked(site_history),
+        last_change: last_change(site_history),
+    }
+}
+
+/// Current Unix time in seconds.
+pub(crate) fn unix_now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_else(|_| std::time::Duration::from_secs(0))
+        .as_secs()
+}
+
+/// When a site went down, if its latest completed check failed
+///
+/// This is the start of the first of the failed checks that follow the last check the
+/// site passed (or the oldest recorded check, if it failed all of them). Checks in
+/// progress do not end an outage. `None` while the site is up, paused, or not checked
+/// yet, or when the start time was not recorded.
+///
+/// # Examples
+///
+/// ```
+/// use iron_shield::uptime::{down_since, HistoryEntry, UptimeStatus};
+/// use std::collections::VecDeque;
+///
+/// let entry = |status, checked_at| HistoryEntry {
+///     status,
+///     response_time_ms: None,
+///     failure_reason: None,
+///     checked_at,
+/// };
+/// let mut history = VecDeque::from([
+///     entry(UptimeStatus::Up, 100),
+///     entry(UptimeStatus::Down, 160),
+///     entry(UptimeStatus::Down, 220),
+///     entry(UptimeStatus::Loading, 280),
+/// ]);
+/// assert_eq!(down_since(&history), Some(160));
+///
+/// history.push_back(entry(UptimeStatus::Up, 340));
+/// assert_eq!(down_since(&history), None);
+/// ```
+#[must_use]
+pub fn down_since(site_history: &VecDeque<HistoryEntry>) -> Option<u64> {
+    let latest = latest_completed(site_history)?;
+    if latest.status == UptimeStatus::Down {
+        last_change(site_history)
+    } else {
+        None
+    }
+}
+
+/// When the latest completed check of a site started
+///
+/// Checks in progress are skipped. `None` before the first check completes, or when its
+/// start time was not recorded.
+///
+/// # Examples
+///
+/// ```
+/// use iron_shield::uptime::{last_checked, HistoryEntry, UptimeStatus};
+/// use std::collections::VecDeque;
+///
+/// let entry = |status, checked_at| HistoryEntry {
+///     status,
+///     response_time_ms: None,
+///     failure_reason: None,
+///     checked_at,
+/// };
+/// let history = VecDeque::from([entry(UptimeStatus::Up, 100), entry(UptimeStatus::Loading, 160)]);
+/// assert_eq!(last_checked(&history), Some(100));
+/// assert_eq!(last_checked(&VecDeque::new()), None);
+/// ```
+#[must_use]
+pub fn last_checked(site_history: &VecDeque<HistoryEntry>) -> Option<u64> {
+    latest_completed(site_history)
+        .map(|entry| entry.checked_at)
+        .filter(|checked_at| *checked_at > 0)
+}
+
+/// When a site entered the status of its latest completed check
+///
+/// This is the start of the first of the checks with that status that follow the last
+/// check with another status (or the oldest recorded check, if there is none). Like in
+/// [`down_since`], checks in progress are skipped. `None` before the first check
+/// completes, or when the start time was not recorded.
+///
+/// # Examples
+///
+/// ```
+/// use iron_shield::uptime::{last_change, HistoryEntry, UptimeStatus};
+/// use std::collections::VecDeque;
+///
+/// let entry = |status, checked_at| HistoryEntry {
+///     status,
+///     response_time_ms: None,
+///     failure_reason: None,
+///     checked_at,
+/// };
+/// let history = VecDeque::from([
+///     entry(UptimeStatus::Down, 100),
+///     entry(UptimeStatus::Up, 160),
+///     entry(UptimeStatus::Loading, 220),
+///     entry(UptimeStatus::Up, 280),
+/// ]);
+/// assert_eq!(last_change(&history), Some(160));
+/// ```
+#[must_use]
+pub fn last_change(site_history: &VecDeque<HistoryEntry>) -> Option<u64> {
+    let mut completed = site_history
+        .iter()
+        .rev()
+        .filter(|entry| entry.status != UptimeStatus::Loading);
+    let latest = completed.next()?;
+    let mut since = latest.checked_at;
+    for entry in completed.take_while(|entry| entry.status == latest.status) {
+        since = entry.checked_at;
     }
+    Some(since).filter(|since| *since > 0)
+}
+
+/// The most recent check of a site that is not in progress
+fn latest_completed(site_history: &VecDeque<HistoryEntry>) -> Option<&HistoryEntry> {
+    site_history
+        .iter()
+        .rev()
+        .find(|entry| entry.status != UptimeStatus::Loading)
 }
 
 /// Replace the most recent `Loading` entry (if present) with the final status and metadata.
@@ -659,88 +1131,65 @@ fn apply_final_status(
     site_history: &mut VecDeque<HistoryEntry>,
     final_status: UptimeStatus,
     response_time_ms: Option<u64>,
+    failure_reason: Option<String>,
 ) {
-    let mut replaced = false;
+    let entry = HistoryEntry {
+        status: final_status,
+        response_time_ms,
+        failure_reason,
+        checked_at: unix_now(),
+    };
 
     if let Some(last) = site_history.back_mut() {
         if last.status == UptimeStatus::Loading {
+            // Keep when the check started
             *last = HistoryEntry {
-                status: final_status,
-                response_time_ms,
+                checked_at: last.checked_at,
+                ..entry
             };
-            replaced = true;
+            return;
         }
     }
 
-    if !replaced {
-        site_history.push_back(HistoryEntry {
-            status: final_status,
-            response_time_ms,
-        });
-        if site_history.len() > MAX_HISTORY_ENTRIES {
-            site_history.pop_front();
-        }
+    site_history.push_back(entry);
+    if site_history.len() > MAX_HISTORY_ENTRIES {
+        site_history.pop_front();
     }
 }
 
-/// Helper function to check the status of a website
-///
-/// This function performs an HTTP HEAD request to the specified URL and determines
-/// the uptime status based on the response. It handles timeouts and connection errors,
-/// returning `UptimeStatus::Down` for any failure condition.
-///
-/// # Arguments
-///
-/// * `client` - A reqwest HTTP client to use for the request
-/// * `url` - The URL of the site to check
-///
-/// # Returns
-///
-/// A `SiteCheckResult` containing the status and measured response time
-///
-/// # Note
-///
-/// The function uses a timeout of 10 seconds for the request. It returns `UptimeStatus::Down`
-/// for any request failure, including timeouts, connection errors, or non-success HTTP status codes.
-async fn check_site_status(client: &reqwest::Client, url: &str) -> SiteCheckResult {
-    debug!("Checking site status: {url}");
-    let start = Instant::now();
-    let status = match client
-        .head(url)
-        .timeout(std::time::Duration::from_secs(10))
-        .send()
-        .await
-    {
-        Ok(response) => {
-            let status = response.status();
-            if status.is_success() {
-                debug!("Site {url} is UP: status {status}");
-                UptimeStatus::Up
-            } else {
-                debug!("Site {url} is DOWN: status {status}");
-                UptimeStatus::Down
-            }
-        }
-        Err(e) => {
-            debug!("Site {url} is DOWN: error {e}");
-            UptimeStatus::Down
-        }
-    };
-
-    let response_time_ms = start.elapsed().as_millis().try_into().unwrap_or(u64::MAX);
-
-    SiteCheckResult {
+/// Check `url` once with `settings`, outside of any monitoring loop and its history.
+pub(crate) async fn check_now(url: &str, settings: &CheckSettings) -> HistoryEntry {
+    let checked_at = unix_now();
+    let CheckResult {
+        status,
+        response_time_ms,
+        failure_reason,
+        ..
+    } = checkers::check(url, settings).await;
+    HistoryEntry {
         status,
-        response_time_ms: Some(response_time_ms),
+        response_time_ms,
+        failure_reason,
+        checked_at,
     }
 }
 
+/// Current Unix time in nanoseconds, saturating at `u64::MAX`.
+pub(crate) fn unix_now_nanos() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos()
+        .try_into()
+        .unwrap_or(u64::MAX)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::config::Config;
     use std::collections::VecDeque;
-    use std::sync::{Arc, RwLock};
+    use std::sync::Arc;
 
     fn make_history(statuses: &[UptimeStatus]) -> VecDeque<HistoryEntry> {
         statuses
@@ -748,6 +1197,8 @@ mod tests {
             .map(|status| HistoryEntry {
                 status: *status,
                 response_time_ms: None,
+                failure_reason: None,
+                checked_at: 0,
             })
             .collect()
     }
@@ -842,14 +1293,20 @@ mod tests {
             HistoryEntry {
                 status: UptimeStatus::Up,
                 response_time_ms: Some(100),
+                failure_reason: None,
+                checked_at: 0,
             },
             HistoryEntry {
                 status: UptimeStatus::Down,
                 response_time_ms: None,
+                failure_reason: None,
+                checked_at: 0,
             },
             HistoryEntry {
                 status: UptimeStatus::Loading,
                 response_time_ms: None,
+                failure_reason: None,
+                checked_at: 0,
             },
         ]);
 
@@ -865,22 +1322,25 @@ mod tests {
                 HistoryEntry {
                     status: UptimeStatus::Up,
                     response_time_ms: Some(100),
+                    failure_reason: None,
+                    checked_at: 0,
                 },
                 HistoryEntry {
                     status: UptimeStatus::Down,
                     response_time_ms: None,
+                    failure_reason: None,
+                    checked_at: 0,
                 },
                 HistoryEntry {
                     status: UptimeStatus::Loading,
                     response_time_ms: None,
+                    failure_reason: None,
+                    checked_at: 0,
                 }
             ]
         );
         assert_eq!(uptime_history.response_time_ms, Some(120));
-        assert_eq!(
-            uptime_history.max_history_entries,
-            MAX_HISTORY_ENTRIES
-        );
+        assert_eq!(uptime_history.max_history_entries, MAX_HISTORY_ENTRIES);
 
         // Check that timestamp is reasonable (within a few seconds of now)
         let current_time = std::time::SystemTime::now()
@@ -897,14 +1357,23 @@ mod tests {
             HistoryEntry {
                 status: UptimeStatus::Up,
                 response_time_ms: Some(80),
+                failure_reason: None,
+                checked_at: 0,
             },
             HistoryEntry {
                 status: UptimeStatus::Loading,
                 response_time_ms: None,
+                failure_reason: None,
+                checked_at: 0,
             },
         ]);
 
-        apply_final_status(&mut history, UptimeStatus::Down, Some(150));
+        apply_final_status(
+            &mut history,
+            UptimeStatus::Down,
+            Some(150),
+            Some("status 503 is not successful".to_string()),
+        );
 
         assert_eq!(
             history,
@@ -912,10 +1381,14 @@ mod tests {
                 HistoryEntry {
                     status: UptimeStatus::Up,
                     response_time_ms: Some(80),
+                    failure_reason: None,
+                    checked_at: 0,
                 },
                 HistoryEntry {
                     status: UptimeStatus::Down,
                     response_time_ms: Some(150),
+                    failure_reason: Some("status 503 is not successful".to_string()),
+                    checked_at: 0,
                 }
             ])
         );
@@ -927,63 +1400,247 @@ mod tests {
             HistoryEntry {
                 status: UptimeStatus::Up,
                 response_time_ms: None,
+                failure_reason: None,
+                checked_at: 0,
             };
             MAX_HISTORY_ENTRIES
         ]);
 
-        apply_final_status(&mut history, UptimeStatus::Down, Some(200));
+        apply_final_status(&mut history, UptimeStatus::Down, Some(200), None);
 
         assert_eq!(history.len(), MAX_HISTORY_ENTRIES);
+        let last = history.back().expect("history should not be empty");
+        assert!(last.checked_at > 0);
         assert_eq!(
-            history.back(),
-            Some(&HistoryEntry {
+            last,
+            &HistoryEntry {
                 status: UptimeStatus::Down,
                 response_time_ms: Some(200),
-            })
+                failure_reason: None,
+                checked_at: last.checked_at,
+            }
         );
     }
 
     #[test]
     fn test_uptime_state_creation() {
-        // Create a mock config
-        let config = Arc::new(RwLock::new(Config {
+        let config = Config {
             site_name: "Test Site".to_string(),
             clock: crate::config::Clock::None,
             opentelemetry_endpoint: None,
             sites: vec![],
-        }));
+            ..Config::default()
+        };
+        let uptime_state = UptimeState::new(config, std::path::PathBuf::from("config.json5"));
 
-        let history = Arc::new(RwLock::new(HashMap::new()));
+        // Verify that the state can be created without issues
+        assert_eq!(uptime_state.config.load().site_name, "Test Site");
+        assert!(uptime_state.history.read().is_empty());
+    }
 
-        let config_file_path = std::path::PathBuf::from("config.json5");
+    #[test]
+    fn test_uptime_query_selects_dashboard_sites() {
+        let site = |name: &str| Site {
+            name: name.to_string(),
+            url: format!("https://{name}.example.com"),
+            category: String::new(),
+            tags: vec![],
+            monitor_interval_secs: Some(crate::config::DEFAULT_MONITOR_INTERVAL_SECS),
+            monitor_timeout_secs: None,
+            monitor_retries: None,
+            monitor_user_agent: None,
+            disabled: false,
+            icon: None,
+            order: None,
+            assertions: None,
+            uptime_percentage: 0.0,
+        };
+        let config = Config {
+            sites: vec![site("jira"), site("nas")],
+            dashboards: vec![crate::config::Dashboard {
+                name: "homelab".to_string(),
+                title: None,
+                sites: vec!["nas".to_string()],
+            }],
+            ..Config::default()
+        };
+        let names = |query: UptimeQuery| -> Vec<String> {
+            query
+                .select_sites(&config)
+                .into_iter()
+                .map(|site| site.name)
+                .collect()
+        };
 
-        let (shutdown_events, _) = tokio::sync::broadcast::channel(1);
-        let shutdown_token = CancellationToken::new();
+        assert_eq!(names(UptimeQuery::default()), vec!["jira", "nas"]);
+        assert_eq!(
+            names(UptimeQuery {
+                dashboard: Some("homelab".to_string()),
+                ..UptimeQuery::default()
+            }),
+            vec!["nas"]
+        );
+        assert!(names(UptimeQuery {
+            dashboard: Some("unknown".to_string()),
+            ..UptimeQuery::default()
+        })
+        .is_empty());
+    }
 
-        let uptime_state = UptimeState {
-            config,
-            history,
-            config_file_path,
-            shutdown_events,
-            shutdown_token,
+    #[test]
+    fn test_uptime_query_filters_by_tag_and_category() {
+        let site = |name: &str, category: &str, tags: &[&str]| Site {
+            name: name.to_string(),
+            url: format!("https://{name}.example.com"),
+            category: category.to_string(),
+            tags: tags.iter().map(|tag| (*tag).to_string()).collect(),
+            monitor_interval_secs: Some(crate::config::DEFAULT_MONITOR_INTERVAL_SECS),
+            monitor_timeout_secs: None,
+            monitor_retries: None,
+            monitor_user_agent: None,
+            disabled: false,
+            icon: None,
+            order: None,
+            assertions: None,
+            uptime_percentage: 0.0,
+        };
+        let config = Config {
+            sites: vec![
+                site("api", "Infra", &["prod", "backend"]),
+                site("staging", "Infra", &["staging"]),
+                site("blog", "Web", &["Prod"]),
+            ],
+            ..Config::default()
+        };
+        let names = |tag: Option<&str>, category: Option<&str>| -> Vec<String> {
+            UptimeQuery {
+                tag: tag.map(str::to_string),
+                category: category.map(str::to_string),
+                ..UptimeQuery::default()
+            }
+            .select_sites(&config)
+            .into_iter()
+            .map(|site| site.name)
+            .collect()
         };
 
-        // Verify that the state can be created without issues
-        assert!(uptime_state.config.read().is_ok());
-        assert!(uptime_state.history.read().is_ok());
+        assert_eq!(names(Some("prod"), None), vec!["api", "blog"]);
+        assert_eq!(names(Some("prod"), Some("infra")), vec!["api"]);
+        assert_eq!(
+            names(Some("staging, backend"), None),
+            vec!["api", "staging"]
+        );
+        assert_eq!(names(None, Some("Web")), vec!["blog"]);
+        assert_eq!(names(Some(""), None), vec!["api", "staging", "blog"]);
+        assert!(names(Some("missing"), None).is_empty());
     }
 
-    #[tokio::test]
-    async fn test_check_site_status_up() {
-        // This test requires a real server to test against
-        // For now, we can only test error conditions
-        let client = reqwest::Client::new();
-
-        // Test with a URL that should result in an error (nonexistent domain)
-        let result =
-            check_site_status(&client, "http://definitely-not-a-real-domain-12345.com").await;
-        assert_eq!(result.status, UptimeStatus::Down);
-        assert!(result.response_time_ms.is_some());
+    #[test]
+    fn test_parse_last_event_id() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(parse_last_event_id(&headers), None);
+
+        headers.insert("last-event-id", " 42 ".parse().expect("valid header"));
+        assert_eq!(parse_last_event_id(&headers), Some(42));
+
+        headers.insert(
+            "last-event-id",
+            "not-a-number".parse().expect("valid header"),
+        );
+        assert_eq!(parse_last_event_id(&headers), None);
+    }
+
+    #[test]
+    fn test_replay_snapshot_only_includes_configured_sites_in_order() {
+        let site = |name: &str| Site {
+            name: name.to_string(),
+            url: format!("https://{name}.example.com"),
+            category: "Web".to_string(),
+            tags: vec![],
+            monitor_interval_secs: Some(crate::config::DEFAULT_MONITOR_INTERVAL_SECS),
+            monitor_timeout_secs: None,
+            monitor_retries: None,
+            monitor_user_agent: None,
+            disabled: false,
+            icon: None,
+            order: None,
+            assertions: None,
+            uptime_percentage: 0.0,
+        };
+        let config = Config {
+            sites: vec![site("beta"), site("alpha"), site("fresh")],
+            ..Config::default()
+        };
+        let history = HashMap::from([
+            ("alpha".to_string(), make_history(&[UptimeStatus::Up])),
+            ("beta".to_string(), make_history(&[UptimeStatus::Down])),
+            ("removed".to_string(), make_history(&[UptimeStatus::Up])),
+            ("fresh".to_string(), VecDeque::new()),
+        ]);
+        let state = UptimeState::new(config, std::path::PathBuf::from("config.json5"));
+        *state.history.write() = history;
+
+        let replay = replay_snapshot(&state, &UptimeQuery::default());
+
+        let ids: Vec<&str> = replay.iter().map(|h| h.site_id.as_str()).collect();
+        assert_eq!(ids, vec!["beta", "alpha"]);
+        assert_eq!(replay[0].status, UptimeStatus::Down);
+    }
+
+    #[test]
+    fn test_sort_sites_by_uptime_and_response_time() {
+        let site = |name: &str| Site {
+            name: name.to_string(),
+            url: format!("https://{name}.example.com"),
+            category: String::new(),
+            tags: vec![],
+            monitor_interval_secs: None,
+            monitor_timeout_secs: None,
+            monitor_retries: None,
+            monitor_user_agent: None,
+            disabled: false,
+            icon: None,
+            order: None,
+            assertions: None,
+            uptime_percentage: 0.0,
+        };
+        let timed = |status, response_time_ms| HistoryEntry {
+            status,
+            response_time_ms,
+            failure_reason: None,
+            checked_at: 0,
+        };
+        let history = HashMap::from([
+            (
+                "fast".to_string(),
+                VecDeque::from([timed(UptimeStatus::Up, Some(20))]),
+            ),
+            (
+                "slow".to_string(),
+                VecDeque::from([
+                    timed(UptimeStatus::Down, None),
+                    timed(UptimeStatus::Up, Some(900)),
+                    timed(UptimeStatus::Loading, None),
+                ]),
+            ),
+            (
+                "Broken".to_string(),
+                VecDeque::from([timed(UptimeStatus::Down, None)]),
+            ),
+        ]);
+        let names = |sites: &[Site]| -> Vec<String> {
+            sites.iter().map(|site| site.name.clone()).collect()
+        };
+        let mut sites = vec![site("fast"), site("slow"), site("Broken")];
+
+        sort_sites(&mut sites, SortBy::Config, &history);
+        assert_eq!(names(&sites), ["fast", "slow", "Broken"]);
+        sort_sites(&mut sites, SortBy::ResponseTime, &history);
+        assert_eq!(names(&sites), ["Broken", "slow", "fast"]);
+        sort_sites(&mut sites, SortBy::Uptime, &history);
+        assert_eq!(names(&sites), ["Broken", "slow", "fast"]);
+        sort_sites(&mut sites, SortBy::Name, &history);
+        assert_eq!(names(&sites), ["Broken", "fast", "slow"]);
     }
 
     // Test the data structures
@@ -997,19 +1654,29 @@ mod tests {
                 HistoryEntry {
                     status: UptimeStatus::Up,
                     response_time_ms: Some(80),
+                    failure_reason: None,
+                    checked_at: 0,
                 },
                 HistoryEntry {
                     status: UptimeStatus::Down,
                     response_time_ms: None,
+                    failure_reason: None,
+                    checked_at: 0,
                 },
                 HistoryEntry {
                     status: UptimeStatus::Loading,
                     response_time_ms: None,
+                    failure_reason: None,
+                    checked_at: 0,
                 },
             ],
             uptime_percentage: 50.0,
             response_time_ms: Some(250),
             max_history_entries: MAX_HISTORY_ENTRIES,
+            latency_ms: vec![Some(80), None],
+            down_since: None,
+            last_checked: None,
+            last_change: None,
         };
 
         // Test serialization/deserialization
@@ -1032,40 +1699,7 @@ mod tests {
             uptime_history.max_history_entries,
             deserialized.max_history_entries
         );
-    }
-
-    #[tokio::test]
-    async fn test_check_site_status_with_valid_url() {
-        // Test with a URL that should return success (HTTPbin is commonly used for testing)
-        let client = reqwest::Client::new();
-        let result = check_site_status(&client, "https://httpbin.org/status/200").await;
-        // Note: This might fail if no internet connection, but it's a good test when available
-        // For now, we'll just check that it doesn't panic
-        assert!(matches!(
-            result.status,
-            UptimeStatus::Up | UptimeStatus::Down
-        ));
-    }
-
-    #[tokio::test]
-    async fn test_check_site_status_down_non_2xx() {
-        // Test with a URL that returns a 500 Internal Server Error
-        let client = reqwest::Client::new();
-        let result = check_site_status(&client, "https://httpbin.org/status/500").await;
-        assert_eq!(result.status, UptimeStatus::Down);
-    }
-
-    #[tokio::test]
-    async fn test_check_site_status_with_timeout() {
-        // Test with a URL that should timeout
-        let client = reqwest::Client::builder()
-            .timeout(std::time::Duration::from_millis(1)) // Very short timeout
-            .build()
-            .unwrap();
-
-        // Use a URL that will likely timeout with 1ms timeout
-        let result = check_site_status(&client, "https://httpbin.org/delay/10").await;
-        assert_eq!(result.status, UptimeStatus::Down);
+        assert_eq!(uptime_history.latency_ms, deserialized.latency_ms);
     }
 
     #[test]
@@ -1098,4 +1732,261 @@ mod tests {
         let percentage = calculate_uptime_percentage(&history);
         assert!((percentage - 75.0).abs() < f64::EPSILON);
     }
+
+    /// A state running `config`, as the server builds it, with no `/uptime` stream open
+    fn monitored_state(config: Config) -> Arc<UptimeState> {
+        Arc::new(UptimeState::new(
+            config,
+            std::path::PathBuf::from("config.json5"),
+        ))
+    }
+
+    /// A site checking `url` once per cycle, without retries
+    fn monitored_site(name: &str, url: &str) -> Site {
+        Site {
+            name: name.to_string(),
+            url: url.to_string(),
+            category: String::new(),
+            tags: vec![],
+            monitor_interval_secs: None,
+            monitor_timeout_secs: Some(5),
+            monitor_retries: Some(0),
+            monitor_user_agent: None,
+            disabled: false,
+            icon: None,
+            order: None,
+            assertions: None,
+            uptime_percentage: 0.0,
+        }
+    }
+
+    /// Serve `app` on a free local port, returning its base URL
+    async fn serve(app: axum::Router) -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        url
+    }
+
+    #[tokio::test]
+    async fn monitor_notifies_outages_and_recoveries_without_open_streams() {
+        use axum::http::StatusCode;
+        use std::sync::atomic::AtomicBool;
+        use std::sync::Mutex;
+
+        let healthy = Arc::new(AtomicBool::new(false));
+        let site = axum::Router::new().route(
+            "/",
+            axum::routing::get({
+                let healthy = Arc::clone(&healthy);
+                move || async move {
+                    if healthy.load(Ordering::SeqCst) {
+                        StatusCode::OK
+                    } else {
+                        StatusCode::SERVICE_UNAVAILABLE
+                    }
+                }
+            }),
+        );
+        let site_url = serve(site).await;
+
+        let published = Arc::new(Mutex::new(Vec::new()));
+        let ntfy = axum::Router::new().route(
+            "/",
+            axum::routing::post({
+                let published = Arc::clone(&published);
+                move |axum::Json(body): axum::Json<serde_json::Value>| async move {
+                    published.lock().unwrap().push(body["title"].clone());
+                }
+            }),
+        );
+        let ntfy_url = serve(ntfy).await;
+
+        let state = monitored_state(Config {
+            sites: vec![monitored_site("nas", &site_url)],
+            notifiers: vec![crate::notifications::Notifier::Ntfy(
+                crate::notifications::Ntfy {
+                    url: ntfy_url,
+                    topic: "homelab".to_string(),
+                    token: None,
+                    priorities: crate::notifications::default_ntfy_priorities(),
+                    templates: crate::notifications::Templates::default(),
+                },
+            )],
+            ..Config::default()
+        });
+        let titles = |count: usize| {
+            let published = Arc::clone(&published);
+            async move {
+                for _ in 0..100 {
+                    if published.lock().unwrap().len() >= count {
+                        break;
+                    }
+                    tokio::time::sleep(Duration::from_millis(50)).await;
+                }
+                published.lock().unwrap().clone()
+            }
+        };
+
+        // Every cycle starts with no check times, so the site is due each time
+        run_cycle(&state, &mut HashMap::new()).await;
+        assert_eq!(titles(1).await, [serde_json::json!("nas is down")]);
+
+        healthy.store(true, Ordering::SeqCst);
+        run_cycle(&state, &mut HashMap::new()).await;
+        let titles = titles(2).await;
+        assert_eq!(titles.len(), 2);
+        assert!(titles[1]
+            .as_str()
+            .unwrap()
+            .starts_with("nas recovered after"));
+        assert_eq!(state.self_monitor.status().cycles, 2);
+    }
+
+    /// Payloads received by the sinks of [`monitor_sends_each_cycle_to_every_sink_once`],
+    /// with the name of the sink that received each
+    type Received = Arc<std::sync::Mutex<Vec<(&'static str, String)>>>;
+
+    /// Serve `sink` on a free local port, recording what it is sent in `received`
+    async fn serve_sink(
+        received: &Received,
+        sink: &'static str,
+        path: &str,
+        handler: fn(axum::http::HeaderMap, String) -> String,
+    ) -> String {
+        let received = Arc::clone(received);
+        serve(axum::Router::new().route(
+            path,
+            axum::routing::post(move |headers, body| async move {
+                received
+                    .lock()
+                    .unwrap()
+                    .push((sink, handler(headers, body)));
+                axum::http::StatusCode::NO_CONTENT
+            }),
+        ))
+        .await
+    }
+
+    #[tokio::test]
+    async fn monitor_sends_each_cycle_to_every_sink_once() {
+        let site_url = serve(axum::Router::new().route(
+            "/",
+            axum::routing::get(|| async { axum::http::StatusCode::BAD_GATEWAY }),
+        ))
+        .await;
+        let received = Received::default();
+        let gotify_url = serve_sink(&received, "gotify", "/message", |headers, body| {
+            let body: serde_json::Value = serde_json::from_str(&body).unwrap();
+            format!(
+                "{} {} {}",
+                headers["x-gotify-key"].to_str().unwrap(),
+                body["title"].as_str().unwrap(),
+                body["priority"]
+            )
+        })
+        .await;
+        let influxdb_url = serve_sink(
+            &received,
+            "influxdb",
+            crate::influxdb::WRITE_PATH,
+            |_, body| body,
+        )
+        .await;
+        let statsd = tokio::net::UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let statsd_port = statsd.local_addr().unwrap().port();
+        tokio::spawn({
+            let received = Arc::clone(&received);
+            async move {
+                let mut datagram = [0; 1500];
+                while let Ok(len) = statsd.recv(&mut datagram).await {
+                    let payload = String::from_utf8_lossy(&datagram[..len]).into_owned();
+                    received.lock().unwrap().push(("statsd", payload));
+                }
+            }
+        });
+        let carbon = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let carbon_port = carbon.local_addr().unwrap().port();
+        tokio::spawn({
+            let received = Arc::clone(&received);
+            async move {
+                use tokio::io::AsyncReadExt;
+
+                while let Ok((mut connection, _)) = carbon.accept().await {
+                    let mut lines = String::new();
+                    connection.read_to_string(&mut lines).await.unwrap();
+                    received.lock().unwrap().push(("graphite", lines));
+                }
+            }
+        });
+        let state = monitored_state(Config {
+            site_name: "Home Lab".to_string(),
+            sites: vec![monitored_site("Home Assistant", &site_url)],
+            notifiers: vec![crate::notifications::Notifier::Gotify(
+                crate::notifications::Gotify {
+                    url: gotify_url,
+                    token: Some("app-token".to_string()),
+                    priorities: crate::notifications::Priorities {
+                        critical: 8,
+                        info: 2,
+                    },
+                    templates: crate::notifications::Templates::default(),
+                },
+            )],
+            statsd: Some(crate::statsd::Statsd {
+                host: "127.0.0.1".to_string(),
+                port: statsd_port,
+                prefix: "homelab".to_string(),
+            }),
+            influxdb: Some(crate::influxdb::InfluxDb {
+                url: influxdb_url,
+                org: None,
+                bucket: "iron_shield".to_string(),
+                token: None,
+            }),
+            graphite: Some(crate::graphite::Graphite {
+                host: "127.0.0.1".to_string(),
+                port: carbon_port,
+                prefix: "iron_shield".to_string(),
+            }),
+            ..Config::default()
+        });
+        // Sink, payloads over two cycles, and how each payload starts. A second failed
+        // check leaves the incident open without notifying again.
+        let expected = [
+            ("gotify", 1, "app-token Home Assistant is down 8"),
+            ("statsd", 2, "homelab.site.home_assistant.up:0|g\n"),
+            (
+                "influxdb",
+                2,
+                "iron_shield_check,dashboard=Home\\ Lab,site=Home\\ Assistant up=0i,",
+            ),
+            ("graphite", 2, "iron_shield.home_assistant.up 0 "),
+        ];
+
+        run_cycle(&state, &mut HashMap::new()).await;
+        run_cycle(&state, &mut HashMap::new()).await;
+        let total: usize = expected.iter().map(|(_, count, _)| count).sum();
+        for _ in 0..100 {
+            if received.lock().unwrap().len() >= total {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+        tokio::time::sleep(Duration::from_millis(200)).await;
+
+        let received = received.lock().unwrap().clone();
+        for (sink, count, start) in expected {
+            let payloads: Vec<&String> = received
+                .iter()
+                .filter(|(name, _)| *name == sink)
+                .map(|(_, payload)| payload)
+                .collect();
+            assert_eq!(payloads.len(), count, "{sink}: {payloads:?}");
+            assert!(
+                payloads.iter().all(|payload| payload.starts_with(start)),
+                "{sink}: {payloads:?}"
+            );
+        }
+    }
 }
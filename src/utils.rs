@@ -1,72 +1,195 @@
+use crate::config::Clock;
 use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+use std::fmt::Write;
 
-/// Get the current UTC time formatted as a string with "UTC" suffix
+/// Format `time` for a clock shown in the `clock` format
 ///
-/// This function returns the current time in UTC timezone formatted as "HH:MM:SS UTC".
-/// The format is consistent and follows the 24-hour format for hours, minutes, and seconds.
+/// This matches what the dashboard's clock script shows once it starts ticking, so the
+/// server-rendered time does not change shape on first paint or without JavaScript:
+/// "1:05 PM" for [`Clock::Hour12`] and "13:05" otherwise, since clocks fall back to
+/// 24-hour when the main clock is hidden.
+///
+/// # Examples
+///
+/// ```
+/// use chrono::TimeZone;
+/// use iron_shield::config::Clock;
+/// use iron_shield::utils::format_time;
+///
+/// let time = chrono_tz::UTC.with_ymd_and_hms(2024, 5, 1, 13, 5, 0).unwrap();
+/// assert_eq!(format_time(&time, &Clock::Hour12), "1:05 PM");
+/// assert_eq!(format_time(&time, &Clock::Hour24), "13:05");
+/// assert_eq!(format_time(&time, &Clock::None), "13:05");
+/// ```
+#[must_use]
+pub fn format_time(time: &DateTime<Tz>, clock: &Clock) -> String {
+    match clock {
+        Clock::Hour12 => time.format("%-I:%M %p").to_string(),
+        Clock::Hour24 | Clock::None => time.format("%H:%M").to_string(),
+    }
+}
+
+/// Get the current time in a time zone formatted for a clock in the `clock` format
+///
+/// # Arguments
+///
+/// * `clock` - The configured clock format, usually `Config::clock`
+/// * `timezone` - The time zone to show the time in, usually `Config::tz()`
 ///
 /// # Returns
 ///
-/// A `String` containing the current UTC time in the format "HH:MM:SS UTC".
+/// A `String` containing the current time as described for [`format_time`].
 ///
 /// # Examples
 ///
 /// ```
+/// use iron_shield::config::Clock;
 /// use iron_shield::utils::get_current_time_string;
 ///
-/// let time_string = get_current_time_string();
+/// let time_string = get_current_time_string(&Clock::Hour12, chrono_tz::UTC);
 /// println!("Current time: {}", time_string);
-/// assert!(time_string.contains("UTC"));
+/// assert!(time_string.ends_with(" AM") || time_string.ends_with(" PM"));
 /// ```
 #[must_use]
-pub fn get_current_time_string() -> String {
+pub fn get_current_time_string(clock: &Clock, timezone: Tz) -> String {
     let now: DateTime<Utc> = Utc::now();
-    now.format("%H:%M UTC").to_string()
+    format_time(&now.with_timezone(&timezone), clock)
+}
+
+/// `secs` as its two largest units, such as "45s", "12m", "3h 5m", or "2d 4h"; matches
+/// `formatDuration` in the frontend.
+///
+/// # Examples
+///
+/// ```
+/// use iron_shield::utils::format_duration;
+///
+/// assert_eq!(format_duration(32), "32s");
+/// assert_eq!(format_duration(2 * 3600 + 14 * 60 + 5), "2h 14m");
+/// ```
+#[must_use]
+pub fn format_duration(secs: u64) -> String {
+    let (days, hours, minutes) = (secs / 86_400, secs / 3600 % 24, secs / 60 % 60);
+    match (days, hours, minutes) {
+        (0, 0, 0) => format!("{secs}s"),
+        (0, 0, minutes) => format!("{minutes}m"),
+        (0, hours, 0) => format!("{hours}h"),
+        (0, hours, minutes) => format!("{hours}h {minutes}m"),
+        (days, 0, _) => format!("{days}d"),
+        (days, hours, _) => format!("{days}d {hours}h"),
+    }
+}
+
+/// The time from the Unix timestamp `since` to `now` put into `template` in place of its
+/// `{}`, such as "checked 32s ago"; matches `formatRelative` in the frontend.
+///
+/// Templates are translated phrases (see [`crate::i18n`]), so each language can place
+/// the duration where its word order needs it. A `since` after `now`, as from a clock
+/// that is behind, counts as no time at all.
+///
+/// # Examples
+///
+/// ```
+/// use iron_shield::utils::format_relative;
+///
+/// assert_eq!(format_relative("{} ago", 1_000, 1_032), "32s ago");
+/// assert_eq!(format_relative("down for {}", 1_000, 1_000 + 8_040), "down for 2h 14m");
+/// assert_eq!(format_relative("vor {}", 1_000, 990), "vor 0s");
+/// ```
+#[must_use]
+pub fn format_relative(template: &str, since: u64, now: u64) -> String {
+    template.replacen("{}", &format_duration(now.saturating_sub(since)), 1)
+}
+
+/// Lowercase hex encoding of `bytes`
+///
+/// # Examples
+///
+/// ```
+/// use iron_shield::utils::hex;
+///
+/// assert_eq!(hex(&[0x0f, 0xa0]), "0fa0");
+/// ```
+#[must_use]
+pub fn hex(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .fold(String::with_capacity(bytes.len() * 2), |mut out, byte| {
+            let _ = write!(out, "{byte:02x}");
+            out
+        })
+}
+
+/// The bytes of the hex string `value`, or `None` if it is not one
+///
+/// # Examples
+///
+/// ```
+/// use iron_shield::utils::from_hex;
+///
+/// assert_eq!(from_hex("0fA0"), Some(vec![0x0f, 0xa0]));
+/// assert_eq!(from_hex("0fa"), None);
+/// assert_eq!(from_hex("zz"), None);
+/// ```
+#[must_use]
+pub fn from_hex(value: &str) -> Option<Vec<u8>> {
+    if !value.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..value.len())
+        .step_by(2)
+        .map(|start| u8::from_str_radix(value.get(start..start + 2)?, 16).ok())
+        .collect()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use chrono::TimeZone;
 
     #[test]
     fn test_get_current_time_string_format() {
-        let time_string = get_current_time_string();
-
-        // Check that it contains "UTC" suffix
-        assert!(time_string.ends_with(" UTC"));
-
-        // The time part should be at least 7 characters (H:MM:SS) to 8 characters (HH:MM)
-        // plus 4 for " UTC", so total length should be between 11 and 12
-        assert!(time_string.len() >= 8);
-        assert!(time_string.len() <= 9);
-
-        // Extract the time part (before " UTC")
-        let time_part = &time_string[..time_string.len() - 4]; // Remove " UTC"
+        let time_string = get_current_time_string(&Clock::Hour24, Tz::UTC);
 
         // Split by ':'
-        let parts: Vec<&str> = time_part.split(':').collect();
+        let parts: Vec<&str> = time_string.split(':').collect();
         assert_eq!(parts.len(), 2); // hours:minutes
 
-        // Check that each part contains only digits
+        // Check that each part is exactly two digits
         for part in parts {
             assert!(part.chars().all(|c| c.is_ascii_digit()));
-            // Each part should be either 1 or 2 digits
-            assert!(part.len() == 1 || part.len() == 2);
+            assert_eq!(part.len(), 2);
         }
+    }
 
-        // Check that there are exactly 1 colons in the time part
-        assert_eq!(time_part.chars().filter(|&c| c == ':').count(), 1);
+    #[test]
+    fn test_get_current_time_string_uses_timezone() {
+        let utc = get_current_time_string(&Clock::Hour24, Tz::UTC);
+        let tokyo = get_current_time_string(&Clock::Hour24, Tz::Asia__Tokyo);
+
+        let hour = |time: &str| time[..2].parse::<u32>().expect("hour should be numeric");
+        // Tokyo is always UTC+9; allow for the hour rolling over between the two calls
+        let offset = (hour(&tokyo) + 24 - hour(&utc)) % 24;
+        assert!(offset == 9 || offset == 10);
     }
 
     #[test]
-    fn test_get_current_time_string_contains_utc() {
-        let time_string = get_current_time_string();
-        assert!(time_string.contains("UTC"));
+    fn test_format_time_hour12_midnight_and_noon() {
+        let at = |hour| Tz::UTC.with_ymd_and_hms(2024, 5, 1, hour, 0, 0).unwrap();
+
+        assert_eq!(format_time(&at(0), &Clock::Hour12), "12:00 AM");
+        assert_eq!(format_time(&at(12), &Clock::Hour12), "12:00 PM");
+        assert_eq!(format_time(&at(9), &Clock::Hour12), "9:00 AM");
+        assert_eq!(format_time(&at(9), &Clock::Hour24), "09:00");
     }
 
     #[test]
-    fn test_get_current_time_string_not_empty() {
-        let time_string = get_current_time_string();
-        assert!(!time_string.is_empty());
+    fn format_duration_uses_the_two_largest_units() {
+        assert_eq!(format_duration(45), "45s");
+        assert_eq!(format_duration(12 * 60 + 5), "12m");
+        assert_eq!(format_duration(3 * 3600), "3h");
+        assert_eq!(format_duration(3 * 3600 + 5 * 60), "3h 5m");
+        assert_eq!(format_duration(2 * 86_400 + 4 * 3600 + 59), "2d 4h");
     }
 }
@@ -0,0 +1,148 @@
+//! Variable interpolation in configuration values
+//!
+//! Any string in the configuration can refer to a variable as `${NAME}`, for example
+//! `url: "https://grafana.${BASE_DOMAIN}"`. Variables are looked up in the config's
+//! `vars` block first and then in the environment, and are expanded when the
+//! configuration is loaded, so a shared value such as a domain suffix is written once.
+//!
+//! Names are letters, digits, and underscores and cannot start with a digit. References
+//! with a source such as `${env:NAME}` are secret references (see the `secrets` module)
+//! and are left for it to resolve. The config module keeps the original template next
+//! to the expanded value so the template, not the expansion, is written back when the
+//! configuration is saved.
+
+/// Returns `true` when a value refers to at least one variable.
+///
+/// # Examples
+///
+/// ```
+/// use iron_shield::variables::has_variables;
+///
+/// assert!(has_variables("https://grafana.${BASE_DOMAIN}"));
+/// assert!(!has_variables("${env:API_TOKEN}"));
+/// assert!(!has_variables("https://grafana.example.com"));
+/// ```
+#[must_use]
+pub fn has_variables(value: &str) -> bool {
+    let mut rest = value;
+    while let Some(start) = rest.find("${") {
+        let reference = &rest[start + 2..];
+        match reference.find('}') {
+            Some(end) if reference[..end].contains(':') => rest = &reference[end + 1..],
+            _ => return true,
+        }
+    }
+    false
+}
+
+/// Expand every `${NAME}` in a value using `lookup`.
+///
+/// Values without variables are returned unchanged, and secret references are copied
+/// through as written.
+///
+/// # Errors
+///
+/// Returns a message naming an undefined variable, or describing an unterminated
+/// reference or an invalid variable name.
+///
+/// # Examples
+///
+/// ```
+/// use iron_shield::variables::expand;
+///
+/// let lookup = |name: &str| (name == "BASE_DOMAIN").then(|| "home.lan".to_string());
+///
+/// assert_eq!(
+///     expand("https://grafana.${BASE_DOMAIN}", lookup).unwrap(),
+///     "https://grafana.home.lan"
+/// );
+/// assert!(expand("https://${MISSING}", lookup).is_err());
+/// ```
+pub fn expand(value: &str, lookup: impl Fn(&str) -> Option<String>) -> Result<String, String> {
+    let mut expanded = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(start) = rest.find("${") {
+        expanded.push_str(&rest[..start]);
+        let reference = &rest[start + 2..];
+        let Some(end) = reference.find('}') else {
+            return Err(format!(
+                "has an unterminated variable reference in \"{value}\""
+            ));
+        };
+        let name = &reference[..end];
+        if name.contains(':') {
+            expanded.push_str(&rest[start..start + 2 + end + 1]);
+        } else if !is_variable_name(name) {
+            return Err(format!(
+                "refers to \"${{{name}}}\", but variable names may only contain letters, digits, and underscores"
+            ));
+        } else {
+            let variable = lookup(name).ok_or_else(|| {
+                format!(
+                    "uses undefined variable {name}; define it under vars or in the environment"
+                )
+            })?;
+            expanded.push_str(&variable);
+        }
+        rest = &reference[end + 1..];
+    }
+    expanded.push_str(rest);
+    Ok(expanded)
+}
+
+/// Returns `true` for names like `BASE_DOMAIN` or `port_2`.
+fn is_variable_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|first| first.is_ascii_alphabetic() || first == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lookup(name: &str) -> Option<String> {
+        match name {
+            "BASE_DOMAIN" => Some("home.lan".to_string()),
+            "PORT" => Some("3000".to_string()),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn expands_variables_and_keeps_secret_references() {
+        assert_eq!(
+            expand("http://grafana.${BASE_DOMAIN}:${PORT}/", lookup),
+            Ok("http://grafana.home.lan:3000/".to_string())
+        );
+        assert_eq!(
+            expand("https://otel.${BASE_DOMAIN}/?token=${env:TOKEN}", lookup),
+            Ok("https://otel.home.lan/?token=${env:TOKEN}".to_string())
+        );
+        assert_eq!(expand("plain", lookup), Ok("plain".to_string()));
+    }
+
+    #[test]
+    fn reports_undefined_unterminated_and_invalid_references() {
+        assert!(expand("${DOMAIN}", lookup)
+            .unwrap_err()
+            .contains("undefined variable DOMAIN"));
+        assert!(expand("https://${BASE_DOMAIN", lookup)
+            .unwrap_err()
+            .contains("unterminated"));
+        assert!(expand("${1st}", lookup)
+            .unwrap_err()
+            .contains("variable names may only contain"));
+    }
+
+    #[test]
+    fn detects_variables_but_not_secret_references() {
+        assert!(has_variables("${BASE_DOMAIN}"));
+        assert!(has_variables("${env:TOKEN}/${BASE_DOMAIN}"));
+        assert!(has_variables("unterminated ${"));
+        assert!(!has_variables("file:/run/secrets/token"));
+        assert!(!has_variables("${file:/run/secrets/token}"));
+    }
+}
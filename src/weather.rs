@@ -0,0 +1,479 @@
+//! Weather widget
+//!
+//! Dashboards used as wall displays can show the current weather next to the clock. The
+//! config's `weather` block picks a provider and location:
+//!
+//! ```json5
+//! weather: {
+//!     provider: "openweathermap",
+//!     api_key: "${env:OPENWEATHERMAP_API_KEY}",
+//!     location: "Berlin,DE",
+//!     units: "metric",
+//!     refresh_secs: 900,
+//! }
+//! ```
+//!
+//! - `openweathermap` needs an `api_key` and takes a city name as `location`
+//! - `openmeteo` needs no key and takes `latitude,longitude` as `location`
+//!
+//! The server fetches the weather when a page is rendered and keeps it for
+//! `refresh_secs`, so browsers never contact the provider and the API key stays on the
+//! server; it may be a secret reference (see the `secrets` module). If a fetch fails the
+//! last report keeps being shown until the next attempt.
+
+use crate::uptime::UptimeState;
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+use tracing::{debug, warn};
+
+/// Seconds a weather report is kept when `refresh_secs` is not set
+pub const DEFAULT_REFRESH_SECS: u64 = 600;
+/// Shortest allowed `refresh_secs`, which keeps free provider plans within their limits
+pub const MIN_REFRESH_SECS: u64 = 60;
+
+/// How long a provider may take to answer
+const FETCH_TIMEOUT: Duration = Duration::from_secs(5);
+const OPENWEATHERMAP_URL: &str = "https://api.openweathermap.org/data/2.5/weather";
+const OPENMETEO_URL: &str = "https://api.open-meteo.com/v1/forecast";
+
+/// Returns the default number of seconds a weather report is kept.
+#[must_use]
+pub fn default_refresh_secs() -> u64 {
+    DEFAULT_REFRESH_SECS
+}
+
+/// Settings of the weather widget
+///
+/// # Fields
+///
+/// * `provider` - Service the weather is fetched from
+/// * `api_key` - Key for providers that need one; may be a secret reference
+/// * `location` - City name for `openweathermap`, `latitude,longitude` for `openmeteo`
+/// * `units` - `metric` (°C) or `imperial` (°F)
+/// * `refresh_secs` - How long a fetched report is shown before fetching again
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+pub struct Weather {
+    /// Service the weather is fetched from
+    pub provider: WeatherProvider,
+    /// Key for providers that need one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_key: Option<String>,
+    /// Where to show the weather for
+    pub location: String,
+    /// Unit system of the temperature
+    #[serde(default)]
+    pub units: Units,
+    /// Seconds a fetched report is kept
+    #[serde(default = "default_refresh_secs")]
+    pub refresh_secs: u64,
+}
+
+/// A weather service
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum WeatherProvider {
+    /// `OpenWeatherMap`'s current weather API, which needs an API key
+    OpenWeatherMap,
+    /// Open-Meteo's forecast API, which is free without a key
+    OpenMeteo,
+}
+
+/// Unit system of reported temperatures
+#[derive(Debug, Default, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Units {
+    /// Degrees Celsius
+    #[default]
+    Metric,
+    /// Degrees Fahrenheit
+    Imperial,
+}
+
+impl Units {
+    fn symbol(self) -> &'static str {
+        match self {
+            Units::Metric => "°C",
+            Units::Imperial => "°F",
+        }
+    }
+}
+
+impl Weather {
+    /// Find problems with the settings, as field paths relative to `weather` with a
+    /// message for each.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use iron_shield::weather::{Units, Weather, WeatherProvider};
+    ///
+    /// let weather = Weather {
+    ///     provider: WeatherProvider::OpenMeteo,
+    ///     api_key: None,
+    ///     location: "52.52,13.41".to_string(),
+    ///     units: Units::Metric,
+    ///     refresh_secs: 600,
+    /// };
+    /// assert!(weather.validate().is_empty());
+    ///
+    /// let keyless = Weather {
+    ///     provider: WeatherProvider::OpenWeatherMap,
+    ///     location: "Berlin,DE".to_string(),
+    ///     ..weather
+    /// };
+    /// assert_eq!(keyless.validate()[0].0, "api_key");
+    /// ```
+    #[must_use]
+    pub fn validate(&self) -> Vec<(String, String)> {
+        let mut problems = Vec::new();
+        if self.location.trim().is_empty() {
+            problems.push(("location".to_string(), "cannot be empty".to_string()));
+        } else if self.provider == WeatherProvider::OpenMeteo && self.coordinates().is_none() {
+            problems.push((
+                "location".to_string(),
+                format!(
+                    "\"{}\" must be \"latitude,longitude\" for openmeteo",
+                    self.location
+                ),
+            ));
+        }
+        if self.provider == WeatherProvider::OpenWeatherMap
+            && self
+                .api_key
+                .as_deref()
+                .is_none_or(|key| key.trim().is_empty())
+        {
+            problems.push((
+                "api_key".to_string(),
+                "is required by openweathermap".to_string(),
+            ));
+        }
+        if self.refresh_secs < MIN_REFRESH_SECS {
+            problems.push((
+                "refresh_secs".to_string(),
+                format!("must be at least {MIN_REFRESH_SECS}"),
+            ));
+        }
+        problems
+    }
+
+    /// Latitude and longitude of a `latitude,longitude` location.
+    fn coordinates(&self) -> Option<(f64, f64)> {
+        let (latitude, longitude) = self.location.split_once(',')?;
+        let latitude: f64 = latitude.trim().parse().ok()?;
+        let longitude: f64 = longitude.trim().parse().ok()?;
+        ((-90.0..=90.0).contains(&latitude) && (-180.0..=180.0).contains(&longitude))
+            .then_some((latitude, longitude))
+    }
+}
+
+/// The current weather at the configured location
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct WeatherReport {
+    /// Place the report is for, as named by the provider or the config
+    pub location: String,
+    /// Current temperature in the configured units
+    pub temperature: f64,
+    /// Unit symbol of `temperature`, such as `°C`
+    pub unit: &'static str,
+    /// Short description of the conditions, such as "light rain"
+    pub description: String,
+}
+
+impl WeatherReport {
+    /// The temperature rounded to whole degrees with its unit, such as `12°C`.
+    #[must_use]
+    pub fn temperature_label(&self) -> String {
+        format!("{:.0}{}", self.temperature, self.unit)
+    }
+}
+
+/// The last weather report fetched, shared by every page render
+#[derive(Debug, Default)]
+pub struct WeatherCache {
+    cached: tokio::sync::Mutex<Option<CachedWeather>>,
+}
+
+#[derive(Debug)]
+struct CachedWeather {
+    /// Settings the report was fetched with; changing them fetches again
+    settings: Weather,
+    fetched_at: Instant,
+    report: Option<WeatherReport>,
+}
+
+/// The current weather for the configured `weather` widget, if any
+///
+/// Returns the cached report while it is younger than `refresh_secs` and fetches a new
+/// one otherwise. Concurrent renders wait for a single fetch. When fetching fails the
+/// previous report is returned and the next attempt waits for `refresh_secs`.
+pub async fn current(state: &UptimeState) -> Option<WeatherReport> {
+    let settings = state.config.load().weather.clone()?;
+    let mut cached = state.weather.cached.lock().await;
+    if let Some(entry) = cached.as_ref().filter(|entry| {
+        entry.settings == settings
+            && entry.fetched_at.elapsed() < Duration::from_secs(settings.refresh_secs)
+    }) {
+        return entry.report.clone();
+    }
+
+    let report = match fetch(&settings).await {
+        Ok(report) => {
+            debug!("Fetched weather for {}", report.location);
+            Some(report)
+        }
+        Err(err) => {
+            warn!(
+                "Unable to fetch the weather for {}: {err}",
+                settings.location
+            );
+            cached
+                .take()
+                .filter(|entry| entry.settings == settings)
+                .and_then(|entry| entry.report)
+        }
+    };
+    *cached = Some(CachedWeather {
+        settings,
+        fetched_at: Instant::now(),
+        report: report.clone(),
+    });
+    report
+}
+
+/// Fetch the current weather from the configured provider.
+async fn fetch(settings: &Weather) -> Result<WeatherReport, String> {
+    let client = reqwest::Client::builder()
+        .timeout(FETCH_TIMEOUT)
+        .build()
+        .map_err(|err| err.to_string())?;
+    let request = match settings.provider {
+        WeatherProvider::OpenWeatherMap => client.get(OPENWEATHERMAP_URL).query(&[
+            ("q", settings.location.as_str()),
+            ("appid", settings.api_key.as_deref().unwrap_or_default()),
+            ("units", openweathermap_units(settings.units)),
+        ]),
+        WeatherProvider::OpenMeteo => {
+            let (latitude, longitude) = settings
+                .coordinates()
+                .ok_or_else(|| "location is not \"latitude,longitude\"".to_string())?;
+            client.get(OPENMETEO_URL).query(&[
+                ("latitude", latitude.to_string().as_str()),
+                ("longitude", longitude.to_string().as_str()),
+                ("current", "temperature_2m,weather_code"),
+                ("temperature_unit", openmeteo_units(settings.units)),
+            ])
+        }
+    };
+
+    // Errors leave out the URL, which carries the API key
+    let response = request
+        .send()
+        .await
+        .and_then(reqwest::Response::error_for_status)
+        .map_err(|err| err.without_url().to_string())?;
+    let body: serde_json::Value = response
+        .json()
+        .await
+        .map_err(|err| err.without_url().to_string())?;
+    match settings.provider {
+        WeatherProvider::OpenWeatherMap => parse_openweathermap(body, settings),
+        WeatherProvider::OpenMeteo => parse_openmeteo(body, settings),
+    }
+}
+
+fn openweathermap_units(units: Units) -> &'static str {
+    match units {
+        Units::Metric => "metric",
+        Units::Imperial => "imperial",
+    }
+}
+
+fn openmeteo_units(units: Units) -> &'static str {
+    match units {
+        Units::Metric => "celsius",
+        Units::Imperial => "fahrenheit",
+    }
+}
+
+/// Read an `OpenWeatherMap` current weather response.
+fn parse_openweathermap(
+    body: serde_json::Value,
+    settings: &Weather,
+) -> Result<WeatherReport, String> {
+    #[derive(Deserialize)]
+    struct Response {
+        #[serde(default)]
+        name: String,
+        main: Main,
+        #[serde(default)]
+        weather: Vec<Condition>,
+    }
+    #[derive(Deserialize)]
+    struct Main {
+        temp: f64,
+    }
+    #[derive(Deserialize)]
+    struct Condition {
+        description: String,
+    }
+
+    let response: Response =
+        serde_json::from_value(body).map_err(|err| format!("unexpected response: {err}"))?;
+    Ok(WeatherReport {
+        location: if response.name.is_empty() {
+            settings.location.clone()
+        } else {
+            response.name
+        },
+        temperature: response.main.temp,
+        unit: settings.units.symbol(),
+        description: response
+            .weather
+            .into_iter()
+            .next()
+            .map(|condition| condition.description)
+            .unwrap_or_default(),
+    })
+}
+
+/// Read an Open-Meteo forecast response with `current` conditions.
+fn parse_openmeteo(body: serde_json::Value, settings: &Weather) -> Result<WeatherReport, String> {
+    #[derive(Deserialize)]
+    struct Response {
+        current: Current,
+    }
+    #[derive(Deserialize)]
+    struct Current {
+        temperature_2m: f64,
+        weather_code: u8,
+    }
+
+    let response: Response =
+        serde_json::from_value(body).map_err(|err| format!("unexpected response: {err}"))?;
+    Ok(WeatherReport {
+        location: settings.location.clone(),
+        temperature: response.current.temperature_2m,
+        unit: settings.units.symbol(),
+        description: wmo_description(response.current.weather_code).to_string(),
+    })
+}
+
+/// Description of a WMO weather interpretation code, as reported by Open-Meteo.
+fn wmo_description(code: u8) -> &'static str {
+    match code {
+        0 => "clear sky",
+        1 => "mainly clear",
+        2 => "partly cloudy",
+        3 => "overcast",
+        45 | 48 => "fog",
+        51 | 53 | 55 => "drizzle",
+        56 | 57 => "freezing drizzle",
+        61 | 63 | 65 => "rain",
+        66 | 67 => "freezing rain",
+        71 | 73 | 75 | 77 => "snow",
+        80..=82 => "rain showers",
+        85 | 86 => "snow showers",
+        95..=99 => "thunderstorm",
+        _ => "",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings(provider: WeatherProvider, location: &str) -> Weather {
+        Weather {
+            provider,
+            api_key: Some("key".to_string()),
+            location: location.to_string(),
+            units: Units::Imperial,
+            refresh_secs: DEFAULT_REFRESH_SECS,
+        }
+    }
+
+    #[test]
+    fn reads_provider_responses() {
+        let openweathermap = parse_openweathermap(
+            serde_json::json!({
+                "name": "Berlin",
+                "main": { "temp": 53.6 },
+                "weather": [{ "description": "light rain" }]
+            }),
+            &settings(WeatherProvider::OpenWeatherMap, "Berlin,DE"),
+        )
+        .unwrap();
+        assert_eq!(openweathermap.location, "Berlin");
+        assert_eq!(openweathermap.temperature_label(), "54°F");
+        assert_eq!(openweathermap.description, "light rain");
+
+        let openmeteo = parse_openmeteo(
+            serde_json::json!({ "current": { "temperature_2m": -1.2, "weather_code": 73 } }),
+            &settings(WeatherProvider::OpenMeteo, "52.52,13.41"),
+        )
+        .unwrap();
+        assert_eq!(openmeteo.location, "52.52,13.41");
+        assert_eq!(openmeteo.temperature_label(), "-1°F");
+        assert_eq!(openmeteo.description, "snow");
+
+        assert!(parse_openmeteo(
+            serde_json::json!({ "error": true }),
+            &settings(WeatherProvider::OpenMeteo, "0,0")
+        )
+        .is_err());
+    }
+
+    #[tokio::test]
+    async fn current_reuses_a_fresh_report_for_the_same_settings() {
+        let weather = settings(WeatherProvider::OpenMeteo, "52.52,13.41");
+        let report = WeatherReport {
+            location: "Berlin".to_string(),
+            temperature: 50.0,
+            unit: "°F",
+            description: "overcast".to_string(),
+        };
+        let state = UptimeState::new(
+            crate::config::Config::default(),
+            std::path::PathBuf::from("config.json5"),
+        );
+        *state.weather.cached.lock().await = Some(CachedWeather {
+            settings: weather.clone(),
+            fetched_at: Instant::now(),
+            report: Some(report.clone()),
+        });
+
+        // No widget configured
+        assert_eq!(current(&state).await, None);
+
+        state
+            .config
+            .update(|config| {
+                config.weather = Some(weather);
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(current(&state).await, Some(report));
+    }
+
+    #[test]
+    fn validation_names_each_problem() {
+        let mut weather = settings(WeatherProvider::OpenMeteo, "Berlin");
+        weather.refresh_secs = 5;
+        let fields: Vec<String> = weather
+            .validate()
+            .into_iter()
+            .map(|(field, _)| field)
+            .collect();
+        assert_eq!(fields, ["location", "refresh_secs"]);
+
+        let mut weather = settings(WeatherProvider::OpenWeatherMap, " ");
+        weather.api_key = Some(String::new());
+        let fields: Vec<String> = weather
+            .validate()
+            .into_iter()
+            .map(|(field, _)| field)
+            .collect();
+        assert_eq!(fields, ["location", "api_key"]);
+    }
+}
@@ -0,0 +1,373 @@
+//! Custom dashboard widgets
+//!
+//! Besides bookmarks, the dashboard can show tiles with other content, such as a
+//! calendar or a Grafana panel. Each entry of the config's `widgets` list has a title and
+//! either an `iframe` URL or an `html` snippet:
+//!
+//! ```json5
+//! widgets: [
+//!     { title: "Calendar", iframe: "https://calendar.example.com/embed", height: 400 },
+//!     { title: "On call", html: "<p>This week: <strong>Sam</strong></p>" },
+//! ]
+//! ```
+//!
+//! Frames are sandboxed so an embedded page cannot navigate the dashboard away, and run
+//! in an opaque origin so their scripts cannot reach the cookies or storage of their own
+//! site, even when it is served from the dashboard's origin. HTML
+//! snippets are sanitized with [`sanitize_html`] before they are rendered: only common
+//! formatting elements and safe attributes are kept, and scripts, styles, event handlers,
+//! and `javascript:` links are removed.
+
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+
+/// Height, in pixels, of a widget that does not set `height`
+pub const DEFAULT_HEIGHT: u32 = 300;
+
+/// Elements kept by [`sanitize_html`]; others are dropped but their text is kept
+const ALLOWED_ELEMENTS: &[&str] = &[
+    "a",
+    "abbr",
+    "b",
+    "blockquote",
+    "br",
+    "code",
+    "dd",
+    "div",
+    "dl",
+    "dt",
+    "em",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "hr",
+    "i",
+    "img",
+    "li",
+    "ol",
+    "p",
+    "pre",
+    "small",
+    "span",
+    "strong",
+    "sub",
+    "sup",
+    "table",
+    "tbody",
+    "td",
+    "th",
+    "thead",
+    "tr",
+    "u",
+    "ul",
+];
+
+/// Elements dropped by [`sanitize_html`] together with everything inside them
+const DROPPED_ELEMENTS: &[&str] = &[
+    "script", "style", "iframe", "object", "embed", "template", "noscript", "svg", "math",
+    "textarea", "select", "title",
+];
+
+/// Elements without a closing tag
+const VOID_ELEMENTS: &[&str] = &["br", "hr", "img"];
+
+/// A tile on the dashboard showing an embedded page or an HTML snippet
+///
+/// # Fields
+///
+/// * `title` - Heading of the tile
+/// * `iframe` - `http` or `https` URL of a page embedded in the tile
+/// * `html` - HTML snippet shown in the tile after [`sanitize_html`]
+/// * `height` - Height of the tile's content in pixels, [`DEFAULT_HEIGHT`] if unset
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+pub struct Widget {
+    /// Heading of the tile
+    pub title: String,
+    /// URL of a page embedded in the tile
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub iframe: Option<String>,
+    /// HTML snippet shown in the tile
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub html: Option<String>,
+    /// Height of the tile's content in pixels
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub height: Option<u32>,
+}
+
+impl Widget {
+    /// Find problems with the widget, as field names with a message for each.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use iron_shield::widgets::Widget;
+    ///
+    /// let widget = Widget {
+    ///     title: "Calendar".to_string(),
+    ///     iframe: Some("https://calendar.example.com/embed".to_string()),
+    ///     html: None,
+    ///     height: None,
+    /// };
+    /// assert!(widget.validate().is_empty());
+    ///
+    /// let both = Widget {
+    ///     html: Some("<p>Hi</p>".to_string()),
+    ///     ..widget
+    /// };
+    /// assert_eq!(both.validate()[0].0, "iframe");
+    /// ```
+    #[must_use]
+    pub fn validate(&self) -> Vec<(String, String)> {
+        let mut problems = Vec::new();
+        if self.title.trim().is_empty() {
+            problems.push(("title".to_string(), "cannot be empty".to_string()));
+        }
+        match (&self.iframe, &self.html) {
+            (Some(_), Some(_)) => problems.push((
+                "iframe".to_string(),
+                "cannot be set together with html".to_string(),
+            )),
+            (None, None) => {
+                problems.push(("iframe".to_string(), "or html must be set".to_string()))
+            }
+            (Some(iframe), None) if !is_http_url(iframe) => problems.push((
+                "iframe".to_string(),
+                format!("\"{iframe}\" is not an http or https URL"),
+            )),
+            _ => {}
+        }
+        if self.height == Some(0) {
+            problems.push(("height".to_string(), "must be at least 1".to_string()));
+        }
+        problems
+    }
+
+    /// Height of the tile's content in pixels.
+    #[must_use]
+    pub fn height(&self) -> u32 {
+        self.height.unwrap_or(DEFAULT_HEIGHT)
+    }
+
+    /// The widget's HTML snippet, sanitized for rendering.
+    #[must_use]
+    pub fn safe_html(&self) -> Option<String> {
+        self.html.as_deref().map(sanitize_html)
+    }
+}
+
+fn is_http_url(value: &str) -> bool {
+    url::Url::parse(value)
+        .is_ok_and(|url| matches!(url.scheme(), "http" | "https") && url.host().is_some())
+}
+
+/// Whether `value` is safe as a link or image address: relative, or `http`, `https`, or
+/// `mailto`.
+fn is_safe_url(value: &str) -> bool {
+    let lowered: String = value
+        .chars()
+        .filter(|c| !c.is_whitespace() && !c.is_control())
+        .collect::<String>()
+        .to_ascii_lowercase();
+    match lowered.split_once(':') {
+        Some((scheme, _)) if !scheme.contains(['/', '?', '#']) => {
+            matches!(scheme, "http" | "https" | "mailto")
+        }
+        _ => true,
+    }
+}
+
+/// Keep only safe markup in an HTML snippet
+///
+/// Elements in a fixed allowlist of formatting and layout tags are kept with the
+/// attributes `title`, `href` on links, and `src`, `alt`, `width`, and `height` on images,
+/// when their addresses are relative or `http`, `https`, or `mailto`. Links open in a new
+/// tab. Other elements are dropped but their text is kept, except for scripts, styles,
+/// and embedded content, which are dropped entirely. Comments are removed and text is
+/// escaped, and every kept element is closed.
+///
+/// # Examples
+///
+/// ```
+/// use iron_shield::widgets::sanitize_html;
+///
+/// assert_eq!(
+///     sanitize_html(r#"<p onclick="steal()">Hi <b>there</b><script>steal()</script></p>"#),
+///     "<p>Hi <b>there</b></p>"
+/// );
+/// assert_eq!(
+///     sanitize_html(r#"<a href="javascript:steal()">x</a>"#),
+///     r#"<a rel="noopener noreferrer" target="_blank">x</a>"#
+/// );
+/// ```
+#[must_use]
+pub fn sanitize_html(html: &str) -> String {
+    let mut output = String::with_capacity(html.len());
+    let mut open: Vec<&'static str> = Vec::new();
+    let mut rest = html;
+
+    while let Some(start) = rest.find('<') {
+        push_text(&mut output, &rest[..start]);
+        rest = &rest[start..];
+
+        if let Some(comment) = rest.strip_prefix("<!--") {
+            rest = comment.find("-->").map_or("", |end| &comment[end + 3..]);
+            continue;
+        }
+        let Some(end) = rest.find('>') else {
+            push_text(&mut output, rest);
+            rest = "";
+            break;
+        };
+        let tag = &rest[1..end];
+        rest = &rest[end + 1..];
+
+        let closing = tag.starts_with('/');
+        let tag = tag.trim_start_matches('/');
+        let name_end = tag
+            .find(|c: char| c.is_whitespace() || c == '/')
+            .unwrap_or(tag.len());
+        let name = tag[..name_end].to_ascii_lowercase();
+        let attributes = &tag[name_end..];
+
+        if !closing {
+            if let Some(dropped) = DROPPED_ELEMENTS.iter().find(|dropped| **dropped == name) {
+                let close = format!("</{dropped}");
+                rest = rest
+                    .to_ascii_lowercase()
+                    .find(&close)
+                    .and_then(|close_start| {
+                        let after = &rest[close_start..];
+                        after.find('>').map(|end| &after[end + 1..])
+                    })
+                    .unwrap_or("");
+                continue;
+            }
+        }
+        let Some(&element) = ALLOWED_ELEMENTS.iter().find(|allowed| **allowed == name) else {
+            continue;
+        };
+
+        if closing {
+            if let Some(position) = open.iter().rposition(|tag| *tag == element) {
+                for tag in open.drain(position..).rev() {
+                    let _ = write!(output, "</{tag}>");
+                }
+            }
+            continue;
+        }
+
+        output.push('<');
+        output.push_str(element);
+        push_attributes(&mut output, element, attributes);
+        output.push('>');
+        if !VOID_ELEMENTS.contains(&element) {
+            open.push(element);
+        }
+    }
+    push_text(&mut output, rest);
+    for tag in open.into_iter().rev() {
+        let _ = write!(output, "</{tag}>");
+    }
+    output
+}
+
+/// Write the allowed attributes of an `element` start tag.
+fn push_attributes(output: &mut String, element: &str, attributes: &str) {
+    let allowed: &[&str] = match element {
+        "a" => &["href", "title"],
+        "img" => &["src", "alt", "width", "height", "title"],
+        _ => &["title"],
+    };
+    for name in allowed {
+        let Some(value) = crate::bookmarks::attribute(attributes, name) else {
+            continue;
+        };
+        let is_address = matches!(*name, "href" | "src");
+        if is_address && !is_safe_url(&value) {
+            continue;
+        }
+        if matches!(*name, "width" | "height") && value.parse::<u32>().is_err() {
+            continue;
+        }
+        let _ = write!(output, " {name}=\"{}\"", escape(&value));
+    }
+    if element == "a" {
+        output.push_str(" rel=\"noopener noreferrer\" target=\"_blank\"");
+    }
+}
+
+/// Append `text` with the characters that are special in HTML escaped.
+fn push_text(output: &mut String, text: &str) {
+    output.push_str(&escape(text));
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            '&' => escaped.push_str("&amp;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sanitizing_removes_active_content_and_balances_tags() {
+        assert_eq!(
+            sanitize_html(
+                "<div><style>body{}</style><img src=x onerror=alert(1)><SCRIPT>alert(1)</script >\
+                 <iframe src=\"https://evil.example\"></iframe><ul><li>one<li>two</ul>"
+            ),
+            "<div><img src=\"x\"><ul><li>one<li>two</li></li></ul></div>"
+        );
+        assert_eq!(
+            sanitize_html("<!-- hidden --><custom-tag>kept</custom-tag> & <b>open"),
+            "kept &amp; <b>open</b>"
+        );
+        assert_eq!(
+            sanitize_html("<a href=\" JaVa\tScript:alert(1)\" title='\"x\"'>x</a> 1 < 2"),
+            "<a title=\"&quot;x&quot;\" rel=\"noopener noreferrer\" target=\"_blank\">x</a> 1 &lt; 2"
+        );
+        assert_eq!(
+            sanitize_html("<img src=\"/branding/logo\" width=\"20\" height=\"tall\">"),
+            "<img src=\"/branding/logo\" width=\"20\">"
+        );
+    }
+
+    #[test]
+    fn validation_requires_one_content_source() {
+        let widget = Widget {
+            title: " ".to_string(),
+            iframe: None,
+            html: None,
+            height: Some(0),
+        };
+        let fields: Vec<String> = widget
+            .validate()
+            .into_iter()
+            .map(|(field, _)| field)
+            .collect();
+        assert_eq!(fields, ["title", "iframe", "height"]);
+
+        let widget = Widget {
+            title: "Panel".to_string(),
+            iframe: Some("javascript:alert(1)".to_string()),
+            html: None,
+            height: None,
+        };
+        assert_eq!(widget.validate().len(), 1);
+        assert_eq!(widget.height(), DEFAULT_HEIGHT);
+    }
+}
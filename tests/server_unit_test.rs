@@ -50,10 +50,17 @@ async fn test_server_starts_and_serves_index() {
             url: "http://example.com".to_string(),
             category: "Test".to_string(),
             tags: vec!["test".to_string()],
-            monitor_interval_secs: iron_shield::config::DEFAULT_MONITOR_INTERVAL_SECS,
+            monitor_interval_secs: Some(iron_shield::config::DEFAULT_MONITOR_INTERVAL_SECS),
+            monitor_timeout_secs: None,
+            monitor_retries: None,
+            monitor_user_agent: None,
             disabled: false,
+            icon: None,
+            order: None,
+            assertions: None,
             uptime_percentage: 0.0,
         }],
+        ..Config::default()
     };
     let config_content =
         serde_json::to_string_pretty(&test_config).expect("Failed to serialize test config");
@@ -97,3 +104,50 @@ async fn test_server_starts_and_serves_index() {
     // Wait for the server to shut down
     server_handle.await.expect("Server task failed");
 }
+
+#[tokio::test]
+async fn test_server_shutdown_closes_open_sse_streams() {
+    let mut config_file = NamedTempFile::new().expect("Failed to create temp config file");
+    let test_config = Config {
+        site_name: "Shutdown Test".to_string(),
+        shutdown_timeout_secs: 30,
+        ..Config::default()
+    };
+    let config_content =
+        serde_json::to_string_pretty(&test_config).expect("Failed to serialize test config");
+    config_file
+        .write_all(config_content.as_bytes())
+        .expect("Failed to write to temp config file");
+    let config_path = config_file.path().to_path_buf();
+
+    let Some(port) = find_available_port().await else {
+        return;
+    };
+    let cancel_token = CancellationToken::new();
+
+    let server_handle = tokio::spawn({
+        let cancel_token = cancel_token.clone();
+        async move {
+            run(port, Some(config_path), cancel_token)
+                .await
+                .expect("Server failed to start");
+        }
+    });
+
+    sleep(Duration::from_secs(1)).await;
+
+    // Keep an SSE connection open while shutting down
+    let sse_response = reqwest::get(format!("http://127.0.0.1:{port}/uptime"))
+        .await
+        .expect("Failed to open SSE stream");
+    assert_eq!(sse_response.status(), StatusCode::OK);
+
+    cancel_token.cancel();
+
+    // The open stream must not hold the server until the 30s drain timeout
+    tokio::time::timeout(Duration::from_secs(5), server_handle)
+        .await
+        .expect("Server did not shut down while an SSE client was connected")
+        .expect("Server task failed");
+    drop(sse_response);
+}
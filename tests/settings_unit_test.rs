@@ -1,20 +1,16 @@
 use axum::{
-    extract::{Json, State},
-    http::StatusCode,
+    extract::{Form, Json, Path, Query, State},
+    http::{HeaderMap, StatusCode},
     response::IntoResponse,
 };
 use http_body_util::BodyExt; // For .collect()
 use iron_shield::config::{Clock, Config, Site, DEFAULT_MONITOR_INTERVAL_SECS};
-use iron_shield::error::IronShieldError;
 use iron_shield::settings::{ConfigUpdate, SiteUpdate};
-use iron_shield::uptime::UptimeState;
+use iron_shield::uptime::{ServerEvent, UptimeState};
 use std::fs;
 use std::path::PathBuf;
 use std::sync::Arc;
-use std::sync::RwLock; // Use std::sync::RwLock
 use tempfile::tempdir;
-use tokio::sync::broadcast;
-use tokio_util::sync::CancellationToken;
 
 #[tokio::test]
 async fn test_config_update_validate_valid() {
@@ -28,16 +24,28 @@ async fn test_config_update_validate_valid() {
                 url: "https://www.google.com".to_string(),
                 category: "Search".to_string(),
                 tags: vec!["web".to_string()],
-                monitor_interval_secs: DEFAULT_MONITOR_INTERVAL_SECS,
+                monitor_interval_secs: Some(DEFAULT_MONITOR_INTERVAL_SECS),
+                monitor_timeout_secs: None,
+                monitor_retries: None,
+                monitor_user_agent: None,
                 disabled: false,
+                icon: None,
+                order: None,
+                assertions: None,
             },
             SiteUpdate {
                 name: "Rust-lang".to_string(),
                 url: "https://www.rust-lang.org".to_string(),
                 category: "Programming".to_string(),
                 tags: vec!["dev".to_string(), "oss".to_string()],
-                monitor_interval_secs: DEFAULT_MONITOR_INTERVAL_SECS,
+                monitor_interval_secs: Some(DEFAULT_MONITOR_INTERVAL_SECS),
+                monitor_timeout_secs: None,
+                monitor_retries: None,
+                monitor_user_agent: None,
                 disabled: false,
+                icon: None,
+                order: None,
+                assertions: None,
             },
         ],
     };
@@ -57,7 +65,7 @@ async fn test_config_update_validate_empty_site_name() {
     let err = config_update.validate().unwrap_err();
     assert_eq!(
         err.to_string(),
-        IronShieldError::from("Site name cannot be empty").to_string()
+        "Invalid configuration: site_name cannot be empty"
     );
 }
 
@@ -73,7 +81,7 @@ async fn test_config_update_validate_invalid_clock_format() {
     let err = config_update.validate().unwrap_err();
     assert_eq!(
         err.to_string(),
-        IronShieldError::from("Invalid clock format").to_string()
+        "Invalid configuration: clock must be one of \"24hour\", \"12hour\" or \"none\""
     );
 }
 
@@ -88,15 +96,21 @@ async fn test_config_update_validate_empty_site_entry_name() {
             url: "https://www.google.com".to_string(),
             category: "Search".to_string(),
             tags: vec![],
-            monitor_interval_secs: DEFAULT_MONITOR_INTERVAL_SECS,
+            monitor_interval_secs: Some(DEFAULT_MONITOR_INTERVAL_SECS),
+            monitor_timeout_secs: None,
+            monitor_retries: None,
+            monitor_user_agent: None,
             disabled: false,
+            icon: None,
+            order: None,
+            assertions: None,
         }],
     };
 
     let err = config_update.validate().unwrap_err();
     assert_eq!(
         err.to_string(),
-        IronShieldError::from("Site name cannot be empty").to_string()
+        "Invalid configuration: sites[0].name cannot be empty"
     );
 }
 
@@ -111,15 +125,21 @@ async fn test_config_update_validate_empty_site_entry_url() {
             url: String::new(),
             category: "Search".to_string(),
             tags: vec![],
-            monitor_interval_secs: DEFAULT_MONITOR_INTERVAL_SECS,
+            monitor_interval_secs: Some(DEFAULT_MONITOR_INTERVAL_SECS),
+            monitor_timeout_secs: None,
+            monitor_retries: None,
+            monitor_user_agent: None,
             disabled: false,
+            icon: None,
+            order: None,
+            assertions: None,
         }],
     };
 
     let err = config_update.validate().unwrap_err();
     assert_eq!(
         err.to_string(),
-        IronShieldError::from("Site URL cannot be empty").to_string()
+        "Invalid configuration: sites[0].url cannot be empty"
     );
 }
 
@@ -134,15 +154,21 @@ async fn test_config_update_validate_invalid_site_entry_url() {
             url: "invalid-url".to_string(),
             category: "Search".to_string(),
             tags: vec![],
-            monitor_interval_secs: DEFAULT_MONITOR_INTERVAL_SECS,
+            monitor_interval_secs: Some(DEFAULT_MONITOR_INTERVAL_SECS),
+            monitor_timeout_secs: None,
+            monitor_retries: None,
+            monitor_user_agent: None,
             disabled: false,
+            icon: None,
+            order: None,
+            assertions: None,
         }],
     };
 
     let err = config_update.validate().unwrap_err();
     assert_eq!(
         err.to_string(),
-        IronShieldError::from("Invalid URL format: invalid-url").to_string()
+        "Invalid configuration: sites[0].url is not a valid URL: relative URL without a base"
     );
 }
 
@@ -153,15 +179,9 @@ fn create_test_uptime_state(config_file_path: PathBuf) -> Arc<UptimeState> {
         clock: Clock::Hour24,
         opentelemetry_endpoint: None,
         sites: vec![],
+        ..Config::default()
     };
-    let (shutdown_events, _) = broadcast::channel(1);
-    Arc::new(UptimeState {
-        config: Arc::new(RwLock::new(config)), // Use std::sync::RwLock
-        history: Arc::new(RwLock::new(std::collections::HashMap::new())),
-        config_file_path,
-        shutdown_events,
-        shutdown_token: CancellationToken::new(),
-    })
+    Arc::new(UptimeState::new(config, config_file_path))
 }
 
 #[tokio::test]
@@ -175,6 +195,8 @@ async fn test_save_config_success() {
         .expect("Failed to write initial config file in test_save_config_success");
 
     let state = create_test_uptime_state(temp_config_path.clone());
+    let mut server_events = state.server_events.subscribe();
+    *state.config_error.write() = Some("Invalid configuration".to_string());
 
     let payload = ConfigUpdate {
         site_name: "Updated Site Name".to_string(),
@@ -185,13 +207,24 @@ async fn test_save_config_success() {
             url: "https://new.example.com".to_string(),
             category: "Test".to_string(),
             tags: vec!["new".to_string()],
-            monitor_interval_secs: DEFAULT_MONITOR_INTERVAL_SECS,
+            monitor_interval_secs: Some(DEFAULT_MONITOR_INTERVAL_SECS),
+            monitor_timeout_secs: None,
+            monitor_retries: None,
+            monitor_user_agent: None,
             disabled: false,
+            icon: None,
+            order: None,
+            assertions: None,
         }],
     };
 
-    let response =
-        iron_shield::settings::save_config(State(state.clone()), Json(payload.clone())).await;
+    let response = iron_shield::settings::save_config(
+        State(state.clone()),
+        Query(iron_shield::settings::SaveQuery::default()),
+        HeaderMap::new(),
+        Json(payload.clone()),
+    )
+    .await;
     let (parts, body) = response.into_response().into_parts();
     let body_bytes = body
         .collect()
@@ -216,24 +249,33 @@ async fn test_save_config_success() {
             url: "https://new.example.com".to_string(),
             category: "Test".to_string(),
             tags: vec!["new".to_string()],
-            monitor_interval_secs: DEFAULT_MONITOR_INTERVAL_SECS,
+            monitor_interval_secs: Some(DEFAULT_MONITOR_INTERVAL_SECS),
+            monitor_timeout_secs: None,
+            monitor_retries: None,
+            monitor_user_agent: None,
             disabled: false,
+            icon: None,
+            order: None,
+            assertions: None,
             uptime_percentage: 0.0,
         }],
+        ..Config::default()
     };
     let expected_json = json5::to_string(&expected_config)
         .expect("Failed to serialize expected config to JSON5 in test_save_config_success");
     assert_eq!(file_content, expected_json);
 
     // Verify in-memory config update
-    let config_guard = state
-        .config
-        .read()
-        .expect("Failed to acquire config read lock in test_save_config_success"); // Removed .await
+    let config_guard = state.config.load(); // Removed .await
     assert_eq!(config_guard.site_name, "Updated Site Name");
     assert_eq!(config_guard.clock, Clock::Hour12);
     assert_eq!(config_guard.sites.len(), 1);
     assert_eq!(config_guard.sites[0].name, "New Site");
+
+    // Open dashboards are told to refresh their site list
+    assert_eq!(server_events.try_recv(), Ok(ServerEvent::ConfigUpdated));
+    // The saved file replaces the one an earlier reload rejected
+    assert_eq!(*state.config_error.read(), None);
 }
 
 #[tokio::test]
@@ -245,6 +287,7 @@ async fn test_save_config_invalid_payload() {
         .expect("Failed to write initial config file in test_save_config_invalid_payload");
 
     let state = create_test_uptime_state(temp_config_path.clone());
+    let mut server_events = state.server_events.subscribe();
 
     let payload = ConfigUpdate {
         site_name: String::new(), // Invalid site name
@@ -253,8 +296,13 @@ async fn test_save_config_invalid_payload() {
         sites: vec![],
     };
 
-    let response =
-        iron_shield::settings::save_config(State(state.clone()), Json(payload.clone())).await;
+    let response = iron_shield::settings::save_config(
+        State(state.clone()),
+        Query(iron_shield::settings::SaveQuery::default()),
+        HeaderMap::new(),
+        Json(payload.clone()),
+    )
+    .await;
     let (parts, body) = response.into_response().into_parts();
     let body_bytes = body
         .collect()
@@ -264,8 +312,17 @@ async fn test_save_config_invalid_payload() {
     let body_string = String::from_utf8(body_bytes.to_vec())
         .expect("Failed to convert response body to string in save_config_invalid_payload test");
 
-    assert_eq!(parts.status, StatusCode::INTERNAL_SERVER_ERROR);
-    assert_eq!(body_string, "Error: Site name cannot be empty");
+    assert_eq!(parts.status, StatusCode::BAD_REQUEST);
+    let error: serde_json::Value =
+        serde_json::from_str(&body_string).expect("Error response should be JSON");
+    assert_eq!(
+        error,
+        serde_json::json!({
+            "code": "CONFIG_VALIDATION",
+            "message": "Invalid configuration: site_name cannot be empty",
+            "errors": [{ "field": "site_name", "message": "cannot be empty" }]
+        })
+    );
 
     // Verify file content is unchanged (or still initial empty json)
     let file_content = fs::read_to_string(&temp_config_path)
@@ -273,9 +330,1654 @@ async fn test_save_config_invalid_payload() {
     assert_eq!(file_content, "{}");
 
     // Verify in-memory config is unchanged
-    let config_guard = state
-        .config
-        .read()
-        .expect("Failed to acquire config read lock in test_save_config_invalid_payload"); // Removed .await
+    let config_guard = state.config.load(); // Removed .await
     assert_eq!(config_guard.site_name, "Initial Site");
+    assert!(server_events.try_recv().is_err());
+}
+
+#[tokio::test]
+async fn test_save_config_keeps_secret_references_out_of_the_file() {
+    let temp_dir = tempdir().expect("Failed to create temporary directory for secret test");
+    let temp_config_path = temp_dir.path().join("config.json5");
+    let secret_path = temp_dir.path().join("endpoint");
+    fs::write(&secret_path, "http://127.0.0.1:9/ingest\n").expect("Failed to write secret");
+    let reference = format!("file:{}", secret_path.display());
+    fs::write(
+        &temp_config_path,
+        format!("{{ opentelemetry_endpoint: {reference:?} }}"),
+    )
+    .expect("Failed to write initial config file");
+
+    let state = create_test_uptime_state(temp_config_path.clone());
+    state
+        .config
+        .store(Config::load(&temp_config_path).expect("Initial config should load"));
+
+    let payload = ConfigUpdate {
+        site_name: "Secret Site".to_string(),
+        clock: "none".to_string(),
+        opentelemetry_endpoint: Some(reference.clone()),
+        sites: vec![],
+    };
+
+    let response = iron_shield::settings::save_config(
+        State(state.clone()),
+        Query(iron_shield::settings::SaveQuery::default()),
+        HeaderMap::new(),
+        Json(payload),
+    )
+    .await;
+    assert_eq!(response.into_response().status(), StatusCode::OK);
+
+    let saved: Config = json5::from_str(
+        &fs::read_to_string(&temp_config_path).expect("Failed to read saved config"),
+    )
+    .expect("Saved config should parse");
+    assert_eq!(saved.opentelemetry_endpoint, Some(reference));
+
+    let config_guard = state.config.load();
+    assert_eq!(
+        config_guard.opentelemetry_endpoint.as_deref(),
+        Some("http://127.0.0.1:9/ingest")
+    );
+}
+
+#[tokio::test]
+async fn test_settings_api_rejects_new_secret_references() {
+    let temp_dir = tempdir().expect("Failed to create temporary directory for secret test");
+    let temp_config_path = temp_dir.path().join("config.json5");
+    fs::write(&temp_config_path, "{}").expect("Failed to write initial config file");
+    let secret_path = temp_dir.path().join("password");
+    fs::write(&secret_path, "hunter2\n").expect("Failed to write secret");
+    let state = create_test_uptime_state(temp_config_path.clone());
+
+    for reference in [
+        format!("file:{}", secret_path.display()),
+        format!(
+            "https://collector.example.com/${{file:{}}}",
+            secret_path.display()
+        ),
+        "https://collector.example.com/${env:HOME}".to_string(),
+    ] {
+        let payload = ConfigUpdate {
+            site_name: "Secret Site".to_string(),
+            clock: "none".to_string(),
+            opentelemetry_endpoint: Some(reference.clone()),
+            sites: vec![],
+        };
+        let (status, body) = response_text(
+            iron_shield::settings::save_config(
+                State(state.clone()),
+                Query(iron_shield::settings::SaveQuery::default()),
+                HeaderMap::new(),
+                Json(payload),
+            )
+            .await
+            .into_response(),
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{reference}: {body}");
+        let error: serde_json::Value = serde_json::from_str(&body).expect("Error should be JSON");
+        assert_eq!(error["errors"][0]["field"], "opentelemetry_endpoint");
+        assert_eq!(
+            error["errors"][0]["message"],
+            "has a new secret reference; add it to the config file instead"
+        );
+
+        let patch = serde_json::json!({ "opentelemetry_endpoint": reference });
+        let (status, _) = response_text(
+            iron_shield::settings::patch_settings(
+                State(state.clone()),
+                HeaderMap::new(),
+                Json(
+                    patch
+                        .as_object()
+                        .expect("Patch should be an object")
+                        .clone(),
+                ),
+            )
+            .await
+            .into_response(),
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{reference}");
+    }
+
+    assert_eq!(
+        fs::read_to_string(&temp_config_path).expect("Failed to read config"),
+        "{}"
+    );
+    assert_eq!(state.config.load().opentelemetry_endpoint, None);
+}
+
+/// Creates a state whose config file holds one site whose URL uses a `vars` variable.
+fn create_site_patch_state(config_file_path: PathBuf) -> Arc<UptimeState> {
+    fs::write(
+        &config_file_path,
+        r#"{
+            vars: { BASE_DOMAIN: "home.lan" },
+            sites: [
+                { name: "NAS", url: "http://nas.${BASE_DOMAIN}", category: "Storage", tags: ["home"] },
+                { name: "Router", url: "http://router.${BASE_DOMAIN}", tags: [] },
+            ],
+        }"#,
+    )
+    .expect("Failed to write initial config file");
+    let state = create_test_uptime_state(config_file_path.clone());
+    state
+        .config
+        .store(Config::load(&config_file_path).expect("Initial config should load"));
+    state
+}
+
+async fn patch_site(
+    state: &Arc<UptimeState>,
+    name: &str,
+    patch: serde_json::Value,
+) -> (StatusCode, String) {
+    let patch = patch
+        .as_object()
+        .expect("Patch should be an object")
+        .clone();
+    let response = iron_shield::settings::patch_site(
+        State(state.clone()),
+        Path(name.to_string()),
+        Json(patch),
+    )
+    .await;
+    let (parts, body) = response.into_response().into_parts();
+    let body_bytes = body
+        .collect()
+        .await
+        .expect("Failed to collect patch response body")
+        .to_bytes();
+    (
+        parts.status,
+        String::from_utf8(body_bytes.to_vec()).expect("Patch response should be UTF-8"),
+    )
+}
+
+#[tokio::test]
+async fn test_patch_site_changes_only_the_given_fields() {
+    let temp_dir = tempdir().expect("Failed to create temporary directory for patch test");
+    let temp_config_path = temp_dir.path().join("config.json5");
+    let state = create_site_patch_state(temp_config_path.clone());
+    let mut server_events = state.server_events.subscribe();
+
+    let (status, body) = patch_site(
+        &state,
+        "NAS",
+        serde_json::json!({ "tags": ["storage"], "icon": "server" }),
+    )
+    .await;
+
+    assert_eq!(status, StatusCode::OK);
+    let site: SiteUpdate = serde_json::from_str(&body).expect("Response should be a site");
+    assert_eq!(site.tags, vec!["storage".to_string()]);
+    assert_eq!(site.url, "http://nas.home.lan");
+    assert_eq!(site.category, "Storage");
+
+    let saved: Config = json5::from_str(
+        &fs::read_to_string(&temp_config_path).expect("Failed to read saved config"),
+    )
+    .expect("Saved config should parse");
+    assert_eq!(saved.sites.len(), 2);
+    assert_eq!(saved.sites[0].tags, vec!["storage".to_string()]);
+    assert_eq!(saved.sites[0].icon.as_deref(), Some("server"));
+    // Untouched values keep their variable references
+    assert_eq!(saved.sites[0].url, "http://nas.${BASE_DOMAIN}");
+    assert_eq!(saved.sites[1].url, "http://router.${BASE_DOMAIN}");
+
+    let config_guard = state.config.load();
+    assert_eq!(config_guard.sites[0].icon.as_deref(), Some("server"));
+    assert_eq!(config_guard.sites[0].url, "http://nas.home.lan");
+    drop(config_guard);
+    assert_eq!(server_events.try_recv(), Ok(ServerEvent::ConfigUpdated));
+}
+
+#[tokio::test]
+async fn test_patch_site_rejects_unknown_sites_and_invalid_patches() {
+    let temp_dir = tempdir().expect("Failed to create temporary directory for patch test");
+    let temp_config_path = temp_dir.path().join("config.json5");
+    let state = create_site_patch_state(temp_config_path.clone());
+    let original = fs::read_to_string(&temp_config_path).expect("Failed to read config");
+
+    let (status, body) = patch_site(&state, "Printer", serde_json::json!({ "tags": [] })).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(
+        body,
+        r#"{"code":"SITE_NOT_FOUND","message":"No site named \"Printer\"","errors":[]}"#
+    );
+
+    let (status, body) = patch_site(&state, "NAS", serde_json::json!({ "colour": "red" })).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let error: serde_json::Value = serde_json::from_str(&body).expect("Error should be JSON");
+    assert_eq!(
+        error["errors"],
+        serde_json::json!([{ "field": "colour", "message": "is not a site field" }])
+    );
+
+    let (status, body) = patch_site(&state, "NAS", serde_json::json!({ "name": "router" })).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let error: serde_json::Value = serde_json::from_str(&body).expect("Error should be JSON");
+    assert_eq!(error["errors"][0]["field"], "sites[1].name");
+    assert_eq!(
+        error["errors"][0]["message"],
+        "\"Router\" duplicates sites[0].name \"router\""
+    );
+
+    let (status, _) = patch_site(&state, "NAS", serde_json::json!({ "url": "not a url" })).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    assert_eq!(
+        fs::read_to_string(&temp_config_path).expect("Failed to read config"),
+        original
+    );
+    assert_eq!(state.config.load().sites[0].url, "http://nas.home.lan");
+}
+
+async fn add_site(state: &Arc<UptimeState>, site: serde_json::Value) -> (StatusCode, String) {
+    let site: SiteUpdate = serde_json::from_value(site).expect("Site should deserialize");
+    let response = iron_shield::settings::add_site(State(state.clone()), Json(site)).await;
+    let (parts, body) = response.into_response().into_parts();
+    let body_bytes = body
+        .collect()
+        .await
+        .expect("Failed to collect add response body")
+        .to_bytes();
+    (
+        parts.status,
+        String::from_utf8(body_bytes.to_vec()).expect("Add response should be UTF-8"),
+    )
+}
+
+#[tokio::test]
+async fn test_add_site_appends_and_saves_the_site() {
+    let temp_dir = tempdir().expect("Failed to create temporary directory for add test");
+    let temp_config_path = temp_dir.path().join("config.json5");
+    let state = create_site_patch_state(temp_config_path.clone());
+    let mut server_events = state.server_events.subscribe();
+
+    let (status, body) = add_site(
+        &state,
+        serde_json::json!({ "name": "Grafana", "url": "https://grafana.${BASE_DOMAIN}" }),
+    )
+    .await;
+
+    assert_eq!(status, StatusCode::CREATED);
+    let site: SiteUpdate = serde_json::from_str(&body).expect("Response should be a site");
+    assert_eq!(site.url, "https://grafana.home.lan");
+    assert!(site.tags.is_empty());
+
+    let saved: Config = json5::from_str(
+        &fs::read_to_string(&temp_config_path).expect("Failed to read saved config"),
+    )
+    .expect("Saved config should parse");
+    let names: Vec<&str> = saved.sites.iter().map(|site| site.name.as_str()).collect();
+    assert_eq!(names, ["NAS", "Router", "Grafana"]);
+    assert_eq!(saved.sites[2].url, "https://grafana.${BASE_DOMAIN}");
+    assert_eq!(saved.sites[0].url, "http://nas.${BASE_DOMAIN}");
+
+    assert_eq!(state.config.load().sites.len(), 3);
+    assert_eq!(server_events.try_recv(), Ok(ServerEvent::ConfigUpdated));
+}
+
+#[tokio::test]
+async fn test_add_site_rejects_existing_names_and_invalid_sites() {
+    let temp_dir = tempdir().expect("Failed to create temporary directory for add test");
+    let temp_config_path = temp_dir.path().join("config.json5");
+    let state = create_site_patch_state(temp_config_path.clone());
+    let original = fs::read_to_string(&temp_config_path).expect("Failed to read config");
+
+    let (status, body) = add_site(
+        &state,
+        serde_json::json!({ "name": "nas", "url": "http://nas.example.com" }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    assert_eq!(
+        body,
+        r#"{"code":"SITE_EXISTS","message":"A site named \"nas\" already exists","errors":[]}"#
+    );
+
+    let (status, body) = add_site(
+        &state,
+        serde_json::json!({ "name": "Printer", "url": "http://printer.${MISSING_DOMAIN}" }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let error: serde_json::Value = serde_json::from_str(&body).expect("Error should be JSON");
+    assert_eq!(error["errors"][0]["field"], "sites[2].url");
+    assert!(error["errors"][0]["message"]
+        .as_str()
+        .is_some_and(|message| message.starts_with("uses undefined variable MISSING_DOMAIN")));
+
+    assert_eq!(
+        fs::read_to_string(&temp_config_path).expect("Failed to read config"),
+        original
+    );
+    assert_eq!(state.config.load().sites.len(), 2);
+}
+
+#[tokio::test]
+async fn test_delete_site_removes_site_references_and_history() {
+    let temp_dir = tempdir().expect("Failed to create temporary directory for delete test");
+    let temp_config_path = temp_dir.path().join("config.json5");
+    let state = create_site_patch_state(temp_config_path.clone());
+    state
+        .config
+        .update(|config| {
+            config.groups = vec![iron_shield::config::Group {
+                name: "Home".to_string(),
+                sites: vec!["NAS".to_string(), "Router".to_string()],
+                collapsed: false,
+            }];
+            Ok(())
+        })
+        .expect("Failed to group the sites");
+    for name in ["NAS", "Router"] {
+        state.history.write().insert(
+            name.to_string(),
+            std::collections::VecDeque::from([iron_shield::uptime::HistoryEntry {
+                status: iron_shield::uptime::UptimeStatus::Up,
+                response_time_ms: Some(12),
+                failure_reason: None,
+                checked_at: 0,
+            }]),
+        );
+    }
+    let mut server_events = state.server_events.subscribe();
+
+    let response =
+        iron_shield::settings::delete_site(State(state.clone()), Path("NAS".to_string()))
+            .await
+            .into_response();
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+    let saved: Config = json5::from_str(
+        &fs::read_to_string(&temp_config_path).expect("Failed to read saved config"),
+    )
+    .expect("Saved config should parse");
+    assert_eq!(saved.sites.len(), 1);
+    assert_eq!(saved.sites[0].name, "Router");
+    assert_eq!(saved.groups[0].sites, vec!["Router".to_string()]);
+
+    {
+        let history = state.history.read();
+        assert!(!history.contains_key("NAS"));
+        assert!(history.contains_key("Router"));
+    }
+    assert_eq!(server_events.try_recv(), Ok(ServerEvent::ConfigUpdated));
+
+    let response =
+        iron_shield::settings::delete_site(State(state.clone()), Path("NAS".to_string()))
+            .await
+            .into_response();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_save_config_rejects_stale_revisions() {
+    let temp_dir = tempdir().expect("Failed to create temporary directory for revision test");
+    let temp_config_path = temp_dir.path().join("config.json5");
+    let state = create_site_patch_state(temp_config_path.clone());
+
+    let response = iron_shield::settings::get_config(State(state.clone()))
+        .await
+        .into_response();
+    assert_eq!(response.status(), StatusCode::OK);
+    let etag = response
+        .headers()
+        .get(axum::http::header::ETAG)
+        .expect("GET /api/config should return an ETag")
+        .clone();
+    let body_bytes = response
+        .into_body()
+        .collect()
+        .await
+        .expect("Failed to collect config body")
+        .to_bytes();
+    let loaded: ConfigUpdate =
+        serde_json::from_slice(&body_bytes).expect("Body should be a config update");
+    assert_eq!(loaded.sites[0].url, "http://nas.${BASE_DOMAIN}");
+
+    let mut first_tab = loaded.clone();
+    first_tab.site_name = "First tab".to_string();
+    let mut second_tab = loaded;
+    second_tab.site_name = "Second tab".to_string();
+    let mut headers = HeaderMap::new();
+    headers.insert(axum::http::header::IF_MATCH, etag);
+
+    let response = iron_shield::settings::save_config(
+        State(state.clone()),
+        Query(iron_shield::settings::SaveQuery::default()),
+        headers.clone(),
+        Json(first_tab),
+    )
+    .await
+    .into_response();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_ne!(
+        response.headers().get(axum::http::header::ETAG),
+        headers.get(axum::http::header::IF_MATCH)
+    );
+    let saved = fs::read_to_string(&temp_config_path).expect("Failed to read saved config");
+
+    let response = iron_shield::settings::save_config(
+        State(state.clone()),
+        Query(iron_shield::settings::SaveQuery::default()),
+        headers,
+        Json(second_tab),
+    )
+    .await
+    .into_response();
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+    assert_eq!(
+        fs::read_to_string(&temp_config_path).expect("Failed to read config"),
+        saved
+    );
+    assert_eq!(state.config.load().site_name, "First tab");
+}
+
+#[tokio::test]
+async fn test_rollback_config_restores_the_previous_save() {
+    let temp_dir = tempdir().expect("Failed to create temporary directory for rollback test");
+    let temp_config_path = temp_dir.path().join("config.json5");
+    let state = create_site_patch_state(temp_config_path.clone());
+
+    let response = iron_shield::settings::rollback_config(State(state.clone()))
+        .await
+        .into_response();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    let original = fs::read_to_string(&temp_config_path).expect("Failed to read config");
+    let mut update = ConfigUpdate::from(&*state.config.load());
+    update.site_name = "Bad save".to_string();
+    let response = iron_shield::settings::save_config(
+        State(state.clone()),
+        Query(iron_shield::settings::SaveQuery::default()),
+        HeaderMap::new(),
+        Json(update),
+    )
+    .await
+    .into_response();
+    assert_eq!(response.status(), StatusCode::OK);
+    let bad_save = fs::read_to_string(&temp_config_path).expect("Failed to read config");
+
+    let mut server_events = state.server_events.subscribe();
+    let response = iron_shield::settings::rollback_config(State(state.clone()))
+        .await
+        .into_response();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        fs::read_to_string(&temp_config_path).expect("Failed to read config"),
+        original
+    );
+    {
+        let config_guard = state.config.load();
+        assert_eq!(config_guard.site_name, Config::default().site_name);
+        assert_eq!(config_guard.sites[0].url, "http://nas.home.lan");
+    }
+    assert_eq!(server_events.try_recv(), Ok(ServerEvent::ConfigUpdated));
+
+    // Rolling back again undoes the rollback
+    let response = iron_shield::settings::rollback_config(State(state.clone()))
+        .await
+        .into_response();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        fs::read_to_string(&temp_config_path).expect("Failed to read config"),
+        bad_save
+    );
+}
+
+async fn check_site(
+    state: &Arc<UptimeState>,
+    request: serde_json::Value,
+) -> (StatusCode, serde_json::Value) {
+    let request = serde_json::from_value(request).expect("Request should be a check request");
+    let response = iron_shield::settings::check_site(State(state.clone()), Json(request))
+        .await
+        .into_response();
+    let (parts, body) = response.into_parts();
+    let body_bytes = body
+        .collect()
+        .await
+        .expect("Failed to collect check response body")
+        .to_bytes();
+    (
+        parts.status,
+        serde_json::from_slice(&body_bytes).expect("Check response should be JSON"),
+    )
+}
+
+#[tokio::test]
+async fn test_check_site_probes_once_without_saving() {
+    let temp_dir = tempdir().expect("Failed to create temporary directory for check test");
+    let temp_config_path = temp_dir.path().join("config.json5");
+    let state = create_site_patch_state(temp_config_path.clone());
+    let original = fs::read_to_string(&temp_config_path).expect("Failed to read config");
+
+    let app = axum::Router::new()
+        .route("/up", axum::routing::get(|| async { "ok" }))
+        .route(
+            "/down",
+            axum::routing::get(|| async { StatusCode::SERVICE_UNAVAILABLE }),
+        );
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("Failed to bind test listener");
+    let address = listener
+        .local_addr()
+        .expect("Listener should have an address");
+    tokio::spawn(async move { axum::serve(listener, app).await });
+
+    let (status, result) = check_site(
+        &state,
+        serde_json::json!({ "url": format!("http://{address}/up") }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(result["status"], "up");
+    assert!(result["response_time_ms"].is_u64());
+
+    let (status, result) = check_site(
+        &state,
+        serde_json::json!({ "url": format!("http://{address}/down"), "monitor_timeout_secs": 2 }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(result["status"], "down");
+    assert!(result["failure_reason"]
+        .as_str()
+        .is_some_and(|reason| reason.contains("503")));
+
+    let (status, result) = check_site(
+        &state,
+        serde_json::json!({ "url": "http://${MISSING_CHECK_HOST}/", "monitor_timeout_secs": 0 }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let fields: Vec<&str> = result["errors"]
+        .as_array()
+        .expect("Errors should be a list")
+        .iter()
+        .filter_map(|error| error["field"].as_str())
+        .collect();
+    assert_eq!(fields, vec!["url"]);
+
+    // Environment variables are not expanded, so they cannot be sent to the caller's URL
+    std::env::set_var("IRON_SHIELD_CHECK_TEST_TOKEN", "hunter2");
+    let (status, result) = check_site(
+        &state,
+        serde_json::json!({
+            "url": format!("http://{address}/up?token=${{IRON_SHIELD_CHECK_TEST_TOKEN}}"),
+            "monitor_timeout_secs": 0,
+        }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(result["errors"][0]["field"], "url");
+    assert!(result["errors"][0]["message"]
+        .as_str()
+        .is_some_and(|message| message.contains("IRON_SHIELD_CHECK_TEST_TOKEN")));
+
+    let (status, result) = check_site(
+        &state,
+        serde_json::json!({ "url": "not a url", "monitor_timeout_secs": 0 }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(result["errors"].as_array().map(Vec::len), Some(2));
+
+    assert_eq!(
+        fs::read_to_string(&temp_config_path).expect("Failed to read config"),
+        original
+    );
+    assert!(state.history.read().is_empty());
+}
+
+async fn response_text(response: axum::response::Response) -> (StatusCode, String) {
+    let (parts, body) = response.into_parts();
+    let body_bytes = body
+        .collect()
+        .await
+        .expect("Failed to collect response body")
+        .to_bytes();
+    (
+        parts.status,
+        String::from_utf8(body_bytes.to_vec()).expect("Response should be UTF-8"),
+    )
+}
+
+fn settings_form(state: &Arc<UptimeState>, fields: &[(&str, &str)]) -> Vec<(String, String)> {
+    let revision = state.config.load().revision();
+    std::iter::once(("revision".to_string(), revision))
+        .chain(
+            fields
+                .iter()
+                .map(|(key, value)| ((*key).to_string(), (*value).to_string())),
+        )
+        .collect()
+}
+
+#[tokio::test]
+async fn test_settings_page_lists_sites_and_saves_the_form() {
+    let temp_dir = tempdir().expect("Failed to create temporary directory for settings page");
+    let temp_config_path = temp_dir.path().join("config.json5");
+    let state = create_site_patch_state(temp_config_path.clone());
+
+    let (status, html) = response_text(
+        iron_shield::settings_page::settings_page(
+            State(state.clone()),
+            Query(Default::default()),
+            HeaderMap::new(),
+        )
+        .await
+        .into_response(),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(html.contains(r#"name="sites[0].url" value="http://nas.${BASE_DOMAIN}""#));
+    assert!(html.contains(r#"name="sites[1].name" value="Router""#));
+
+    let form = settings_form(
+        &state,
+        &[
+            ("site_name", "Lab"),
+            ("clock", "12hour"),
+            ("opentelemetry_endpoint", ""),
+            ("sites[0].name", "NAS"),
+            ("sites[0].url", "http://nas.${BASE_DOMAIN}"),
+            ("sites[0].category", "Storage"),
+            ("sites[0].tags", "home, storage"),
+            ("sites[0].monitor_interval_secs", "30"),
+            ("sites[1].name", "Router"),
+            ("sites[1].url", "http://router.${BASE_DOMAIN}"),
+            ("sites[1].remove", "on"),
+            ("new_site.name", "Printer"),
+            ("new_site.url", "http://printer.${BASE_DOMAIN}"),
+            ("new_site.disabled", "on"),
+        ],
+    );
+    let response = iron_shield::settings_page::save_settings_page(
+        State(state.clone()),
+        HeaderMap::new(),
+        Form(form),
+    )
+    .await
+    .into_response();
+    assert_eq!(response.status(), StatusCode::SEE_OTHER);
+    assert_eq!(
+        response.headers().get(axum::http::header::LOCATION),
+        Some(&axum::http::HeaderValue::from_static(
+            "/settings?saved=true"
+        ))
+    );
+
+    let saved: Config = json5::from_str(
+        &fs::read_to_string(&temp_config_path).expect("Failed to read saved config"),
+    )
+    .expect("Saved config should parse");
+    assert_eq!(saved.site_name, "Lab");
+    assert_eq!(saved.clock, Clock::Hour12);
+    let names: Vec<&str> = saved.sites.iter().map(|site| site.name.as_str()).collect();
+    assert_eq!(names, vec!["NAS", "Printer"]);
+    assert_eq!(saved.sites[0].tags, vec!["home", "storage"]);
+    assert_eq!(saved.sites[0].monitor_interval_secs, Some(30));
+    assert_eq!(saved.sites[1].url, "http://printer.${BASE_DOMAIN}");
+    assert!(saved.sites[1].disabled);
+    assert_eq!(state.config.load().sites[1].url, "http://printer.home.lan");
+}
+
+#[tokio::test]
+async fn test_settings_page_shows_rejected_fields_next_to_their_rows() {
+    let temp_dir = tempdir().expect("Failed to create temporary directory for settings page");
+    let temp_config_path = temp_dir.path().join("config.json5");
+    let state = create_site_patch_state(temp_config_path.clone());
+    let original = fs::read_to_string(&temp_config_path).expect("Failed to read config");
+
+    let form = settings_form(
+        &state,
+        &[
+            ("site_name", "Lab"),
+            ("clock", "24hour"),
+            ("sites[0].name", "NAS"),
+            ("sites[0].url", "http://nas.${BASE_DOMAIN}"),
+            ("sites[0].remove", "on"),
+            ("sites[1].name", "Router"),
+            ("sites[1].url", "not a url"),
+            ("new_site.name", "router"),
+            ("new_site.url", "http://printer.lan"),
+        ],
+    );
+    let (status, html) = response_text(
+        iron_shield::settings_page::save_settings_page(
+            State(state.clone()),
+            HeaderMap::new(),
+            Form(form),
+        )
+        .await
+        .into_response(),
+    )
+    .await;
+
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(html.contains("sites[1].url is not a valid URL"));
+    assert!(html.contains(r#"new_site.name &quot;router&quot; duplicates sites[1].name"#));
+    // Submitted values are kept, including the removal
+    assert!(html.contains(r#"name="sites[1].url" value="not a url""#));
+    assert!(html.contains(r#"name="sites[0].remove" aria-label="Remove NAS" checked"#));
+    assert_eq!(
+        fs::read_to_string(&temp_config_path).expect("Failed to read config"),
+        original
+    );
+
+    let mut form = settings_form(&state, &[("site_name", "Lab"), ("clock", "24hour")]);
+    form[0].1 = "stale".to_string();
+    let response = iron_shield::settings_page::save_settings_page(
+        State(state.clone()),
+        HeaderMap::new(),
+        Form(form),
+    )
+    .await
+    .into_response();
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+}
+
+#[tokio::test]
+async fn test_toggle_site_disabled_flips_and_saves_the_flag() {
+    let temp_dir = tempdir().expect("Failed to create temporary directory for toggle test");
+    let temp_config_path = temp_dir.path().join("config.json5");
+    let state = create_site_patch_state(temp_config_path.clone());
+    let mut server_events = state.server_events.subscribe();
+
+    let toggle = |name: &str| {
+        iron_shield::settings::toggle_site_disabled(State(state.clone()), Path(name.to_string()))
+    };
+
+    let (status, body) = response_text(toggle("Router").await.into_response()).await;
+    assert_eq!(status, StatusCode::OK);
+    let site: SiteUpdate = serde_json::from_str(&body).expect("Response should be a site");
+    assert!(site.disabled);
+    assert_eq!(site.url, "http://router.home.lan");
+
+    let saved: Config = json5::from_str(
+        &fs::read_to_string(&temp_config_path).expect("Failed to read saved config"),
+    )
+    .expect("Saved config should parse");
+    assert!(saved.sites[1].disabled);
+    assert!(!saved.sites[0].disabled);
+    assert_eq!(saved.sites[1].url, "http://router.${BASE_DOMAIN}");
+    assert!(state.config.load().sites[1].disabled);
+    assert_eq!(server_events.try_recv(), Ok(ServerEvent::ConfigUpdated));
+
+    let (status, body) = response_text(toggle("Router").await.into_response()).await;
+    assert_eq!(status, StatusCode::OK);
+    let site: SiteUpdate = serde_json::from_str(&body).expect("Response should be a site");
+    assert!(!site.disabled);
+
+    let (status, _) = response_text(toggle("Printer").await.into_response()).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_patch_settings_changes_only_global_settings() {
+    let temp_dir = tempdir().expect("Failed to create temporary directory for settings patch");
+    let temp_config_path = temp_dir.path().join("config.json5");
+    let state = create_site_patch_state(temp_config_path.clone());
+
+    let patch_settings = |headers: HeaderMap, patch: serde_json::Value| {
+        let patch = patch
+            .as_object()
+            .expect("Patch should be an object")
+            .clone();
+        iron_shield::settings::patch_settings(State(state.clone()), headers, Json(patch))
+    };
+
+    let response = patch_settings(
+        HeaderMap::new(),
+        serde_json::json!({ "clock": "12hour", "timezone": "Europe/Berlin" }),
+    )
+    .await
+    .into_response();
+    assert_eq!(response.status(), StatusCode::OK);
+    let etag = response
+        .headers()
+        .get(axum::http::header::ETAG)
+        .expect("The response should carry the new revision")
+        .clone();
+    let (_, body) = response_text(response).await;
+    let settings: iron_shield::settings::GlobalSettings =
+        serde_json::from_str(&body).expect("Response should be the global settings");
+    assert_eq!(settings.clock, "12hour");
+    assert_eq!(settings.timezone, "Europe/Berlin");
+
+    let saved: Config = json5::from_str(
+        &fs::read_to_string(&temp_config_path).expect("Failed to read saved config"),
+    )
+    .expect("Saved config should parse");
+    assert_eq!(saved.clock, Clock::Hour12);
+    assert_eq!(saved.timezone, "Europe/Berlin");
+    assert_eq!(saved.sites.len(), 2);
+    assert_eq!(saved.sites[0].url, "http://nas.${BASE_DOMAIN}");
+    assert_eq!(saved.sites[0].category, "Storage");
+    assert_eq!(state.config.load().timezone, "Europe/Berlin");
+
+    for invalid in [
+        serde_json::json!({ "sites": [] }),
+        serde_json::json!({ "timezone": "Mars/Olympus_Mons" }),
+        serde_json::json!({ "clock": "sundial" }),
+    ] {
+        let (status, _) = response_text(
+            patch_settings(HeaderMap::new(), invalid)
+                .await
+                .into_response(),
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        axum::http::header::IF_MATCH,
+        axum::http::HeaderValue::from_static("\"stale\""),
+    );
+    let (status, _) = response_text(
+        patch_settings(headers, serde_json::json!({ "site_name": "Lab" }))
+            .await
+            .into_response(),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CONFLICT);
+
+    let mut headers = HeaderMap::new();
+    headers.insert(axum::http::header::IF_MATCH, etag);
+    let (status, _) = response_text(
+        patch_settings(headers, serde_json::json!({ "site_name": "Lab" }))
+            .await
+            .into_response(),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_preview_config_describes_changes_without_saving() {
+    let temp_dir = tempdir().expect("Failed to create temporary directory for preview test");
+    let temp_config_path = temp_dir.path().join("config.json5");
+    let state = create_site_patch_state(temp_config_path.clone());
+    let original = fs::read_to_string(&temp_config_path).expect("Failed to read config");
+
+    let mut update = ConfigUpdate::from(&state.config.load().for_persistence());
+    update.sites.remove(0);
+    update.sites[0].tags = vec!["network".to_string()];
+    let response = iron_shield::settings::preview_config(State(state.clone()), Json(update))
+        .await
+        .into_response();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response
+            .headers()
+            .get(axum::http::header::ETAG)
+            .and_then(|etag| etag.to_str().ok()),
+        Some(format!("\"{}\"", state.config.load().revision()).as_str())
+    );
+    let (_, body) = response_text(response).await;
+    let preview: serde_json::Value = serde_json::from_str(&body).expect("Body should be JSON");
+    assert_eq!(preview["removed_sites"], serde_json::json!(["NAS"]));
+    assert_eq!(
+        preview["changed_sites"],
+        serde_json::json!([{ "name": "Router", "fields": ["tags"] }])
+    );
+    assert_eq!(
+        preview["changes"],
+        serde_json::json!(["Remove site \"NAS\"", "Change tags of site \"Router\""])
+    );
+
+    let mut invalid = ConfigUpdate::from(&state.config.load().for_persistence());
+    invalid.sites[0].url = "not a url".to_string();
+    let response = iron_shield::settings::preview_config(State(state.clone()), Json(invalid))
+        .await
+        .into_response();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    assert_eq!(
+        fs::read_to_string(&temp_config_path).expect("Failed to read config"),
+        original
+    );
+}
+
+#[tokio::test]
+async fn test_save_config_dry_run_reports_changes_without_applying_them() {
+    let temp_dir = tempdir().expect("Failed to create temporary directory for dry run test");
+    let temp_config_path = temp_dir.path().join("config.json5");
+    let state = create_site_patch_state(temp_config_path.clone());
+    let original = fs::read_to_string(&temp_config_path).expect("Failed to read config");
+    let revision = state.config.load().revision();
+
+    let mut update = ConfigUpdate::from(&state.config.load().for_persistence());
+    update.sites.remove(0);
+    // Nothing listens on the discard port, so the endpoint cannot be reached
+    update.opentelemetry_endpoint = Some("http://127.0.0.1:9/v1/uptime".to_string());
+    let response = iron_shield::settings::save_config(
+        State(state.clone()),
+        Query(iron_shield::settings::SaveQuery { dry_run: true }),
+        HeaderMap::new(),
+        Json(update.clone()),
+    )
+    .await
+    .into_response();
+    let (status, body) = response_text(response).await;
+    assert_eq!(status, StatusCode::OK);
+    let report: serde_json::Value = serde_json::from_str(&body).expect("Body should be JSON");
+    assert_eq!(report["removed_sites"], serde_json::json!(["NAS"]));
+    assert_eq!(report["revision"], serde_json::json!(revision));
+    assert_eq!(
+        report["telemetry"]["endpoint"],
+        serde_json::json!("http://127.0.0.1:9/v1/uptime")
+    );
+    assert_eq!(report["telemetry"]["reachable"], serde_json::json!(false));
+
+    assert_eq!(
+        fs::read_to_string(&temp_config_path).expect("Failed to read config"),
+        original
+    );
+    let config = state.config.load().clone();
+    assert_eq!(config.revision(), revision);
+    assert_eq!(config.sites.len(), 2);
+
+    update.sites[0].url = "ftp://router.home.lan".to_string();
+    let response = iron_shield::settings::save_config(
+        State(state.clone()),
+        Query(iron_shield::settings::SaveQuery { dry_run: true }),
+        HeaderMap::new(),
+        Json(update),
+    )
+    .await
+    .into_response();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_api_keys_protect_writes_once_created_and_can_be_revoked() {
+    let temp_dir = tempdir().expect("Failed to create temporary directory for API key test");
+    let temp_config_path = temp_dir.path().join("config.json5");
+    let state = create_site_patch_state(temp_config_path.clone());
+
+    let app = axum::Router::new()
+        .route(
+            "/api/keys",
+            axum::routing::get(iron_shield::api_keys::list_api_keys)
+                .post(iron_shield::api_keys::create_api_key),
+        )
+        .route(
+            "/api/keys/:name",
+            axum::routing::delete(iron_shield::api_keys::revoke_api_key),
+        )
+        .route(
+            "/api/sites/:name/disabled",
+            axum::routing::post(iron_shield::settings::toggle_site_disabled),
+        )
+        .layer(axum::middleware::from_fn_with_state(
+            state.clone(),
+            iron_shield::api_keys::require_api_key,
+        ))
+        .with_state(state.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("Failed to bind test listener");
+    let base = format!(
+        "http://{}",
+        listener
+            .local_addr()
+            .expect("Listener should have an address")
+    );
+    tokio::spawn(async move { axum::serve(listener, app).await });
+    let client = reqwest::Client::new();
+
+    // The first key can be created while the API is still open
+    let response = client
+        .post(format!("{base}/api/keys"))
+        .json(&serde_json::json!({ "name": "home-assistant" }))
+        .send()
+        .await
+        .expect("Failed to create key");
+    assert_eq!(response.status(), reqwest::StatusCode::CREATED);
+    let created: iron_shield::api_keys::CreatedApiKey =
+        response.json().await.expect("Response should be a key");
+    let saved = fs::read_to_string(&temp_config_path).expect("Failed to read saved config");
+    assert!(!saved.contains(&created.key));
+    assert!(saved.contains(&iron_shield::api_keys::hash(&created.key)));
+
+    let toggle = format!("{base}/api/sites/NAS/disabled");
+    let response = client.post(&toggle).send().await.expect("Failed to toggle");
+    assert_eq!(response.status(), reqwest::StatusCode::UNAUTHORIZED);
+    let response = client
+        .post(&toggle)
+        .bearer_auth("ish_wrong")
+        .send()
+        .await
+        .expect("Failed to toggle");
+    assert_eq!(response.status(), reqwest::StatusCode::UNAUTHORIZED);
+    assert!(!state.config.load().sites[0].disabled);
+
+    let response = client
+        .post(&toggle)
+        .header("X-Api-Key", &created.key)
+        .send()
+        .await
+        .expect("Failed to toggle");
+    assert_eq!(response.status(), reqwest::StatusCode::OK);
+    assert!(state.config.load().sites[0].disabled);
+
+    // Reads stay open unless `api_keys_protect_reads` is set
+    let listed: Vec<iron_shield::api_keys::ApiKeySummary> = client
+        .get(format!("{base}/api/keys"))
+        .send()
+        .await
+        .expect("Failed to list keys")
+        .json()
+        .await
+        .expect("Response should list keys");
+    assert_eq!(listed.len(), 1);
+    assert_eq!(listed[0].name, "home-assistant");
+    state
+        .config
+        .update(|config| {
+            config.api_keys_protect_reads = true;
+            Ok(())
+        })
+        .expect("Failed to protect reads");
+    let response = client
+        .get(format!("{base}/api/keys"))
+        .send()
+        .await
+        .expect("Failed to list keys");
+    assert_eq!(response.status(), reqwest::StatusCode::UNAUTHORIZED);
+
+    let response = client
+        .delete(format!("{base}/api/keys/home-assistant"))
+        .bearer_auth(&created.key)
+        .send()
+        .await
+        .expect("Failed to revoke key");
+    assert_eq!(response.status(), reqwest::StatusCode::NO_CONTENT);
+    assert!(state.config.load().api_keys.is_empty());
+    let response = client.post(&toggle).send().await.expect("Failed to toggle");
+    assert_eq!(response.status(), reqwest::StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_settings_page_needs_an_api_key_once_keys_exist() {
+    let temp_dir = tempdir().expect("Failed to create temporary directory for settings page");
+    let temp_config_path = temp_dir.path().join("config.json5");
+    let state = create_site_patch_state(temp_config_path.clone());
+    let key = iron_shield::api_keys::generate();
+    state
+        .config
+        .update(|config| {
+            config.api_keys.push(iron_shield::config::ApiKey {
+                name: "browser".to_string(),
+                hash: iron_shield::api_keys::hash(&key),
+            });
+            Ok(())
+        })
+        .expect("Failed to add an API key");
+
+    let (_, html) = response_text(
+        iron_shield::settings_page::settings_page(
+            State(state.clone()),
+            Query(Default::default()),
+            HeaderMap::new(),
+        )
+        .await
+        .into_response(),
+    )
+    .await;
+    assert!(html.contains(r#"name="api_key""#));
+
+    let fields = [
+        ("site_name", "Lab"),
+        ("clock", "24hour"),
+        ("sites[0].name", "NAS"),
+        ("sites[0].url", "http://nas.${BASE_DOMAIN}"),
+        ("sites[1].name", "Router"),
+        ("sites[1].url", "http://router.${BASE_DOMAIN}"),
+    ];
+    let (status, html) = response_text(
+        iron_shield::settings_page::save_settings_page(
+            State(state.clone()),
+            HeaderMap::new(),
+            Form(settings_form(&state, &fields)),
+        )
+        .await
+        .into_response(),
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert!(html.contains("Enter a valid API key"));
+
+    let mut form = settings_form(&state, &fields);
+    form.push(("api_key".to_string(), key));
+    let response = iron_shield::settings_page::save_settings_page(
+        State(state.clone()),
+        HeaderMap::new(),
+        Form(form),
+    )
+    .await
+    .into_response();
+    assert_eq!(response.status(), StatusCode::SEE_OTHER);
+    assert_eq!(state.config.load().site_name, "Lab");
+}
+
+#[tokio::test]
+async fn test_import_bookmarks_adds_new_links_as_sites() {
+    let temp_dir = tempdir().expect("Failed to create temporary directory for import test");
+    let temp_config_path = temp_dir.path().join("config.json5");
+    let state = create_site_patch_state(temp_config_path.clone());
+    let mut server_events = state.server_events.subscribe();
+    let export = r#"<!DOCTYPE NETSCAPE-Bookmark-file-1>
+<H1>Bookmarks</H1>
+<DL><p>
+    <DT><H3>Storage</H3>
+    <DL><p>
+        <DT><A HREF="http://nas.home.lan/">NAS</A>
+        <DT><A HREF="https://backups.home.lan">Backups</A>
+    </DL><p>
+    <DT><A HREF="https://router.example.com">Router</A>
+</DL><p>"#;
+
+    let response =
+        iron_shield::bookmarks::import_bookmarks(State(state.clone()), export.to_string()).await;
+    let (status, body) = response_text(response.into_response()).await;
+
+    assert_eq!(status, StatusCode::OK);
+    let summary: serde_json::Value = serde_json::from_str(&body).expect("Body should be JSON");
+    assert_eq!(
+        summary["added"],
+        serde_json::json!(["Backups", "Router (2)"])
+    );
+    assert_eq!(
+        summary["skipped"],
+        serde_json::json!(["http://nas.home.lan/"])
+    );
+
+    let saved: Config = json5::from_str(
+        &fs::read_to_string(&temp_config_path).expect("Failed to read saved config"),
+    )
+    .expect("Saved config should parse");
+    let sites: Vec<(&str, &str)> = saved
+        .sites
+        .iter()
+        .map(|site| (site.name.as_str(), site.category.as_str()))
+        .collect();
+    assert_eq!(
+        sites,
+        [
+            ("NAS", "Storage"),
+            ("Router", ""),
+            ("Backups", "Storage"),
+            ("Router (2)", "")
+        ]
+    );
+    assert_eq!(saved.sites[0].url, "http://nas.${BASE_DOMAIN}");
+    assert_eq!(server_events.try_recv(), Ok(ServerEvent::ConfigUpdated));
+}
+
+#[tokio::test]
+async fn test_import_monitors_adds_gatus_endpoints_and_rejects_unreadable_files() {
+    let temp_dir = tempdir().expect("Failed to create temporary directory for import test");
+    let temp_config_path = temp_dir.path().join("config.json5");
+    let state = create_site_patch_state(temp_config_path.clone());
+    let gatus = r#"
+endpoints:
+  - name: Grafana
+    url: https://grafana.home.lan
+    interval: 30s
+    conditions:
+      - "[STATUS] == 200"
+      - "[CONNECTED] == true"
+"#;
+
+    let response =
+        iron_shield::import::import_monitors(State(state.clone()), gatus.to_string()).await;
+    let (status, body) = response_text(response.into_response()).await;
+
+    assert_eq!(status, StatusCode::OK);
+    let summary: serde_json::Value = serde_json::from_str(&body).expect("Body should be JSON");
+    assert_eq!(summary["added"], serde_json::json!(["Grafana"]));
+    assert_eq!(
+        summary["unsupported"],
+        serde_json::json!(["Grafana: condition \"[CONNECTED] == true\""])
+    );
+    let saved: Config = json5::from_str(
+        &fs::read_to_string(&temp_config_path).expect("Failed to read saved config"),
+    )
+    .expect("Saved config should parse");
+    assert_eq!(saved.sites[2].monitor_interval_secs, Some(30));
+    assert_eq!(
+        saved.sites[2]
+            .assertions
+            .as_ref()
+            .expect("Status condition should be kept")
+            .status,
+        [200]
+    );
+
+    let response =
+        iron_shield::import::import_monitors(State(state.clone()), "monitors: [".to_string()).await;
+    let (status, _) = response_text(response.into_response()).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_tag_endpoints_rename_and_delete_tags_on_every_site() {
+    let temp_dir = tempdir().expect("Failed to create temporary directory for tag test");
+    let temp_config_path = temp_dir.path().join("config.json5");
+    let state = create_site_patch_state(temp_config_path.clone());
+    let mut server_events = state.server_events.subscribe();
+
+    let rename = iron_shield::tags::rename_tag(
+        State(state.clone()),
+        Path("home".to_string()),
+        Json(iron_shield::tags::TagRename {
+            name: "homelab".to_string(),
+        }),
+    )
+    .await;
+    let (status, body) = response_text(rename.into_response()).await;
+    assert_eq!(status, StatusCode::OK);
+    let tags: Vec<iron_shield::tags::TagUsage> =
+        serde_json::from_str(&body).expect("Response should list tags");
+    assert_eq!(
+        tags,
+        [iron_shield::tags::TagUsage {
+            name: "homelab".to_string(),
+            sites: 1
+        }]
+    );
+
+    let saved: Config = json5::from_str(
+        &fs::read_to_string(&temp_config_path).expect("Failed to read saved config"),
+    )
+    .expect("Saved config should parse");
+    assert_eq!(saved.sites[0].tags, ["homelab"]);
+    assert_eq!(saved.sites[0].url, "http://nas.${BASE_DOMAIN}");
+    assert_eq!(server_events.try_recv(), Ok(ServerEvent::ConfigUpdated));
+
+    let delete =
+        |tag: &str| iron_shield::tags::delete_tag(State(state.clone()), Path(tag.to_string()));
+    let (status, _) = response_text(delete("homelab").await.into_response()).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    assert!(state.config.load().sites[0].tags.is_empty());
+
+    let (status, _) = response_text(delete("homelab").await.into_response()).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_duplicate_site_copies_check_options_under_a_new_name() {
+    let temp_dir = tempdir().expect("Failed to create temporary directory for duplicate test");
+    let temp_config_path = temp_dir.path().join("config.json5");
+    let state = create_site_patch_state(temp_config_path.clone());
+
+    let duplicate = |name: &str, copy: serde_json::Value| {
+        iron_shield::settings::duplicate_site(
+            State(state.clone()),
+            Path(name.to_string()),
+            Json(serde_json::from_value(copy).expect("Copy should deserialize")),
+        )
+    };
+
+    let (status, body) = response_text(
+        duplicate("NAS", serde_json::json!({ "name": "Backup NAS" }))
+            .await
+            .into_response(),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+    let site: SiteUpdate = serde_json::from_str(&body).expect("Response should be a site");
+    assert_eq!(site.url, "http://nas.home.lan");
+    assert_eq!(site.category, "Storage");
+    assert_eq!(site.tags, ["home"]);
+
+    let (status, _) = response_text(
+        duplicate(
+            "NAS",
+            serde_json::json!({ "name": "Media NAS", "url": "http://media.${BASE_DOMAIN}" }),
+        )
+        .await
+        .into_response(),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+
+    let saved: Config = json5::from_str(
+        &fs::read_to_string(&temp_config_path).expect("Failed to read saved config"),
+    )
+    .expect("Saved config should parse");
+    let sites: Vec<(&str, &str)> = saved
+        .sites
+        .iter()
+        .map(|site| (site.name.as_str(), site.url.as_str()))
+        .collect();
+    assert_eq!(
+        sites,
+        [
+            ("NAS", "http://nas.${BASE_DOMAIN}"),
+            ("Router", "http://router.${BASE_DOMAIN}"),
+            ("Backup NAS", "http://nas.${BASE_DOMAIN}"),
+            ("Media NAS", "http://media.${BASE_DOMAIN}"),
+        ]
+    );
+
+    let (status, _) = response_text(
+        duplicate("NAS", serde_json::json!({ "name": "router" }))
+            .await
+            .into_response(),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    let (status, _) = response_text(
+        duplicate("Printer", serde_json::json!({ "name": "Printer 2" }))
+            .await
+            .into_response(),
+    )
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_branding_serves_only_the_configured_files() {
+    let temp_dir = tempdir().expect("Failed to create temporary directory for branding test");
+    let temp_config_path = temp_dir.path().join("config.json5");
+    fs::create_dir(temp_dir.path().join("branding")).expect("Failed to create branding dir");
+    fs::write(
+        temp_dir.path().join("branding/theme.css"),
+        "body { color: rebeccapurple; }",
+    )
+    .expect("Failed to write stylesheet");
+    fs::write(temp_dir.path().join("branding/logo.svg"), "<svg></svg>")
+        .expect("Failed to write logo");
+    let state = create_test_uptime_state(temp_config_path);
+
+    let response = iron_shield::branding::logo(State(state.clone()), HeaderMap::new())
+        .await
+        .into_response();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    state
+        .config
+        .update(|config| {
+            config.custom_css = Some("branding/theme.css".to_string());
+            config.logo = Some("branding/logo.svg".to_string());
+            Ok(())
+        })
+        .expect("Failed to configure branding");
+
+    let response = iron_shield::branding::custom_css(State(state.clone()), HeaderMap::new())
+        .await
+        .into_response();
+    let header = |response: &axum::response::Response, name| {
+        response
+            .headers()
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string)
+    };
+    assert_eq!(
+        header(&response, axum::http::header::CONTENT_TYPE).as_deref(),
+        Some("text/css; charset=utf-8")
+    );
+    assert_eq!(
+        header(&response, axum::http::header::X_CONTENT_TYPE_OPTIONS).as_deref(),
+        Some("nosniff")
+    );
+    let etag = header(&response, axum::http::header::ETAG).expect("Response should have an ETag");
+    let (status, body) = response_text(response).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body, "body { color: rebeccapurple; }");
+
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        axum::http::header::IF_NONE_MATCH,
+        etag.parse().expect("ETag should be a valid header"),
+    );
+    let response = iron_shield::branding::custom_css(State(state.clone()), headers)
+        .await
+        .into_response();
+    assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+
+    let response = iron_shield::branding::logo(State(state.clone()), HeaderMap::new())
+        .await
+        .into_response();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        header(&response, axum::http::header::CONTENT_TYPE).as_deref(),
+        Some("image/svg+xml")
+    );
+    assert!(header(&response, axum::http::header::CONTENT_SECURITY_POLICY).is_some());
+}
+
+/// Creates a state whose config file takes the router's host from the environment, with
+/// a second environment variable that edits must not be able to read.
+fn create_env_template_state(config_file_path: PathBuf) -> Arc<UptimeState> {
+    std::env::set_var("IRON_SHIELD_EDIT_TEST_HOST", "router.lan");
+    std::env::set_var("IRON_SHIELD_EDIT_TEST_SECRET", "hunter2");
+    fs::write(
+        &config_file_path,
+        r#"{
+            vars: { BASE_DOMAIN: "home.lan" },
+            sites: [
+                { name: "NAS", url: "http://nas.${BASE_DOMAIN}", tags: [] },
+                { name: "Router", url: "http://${IRON_SHIELD_EDIT_TEST_HOST}/", tags: [] },
+            ],
+        }"#,
+    )
+    .expect("Failed to write initial config file");
+    let state = create_test_uptime_state(config_file_path.clone());
+    state
+        .config
+        .store(Config::load(&config_file_path).expect("Initial config should load"));
+    state
+}
+
+/// Asserts that an API error rejects `field` for using the secret environment variable.
+fn assert_rejects_secret_variable(status: StatusCode, body: &str, field: &str) {
+    assert_eq!(status, StatusCode::BAD_REQUEST, "{body}");
+    let error: serde_json::Value = serde_json::from_str(body).expect("Error should be JSON");
+    assert_eq!(error["errors"][0]["field"], field, "{body}");
+    assert!(
+        error["errors"][0]["message"].as_str().is_some_and(
+            |message| message.contains("undefined variable IRON_SHIELD_EDIT_TEST_SECRET")
+        )
+    );
+    assert!(!body.contains("hunter2"));
+}
+
+#[tokio::test]
+async fn test_save_config_expands_only_vars_and_kept_file_templates() {
+    let temp_dir = tempdir().expect("Failed to create temporary directory for variable test");
+    let temp_config_path = temp_dir.path().join("config.json5");
+    let state = create_env_template_state(temp_config_path.clone());
+    let save = |update: ConfigUpdate| {
+        iron_shield::settings::save_config(
+            State(state.clone()),
+            Query(iron_shield::settings::SaveQuery::default()),
+            HeaderMap::new(),
+            Json(update),
+        )
+    };
+
+    let mut update = ConfigUpdate::from(&state.config.load().for_persistence());
+    update.sites.remove(0);
+    let (status, body) = response_text(save(update.clone()).await.into_response()).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(state.config.load().sites[0].url, "http://router.lan/");
+
+    update.sites[0].url = "http://router.lan/?token=${IRON_SHIELD_EDIT_TEST_SECRET}".to_string();
+    let (status, body) = response_text(save(update).await.into_response()).await;
+    assert_rejects_secret_variable(status, &body, "sites[0].url");
+    assert_eq!(state.config.load().sites[0].url, "http://router.lan/");
+}
+
+#[tokio::test]
+async fn test_preview_config_does_not_expand_environment_variables() {
+    let temp_dir = tempdir().expect("Failed to create temporary directory for variable test");
+    let temp_config_path = temp_dir.path().join("config.json5");
+    let state = create_env_template_state(temp_config_path.clone());
+
+    let mut update = ConfigUpdate::from(&state.config.load().for_persistence());
+    update.site_name = "${IRON_SHIELD_EDIT_TEST_SECRET}".to_string();
+    let response = iron_shield::settings::preview_config(State(state.clone()), Json(update))
+        .await
+        .into_response();
+    let (status, body) = response_text(response).await;
+    assert_rejects_secret_variable(status, &body, "site_name");
+}
+
+#[tokio::test]
+async fn test_patch_settings_does_not_expand_environment_variables() {
+    let temp_dir = tempdir().expect("Failed to create temporary directory for variable test");
+    let temp_config_path = temp_dir.path().join("config.json5");
+    let state = create_env_template_state(temp_config_path.clone());
+    let original = fs::read_to_string(&temp_config_path).expect("Failed to read config");
+
+    let patch = serde_json::json!({ "site_name": "${IRON_SHIELD_EDIT_TEST_SECRET}" });
+    let response = iron_shield::settings::patch_settings(
+        State(state.clone()),
+        HeaderMap::new(),
+        Json(
+            patch
+                .as_object()
+                .expect("Patch should be an object")
+                .clone(),
+        ),
+    )
+    .await
+    .into_response();
+    let (status, body) = response_text(response).await;
+    assert_rejects_secret_variable(status, &body, "site_name");
+    assert_eq!(
+        fs::read_to_string(&temp_config_path).expect("Failed to read config"),
+        original
+    );
+}
+
+#[tokio::test]
+async fn test_patch_site_does_not_expand_environment_variables() {
+    let temp_dir = tempdir().expect("Failed to create temporary directory for variable test");
+    let temp_config_path = temp_dir.path().join("config.json5");
+    let state = create_env_template_state(temp_config_path.clone());
+
+    let (status, body) = patch_site(
+        &state,
+        "NAS",
+        serde_json::json!({ "url": "http://nas.${IRON_SHIELD_EDIT_TEST_SECRET}" }),
+    )
+    .await;
+    assert_rejects_secret_variable(status, &body, "sites[0].url");
+
+    // The router keeps the host the config file takes from the environment
+    let (status, body) = patch_site(&state, "Router", serde_json::json!({ "tags": ["lan"] })).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    let saved: Config = json5::from_str(
+        &fs::read_to_string(&temp_config_path).expect("Failed to read saved config"),
+    )
+    .expect("Saved config should parse");
+    assert_eq!(saved.sites[1].url, "http://${IRON_SHIELD_EDIT_TEST_HOST}/");
+}
+
+#[tokio::test]
+async fn test_add_site_does_not_expand_environment_variables() {
+    let temp_dir = tempdir().expect("Failed to create temporary directory for variable test");
+    let temp_config_path = temp_dir.path().join("config.json5");
+    let state = create_env_template_state(temp_config_path.clone());
+
+    let (status, body) = add_site(
+        &state,
+        serde_json::json!({
+            "name": "Exfiltrate",
+            "url": "https://collector.example.com/${IRON_SHIELD_EDIT_TEST_SECRET}",
+        }),
+    )
+    .await;
+    assert_rejects_secret_variable(status, &body, "sites[2].url");
+    assert_eq!(state.config.load().sites.len(), 2);
+}
+
+#[tokio::test]
+async fn test_duplicate_site_does_not_expand_environment_variables() {
+    let temp_dir = tempdir().expect("Failed to create temporary directory for variable test");
+    let temp_config_path = temp_dir.path().join("config.json5");
+    let state = create_env_template_state(temp_config_path.clone());
+    let duplicate = |copy: serde_json::Value| {
+        iron_shield::settings::duplicate_site(
+            State(state.clone()),
+            Path("Router".to_string()),
+            Json(serde_json::from_value(copy).expect("Copy should deserialize")),
+        )
+    };
+
+    let (status, body) = response_text(
+        duplicate(serde_json::json!({
+            "name": "Exfiltrate",
+            "url": "https://collector.example.com/${IRON_SHIELD_EDIT_TEST_SECRET}",
+        }))
+        .await
+        .into_response(),
+    )
+    .await;
+    assert_rejects_secret_variable(status, &body, "sites[2].url");
+
+    // A copy made as written keeps the router's host from the environment
+    let (status, body) = response_text(
+        duplicate(serde_json::json!({ "name": "Backup Router" }))
+            .await
+            .into_response(),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED, "{body}");
+    let site: SiteUpdate = serde_json::from_str(&body).expect("Response should be a site");
+    assert_eq!(site.url, "http://router.lan/");
+}
+
+#[tokio::test]
+async fn test_settings_page_does_not_expand_environment_variables() {
+    let temp_dir = tempdir().expect("Failed to create temporary directory for variable test");
+    let temp_config_path = temp_dir.path().join("config.json5");
+    let state = create_env_template_state(temp_config_path.clone());
+    let save = |url: &str| {
+        let form = settings_form(
+            &state,
+            &[
+                ("site_name", "Lab"),
+                ("clock", "24hour"),
+                ("sites[0].name", "NAS"),
+                ("sites[0].url", url),
+                ("sites[1].name", "Router"),
+                ("sites[1].url", "http://${IRON_SHIELD_EDIT_TEST_HOST}/"),
+            ],
+        );
+        iron_shield::settings_page::save_settings_page(
+            State(state.clone()),
+            HeaderMap::new(),
+            Form(form),
+        )
+    };
+
+    let (status, html) = response_text(
+        save("http://nas.lan/?token=${IRON_SHIELD_EDIT_TEST_SECRET}")
+            .await
+            .into_response(),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(html.contains("sites[0].url uses undefined variable IRON_SHIELD_EDIT_TEST_SECRET"));
+    assert!(!html.contains("hunter2"));
+
+    let response = save("http://nas.${BASE_DOMAIN}").await.into_response();
+    assert_eq!(response.status(), StatusCode::SEE_OTHER);
+    assert_eq!(state.config.load().sites[1].url, "http://router.lan/");
 }